@@ -1,10 +1,11 @@
 use crate::{
-    config::WebAsset,
+    config::{InputAssetType, WebAsset},
     hash::Hash,
     lockfile::LockfileEntry,
+    sync::downscale::{Downscale, downscaled_size},
     util::{alpha_bleed::alpha_bleed, svg::svg_to_png},
 };
-use anyhow::Context;
+use anyhow::{Context, bail};
 use bytes::Bytes;
 use image::DynamicImage;
 use relative_path::RelativePathBuf;
@@ -21,6 +22,8 @@ const SUPPORTED_EXTENSIONS: &[(&str, AssetCtor)] = &[
     ("wav", |_| Ok(AssetType::Audio(AudioType::Wav))),
     ("png", |_| Ok(AssetType::Image(ImageType::Png))),
     ("svg", |_| Ok(AssetType::Image(ImageType::Png))),
+    // Roblox doesn't take WebP, so it is converted to PNG when processed.
+    ("webp", |_| Ok(AssetType::Image(ImageType::Png))),
     ("jpg", |_| Ok(AssetType::Image(ImageType::Jpg))),
     ("jpeg", |_| Ok(AssetType::Image(ImageType::Jpg))),
     ("bmp", |_| Ok(AssetType::Image(ImageType::Bmp))),
@@ -61,6 +64,7 @@ pub struct Asset {
     /// The hash before processing
     pub hash: Hash,
     is_svg: bool,
+    is_webp: bool,
 }
 
 impl Asset {
@@ -77,9 +81,13 @@ impl Asset {
             .context("Unknown file type")??;
 
         let mut is_svg = false;
+        let mut is_webp = false;
         if ext == "svg" {
             ext = "png".to_string();
             is_svg = true;
+        } else if ext == "webp" {
+            ext = "png".to_string();
+            is_webp = true;
         }
 
         let hash = Hash::new_from_bytes(&data);
@@ -91,17 +99,46 @@ impl Asset {
             ext,
             hash,
             is_svg,
+            is_webp,
         })
     }
 
-    pub fn process(&mut self, font_db: Arc<fontdb::Database>, bleed: bool) -> anyhow::Result<()> {
+    pub fn process(
+        &mut self,
+        font_db: Arc<fontdb::Database>,
+        bleed: bool,
+        downscale: Option<Downscale>,
+    ) -> anyhow::Result<()> {
         if self.is_svg {
             self.data = svg_to_png(&self.data, font_db)
                 .context("Failed to convert to PNG")?
                 .into();
         }
 
-        if bleed && let AssetType::Image(_) = self.ty {
+        if self.is_webp {
+            let image = image::load_from_memory_with_format(&self.data, image::ImageFormat::WebP)
+                .context("Failed to decode WebP")?;
+            let mut writer = Cursor::new(Vec::new());
+            image.write_to(&mut writer, image::ImageFormat::Png)?;
+            self.data = writer.into_inner().into();
+        }
+
+        if let (Some(downscale), AssetType::Image(_) | AssetType::Decal(_)) = (downscale, self.ty) {
+            let image: DynamicImage = image::load_from_memory(&self.data)?;
+            let (width, height) =
+                downscaled_size(image.width(), image.height(), downscale.max_dimension);
+            if (width, height) != (image.width(), image.height()) {
+                let format = image::ImageFormat::from_extension(&self.ext)
+                    .unwrap_or(image::ImageFormat::Png);
+                let mut writer = Cursor::new(Vec::new());
+                image
+                    .resize_exact(width, height, downscale.filter)
+                    .write_to(&mut writer, format)?;
+                self.data = writer.into_inner().into();
+            }
+        }
+
+        if bleed && let AssetType::Image(_) | AssetType::Decal(_) = self.ty {
             let mut image: DynamicImage = image::load_from_memory(&self.data)?;
             alpha_bleed(&mut image);
 
@@ -112,6 +149,27 @@ impl Asset {
 
         Ok(())
     }
+
+    /// Uploads the asset as `requested` instead of the type implied by its
+    /// extension. Only types that accept the file's contents are allowed.
+    pub fn set_type(&mut self, requested: InputAssetType) -> anyhow::Result<()> {
+        self.ty = match (requested, self.ty) {
+            (InputAssetType::Image, AssetType::Image(ty) | AssetType::Decal(ty)) => {
+                AssetType::Image(ty)
+            }
+            (InputAssetType::Decal, AssetType::Image(ty) | AssetType::Decal(ty)) => {
+                AssetType::Decal(ty)
+            }
+            (InputAssetType::Audio, AssetType::Audio(ty)) => AssetType::Audio(ty),
+            (requested, ty) => bail!(
+                "Cannot upload {} as {} (it is {})",
+                self.path,
+                requested.name(),
+                ty.asset_type()
+            ),
+        };
+        Ok(())
+    }
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -119,6 +177,7 @@ pub enum AssetType {
     Model(ModelType),
     Animation,
     Image(ImageType),
+    Decal(ImageType),
     Audio(AudioType),
     Video(VideoType),
 }
@@ -131,6 +190,7 @@ impl AssetType {
             AssetType::Model(_) => "Model",
             AssetType::Animation => "Animation",
             AssetType::Image(_) => "Image",
+            AssetType::Decal(_) => "Decal",
             AssetType::Audio(_) => "Audio",
             AssetType::Video(_) => "Video",
         }
@@ -145,10 +205,10 @@ impl AssetType {
             AssetType::Model(ModelType::GltfBinary) => "model/gltf-binary",
             AssetType::Model(ModelType::Roblox) => "model/x-rbxm",
 
-            AssetType::Image(ImageType::Png) => "image/png",
-            AssetType::Image(ImageType::Jpg) => "image/jpeg",
-            AssetType::Image(ImageType::Bmp) => "image/bmp",
-            AssetType::Image(ImageType::Tga) => "image/tga",
+            AssetType::Image(ImageType::Png) | AssetType::Decal(ImageType::Png) => "image/png",
+            AssetType::Image(ImageType::Jpg) | AssetType::Decal(ImageType::Jpg) => "image/jpeg",
+            AssetType::Image(ImageType::Bmp) | AssetType::Decal(ImageType::Bmp) => "image/bmp",
+            AssetType::Image(ImageType::Tga) | AssetType::Decal(ImageType::Tga) => "image/tga",
 
             AssetType::Audio(AudioType::Mp3) => "audio/mpeg",
             AssetType::Audio(AudioType::Ogg) => "audio/ogg",
@@ -1,7 +1,11 @@
-use crate::config::CreatorType;
+use crate::{
+    config::CreatorType,
+    lockfile::Lockfile,
+    sync::{downscale::DownscaleRule, progress::ProgressCallback},
+};
 use clap::{Args, Parser, Subcommand};
 use clap_verbosity_flag::{InfoLevel, Verbosity};
-use std::path::PathBuf;
+use std::{collections::HashSet, path::PathBuf};
 
 #[derive(Parser)]
 #[command(version, about = "Upload and reference Roblox assets in code.")]
@@ -67,9 +71,40 @@ pub struct SyncArgs {
     #[arg(long)]
     pub expected_price: Option<u32>,
 
+    /// Maximum number of uploads in flight at once.
+    #[arg(long)]
+    pub upload_concurrency: Option<usize>,
+
+    /// Maximum number of Open Cloud requests per minute.
+    #[arg(long, value_name = "REQUESTS_PER_MINUTE")]
+    pub rate_limit: Option<u32>,
+
     /// Path to the project directory. Defaults to the current directory.
     #[arg(short, long, default_value = ".")]
     pub project: PathBuf,
+
+    /// Only process and upload these files (relative to the project directory).
+    /// Other files keep the asset ids recorded in the lockfile.
+    #[arg(skip)]
+    pub only: Option<HashSet<PathBuf>>,
+
+    /// Entries from other projects (same creator) whose assets can be reused
+    /// instead of uploading identical files again.
+    #[arg(skip)]
+    pub shared_lockfile: Option<Lockfile>,
+
+    /// Receives an event for every asset as it starts, completes or fails.
+    #[arg(skip)]
+    pub progress: Option<ProgressCallback>,
+
+    /// Caps the size of images before they are uploaded.
+    #[arg(skip)]
+    pub downscale: Option<DownscaleRule>,
+
+    /// Journal file recording uploads as they happen, so an interrupted sync
+    /// resumes without uploading finished assets again.
+    #[arg(skip)]
+    pub journal: Option<PathBuf>,
 }
 
 impl SyncArgs {
@@ -63,6 +63,36 @@ pub struct Codegen {
     pub strip_extensions: bool,
     /// Generate the Content data type instead of strings
     pub content: bool,
+    /// How keys are named in truffle's augmented modules
+    pub key_style: KeyStyle,
+    /// Write each top-level folder of truffle's augmented modules to a module of its own
+    pub split: bool,
+    /// Declare Luau types for the assets in truffle's augmented Luau module
+    pub luau_types: bool,
+    /// Also write the augmented assets to this TypeScript module as `as const` data
+    pub typescript_module: Option<PathBuf>,
+    /// Template file truffle's augmented Luau module is rendered into
+    pub luau_template: Option<PathBuf>,
+    /// Template file truffle's augmented TypeScript declaration is rendered into
+    pub dts_template: Option<PathBuf>,
+}
+
+/// Naming transform for the keys of truffle's augmented modules
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, Default, PartialEq, Eq, JsonSchema)]
+pub enum KeyStyle {
+    /// Keys as they are on disk
+    #[default]
+    #[serde(rename = "keep")]
+    Keep,
+    /// `playButton`, with asset extensions stripped
+    #[serde(rename = "camelCase")]
+    CamelCase,
+    /// `play_button`, with asset extensions stripped
+    #[serde(rename = "snake_case")]
+    SnakeCase,
+    /// `play-button`, with only asset extensions stripped
+    #[serde(rename = "strip-extension")]
+    StripExtension,
 }
 
 /// The type of Creator
@@ -103,12 +133,39 @@ pub struct Input {
     #[serde(default = "default_true")]
     pub bleed: bool,
 
+    /// The Roblox asset type to upload the files as, instead of the one implied by their extension. Changing it won't reupload files that are already in the lockfile
+    #[serde(default)]
+    pub asset_type: Option<InputAssetType>,
+
     /// A map of paths relative to the input path to existing assets on Roblox
     #[serde(default)]
     #[schemars(with = "HashMap<PathBuf, WebAsset>")]
     pub web: HashMap<RelativePathBuf, WebAsset>,
 }
 
+/// A Roblox asset type an input's files can be uploaded as
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq, ValueEnum, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum InputAssetType {
+    /// An Image asset, the default for image files
+    Image,
+    /// A Decal, for surfaces that need one
+    Decal,
+    /// An Audio asset, the default for audio files
+    Audio,
+}
+
+impl InputAssetType {
+    /// The name Roblox uses for the asset type
+    pub fn name(&self) -> &'static str {
+        match self {
+            InputAssetType::Image => "Image",
+            InputAssetType::Decal => "Decal",
+            InputAssetType::Audio => "Audio",
+        }
+    }
+}
+
 /// An asset that exists on Roblox
 #[derive(Debug, Deserialize, Serialize, Clone, JsonSchema)]
 pub struct WebAsset {
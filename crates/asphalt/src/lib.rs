@@ -10,9 +10,14 @@ pub mod glob;
 pub mod hash;
 pub mod lockfile;
 pub mod sync;
+pub mod upload;
 pub mod util;
 pub mod web_api;
 
 pub use cli::{SyncArgs, SyncTarget};
 pub use config::Config;
-pub use sync::{sync, sync_with_config};
+pub use sync::{
+    downscale::{Downscale, DownscaleRule},
+    progress::{ProgressCallback, ProgressEvent},
+    sync, sync_with_config,
+};
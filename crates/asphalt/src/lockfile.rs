@@ -9,7 +9,7 @@ use std::{
 
 pub const FILE_NAME: &str = "truffle.lock.toml";
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Lockfile {
     version: u32,
     inputs: BTreeMap<String, BTreeMap<Hash, LockfileEntry>>,
@@ -41,6 +41,21 @@ impl Lockfile {
             .insert(hash.to_owned(), entry);
     }
 
+    /// Looks up a hash in any input.
+    pub fn find(&self, hash: &Hash) -> Option<&LockfileEntry> {
+        self.inputs.values().find_map(|m| m.get(hash))
+    }
+
+    /// Adds every entry of `other`, keeping existing ones.
+    pub fn merge(&mut self, other: &Lockfile) {
+        for (input_name, entries) in &other.inputs {
+            let merged = self.inputs.entry(input_name.clone()).or_default();
+            for (hash, entry) in entries {
+                merged.entry(*hash).or_insert_with(|| entry.clone());
+            }
+        }
+    }
+
     pub async fn write_to(&self, project_dir: &Path) -> anyhow::Result<()> {
         let mut content = toml::to_string(self)?;
         content.insert_str(0, "# This file is automatically @generated by Asphalt.\n# It is not intended for manual editing.\n");
@@ -87,17 +102,20 @@ impl RawLockfile {
         let lockfile_path = project_dir.join(FILE_NAME);
         let content = fs::read_to_string(&lockfile_path).await;
 
-        let content = match content {
-            Err(_) => return Ok(Self::default()),
-            Ok(content) => content,
-        };
+        match content {
+            Err(_) => Ok(Self::default()),
+            Ok(content) => Self::parse(&content),
+        }
+    }
 
-        let raw: toml::Value = toml::from_str(&content)?;
+    /// Parses the contents of a lockfile of any version.
+    pub fn parse(content: &str) -> anyhow::Result<RawLockfile> {
+        let raw: toml::Value = toml::from_str(content)?;
 
         match raw.get("version").and_then(|v| v.as_integer()) {
-            Some(2) => Ok(RawLockfile::V2(toml::from_str(&content)?)),
-            Some(1) => Ok(RawLockfile::V1(toml::from_str(&content)?)),
-            Some(0) | None => Ok(RawLockfile::V0(toml::from_str(&content)?)),
+            Some(2) => Ok(RawLockfile::V2(toml::from_str(content)?)),
+            Some(1) => Ok(RawLockfile::V1(toml::from_str(content)?)),
+            Some(0) | None => Ok(RawLockfile::V0(toml::from_str(content)?)),
             _ => bail!("Unsupported lockfile version"),
         }
     }
@@ -23,7 +23,8 @@ impl Backend for Cloud {
                     .context("An API key is required to use the Cloud backend")?,
                 params.creator,
                 params.expected_price,
-            ),
+            )
+            .with_limits(params.upload_concurrency, params.rate_limit),
         })
     }
 
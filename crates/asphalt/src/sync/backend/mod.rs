@@ -30,5 +30,7 @@ pub struct Params {
     pub api_key: Option<String>,
     pub creator: config::Creator,
     pub expected_price: Option<u32>,
+    pub upload_concurrency: Option<usize>,
+    pub rate_limit: Option<u32>,
     pub project_dir: PathBuf,
 }
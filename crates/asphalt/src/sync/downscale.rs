@@ -0,0 +1,47 @@
+//! Downscaling of oversized images before they are uploaded.
+//!
+//! Embedders pass a [`DownscaleRule`] through
+//! [`SyncArgs::downscale`](crate::cli::SyncArgs::downscale) to cap the size of
+//! images per file. Like alpha bleeding, changing the cap won't reupload files
+//! that are already in the lockfile.
+
+use image::imageops::FilterType;
+use std::{path::Path, sync::Arc};
+
+/// Picks the cap for a file, given its path as walked by the sync. Called
+/// from the sync worker tasks.
+pub type DownscaleRule = Arc<dyn Fn(&Path) -> Option<Downscale> + Send + Sync>;
+
+/// The longest side an image may have, and how it is resampled to fit.
+#[derive(Debug, Clone, Copy)]
+pub struct Downscale {
+    pub max_dimension: u32,
+    pub filter: FilterType,
+}
+
+/// The size a `width`x`height` image is scaled to so its longest side fits in
+/// `max_dimension`, keeping its aspect ratio. Smaller images keep their size.
+pub fn downscaled_size(width: u32, height: u32, max_dimension: u32) -> (u32, u32) {
+    let longest = width.max(height);
+    if longest <= max_dimension || max_dimension == 0 {
+        return (width, height);
+    }
+    let scale = |side: u32| {
+        ((u64::from(side) * u64::from(max_dimension) + u64::from(longest) / 2) / u64::from(longest))
+            .max(1) as u32
+    };
+    (scale(width), scale(height))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fits_the_longest_side() {
+        assert_eq!(downscaled_size(2048, 1024, 1024), (1024, 512));
+        assert_eq!(downscaled_size(300, 4000, 1024), (77, 1024));
+        assert_eq!(downscaled_size(5000, 2, 1024), (1024, 1));
+        assert_eq!(downscaled_size(512, 512, 1024), (512, 512));
+    }
+}
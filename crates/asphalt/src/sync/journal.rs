@@ -0,0 +1,192 @@
+//! Write-ahead journal of uploads.
+//!
+//! The lockfile only holds every asset once a sync finishes. Before an asset
+//! is uploaded its intent is appended to the journal, followed by its asset id
+//! once the upload went through, so a sync that is interrupted halfway can
+//! pick up the finished uploads on the next run instead of uploading them
+//! again. The journal is removed once the lockfile has been written.
+
+use crate::{
+    hash::Hash,
+    lockfile::{Lockfile, LockfileEntry},
+};
+use anyhow::Context;
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashSet,
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+enum Record {
+    Upload {
+        input: String,
+        hash: Hash,
+        path: PathBuf,
+    },
+    Uploaded {
+        input: String,
+        hash: Hash,
+        asset_id: u64,
+    },
+}
+
+/// What an interrupted sync left behind.
+#[derive(Debug, Default)]
+pub struct Recovered {
+    /// Uploads that finished before the interruption.
+    pub lockfile: Lockfile,
+    /// Files whose upload started but never finished. They may or may not
+    /// have reached Roblox and are uploaded again.
+    pub interrupted: Vec<PathBuf>,
+}
+
+pub struct Journal {
+    path: PathBuf,
+    file: Mutex<fs::File>,
+}
+
+impl Journal {
+    /// Opens the journal at `path`, recovering the records of a previous run
+    /// that did not finish.
+    pub fn open(path: &Path) -> anyhow::Result<(Self, Recovered)> {
+        let content = fs::read_to_string(path).unwrap_or_default();
+        let recovered = recover(&content);
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create {}", parent.display()))?;
+        }
+        let mut file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .with_context(|| format!("Failed to open sync journal {}", path.display()))?;
+        // Terminate a line cut off by a crash so new records start on their own line
+        if !content.is_empty() && !content.ends_with('\n') {
+            file.write_all(b"\n")?;
+        }
+
+        let journal = Self {
+            path: path.to_path_buf(),
+            file: Mutex::new(file),
+        };
+        Ok((journal, recovered))
+    }
+
+    /// Records that `path` is about to be uploaded.
+    pub fn upload(&self, input: &str, hash: &Hash, path: &Path) {
+        self.append(&Record::Upload {
+            input: input.to_string(),
+            hash: *hash,
+            path: path.to_path_buf(),
+        });
+    }
+
+    /// Records a finished upload.
+    pub fn uploaded(&self, input: &str, hash: &Hash, asset_id: u64) {
+        self.append(&Record::Uploaded {
+            input: input.to_string(),
+            hash: *hash,
+            asset_id,
+        });
+    }
+
+    /// Removes the journal once everything it recorded is in the lockfile.
+    pub fn finish(self) -> anyhow::Result<()> {
+        drop(self.file);
+        fs::remove_file(&self.path)
+            .with_context(|| format!("Failed to remove sync journal {}", self.path.display()))
+    }
+
+    fn append(&self, record: &Record) {
+        let mut line = serde_json::to_string(record).expect("journal records serialize");
+        line.push('\n');
+
+        let mut file = self.file.lock().unwrap();
+        let written = file
+            .write_all(line.as_bytes())
+            .and_then(|_| file.sync_data());
+        if let Err(e) = written {
+            warn!("Failed to write sync journal {}: {e}", self.path.display());
+        }
+    }
+}
+
+/// Replays the journal. A line cut off by a crash is skipped.
+fn recover(content: &str) -> Recovered {
+    let mut recovered = Recovered::default();
+    let mut started = Vec::new();
+    let mut finished = HashSet::new();
+
+    for record in content
+        .lines()
+        .filter_map(|line| serde_json::from_str::<Record>(line).ok())
+    {
+        match record {
+            Record::Upload { input, hash, path } => started.push((input, hash, path)),
+            Record::Uploaded {
+                input,
+                hash,
+                asset_id,
+            } => {
+                recovered
+                    .lockfile
+                    .insert(&input, &hash, LockfileEntry { asset_id });
+                finished.insert((input, hash));
+            }
+        }
+    }
+
+    recovered.interrupted = started
+        .into_iter()
+        .filter(|(input, hash, _)| !finished.contains(&(input.clone(), *hash)))
+        .map(|(_, _, path)| path)
+        .collect();
+    recovered
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recovers_finished_uploads_after_an_interruption() {
+        let path = std::env::temp_dir().join(format!("asphalt-journal-{}", std::process::id()));
+        let _ = fs::remove_file(&path);
+        let a = Hash::new_from_bytes(b"a");
+        let b = Hash::new_from_bytes(b"b");
+
+        let (journal, recovered) = Journal::open(&path).unwrap();
+        assert!(recovered.interrupted.is_empty());
+        journal.upload("images", &a, Path::new("images/a.png"));
+        journal.uploaded("images", &a, 1);
+        journal.upload("images", &b, Path::new("images/b.png"));
+        drop(journal);
+        // A torn write at the end of the journal is ignored
+        fs::OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .unwrap()
+            .write_all(b"{\"op\":\"uplo")
+            .unwrap();
+
+        let (journal, recovered) = Journal::open(&path).unwrap();
+        assert_eq!(recovered.lockfile.get("images", &a).unwrap().asset_id, 1);
+        assert!(recovered.lockfile.get("images", &b).is_none());
+        assert_eq!(recovered.interrupted, vec![PathBuf::from("images/b.png")]);
+
+        journal.uploaded("images", &b, 2);
+        let (journal, recovered) = Journal::open(&path).unwrap();
+        assert_eq!(recovered.lockfile.get("images", &b).unwrap().asset_id, 2);
+        assert!(recovered.interrupted.is_empty());
+
+        journal.finish().unwrap();
+        assert!(!path.exists());
+    }
+}
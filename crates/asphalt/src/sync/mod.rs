@@ -4,7 +4,7 @@ use crate::{
     config::Config,
     hash::Hash,
     lockfile::{LockfileEntry, RawLockfile},
-    sync::{backend::Backend, collect::collect_events},
+    sync::{backend::Backend, collect::collect_events, journal::Journal},
 };
 use anyhow::{Context, bail};
 use fs_err::tokio as fs;
@@ -18,6 +18,9 @@ use tokio::sync::mpsc::{self};
 mod backend;
 mod codegen;
 mod collect;
+pub mod downscale;
+mod journal;
+pub mod progress;
 mod walk;
 
 enum TargetBackend {
@@ -73,10 +76,25 @@ pub async fn sync_with_config(
 ) -> anyhow::Result<()> {
     let target = args.target();
 
-    let existing_lockfile = RawLockfile::read_from(&config.project_dir)
+    let mut existing_lockfile = RawLockfile::read_from(&config.project_dir)
         .await?
         .into_lockfile()?;
 
+    let journal = match &args.journal {
+        Some(path) if target.write_on_sync() => {
+            let (journal, recovered) = Journal::open(path)?;
+            if !recovered.interrupted.is_empty() {
+                info!(
+                    "Resuming an interrupted sync; {} unfinished upload(s) will be retried",
+                    recovered.interrupted.len()
+                );
+            }
+            existing_lockfile.merge(&recovered.lockfile);
+            Some(Arc::new(journal))
+        }
+        _ => None,
+    };
+
     let font_db = Arc::new({
         let mut db = fontdb::Database::new();
         db.load_system_fonts();
@@ -95,11 +113,18 @@ pub async fn sync_with_config(
         target,
         existing_lockfile,
         font_db,
+        only: args.only,
+        shared_lockfile: args.shared_lockfile,
+        progress: args.progress,
+        downscale: args.downscale,
+        journal: journal.clone(),
         backend: {
             let params = backend::Params {
                 api_key: args.api_key,
                 creator: config.creator.clone(),
                 expected_price: args.expected_price,
+                upload_concurrency: args.upload_concurrency,
+                rate_limit: args.rate_limit,
                 project_dir: config.project_dir.clone(),
             };
             match &target {
@@ -133,6 +158,11 @@ pub async fn sync_with_config(
         results.new_lockfile.write_to(&config.project_dir).await?;
     }
 
+    // Every finished upload is in the lockfile now; the walk dropped its handle.
+    if let Some(journal) = journal.and_then(Arc::into_inner) {
+        journal.finish()?;
+    }
+
     for (input_name, source) in results.input_sources {
         let input = config
             .inputs
@@ -0,0 +1,33 @@
+//! Per-asset progress events for library users.
+//!
+//! The CLI renders its own progress bar; embedders pass a [`ProgressCallback`]
+//! through [`SyncArgs::progress`](crate::cli::SyncArgs::progress) to follow
+//! every asset as it is processed and uploaded.
+
+use std::{path::PathBuf, sync::Arc};
+
+/// Called from the sync worker tasks, so it must be cheap and thread-safe.
+pub type ProgressCallback = Arc<dyn Fn(ProgressEvent) + Send + Sync>;
+
+#[allow(dead_code)] // Only read through the library (truffle)
+#[derive(Debug, Clone)]
+pub enum ProgressEvent {
+    /// A file matched an input and is about to be processed.
+    Started { path: PathBuf, bytes: u64 },
+    /// A file finished. `new` is set when it was processed and sent to the
+    /// target instead of reusing a lockfile entry; `duplicate` when its content
+    /// matched an earlier file of the same input.
+    Completed {
+        path: PathBuf,
+        bytes: u64,
+        new: bool,
+        duplicate: bool,
+        asset_id: Option<String>,
+    },
+    /// A file failed to process or upload.
+    Failed {
+        path: PathBuf,
+        bytes: u64,
+        error: String,
+    },
+}
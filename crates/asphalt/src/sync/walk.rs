@@ -1,10 +1,15 @@
 use crate::{
-    asset::{self, Asset},
+    asset::{self, Asset, AssetRef},
     cli::SyncTarget,
-    config::Config,
+    config::{Config, InputAssetType},
     hash::Hash,
     lockfile::Lockfile,
-    sync::TargetBackend,
+    sync::{
+        TargetBackend,
+        downscale::DownscaleRule,
+        journal::Journal,
+        progress::{ProgressCallback, ProgressEvent},
+    },
 };
 use anyhow::Context;
 use fs_err::tokio as fs;
@@ -13,10 +18,10 @@ use relative_path::PathExt;
 use resvg::usvg::fontdb;
 use std::{
     collections::{
-        HashMap,
+        HashMap, HashSet,
         hash_map::{self},
     },
-    path::{Path, PathBuf},
+    path::{Component, Path, PathBuf},
     sync::Arc,
 };
 use tokio::{
@@ -30,6 +35,19 @@ pub struct Params {
     pub existing_lockfile: Lockfile,
     pub font_db: Arc<fontdb::Database>,
     pub backend: Option<TargetBackend>,
+    pub only: Option<HashSet<PathBuf>>,
+    pub shared_lockfile: Option<Lockfile>,
+    pub progress: Option<ProgressCallback>,
+    pub downscale: Option<DownscaleRule>,
+    pub journal: Option<Arc<Journal>>,
+}
+
+impl Params {
+    fn report(&self, event: ProgressEvent) {
+        if let Some(progress) = &self.progress {
+            progress(event);
+        }
+    }
 }
 
 struct InputState {
@@ -38,6 +56,7 @@ struct InputState {
     input_prefix: PathBuf,
     seen_hashes: Arc<Mutex<HashMap<Hash, PathBuf>>>,
     bleed: bool,
+    asset_type: Option<InputAssetType>,
 }
 
 pub async fn walk(params: Params, config: &Config, tx: &UnboundedSender<super::Event>) {
@@ -52,6 +71,7 @@ pub async fn walk(params: Params, config: &Config, tx: &UnboundedSender<super::E
             input_prefix: input_prefix.clone(),
             seen_hashes: Arc::new(Mutex::new(HashMap::new())),
             bleed: input.bleed,
+            asset_type: input.asset_type,
         });
 
         let mut join_set = JoinSet::new();
@@ -76,6 +96,7 @@ pub async fn walk(params: Params, config: &Config, tx: &UnboundedSender<super::E
         {
             let Ok(entry) = entry else { continue };
 
+            let bytes = entry.metadata().map(|m| m.len()).unwrap_or_default();
             let path = entry.into_path();
             if !path.is_file() {
                 continue;
@@ -87,6 +108,13 @@ pub async fn walk(params: Params, config: &Config, tx: &UnboundedSender<super::E
                 continue;
             }
 
+            let in_scope = match &params.only {
+                Some(only) => path
+                    .strip_prefix(&config.project_dir)
+                    .is_ok_and(|rel_path| only.contains(&normalize(rel_path))),
+                None => true,
+            };
+
             let state = state.clone();
             let semaphore = semaphore.clone();
             let tx = tx.clone();
@@ -97,9 +125,18 @@ pub async fn walk(params: Params, config: &Config, tx: &UnboundedSender<super::E
                 let _permit = semaphore.acquire_owned().await.unwrap();
 
                 tx.send(super::Event::InFlight(path.clone())).unwrap();
+                state.params.report(ProgressEvent::Started {
+                    path: path.clone(),
+                    bytes,
+                });
 
-                if let Err(e) = process_entry(state.clone(), &path, &tx).await {
+                if let Err(e) = process_entry(state.clone(), &path, bytes, in_scope, &tx).await {
                     warn!("Failed to process file {}: {e:?}", path.display());
+                    state.params.report(ProgressEvent::Failed {
+                        path: path.clone(),
+                        bytes,
+                        error: format!("{e:#}"),
+                    });
                     tx.send(super::Event::Failed(path.clone())).unwrap();
                 }
             });
@@ -109,9 +146,18 @@ pub async fn walk(params: Params, config: &Config, tx: &UnboundedSender<super::E
     }
 }
 
+/// Drops `.` components so `./assets/a.png` compares equal to `assets/a.png`.
+fn normalize(path: &Path) -> PathBuf {
+    path.components()
+        .filter(|c| !matches!(c, Component::CurDir))
+        .collect()
+}
+
 async fn process_entry(
     state: Arc<InputState>,
     path: &Path,
+    bytes: u64,
+    in_scope: bool,
     tx: &UnboundedSender<super::Event>,
 ) -> anyhow::Result<()> {
     debug!("Handling entry: {}", path.display());
@@ -121,11 +167,18 @@ async fn process_entry(
     let data = fs::read(path).await?;
 
     let mut asset = Asset::new(rel_path.clone(), data.into()).context("Failed to create asset")?;
+    if let Some(asset_type) = state.asset_type {
+        asset.set_type(asset_type)?;
+    }
 
     let lockfile_entry = state
         .params
         .existing_lockfile
-        .get(&state.input_name, &asset.hash);
+        .get(&state.input_name, &asset.hash)
+        .or_else(|| {
+            let shared = state.params.shared_lockfile.as_ref()?;
+            shared.find(&asset.hash)
+        });
 
     {
         let mut seen_hashes = state.seen_hashes.lock().await;
@@ -137,6 +190,13 @@ async fn process_entry(
 
                 debug!("Duplicate asset found: {} -> {}", rel_path, rel_seen_path);
 
+                state.params.report(ProgressEvent::Completed {
+                    path: path.into(),
+                    bytes,
+                    new: false,
+                    duplicate: true,
+                    asset_id: lockfile_entry.map(|entry| AssetRef::from(entry).to_string()),
+                });
                 let event = super::Event::Finished {
                     state: super::EventState::Duplicate,
                     input_name: state.input_name.clone(),
@@ -155,27 +215,47 @@ async fn process_entry(
         }
     }
 
+    // Out-of-scope files are never processed or uploaded; they keep the id
+    // from the lockfile (or stay out of codegen if they have none).
     let always_target = matches!(state.params.target, SyncTarget::Studio | SyncTarget::Debug);
-    let is_new = always_target || lockfile_entry.is_none();
+    let is_new = in_scope && (always_target || lockfile_entry.is_none());
 
     if is_new {
         let font_db = state.params.font_db.clone();
         let bleed = state.bleed;
+        let downscale = state.params.downscale.as_ref().and_then(|rule| rule(path));
 
         asset = tokio::task::spawn_blocking(move || -> anyhow::Result<Asset> {
             let mut asset = asset;
-            asset.process(font_db, bleed)?;
+            asset.process(font_db, bleed, downscale)?;
             Ok(asset)
         })
         .await?
         .context("Failed to process asset")?;
     }
 
+    let journal = state.params.journal.as_ref().filter(|_| is_new);
+    if let Some(journal) = journal {
+        journal.upload(&state.input_name, &asset.hash, path);
+    }
+
     let asset_ref = match state.params.backend {
-        Some(ref backend) => backend.sync(&asset, lockfile_entry).await?,
-        None => lockfile_entry.map(Into::into),
+        Some(ref backend) if in_scope => backend.sync(&asset, lockfile_entry).await?,
+        _ => lockfile_entry.map(Into::into),
     };
 
+    if let (Some(journal), Some(AssetRef::Cloud(id))) = (journal, &asset_ref) {
+        journal.uploaded(&state.input_name, &asset.hash, *id);
+    }
+
+    state.params.report(ProgressEvent::Completed {
+        path: path.into(),
+        bytes,
+        new: is_new,
+        duplicate: false,
+        asset_id: asset_ref.as_ref().map(ToString::to_string),
+    });
+
     let event = super::Event::Finished {
         state: super::EventState::Synced { new: is_new },
         input_name: state.input_name.clone(),
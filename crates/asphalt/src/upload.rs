@@ -1,23 +1,19 @@
-use crate::{asset::Asset, cli::UploadArgs, config::Creator, web_api::WebApiClient};
+use crate::{
+    asset::Asset,
+    cli::UploadArgs,
+    config::{Creator, InputAssetType},
+    web_api::WebApiClient,
+};
 use anyhow::Context;
 use fs_err::tokio as fs;
-use relative_path::PathExt;
+use relative_path::RelativePathBuf;
 use resvg::usvg::fontdb::Database;
-use std::{path::PathBuf, sync::Arc};
+use std::{path::Path, sync::Arc};
 
 pub async fn upload(args: UploadArgs) -> anyhow::Result<()> {
-    let path = PathBuf::from(&args.path);
-    let data = fs::read(&path).await?;
-
-    let mut font_db = Database::new();
-    font_db.load_system_fonts();
-
-    let mut asset =
-        Asset::new(path.relative_to(".")?, data.into()).context("Failed to create asset")?;
-
-    asset
-        .process(Arc::new(font_db), args.bleed)
-        .context("Failed to process asset")?;
+    let path = Path::new(&args.path);
+    let data = fs::read(path).await?;
+    let asset = prepare(path, data, args.bleed, None)?;
 
     let creator = Creator {
         ty: args.creator_type,
@@ -41,3 +37,32 @@ pub async fn upload(args: UploadArgs) -> anyhow::Result<()> {
 
     Ok(())
 }
+
+/// Processes the contents of `path` the way a sync would, ready to upload.
+/// `data` may differ from the file on disk when the caller preprocessed it.
+pub fn prepare(
+    path: &Path,
+    data: Vec<u8>,
+    bleed: bool,
+    asset_type: Option<InputAssetType>,
+) -> anyhow::Result<Asset> {
+    let file_name = path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .context("File has no name")?;
+
+    let mut font_db = Database::new();
+    font_db.load_system_fonts();
+
+    let mut asset = Asset::new(RelativePathBuf::from(file_name), data.into())
+        .context("Failed to create asset")?;
+    if let Some(asset_type) = asset_type {
+        asset.set_type(asset_type)?;
+    }
+
+    asset
+        .process(Arc::new(font_db), bleed, None)
+        .context("Failed to process asset")?;
+
+    Ok(asset)
+}
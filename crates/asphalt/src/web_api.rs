@@ -11,7 +11,7 @@ use std::{
     sync::atomic::{AtomicBool, Ordering},
     time::Duration,
 };
-use tokio::sync::Mutex;
+use tokio::sync::{Mutex, Semaphore};
 use tokio::time::Instant;
 
 const RATELIMIT_RESET_HEADER: &str = "x-ratelimit-reset";
@@ -29,6 +29,11 @@ pub struct WebApiClient {
     fatally_failed: AtomicBool,
     /// Shared rate limit state: when we can next make a request
     rate_limit_reset: Mutex<Option<Instant>>,
+    /// Caps how many uploads are in flight at once
+    upload_permits: Option<Semaphore>,
+    /// Minimum spacing between requests, and when the next one may start
+    request_interval: Option<Duration>,
+    next_request: Mutex<Option<Instant>>,
 }
 
 impl WebApiClient {
@@ -40,14 +45,36 @@ impl WebApiClient {
             expected_price,
             fatally_failed: AtomicBool::new(false),
             rate_limit_reset: Mutex::new(None),
+            upload_permits: None,
+            request_interval: None,
+            next_request: Mutex::new(None),
         }
     }
 
+    /// Limits concurrent uploads and spaces requests to stay under
+    /// `requests_per_minute`.
+    pub fn with_limits(
+        mut self,
+        upload_concurrency: Option<usize>,
+        requests_per_minute: Option<u32>,
+    ) -> Self {
+        self.upload_permits = upload_concurrency.map(|n| Semaphore::new(n.max(1)));
+        self.request_interval = requests_per_minute
+            .filter(|&n| n > 0)
+            .map(|n| Duration::from_secs(60) / n);
+        self
+    }
+
     pub async fn upload(&self, asset: &Asset) -> anyhow::Result<u64> {
         if env::var("ASPHALT_TEST").is_ok() {
             return Ok(asset.hash.as_u64());
         }
 
+        let _permit = match &self.upload_permits {
+            Some(permits) => Some(permits.acquire().await?),
+            None => None,
+        };
+
         let file_name = asset.path.file_name().unwrap();
         let display_name = trim_display_name(file_name);
 
@@ -135,6 +162,22 @@ impl WebApiClient {
         bail!("Operation polling exceeded maximum retries")
     }
 
+    /// Reserves the next request slot when a request rate is configured.
+    async fn wait_for_request_slot(&self) {
+        let Some(interval) = self.request_interval else {
+            return;
+        };
+
+        let start = {
+            let mut next = self.next_request.lock().await;
+            let now = Instant::now();
+            let start = next.map_or(now, |next| next.max(now));
+            *next = Some(start + interval);
+            start
+        };
+        tokio::time::sleep_until(start).await;
+    }
+
     async fn send_with_retry<F>(&self, make_req: F) -> anyhow::Result<Response>
     where
         F: Fn(&reqwest::Client) -> RequestBuilder,
@@ -147,6 +190,8 @@ impl WebApiClient {
         let mut attempt = 0;
 
         loop {
+            self.wait_for_request_slot().await;
+
             {
                 let reset = self.rate_limit_reset.lock().await;
                 if let Some(reset_at) = *reset {
@@ -2,9 +2,11 @@ use anyhow::{Context, Result};
 use asphalt::config::Config as AsphaltConfig;
 use fs_err::tokio as fs;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::path::PathBuf;
 
 pub const FILE_NAME: &str = "truffle.toml";
+pub const WORKSPACE_FILE_NAME: &str = "truffle-workspace.toml";
 
 /// Truffle configuration that extends Asphalt's configuration
 #[derive(Debug, Deserialize, Serialize, Clone)]
@@ -16,6 +18,32 @@ pub struct TruffleConfig {
     /// Truffle-specific configuration
     #[serde(default)]
     pub truffle: TruffleOptions,
+
+    /// Font atlases generated and uploaded during sync, by name
+    #[serde(default)]
+    pub fonts: BTreeMap<String, FontOptions>,
+}
+
+/// Root `truffle-workspace.toml` of a monorepo with several truffle projects
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct WorkspaceConfig {
+    /// Member project directories, relative to the workspace root
+    pub members: Vec<PathBuf>,
+
+    /// Content-addressed cache shared by all members
+    #[serde(default = "default_workspace_cache_dir")]
+    pub cache_dir: PathBuf,
+}
+
+impl WorkspaceConfig {
+    /// Read truffle-workspace.toml from the current directory
+    pub async fn read() -> Result<Self> {
+        let config_str = fs::read_to_string(WORKSPACE_FILE_NAME)
+            .await
+            .context("Failed to read truffle-workspace.toml")?;
+
+        toml::from_str(&config_str).context("Failed to parse truffle-workspace.toml")
+    }
 }
 
 /// Truffle-specific options
@@ -33,6 +61,16 @@ pub struct TruffleOptions {
     #[serde(default)]
     pub highlight_force: bool,
 
+    /// Write generated variants (highlights) to `<scratch_dir>/generated` instead of
+    /// next to their sources in the images folder
+    #[serde(default)]
+    pub generated_in_scratch: bool,
+
+    /// Images folders synced together in place of `--images-folder`, each
+    /// with its keys namespaced under a prefix
+    #[serde(default)]
+    pub image_roots: Vec<ImageRoot>,
+
     /// Pack UI images into atlas textures before syncing
     #[serde(default)]
     pub atlas: bool,
@@ -41,17 +79,334 @@ pub struct TruffleOptions {
     #[serde(default = "default_atlas_size")]
     pub atlas_size: u32,
 
-    /// Padding (in pixels) around each sprite in the atlas
+    /// Atlas texture height (power of two), for rectangular pages; defaults
+    /// to `atlas_size`
+    #[serde(default)]
+    pub atlas_height: Option<u32>,
+
+    /// Shrink each atlas page to the smallest power-of-two size that fits its
+    /// sprites
+    #[serde(default)]
+    pub atlas_shrink: bool,
+
+    /// Padding (in pixels) around each sprite in the atlas, filled with its
+    /// extruded edge pixels
     #[serde(default = "default_atlas_padding")]
     pub atlas_padding: u32,
 
+    /// Algorithm used to arrange sprites on atlas pages
+    #[serde(default)]
+    pub atlas_packer: AtlasPacker,
+
+    /// Rotate sprites 90° in atlases when that packs them tighter
+    #[serde(default)]
+    pub atlas_rotation: bool,
+
+    /// Crop transparent margins off sprites before packing them into atlases
+    #[serde(default)]
+    pub atlas_trim: bool,
+
+    /// PNG compression level used when writing atlas pages
+    #[serde(default)]
+    pub atlas_compression: AtlasCompression,
+
+    /// Composite atlas pages one at a time to bound peak memory
+    #[serde(default)]
+    pub atlas_low_memory: bool,
+
+    /// Write the atlas packing report to `<scratch_dir>/atlas-report.json`
+    #[serde(default)]
+    pub atlas_report: bool,
+
+    /// Sprite sheet descriptions (TexturePacker JSON hash or array), relative
+    /// to the images folder, whose sheets are used as atlas pages as they are
+    #[serde(default)]
+    pub atlas_prepacked: Vec<String>,
+
     /// Atlas image keys to exclude from packing
     #[serde(default)]
     pub atlas_exclude: Vec<String>,
 
+    /// Upload images wider or taller than this standalone instead of packing them
+    #[serde(default)]
+    pub atlas_max_sprite_size: Option<u32>,
+
+    /// Upload images narrower and shorter than this standalone instead of packing them
+    #[serde(default)]
+    pub atlas_min_sprite_size: Option<u32>,
+
+    /// Export `.aseprite`/`.ase` files in the images folder during sync
+    #[serde(default)]
+    pub aseprite_export: Option<AsepriteExport>,
+
+    /// Export animated `.gif` files in the images folder to numbered frames during sync
+    #[serde(default)]
+    pub gif_export: bool,
+
+    /// Flatten `.psd` files in the images folder to PNG during sync
+    #[serde(default)]
+    pub psd_export: bool,
+
+    /// Regex of PSD layer group names to export as `name/<group>.png` instead of flattening
+    #[serde(default)]
+    pub psd_groups: Option<String>,
+
+    /// Scales to rasterize SVG images at during sync (1 → `name.png`, N → `name@Nx.png`)
+    #[serde(default)]
+    pub svg_scales: Vec<u32>,
+
+    /// Write the `svg_scales` PNGs to `<scratch_dir>/generated` instead of next to
+    /// their SVGs (implies `generated_in_scratch`)
+    #[serde(default)]
+    pub svg_in_scratch: bool,
+
+    /// Augmentation stages turned on or off by name (`highlights = false`)
+    #[serde(default)]
+    pub augmenters: BTreeMap<String, bool>,
+
+    /// Downscale images whose longest side exceeds this many pixels before uploading them
+    #[serde(default)]
+    pub max_dimension: Option<u32>,
+
+    /// Caps for images under a directory of the images folder (`"ui/icons" = 256`),
+    /// overriding `max_dimension`
+    #[serde(default)]
+    pub directory_max_dimensions: BTreeMap<String, u32>,
+
+    /// Resampling filter used to downscale oversized images
+    #[serde(default)]
+    pub downscale_filter: DownscaleFilter,
+
+    /// Order generated keys naturally (`frame2` before `frame10`) instead of byte-wise
+    #[serde(default)]
+    pub natural_sort: bool,
+
+    /// Generate animation timelines from exported timelines and numbered image sequences
+    #[serde(default)]
+    pub animations: bool,
+
+    /// Frame duration (in milliseconds) for numbered sequences without timing data
+    #[serde(default = "default_animation_frame_ms")]
+    pub animation_frame_ms: u32,
+
+    /// Path to write the Luau flipbook player to (a `.d.ts` is written next to it)
+    #[serde(default)]
+    pub animation_player: Option<PathBuf>,
+
+    /// Globs (relative to the images folder) of images to export as raw RGBA buffers for EditableImage
+    #[serde(default)]
+    pub editable_images: Vec<String>,
+
+    /// Folder to write raw image modules and their loader to
+    #[serde(default = "default_editable_images_output")]
+    pub editable_images_output: PathBuf,
+
+    /// Asset hygiene rules checked during sync
+    #[serde(default)]
+    pub policy: PolicyOptions,
+
     /// Scratch directory for intermediate/generated files
     #[serde(default = "default_scratch_dir")]
     pub scratch_dir: PathBuf,
+
+    /// Remote store shared by the intermediate cache and atlas manifests
+    #[serde(default)]
+    pub remote_cache: Option<RemoteCacheOptions>,
+
+    /// Webhook notified when a sync finishes or fails
+    #[serde(default)]
+    pub notify: Option<NotifyOptions>,
+
+    /// Locale names recognized as `<locale>/` folders or `@<locale>` suffixes
+    #[serde(default)]
+    pub locales: Vec<String>,
+
+    /// Locale used for the base entry when no unlocalized image exists
+    #[serde(default)]
+    pub default_locale: Option<String>,
+
+    /// Theme names recognized as `<theme>/` folders or `@<theme>` suffixes
+    #[serde(default)]
+    pub themes: Vec<String>,
+
+    /// Theme used for the base entry when no unthemed image exists
+    #[serde(default)]
+    pub default_theme: Option<String>,
+}
+
+/// An images folder whose keys are namespaced under `prefix`
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+pub struct ImageRoot {
+    /// Images folder, matching the path of one Asphalt input
+    pub path: PathBuf,
+    /// Key prefix (`ui` turns `play.png` into `ui/play.png`)
+    pub prefix: String,
+}
+
+/// A font atlas `truffle font` generates during sync
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct FontOptions {
+    /// Font file to rasterize
+    pub input: PathBuf,
+
+    /// Luau module the atlas metadata is written to, with the uploaded atlas ids
+    /// (a `.d.ts` is written next to it)
+    pub output: PathBuf,
+
+    /// Characters to pack (defaults to printable ASCII)
+    #[serde(default)]
+    pub charset: Option<String>,
+
+    /// Pixel size to rasterize at (defaults to fitting the cell)
+    #[serde(default)]
+    pub size: Option<f32>,
+
+    /// Cell size in pixels
+    #[serde(default)]
+    pub cell: Option<u32>,
+
+    /// Thickness in pixels of an outline variant (0 disables it)
+    #[serde(default)]
+    pub outline: u32,
+}
+
+/// How sprites are arranged on atlas pages
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum AtlasPacker {
+    /// Rows of sprites sorted by height
+    #[default]
+    Shelf,
+    /// MaxRects, which fills pages tightly when sprite sizes vary
+    MaxRects,
+}
+
+/// How hard atlas pages are compressed when they are written
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum AtlasCompression {
+    /// Quick encoding for iterating locally; larger files
+    Fast,
+    /// Balanced encoding speed and file size
+    #[default]
+    Default,
+    /// Slowest encoding, smallest files to upload
+    Best,
+}
+
+/// Resampling filter for downscaled images, fastest to sharpest
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum DownscaleFilter {
+    /// Nearest neighbor, which keeps pixel art crisp
+    Nearest,
+    /// Linear filtering
+    Triangle,
+    /// Cubic filtering
+    CatmullRom,
+    /// Gaussian filtering, which softens edges
+    Gaussian,
+    /// Lanczos with a window of 3
+    #[default]
+    Lanczos3,
+}
+
+/// How Aseprite files are exported during sync
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum AsepriteExport {
+    /// Flatten the first frame's visible layers into `name.png`
+    Flatten,
+    /// Export each tag as numbered frames under `name/` with an `animations.json` timeline
+    Tags,
+}
+
+/// Remote backing store for the content-addressed cache
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct RemoteCacheOptions {
+    /// `s3://bucket/prefix` or an `http(s)://` base URL that accepts GET and PUT
+    pub url: String,
+
+    /// Endpoint of an S3-compatible store (defaults to AWS for the region)
+    #[serde(default)]
+    pub endpoint: Option<String>,
+
+    /// S3 region used for request signing
+    #[serde(default = "default_remote_cache_region")]
+    pub region: String,
+
+    /// Only download artifacts, never upload them
+    #[serde(default)]
+    pub read_only: bool,
+}
+
+/// Webhook notification sent after each sync
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct NotifyOptions {
+    /// Slack or Discord incoming webhook URL (or any endpoint accepting a JSON POST)
+    pub url: String,
+
+    /// Environment name included in the message, e.g. `production`
+    #[serde(default)]
+    pub environment: Option<String>,
+
+    /// Link to the sync report included in the message (`TRUFFLE_REPORT_URL` overrides it)
+    #[serde(default)]
+    pub report_url: Option<String>,
+
+    /// Notify after successful syncs
+    #[serde(default = "default_true")]
+    pub on_success: bool,
+
+    /// Notify after failed syncs
+    #[serde(default = "default_true")]
+    pub on_failure: bool,
+}
+
+/// Asset hygiene rules, each reported at its own level
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+pub struct PolicyOptions {
+    /// Maximum file size in bytes
+    #[serde(default)]
+    pub max_file_size: Option<PolicyRule<u64>>,
+
+    /// Maximum image `[width, height]` in pixels
+    #[serde(default)]
+    pub max_dimensions: Option<PolicyRule<[u32; 2]>>,
+
+    /// Allowed file extensions (e.g. `["png", "svg"]`)
+    #[serde(default)]
+    pub allowed_formats: Option<PolicyRule<Vec<String>>>,
+
+    /// Regex that image paths (relative to the images folder) must match
+    #[serde(default)]
+    pub naming: Option<PolicyRule<String>>,
+
+    /// Globs of images that must have a `-highlight.png` sibling
+    #[serde(default)]
+    pub require_highlight: Option<PolicyRule<Vec<String>>>,
+
+    /// Maximum number of atlas pages when packing atlases
+    #[serde(default)]
+    pub max_atlas_pages: Option<PolicyRule<usize>>,
+}
+
+/// A policy limit and how violations are reported
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct PolicyRule<T> {
+    pub value: T,
+
+    #[serde(default)]
+    pub level: PolicyLevel,
+}
+
+/// Whether a policy violation only warns or fails the sync
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum PolicyLevel {
+    #[default]
+    Warn,
+    Error,
 }
 
 fn default_thickness() -> u32 {
@@ -66,6 +421,26 @@ fn default_atlas_size() -> u32 {
     1024
 }
 
+fn default_animation_frame_ms() -> u32 {
+    100
+}
+
+fn default_editable_images_output() -> PathBuf {
+    PathBuf::from("src/shared/data/editable")
+}
+
+fn default_remote_cache_region() -> String {
+    "us-east-1".to_string()
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_workspace_cache_dir() -> PathBuf {
+    PathBuf::from(".truffle/cache")
+}
+
 fn default_scratch_dir() -> PathBuf {
     PathBuf::from(".truffle")
 }
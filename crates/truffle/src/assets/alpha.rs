@@ -0,0 +1,115 @@
+use super::augmenter::{AssetContext, Augmenter};
+use super::model::{AssetMeta, SliceCenter};
+use image::DynamicImage;
+
+/// Optional stage scanning the alpha channel of each image: `opaque` tells
+/// whether every pixel is fully opaque, and `alphaBleed` bounds the visible
+/// pixels when the image has a transparent margin. Off unless `alpha = true`
+/// is set in `[truffle.augmenters]`, since every image is decoded.
+pub struct AlphaCoverage;
+
+impl Augmenter for AlphaCoverage {
+    fn name(&self) -> &'static str {
+        "alpha"
+    }
+
+    fn enabled_by_default(&self) -> bool {
+        false
+    }
+
+    fn augment(&self, meta: &mut AssetMeta, asset: &AssetContext) {
+        if meta.kind.is_some() || !crate::image::is_raster_image(asset.path) {
+            return;
+        }
+        let image = match image::open(asset.path) {
+            Ok(image) => image,
+            Err(e) => {
+                println!(
+                    "[sync] WARN: Failed to read {}: {} – skipping alpha coverage.",
+                    asset.path.display(),
+                    e
+                );
+                return;
+            }
+        };
+        let (opaque, bounds) = alpha_coverage(&image);
+        meta.opaque = Some(opaque);
+        meta.alpha_bleed = bounds;
+    }
+}
+
+/// Whether every pixel of `image` is opaque, and the bounds of its visible
+/// pixels when they don't cover the whole image.
+fn alpha_coverage(image: &DynamicImage) -> (bool, Option<SliceCenter>) {
+    if !image.color().has_alpha() {
+        return (true, None);
+    }
+    let image = image.to_rgba8();
+    let mut opaque = true;
+    let mut bounds: Option<SliceCenter> = None;
+    for (x, y, pixel) in image.enumerate_pixels() {
+        let alpha = pixel[3];
+        opaque &= alpha == u8::MAX;
+        if alpha == 0 {
+            continue;
+        }
+        let rect = bounds.get_or_insert(SliceCenter {
+            min_x: x,
+            min_y: y,
+            max_x: x + 1,
+            max_y: y + 1,
+        });
+        rect.min_x = rect.min_x.min(x);
+        rect.min_y = rect.min_y.min(y);
+        rect.max_x = rect.max_x.max(x + 1);
+        rect.max_y = rect.max_y.max(y + 1);
+    }
+    let whole = SliceCenter {
+        min_x: 0,
+        min_y: 0,
+        max_x: image.width(),
+        max_y: image.height(),
+    };
+    (opaque, bounds.filter(|bounds| *bounds != whole))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{Rgb, RgbImage, Rgba, RgbaImage};
+
+    #[test]
+    fn bounds_cover_the_visible_pixels() {
+        let mut sprite = RgbaImage::new(8, 6);
+        sprite.put_pixel(2, 1, Rgba([255, 255, 255, 255]));
+        sprite.put_pixel(5, 3, Rgba([255, 255, 255, 40]));
+        assert_eq!(
+            alpha_coverage(&DynamicImage::ImageRgba8(sprite)),
+            (
+                false,
+                Some(SliceCenter {
+                    min_x: 2,
+                    min_y: 1,
+                    max_x: 6,
+                    max_y: 4,
+                })
+            )
+        );
+
+        let faded = RgbaImage::from_pixel(4, 4, Rgba([0, 0, 0, 128]));
+        assert_eq!(
+            alpha_coverage(&DynamicImage::ImageRgba8(faded)),
+            (false, None)
+        );
+        let solid = RgbaImage::from_pixel(4, 4, Rgba([0, 0, 0, 255]));
+        assert_eq!(
+            alpha_coverage(&DynamicImage::ImageRgba8(solid)),
+            (true, None)
+        );
+        let photo = RgbImage::from_pixel(4, 4, Rgb([10, 20, 30]));
+        assert_eq!(
+            alpha_coverage(&DynamicImage::ImageRgb8(photo)),
+            (true, None)
+        );
+    }
+}
@@ -0,0 +1,266 @@
+use super::model::{AssetMeta, AssetValue};
+use super::sort::KeyOrder;
+use super::variants::collect_leaves;
+use crate::image::animation::{read_manifest, ANIMATIONS_FILE};
+use regex::Regex;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+use walkdir::WalkDir;
+
+/// One frame of an animation timeline.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AnimationFrame {
+    pub asset: AssetMeta,
+    pub duration_ms: u32,
+}
+
+/// A playable sequence of frames, keyed by `<folder>/<name>` in the module.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Animation {
+    pub frames: Vec<AnimationFrame>,
+    /// `forward`, `reverse`, `pingpong` or `pingpong_reverse`.
+    pub loop_mode: String,
+    /// Number of times to play the animation (0 = loop forever).
+    pub repeat: u32,
+}
+
+/// Luau flipbook player that plays [`Animation`] timelines on image labels.
+pub const FLIPBOOK_LUAU: &str = include_str!("flipbook.luau");
+
+/// TypeScript declarations for [`FLIPBOOK_LUAU`].
+pub const FLIPBOOK_DTS: &str = include_str!("flipbook.d.ts");
+
+#[derive(Debug, Clone)]
+pub struct AnimationOptions {
+    /// Frame duration for numbered sequences, which carry no timing of their own.
+    pub frame_ms: u32,
+    /// Orders frames whose numbers are equal (`walk_1.png`, `walk_01.png`).
+    pub key_order: KeyOrder,
+}
+
+/// Builds animation timelines from the synced assets.
+///
+/// Folders with an `animations.json` (written by the Aseprite and GIF
+/// exporters) use its frames and timing. Elsewhere, two or more images that
+/// only differ by a trailing number (`walk_01.png`, `walk_02.png`) form a
+/// forward-looping sequence.
+pub fn build_animations(
+    assets: &BTreeMap<String, AssetValue>,
+    images_folder: &Path,
+    options: &AnimationOptions,
+) -> BTreeMap<String, Animation> {
+    let mut leaves = Vec::new();
+    collect_leaves(assets, &mut Vec::new(), &mut leaves);
+    let index: BTreeMap<String, AssetMeta> = leaves
+        .into_iter()
+        .map(|(path, meta)| (path.join("/"), meta))
+        .collect();
+
+    let mut animations = BTreeMap::new();
+    let mut manifest_dirs = BTreeSet::new();
+
+    let manifests = WalkDir::new(images_folder)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file() && e.file_name() == ANIMATIONS_FILE);
+    for entry in manifests {
+        let Some(dir) = entry
+            .path()
+            .parent()
+            .and_then(|dir| dir.strip_prefix(images_folder).ok())
+        else {
+            continue;
+        };
+        let dir = dir
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+
+        let manifest = match read_manifest(entry.path()) {
+            Ok(manifest) => manifest,
+            Err(e) => {
+                println!("[sync] WARN: {} – skipping animations.", e);
+                continue;
+            }
+        };
+        manifest_dirs.insert(dir.clone());
+
+        for (name, entry) in manifest {
+            let key = join_key(&dir, &name);
+            let mut frames = Vec::new();
+            for (i, file) in entry.frames.iter().enumerate() {
+                let Some(asset) = find_frame(&index, &join_key(&dir, file)) else {
+                    println!(
+                        "[sync] WARN: Animation {} references {}, which is not in the assets module – skipping.",
+                        key, file
+                    );
+                    frames.clear();
+                    break;
+                };
+                frames.push(AnimationFrame {
+                    asset: asset.clone(),
+                    duration_ms: entry.durations.get(i).copied().unwrap_or(options.frame_ms),
+                });
+            }
+            if !frames.is_empty() {
+                animations.insert(
+                    key,
+                    Animation {
+                        frames,
+                        loop_mode: entry.direction,
+                        repeat: entry.repeat,
+                    },
+                );
+            }
+        }
+    }
+
+    let numbered = Regex::new(r"^(.*?)[-_]?(\d+)(\.png)?$").unwrap();
+    let mut sequences: BTreeMap<String, Vec<(u64, &str, &AssetMeta)>> = BTreeMap::new();
+    for (path, meta) in &index {
+        let (dir, file) = path.rsplit_once('/').unwrap_or(("", path.as_str()));
+        if manifest_dirs.contains(dir) || file.contains("-highlight") {
+            continue;
+        }
+        let Some(captures) = numbered.captures(file) else {
+            continue;
+        };
+        let Ok(number) = captures[2].parse::<u64>() else {
+            continue;
+        };
+        let key = join_key(dir, &captures[1]);
+        if key.is_empty() || animations.contains_key(&key) {
+            continue;
+        }
+        sequences
+            .entry(key)
+            .or_default()
+            .push((number, path.as_str(), meta));
+    }
+
+    for (key, mut frames) in sequences {
+        if frames.len() < 2 {
+            continue;
+        }
+        frames.sort_by(|(a, a_path, _), (b, b_path, _)| {
+            a.cmp(b)
+                .then_with(|| options.key_order.compare(a_path, b_path))
+        });
+        animations.insert(
+            key,
+            Animation {
+                frames: frames
+                    .into_iter()
+                    .map(|(_, _, asset)| AnimationFrame {
+                        asset: asset.clone(),
+                        duration_ms: options.frame_ms,
+                    })
+                    .collect(),
+                loop_mode: "forward".into(),
+                repeat: 0,
+            },
+        );
+    }
+
+    animations
+}
+
+fn join_key(dir: &str, name: &str) -> String {
+    match (dir.is_empty(), name.is_empty()) {
+        (true, _) => name.to_string(),
+        (false, true) => dir.to_string(),
+        (false, false) => format!("{}/{}", dir, name),
+    }
+}
+
+/// Looks up a frame by file path, also accepting keys with stripped extensions.
+fn find_frame<'a>(index: &'a BTreeMap<String, AssetMeta>, path: &str) -> Option<&'a AssetMeta> {
+    index
+        .get(path)
+        .or_else(|| index.get(path.strip_suffix(".png")?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn meta(id: &str) -> AssetValue {
+        AssetValue::Object(AssetMeta {
+            id: id.to_string(),
+            width: Some(16),
+            height: Some(16),
+            ..Default::default()
+        })
+    }
+
+    fn options() -> AnimationOptions {
+        AnimationOptions {
+            frame_ms: 80,
+            key_order: KeyOrder::Lexical,
+        }
+    }
+
+    #[test]
+    fn numbered_images_form_sequences() {
+        let mut fx = BTreeMap::new();
+        for (file, id) in [
+            ("walk_10.png", "3"),
+            ("walk_2.png", "2"),
+            ("walk_1.png", "1"),
+            ("walk_1-highlight.png", "9"),
+            ("icon1.png", "4"),
+            ("run02.png", "6"),
+            ("run01.png", "5"),
+        ] {
+            fx.insert(file.to_string(), meta(id));
+        }
+        let mut assets = BTreeMap::new();
+        assets.insert("fx".to_string(), AssetValue::Table(fx));
+
+        let animations = build_animations(&assets, Path::new("missing"), &options());
+        assert_eq!(animations.len(), 2);
+        let run: Vec<_> = animations["fx/run"]
+            .frames
+            .iter()
+            .map(|f| f.asset.id.as_str())
+            .collect();
+        assert_eq!(run, vec!["5", "6"]);
+        let walk = &animations["fx/walk"];
+        let ids: Vec<_> = walk.frames.iter().map(|f| f.asset.id.as_str()).collect();
+        assert_eq!(ids, vec!["1", "2", "3"]);
+        assert_eq!(walk.frames[0].duration_ms, 80);
+        assert_eq!(walk.loop_mode, "forward");
+    }
+
+    #[test]
+    fn manifests_provide_timing_and_loop_mode() {
+        let root = std::env::temp_dir().join(format!("truffle-anim-{}", std::process::id()));
+        let dir = root.join("hero");
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(
+            dir.join(ANIMATIONS_FILE),
+            r#"{
+                "run": { "frames": ["run01.png", "run02.png"], "durations": [50, 70], "direction": "pingpong", "repeat": 2 },
+                "jump": { "frames": ["jump01.png"], "durations": [100], "direction": "forward", "repeat": 0 }
+            }"#,
+        )
+        .unwrap();
+
+        let mut hero = BTreeMap::new();
+        hero.insert("run01".to_string(), meta("1"));
+        hero.insert("run02".to_string(), meta("2"));
+        let mut assets = BTreeMap::new();
+        assets.insert("hero".to_string(), AssetValue::Table(hero));
+
+        let animations = build_animations(&assets, &root, &options());
+        std::fs::remove_dir_all(&root).ok();
+
+        assert_eq!(animations.len(), 1, "jump01.png was never synced");
+        let run = &animations["hero/run"];
+        assert_eq!(run.loop_mode, "pingpong");
+        assert_eq!(run.repeat, 2);
+        let durations: Vec<_> = run.frames.iter().map(|f| f.duration_ms).collect();
+        assert_eq!(durations, vec![50, 70]);
+    }
+}
@@ -1,8 +1,12 @@
 use super::model::{AssetMeta, AssetValue};
+use crate::cache::ContentCache;
 use anyhow::{Context, Result};
 use asphalt::glob::Glob;
-use image::{GenericImageView, ImageBuffer, Rgba};
-use std::collections::{BTreeMap, HashMap, HashSet};
+use image::codecs::png::{CompressionType, FilterType, PngEncoder};
+use image::{ImageBuffer, ImageEncoder, Rgba};
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::path::{Path, PathBuf};
 use walkdir::WalkDir;
 
@@ -12,8 +16,40 @@ const MIN_ATLAS_SIZE: u32 = 256;
 #[derive(Debug, Clone)]
 pub struct AtlasOptions {
     pub padding: u32,
+    /// Page width, and page height unless `height` is set.
     pub size: u32,
+    pub height: Option<u32>,
+    /// Shrink each page to the smallest power-of-two size that holds its
+    /// sprites instead of always writing full-size pages.
+    pub shrink: bool,
     pub exclude: AtlasExclude,
+    /// Content-addressed cache for composited atlas pages.
+    pub cache: Option<ContentCache>,
+    /// Generated variants kept outside the images folder, packed under the
+    /// same keys as if they sat next to their sources.
+    pub generated_dir: Option<PathBuf>,
+    pub algorithm: PackingAlgorithm,
+    /// Let the packer turn sprites 90° when that packs them tighter.
+    pub allow_rotation: bool,
+    /// Crop fully transparent margins off sprites before packing.
+    pub trim: bool,
+    /// zlib effort spent encoding pages; higher levels upload smaller files.
+    pub compression: CompressionType,
+    /// Composite one page at a time and drop each decoded sprite once it is
+    /// blitted, trading speed for a peak memory of about one page.
+    pub low_memory: bool,
+}
+
+/// How sprites are arranged on atlas pages.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PackingAlgorithm {
+    /// Rows of sprites sorted by height. Fast, but wastes space next to short
+    /// sprites when sizes vary.
+    #[default]
+    Shelf,
+    /// MaxRects with best-short-side-fit: keeps track of every free rectangle
+    /// on a page, so mixed sizes fill pages tightly.
+    MaxRects,
 }
 
 impl Default for AtlasOptions {
@@ -21,7 +57,16 @@ impl Default for AtlasOptions {
         Self {
             padding: 4,
             size: 1024,
+            height: None,
+            shrink: false,
             exclude: AtlasExclude::default(),
+            cache: None,
+            generated_dir: None,
+            algorithm: PackingAlgorithm::default(),
+            allow_rotation: false,
+            trim: false,
+            compression: CompressionType::Default,
+            low_memory: false,
         }
     }
 }
@@ -42,7 +87,7 @@ impl AtlasExclude {
     }
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub struct AtlasRect {
     pub x: u32,
     pub y: u32,
@@ -53,7 +98,43 @@ pub struct AtlasRect {
 #[derive(Debug, Clone)]
 pub struct SpritePlacement {
     pub atlas_file_name: String,
+    /// The sprite's area on the page. For rotated sprites `w` and `h` are the
+    /// sprite's height and width.
     pub rect: AtlasRect,
+    /// The sprite is stored rotated 90° clockwise.
+    pub rotated: bool,
+    /// Set when transparent margins were cropped off before packing.
+    pub trim: Option<SpriteTrim>,
+}
+
+/// Where the packed part of a trimmed sprite sits in its source image.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SpriteTrim {
+    /// Offset of the kept pixels from the source's top-left corner.
+    pub x: u32,
+    pub y: u32,
+    /// Size of the source image before trimming.
+    pub source_w: u32,
+    pub source_h: u32,
+}
+
+impl SpritePlacement {
+    /// The packed pixels' width and height, undoing any rotation.
+    pub fn sprite_size(&self) -> (u32, u32) {
+        if self.rotated {
+            (self.rect.h, self.rect.w)
+        } else {
+            (self.rect.w, self.rect.h)
+        }
+    }
+
+    /// The width and height of the source image, before trimming.
+    pub fn source_size(&self) -> (u32, u32) {
+        match self.trim {
+            Some(trim) => (trim.source_w, trim.source_h),
+            None => self.sprite_size(),
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -62,6 +143,7 @@ struct PendingSprite {
     src_path: PathBuf,
     w: u32,
     h: u32,
+    trim: Option<SpriteTrim>,
 }
 
 #[derive(Debug, Clone)]
@@ -70,6 +152,8 @@ struct PlacedSprite {
     src_path: PathBuf,
     atlas_index: usize,
     rect: AtlasRect,
+    rotated: bool,
+    trim: Option<SpriteTrim>,
 }
 
 pub fn build_atlases(
@@ -77,7 +161,8 @@ pub fn build_atlases(
     output_dir: &Path,
     options: AtlasOptions,
 ) -> Result<BTreeMap<String, SpritePlacement>> {
-    let atlas_size = validate_atlas_size(options.size)?;
+    let page_w = validate_atlas_size(options.size)?;
+    let page_h = validate_atlas_size(options.height.unwrap_or(options.size))?;
     if output_dir.exists() {
         std::fs::remove_dir_all(output_dir).with_context(|| {
             format!("failed to clean atlas output dir: {}", output_dir.display())
@@ -90,10 +175,22 @@ pub fn build_atlases(
         )
     })?;
 
-    let sprites = scan_pngs(images_folder, &options.exclude)?;
-    let placed = pack_sprites(&sprites, options.padding, atlas_size)?;
+    let mut roots = vec![images_folder];
+    roots.extend(options.generated_dir.as_deref().filter(|dir| dir.is_dir()));
+    let sprites = scan_images(&roots, &options.exclude, options.trim)?;
+    let Deduped {
+        sprites,
+        duplicates,
+    } = dedupe_sprites(sprites)?;
+    let placed = pack_sprites(
+        &sprites,
+        options.padding,
+        (page_w, page_h),
+        options.algorithm,
+        options.allow_rotation,
+    )?;
 
-    write_atlas_images(&placed, output_dir, options.padding, atlas_size)?;
+    write_atlas_images(&placed, output_dir, (page_w, page_h), &options)?;
 
     let mut placements = BTreeMap::new();
     for sprite in placed {
@@ -102,12 +199,70 @@ pub fn build_atlases(
             SpritePlacement {
                 atlas_file_name: atlas_file_name(sprite.atlas_index),
                 rect: sprite.rect,
+                rotated: sprite.rotated,
+                trim: sprite.trim,
             },
         );
     }
+    for (key, original) in duplicates {
+        if let Some(placement) = placements.get(&original).cloned() {
+            placements.insert(key, placement);
+        }
+    }
     Ok(placements)
 }
 
+/// The sprites left to pack after deduplication.
+struct Deduped {
+    sprites: Vec<PendingSprite>,
+    /// `(duplicate key, packed key)` pairs.
+    duplicates: Vec<(String, String)>,
+}
+
+/// Splits off sprites whose file is byte-identical to an earlier one, so
+/// each image is packed once.
+fn dedupe_sprites(sprites: Vec<PendingSprite>) -> Result<Deduped> {
+    // Only sprites sharing a size can match, so most files are never read.
+    let mut sizes: HashMap<(u32, u32), usize> = HashMap::new();
+    for s in &sprites {
+        *sizes.entry((s.w, s.h)).or_default() += 1;
+    }
+
+    let hashes = sprites
+        .par_iter()
+        .map(|s| {
+            if sizes[&(s.w, s.h)] < 2 {
+                return Ok(None);
+            }
+            let bytes = std::fs::read(&s.src_path)
+                .with_context(|| format!("failed to read png: {}", s.src_path.display()))?;
+            Ok(Some(blake3::hash(&bytes)))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut unique = Vec::with_capacity(sprites.len());
+    let mut duplicates = Vec::new();
+    let mut seen: HashMap<blake3::Hash, String> = HashMap::new();
+    for (s, hash) in sprites.into_iter().zip(hashes) {
+        if let Some(hash) = hash {
+            match seen.entry(hash) {
+                std::collections::hash_map::Entry::Occupied(original) => {
+                    duplicates.push((s.key, original.get().clone()));
+                    continue;
+                }
+                std::collections::hash_map::Entry::Vacant(slot) => {
+                    slot.insert(s.key.clone());
+                }
+            }
+        }
+        unique.push(s);
+    }
+    Ok(Deduped {
+        sprites: unique,
+        duplicates,
+    })
+}
+
 pub fn build_atlased_assets(
     placements: &BTreeMap<String, SpritePlacement>,
     atlas_ids: &HashMap<String, String>,
@@ -120,23 +275,22 @@ pub fn build_atlased_assets(
             .cloned()
             .with_context(|| format!("missing atlas id for {}", placement.atlas_file_name))?;
 
+        let (width, height) = placement.source_size();
         let mut meta = AssetMeta {
             id: atlas_id,
-            width: Some(placement.rect.w),
-            height: Some(placement.rect.h),
+            width: Some(width),
+            height: Some(height),
             rect_x: Some(placement.rect.x),
             rect_y: Some(placement.rect.y),
             rect_w: Some(placement.rect.w),
             rect_h: Some(placement.rect.h),
-            highlight_id: None,
-            highlight_rect_x: None,
-            highlight_rect_y: None,
-            highlight_rect_w: None,
-            highlight_rect_h: None,
+            rotated: placement.rotated.then_some(true),
+            trim_x: placement.trim.map(|trim| trim.x),
+            trim_y: placement.trim.map(|trim| trim.y),
+            ..Default::default()
         };
 
-        if !key.ends_with("-highlight.png") {
-            let highlight_key = key.replace(".png", "-highlight.png");
+        if let Some(highlight_key) = crate::image::highlight::highlight_key(key) {
             if let Some(highlight) = placements.get(&highlight_key) {
                 if let Some(h_id) = atlas_ids.get(&highlight.atlas_file_name) {
                     meta.highlight_id = Some(h_id.clone());
@@ -144,6 +298,9 @@ pub fn build_atlased_assets(
                     meta.highlight_rect_y = Some(highlight.rect.y);
                     meta.highlight_rect_w = Some(highlight.rect.w);
                     meta.highlight_rect_h = Some(highlight.rect.h);
+                    meta.highlight_rotated = highlight.rotated.then_some(true);
+                    meta.highlight_trim_x = highlight.trim.map(|trim| trim.x);
+                    meta.highlight_trim_y = highlight.trim.map(|trim| trim.y);
                 }
             }
         }
@@ -154,48 +311,66 @@ pub fn build_atlased_assets(
     Ok(root)
 }
 
-fn scan_pngs(images_folder: &Path, exclude: &AtlasExclude) -> Result<Vec<PendingSprite>> {
-    let mut sprites = Vec::new();
-    for entry in WalkDir::new(images_folder)
-        .follow_links(false)
-        .into_iter()
-        .filter_map(|e| e.ok())
-    {
-        if !entry.file_type().is_file() {
-            continue;
-        }
+/// Collects the images below each root. A key found in an earlier root hides
+/// the same key in later ones. With `trim`, sprites are decoded and sized by
+/// their visible pixels.
+fn scan_images(roots: &[&Path], exclude: &AtlasExclude, trim: bool) -> Result<Vec<PendingSprite>> {
+    let mut files = Vec::new();
+    let mut seen = HashSet::new();
+    for root in roots {
+        for entry in WalkDir::new(root)
+            .follow_links(false)
+            .sort_by_file_name()
+            .into_iter()
+            .filter_map(|e| e.ok())
+        {
+            if !entry.file_type().is_file() {
+                continue;
+            }
 
-        let path = entry.path();
-        if path.extension().and_then(|s| s.to_str()) != Some("png") {
-            continue;
-        }
+            let path = entry.path();
+            if !crate::image::is_raster_image(path) {
+                continue;
+            }
 
-        let rel = path
-            .strip_prefix(images_folder)
-            .with_context(|| format!("failed to get relative path for {}", path.display()))?;
+            let rel = path
+                .strip_prefix(root)
+                .with_context(|| format!("failed to get relative path for {}", path.display()))?;
 
-        let key = rel
-            .components()
-            .map(|c| c.as_os_str().to_string_lossy())
-            .collect::<Vec<_>>()
-            .join("/");
+            let key = rel
+                .components()
+                .map(|c| c.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
 
-        if exclude.is_match(&key) {
-            continue;
-        }
-
-        let img = image::open(path)
-            .with_context(|| format!("failed to decode png: {}", path.display()))?;
-        let (w, h) = img.dimensions();
+            if exclude.is_match(&key) || !seen.insert(key.clone()) {
+                continue;
+            }
 
-        sprites.push(PendingSprite {
-            key,
-            src_path: path.to_path_buf(),
-            w,
-            h,
-        });
+            files.push((key, path.to_path_buf()));
+        }
     }
 
+    // Header reads are cheap but add up across thousands of sprites.
+    let mut sprites = files
+        .into_par_iter()
+        .map(|(key, src_path)| {
+            let (w, h, trim) = if trim {
+                trimmed_size(&src_path)?
+            } else {
+                let (w, h) = image_dimensions(&src_path)?;
+                (w, h, None)
+            };
+            Ok(PendingSprite {
+                key,
+                src_path,
+                w,
+                h,
+                trim,
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+
     sprites.sort_by(|a, b| {
         b.h.cmp(&a.h)
             .then_with(|| b.w.cmp(&a.w))
@@ -205,36 +380,201 @@ fn scan_pngs(images_folder: &Path, exclude: &AtlasExclude) -> Result<Vec<Pending
     Ok(sprites)
 }
 
-fn pack_sprites(sprites: &[PendingSprite], padding: u32, atlas_size: u32) -> Result<Vec<PlacedSprite>> {
-    let mut atlas_index: usize = 0;
-    let mut cursor_x: u32 = 0;
-    let mut cursor_y: u32 = 0;
-    let mut row_h: u32 = 0;
+/// Keys of the images below `roots` that fall outside the sprite size limits,
+/// so they can be excluded from packing like `atlas_exclude` entries.
+pub fn sprite_keys_outside_size(
+    roots: &[&Path],
+    min: Option<u32>,
+    max: Option<u32>,
+) -> Result<Vec<String>> {
+    if min.is_none() && max.is_none() {
+        return Ok(Vec::new());
+    }
+    let sprites = scan_images(roots, &AtlasExclude::default(), false)?;
+    Ok(sprites
+        .into_iter()
+        .filter(|s| outside_size(s.w, s.h, min, max))
+        .map(|s| s.key)
+        .collect())
+}
 
-    let mut placed = Vec::with_capacity(sprites.len());
+/// Whether a `w`x`h` sprite has a side above `max` or both sides below `min`.
+fn outside_size(w: u32, h: u32, min: Option<u32>, max: Option<u32>) -> bool {
+    max.is_some_and(|max| w > max || h > max) || min.is_some_and(|min| w < min && h < min)
+}
 
+/// The size of the visible part of a PNG, and where it sits when any
+/// transparent margin can be cropped. Fully transparent images keep one
+/// pixel.
+fn trimmed_size(path: &Path) -> Result<(u32, u32, Option<SpriteTrim>)> {
+    let img = image::open(path)
+        .with_context(|| format!("failed to decode image: {}", path.display()))?
+        .to_rgba8();
+    let (source_w, source_h) = img.dimensions();
+    let bounds = opaque_bounds(&img).unwrap_or(AtlasRect {
+        x: 0,
+        y: 0,
+        w: source_w.min(1),
+        h: source_h.min(1),
+    });
+    if (bounds.w, bounds.h) == (source_w, source_h) {
+        return Ok((source_w, source_h, None));
+    }
+    Ok((
+        bounds.w,
+        bounds.h,
+        Some(SpriteTrim {
+            x: bounds.x,
+            y: bounds.y,
+            source_w,
+            source_h,
+        }),
+    ))
+}
+
+/// The smallest rect holding every pixel that isn't fully transparent.
+fn opaque_bounds(img: &ImageBuffer<Rgba<u8>, Vec<u8>>) -> Option<AtlasRect> {
+    let (mut min_x, mut min_y, mut max_x, mut max_y) = (u32::MAX, u32::MAX, 0, 0);
+    for (x, y, pixel) in img.enumerate_pixels() {
+        if pixel[3] != 0 {
+            min_x = min_x.min(x);
+            min_y = min_y.min(y);
+            max_x = max_x.max(x);
+            max_y = max_y.max(y);
+        }
+    }
+    (min_x != u32::MAX).then(|| AtlasRect {
+        x: min_x,
+        y: min_y,
+        w: max_x - min_x + 1,
+        h: max_y - min_y + 1,
+    })
+}
+
+/// Reads the size from the image header without decoding any pixels.
+fn image_dimensions(path: &Path) -> Result<(u32, u32)> {
+    image::image_dimensions(path)
+        .with_context(|| format!("failed to read image header: {}", path.display()))
+}
+
+/// Packs `(key, width, height)` rects onto pages of `page` pixels with the
+/// sprite packer, for atlases built outside a sync (font glyphs). Keys must
+/// be unique. Returns each rect's page index and area, in input order.
+pub fn pack_rects(
+    sizes: &[(String, u32, u32)],
+    padding: u32,
+    page: (u32, u32),
+    algorithm: PackingAlgorithm,
+) -> Result<Vec<(usize, AtlasRect)>> {
+    let sprites: Vec<PendingSprite> = sizes
+        .iter()
+        .map(|(key, w, h)| PendingSprite {
+            key: key.clone(),
+            src_path: PathBuf::new(),
+            w: *w,
+            h: *h,
+            trim: None,
+        })
+        .collect();
+    let placed: HashMap<String, (usize, AtlasRect)> =
+        pack_sprites(&sprites, padding, page, algorithm, false)?
+            .into_iter()
+            .map(|s| (s.key, (s.atlas_index, s.rect)))
+            .collect();
+    Ok(sizes.iter().map(|(key, _, _)| placed[key]).collect())
+}
+
+/// Packs sprites onto pages of `page` (width, height) pixels.
+fn pack_sprites(
+    sprites: &[PendingSprite],
+    padding: u32,
+    page: (u32, u32),
+    algorithm: PackingAlgorithm,
+    allow_rotation: bool,
+) -> Result<Vec<PlacedSprite>> {
+    let (page_w, page_h) = page;
     for s in sprites {
         let alloc_w = s.w + padding.saturating_mul(2);
         let alloc_h = s.h + padding.saturating_mul(2);
-
-        if alloc_w > atlas_size || alloc_h > atlas_size {
+        let fits = |w: u32, h: u32| w <= page_w && h <= page_h;
+        let fits_page = fits(alloc_w, alloc_h) || (allow_rotation && fits(alloc_h, alloc_w));
+        if !fits_page {
             anyhow::bail!(
                 "{} is too large to pack into a {}x{} atlas ({}x{})",
                 s.key,
-                atlas_size,
-                atlas_size,
+                page_w,
+                page_h,
                 s.w,
                 s.h
             );
         }
+    }
 
-        if cursor_x.saturating_add(alloc_w) > atlas_size {
+    Ok(match algorithm {
+        PackingAlgorithm::Shelf => pack_shelves(sprites, padding, page, allow_rotation),
+        PackingAlgorithm::MaxRects => pack_max_rects(sprites, padding, page, allow_rotation),
+    })
+}
+
+/// Packs rows of sprites. With rotation, sprites taller than wide are laid
+/// on their side first so rows stay low, unless only the upright sprite fits
+/// the page.
+fn pack_shelves(
+    sprites: &[PendingSprite],
+    padding: u32,
+    (page_w, page_h): (u32, u32),
+    allow_rotation: bool,
+) -> Vec<PlacedSprite> {
+    let mut order: Vec<(&PendingSprite, bool)> = sprites
+        .iter()
+        .map(|s| {
+            let fits = |w: u32, h: u32| {
+                w + padding.saturating_mul(2) <= page_w && h + padding.saturating_mul(2) <= page_h
+            };
+            let rotated = allow_rotation
+                && if fits(s.w, s.h) && fits(s.h, s.w) {
+                    s.h > s.w
+                } else {
+                    !fits(s.w, s.h)
+                };
+            (s, rotated)
+        })
+        .collect();
+    if allow_rotation {
+        let size = |&(s, rotated): &(&PendingSprite, bool)| {
+            if rotated {
+                (s.h, s.w)
+            } else {
+                (s.w, s.h)
+            }
+        };
+        order.sort_by(|a, b| {
+            let ((aw, ah), (bw, bh)) = (size(a), size(b));
+            bh.cmp(&ah)
+                .then_with(|| bw.cmp(&aw))
+                .then_with(|| a.0.key.cmp(&b.0.key))
+        });
+    }
+
+    let mut atlas_index: usize = 0;
+    let mut cursor_x: u32 = 0;
+    let mut cursor_y: u32 = 0;
+    let mut row_h: u32 = 0;
+
+    let mut placed = Vec::with_capacity(sprites.len());
+
+    for (s, rotated) in order {
+        let (w, h) = if rotated { (s.h, s.w) } else { (s.w, s.h) };
+        let alloc_w = w + padding.saturating_mul(2);
+        let alloc_h = h + padding.saturating_mul(2);
+
+        if cursor_x.saturating_add(alloc_w) > page_w {
             cursor_x = 0;
             cursor_y = cursor_y.saturating_add(row_h);
             row_h = 0;
         }
 
-        if cursor_y.saturating_add(alloc_h) > atlas_size {
+        if cursor_y.saturating_add(alloc_h) > page_h {
             atlas_index += 1;
             cursor_x = 0;
             cursor_y = 0;
@@ -244,8 +584,8 @@ fn pack_sprites(sprites: &[PendingSprite], padding: u32, atlas_size: u32) -> Res
         let rect = AtlasRect {
             x: cursor_x + padding,
             y: cursor_y + padding,
-            w: s.w,
-            h: s.h,
+            w,
+            h,
         };
 
         placed.push(PlacedSprite {
@@ -253,68 +593,651 @@ fn pack_sprites(sprites: &[PendingSprite], padding: u32, atlas_size: u32) -> Res
             src_path: s.src_path.clone(),
             atlas_index,
             rect,
+            rotated,
+            trim: s.trim,
         });
 
         cursor_x = cursor_x.saturating_add(alloc_w);
         row_h = row_h.max(alloc_h);
     }
 
-    Ok(placed)
+    placed
+}
+
+/// Places the largest sprites first, each on the first page with room for
+/// it, at the free spot that leaves the shortest leftover side. With
+/// rotation, both orientations compete for that spot.
+fn pack_max_rects(
+    sprites: &[PendingSprite],
+    padding: u32,
+    (page_w, page_h): (u32, u32),
+    allow_rotation: bool,
+) -> Vec<PlacedSprite> {
+    let mut order: Vec<&PendingSprite> = sprites.iter().collect();
+    order.sort_by(|a, b| {
+        b.w.max(b.h)
+            .cmp(&a.w.max(a.h))
+            .then_with(|| b.w.min(b.h).cmp(&a.w.min(a.h)))
+            .then_with(|| a.key.cmp(&b.key))
+    });
+
+    let mut pages: Vec<MaxRectsPage> = Vec::new();
+    let mut placed = Vec::with_capacity(sprites.len());
+    for s in order {
+        let alloc_w = s.w + padding.saturating_mul(2);
+        let alloc_h = s.h + padding.saturating_mul(2);
+        let can_rotate = allow_rotation && s.w != s.h;
+
+        // Ties keep the sprite upright.
+        let fit = |page: &MaxRectsPage| {
+            let upright = page.find(alloc_w, alloc_h).map(|spot| (spot, false));
+            let turned = can_rotate
+                .then(|| page.find(alloc_h, alloc_w))
+                .flatten()
+                .map(|spot| (spot, true));
+            upright
+                .into_iter()
+                .chain(turned)
+                .min_by_key(|(spot, _)| spot.score)
+        };
+        let found = pages
+            .iter()
+            .enumerate()
+            .find_map(|(index, page)| fit(page).map(|found| (index, found)));
+        let (atlas_index, (spot, rotated)) = match found {
+            Some(found) => found,
+            None => {
+                let page = MaxRectsPage::new(page_w, page_h);
+                let found = fit(&page).expect("sprite sizes are checked against the page size");
+                pages.push(page);
+                (pages.len() - 1, found)
+            }
+        };
+        let (w, h) = if rotated { (s.h, s.w) } else { (s.w, s.h) };
+        pages[atlas_index].place(AtlasRect {
+            x: spot.x,
+            y: spot.y,
+            w: w + padding.saturating_mul(2),
+            h: h + padding.saturating_mul(2),
+        });
+
+        placed.push(PlacedSprite {
+            key: s.key.clone(),
+            src_path: s.src_path.clone(),
+            atlas_index,
+            rect: AtlasRect {
+                x: spot.x + padding,
+                y: spot.y + padding,
+                w,
+                h,
+            },
+            rotated,
+            trim: s.trim,
+        });
+    }
+
+    placed
+}
+
+/// The top-left corner of a free spot, with its best-short-side-fit score
+/// (lower is better).
+struct FreeSpot {
+    x: u32,
+    y: u32,
+    score: (u32, u32, u32, u32),
+}
+
+/// The free space of one page as a set of maximal, possibly overlapping
+/// rectangles.
+struct MaxRectsPage {
+    free: Vec<AtlasRect>,
+}
+
+impl MaxRectsPage {
+    fn new(w: u32, h: u32) -> Self {
+        Self {
+            free: vec![AtlasRect { x: 0, y: 0, w, h }],
+        }
+    }
+
+    /// The best free spot for a `w`x`h` rectangle.
+    fn find(&self, w: u32, h: u32) -> Option<FreeSpot> {
+        self.free
+            .iter()
+            .filter(|free| w <= free.w && h <= free.h)
+            .map(|free| {
+                let (dw, dh) = (free.w - w, free.h - h);
+                FreeSpot {
+                    x: free.x,
+                    y: free.y,
+                    score: (dw.min(dh), dw.max(dh), free.y, free.x),
+                }
+            })
+            .min_by_key(|spot| spot.score)
+    }
+
+    /// Marks `used` as taken: splits every free rectangle it overlaps into
+    /// the parts around it, then drops rectangles contained in others.
+    fn place(&mut self, used: AtlasRect) {
+        let mut free = Vec::with_capacity(self.free.len() + 4);
+        for rect in &self.free {
+            if !overlaps(rect, &used) {
+                free.push(*rect);
+                continue;
+            }
+            if used.x > rect.x {
+                free.push(AtlasRect {
+                    w: used.x - rect.x,
+                    ..*rect
+                });
+            }
+            if used.x + used.w < rect.x + rect.w {
+                free.push(AtlasRect {
+                    x: used.x + used.w,
+                    w: rect.x + rect.w - (used.x + used.w),
+                    ..*rect
+                });
+            }
+            if used.y > rect.y {
+                free.push(AtlasRect {
+                    h: used.y - rect.y,
+                    ..*rect
+                });
+            }
+            if used.y + used.h < rect.y + rect.h {
+                free.push(AtlasRect {
+                    y: used.y + used.h,
+                    h: rect.y + rect.h - (used.y + used.h),
+                    ..*rect
+                });
+            }
+        }
+
+        let mut kept: Vec<AtlasRect> = Vec::with_capacity(free.len());
+        for (i, rect) in free.iter().enumerate() {
+            let redundant = free
+                .iter()
+                .enumerate()
+                .any(|(j, other)| j != i && contains(other, rect) && (other != rect || j < i));
+            if !redundant {
+                kept.push(*rect);
+            }
+        }
+        self.free = kept;
+    }
+}
+
+fn overlaps(a: &AtlasRect, b: &AtlasRect) -> bool {
+    a.x < b.x + b.w && b.x < a.x + a.w && a.y < b.y + b.h && b.y < a.y + a.h
+}
+
+fn contains(outer: &AtlasRect, inner: &AtlasRect) -> bool {
+    inner.x >= outer.x
+        && inner.y >= outer.y
+        && inner.x + inner.w <= outer.x + outer.w
+        && inner.y + inner.h <= outer.y + outer.h
 }
 
 fn write_atlas_images(
     placed: &[PlacedSprite],
     output_dir: &Path,
-    padding: u32,
-    atlas_size: u32,
+    page: (u32, u32),
+    options: &AtlasOptions,
 ) -> Result<()> {
-    let mut per_atlas: HashMap<usize, Vec<&PlacedSprite>> = HashMap::new();
+    let mut per_atlas: BTreeMap<usize, Vec<&PlacedSprite>> = BTreeMap::new();
     for s in placed {
         per_atlas.entry(s.atlas_index).or_default().push(s);
     }
 
-    let mut atlas_indices: Vec<usize> = per_atlas.keys().cloned().collect();
-    atlas_indices.sort();
+    let write_page = |(&atlas_index, sprites): (&usize, &Vec<&PlacedSprite>)| {
+        let size = if options.shrink {
+            shrunk_page_size(sprites, options.padding, page)
+        } else {
+            page
+        };
+        write_atlas_page(
+            sprites,
+            &output_dir.join(atlas_file_name(atlas_index)),
+            size,
+            options,
+        )
+    };
+    // Pages are independent, so they are composited (and their sprites
+    // decoded) in parallel unless memory is tight.
+    if options.low_memory {
+        per_atlas.iter().try_for_each(write_page)
+    } else {
+        per_atlas.par_iter().try_for_each(write_page)
+    }
+}
 
-    for atlas_index in atlas_indices {
-        let sprites = per_atlas.get(&atlas_index).unwrap();
-        let mut atlas: ImageBuffer<Rgba<u8>, Vec<u8>> =
-            ImageBuffer::from_pixel(atlas_size, atlas_size, Rgba([0, 0, 0, 0]));
+/// The smallest power-of-two page, up to the full `page` size, that holds
+/// every sprite and its extruded padding. Sprites are packed from the
+/// top-left corner, so their positions stay valid on the smaller page.
+fn shrunk_page_size(sprites: &[&PlacedSprite], padding: u32, page: (u32, u32)) -> (u32, u32) {
+    let (mut right, mut bottom) = (1, 1);
+    for s in sprites {
+        right = right.max(s.rect.x + s.rect.w + padding);
+        bottom = bottom.max(s.rect.y + s.rect.h + padding);
+    }
+    (
+        right.next_power_of_two().min(page.0),
+        bottom.next_power_of_two().min(page.1),
+    )
+}
 
+fn write_atlas_page(
+    sprites: &[&PlacedSprite],
+    path: &Path,
+    (page_w, page_h): (u32, u32),
+    options: &AtlasOptions,
+) -> Result<()> {
+    let (padding, compression, cache) =
+        (options.padding, options.compression, options.cache.as_ref());
+    // A page is fully determined by its layout, encoding and the source PNG
+    // bytes. Sources are hashed one at a time so they are never all in
+    // memory at once.
+    let cache_key = match cache {
+        Some(_) => {
+            let mut parts = vec![
+                page_w.to_le_bytes().to_vec(),
+                page_h.to_le_bytes().to_vec(),
+                padding.to_le_bytes().to_vec(),
+                format!("{:?}", compression).into_bytes(),
+            ];
+            for s in sprites {
+                let bytes = std::fs::read(&s.src_path)
+                    .with_context(|| format!("failed to read png: {}", s.src_path.display()))?;
+                parts.push(blake3::hash(&bytes).as_bytes().to_vec());
+                let (trim_x, trim_y) = s.trim.map_or((0, 0), |trim| (trim.x, trim.y));
+                parts.push(
+                    [
+                        &s.rect.x.to_le_bytes()[..],
+                        &s.rect.y.to_le_bytes(),
+                        &s.rect.w.to_le_bytes(),
+                        &s.rect.h.to_le_bytes(),
+                        &trim_x.to_le_bytes(),
+                        &trim_y.to_le_bytes(),
+                        &[s.rotated as u8],
+                    ]
+                    .concat(),
+                );
+            }
+            let parts: Vec<&[u8]> = parts.iter().map(Vec::as_slice).collect();
+            Some(ContentCache::key("atlas-page", &parts))
+        }
+        None => None,
+    };
+    let outputs = [("atlas.png", path)];
+    if let (Some(cache), Some(key)) = (cache, &cache_key) {
+        if cache.restore(key, &outputs) {
+            return Ok(());
+        }
+    }
+
+    let mut atlas: ImageBuffer<Rgba<u8>, Vec<u8>> =
+        ImageBuffer::from_pixel(page_w, page_h, Rgba([0, 0, 0, 0]));
+
+    if options.low_memory {
         for s in sprites {
-            let img = image::open(&s.src_path)
-                .with_context(|| format!("failed to decode png: {}", s.src_path.display()))?
-                .to_rgba8();
+            let img = load_sprite(s)?;
             blit_with_extrude(&mut atlas, &img, s.rect.x, s.rect.y, padding);
         }
-
-        let path = output_dir.join(atlas_file_name(atlas_index));
-        image::DynamicImage::ImageRgba8(atlas)
-            .save(&path)
-            .with_context(|| format!("failed to write atlas png: {}", path.display()))?;
+    } else {
+        // Decoding dominates page composition; sprites don't overlap, but
+        // blitting stays sequential to keep a single mutable page.
+        let images = sprites
+            .par_iter()
+            .map(|s| load_sprite(s))
+            .collect::<Result<Vec<_>>>()?;
+        for (s, img) in sprites.iter().zip(&images) {
+            blit_with_extrude(&mut atlas, img, s.rect.x, s.rect.y, padding);
+        }
     }
 
+    write_png(&atlas, path, compression)?;
+    if let (Some(cache), Some(key)) = (cache, &cache_key) {
+        cache.store(key, &outputs)?;
+    }
     Ok(())
 }
 
+/// Decodes a sprite's pixels as they are laid out on the page: trimmed and
+/// rotated.
+fn load_sprite(s: &PlacedSprite) -> Result<ImageBuffer<Rgba<u8>, Vec<u8>>> {
+    let mut img = image::open(&s.src_path)
+        .with_context(|| format!("failed to decode png: {}", s.src_path.display()))?
+        .to_rgba8();
+    if let Some(trim) = s.trim {
+        let (w, h) = if s.rotated {
+            (s.rect.h, s.rect.w)
+        } else {
+            (s.rect.w, s.rect.h)
+        };
+        img = image::imageops::crop_imm(&img, trim.x, trim.y, w, h).to_image();
+    }
+    Ok(if s.rotated {
+        image::imageops::rotate90(&img)
+    } else {
+        img
+    })
+}
+
+/// Encodes a page with fixed settings, so the same layout, sprites and
+/// compression always produce the same bytes.
+fn write_png(
+    atlas: &ImageBuffer<Rgba<u8>, Vec<u8>>,
+    path: &Path,
+    compression: CompressionType,
+) -> Result<()> {
+    let file = std::fs::File::create(path)
+        .with_context(|| format!("failed to create atlas png: {}", path.display()))?;
+    PngEncoder::new_with_quality(
+        std::io::BufWriter::new(file),
+        compression,
+        FilterType::Adaptive,
+    )
+    .write_image(
+        atlas.as_raw(),
+        atlas.width(),
+        atlas.height(),
+        image::ExtendedColorType::Rgba8,
+    )
+    .with_context(|| format!("failed to write atlas png: {}", path.display()))
+}
+
+/// A sprite sheet packed by another tool (TexturePacker's JSON hash or array
+/// format), used as an atlas page as it is.
+#[derive(Debug, Clone)]
+pub struct PrepackedAtlas {
+    /// Key of the sheet image, which is neither packed nor uploaded alone.
+    pub sheet_key: String,
+    sheet_path: PathBuf,
+    /// Name of the page the sheet is copied to.
+    pub page_name: String,
+    /// Frames keyed by `<json dir>/<json stem>/<frame name>`.
+    pub placements: BTreeMap<String, SpritePlacement>,
+}
+
+#[derive(Deserialize)]
+struct SheetFile {
+    frames: SheetFrames,
+    meta: SheetMeta,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum SheetFrames {
+    Hash(BTreeMap<String, SheetFrame>),
+    Array(Vec<NamedSheetFrame>),
+}
+
+#[derive(Deserialize)]
+struct NamedSheetFrame {
+    filename: String,
+    #[serde(flatten)]
+    frame: SheetFrame,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SheetFrame {
+    frame: AtlasRect,
+    #[serde(default)]
+    rotated: bool,
+    #[serde(default)]
+    trimmed: bool,
+    sprite_source_size: Option<AtlasRect>,
+    source_size: Option<SheetSize>,
+}
+
+#[derive(Deserialize)]
+struct SheetSize {
+    w: u32,
+    h: u32,
+}
+
+#[derive(Deserialize)]
+struct SheetMeta {
+    image: String,
+}
+
+impl PrepackedAtlas {
+    /// Reads the sheet description at `json_key` (relative to
+    /// `images_folder`) and checks every frame against the sheet's size.
+    ///
+    /// Frame rects are taken as the sprite's upright size, as TexturePacker
+    /// writes them; rotated frames get the turned rect on the page.
+    pub fn read(images_folder: &Path, json_key: &str) -> Result<Self> {
+        let json_path = images_folder.join(json_key);
+        let content = std::fs::read_to_string(&json_path)
+            .with_context(|| format!("failed to read sprite sheet: {}", json_path.display()))?;
+        let sheet: SheetFile = serde_json::from_str(&content)
+            .with_context(|| format!("failed to parse sprite sheet: {}", json_path.display()))?;
+
+        let (dir, file) = json_key.rsplit_once('/').unwrap_or(("", json_key));
+        let stem = file.strip_suffix(".json").unwrap_or(file);
+        let prefix = if dir.is_empty() {
+            stem.to_string()
+        } else {
+            format!("{}/{}", dir, stem)
+        };
+        let sheet_key = if dir.is_empty() {
+            sheet.meta.image.clone()
+        } else {
+            format!("{}/{}", dir, sheet.meta.image)
+        };
+        let sheet_path = images_folder.join(&sheet_key);
+        let (sheet_w, sheet_h) = image_dimensions(&sheet_path)?;
+        let page_name = format!("prepacked_{}.png", prefix.replace('/', "_"));
+
+        let frames: Vec<(String, SheetFrame)> = match sheet.frames {
+            SheetFrames::Hash(frames) => frames.into_iter().collect(),
+            SheetFrames::Array(frames) => frames
+                .into_iter()
+                .map(|named| (named.filename, named.frame))
+                .collect(),
+        };
+        let mut placements = BTreeMap::new();
+        for (name, frame) in frames {
+            let AtlasRect { x, y, w, h } = frame.frame;
+            let rect = if frame.rotated {
+                AtlasRect { x, y, w: h, h: w }
+            } else {
+                frame.frame
+            };
+            if rect.x + rect.w > sheet_w || rect.y + rect.h > sheet_h {
+                anyhow::bail!(
+                    "frame {} in {} lies outside the {}x{} sheet",
+                    name,
+                    json_path.display(),
+                    sheet_w,
+                    sheet_h
+                );
+            }
+            let trim = match (frame.trimmed, frame.sprite_source_size, frame.source_size) {
+                (true, Some(offset), Some(source)) => Some(SpriteTrim {
+                    x: offset.x,
+                    y: offset.y,
+                    source_w: source.w,
+                    source_h: source.h,
+                }),
+                _ => None,
+            };
+            placements.insert(
+                format!("{}/{}", prefix, name),
+                SpritePlacement {
+                    atlas_file_name: page_name.clone(),
+                    rect,
+                    rotated: frame.rotated,
+                    trim,
+                },
+            );
+        }
+
+        Ok(Self {
+            sheet_key,
+            sheet_path,
+            page_name,
+            placements,
+        })
+    }
+
+    /// Copies the sheet into `output_dir` as an atlas page.
+    pub fn write_page(&self, output_dir: &Path) -> Result<()> {
+        let page = output_dir.join(&self.page_name);
+        std::fs::copy(&self.sheet_path, &page).with_context(|| {
+            format!(
+                "failed to copy sprite sheet {} to {}",
+                self.sheet_path.display(),
+                page.display()
+            )
+        })?;
+        Ok(())
+    }
+}
+
+/// Packing statistics of one atlas page.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PageReport {
+    pub file_name: String,
+    pub width: u32,
+    pub height: u32,
+    /// Packed sprites; duplicates sharing a rect count once.
+    pub sprites: usize,
+    /// Share of the page covered by sprite pixels, padding excluded (0-100).
+    pub fill_percent: f64,
+    /// The largest rectangle left empty on the page, padding included.
+    pub largest_free: AtlasRect,
+}
+
+/// Reports fill and free space for each page in `output_dir`, so page size
+/// and padding can be tuned against real sprites.
+pub fn atlas_report(
+    output_dir: &Path,
+    placements: &BTreeMap<String, SpritePlacement>,
+    padding: u32,
+) -> Result<Vec<PageReport>> {
+    let mut pages: BTreeMap<&str, BTreeSet<(u32, u32, u32, u32)>> = BTreeMap::new();
+    for placement in placements.values() {
+        let AtlasRect { x, y, w, h } = placement.rect;
+        pages
+            .entry(placement.atlas_file_name.as_str())
+            .or_default()
+            .insert((x, y, w, h));
+    }
+
+    let mut reports = Vec::with_capacity(pages.len());
+    for (file_name, rects) in pages {
+        let path = output_dir.join(file_name);
+        let (width, height) = image_dimensions(&path)?;
+        let mut free = MaxRectsPage::new(width, height);
+        let mut used: u64 = 0;
+        for &(x, y, w, h) in &rects {
+            used += w as u64 * h as u64;
+            let (left, top) = (x.saturating_sub(padding), y.saturating_sub(padding));
+            free.place(AtlasRect {
+                x: left,
+                y: top,
+                w: (x + w + padding).min(width) - left,
+                h: (y + h + padding).min(height) - top,
+            });
+        }
+        let largest_free = free
+            .free
+            .iter()
+            .copied()
+            .max_by_key(|rect| {
+                (
+                    rect.w as u64 * rect.h as u64,
+                    std::cmp::Reverse((rect.y, rect.x)),
+                )
+            })
+            .unwrap_or(AtlasRect {
+                x: 0,
+                y: 0,
+                w: 0,
+                h: 0,
+            });
+        reports.push(PageReport {
+            file_name: file_name.to_string(),
+            width,
+            height,
+            sprites: rects.len(),
+            fill_percent: used as f64 * 100.0 / (width as u64 * height as u64) as f64,
+            largest_free,
+        });
+    }
+    Ok(reports)
+}
+
+/// Names of the atlas pages that differ between a fresh build in `fresh` and
+/// the pages in `on_disk`, including pages only one of them has.
+pub fn stale_atlas_pages(fresh: &Path, on_disk: &Path) -> Result<Vec<String>> {
+    fn pages(dir: &Path) -> Result<BTreeMap<String, PathBuf>> {
+        let mut pages = BTreeMap::new();
+        if !dir.is_dir() {
+            return Ok(pages);
+        }
+        for entry in std::fs::read_dir(dir)
+            .with_context(|| format!("failed to read atlas dir: {}", dir.display()))?
+        {
+            let path = entry?.path();
+            if path.is_file() && path.extension().and_then(|s| s.to_str()) == Some("png") {
+                pages.insert(entry_name(&path), path);
+            }
+        }
+        Ok(pages)
+    }
+    fn entry_name(path: &Path) -> String {
+        path.file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default()
+    }
+
+    let fresh = pages(fresh)?;
+    let on_disk = pages(on_disk)?;
+    let mut stale = Vec::new();
+    for name in fresh.keys().chain(on_disk.keys()).collect::<BTreeSet<_>>() {
+        let same = match (fresh.get(name), on_disk.get(name)) {
+            (Some(a), Some(b)) => std::fs::read(a)? == std::fs::read(b)?,
+            _ => false,
+        };
+        if !same {
+            stale.push(name.clone());
+        }
+    }
+    Ok(stale)
+}
+
+/// Copies `src` to `inner_x`,`inner_y` and repeats its edge rows and columns
+/// `padding` pixels outward, so bilinear filtering at the sprite's border
+/// samples its own colors instead of transparency or a neighbour.
 fn blit_with_extrude(
     dst: &mut ImageBuffer<Rgba<u8>, Vec<u8>>,
     src: &ImageBuffer<Rgba<u8>, Vec<u8>>,
     inner_x: u32,
     inner_y: u32,
-    _padding: u32,
+    padding: u32,
 ) {
-    let w = src.width();
-    let h = src.height();
-
-    for dy in 0..h {
-        for dx in 0..w {
-            let p = src.get_pixel(dx, dy);
-            let tx = inner_x + dx;
-            let ty = inner_y + dy;
-            if tx < dst.width() && ty < dst.height() {
-                dst.put_pixel(tx, ty, *p);
-            }
+    let (w, h) = src.dimensions();
+    if w == 0 || h == 0 {
+        return;
+    }
+
+    let x_end = inner_x
+        .saturating_add(w)
+        .saturating_add(padding)
+        .min(dst.width());
+    let y_end = inner_y
+        .saturating_add(h)
+        .saturating_add(padding)
+        .min(dst.height());
+    for ty in inner_y.saturating_sub(padding)..y_end {
+        let sy = ty.clamp(inner_y, inner_y + h - 1) - inner_y;
+        for tx in inner_x.saturating_sub(padding)..x_end {
+            let sx = tx.clamp(inner_x, inner_x + w - 1) - inner_x;
+            dst.put_pixel(tx, ty, *src.get_pixel(sx, sy));
         }
     }
 }
@@ -371,3 +1294,396 @@ fn insert_meta(root: &mut BTreeMap<String, AssetValue>, path: &[String], meta: A
 
     insert_meta(map, &path[1..], meta);
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sprites(sizes: &[(u32, u32)]) -> Vec<PendingSprite> {
+        sizes
+            .iter()
+            .enumerate()
+            .map(|(i, &(w, h))| PendingSprite {
+                key: format!("sprite_{:02}.png", i),
+                src_path: PathBuf::new(),
+                w,
+                h,
+                trim: None,
+            })
+            .collect()
+    }
+
+    fn page_count(placed: &[PlacedSprite]) -> usize {
+        placed.iter().map(|s| s.atlas_index + 1).max().unwrap_or(0)
+    }
+
+    #[test]
+    fn max_rects_fits_mixed_sizes_into_fewer_pages() {
+        // A tall sprite next to many small ones: shelves waste the space
+        // beside it, MaxRects fills it.
+        let mut sizes = vec![(64, 256), (192, 64)];
+        sizes.extend(std::iter::repeat_n((32, 32), 30));
+        let sprites = sprites(&sizes);
+
+        let shelf = pack_sprites(&sprites, 0, (256, 256), PackingAlgorithm::Shelf, false).unwrap();
+        let max_rects =
+            pack_sprites(&sprites, 0, (256, 256), PackingAlgorithm::MaxRects, false).unwrap();
+        assert_eq!(max_rects.len(), sprites.len());
+        assert_eq!(page_count(&max_rects), 1);
+        assert!(page_count(&shelf) > 1);
+
+        for (i, a) in max_rects.iter().enumerate() {
+            assert!(a.rect.x + a.rect.w <= 256 && a.rect.y + a.rect.h <= 256);
+            for b in &max_rects[i + 1..] {
+                assert!(a.atlas_index != b.atlas_index || !overlaps(&a.rect, &b.rect));
+            }
+        }
+    }
+
+    #[test]
+    fn max_rects_keeps_padding_between_sprites() {
+        let sprites = sprites(&[(10, 10), (10, 10)]);
+        let placed =
+            pack_sprites(&sprites, 2, (256, 256), PackingAlgorithm::MaxRects, false).unwrap();
+        let grow = |r: &AtlasRect| AtlasRect {
+            x: r.x - 2,
+            y: r.y - 2,
+            w: r.w + 4,
+            h: r.h + 4,
+        };
+        assert!(!overlaps(&grow(&placed[0].rect), &grow(&placed[1].rect)));
+        assert!(
+            pack_sprites(&sprites, 200, (256, 256), PackingAlgorithm::MaxRects, false).is_err()
+        );
+    }
+
+    #[test]
+    fn rotation_lays_tall_sprites_flat() {
+        // Two tall sprites only share a 256px page when one is turned.
+        let sprites = sprites(&[(200, 60), (60, 200)]);
+        for algorithm in [PackingAlgorithm::Shelf, PackingAlgorithm::MaxRects] {
+            let upright = pack_sprites(&sprites, 0, (256, 256), algorithm, false).unwrap();
+            assert!(upright.iter().all(|s| !s.rotated));
+
+            let placed = pack_sprites(&sprites, 0, (256, 256), algorithm, true).unwrap();
+            assert_eq!(page_count(&placed), 1);
+            let turned = placed.iter().find(|s| s.key == "sprite_01.png").unwrap();
+            assert!(turned.rotated);
+            assert_eq!((turned.rect.w, turned.rect.h), (200, 60));
+            assert!(!overlaps(&placed[0].rect, &placed[1].rect));
+        }
+    }
+
+    #[test]
+    fn extrudes_edge_pixels_into_padding() {
+        let mut src = ImageBuffer::new(2, 2);
+        src.put_pixel(0, 0, Rgba([255, 0, 0, 255]));
+        src.put_pixel(1, 0, Rgba([0, 255, 0, 255]));
+        src.put_pixel(0, 1, Rgba([0, 0, 255, 255]));
+        src.put_pixel(1, 1, Rgba([255, 255, 255, 255]));
+        let mut dst = ImageBuffer::from_pixel(8, 8, Rgba([0, 0, 0, 0]));
+        blit_with_extrude(&mut dst, &src, 2, 2, 2);
+
+        assert_eq!(dst.get_pixel(0, 0), src.get_pixel(0, 0));
+        assert_eq!(dst.get_pixel(5, 0), src.get_pixel(1, 0));
+        assert_eq!(dst.get_pixel(1, 3), src.get_pixel(0, 1));
+        assert_eq!(dst.get_pixel(5, 5), src.get_pixel(1, 1));
+        assert_eq!(dst.get_pixel(3, 2), src.get_pixel(1, 0));
+        assert_eq!(dst.get_pixel(6, 6), &Rgba([0, 0, 0, 0]));
+    }
+
+    #[test]
+    fn sprites_outside_size_limits_are_excluded() {
+        assert!(!outside_size(2048, 2048, None, None));
+        assert!(outside_size(2048, 64, None, Some(1024)));
+        assert!(!outside_size(1024, 1024, None, Some(1024)));
+        assert!(outside_size(1, 1, Some(4), None));
+        assert!(!outside_size(1, 64, Some(4), None));
+    }
+
+    #[test]
+    fn finds_stale_atlas_pages() {
+        let dir = std::env::temp_dir().join(format!("truffle-stale-pages-{}", std::process::id()));
+        let (fresh, on_disk) = (dir.join("fresh"), dir.join("disk"));
+        std::fs::create_dir_all(&fresh).unwrap();
+        std::fs::create_dir_all(&on_disk).unwrap();
+        std::fs::write(fresh.join("atlas_000.png"), b"same").unwrap();
+        std::fs::write(on_disk.join("atlas_000.png"), b"same").unwrap();
+        std::fs::write(fresh.join("atlas_001.png"), b"new").unwrap();
+        std::fs::write(on_disk.join("atlas_001.png"), b"old").unwrap();
+        std::fs::write(on_disk.join("atlas_002.png"), b"gone").unwrap();
+
+        let stale = stale_atlas_pages(&fresh, &on_disk).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(stale, ["atlas_001.png", "atlas_002.png"]);
+    }
+
+    #[test]
+    fn identical_sprites_are_packed_once() {
+        let dir = std::env::temp_dir().join(format!("truffle-dedupe-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let write = |name: &str, color: u8| {
+            let path = dir.join(name);
+            ImageBuffer::from_pixel(4, 4, Rgba([color, 0, 0, 255]))
+                .save(&path)
+                .unwrap();
+            PendingSprite {
+                key: name.to_string(),
+                src_path: path,
+                w: 4,
+                h: 4,
+                trim: None,
+            }
+        };
+        let sprites = vec![write("a.png", 1), write("b.png", 2), write("c.png", 1)];
+
+        let deduped = dedupe_sprites(sprites).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+        let keys: Vec<&str> = deduped.sprites.iter().map(|s| s.key.as_str()).collect();
+        assert_eq!(keys, ["a.png", "b.png"]);
+        assert_eq!(
+            deduped.duplicates,
+            [("c.png".to_string(), "a.png".to_string())]
+        );
+    }
+
+    #[test]
+    fn trims_transparent_margins() {
+        let mut img = ImageBuffer::from_pixel(10, 8, Rgba([0, 0, 0, 0]));
+        img.put_pixel(2, 3, Rgba([255, 0, 0, 255]));
+        img.put_pixel(6, 4, Rgba([255, 0, 0, 1]));
+        assert_eq!(
+            opaque_bounds(&img),
+            Some(AtlasRect {
+                x: 2,
+                y: 3,
+                w: 5,
+                h: 2
+            })
+        );
+
+        let path = std::env::temp_dir().join(format!("truffle-trim-{}.png", std::process::id()));
+        img.save(&path).unwrap();
+        let trimmed = trimmed_size(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(
+            trimmed,
+            (
+                5,
+                2,
+                Some(SpriteTrim {
+                    x: 2,
+                    y: 3,
+                    source_w: 10,
+                    source_h: 8
+                })
+            )
+        );
+        assert_eq!(
+            opaque_bounds(&ImageBuffer::from_pixel(2, 2, Rgba([0, 0, 0, 0]))),
+            None
+        );
+    }
+
+    #[test]
+    fn best_compression_writes_smaller_identical_pages() {
+        let img = ImageBuffer::from_fn(128, 128, |x, y| {
+            Rgba([(x * 2) as u8, (y * 2) as u8, ((x ^ y) & 0xf0) as u8, 255])
+        });
+        let dir = std::env::temp_dir().join(format!("truffle-compression-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let fast = dir.join("fast.png");
+        let best = dir.join("best.png");
+        write_png(&img, &fast, CompressionType::Fast).unwrap();
+        write_png(&img, &best, CompressionType::Best).unwrap();
+
+        let size = |path: &Path| std::fs::metadata(path).unwrap().len();
+        assert!(size(&best) < size(&fast));
+        assert_eq!(image::open(&best).unwrap().to_rgba8(), img);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn packs_rectangular_pages() {
+        let sprites = sprites(&[(200, 100), (200, 100), (200, 100)]);
+        for algorithm in [PackingAlgorithm::Shelf, PackingAlgorithm::MaxRects] {
+            let placed = pack_sprites(&sprites, 0, (256, 512), algorithm, false).unwrap();
+            assert_eq!(page_count(&placed), 1);
+            assert!(placed.iter().all(|s| s.rect.y + s.rect.h <= 512));
+        }
+
+        // Only fits the page lying on its side.
+        let tall = self::sprites(&[(100, 300)]);
+        assert!(pack_sprites(&tall, 0, (512, 256), PackingAlgorithm::Shelf, false).is_err());
+        let placed = pack_sprites(&tall, 0, (512, 256), PackingAlgorithm::Shelf, true).unwrap();
+        assert!(placed[0].rotated);
+    }
+
+    #[test]
+    fn shrinks_pages_to_their_content() {
+        let placed = pack_sprites(
+            &sprites(&[(100, 40), (60, 30)]),
+            2,
+            (1024, 512),
+            PackingAlgorithm::Shelf,
+            false,
+        )
+        .unwrap();
+        let page: Vec<&PlacedSprite> = placed.iter().collect();
+        assert_eq!(shrunk_page_size(&page, 2, (1024, 512)), (256, 64));
+        assert_eq!(shrunk_page_size(&page, 2, (128, 512)), (128, 64));
+    }
+
+    #[test]
+    fn reports_fill_and_largest_free_area() {
+        let dir = std::env::temp_dir().join(format!("truffle-atlas-report-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        ImageBuffer::from_pixel(64, 64, Rgba([0u8, 0, 0, 0]))
+            .save(dir.join("atlas_000.png"))
+            .unwrap();
+        let placement = |x| SpritePlacement {
+            atlas_file_name: "atlas_000.png".into(),
+            rect: AtlasRect {
+                x,
+                y: 2,
+                w: 16,
+                h: 16,
+            },
+            rotated: false,
+            trim: None,
+        };
+        let placements = BTreeMap::from([
+            ("a.png".to_string(), placement(2)),
+            ("b.png".to_string(), placement(22)),
+            ("copy_of_a.png".to_string(), placement(2)),
+        ]);
+
+        let report = atlas_report(&dir, &placements, 2).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(
+            report,
+            vec![PageReport {
+                file_name: "atlas_000.png".into(),
+                width: 64,
+                height: 64,
+                sprites: 2,
+                fill_percent: 12.5,
+                largest_free: AtlasRect {
+                    x: 0,
+                    y: 20,
+                    w: 64,
+                    h: 44
+                },
+            }]
+        );
+    }
+
+    #[test]
+    fn reads_prepacked_sheets() {
+        let dir = std::env::temp_dir().join(format!("truffle-prepacked-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("ui")).unwrap();
+        ImageBuffer::from_pixel(64, 32, Rgba([0u8, 0, 0, 0]))
+            .save(dir.join("ui/icons.png"))
+            .unwrap();
+        std::fs::write(
+            dir.join("ui/icons.json"),
+            r#"{
+                "frames": {
+                    "play.png": { "frame": { "x": 0, "y": 0, "w": 20, "h": 10 }, "rotated": true },
+                    "stop.png": {
+                        "frame": { "x": 20, "y": 0, "w": 8, "h": 8 },
+                        "trimmed": true,
+                        "spriteSourceSize": { "x": 2, "y": 1, "w": 8, "h": 8 },
+                        "sourceSize": { "w": 12, "h": 12 }
+                    }
+                },
+                "meta": { "image": "icons.png" }
+            }"#,
+        )
+        .unwrap();
+        std::fs::write(
+            dir.join("ui/broken.json"),
+            r#"{ "frames": [{ "filename": "a", "frame": { "x": 60, "y": 0, "w": 8, "h": 8 } }], "meta": { "image": "icons.png" } }"#,
+        )
+        .unwrap();
+
+        let sheet = PrepackedAtlas::read(&dir, "ui/icons.json").unwrap();
+        let broken = PrepackedAtlas::read(&dir, "ui/broken.json");
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(sheet.sheet_key, "ui/icons.png");
+        assert_eq!(sheet.page_name, "prepacked_ui_icons.png");
+        let play = &sheet.placements["ui/icons/play.png"];
+        assert!(play.rotated);
+        assert_eq!(
+            play.rect,
+            AtlasRect {
+                x: 0,
+                y: 0,
+                w: 10,
+                h: 20
+            }
+        );
+        assert_eq!(play.sprite_size(), (20, 10));
+        let stop = &sheet.placements["ui/icons/stop.png"];
+        assert_eq!(stop.source_size(), (12, 12));
+        assert_eq!(stop.trim.map(|trim| (trim.x, trim.y)), Some((2, 1)));
+        assert!(broken.is_err());
+    }
+
+    #[test]
+    fn low_memory_pages_match_parallel_pages() {
+        let root = std::env::temp_dir().join(format!("truffle-low-memory-{}", std::process::id()));
+        let images = root.join("images");
+        std::fs::create_dir_all(&images).unwrap();
+        for (i, (w, h)) in [(200, 120), (90, 200), (150, 150), (60, 30)]
+            .into_iter()
+            .enumerate()
+        {
+            ImageBuffer::from_fn(w, h, |x, y| Rgba([i as u8 * 60, x as u8, y as u8, 255]))
+                .save(images.join(format!("sprite_{}.png", i)))
+                .unwrap();
+        }
+
+        let build = |name: &str, low_memory: bool| {
+            let out = root.join(name);
+            let options = AtlasOptions {
+                size: 256,
+                low_memory,
+                ..Default::default()
+            };
+            build_atlases(&images, &out, options).unwrap();
+            out
+        };
+        let parallel = build("parallel", false);
+        let low_memory = build("low-memory", true);
+        let stale = stale_atlas_pages(&parallel, &low_memory).unwrap();
+        let pages = std::fs::read_dir(&parallel).unwrap().count();
+        std::fs::remove_dir_all(&root).unwrap();
+        assert!(pages > 1);
+        assert!(stale.is_empty(), "{:?}", stale);
+    }
+
+    #[test]
+    fn packs_jpeg_and_bmp_sources() {
+        let root = std::env::temp_dir().join(format!("truffle-raster-{}", std::process::id()));
+        let images = root.join("images");
+        std::fs::create_dir_all(&images).unwrap();
+        let photo = image::RgbImage::from_pixel(40, 30, image::Rgb([200, 100, 50]));
+        photo.save(images.join("photo.jpg")).unwrap();
+        photo.save(images.join("tile.bmp")).unwrap();
+        std::fs::write(images.join("notes.txt"), "not an image").unwrap();
+
+        let placements = build_atlases(&images, &root.join("out"), AtlasOptions::default());
+        std::fs::remove_dir_all(&root).unwrap();
+        let placements = placements.unwrap();
+
+        assert_eq!(
+            placements.keys().collect::<Vec<_>>(),
+            ["photo.jpg", "tile.bmp"]
+        );
+        for placement in placements.values() {
+            assert_eq!((placement.rect.w, placement.rect.h), (40, 30));
+        }
+    }
+}
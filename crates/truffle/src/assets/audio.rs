@@ -0,0 +1,235 @@
+use std::path::Path;
+
+/// The length of an audio file in seconds, rounded to milliseconds. Only the
+/// headers are parsed: Ogg (Vorbis and Opus), MP3 (Xing/VBRI or constant
+/// bitrate), WAV and FLAC.
+pub fn audio_duration(path: &Path) -> Option<f64> {
+    let data = std::fs::read(path).ok()?;
+    let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+    let seconds = match extension.as_str() {
+        "ogg" => ogg_duration(&data),
+        "mp3" => mp3_duration(&data),
+        "wav" => wav_duration(&data),
+        "flac" => flac_duration(&data),
+        _ => None,
+    }?;
+    Some((seconds * 1000.0).round() / 1000.0)
+}
+
+fn u16_le(data: &[u8], at: usize) -> Option<u16> {
+    Some(u16::from_le_bytes(data.get(at..at + 2)?.try_into().ok()?))
+}
+
+fn u32_le(data: &[u8], at: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(data.get(at..at + 4)?.try_into().ok()?))
+}
+
+fn u32_be(data: &[u8], at: usize) -> Option<u32> {
+    Some(u32::from_be_bytes(data.get(at..at + 4)?.try_into().ok()?))
+}
+
+/// The granule position of the last page over the stream's sample rate.
+/// Opus always counts 48 kHz samples and starts after `pre_skip` of them.
+fn ogg_duration(data: &[u8]) -> Option<f64> {
+    if !data.starts_with(b"OggS") {
+        return None;
+    }
+    let segments = *data.get(26)? as usize;
+    let packet = data.get(27 + segments..)?;
+    let (rate, pre_skip) = if packet.starts_with(b"\x01vorbis") {
+        (u32_le(packet, 12)?, 0)
+    } else if packet.starts_with(b"OpusHead") {
+        (48_000, u16_le(packet, 10)?)
+    } else {
+        return None;
+    };
+    if rate == 0 {
+        return None;
+    }
+
+    let last_page = data.windows(4).rposition(|window| window == b"OggS")?;
+    let granule = i64::from_le_bytes(data.get(last_page + 6..last_page + 14)?.try_into().ok()?);
+    if granule < 0 {
+        return None;
+    }
+    Some((granule - i64::from(pre_skip)).max(0) as f64 / f64::from(rate))
+}
+
+/// MPEG audio layer III: the frame count of a Xing/Info or VBRI header, or
+/// the stream size over the bitrate of the first frame.
+fn mp3_duration(data: &[u8]) -> Option<f64> {
+    let mut start = 0;
+    if data.starts_with(b"ID3") {
+        let size = data
+            .get(6..10)?
+            .iter()
+            .fold(0usize, |size, byte| (size << 7) | (*byte & 0x7f) as usize);
+        let footer = if data.get(5)? & 0x10 != 0 { 10 } else { 0 };
+        start = 10 + size + footer;
+    }
+    let frame = start
+        + data
+            .get(start..)?
+            .windows(2)
+            .position(|w| w[0] == 0xff && w[1] & 0xe0 == 0xe0)?;
+
+    let header = u32_be(data, frame)?;
+    let version = (header >> 19) & 3;
+    let layer = (header >> 17) & 3;
+    if layer != 1 || version == 1 {
+        return None;
+    }
+    let mpeg1 = version == 3;
+    let rates: [u32; 3] = match version {
+        3 => [44_100, 48_000, 32_000],
+        2 => [22_050, 24_000, 16_000],
+        _ => [11_025, 12_000, 8_000],
+    };
+    let rate = *rates.get(((header >> 10) & 3) as usize)?;
+    let samples_per_frame = if mpeg1 { 1152.0 } else { 576.0 };
+    let mono = (header >> 6) & 3 == 3;
+
+    let side_info = match (mpeg1, mono) {
+        (true, true) => 17,
+        (true, false) => 32,
+        (false, true) => 9,
+        (false, false) => 17,
+    };
+    let xing = frame + 4 + side_info;
+    if matches!(data.get(xing..xing + 4), Some(b"Xing" | b"Info")) {
+        let flags = u32_be(data, xing + 4)?;
+        if flags & 1 != 0 {
+            let frames = u32_be(data, xing + 8)?;
+            return Some(f64::from(frames) * samples_per_frame / f64::from(rate));
+        }
+    }
+    let vbri = frame + 4 + 32;
+    if data.get(vbri..vbri + 4) == Some(b"VBRI") {
+        let frames = u32_be(data, vbri + 14)?;
+        return Some(f64::from(frames) * samples_per_frame / f64::from(rate));
+    }
+
+    const MPEG1_KBPS: [u32; 15] = [
+        0, 32, 40, 48, 56, 64, 80, 96, 112, 128, 160, 192, 224, 256, 320,
+    ];
+    const MPEG2_KBPS: [u32; 15] = [0, 8, 16, 24, 32, 40, 48, 56, 64, 80, 96, 112, 128, 144, 160];
+    let table = if mpeg1 { MPEG1_KBPS } else { MPEG2_KBPS };
+    let kbps = *table.get(((header >> 12) & 0xf) as usize)?;
+    if kbps == 0 {
+        return None;
+    }
+    let mut end = data.len();
+    if end >= 128 && data.get(end - 128..end - 125) == Some(b"TAG") {
+        end -= 128;
+    }
+    Some(end.saturating_sub(frame) as f64 * 8.0 / (f64::from(kbps) * 1000.0))
+}
+
+/// The `data` chunk size over the byte rate of the `fmt ` chunk.
+fn wav_duration(data: &[u8]) -> Option<f64> {
+    if !data.starts_with(b"RIFF") || data.get(8..12)? != b"WAVE" {
+        return None;
+    }
+    let mut at = 12;
+    let mut byte_rate = None;
+    while at + 8 <= data.len() {
+        let id = &data[at..at + 4];
+        let size = u32_le(data, at + 4)? as usize;
+        match id {
+            b"fmt " => byte_rate = u32_le(data, at + 16),
+            b"data" => {
+                let byte_rate = byte_rate.filter(|rate| *rate > 0)?;
+                return Some(size as f64 / f64::from(byte_rate));
+            }
+            _ => {}
+        }
+        at += 8 + size + size % 2;
+    }
+    None
+}
+
+/// Total samples over the sample rate, both from STREAMINFO.
+fn flac_duration(data: &[u8]) -> Option<f64> {
+    if !data.starts_with(b"fLaC") {
+        return None;
+    }
+    // STREAMINFO is always the first metadata block; the rate and sample
+    // count sit 10 bytes into it.
+    let packed = u64::from_be_bytes(data.get(18..26)?.try_into().ok()?);
+    let rate = (packed >> 44) as u32;
+    let samples = packed & ((1 << 36) - 1);
+    if rate == 0 || samples == 0 {
+        return None;
+    }
+    Some(samples as f64 / f64::from(rate))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ogg_page(granule: i64, packet: &[u8]) -> Vec<u8> {
+        let mut page = b"OggS\0\0".to_vec();
+        page.extend(granule.to_le_bytes());
+        page.extend([0; 12]);
+        page.push(1);
+        page.push(packet.len() as u8);
+        page.extend(packet);
+        page
+    }
+
+    #[test]
+    fn reads_durations_from_headers() {
+        // Ogg Vorbis: 88200 samples at 44.1 kHz
+        let mut vorbis = b"\x01vorbis".to_vec();
+        vorbis.extend([0, 0, 0, 0, 2]);
+        vorbis.extend(44_100u32.to_le_bytes());
+        let mut ogg = ogg_page(0, &vorbis);
+        ogg.extend(ogg_page(88_200, b"audio"));
+        assert_eq!(ogg_duration(&ogg), Some(2.0));
+
+        // Ogg Opus: 48 kHz granules after a 312 sample pre-skip
+        let mut opus = b"OpusHead\x01\x02".to_vec();
+        opus.extend(312u16.to_le_bytes());
+        let mut ogg = ogg_page(0, &opus);
+        ogg.extend(ogg_page(72_312, b"audio"));
+        assert_eq!(ogg_duration(&ogg), Some(1.5));
+
+        // MP3: 128 kbps MPEG-1 layer III, 32000 bytes of frames
+        let mut mp3 = b"ID3\x04\0\0\0\0\0\x02".to_vec();
+        mp3.extend([0; 2]);
+        let mut frames = vec![0; 32_000];
+        frames[..4].copy_from_slice(&[0xff, 0xfb, 0x90, 0x00]);
+        mp3.extend(frames);
+        assert_eq!(mp3_duration(&mp3), Some(2.0));
+
+        // MP3 with a Xing header: 100 frames of 1152 samples at 48 kHz
+        let mut xing = vec![0; 600];
+        xing[..4].copy_from_slice(&[0xff, 0xfb, 0x94, 0x00]);
+        xing[36..40].copy_from_slice(b"Xing");
+        xing[40..44].copy_from_slice(&1u32.to_be_bytes());
+        xing[44..48].copy_from_slice(&100u32.to_be_bytes());
+        assert_eq!(mp3_duration(&xing), Some(2.4));
+
+        // WAV: 16-bit stereo at 8 kHz, 16000 bytes of samples
+        let mut wav = b"RIFF\0\0\0\0WAVEfmt ".to_vec();
+        wav.extend(16u32.to_le_bytes());
+        wav.extend([1, 0, 2, 0]);
+        wav.extend(8_000u32.to_le_bytes());
+        wav.extend(32_000u32.to_le_bytes());
+        wav.extend([4, 0, 16, 0]);
+        wav.extend(b"data");
+        wav.extend(16_000u32.to_le_bytes());
+        assert_eq!(wav_duration(&wav), Some(0.5));
+
+        // FLAC: 22050 samples at 44.1 kHz
+        let mut flac = b"fLaC\x00\x00\x00\x22".to_vec();
+        flac.extend([0; 10]);
+        let packed: u64 = (44_100 << 44) | 22_050;
+        flac.extend(packed.to_be_bytes());
+        assert_eq!(flac_duration(&flac), Some(0.5));
+
+        assert_eq!(ogg_duration(b"not audio"), None);
+        assert_eq!(wav_duration(b"RIFF\0\0\0\0WAVE"), None);
+    }
+}
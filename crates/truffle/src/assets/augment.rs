@@ -1,6 +1,14 @@
+use super::audio::audio_duration;
+use super::augmenter::{enabled_augmenters, run_augmenters, AssetContext, Augmenter};
+use super::kind::{asset_kind, AUDIO_KIND};
 use super::model::{AssetMeta, AssetValue};
-use std::collections::BTreeMap;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
 use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::SystemTime;
+use truffle_config::TruffleOptions;
 
 pub trait ImageMetadataReader: Send + Sync {
     fn dimensions(&self, path: &Path) -> Option<(u32, u32)>;
@@ -10,161 +18,347 @@ pub struct FsImageMetadata;
 
 impl ImageMetadataReader for FsImageMetadata {
     fn dimensions(&self, path: &Path) -> Option<(u32, u32)> {
-        let decoder = png::Decoder::new(std::fs::File::open(path).ok()?);
-        let reader = decoder.read_info().ok()?;
-        let info = reader.info();
-        Some((info.width, info.height))
+        if path.extension().and_then(|s| s.to_str()) == Some("svg") {
+            return crate::image::svg::svg_size(path);
+        }
+
+        if path.extension().and_then(|s| s.to_str()) == Some("png") {
+            let decoder = png::Decoder::new(std::fs::File::open(path).ok()?);
+            let reader = decoder.read_info().ok()?;
+            let info = reader.info();
+            return Some((info.width, info.height));
+        }
+
+        // JPEG, WebP and BMP headers are read by the `image` crate.
+        if crate::image::is_raster_image(path) {
+            return image::image_dimensions(path).ok();
+        }
+        None
     }
 }
 
-pub fn augment_assets(
-    assets: &BTreeMap<String, AssetValue>,
-    images_folder: &Path,
-    reader: &dyn ImageMetadataReader,
-) -> BTreeMap<String, AssetValue> {
-    let mut augmented = BTreeMap::new();
-    for (category, node) in assets {
-        augmented.insert(
-            category.clone(),
-            augment_node(
-                node.clone(),
-                assets,
-                std::slice::from_ref(category),
-                images_folder,
-                reader,
-            ),
-        );
-    }
-    augmented
+/// Remembers image sizes across syncs, re-reading a file only when its
+/// modification time or length changes. The sizes can be kept in a file
+/// between runs with [`load`](Self::load) and [`save`](Self::save).
+#[derive(Default)]
+pub struct CachedImageMetadata {
+    entries: Mutex<HashMap<PathBuf, CachedDimensions>>,
 }
 
-fn augment_node(
-    node: AssetValue,
-    assets: &BTreeMap<String, AssetValue>,
-    path_segments: &[String],
-    images_folder: &Path,
-    reader: &dyn ImageMetadataReader,
-) -> AssetValue {
-    let id_str = match &node {
-        AssetValue::String(s) => Some(s.clone()),
-        AssetValue::Number(n) => Some(n.to_string()),
-        _ => None,
-    };
+#[derive(Serialize, Deserialize)]
+struct CachedDimensions {
+    modified: Option<SystemTime>,
+    len: u64,
+    dimensions: Option<(u32, u32)>,
+}
 
-    match node {
-        AssetValue::String(_) | AssetValue::Number(_) => {
-            let id_str = id_str.unwrap();
-            let image_path = build_image_path(images_folder, path_segments);
-            let (width, height) = reader.dimensions(&image_path).unwrap_or((0, 0));
-
-            if width == 0 && height == 0 {
-                println!(
-                    "[sync] WARN: {} is not a PNG or is unreadable – skipping size metadata.",
-                    image_path.display()
-                );
-            }
+impl CachedImageMetadata {
+    /// Sizes saved by an earlier run, or an empty cache when `path` is
+    /// missing or unreadable.
+    pub fn load(path: &Path) -> Self {
+        let entries = std::fs::read(path)
+            .ok()
+            .and_then(|data| serde_json::from_slice(&data).ok())
+            .unwrap_or_default();
+        Self {
+            entries: Mutex::new(entries),
+        }
+    }
 
-            let mut meta = AssetMeta {
-                id: id_str,
-                width: Some(width),
-                height: Some(height),
-                rect_x: None,
-                rect_y: None,
-                rect_w: None,
-                rect_h: None,
-                highlight_id: None,
-                highlight_rect_x: None,
-                highlight_rect_y: None,
-                highlight_rect_w: None,
-                highlight_rect_h: None,
-            };
+    /// Writes the cached sizes to `path`, dropping images that no longer
+    /// exist.
+    pub fn save(&self, path: &Path) -> anyhow::Result<()> {
+        let mut entries = self
+            .entries
+            .lock()
+            .map_err(|_| anyhow::anyhow!("Image size cache is poisoned"))?;
+        entries.retain(|path, _| path.exists());
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, serde_json::to_vec(&*entries)?)?;
+        Ok(())
+    }
 
-            if let Some(highlight_id) = get_highlight_asset_id(assets, path_segments) {
-                meta.highlight_id = Some(highlight_id);
-            }
+    /// Number of images whose size is cached.
+    pub fn cached_images(&self) -> usize {
+        self.entries
+            .lock()
+            .map(|entries| entries.len())
+            .unwrap_or(0)
+    }
+}
 
-            AssetValue::Object(meta)
-        }
-        AssetValue::Object(mut meta) => {
-            let image_path = build_image_path(images_folder, path_segments);
-            let (width, height) = reader
-                .dimensions(&image_path)
-                .unwrap_or((meta.width.unwrap_or(0), meta.height.unwrap_or(0)));
-
-            if width == 0 && height == 0 && meta.width.is_none() {
-                println!(
-                    "[sync] WARN: {} is not a PNG or is unreadable – skipping size metadata.",
-                    image_path.display()
-                );
+impl ImageMetadataReader for CachedImageMetadata {
+    fn dimensions(&self, path: &Path) -> Option<(u32, u32)> {
+        let metadata = std::fs::metadata(path).ok()?;
+        let modified = metadata.modified().ok();
+        let len = metadata.len();
+
+        if let Some(entry) = self.entries.lock().ok()?.get(path) {
+            if entry.modified == modified && entry.len == len {
+                return entry.dimensions;
             }
+        }
 
-            meta.width = Some(width);
-            meta.height = Some(height);
+        // Read without holding the lock so parallel lookups don't queue up.
+        let dimensions = FsImageMetadata.dimensions(path);
+        self.entries.lock().ok()?.insert(
+            path.to_path_buf(),
+            CachedDimensions {
+                modified,
+                len,
+                dimensions,
+            },
+        );
+        dimensions
+    }
+}
 
-            if meta.highlight_id.is_none() {
-                if let Some(highlight_id) = get_highlight_asset_id(assets, path_segments) {
-                    meta.highlight_id = Some(highlight_id);
+/// Reads images missing from the images folder from the generated variants
+/// dir instead, so variants kept in the scratch dir resolve as if they sat
+/// next to their sources. Without a generated dir, reads go to `inner`.
+pub struct GeneratedImageMetadata<'a> {
+    pub inner: &'a dyn ImageMetadataReader,
+    pub images_folder: &'a Path,
+    pub generated_dir: Option<&'a Path>,
+}
+
+impl ImageMetadataReader for GeneratedImageMetadata<'_> {
+    fn dimensions(&self, path: &Path) -> Option<(u32, u32)> {
+        if let Some(generated_dir) = self.generated_dir {
+            if !path.exists() {
+                if let Ok(key) = path.strip_prefix(self.images_folder) {
+                    return self.inner.dimensions(&generated_dir.join(key));
                 }
             }
+        }
+        self.inner.dimensions(path)
+    }
+}
+
+/// Sizes read ahead of the augment walk, falling back to `inner` for paths
+/// that weren't.
+struct PrefetchedImageMetadata<'a> {
+    inner: &'a dyn ImageMetadataReader,
+    dimensions: HashMap<PathBuf, Option<(u32, u32)>>,
+}
 
-            AssetValue::Object(meta)
+impl ImageMetadataReader for PrefetchedImageMetadata<'_> {
+    fn dimensions(&self, path: &Path) -> Option<(u32, u32)> {
+        match self.dimensions.get(path) {
+            Some(dimensions) => *dimensions,
+            None => self.inner.dimensions(path),
         }
-        AssetValue::Table(map) => {
-            let mut result = BTreeMap::new();
-            let mut keys: Vec<String> = map.keys().cloned().collect();
-            keys.sort();
-
-            for key in keys {
-                let mut child_path = path_segments.to_vec();
-                child_path.push(key.clone());
-                result.insert(
-                    key.clone(),
-                    augment_node(
-                        map[&key].clone(),
-                        assets,
-                        &child_path,
-                        images_folder,
-                        reader,
-                    ),
-                );
+    }
+}
+
+/// Built-in stage reading the size of each image, or the kind (and duration)
+/// of audio and model files.
+pub struct Dimensions<'a> {
+    pub reader: &'a dyn ImageMetadataReader,
+}
+
+impl Augmenter for Dimensions<'_> {
+    fn name(&self) -> &'static str {
+        "dimensions"
+    }
+
+    fn augment(&self, meta: &mut AssetMeta, asset: &AssetContext) {
+        if let Some(kind) = asset_kind(asset.path) {
+            meta.kind = Some(kind.to_string());
+            if kind == AUDIO_KIND {
+                meta.duration = read_duration(asset.path).or(meta.duration);
             }
+            return;
+        }
+        let (width, height) = self
+            .reader
+            .dimensions(asset.path)
+            .unwrap_or((meta.width.unwrap_or(0), meta.height.unwrap_or(0)));
 
-            AssetValue::Table(result)
+        if width == 0 && height == 0 && meta.width.is_none() {
+            println!(
+                "[sync] WARN: {} is not a supported image or is unreadable – skipping size metadata.",
+                asset.path.display()
+            );
         }
+
+        meta.width = Some(width);
+        meta.height = Some(height);
     }
 }
 
-fn build_image_path(images_folder: &Path, segments: &[String]) -> PathBuf {
-    let relative = segments.join("/");
-    images_folder.join(relative)
+/// Built-in stage linking each image to the id of its `-highlight.png`
+/// sibling.
+pub struct HighlightLinks;
+
+impl Augmenter for HighlightLinks {
+    fn name(&self) -> &'static str {
+        "highlights"
+    }
+
+    fn augment(&self, meta: &mut AssetMeta, asset: &AssetContext) {
+        if meta.highlight_id.is_none() && meta.kind.is_none() {
+            meta.highlight_id = get_highlight_asset_id(asset.assets, asset.key);
+        }
+    }
 }
 
-fn get_highlight_asset_id(
+/// Turns bare ids into metadata and runs the enabled source stages (sizes
+/// and highlight links) over it.
+pub fn augment_assets(
     assets: &BTreeMap<String, AssetValue>,
-    path_segments: &[String],
-) -> Option<String> {
-    let last_segment = path_segments.last()?;
-    if last_segment.ends_with("-highlight.png") {
-        return None;
+    images_folder: &Path,
+    reader: &dyn ImageMetadataReader,
+    options: &TruffleOptions,
+) -> BTreeMap<String, AssetValue> {
+    // Image headers are read in parallel up front; the walk itself is cheap.
+    let mut paths = Vec::new();
+    collect_image_paths(assets, images_folder, &mut Vec::new(), &mut paths);
+    let reader = PrefetchedImageMetadata {
+        inner: reader,
+        dimensions: paths
+            .into_par_iter()
+            .map(|path| {
+                let dimensions = reader.dimensions(&path);
+                (path, dimensions)
+            })
+            .collect(),
+    };
+
+    let mut augmented = ids_to_meta(assets);
+    let dimensions = Dimensions { reader: &reader };
+    let stages = enabled_augmenters(&[&dimensions, &HighlightLinks], options);
+    run_augmenters(&mut augmented, images_folder, "", &stages);
+    augmented
+}
+
+/// Paths of the images whose size the augment walk reads.
+fn collect_image_paths(
+    map: &BTreeMap<String, AssetValue>,
+    images_folder: &Path,
+    path_segments: &mut Vec<String>,
+    paths: &mut Vec<PathBuf>,
+) {
+    for (key, node) in map {
+        path_segments.push(key.clone());
+        match node {
+            AssetValue::String(_) | AssetValue::Number(_) | AssetValue::Object(_) => {
+                let path = build_image_path(images_folder, path_segments);
+                if asset_kind(&path).is_none() {
+                    paths.push(path);
+                }
+            }
+            AssetValue::Table(map) => collect_image_paths(map, images_folder, path_segments, paths),
+            AssetValue::Bool(_) | AssetValue::Surface(_) => {}
+        }
+        path_segments.pop();
     }
+}
 
-    let mut highlight_path = path_segments.to_vec();
-    if let Some(last) = highlight_path.last_mut() {
-        *last = last.replace(".png", "-highlight.png");
+/// Wraps every bare asset id in the tree in metadata of its own.
+fn ids_to_meta(map: &BTreeMap<String, AssetValue>) -> BTreeMap<String, AssetValue> {
+    map.iter()
+        .map(|(key, node)| {
+            let node = match node {
+                AssetValue::String(id) => AssetValue::Object(AssetMeta {
+                    id: id.clone(),
+                    ..Default::default()
+                }),
+                AssetValue::Number(n) => AssetValue::Object(AssetMeta {
+                    id: n.to_string(),
+                    ..Default::default()
+                }),
+                AssetValue::Table(map) => AssetValue::Table(ids_to_meta(map)),
+                node => node.clone(),
+            };
+            (key.clone(), node)
+        })
+        .collect()
+}
+
+/// The duration of the audio file at `path`, warning when it can't be read.
+fn read_duration(path: &Path) -> Option<f64> {
+    let duration = audio_duration(path);
+    if duration.is_none() {
+        println!(
+            "[sync] WARN: {} is not a supported audio file or is unreadable – skipping duration.",
+            path.display()
+        );
     }
+    duration
+}
+
+fn build_image_path(images_folder: &Path, segments: &[String]) -> PathBuf {
+    let relative = segments.join("/");
+    images_folder.join(relative)
+}
+
+/// Looks up the `-highlight.png` sibling of the asset at `key`.
+fn get_highlight_asset_id(assets: &BTreeMap<String, AssetValue>, key: &str) -> Option<String> {
+    let path_segments: Vec<&str> = key.split('/').collect();
+    let (last_segment, parents) = path_segments.split_last()?;
+    let highlight_key = crate::image::highlight::highlight_key(last_segment)?;
 
-    let mut node = Some(AssetValue::Table(assets.clone()));
-    for segment in &highlight_path {
-        node = match node? {
-            AssetValue::Table(map) => map.get(segment).cloned(),
-            _ => None,
+    let mut map = assets;
+    for segment in parents {
+        map = match map.get(*segment)? {
+            AssetValue::Table(children) => children,
+            _ => return None,
         };
     }
 
-    match node? {
-        AssetValue::String(s) => Some(s),
+    match map.get(&highlight_key)? {
+        AssetValue::String(s) => Some(s.clone()),
         AssetValue::Number(n) => Some(n.to_string()),
-        AssetValue::Object(meta) => Some(meta.id),
+        AssetValue::Object(meta) => Some(meta.id.clone()),
         _ => None,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn image_sizes_persist_between_runs() {
+        let dir = std::env::temp_dir().join(format!("truffle-augment-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("ui")).unwrap();
+        for (name, width) in [("play.png", 12), ("stop.png", 7)] {
+            image::RgbaImage::new(width, 5)
+                .save(dir.join("ui").join(name))
+                .unwrap();
+        }
+        let assets = BTreeMap::from([(
+            "ui".to_string(),
+            AssetValue::Table(BTreeMap::from([
+                ("play.png".to_string(), AssetValue::Number(1.0)),
+                ("stop.png".to_string(), AssetValue::Number(2.0)),
+            ])),
+        )]);
+
+        let images = CachedImageMetadata::default();
+        let options = TruffleOptions::default();
+        let augmented = augment_assets(&assets, &dir, &images, &options);
+        assert_eq!(images.cached_images(), 2);
+        let cache = dir.join("image-sizes.json");
+        images.save(&cache).unwrap();
+
+        let reloaded = CachedImageMetadata::load(&cache);
+        assert_eq!(reloaded.cached_images(), 2);
+        assert_eq!(
+            augment_assets(&assets, &dir, &reloaded, &options),
+            augmented
+        );
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let AssetValue::Table(ui) = &augmented["ui"] else {
+            panic!("expected a table");
+        };
+        let AssetValue::Object(play) = &ui["play.png"] else {
+            panic!("expected an asset");
+        };
+        assert_eq!((play.width, play.height), (Some(12), Some(5)));
+    }
+}
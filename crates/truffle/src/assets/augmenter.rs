@@ -0,0 +1,168 @@
+use super::model::{AssetMeta, AssetValue};
+use std::collections::BTreeMap;
+use std::path::Path;
+use truffle_config::TruffleOptions;
+
+/// What an [`Augmenter`] knows about the asset it fills in.
+pub struct AssetContext<'a> {
+    /// Key of the asset relative to its images folder (`ui/play.png`).
+    pub key: &'a str,
+    /// Prefix the images root nests its keys under, or `""`.
+    pub prefix: &'a str,
+    /// The asset's source file in the images folder.
+    pub path: &'a Path,
+    /// The tree as it was before this run, for looking up related assets.
+    pub assets: &'a BTreeMap<String, AssetValue>,
+}
+
+/// One stage of augmentation. Stages run in order on every asset of the
+/// tree and can be turned on or off by name in `[truffle.augmenters]`.
+///
+/// Adding a stage means implementing this trait and listing it where the
+/// built-in stages are run: [`augment_assets`](super::augment_assets) for
+/// stages that need the source files, `render_modules` in the sync command
+/// for stages that need the final (atlased) sizes.
+pub trait Augmenter: Sync {
+    /// Name of the stage in `[truffle.augmenters]`.
+    fn name(&self) -> &'static str;
+
+    /// Whether the stage runs when truffle.toml doesn't mention it.
+    fn enabled_by_default(&self) -> bool {
+        true
+    }
+
+    /// Fills in fields of `meta`.
+    fn augment(&self, meta: &mut AssetMeta, asset: &AssetContext);
+}
+
+/// The `stages` that `options` leaves enabled, in order.
+pub fn enabled_augmenters<'a>(
+    stages: &[&'a dyn Augmenter],
+    options: &TruffleOptions,
+) -> Vec<&'a dyn Augmenter> {
+    stages
+        .iter()
+        .filter(|stage| {
+            options
+                .augmenters
+                .get(stage.name())
+                .copied()
+                .unwrap_or_else(|| stage.enabled_by_default())
+        })
+        .copied()
+        .collect()
+}
+
+/// Runs `stages` on every asset in `assets`, whose files live in
+/// `images_folder`. `prefix` is the key prefix of the images root, if any.
+pub fn run_augmenters(
+    assets: &mut BTreeMap<String, AssetValue>,
+    images_folder: &Path,
+    prefix: &str,
+    stages: &[&dyn Augmenter],
+) {
+    if stages.is_empty() {
+        return;
+    }
+    let snapshot = assets.clone();
+    let mut walk = Walk {
+        snapshot: &snapshot,
+        images_folder,
+        prefix: prefix.trim_matches('/'),
+        stages,
+        keys: Vec::new(),
+    };
+    walk.table(assets);
+}
+
+struct Walk<'a> {
+    snapshot: &'a BTreeMap<String, AssetValue>,
+    images_folder: &'a Path,
+    prefix: &'a str,
+    stages: &'a [&'a dyn Augmenter],
+    keys: Vec<String>,
+}
+
+impl Walk<'_> {
+    fn table(&mut self, map: &mut BTreeMap<String, AssetValue>) {
+        for (key, value) in map.iter_mut() {
+            self.keys.push(key.clone());
+            match value {
+                AssetValue::Object(meta) => {
+                    let key = self.keys.join("/");
+                    let path = self.images_folder.join(&key);
+                    let context = AssetContext {
+                        key: &key,
+                        prefix: self.prefix,
+                        path: &path,
+                        assets: self.snapshot,
+                    };
+                    for stage in self.stages {
+                        stage.augment(meta, &context);
+                    }
+                }
+                AssetValue::Table(map) => self.table(map),
+                _ => {}
+            }
+            self.keys.pop();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Tag(&'static str, bool);
+
+    impl Augmenter for Tag {
+        fn name(&self) -> &'static str {
+            self.0
+        }
+
+        fn enabled_by_default(&self) -> bool {
+            self.1
+        }
+
+        fn augment(&self, meta: &mut AssetMeta, asset: &AssetContext) {
+            meta.extra.insert(
+                self.0.to_string(),
+                AssetValue::String(format!("{}:{}", asset.prefix, asset.key)),
+            );
+        }
+    }
+
+    #[test]
+    fn stages_follow_the_config() {
+        let options = TruffleOptions {
+            augmenters: BTreeMap::from([("on".to_string(), false), ("opt_in".to_string(), true)]),
+            ..Default::default()
+        };
+        let stages = [&Tag("on", true), &Tag("off", false), &Tag("opt_in", false)];
+        let stages: Vec<&dyn Augmenter> = stages.iter().map(|s| *s as &dyn Augmenter).collect();
+        let enabled = enabled_augmenters(&stages, &options);
+        assert_eq!(
+            enabled.iter().map(|s| s.name()).collect::<Vec<_>>(),
+            vec!["opt_in"]
+        );
+
+        let mut assets = BTreeMap::from([(
+            "ui".to_string(),
+            AssetValue::Table(BTreeMap::from([(
+                "play.png".to_string(),
+                AssetValue::Object(AssetMeta::default()),
+            )])),
+        )]);
+        run_augmenters(&mut assets, Path::new("images"), "hud/", &enabled);
+        let AssetValue::Table(ui) = &assets["ui"] else {
+            panic!("expected a table");
+        };
+        let AssetValue::Object(play) = &ui["play.png"] else {
+            panic!("expected an asset");
+        };
+        assert_eq!(
+            play.extra["opt_in"],
+            AssetValue::String("hud:ui/play.png".into())
+        );
+    }
+}
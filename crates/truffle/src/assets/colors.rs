@@ -0,0 +1,87 @@
+use super::augmenter::{AssetContext, Augmenter};
+use super::model::AssetMeta;
+use crate::image::palette::extract_palette;
+use image::RgbaImage;
+
+/// Optional stage recording the average and dominant colors of each image
+/// as `avgColor`/`dominantColor` hex strings (`"#3a7bd5"`), for placeholder
+/// tints while the real image streams in. Off unless `colors = true` is set
+/// in `[truffle.augmenters]`, since every image is decoded.
+pub struct ImageColors;
+
+impl Augmenter for ImageColors {
+    fn name(&self) -> &'static str {
+        "colors"
+    }
+
+    fn enabled_by_default(&self) -> bool {
+        false
+    }
+
+    fn augment(&self, meta: &mut AssetMeta, asset: &AssetContext) {
+        if meta.kind.is_some() || !crate::image::is_raster_image(asset.path) {
+            return;
+        }
+        let image = match image::open(asset.path) {
+            Ok(image) => image.to_rgba8(),
+            Err(e) => {
+                println!(
+                    "[sync] WARN: Failed to read {}: {} – skipping colors.",
+                    asset.path.display(),
+                    e
+                );
+                return;
+            }
+        };
+        meta.avg_color = average_color(&image).map(hex);
+        meta.dominant_color = extract_palette(&image, 8).first().copied().map(hex);
+    }
+}
+
+/// The mean color of the visible pixels, weighted by their alpha.
+fn average_color(image: &RgbaImage) -> Option<[u8; 3]> {
+    let mut sums = [0u64; 3];
+    let mut weight = 0u64;
+    for pixel in image.pixels() {
+        let alpha = u64::from(pixel[3]);
+        for (sum, channel) in sums.iter_mut().zip(pixel.0) {
+            *sum += u64::from(channel) * alpha;
+        }
+        weight += alpha;
+    }
+    if weight == 0 {
+        return None;
+    }
+    Some(sums.map(|sum| ((sum + weight / 2) / weight) as u8))
+}
+
+fn hex([r, g, b]: [u8; 3]) -> String {
+    format!("#{:02x}{:02x}{:02x}", r, g, b)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgba;
+
+    #[test]
+    fn colors_ignore_transparent_pixels() {
+        let mut image = RgbaImage::new(5, 1);
+        for x in 0..3 {
+            image.put_pixel(x, 0, Rgba([255, 0, 0, 255]));
+        }
+        image.put_pixel(3, 0, Rgba([0, 0, 255, 255]));
+        image.put_pixel(4, 0, Rgba([0, 255, 0, 0]));
+
+        assert_eq!(average_color(&image).map(hex).as_deref(), Some("#bf0040"));
+        assert_eq!(
+            extract_palette(&image, 8)
+                .first()
+                .copied()
+                .map(hex)
+                .as_deref(),
+            Some("#ff0000")
+        );
+        assert_eq!(average_color(&RgbaImage::new(2, 2)), None);
+    }
+}
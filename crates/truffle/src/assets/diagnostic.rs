@@ -0,0 +1,90 @@
+use std::fmt::Write;
+use std::path::Path;
+
+/// A 1-based line and column (in characters) in a source file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Location {
+    pub line: usize,
+    pub column: usize,
+}
+
+/// Renders a compiler-style diagnostic: the message, the file location and
+/// the offending source line with the span from `start` to `end` underlined.
+/// Spans that continue past the first line are underlined to its end.
+pub fn render(path: &Path, source: &str, message: &str, start: Location, end: Location) -> String {
+    let mut out = format!("error: {}\n", message);
+    let _ = writeln!(
+        out,
+        "  --> {}:{}:{}",
+        path.display(),
+        start.line,
+        start.column
+    );
+
+    let Some(line) = source.lines().nth(start.line.saturating_sub(1)) else {
+        return out;
+    };
+    let gutter = " ".repeat(start.line.to_string().len());
+    let line_len = line.chars().count();
+    let from = start.column.saturating_sub(1).min(line_len);
+    let to = if end.line == start.line {
+        end.column.saturating_sub(1).min(line_len)
+    } else {
+        line_len
+    };
+
+    // Reuse the line's own tabs so the carets stay aligned with the code
+    let padding: String = line
+        .chars()
+        .take(from)
+        .map(|c| if c == '\t' { '\t' } else { ' ' })
+        .collect();
+    let carets = "^".repeat(to.saturating_sub(from).max(1));
+
+    let _ = writeln!(out, "{} |", gutter);
+    let _ = writeln!(out, "{} | {}", start.line, line);
+    let _ = writeln!(out, "{} | {}{}", gutter, padding, carets);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn underlines_the_span() {
+        let source = "return {\n\t[\"a.png\"] = = 1,\n}\n";
+        let rendered = render(
+            Path::new("assets.luau"),
+            source,
+            "unexpected token `=`",
+            Location {
+                line: 2,
+                column: 14,
+            },
+            Location {
+                line: 2,
+                column: 15,
+            },
+        );
+        assert_eq!(
+            rendered,
+            "error: unexpected token `=`\n  --> assets.luau:2:14\n  |\n2 | \t[\"a.png\"] = = 1,\n  | \t            ^\n"
+        );
+    }
+
+    #[test]
+    fn missing_lines_only_show_the_location() {
+        let rendered = render(
+            Path::new("assets.luau"),
+            "",
+            "unexpected end of file",
+            Location { line: 3, column: 1 },
+            Location { line: 3, column: 1 },
+        );
+        assert_eq!(
+            rendered,
+            "error: unexpected end of file\n  --> assets.luau:3:1\n"
+        );
+    }
+}
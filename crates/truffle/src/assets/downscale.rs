@@ -0,0 +1,201 @@
+use super::augmenter::{AssetContext, Augmenter};
+use super::model::{AssetMeta, SliceCenter};
+use asphalt::sync::downscale::downscaled_size;
+use asphalt::{Downscale, DownscaleRule};
+use image::imageops::FilterType;
+use std::path::PathBuf;
+use std::sync::Arc;
+use truffle_config::{DownscaleFilter, TruffleOptions};
+
+/// Caps on the longest side of uploaded images, from `max_dimension` and
+/// `directory_max_dimensions`.
+#[derive(Debug, Clone)]
+pub struct DownscaleLimits {
+    max_dimension: Option<u32>,
+    /// Directory keys with a trailing slash, deepest first.
+    directories: Vec<(String, u32)>,
+    filter: FilterType,
+}
+
+impl DownscaleLimits {
+    /// The configured caps, or `None` when nothing is downscaled.
+    pub fn new(options: &TruffleOptions) -> Option<Self> {
+        if options.max_dimension.is_none() && options.directory_max_dimensions.is_empty() {
+            return None;
+        }
+        let mut directories: Vec<_> = options
+            .directory_max_dimensions
+            .iter()
+            .map(|(dir, max)| (format!("{}/", dir.trim_matches('/')), *max))
+            .collect();
+        directories.sort_by_key(|(dir, _)| std::cmp::Reverse(dir.len()));
+        Some(Self {
+            max_dimension: options.max_dimension,
+            directories,
+            filter: match options.downscale_filter {
+                DownscaleFilter::Nearest => FilterType::Nearest,
+                DownscaleFilter::Triangle => FilterType::Triangle,
+                DownscaleFilter::CatmullRom => FilterType::CatmullRom,
+                DownscaleFilter::Gaussian => FilterType::Gaussian,
+                DownscaleFilter::Lanczos3 => FilterType::Lanczos3,
+            },
+        })
+    }
+
+    /// The cap for the image at `key`: its deepest configured directory's,
+    /// or the global one.
+    pub fn limit(&self, key: &str) -> Option<u32> {
+        self.directories
+            .iter()
+            .find(|(dir, _)| key.starts_with(dir.as_str()))
+            .map(|(_, max)| *max)
+            .or(self.max_dimension)
+    }
+
+    /// A sync rule capping the files under `folders`, each paired with the
+    /// key prefix its images get. Other files (atlas pages) upload as is.
+    pub fn rule(self, folders: Vec<(PathBuf, String)>) -> DownscaleRule {
+        let folders: Vec<_> = folders
+            .into_iter()
+            .map(|(folder, prefix)| (crate::watch::normalize(&folder), prefix))
+            .collect();
+        Arc::new(move |path| {
+            let path = crate::watch::normalize(path);
+            let key = folders.iter().find_map(|(folder, prefix)| {
+                let key = path.strip_prefix(folder).ok()?.to_string_lossy();
+                Some(match prefix.trim_matches('/') {
+                    "" => key.replace('\\', "/"),
+                    prefix => format!("{}/{}", prefix, key.replace('\\', "/")),
+                })
+            })?;
+            Some(Downscale {
+                max_dimension: self.limit(&key)?,
+                filter: self.filter,
+            })
+        })
+    }
+}
+
+/// Built-in stage recording the size images were downscaled to before
+/// uploading: `width` and `height` become the uploaded size and
+/// `sourceWidth`/`sourceHeight` keep the file's. Nine-slice centers and
+/// alpha bounds are scaled along. Atlas sprites are packed from their sources and keep their
+/// size.
+impl Augmenter for DownscaleLimits {
+    fn name(&self) -> &'static str {
+        "downscale"
+    }
+
+    fn augment(&self, meta: &mut AssetMeta, asset: &AssetContext) {
+        let key = match asset.prefix {
+            "" => asset.key.to_string(),
+            prefix => format!("{}/{}", prefix, asset.key),
+        };
+        if let Some(max_dimension) = self.limit(&key) {
+            apply_meta(meta, max_dimension);
+        }
+    }
+}
+
+fn apply_meta(meta: &mut AssetMeta, max_dimension: u32) {
+    if meta.rect_w.is_some() || meta.kind.is_some() {
+        return;
+    }
+    let (Some(width), Some(height)) = (meta.width, meta.height) else {
+        return;
+    };
+    let (new_width, new_height) = downscaled_size(width, height, max_dimension);
+    if (new_width, new_height) == (width, height) {
+        return;
+    }
+
+    let scale_x = |x: u32| (u64::from(x) * u64::from(new_width) / u64::from(width)) as u32;
+    let scale_y = |y: u32| (u64::from(y) * u64::from(new_height) / u64::from(height)) as u32;
+    let scale_rect = |rect: SliceCenter| SliceCenter {
+        min_x: scale_x(rect.min_x),
+        min_y: scale_y(rect.min_y),
+        max_x: scale_x(rect.max_x),
+        max_y: scale_y(rect.max_y),
+    };
+    meta.slice_center = meta.slice_center.map(scale_rect);
+    meta.alpha_bleed = meta.alpha_bleed.map(scale_rect);
+    meta.source_width = Some(width);
+    meta.source_height = Some(height);
+    meta.width = Some(new_width);
+    meta.height = Some(new_height);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assets::augmenter::run_augmenters;
+    use crate::assets::model::AssetValue;
+    use std::collections::BTreeMap;
+    use std::path::Path;
+
+    fn image(width: u32, height: u32) -> AssetValue {
+        AssetValue::Object(AssetMeta {
+            id: "rbxassetid://1".into(),
+            width: Some(width),
+            height: Some(height),
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn caps_images_by_directory() {
+        let options = TruffleOptions {
+            max_dimension: Some(1024),
+            directory_max_dimensions: BTreeMap::from([("ui/icons".to_string(), 64)]),
+            ..Default::default()
+        };
+        let limits = DownscaleLimits::new(&options).unwrap();
+        assert_eq!(limits.limit("ui/icons/coin.png"), Some(64));
+        assert_eq!(limits.limit("ui/iconsheet.png"), Some(1024));
+
+        let rule = limits
+            .clone()
+            .rule(vec![(PathBuf::from("./assets/images"), "hud".into())]);
+        let downscale = rule(Path::new("assets/images/ui/icons/coin.png"));
+        assert_eq!(downscale.map(|d| d.max_dimension), Some(1024));
+        assert!(rule(Path::new(".truffle/atlas/page0.png")).is_none());
+
+        let mut assets = BTreeMap::from([
+            ("splash.png".to_string(), image(2048, 1536)),
+            (
+                "ui".to_string(),
+                AssetValue::Table(BTreeMap::from([(
+                    "icons".to_string(),
+                    AssetValue::Table(BTreeMap::from([
+                        ("coin.png".to_string(), image(128, 128)),
+                        ("gem.png".to_string(), image(32, 32)),
+                    ])),
+                )])),
+            ),
+        ]);
+        run_augmenters(&mut assets, Path::new(""), "", &[&limits]);
+
+        let AssetValue::Object(splash) = &assets["splash.png"] else {
+            panic!("expected an asset");
+        };
+        assert_eq!((splash.width, splash.height), (Some(1024), Some(768)));
+        assert_eq!(
+            (splash.source_width, splash.source_height),
+            (Some(2048), Some(1536))
+        );
+        let AssetValue::Table(ui) = &assets["ui"] else {
+            panic!("expected a table");
+        };
+        let AssetValue::Table(icons) = &ui["icons"] else {
+            panic!("expected a table");
+        };
+        let AssetValue::Object(coin) = &icons["coin.png"] else {
+            panic!("expected an asset");
+        };
+        assert_eq!((coin.width, coin.source_width), (Some(64), Some(128)));
+        let AssetValue::Object(gem) = &icons["gem.png"] else {
+            panic!("expected an asset");
+        };
+        assert_eq!((gem.width, gem.source_width), (Some(32), None));
+    }
+}
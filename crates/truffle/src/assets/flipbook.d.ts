@@ -0,0 +1,23 @@
+// This file is automatically @generated by truffle.
+// DO NOT EDIT MANUALLY.
+
+interface FlipbookFrame {
+	asset: { id: string; rectX?: number; rectY?: number; rectW?: number; rectH?: number };
+	duration: number;
+}
+
+export interface FlipbookAnimation {
+	loop: string;
+	repeatCount: number;
+	frames: FlipbookFrame[];
+}
+
+export interface Flipbook {
+	Stop(): void;
+}
+
+export declare function play(
+	label: ImageLabel | ImageButton,
+	animation: FlipbookAnimation,
+	onFinished?: () => void,
+): Flipbook;
@@ -0,0 +1,187 @@
+use super::model::AssetValue;
+use asphalt::config::KeyStyle;
+use std::collections::BTreeMap;
+
+/// Renames the keys of `assets` to `style` for the generated modules.
+///
+/// Every `/`-separated segment of a key is renamed, and asset keys lose
+/// their extension (`ui/play-button.png` → `ui/playButton` in camelCase).
+/// Keys that would collide with a sibling keep their original name.
+pub fn style_keys(
+    assets: &BTreeMap<String, AssetValue>,
+    style: KeyStyle,
+) -> BTreeMap<String, AssetValue> {
+    if style == KeyStyle::Keep {
+        return assets.clone();
+    }
+
+    let renamed: Vec<(&String, String)> = assets
+        .iter()
+        .map(|(key, value)| {
+            let is_asset = !matches!(value, AssetValue::Table(_));
+            (key, style_key(key, style, is_asset))
+        })
+        .collect();
+
+    let mut out = BTreeMap::new();
+    for (key, new_key) in &renamed {
+        let collides = renamed
+            .iter()
+            .any(|(other, other_new)| other != key && other_new == new_key);
+        let new_key = if collides {
+            println!(
+                "[sync] WARN: Key \"{}\" would be renamed to \"{}\", which is taken – keeping it.",
+                key, new_key
+            );
+            (*key).clone()
+        } else {
+            new_key.clone()
+        };
+        let value = match &assets[*key] {
+            AssetValue::Table(map) => AssetValue::Table(style_keys(map, style)),
+            value => value.clone(),
+        };
+        out.insert(new_key, value);
+    }
+    out
+}
+
+fn style_key(key: &str, style: KeyStyle, is_asset: bool) -> String {
+    let segments: Vec<&str> = key.split('/').collect();
+    let last = segments.len() - 1;
+    segments
+        .iter()
+        .enumerate()
+        .map(|(i, segment)| {
+            let segment = if is_asset && i == last {
+                strip_extension(segment)
+            } else {
+                segment
+            };
+            let styled = match style {
+                KeyStyle::Keep | KeyStyle::StripExtension => segment.to_string(),
+                KeyStyle::CamelCase => camel_case(segment),
+                KeyStyle::SnakeCase => words(segment).join("_"),
+            };
+            if styled.is_empty() {
+                segment.to_string()
+            } else {
+                styled
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+fn strip_extension(name: &str) -> &str {
+    match name.rsplit_once('.') {
+        Some((stem, _)) if !stem.is_empty() => stem,
+        _ => name,
+    }
+}
+
+fn camel_case(name: &str) -> String {
+    let mut out = String::new();
+    for (i, word) in words(name).into_iter().enumerate() {
+        if i == 0 {
+            out.push_str(&word);
+            continue;
+        }
+        let mut chars = word.chars();
+        if let Some(first) = chars.next() {
+            out.extend(first.to_uppercase());
+            out.push_str(chars.as_str());
+        }
+    }
+    out
+}
+
+/// Lowercase words of `name`, split at separators and where a lowercase
+/// letter or digit meets an uppercase one (`playButton-2x` → `play`,
+/// `button`, `2x`).
+fn words(name: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut current = String::new();
+    let mut previous: Option<char> = None;
+    for c in name.chars() {
+        if !c.is_alphanumeric() {
+            if !current.is_empty() {
+                words.push(std::mem::take(&mut current));
+            }
+            previous = None;
+            continue;
+        }
+        let boundary =
+            c.is_uppercase() && previous.is_some_and(|p| p.is_lowercase() || p.is_ascii_digit());
+        if boundary && !current.is_empty() {
+            words.push(std::mem::take(&mut current));
+        }
+        current.extend(c.to_lowercase());
+        previous = Some(c);
+    }
+    if !current.is_empty() {
+        words.push(current);
+    }
+    words
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assets::model::AssetMeta;
+
+    fn asset(id: &str) -> AssetValue {
+        AssetValue::Object(AssetMeta {
+            id: id.into(),
+            ..Default::default()
+        })
+    }
+
+    fn keys(map: &BTreeMap<String, AssetValue>) -> Vec<&str> {
+        map.keys().map(String::as_str).collect()
+    }
+
+    #[test]
+    fn renames_folders_and_assets() {
+        let assets = BTreeMap::from([(
+            "main-menu".to_string(),
+            AssetValue::Table(BTreeMap::from([
+                ("play_button.png".to_string(), asset("1")),
+                ("shopIcon@2x.png".to_string(), asset("2")),
+            ])),
+        )]);
+
+        let camel = style_keys(&assets, KeyStyle::CamelCase);
+        assert_eq!(keys(&camel), vec!["mainMenu"]);
+        let AssetValue::Table(menu) = &camel["mainMenu"] else {
+            panic!("expected a table");
+        };
+        assert_eq!(keys(menu), vec!["playButton", "shopIcon2x"]);
+
+        let snake = style_keys(&assets, KeyStyle::SnakeCase);
+        let AssetValue::Table(menu) = &snake["main_menu"] else {
+            panic!("expected a table");
+        };
+        assert_eq!(keys(menu), vec!["play_button", "shop_icon_2x"]);
+
+        let stripped = style_keys(&assets, KeyStyle::StripExtension);
+        let AssetValue::Table(menu) = &stripped["main-menu"] else {
+            panic!("expected a table");
+        };
+        assert_eq!(keys(menu), vec!["play_button", "shopIcon@2x"]);
+    }
+
+    #[test]
+    fn flat_keys_and_collisions() {
+        let assets = BTreeMap::from([
+            ("ui/play-button.png".to_string(), asset("1")),
+            ("ui/icon.png".to_string(), asset("2")),
+            ("ui/icon.svg".to_string(), asset("3")),
+        ]);
+        let camel = style_keys(&assets, KeyStyle::CamelCase);
+        assert_eq!(
+            keys(&camel),
+            vec!["ui/icon.png", "ui/icon.svg", "ui/playButton"]
+        );
+    }
+}
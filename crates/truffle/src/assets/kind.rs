@@ -0,0 +1,64 @@
+use std::path::Path;
+use walkdir::WalkDir;
+
+/// `kind` of audio entries in the assets module.
+pub const AUDIO_KIND: &str = "audio";
+
+/// `kind` of model and mesh entries in the assets module.
+pub const MODEL_KIND: &str = "model";
+
+/// Extensions of the non-image files Asphalt uploads, with their `kind`.
+/// Everything else in the images folder is an image and has no `kind`.
+const KINDS: [(&str, &str); 9] = [
+    ("ogg", AUDIO_KIND),
+    ("mp3", AUDIO_KIND),
+    ("wav", AUDIO_KIND),
+    ("flac", AUDIO_KIND),
+    ("rbxm", MODEL_KIND),
+    ("rbxmx", MODEL_KIND),
+    ("fbx", MODEL_KIND),
+    ("gltf", MODEL_KIND),
+    ("glb", MODEL_KIND),
+];
+
+/// The `kind` of the asset at `path`, or `None` for images.
+pub fn asset_kind(path: &Path) -> Option<&'static str> {
+    let ext = path.extension()?.to_str()?;
+    KINDS
+        .iter()
+        .find(|(e, _)| ext.eq_ignore_ascii_case(e))
+        .map(|(_, kind)| *kind)
+}
+
+/// Keys of the audio and model files below `images_folder`. They are
+/// uploaded whole, so atlas syncs exclude them like `atlas_exclude` entries.
+pub fn non_image_keys(images_folder: &Path) -> Vec<String> {
+    WalkDir::new(images_folder)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file() && asset_kind(e.path()).is_some())
+        .filter_map(|e| {
+            let rel = e.path().strip_prefix(images_folder).ok()?;
+            Some(
+                rel.components()
+                    .map(|c| c.as_os_str().to_string_lossy())
+                    .collect::<Vec<_>>()
+                    .join("/"),
+            )
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn kinds_follow_extensions() {
+        assert_eq!(asset_kind(Path::new("sfx/click.OGG")), Some(AUDIO_KIND));
+        assert_eq!(asset_kind(Path::new("props/crate.rbxm")), Some(MODEL_KIND));
+        assert_eq!(asset_kind(Path::new("props/tree.glb")), Some(MODEL_KIND));
+        assert_eq!(asset_kind(Path::new("ui/play.png")), None);
+        assert_eq!(asset_kind(Path::new("README")), None);
+    }
+}
@@ -1,5 +1,11 @@
-use super::model::{convert_map_to_asset_meta, AssetValue};
-use full_moon::{ast, tokenizer::TokenType};
+use super::diagnostic::{self, Location};
+use super::model::{convert_map_to_asset_meta, convert_map_to_surface_set, AssetValue};
+use super::serialize::{split_module_names, EntryComments};
+use full_moon::{
+    ast,
+    node::Node,
+    tokenizer::{Position, TokenReference, TokenType},
+};
 use serde_json;
 use std::collections::BTreeMap;
 use std::fs;
@@ -9,40 +15,172 @@ pub fn load_assets(path: &Path) -> Result<BTreeMap<String, AssetValue>, String>
     let content =
         fs::read_to_string(path).map_err(|e| format!("Failed to read assets file: {}", e))?;
 
-    if path.extension().and_then(|s| s.to_str()) == Some("json") {
-        let json_value: serde_json::Value =
-            serde_json::from_str(&content).map_err(|e| format!("Failed to parse JSON: {}", e))?;
-        return parse_json_value(json_value);
+    // Manifests are read as JSON values, so all three formats share a shape.
+    match path.extension().and_then(|s| s.to_str()) {
+        Some("json") => {
+            let json_value: serde_json::Value = serde_json::from_str(&content)
+                .map_err(|e| format!("Failed to parse JSON: {}", e))?;
+            return parse_json_value(json_value);
+        }
+        Some("toml") => {
+            let json_value: serde_json::Value =
+                toml::from_str(&content).map_err(|e| format!("Failed to parse TOML: {}", e))?;
+            return parse_json_value(json_value);
+        }
+        Some("yaml" | "yml") => {
+            let json_value: serde_json::Value = serde_yaml::from_str(&content)
+                .map_err(|e| format!("Failed to parse YAML: {}", e))?;
+            return parse_json_value(json_value);
+        }
+        _ => {}
     }
 
-    parse_luau_assets_module(&content)
+    let mut assets = parse_luau_assets_module(&content, path)?;
+    // Folders of a split module live in sibling modules the index requires.
+    for name in split_module_names(&content) {
+        let module = path.with_file_name(match path.extension() {
+            Some(extension) => format!("{}.{}", name, extension.to_string_lossy()),
+            None => name.clone(),
+        });
+        assets.insert(name, AssetValue::Table(load_assets(&module)?));
+    }
+    Ok(assets)
 }
 
-fn parse_luau_assets_module(content: &str) -> Result<BTreeMap<String, AssetValue>, String> {
+fn parse_luau_assets_module(
+    content: &str,
+    path: &Path,
+) -> Result<BTreeMap<String, AssetValue>, String> {
     let ast = full_moon::parse(content).map_err(|errors| {
-        let details = errors
+        let diagnostics = errors
             .iter()
-            .map(|e| format!("{:?}", e))
+            .map(|e| {
+                let (start, end) = e.range();
+                diagnostic::render(
+                    path,
+                    content,
+                    &e.error_message(),
+                    location(&start),
+                    location(&end),
+                )
+            })
             .collect::<Vec<_>>()
-            .join(", ");
-        format!("Failed to parse Luau: {}", details)
+            .join("\n");
+        format!("Failed to parse Luau:\n{}", diagnostics)
     })?;
 
     let block = ast.nodes();
+    let locals = module_locals(block);
 
-    if let Some(table) = find_local_assets_table(block) {
-        return convert_table_to_asset_value(table);
-    }
+    let table = find_assets_table(block)
+        .ok_or_else(|| "Could not find assets table in Luau file".to_string())?;
+    let source = Source { path, content };
+    convert_table_to_asset_value(table, locals_before(&locals, table), &source)
+}
+
+/// The module being converted, so errors can point at the code they're about.
+struct Source<'a> {
+    path: &'a Path,
+    content: &'a str,
+}
 
-    if let Some(table) = find_assets_table_in_return(block) {
-        return convert_table_to_asset_value(table);
+fn find_assets_table(block: &ast::Block) -> Option<&ast::TableConstructor> {
+    find_local_assets_table(block)
+        .or_else(|| find_assets_table_in_return(block))
+        .or_else(|| find_direct_return_table(block))
+}
+
+/// The comments written above entries of the module's assets table, so
+/// rewrites can keep them next to the same keys.
+pub fn module_comments(content: &str) -> EntryComments {
+    let Ok(ast) = full_moon::parse(content) else {
+        return EntryComments::default();
+    };
+    find_assets_table(ast.nodes())
+        .map(table_comments)
+        .unwrap_or_default()
+}
+
+fn table_comments(table: &ast::TableConstructor) -> EntryComments {
+    let mut comments = EntryComments::default();
+    for field in table.fields() {
+        let (key, first_token, value) = match field {
+            ast::Field::NameKey { key, value, .. } => (token_text(key), key, value),
+            ast::Field::ExpressionKey {
+                brackets,
+                key,
+                value,
+                ..
+            } => (
+                extract_string_value(key).unwrap_or_else(|_| key.to_string().trim().to_string()),
+                brackets.tokens().0,
+                value,
+            ),
+            _ => continue,
+        };
+        let lines = first_token
+            .leading_trivia()
+            .filter(|token| {
+                matches!(
+                    token.token_type(),
+                    TokenType::SingleLineComment { .. } | TokenType::MultiLineComment { .. }
+                )
+            })
+            .map(|token| token.to_string())
+            .collect();
+        let children = match without_assertion(value) {
+            ast::Expression::TableConstructor(inner) => table_comments(inner).children,
+            _ => BTreeMap::new(),
+        };
+        let entry = EntryComments { lines, children };
+        if !entry.is_empty() {
+            comments.children.insert(key, entry);
+        }
     }
+    comments
+}
+
+/// The text of `token` without the whitespace and comments written around it,
+/// which full_moon keeps as the token's trivia.
+fn token_text(token: &TokenReference) -> String {
+    token.token().to_string()
+}
 
-    if let Some(table) = find_direct_return_table(block) {
-        return convert_table_to_asset_value(table);
+/// The module's top-level locals in declaration order, which entries can
+/// refer to by name (`local BASE = "rbxassetid://"`).
+fn module_locals(block: &ast::Block) -> Vec<(String, &ast::Expression)> {
+    let mut locals = Vec::new();
+    for stmt in block.stmts() {
+        if let ast::Stmt::LocalAssignment(local_assign) = stmt {
+            for (name, expr) in local_assign
+                .names()
+                .iter()
+                .zip(local_assign.expressions().iter())
+            {
+                locals.push((token_text(name), expr));
+            }
+        }
     }
+    locals
+}
 
-    Err("Could not find assets table in Luau file".to_string())
+/// The locals declared before `table`, or all of them when the table isn't
+/// assigned to one (a returned table sees every local).
+fn locals_before<'a, 'b>(
+    locals: &'b [(String, &'a ast::Expression)],
+    table: &ast::TableConstructor,
+) -> &'b [(String, &'a ast::Expression)] {
+    let index = locals.iter().position(|(_, expr)| {
+        matches!(without_assertion(expr), ast::Expression::TableConstructor(local) if std::ptr::eq(local, table))
+    });
+    &locals[..index.unwrap_or(locals.len())]
+}
+
+fn location(position: &Position) -> Location {
+    Location {
+        line: position.line(),
+        column: position.character(),
+    }
 }
 
 fn find_direct_return_table(block: &ast::Block) -> Option<&ast::TableConstructor> {
@@ -74,11 +212,14 @@ fn find_direct_return_table(block: &ast::Block) -> Option<&ast::TableConstructor
 fn looks_like_asset_table(table: &ast::TableConstructor) -> bool {
     // Asphalt-generated Luau returns a table whose keys are file names.
     // We only accept this format if it contains at least one key that looks like an asset file.
-    const EXTENSIONS: [&str; 5] = [".png", ".jpg", ".jpeg", ".webp", ".svg"];
+    const EXTENSIONS: [&str; 15] = [
+        ".png", ".jpg", ".jpeg", ".webp", ".bmp", ".svg", ".ogg", ".mp3", ".wav", ".flac", ".rbxm",
+        ".rbxmx", ".fbx", ".gltf", ".glb",
+    ];
 
     for field in table.fields() {
         let key = match field {
-            ast::Field::NameKey { key, .. } => Some(key.to_string().trim().to_string()),
+            ast::Field::NameKey { key, .. } => Some(token_text(key)),
             ast::Field::ExpressionKey { key, .. } => match key {
                 ast::Expression::String(_) => extract_string_value(key).ok(),
                 _ => None,
@@ -98,6 +239,43 @@ fn looks_like_asset_table(table: &ast::TableConstructor) -> bool {
     false
 }
 
+/// Fields of the module's returned table besides `assets` and
+/// `animations`, as written (`version = 3`), so rewrites keep them. Modules
+/// that return the assets table itself have none.
+pub fn returned_fields(content: &str) -> Vec<String> {
+    let Ok(ast) = full_moon::parse(content) else {
+        return Vec::new();
+    };
+    let Some(ast::LastStmt::Return(ret)) = ast.nodes().last_stmt() else {
+        return Vec::new();
+    };
+    let Some(ast::Expression::TableConstructor(table)) = ret.returns().iter().next() else {
+        return Vec::new();
+    };
+    let is_module_field = |field: &ast::Field, names: &[&str]| matches!(field, ast::Field::NameKey { key, .. } if names.contains(&token_text(key).as_str()));
+    if !table
+        .fields()
+        .iter()
+        .any(|field| is_module_field(field, &["assets"]))
+    {
+        return Vec::new();
+    }
+    table
+        .fields()
+        .iter()
+        .filter(|field| !is_module_field(field, &["assets", "animations"]))
+        .map(|field| field.to_string().trim().to_string())
+        .collect()
+}
+
+/// `expr` without a `:: Type` assertion.
+fn without_assertion(expr: &ast::Expression) -> &ast::Expression {
+    match expr {
+        ast::Expression::TypeAssertion { expression, .. } => without_assertion(expression),
+        _ => expr,
+    }
+}
+
 fn find_local_assets_table(block: &ast::Block) -> Option<&ast::TableConstructor> {
     find_local_table_named(block, "assets")
 }
@@ -113,8 +291,8 @@ fn find_local_table_named<'a>(
                 .iter()
                 .zip(local_assign.expressions().iter())
             {
-                if n.to_string().trim() == name {
-                    if let ast::Expression::TableConstructor(table) = expr {
+                if token_text(n) == name {
+                    if let ast::Expression::TableConstructor(table) = without_assertion(expr) {
                         return Some(table);
                     }
                 }
@@ -154,8 +332,8 @@ fn find_assets_table_in_table<'a>(
 ) -> Option<&'a ast::TableConstructor> {
     for field in table.fields() {
         if let ast::Field::NameKey { key, value, .. } = field {
-            if key.to_string().trim() == "assets" {
-                return match value {
+            if token_text(key) == "assets" {
+                return match without_assertion(value) {
                     ast::Expression::TableConstructor(inner) => Some(inner),
                     ast::Expression::Var(var) => resolve_assets_var(block, var),
                     _ => None,
@@ -171,7 +349,7 @@ fn resolve_assets_var<'a>(
     var: &'a ast::Var,
 ) -> Option<&'a ast::TableConstructor> {
     if let ast::Var::Name(name_ref) = var {
-        if name_ref.to_string().trim() == "assets" {
+        if token_text(name_ref) == "assets" {
             return find_local_assets_table(block);
         }
     }
@@ -183,20 +361,23 @@ fn resolve_local_table<'a>(
     var: &'a ast::Var,
 ) -> Option<&'a ast::TableConstructor> {
     if let ast::Var::Name(name_ref) = var {
-        let name = name_ref.to_string();
-        return find_local_table_named(block, name.trim());
+        return find_local_table_named(block, &token_text(name_ref));
     }
     None
 }
 
 fn convert_table_to_asset_value(
     table: &ast::TableConstructor,
+    locals: &[(String, &ast::Expression)],
+    source: &Source,
 ) -> Result<BTreeMap<String, AssetValue>, String> {
     let mut result = BTreeMap::new();
+    // Array entries are keyed by their position, counting from 1 like Luau.
+    let mut position = 0;
 
     for field in table.fields() {
         let (key, value_expr) = match field {
-            ast::Field::NameKey { key, value, .. } => (key.to_string().trim().to_string(), value),
+            ast::Field::NameKey { key, value, .. } => (token_text(key), value),
             ast::Field::ExpressionKey { key, value, .. } => {
                 let key_str = match key {
                     ast::Expression::String(_) => extract_string_value(key)
@@ -205,11 +386,18 @@ fn convert_table_to_asset_value(
                 };
                 (key_str, value)
             }
-            ast::Field::NoKey(_) => continue,
+            ast::Field::NoKey(value) => {
+                position += 1;
+                (position.to_string(), value)
+            }
             _ => continue,
         };
+        // Folders of a split module are required from their own files.
+        if is_sibling_require(value_expr) {
+            continue;
+        }
 
-        let asset_value = convert_expr_to_asset_value(value_expr)?;
+        let asset_value = convert_expr_to_asset_value(value_expr, locals, source)?;
         result.insert(key, asset_value);
     }
 
@@ -252,7 +440,89 @@ fn extract_number_value(expr: &ast::Expression) -> Result<f64, String> {
     Err("Expression is not a numeric literal".to_string())
 }
 
-fn convert_expr_to_asset_value(expr: &ast::Expression) -> Result<AssetValue, String> {
+/// The arguments of a `function(...)` call.
+fn call_arguments<'a>(
+    expr: &'a ast::Expression,
+    function: &str,
+) -> Option<Vec<&'a ast::Expression>> {
+    let ast::Expression::FunctionCall(call) = expr else {
+        return None;
+    };
+    let ast::Prefix::Name(name) = call.prefix() else {
+        return None;
+    };
+    if token_text(name) != function {
+        return None;
+    }
+    let mut suffixes = call.suffixes();
+    let (Some(ast::Suffix::Call(ast::Call::AnonymousCall(args))), None) =
+        (suffixes.next(), suffixes.next())
+    else {
+        return None;
+    };
+    match args {
+        ast::FunctionArgs::Parentheses { arguments, .. } => Some(arguments.iter().collect()),
+        _ => None,
+    }
+}
+
+/// The argument of a `tostring(x)` call.
+fn tostring_argument(expr: &ast::Expression) -> Option<&ast::Expression> {
+    match call_arguments(expr, "tostring")?.as_slice() {
+        [argument] => Some(*argument),
+        _ => None,
+    }
+}
+
+/// Whether `expr` is a `require(script.Parent.<name>)` call, which is how the
+/// index of a split module pulls in each folder's module.
+fn is_sibling_require(expr: &ast::Expression) -> bool {
+    let arguments = call_arguments(expr, "require").unwrap_or_default();
+    let [ast::Expression::Var(ast::Var::Expression(var))] = arguments[..] else {
+        return false;
+    };
+    let ast::Prefix::Name(root) = var.prefix() else {
+        return false;
+    };
+    let suffixes: Vec<_> = var.suffixes().collect();
+    token_text(root) == "script"
+        && matches!(
+            suffixes.as_slice(),
+            [ast::Suffix::Index(ast::Index::Dot { name: parent, .. }), ast::Suffix::Index(_)]
+                if token_text(parent) == "Parent"
+        )
+}
+
+/// `expr` as an operand of `..`, which only joins strings and numbers.
+fn concat_operand(
+    expr: &ast::Expression,
+    locals: &[(String, &ast::Expression)],
+    source: &Source,
+) -> Result<String, String> {
+    const MESSAGE: &str = "Only strings and numbers can be joined";
+    match convert_expr_to_asset_value(expr, locals, source)? {
+        AssetValue::String(s) => Ok(s),
+        AssetValue::Number(n) => Ok(n.to_string()),
+        _ => Err(match (expr.start_position(), expr.end_position()) {
+            (Some(start), Some(end)) => diagnostic::render(
+                source.path,
+                source.content,
+                MESSAGE,
+                location(&start),
+                location(&end),
+            ),
+            _ => format!("{}: {}", MESSAGE, expr.to_string().trim()),
+        }),
+    }
+}
+
+/// Evaluates `expr` to a value, folding `..` joins, `tostring` calls and
+/// references to the `locals` declared before it.
+fn convert_expr_to_asset_value(
+    expr: &ast::Expression,
+    locals: &[(String, &ast::Expression)],
+    source: &Source,
+) -> Result<AssetValue, String> {
     match expr {
         ast::Expression::String(_) => {
             let unquoted = extract_string_value(expr)?;
@@ -262,10 +532,42 @@ fn convert_expr_to_asset_value(expr: &ast::Expression) -> Result<AssetValue, Str
             let num = extract_number_value(expr)?;
             Ok(AssetValue::Number(num))
         }
+        ast::Expression::Symbol(token_ref) => match token_text(token_ref).as_str() {
+            "true" => Ok(AssetValue::Bool(true)),
+            "false" => Ok(AssetValue::Bool(false)),
+            _ => Err(format!("Unsupported expression type: {:?}", expr)),
+        },
+        ast::Expression::BinaryOperator {
+            lhs,
+            binop: ast::BinOp::TwoDots(_),
+            rhs,
+        } => Ok(AssetValue::String(
+            concat_operand(lhs, locals, source)? + &concat_operand(rhs, locals, source)?,
+        )),
+        ast::Expression::Parentheses { expression, .. }
+        | ast::Expression::TypeAssertion { expression, .. } => {
+            convert_expr_to_asset_value(expression, locals, source)
+        }
+        ast::Expression::Var(ast::Var::Name(name)) => {
+            let name = token_text(name);
+            let index = locals
+                .iter()
+                .rposition(|(local, _)| *local == name)
+                .ok_or_else(|| format!("Unknown variable '{}'", name))?;
+            convert_expr_to_asset_value(locals[index].1, &locals[..index], source)
+        }
+        ast::Expression::FunctionCall(_) => match tostring_argument(expr) {
+            Some(argument) => Ok(AssetValue::String(concat_operand(
+                argument, locals, source,
+            )?)),
+            None => Err(format!("Unsupported expression type: {:?}", expr)),
+        },
         ast::Expression::TableConstructor(table) => {
-            let map = convert_table_to_asset_value(table)?;
+            let map = convert_table_to_asset_value(table, locals, source)?;
             if let Some(meta) = convert_map_to_asset_meta(&map) {
                 Ok(AssetValue::Object(meta))
+            } else if let Some(surface) = convert_map_to_surface_set(&map) {
+                Ok(AssetValue::Surface(surface))
             } else {
                 Ok(AssetValue::Table(map))
             }
@@ -291,6 +593,14 @@ fn parse_json_value_to_asset(value: serde_json::Value) -> Result<AssetValue, Str
     match value {
         serde_json::Value::String(s) => Ok(AssetValue::String(s)),
         serde_json::Value::Number(n) => Ok(AssetValue::Number(n.as_f64().unwrap_or(0.0))),
+        serde_json::Value::Bool(b) => Ok(AssetValue::Bool(b)),
+        serde_json::Value::Array(items) => {
+            let mut result = BTreeMap::new();
+            for (i, item) in items.into_iter().enumerate() {
+                result.insert((i + 1).to_string(), parse_json_value_to_asset(item)?);
+            }
+            Ok(AssetValue::Table(result))
+        }
         serde_json::Value::Object(map) => {
             if map.contains_key("id") {
                 Ok(AssetValue::Object(
@@ -302,7 +612,10 @@ fn parse_json_value_to_asset(value: serde_json::Value) -> Result<AssetValue, Str
                 for (k, v) in map {
                     result.insert(k, parse_json_value_to_asset(v)?);
                 }
-                Ok(AssetValue::Table(result))
+                match convert_map_to_surface_set(&result) {
+                    Some(surface) => Ok(AssetValue::Surface(surface)),
+                    None => Ok(AssetValue::Table(result)),
+                }
             }
         }
         _ => Err("Unsupported value type".to_string()),
@@ -315,7 +628,7 @@ mod tests {
     use std::collections::BTreeMap;
 
     fn sample_luau(content: &str) -> BTreeMap<String, AssetValue> {
-        parse_luau_assets_module(content).unwrap()
+        parse_luau_assets_module(content, Path::new("assets.luau")).unwrap()
     }
 
     #[test]
@@ -386,12 +699,223 @@ return {
         }
     }
 
+    #[test]
+    fn parse_luau_array_entries() {
+        let assets = sample_luau(
+            r#"
+local assets = {
+    ["frames/idle.png"] = "rbxassetid://1",
+    frames = {
+        "rbxassetid://2",
+        { id = "rbxassetid://3", width = 16 },
+        label = "walk",
+    },
+}
+return assets
+"#,
+        );
+        let AssetValue::Table(frames) = &assets["frames"] else {
+            panic!("Expected table for frames");
+        };
+        assert_eq!(frames["1"], AssetValue::String("rbxassetid://2".into()));
+        assert!(matches!(&frames["2"], AssetValue::Object(meta) if meta.id == "rbxassetid://3"));
+        assert_eq!(frames["label"], AssetValue::String("walk".into()));
+    }
+
+    #[test]
+    fn parse_luau_folds_constant_ids() {
+        let assets = sample_luau(
+            r#"
+local BASE = "rbxassetid://"
+local PLAY_ID = 123
+local BUTTON = { id = BASE .. "456", width = 64 }
+
+local assets = {
+    ["play.png"] = BASE .. tostring(PLAY_ID),
+    ["button.png"] = BUTTON,
+    ["nested.png"] = (BASE .. PLAY_ID) .. "7",
+}
+return assets
+"#,
+        );
+        assert_eq!(
+            assets["play.png"],
+            AssetValue::String("rbxassetid://123".into())
+        );
+        assert!(
+            matches!(&assets["button.png"], AssetValue::Object(meta) if meta.id == "rbxassetid://456")
+        );
+        assert_eq!(
+            assets["nested.png"],
+            AssetValue::String("rbxassetid://1237".into())
+        );
+
+        let result = parse_luau_assets_module(
+            "local assets = { [\"a.png\"] = MISSING }\nreturn assets",
+            Path::new("assets.luau"),
+        );
+        assert!(result.unwrap_err().contains("MISSING"));
+
+        let result = parse_luau_assets_module(
+            "local assets = { [\"a.png\"] = makeId(1) }\nreturn assets",
+            Path::new("assets.luau"),
+        );
+        assert!(result.unwrap_err().contains("Unsupported expression type"));
+
+        let result = parse_luau_assets_module(
+            "local assets = {\n    [\"a.png\"] = \"rbxassetid://\" .. { id = 1 },\n}\nreturn assets",
+            Path::new("assets.luau"),
+        );
+        let error = result.unwrap_err();
+        assert!(error.contains("error: Only strings and numbers can be joined"));
+        assert!(error.contains("--> assets.luau:2:36"));
+    }
+
+    #[test]
+    fn parse_luau_skips_sibling_requires() {
+        let assets = sample_luau(
+            r#"
+local assets = {
+    ambience = require(script.Parent.ambience),
+    ["main-menu"] = require(script.Parent["main-menu"]),
+    ["play.png"] = "rbxassetid://1",
+}
+return assets
+"#,
+        );
+        assert_eq!(assets.len(), 1);
+        assert_eq!(
+            assets["play.png"],
+            AssetValue::String("rbxassetid://1".into())
+        );
+    }
+
+    #[test]
+    fn parse_luau_typed_module() {
+        let source = r#"--!strict
+export type AssetMeta = { id: string, width: number? }
+
+local assets: { [string]: AssetMeta } = {
+    ["play.png"] = { id = "rbxassetid://1", width = 64 } :: AssetMeta,
+}
+
+return {
+    assets = assets,
+    version = 3,
+    getId = function(key: string): string
+        return assets[key].id
+    end,
+}
+"#;
+        let assets = sample_luau(source);
+        assert!(matches!(&assets["play.png"], AssetValue::Object(meta) if meta.width == Some(64)));
+        let returns = returned_fields(source);
+        assert_eq!(returns[0], "version = 3");
+        assert!(returns[1].starts_with("getId = function(key: string): string"));
+        assert!(returned_fields("return { [\"a.png\"] = \"rbxassetid://1\" }").is_empty());
+    }
+
+    #[test]
+    fn commented_module_fields_are_recognised() {
+        let source = r#"
+return {
+    -- Asset ids by path
+    assets = {
+        ["play.png"] = "rbxassetid://1",
+    },
+    -- Bumped on format changes
+    version = 3,
+}
+"#;
+        assert_eq!(
+            sample_luau(source)["play.png"],
+            AssetValue::String("rbxassetid://1".into())
+        );
+        let returns = returned_fields(source);
+        assert_eq!(returns.len(), 1);
+        assert!(returns[0].ends_with("version = 3"));
+    }
+
+    #[test]
+    fn comments_are_collected_by_key() {
+        let source = r#"
+local assets = {
+    -- Shown on the title screen
+    ["logo.png"] = "rbxassetid://1",
+    -- Menu art
+    ui = {
+        --[[ Old icon, kept for
+             the legacy shop ]]
+        shop = { id = "rbxassetid://2" }, -- not this one
+        play = "rbxassetid://3",
+    },
+}
+return assets
+"#;
+        let comments = module_comments(source);
+        assert_eq!(
+            comments.children["logo.png"].lines,
+            vec!["-- Shown on the title screen"]
+        );
+        let ui = &comments.children["ui"];
+        assert_eq!(ui.lines, vec!["-- Menu art"]);
+        assert_eq!(
+            ui.children["shop"].lines,
+            vec!["--[[ Old icon, kept for\n             the legacy shop ]]"]
+        );
+        assert!(!ui.children.contains_key("play"));
+
+        // The comments stay out of the keys the entries load under.
+        let assets = sample_luau(source);
+        let AssetValue::Table(ui) = &assets["ui"] else {
+            panic!("Expected table");
+        };
+        assert!(ui.contains_key("shop"));
+        assert!(ui.contains_key("play"));
+    }
+
     #[test]
     fn parse_luau_invalid() {
-        let result = parse_luau_assets_module("return { other = \"value\" }");
+        let result =
+            parse_luau_assets_module("return { other = \"value\" }", Path::new("assets.luau"));
         assert!(result.is_err());
     }
 
+    #[test]
+    fn load_toml_and_yaml_manifests() {
+        let dir = std::env::temp_dir().join(format!("truffle-manifest-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let toml_path = dir.join("assets.toml");
+        fs::write(
+            &toml_path,
+            r#"
+[ui]
+"play.png" = "rbxassetid://1"
+
+[ui."shop.png"]
+id = "rbxassetid://2"
+width = 64
+"#,
+        )
+        .unwrap();
+        let yaml_path = dir.join("assets.yaml");
+        fs::write(
+            &yaml_path,
+            "ui:\n  play.png: rbxassetid://1\n  shop.png:\n    id: rbxassetid://2\n    width: 64\n",
+        )
+        .unwrap();
+
+        let from_toml = load_assets(&toml_path).unwrap();
+        let from_yaml = load_assets(&yaml_path).unwrap();
+        fs::remove_dir_all(&dir).ok();
+        assert_eq!(from_toml, from_yaml);
+        let AssetValue::Table(ui) = &from_toml["ui"] else {
+            panic!("Expected table");
+        };
+        assert_eq!(ui["play.png"], AssetValue::String("rbxassetid://1".into()));
+        assert!(matches!(&ui["shop.png"], AssetValue::Object(meta) if meta.width == Some(64)));
+    }
+
     #[test]
     fn parse_json_assets() {
         let assets = parse_json_value(serde_json::json!({
@@ -405,5 +929,11 @@ return {
         } else {
             panic!("Expected table");
         }
+
+        let assets = parse_json_value(serde_json::json!({ "frames": ["a", "b"] })).unwrap();
+        let AssetValue::Table(frames) = &assets["frames"] else {
+            panic!("Expected table");
+        };
+        assert_eq!(frames["2"], AssetValue::String("b".into()));
     }
 }
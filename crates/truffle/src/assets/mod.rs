@@ -1,10 +1,45 @@
+pub mod alpha;
+pub mod animations;
 pub mod atlas;
+pub mod audio;
 pub mod augment;
+pub mod augmenter;
+pub mod colors;
+pub mod diagnostic;
+pub mod downscale;
+pub mod keys;
+pub mod kind;
 pub mod loader;
 pub mod model;
+pub mod nineslice;
 pub mod serialize;
+pub mod sort;
+pub mod surface;
+pub mod template;
+pub mod variants;
 
-pub use atlas::{build_atlased_assets, build_atlases, AtlasExclude, AtlasOptions};
-pub use augment::{augment_assets, FsImageMetadata};
-pub use loader::load_assets;
-pub use serialize::{render_dts_module, render_luau_module};
+pub use alpha::AlphaCoverage;
+pub use animations::{build_animations, AnimationOptions, FLIPBOOK_DTS, FLIPBOOK_LUAU};
+pub use atlas::{
+    atlas_report, build_atlased_assets, build_atlases, pack_rects, sprite_keys_outside_size,
+    stale_atlas_pages, AtlasExclude, AtlasOptions, PackingAlgorithm, PrepackedAtlas,
+    SpritePlacement,
+};
+pub use augment::{augment_assets, CachedImageMetadata, FsImageMetadata, GeneratedImageMetadata};
+pub use augmenter::{enabled_augmenters, run_augmenters, Augmenter};
+pub use colors::ImageColors;
+pub use downscale::DownscaleLimits;
+pub use keys::style_keys;
+pub use kind::non_image_keys;
+pub use loader::{load_assets, module_comments, returned_fields};
+pub use nineslice::NineSlices;
+pub use serialize::{
+    render_dts_module, render_luau_module, render_split_modules, render_ts_module,
+    split_module_names, EntryComments, RenderOptions,
+};
+pub use sort::KeyOrder;
+pub use surface::{merge_surface_sets, surface_texture_keys};
+pub use template::check_template;
+pub use variants::{
+    merge_locale_variants, merge_scale_variants, merge_theme_variants, LocaleOptions, ThemeOptions,
+};
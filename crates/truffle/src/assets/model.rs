@@ -3,14 +3,17 @@ use std::collections::BTreeMap;
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(untagged)]
+#[allow(clippy::large_enum_variant)]
 pub enum AssetValue {
     String(String),
     Number(f64),
+    Bool(bool),
     Object(AssetMeta),
     Table(BTreeMap<String, AssetValue>),
+    Surface(SurfaceSet),
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
 pub struct AssetMeta {
     pub id: String,
     pub width: Option<u32>,
@@ -24,6 +27,29 @@ pub struct AssetMeta {
     pub rect_w: Option<u32>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub rect_h: Option<u32>,
+    /// Whether the sprite is stored rotated 90° clockwise inside its atlas
+    /// rect, so `rect_w`/`rect_h` are its height and width.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rotated: Option<bool>,
+    /// Offset of the packed rect inside the `width`x`height` source when
+    /// transparent margins were trimmed before packing.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub trim_x: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub trim_y: Option<u32>,
+    /// Size of the source file when it was downscaled to `width`x`height`
+    /// before uploading.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source_width: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source_height: Option<u32>,
+    /// Nine-slice center from an `<image>.9.json` sidecar, in pixels of the
+    /// packed image (`ImageLabel.SliceCenter`).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub slice_center: Option<SliceCenter>,
+    /// `ImageLabel.SliceScale` from the same sidecar.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub slice_scale: Option<f64>,
 
     #[serde(skip_serializing_if = "Option::is_none")]
     pub highlight_id: Option<String>,
@@ -36,6 +62,190 @@ pub struct AssetMeta {
     pub highlight_rect_w: Option<u32>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub highlight_rect_h: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub highlight_rotated: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub highlight_trim_x: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub highlight_trim_y: Option<u32>,
+
+    /// Per-locale variants of this asset, keyed by locale name.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub locales: Option<BTreeMap<String, AssetMeta>>,
+
+    /// Per-theme variants of this asset, keyed by theme name.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub variants: Option<BTreeMap<String, AssetMeta>>,
+
+    /// Resolution multiplier of this asset relative to its base (`@2x` → 2).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scale: Option<u32>,
+
+    /// Higher-resolution versions of this asset, keyed by suffix (`2x`).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scales: Option<BTreeMap<String, AssetMeta>>,
+
+    /// Roblox asset type the file was uploaded as (`Decal`), when its
+    /// Asphalt input sets one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub asset_type: Option<String>,
+
+    /// What the asset is when it isn't an image (`audio` or `model`).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub kind: Option<String>,
+
+    /// Length of an audio asset in seconds.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duration: Option<f64>,
+
+    /// Alpha-weighted average of the visible pixels, as `#rrggbb`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub avg_color: Option<String>,
+
+    /// Most common color of the visible pixels, as `#rrggbb`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dominant_color: Option<String>,
+
+    /// Whether every pixel is fully opaque.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub opaque: Option<bool>,
+
+    /// Bounds of the visible pixels, when the image has a transparent margin.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub alpha_bleed: Option<SliceCenter>,
+
+    /// Fields truffle doesn't know (`pivotX`, `category`), carried over from
+    /// a hand-authored module unchanged.
+    #[serde(flatten)]
+    pub extra: BTreeMap<String, AssetValue>,
+}
+
+/// Every field name `convert_map_to_asset_meta` reads, in both spellings.
+const META_FIELDS: &[&str] = &[
+    "id",
+    "width",
+    "height",
+    "rectX",
+    "rect_x",
+    "rectY",
+    "rect_y",
+    "rectW",
+    "rect_w",
+    "rectH",
+    "rect_h",
+    "rotated",
+    "trimX",
+    "trim_x",
+    "trimY",
+    "trim_y",
+    "sourceWidth",
+    "source_width",
+    "sourceHeight",
+    "source_height",
+    "sliceCenter",
+    "slice_center",
+    "sliceScale",
+    "slice_scale",
+    "highlightId",
+    "highlight_id",
+    "highlightRectX",
+    "highlight_rect_x",
+    "highlightRectY",
+    "highlight_rect_y",
+    "highlightRectW",
+    "highlight_rect_w",
+    "highlightRectH",
+    "highlight_rect_h",
+    "highlightRotated",
+    "highlight_rotated",
+    "highlightTrimX",
+    "highlight_trim_x",
+    "highlightTrimY",
+    "highlight_trim_y",
+    "locales",
+    "variants",
+    "scale",
+    "scales",
+    "assetType",
+    "asset_type",
+    "kind",
+    "duration",
+    "avgColor",
+    "avg_color",
+    "dominantColor",
+    "dominant_color",
+    "opaque",
+    "alphaBleed",
+    "alpha_bleed",
+];
+
+/// The corners of a rect in pixels, like Roblox's `Rect`: nine-slice
+/// centers and alpha bounds.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct SliceCenter {
+    pub min_x: u32,
+    pub min_y: u32,
+    pub max_x: u32,
+    pub max_y: u32,
+}
+
+/// The texture maps of one SurfaceAppearance, merged from
+/// `_color`/`_normal`/`_metalness`/`_roughness` images.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct SurfaceSet {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub color_map: Option<AssetMeta>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub normal_map: Option<AssetMeta>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub metalness_map: Option<AssetMeta>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub roughness_map: Option<AssetMeta>,
+}
+
+impl SurfaceSet {
+    /// Luau field names paired with each map that is present.
+    pub fn maps(&self) -> impl Iterator<Item = (&'static str, &AssetMeta)> {
+        [
+            ("colorMap", &self.color_map),
+            ("normalMap", &self.normal_map),
+            ("metalnessMap", &self.metalness_map),
+            ("roughnessMap", &self.roughness_map),
+        ]
+        .into_iter()
+        .filter_map(|(name, map)| map.as_ref().map(|meta| (name, meta)))
+    }
+}
+
+impl AssetMeta {
+    /// Mutable counterpart of [`AssetMeta::nested_variants`].
+    pub fn nested_variants_mut(&mut self) -> impl Iterator<Item = &mut AssetMeta> {
+        self.locales
+            .iter_mut()
+            .chain(self.variants.iter_mut())
+            .chain(self.scales.iter_mut())
+            .flat_map(|map| map.values_mut())
+    }
+
+    /// Iterates over every variant nested directly under this asset.
+    pub fn nested_variants(&self) -> impl Iterator<Item = &AssetMeta> {
+        self.locales
+            .iter()
+            .chain(self.variants.iter())
+            .chain(self.scales.iter())
+            .flat_map(|map| map.values())
+    }
+}
+
+/// The entries of `map` in order when it holds an array: keys `1` to `n`
+/// and nothing else, as the loader reads Luau array entries.
+pub fn array_items(map: &BTreeMap<String, AssetValue>) -> Option<Vec<&AssetValue>> {
+    if map.is_empty() {
+        return None;
+    }
+    (1..=map.len()).map(|i| map.get(&i.to_string())).collect()
 }
 
 pub(crate) fn asset_value_to_string(value: &AssetValue) -> Option<String> {
@@ -56,6 +266,54 @@ pub(crate) fn value_as_u32(value: &AssetValue) -> Option<u32> {
     }
 }
 
+pub(crate) fn value_as_bool(value: &AssetValue) -> Option<bool> {
+    match value {
+        AssetValue::Bool(b) => Some(*b),
+        _ => None,
+    }
+}
+
+pub(crate) fn value_as_f64(value: &AssetValue) -> Option<f64> {
+    match value {
+        AssetValue::Number(n) => Some(*n),
+        AssetValue::String(s) => s.parse::<f64>().ok(),
+        _ => None,
+    }
+}
+
+pub(crate) fn value_as_slice_center(value: &AssetValue) -> Option<SliceCenter> {
+    let AssetValue::Table(map) = value else {
+        return None;
+    };
+    let field = |name: &str| map.get(name).and_then(value_as_u32);
+    Some(SliceCenter {
+        min_x: field("minX")?,
+        min_y: field("minY")?,
+        max_x: field("maxX")?,
+        max_y: field("maxY")?,
+    })
+}
+
+pub(crate) fn value_as_variants(value: &AssetValue) -> Option<BTreeMap<String, AssetMeta>> {
+    let AssetValue::Table(map) = value else {
+        return None;
+    };
+
+    let variants: BTreeMap<String, AssetMeta> = map
+        .iter()
+        .filter_map(|(name, value)| match value {
+            AssetValue::Object(meta) => Some((name.clone(), meta.clone())),
+            _ => None,
+        })
+        .collect();
+
+    if variants.is_empty() {
+        None
+    } else {
+        Some(variants)
+    }
+}
+
 pub(crate) fn convert_map_to_asset_meta(map: &BTreeMap<String, AssetValue>) -> Option<AssetMeta> {
     let id = asset_value_to_string(map.get("id")?)?;
 
@@ -83,6 +341,31 @@ pub(crate) fn convert_map_to_asset_meta(map: &BTreeMap<String, AssetValue>) -> O
         .get("rectH")
         .or_else(|| map.get("rect_h"))
         .and_then(value_as_u32);
+    let rotated = map.get("rotated").and_then(value_as_bool);
+    let trim_x = map
+        .get("trimX")
+        .or_else(|| map.get("trim_x"))
+        .and_then(value_as_u32);
+    let trim_y = map
+        .get("trimY")
+        .or_else(|| map.get("trim_y"))
+        .and_then(value_as_u32);
+    let source_width = map
+        .get("sourceWidth")
+        .or_else(|| map.get("source_width"))
+        .and_then(value_as_u32);
+    let source_height = map
+        .get("sourceHeight")
+        .or_else(|| map.get("source_height"))
+        .and_then(value_as_u32);
+    let slice_center = map
+        .get("sliceCenter")
+        .or_else(|| map.get("slice_center"))
+        .and_then(value_as_slice_center);
+    let slice_scale = map
+        .get("sliceScale")
+        .or_else(|| map.get("slice_scale"))
+        .and_then(value_as_f64);
 
     let highlight_rect_x = map
         .get("highlightRectX")
@@ -100,6 +383,47 @@ pub(crate) fn convert_map_to_asset_meta(map: &BTreeMap<String, AssetValue>) -> O
         .get("highlightRectH")
         .or_else(|| map.get("highlight_rect_h"))
         .and_then(value_as_u32);
+    let highlight_rotated = map
+        .get("highlightRotated")
+        .or_else(|| map.get("highlight_rotated"))
+        .and_then(value_as_bool);
+    let highlight_trim_x = map
+        .get("highlightTrimX")
+        .or_else(|| map.get("highlight_trim_x"))
+        .and_then(value_as_u32);
+    let highlight_trim_y = map
+        .get("highlightTrimY")
+        .or_else(|| map.get("highlight_trim_y"))
+        .and_then(value_as_u32);
+
+    let locales = map.get("locales").and_then(value_as_variants);
+    let variants = map.get("variants").and_then(value_as_variants);
+    let scale = map.get("scale").and_then(value_as_u32);
+    let scales = map.get("scales").and_then(value_as_variants);
+    let asset_type = map
+        .get("assetType")
+        .or_else(|| map.get("asset_type"))
+        .and_then(asset_value_to_string);
+    let kind = map.get("kind").and_then(asset_value_to_string);
+    let duration = map.get("duration").and_then(value_as_f64);
+    let avg_color = map
+        .get("avgColor")
+        .or_else(|| map.get("avg_color"))
+        .and_then(asset_value_to_string);
+    let dominant_color = map
+        .get("dominantColor")
+        .or_else(|| map.get("dominant_color"))
+        .and_then(asset_value_to_string);
+    let opaque = map.get("opaque").and_then(value_as_bool);
+    let alpha_bleed = map
+        .get("alphaBleed")
+        .or_else(|| map.get("alpha_bleed"))
+        .and_then(value_as_slice_center);
+    let extra = map
+        .iter()
+        .filter(|(key, _)| !META_FIELDS.contains(&key.as_str()))
+        .map(|(key, value)| (key.clone(), value.clone()))
+        .collect();
 
     Some(AssetMeta {
         id,
@@ -109,10 +433,74 @@ pub(crate) fn convert_map_to_asset_meta(map: &BTreeMap<String, AssetValue>) -> O
         rect_y,
         rect_w,
         rect_h,
+        rotated,
+        trim_x,
+        trim_y,
+        source_width,
+        source_height,
+        slice_center,
+        slice_scale,
         highlight_id,
         highlight_rect_x,
         highlight_rect_y,
         highlight_rect_w,
         highlight_rect_h,
+        highlight_rotated,
+        highlight_trim_x,
+        highlight_trim_y,
+        locales,
+        variants,
+        scale,
+        scales,
+        asset_type,
+        kind,
+        duration,
+        avg_color,
+        dominant_color,
+        opaque,
+        alpha_bleed,
+        extra,
     })
 }
+
+/// Records `asset_type` on every asset in the tree, clearing a type left over
+/// from an earlier sync when it is `None`.
+pub fn set_asset_type(assets: &mut BTreeMap<String, AssetValue>, asset_type: Option<&str>) {
+    fn set_meta(meta: &mut AssetMeta, asset_type: Option<&str>) {
+        meta.asset_type = asset_type.map(str::to_string);
+        for variant in meta.nested_variants_mut() {
+            set_meta(variant, asset_type);
+        }
+    }
+
+    for node in assets.values_mut() {
+        match node {
+            AssetValue::Object(meta) => set_meta(meta, asset_type),
+            AssetValue::Table(map) => set_asset_type(map, asset_type),
+            _ => {}
+        }
+    }
+}
+
+/// Recognizes a previously generated surface entry (only `*Map` fields holding assets).
+pub(crate) fn convert_map_to_surface_set(map: &BTreeMap<String, AssetValue>) -> Option<SurfaceSet> {
+    if map.is_empty() {
+        return None;
+    }
+
+    let mut surface = SurfaceSet::default();
+    for (key, value) in map {
+        let AssetValue::Object(meta) = value else {
+            return None;
+        };
+        let slot = match key.as_str() {
+            "colorMap" => &mut surface.color_map,
+            "normalMap" => &mut surface.normal_map,
+            "metalnessMap" => &mut surface.metalness_map,
+            "roughnessMap" => &mut surface.roughness_map,
+            _ => return None,
+        };
+        *slot = Some(meta.clone());
+    }
+    Some(surface)
+}
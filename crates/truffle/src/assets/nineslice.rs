@@ -0,0 +1,170 @@
+use super::augmenter::{AssetContext, Augmenter};
+use super::model::{AssetMeta, SliceCenter};
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+
+/// Suffix of the sidecar next to an image (`button.png.9.json`).
+const NINE_SLICE_SUFFIX: &str = ".9.json";
+
+/// Contents of a nine-slice sidecar: the border widths, in pixels of the
+/// source image, that keep their size when the image is stretched.
+#[derive(Debug, Clone, Copy, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct NineSlice {
+    pub left: u32,
+    pub top: u32,
+    pub right: u32,
+    pub bottom: u32,
+    #[serde(default)]
+    pub scale: Option<f64>,
+}
+
+/// Built-in stage filling in `sliceCenter`/`sliceScale` for every image with
+/// a `<image>.9.json` sidecar next to it.
+///
+/// The center is relative to the packed pixels, so trimmed atlas sprites
+/// keep their borders. Rotated sprites can't be nine-sliced and are skipped
+/// with a warning, as are sidecars that don't parse or don't fit the image.
+pub struct NineSlices;
+
+impl Augmenter for NineSlices {
+    fn name(&self) -> &'static str {
+        "nine_slice"
+    }
+
+    fn augment(&self, meta: &mut AssetMeta, asset: &AssetContext) {
+        apply_meta(meta, &sidecar_path(asset.path));
+    }
+}
+
+fn sidecar_path(image: &Path) -> PathBuf {
+    let mut sidecar = image.as_os_str().to_owned();
+    sidecar.push(NINE_SLICE_SUFFIX);
+    PathBuf::from(sidecar)
+}
+
+fn apply_meta(meta: &mut AssetMeta, sidecar: &Path) {
+    let Ok(content) = std::fs::read_to_string(sidecar) else {
+        return;
+    };
+    let slice = match serde_json::from_str::<NineSlice>(&content) {
+        Ok(slice) => slice,
+        Err(e) => {
+            println!(
+                "[sync] WARN: Failed to parse {}: {} – skipping nine-slice.",
+                sidecar.display(),
+                e
+            );
+            return;
+        }
+    };
+    if meta.rotated == Some(true) {
+        println!(
+            "[sync] WARN: {} belongs to a rotated atlas sprite – skipping nine-slice.",
+            sidecar.display()
+        );
+        return;
+    }
+    match slice_center(meta, &slice) {
+        Some(center) => {
+            meta.slice_center = Some(center);
+            meta.slice_scale = slice.scale;
+        }
+        None => println!(
+            "[sync] WARN: {} insets don't fit the {}x{} image – skipping nine-slice.",
+            sidecar.display(),
+            meta.width.unwrap_or(0),
+            meta.height.unwrap_or(0)
+        ),
+    }
+}
+
+/// The center rect of `slice` within the packed pixels of `meta`, or `None`
+/// when the insets leave no center.
+fn slice_center(meta: &AssetMeta, slice: &NineSlice) -> Option<SliceCenter> {
+    let (width, height) = (meta.width?, meta.height?);
+    if slice.left + slice.right >= width || slice.top + slice.bottom >= height {
+        return None;
+    }
+
+    // Trimmed sprites only pack `rectW`x`rectH` pixels from `trimX`,`trimY`.
+    let (offset_x, offset_y) = (meta.trim_x.unwrap_or(0), meta.trim_y.unwrap_or(0));
+    let (packed_w, packed_h) = match (meta.trim_x, meta.rect_w, meta.rect_h) {
+        (Some(_), Some(w), Some(h)) => (w, h),
+        _ => (width, height),
+    };
+    let clamp = |value: u32, offset: u32, size: u32| value.saturating_sub(offset).min(size);
+    Some(SliceCenter {
+        min_x: clamp(slice.left, offset_x, packed_w),
+        min_y: clamp(slice.top, offset_y, packed_h),
+        max_x: clamp(width - slice.right, offset_x, packed_w),
+        max_y: clamp(height - slice.bottom, offset_y, packed_h),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assets::augmenter::run_augmenters;
+    use crate::assets::model::AssetValue;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn reads_sidecars_relative_to_packed_pixels() {
+        let dir = std::env::temp_dir().join(format!("truffle-nineslice-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("ui")).unwrap();
+        std::fs::write(
+            dir.join("ui/button.png.9.json"),
+            r#"{ "left": 8, "top": 6, "right": 8, "bottom": 6, "scale": 0.5 }"#,
+        )
+        .unwrap();
+        std::fs::write(dir.join("ui/panel.png.9.json"), r#"{ "left": 40 }"#).unwrap();
+
+        let button = AssetMeta {
+            id: "rbxassetid://1".into(),
+            width: Some(64),
+            height: Some(32),
+            ..Default::default()
+        };
+        let trimmed = AssetMeta {
+            rect_w: Some(60),
+            rect_h: Some(32),
+            trim_x: Some(2),
+            trim_y: Some(0),
+            ..button.clone()
+        };
+        let mut assets = BTreeMap::from([(
+            "ui".to_string(),
+            AssetValue::Table(BTreeMap::from([
+                ("button.png".to_string(), AssetValue::Object(trimmed)),
+                ("panel.png".to_string(), AssetValue::Object(button.clone())),
+                ("icon.png".to_string(), AssetValue::Object(button)),
+            ])),
+        )]);
+        run_augmenters(&mut assets, &dir, "", &[&NineSlices]);
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let AssetValue::Table(ui) = &assets["ui"] else {
+            panic!("expected a table");
+        };
+        let AssetValue::Object(button) = &ui["button.png"] else {
+            panic!("expected an asset");
+        };
+        assert_eq!(
+            button.slice_center,
+            Some(SliceCenter {
+                min_x: 6,
+                min_y: 6,
+                max_x: 54,
+                max_y: 26
+            })
+        );
+        assert_eq!(button.slice_scale, Some(0.5));
+        for key in ["panel.png", "icon.png"] {
+            let AssetValue::Object(meta) = &ui[key] else {
+                panic!("expected an asset");
+            };
+            assert_eq!(meta.slice_center, None);
+        }
+    }
+}
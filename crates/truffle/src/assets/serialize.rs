@@ -1,113 +1,947 @@
-use super::model::AssetValue;
-use std::collections::BTreeMap;
+use super::animations::Animation;
+use super::model::{array_items, AssetMeta, AssetValue, SliceCenter};
+use super::sort::KeyOrder;
+use super::template::fill_template;
+use std::collections::{BTreeMap, BTreeSet};
+
+const LUAU_HEADER: &str = "-- This file is automatically @generated by truffle.\n\
+                           -- DO NOT EDIT MANUALLY.\n";
+const DTS_HEADER: &str = "// This file is automatically @generated by truffle.\n\
+                          // DO NOT EDIT MANUALLY.\n";
+
+/// Options shared by the Luau and TypeScript renderers.
+#[derive(Debug, Clone, Default)]
+pub struct RenderOptions {
+    pub key_order: KeyOrder,
+    /// Declare `export type`s in the Luau module and annotate its tables.
+    pub luau_types: bool,
+    /// Template the Luau module is filled into instead of the built-in
+    /// layout; see [`PLACEHOLDERS`](super::template::PLACEHOLDERS).
+    pub luau_template: Option<String>,
+    /// Template for the TypeScript declaration, like `luau_template`.
+    pub dts_template: Option<String>,
+    /// Fields the Luau module returns besides `assets` and `animations`, as
+    /// written (`version = 3`).
+    pub returns: Vec<String>,
+    /// Comments to write above entries of the Luau module.
+    pub comments: EntryComments,
+}
+
+/// Comments written above the entries of a hand-edited Luau module, by key,
+/// so rewrites keep them next to the same entries.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EntryComments {
+    /// The comments above this entry, as written (`-- Shown on the title screen`).
+    pub lines: Vec<String>,
+    pub children: BTreeMap<String, EntryComments>,
+}
+
+impl EntryComments {
+    pub fn is_empty(&self) -> bool {
+        self.lines.is_empty() && self.children.is_empty()
+    }
+}
+
+/// The comments of entry `key` in `comments`, if any.
+fn entry_comments<'a>(comments: Option<&'a EntryComments>, key: &str) -> Option<&'a EntryComments> {
+    comments.and_then(|comments| comments.children.get(key))
+}
+
+/// Pushes the comment lines of entry `key` onto `parts` at `indent`.
+fn push_comments(
+    comments: Option<&EntryComments>,
+    key: &str,
+    indent: &str,
+    parts: &mut Vec<String>,
+) {
+    for line in entry_comments(comments, key)
+        .into_iter()
+        .flat_map(|entry| &entry.lines)
+    {
+        parts.push(format!("{}{}", indent, line));
+    }
+}
+
+/// One top-level folder of a split assets module, required by the index.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SplitModule {
+    pub name: String,
+    pub luau: String,
+    pub dts: String,
+}
+
+pub fn render_luau_module(
+    assets: &BTreeMap<String, AssetValue>,
+    animations: &BTreeMap<String, Animation>,
+    options: &RenderOptions,
+) -> String {
+    render_luau_index(assets, animations, options, &[])
+}
+
+pub fn render_dts_module(
+    assets: &BTreeMap<String, AssetValue>,
+    animations: &BTreeMap<String, Animation>,
+    options: &RenderOptions,
+) -> String {
+    render_dts_index(assets, animations, options, &[])
+}
+
+/// Renders the assets as a TypeScript module of `as const` literals, so
+/// importers get every id as a literal string type.
+pub fn render_ts_module(
+    assets: &BTreeMap<String, AssetValue>,
+    animations: &BTreeMap<String, Animation>,
+    options: &RenderOptions,
+) -> String {
+    let order = options.key_order;
+    let table = serialize_value(
+        &AssetValue::Table(assets.clone()),
+        0,
+        order,
+        Syntax::TypeScript,
+        None,
+    );
+    let mut output = format!(
+        "// This file is automatically @generated by truffle.\n\
+         // DO NOT EDIT MANUALLY.\n\n\
+         export const assets = {} as const;\n",
+        table.trim_end()
+    );
+    if !animations.is_empty() {
+        output.push_str(&format!(
+            "\nexport const animations = {} as const;\n",
+            serialize_animations(animations, order, Syntax::TypeScript)
+        ));
+    }
+    output
+}
+
+/// Renders the modules with every top-level folder of `assets` moved to a
+/// module of its own next to the index (`ui.luau` and `ui.d.ts`). The
+/// index, named `index_name`, requires them back into the same table.
+/// Loose assets, and folders whose name can't be a module, stay inline.
+pub fn render_split_modules(
+    assets: &BTreeMap<String, AssetValue>,
+    animations: &BTreeMap<String, Animation>,
+    options: &RenderOptions,
+    index_name: &str,
+) -> (String, String, Vec<SplitModule>) {
+    let order = options.key_order;
+    let split: Vec<String> = assets
+        .iter()
+        .filter(|(name, value)| {
+            matches!(value, AssetValue::Table(_)) && is_module_name(name, index_name)
+        })
+        .map(|(name, _)| name.clone())
+        .collect();
+    let meta = meta_types(assets);
+
+    let modules = split
+        .iter()
+        .map(|name| {
+            let AssetValue::Table(map) = &assets[name] else {
+                unreachable!("only folders are split");
+            };
+            let value = AssetValue::Table(map.clone());
+            let comments = entry_comments(Some(&options.comments), name);
+            let surfaces = has_surfaces(map);
+            let types = if surfaces {
+                "AssetMeta, AssetSurface"
+            } else {
+                "AssetMeta"
+            };
+            // Luau can't import types without requiring the index, which
+            // requires this module, so the types are declared again.
+            let luau = if options.luau_types {
+                format!(
+                    "-- This file is automatically @generated by truffle.\n\
+                     -- DO NOT EDIT MANUALLY.\n\n\
+                     {}\
+                     local assets: {} = {}\n\
+                     return assets\n",
+                    luau_type_prelude(&meta, surfaces, false, order),
+                    serialize_luau_type(&value, 0, order, &[]),
+                    serialize_luau(&value, 0, order, comments)
+                )
+            } else {
+                format!(
+                    "-- This file is automatically @generated by truffle.\n\
+                     -- DO NOT EDIT MANUALLY.\n\n\
+                     local assets = {}\n\
+                     return assets\n",
+                    serialize_luau(&value, 0, order, comments)
+                )
+            };
+            SplitModule {
+                name: name.clone(),
+                luau,
+                dts: format!(
+                    "// This file is automatically @generated by truffle.\n\
+                     // DO NOT EDIT MANUALLY.\n\n\
+                     import type {{ {} }} from \"./{}\";\n\n\
+                     declare const assets: {}\n\n\
+                     export = assets;\n",
+                    types,
+                    index_name,
+                    serialize_dts(&value, 0, order)
+                ),
+            }
+        })
+        .collect();
+
+    (
+        render_luau_index(assets, animations, options, &split),
+        render_dts_index(assets, animations, options, &split),
+        modules,
+    )
+}
+
+/// Names of the modules an index rendered by [`render_split_modules`]
+/// requires, read back from its source.
+pub fn split_module_names(index: &str) -> Vec<String> {
+    index
+        .lines()
+        // Requires in the returned table are hand-written, not folders.
+        .take_while(|line| !line.starts_with("return "))
+        .filter_map(|line| {
+            let access = line
+                .strip_prefix('\t')?
+                .split_once(" = require(script.Parent")?
+                .1
+                .strip_suffix("),")?;
+            match access.strip_prefix('.') {
+                Some(name) => Some(name.to_string()),
+                None => serde_json::from_str(access.strip_prefix('[')?.strip_suffix(']')?).ok(),
+            }
+        })
+        .collect()
+}
+
+/// Whether a folder can be written as `<name>.luau` next to the index
+/// without clashing with it or with Rojo's `init`.
+fn is_module_name(name: &str, index_name: &str) -> bool {
+    !name.is_empty()
+        && !name.starts_with('.')
+        && name != index_name
+        && !["init", "index"].contains(&name)
+        && !name.contains(['/', '\\', ':', '*', '?', '"', '<', '>', '|'])
+}
+
+/// `script.Parent.name`, or `script.Parent["name"]` when it isn't an
+/// identifier.
+fn split_module_ref(name: &str) -> String {
+    if is_simple_identifier(name) {
+        format!("script.Parent.{}", name)
+    } else {
+        format!("script.Parent[{}]", serde_json::to_string(name).unwrap())
+    }
+}
+
+fn render_luau_index(
+    assets: &BTreeMap<String, AssetValue>,
+    animations: &BTreeMap<String, Animation>,
+    options: &RenderOptions,
+    split: &[String],
+) -> String {
+    let order = options.key_order;
+    let mut parts = vec!["{".to_string()];
+    match array_items(assets) {
+        Some(items) if split.is_empty() => {
+            for item in items {
+                parts.push(format!("\t{},", serialize_luau(item, 1, order, None)));
+            }
+        }
+        _ => {
+            let comments = Some(&options.comments);
+            for key in order.sorted(assets.keys()) {
+                push_comments(comments, key, "\t", &mut parts);
+                let value = if split.contains(key) {
+                    format!("require({})", split_module_ref(key))
+                } else {
+                    serialize_luau(&assets[key], 1, order, entry_comments(comments, key))
+                };
+                parts.push(format!("\t{} = {},", luau_key(key), value));
+            }
+        }
+    }
+    parts.push("}\n".to_string());
+    let table = parts.join("\n");
+
+    let prelude = if options.luau_types {
+        format!(
+            "{}export type Assets = {}\n\n",
+            luau_type_prelude(
+                &meta_types(assets),
+                has_surfaces(assets),
+                !animations.is_empty(),
+                order
+            ),
+            serialize_luau_type(&AssetValue::Table(assets.clone()), 0, order, split)
+        )
+    } else {
+        String::new()
+    };
+
+    if let Some(template) = &options.luau_template {
+        let animations = if animations.is_empty() {
+            "{}".to_string()
+        } else {
+            serialize_animations(animations, order, Syntax::Luau)
+        };
+        return fill_template(
+            template,
+            &[
+                ("header", LUAU_HEADER),
+                ("types", &prelude),
+                ("assets", &table),
+                ("animations", &animations),
+            ],
+        );
+    }
+
+    let (assets_decl, animations_decl) = if options.luau_types {
+        (
+            "local assets: Assets",
+            "local animations: { [string]: AssetAnimation }",
+        )
+    } else {
+        ("local assets", "local animations")
+    };
+
+    let mut returns = vec!["assets = assets"];
+    if !animations.is_empty() {
+        returns.push("animations = animations");
+    }
+    returns.extend(options.returns.iter().map(String::as_str));
+    let returns = returns.join(",\n\t");
+
+    if animations.is_empty() {
+        return format!(
+            "{}\n\
+             {}\
+             {} = {}\n\
+             return {{\n\
+             \t{}\n\
+             }}\n",
+            LUAU_HEADER, prelude, assets_decl, table, returns
+        );
+    }
 
-pub fn render_luau_module(assets: &BTreeMap<String, AssetValue>) -> String {
     format!(
-        "-- This file is automatically @generated by truffle.\n\
-         -- DO NOT EDIT MANUALLY.\n\n\
-         local assets = {}\n\
+        "{}\n\
+         {}\
+         {} = {}\n\
+         {} = {}\n\n\
          return {{\n\
-         \tassets = assets\n\
+         \t{}\n\
          }}\n",
-        serialize_luau(&AssetValue::Table(assets.clone()), 0)
+        LUAU_HEADER,
+        prelude,
+        assets_decl,
+        table,
+        animations_decl,
+        serialize_animations(animations, order, Syntax::Luau),
+        returns
     )
 }
 
-pub fn render_dts_module(assets: &BTreeMap<String, AssetValue>) -> String {
-    format!(
-        "// This file is automatically @generated by truffle.\n\
-         // DO NOT EDIT MANUALLY.\n\n\
+/// The type of a field of the generated `AssetMeta` type.
+enum FieldType {
+    Number,
+    Boolean,
+    String,
+    /// A union of string literals.
+    Literals(BTreeSet<String>),
+    /// Variants keyed by the names in the given alias.
+    Variants(&'static str),
+    Rect,
+    /// A union of primitive types, for unknown fields.
+    Primitives(BTreeSet<&'static str>),
+    Unknown,
+}
+
+impl FieldType {
+    fn dts(&self, order: KeyOrder) -> String {
+        match self {
+            Self::Number => "number".to_string(),
+            Self::Boolean => "boolean".to_string(),
+            Self::String => "string".to_string(),
+            Self::Literals(names) => dts_string_union(names, order),
+            Self::Variants(alias) => format!("Partial<Record<{}, AssetMeta>>", alias),
+            Self::Rect => "{ minX: number; minY: number; maxX: number; maxY: number }".to_string(),
+            Self::Primitives(types) => types.iter().copied().collect::<Vec<_>>().join(" | "),
+            Self::Unknown => "unknown".to_string(),
+        }
+    }
+
+    fn luau(&self, order: KeyOrder) -> String {
+        match self {
+            Self::Variants(alias) => format!("{{ [{}]: AssetMeta }}", alias),
+            Self::Rect => "{ minX: number, minY: number, maxX: number, maxY: number }".to_string(),
+            Self::Unknown => "any".to_string(),
+            _ => self.dts(order),
+        }
+    }
+}
+
+struct MetaField {
+    name: String,
+    ty: FieldType,
+    optional: bool,
+}
+
+/// The `AssetMeta` type both declarations describe: aliases for the variant
+/// names in use, then the fields of every asset in the tree.
+struct MetaTypes {
+    aliases: Vec<(&'static str, BTreeSet<String>)>,
+    fields: Vec<MetaField>,
+}
+
+fn meta_types(assets: &BTreeMap<String, AssetValue>) -> MetaTypes {
+    let locales = collect_variant_names(assets, |meta| meta.locales.as_ref());
+    let themes = collect_variant_names(assets, |meta| meta.variants.as_ref());
+    let scales = collect_variant_names(assets, |meta| meta.scales.as_ref());
+    let asset_types = collect_meta_names(assets, &|meta| meta.asset_type.as_ref());
+    let kinds = collect_meta_names(assets, &|meta| meta.kind.as_ref());
+
+    let mut fields = Vec::new();
+    let mut field = |name: &str, ty: FieldType, optional: bool| {
+        fields.push(MetaField {
+            name: name.to_string(),
+            ty,
+            optional,
+        })
+    };
+    field("id", FieldType::String, false);
+    // Audio and other non-image entries have no size.
+    field("width", FieldType::Number, !kinds.is_empty());
+    field("height", FieldType::Number, !kinds.is_empty());
+    for name in [
+        "rectX",
+        "rectY",
+        "rectW",
+        "rectH",
+        "highlightId",
+        "highlightRectX",
+        "highlightRectY",
+        "highlightRectW",
+        "highlightRectH",
+    ] {
+        let ty = if name == "highlightId" {
+            FieldType::String
+        } else {
+            FieldType::Number
+        };
+        field(name, ty, true);
+    }
+
+    let mut aliases = Vec::new();
+    if !locales.is_empty() {
+        field("locales", FieldType::Variants("AssetLocale"), true);
+        aliases.push(("AssetLocale", locales));
+    }
+    if !themes.is_empty() {
+        field("variants", FieldType::Variants("AssetTheme"), true);
+        aliases.push(("AssetTheme", themes));
+    }
+    if !scales.is_empty() {
+        field("scale", FieldType::Number, true);
+        field("scales", FieldType::Variants("AssetScale"), true);
+        aliases.push(("AssetScale", scales));
+    }
+    if any_meta(assets, &|meta| {
+        meta.rotated.is_some() || meta.highlight_rotated.is_some()
+    }) {
+        field("rotated", FieldType::Boolean, true);
+        field("highlightRotated", FieldType::Boolean, true);
+    }
+    if any_meta(assets, &|meta| {
+        meta.trim_x.is_some() || meta.highlight_trim_x.is_some()
+    }) {
+        for name in ["trimX", "trimY", "highlightTrimX", "highlightTrimY"] {
+            field(name, FieldType::Number, true);
+        }
+    }
+    if any_meta(assets, &|meta| meta.source_width.is_some()) {
+        field("sourceWidth", FieldType::Number, true);
+        field("sourceHeight", FieldType::Number, true);
+    }
+    if any_meta(assets, &|meta| meta.slice_center.is_some()) {
+        field("sliceCenter", FieldType::Rect, true);
+        field("sliceScale", FieldType::Number, true);
+    }
+    if !asset_types.is_empty() {
+        field("assetType", FieldType::Literals(asset_types), true);
+    }
+    if !kinds.is_empty() {
+        field("kind", FieldType::Literals(kinds), true);
+    }
+    if any_meta(assets, &|meta| meta.duration.is_some()) {
+        field("duration", FieldType::Number, true);
+    }
+    if any_meta(assets, &|meta| meta.avg_color.is_some()) {
+        field("avgColor", FieldType::String, true);
+        field("dominantColor", FieldType::String, true);
+    }
+    if any_meta(assets, &|meta| meta.opaque.is_some()) {
+        field("opaque", FieldType::Boolean, true);
+        field("alphaBleed", FieldType::Rect, true);
+    }
+    for (name, types) in collect_extra_fields(assets) {
+        let ty = if types.contains("unknown") {
+            FieldType::Unknown
+        } else {
+            FieldType::Primitives(types)
+        };
+        field(&name, ty, true);
+    }
+
+    MetaTypes { aliases, fields }
+}
+
+/// The `export type` declarations a typed Luau module starts with.
+fn luau_type_prelude(
+    meta: &MetaTypes,
+    surfaces: bool,
+    animations: bool,
+    order: KeyOrder,
+) -> String {
+    let mut out = String::new();
+    for (alias, names) in &meta.aliases {
+        out.push_str(&format!(
+            "export type {} = {}\n\n",
+            alias,
+            dts_string_union(names, order)
+        ));
+    }
+    out.push_str("export type AssetMeta = {\n");
+    for field in &meta.fields {
+        let mut ty = field.ty.luau(order);
+        if field.optional {
+            ty = if ty.contains('|') {
+                format!("({})?", ty)
+            } else {
+                format!("{}?", ty)
+            };
+        }
+        out.push_str(&format!("\t{}: {},\n", luau_key(&field.name), ty));
+    }
+    out.push_str("}\n\n");
+    if surfaces {
+        out.push_str(
+            "export type AssetSurface = {\n\
+             \tcolorMap: AssetMeta?,\n\
+             \tnormalMap: AssetMeta?,\n\
+             \tmetalnessMap: AssetMeta?,\n\
+             \troughnessMap: AssetMeta?,\n\
+             }\n\n",
+        );
+    }
+    if animations {
+        out.push_str(
+            "export type AssetAnimationLoop = \"forward\" | \"reverse\" | \"pingpong\" | \"pingpong_reverse\"\n\n\
+             export type AssetAnimationFrame = {\n\
+             \tasset: AssetMeta,\n\
+             \tduration: number,\n\
+             }\n\n\
+             export type AssetAnimation = {\n\
+             \tloop: AssetAnimationLoop,\n\
+             \trepeatCount: number,\n\
+             \tframes: { AssetAnimationFrame },\n\
+             }\n\n",
+        );
+    }
+    out
+}
+
+/// The Luau type of `value`, with the folders in `split` typed by their
+/// module.
+fn serialize_luau_type(
+    value: &AssetValue,
+    indent: usize,
+    order: KeyOrder,
+    split: &[String],
+) -> String {
+    let indent_str = "\t".repeat(indent);
+    match value {
+        AssetValue::String(_) => "string".to_string(),
+        AssetValue::Number(_) => "number".to_string(),
+        AssetValue::Bool(_) => "boolean".to_string(),
+        AssetValue::Object(_) => "AssetMeta".to_string(),
+        AssetValue::Surface(_) => "AssetSurface".to_string(),
+        AssetValue::Table(map) => {
+            if let Some(items) = array_items(map).filter(|_| split.is_empty()) {
+                let types: BTreeSet<String> = items
+                    .iter()
+                    .map(|item| serialize_luau_type(item, indent, order, &[]))
+                    .collect();
+                return match types.into_iter().collect::<Vec<_>>().as_slice() {
+                    [ty] => format!("{{ {} }}", ty),
+                    _ => "{ any }".to_string(),
+                };
+            }
+            let mut parts = vec!["{".to_string()];
+            for key in order.sorted(map.keys()) {
+                let ty = if indent == 0 && split.contains(key) {
+                    format!("typeof(require({}))", split_module_ref(key))
+                } else {
+                    serialize_luau_type(&map[key], indent + 1, order, &[])
+                };
+                parts.push(format!("{}\t{}: {},", indent_str, luau_key(key), ty));
+            }
+            parts.push(format!("{}}}", indent_str));
+            parts.join("\n")
+        }
+    }
+}
+
+/// The index declaration. `AssetMeta` covers the fields of every asset,
+/// split modules included, since they import it from here.
+fn render_dts_index(
+    assets: &BTreeMap<String, AssetValue>,
+    animations: &BTreeMap<String, Animation>,
+    options: &RenderOptions,
+    split: &[String],
+) -> String {
+    let order = options.key_order;
+    let meta = meta_types(assets);
+
+    let mut type_aliases = String::new();
+    for (alias, names) in &meta.aliases {
+        type_aliases.push_str(&format!(
+            "export type {} = {};\n\n",
+            alias,
+            dts_string_union(names, order)
+        ));
+    }
+    let mut meta_fields = String::new();
+    for field in &meta.fields {
+        meta_fields.push_str(&format!(
+            "\t{}{}: {};\n",
+            dts_key(&field.name),
+            if field.optional { "?" } else { "" },
+            field.ty.dts(order)
+        ));
+    }
+
+    let mut extra_types = String::new();
+    if has_surfaces(assets) {
+        extra_types.push_str(
+            "export interface AssetSurface {\n\
+             \tcolorMap?: AssetMeta;\n\
+             \tnormalMap?: AssetMeta;\n\
+             \tmetalnessMap?: AssetMeta;\n\
+             \troughnessMap?: AssetMeta;\n\
+             }\n\n",
+        );
+    }
+
+    let mut animations_type = "{}".to_string();
+    let mut animation_decl = String::new();
+    let mut exports = "assets";
+    if !animations.is_empty() {
+        extra_types.push_str(
+            "export type AssetAnimationLoop = \"forward\" | \"reverse\" | \"pingpong\" | \"pingpong_reverse\";\n\n\
+             export interface AssetAnimationFrame {\n\
+             \tasset: AssetMeta;\n\
+             \tduration: number;\n\
+             }\n\n\
+             export interface AssetAnimation {\n\
+             \tloop: AssetAnimationLoop;\n\
+             \trepeatCount: number;\n\
+             \tframes: AssetAnimationFrame[];\n\
+             }\n\n",
+        );
+        animations_type = "{\n".to_string();
+        for key in order.sorted(animations.keys()) {
+            animations_type.push_str(&format!(
+                "    {}: AssetAnimation;\n",
+                serde_json::to_string(key).unwrap()
+            ));
+        }
+        animations_type.push('}');
+        animation_decl = format!("declare const animations: {}\n\n", animations_type);
+        exports = "assets, animations";
+    }
+
+    let types = format!(
+        "{}\
          export interface AssetMeta {{\n\
-         \tid: string;\n\
-         \twidth: number;\n\
-         \theight: number;\n\
-         \trectX?: number;\n\
-         \trectY?: number;\n\
-         \trectW?: number;\n\
-         \trectH?: number;\n\
-         \thighlightId?: string;\n\
-         \thighlightRectX?: number;\n\
-         \thighlightRectY?: number;\n\
-         \thighlightRectW?: number;\n\
-         \thighlightRectH?: number;\n\
+         {}\
          }}\n\n\
+         {}",
+        type_aliases, meta_fields, extra_types
+    );
+    let assets_type = dts_assets_type(assets, split, order);
+
+    if let Some(template) = &options.dts_template {
+        return fill_template(
+            template,
+            &[
+                ("header", DTS_HEADER),
+                ("types", &types),
+                ("assets", &assets_type),
+                ("animations", &animations_type),
+            ],
+        );
+    }
+
+    format!(
+        "{}\n\
+         {}\
          declare const assets: {}\n\n\
-         export {{ assets }};\n",
-        serialize_dts(&AssetValue::Table(assets.clone()), 0)
+         {}\
+         export {{ {} }};\n",
+        DTS_HEADER, types, assets_type, animation_decl, exports
     )
 }
 
-fn serialize_luau(value: &AssetValue, indent: usize) -> String {
+/// The type of the `assets` table, with split folders typed by their module.
+fn dts_assets_type(
+    assets: &BTreeMap<String, AssetValue>,
+    split: &[String],
+    order: KeyOrder,
+) -> String {
+    let inline: BTreeMap<String, AssetValue> = assets
+        .iter()
+        .filter(|(name, _)| !split.contains(name))
+        .map(|(name, value)| (name.clone(), value.clone()))
+        .collect();
+    let mut declaration = serialize_dts(&AssetValue::Table(inline), 0, order);
+    if split.is_empty() {
+        return declaration;
+    }
+    declaration.pop();
+    for name in order.sorted(split) {
+        declaration.push_str(&format!(
+            "    {}: typeof import({});\n",
+            dts_key(name),
+            serde_json::to_string(&format!("./{}", name)).unwrap()
+        ));
+    }
+    declaration.push('}');
+    declaration
+}
+
+fn has_surfaces(assets: &BTreeMap<String, AssetValue>) -> bool {
+    assets.values().any(|node| match node {
+        AssetValue::Surface(_) => true,
+        AssetValue::Table(map) => has_surfaces(map),
+        _ => false,
+    })
+}
+
+fn collect_variant_names(
+    assets: &BTreeMap<String, AssetValue>,
+    variants: fn(&AssetMeta) -> Option<&BTreeMap<String, AssetMeta>>,
+) -> BTreeSet<String> {
+    fn walk_meta(
+        meta: &AssetMeta,
+        variants: fn(&AssetMeta) -> Option<&BTreeMap<String, AssetMeta>>,
+        out: &mut BTreeSet<String>,
+    ) {
+        if let Some(map) = variants(meta) {
+            out.extend(map.keys().cloned());
+        }
+        for nested in meta.nested_variants() {
+            walk_meta(nested, variants, out);
+        }
+    }
+
+    fn walk(
+        node: &AssetValue,
+        variants: fn(&AssetMeta) -> Option<&BTreeMap<String, AssetMeta>>,
+        out: &mut BTreeSet<String>,
+    ) {
+        match node {
+            AssetValue::Object(meta) => walk_meta(meta, variants, out),
+            AssetValue::Table(map) => {
+                for child in map.values() {
+                    walk(child, variants, out);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let mut out = BTreeSet::new();
+    for node in assets.values() {
+        walk(node, variants, &mut out);
+    }
+    out
+}
+
+/// Values of a string `field` (asset type, kind) recorded on any asset,
+/// including variants.
+fn collect_meta_names(
+    assets: &BTreeMap<String, AssetValue>,
+    field: &dyn Fn(&AssetMeta) -> Option<&String>,
+) -> BTreeSet<String> {
+    fn walk_meta(
+        meta: &AssetMeta,
+        field: &dyn Fn(&AssetMeta) -> Option<&String>,
+        out: &mut BTreeSet<String>,
+    ) {
+        out.extend(field(meta).cloned());
+        for nested in meta.nested_variants() {
+            walk_meta(nested, field, out);
+        }
+    }
+
+    let mut out = BTreeSet::new();
+    let mut stack: Vec<&AssetValue> = assets.values().collect();
+    while let Some(node) = stack.pop() {
+        match node {
+            AssetValue::Object(meta) => walk_meta(meta, field, &mut out),
+            AssetValue::Surface(surface) => {
+                for (_, meta) in surface.maps() {
+                    walk_meta(meta, field, &mut out);
+                }
+            }
+            AssetValue::Table(map) => stack.extend(map.values()),
+            _ => {}
+        }
+    }
+    out
+}
+
+/// Names of the unknown fields carried on any asset, with the TypeScript
+/// types of their values.
+fn collect_extra_fields(
+    assets: &BTreeMap<String, AssetValue>,
+) -> BTreeMap<String, BTreeSet<&'static str>> {
+    fn walk_meta(meta: &AssetMeta, out: &mut BTreeMap<String, BTreeSet<&'static str>>) {
+        for (name, value) in &meta.extra {
+            let ty = match value {
+                AssetValue::String(_) => "string",
+                AssetValue::Number(_) => "number",
+                AssetValue::Bool(_) => "boolean",
+                _ => "unknown",
+            };
+            out.entry(name.clone()).or_default().insert(ty);
+        }
+        for nested in meta.nested_variants() {
+            walk_meta(nested, out);
+        }
+    }
+
+    let mut out = BTreeMap::new();
+    let mut stack: Vec<&AssetValue> = assets.values().collect();
+    while let Some(node) = stack.pop() {
+        match node {
+            AssetValue::Object(meta) => walk_meta(meta, &mut out),
+            AssetValue::Surface(surface) => {
+                for (_, meta) in surface.maps() {
+                    walk_meta(meta, &mut out);
+                }
+            }
+            AssetValue::Table(map) => stack.extend(map.values()),
+            _ => {}
+        }
+    }
+    out
+}
+
+/// Whether `check` holds for any asset or nested variant in the tree.
+fn any_meta(assets: &BTreeMap<String, AssetValue>, check: &dyn Fn(&AssetMeta) -> bool) -> bool {
+    fn walk_meta(meta: &AssetMeta, check: &dyn Fn(&AssetMeta) -> bool) -> bool {
+        check(meta)
+            || meta
+                .nested_variants()
+                .any(|nested| walk_meta(nested, check))
+    }
+
+    assets.values().any(|node| match node {
+        AssetValue::Object(meta) => walk_meta(meta, check),
+        AssetValue::Table(map) => any_meta(map, check),
+        _ => false,
+    })
+}
+
+fn dts_string_union(names: &BTreeSet<String>, order: KeyOrder) -> String {
+    order
+        .sorted(names)
+        .into_iter()
+        .map(|name| serde_json::to_string(name).unwrap())
+        .collect::<Vec<_>>()
+        .join(" | ")
+}
+
+fn serialize_luau(
+    value: &AssetValue,
+    indent: usize,
+    order: KeyOrder,
+    comments: Option<&EntryComments>,
+) -> String {
+    serialize_value(value, indent, order, Syntax::Luau, comments)
+}
+
+fn serialize_value(
+    value: &AssetValue,
+    indent: usize,
+    order: KeyOrder,
+    syntax: Syntax,
+    comments: Option<&EntryComments>,
+) -> String {
     let indent_str = "\t".repeat(indent);
     let inner_indent = format!("{}\t", indent_str);
     let first_level = indent == 0;
 
     match value {
-        AssetValue::String(s) => format!("\"{}\"", s.replace('"', "\\\"")),
-        AssetValue::Number(n) => n.to_string(),
+        AssetValue::String(s) => syntax.string(s),
+        AssetValue::Number(n) => syntax.number(*n),
+        AssetValue::Bool(b) => b.to_string(),
         AssetValue::Object(meta) => {
-            let mut parts = vec!["{".to_string()];
-            parts.push(format!("{}id = \"{}\",", inner_indent, meta.id));
-            if let Some(w) = meta.width {
-                parts.push(format!("{}width = {},", inner_indent, w));
-            }
-            if let Some(h) = meta.height {
-                parts.push(format!("{}height = {},", inner_indent, h));
-            }
-            if let Some(x) = meta.rect_x {
-                parts.push(format!("{}rectX = {},", inner_indent, x));
-            }
-            if let Some(y) = meta.rect_y {
-                parts.push(format!("{}rectY = {},", inner_indent, y));
-            }
-            if let Some(w) = meta.rect_w {
-                parts.push(format!("{}rectW = {},", inner_indent, w));
-            }
-            if let Some(h) = meta.rect_h {
-                parts.push(format!("{}rectH = {},", inner_indent, h));
-            }
-            if let Some(ref h_id) = meta.highlight_id {
-                parts.push(format!("{}highlightId = \"{}\",", inner_indent, h_id));
-            }
-            if let Some(x) = meta.highlight_rect_x {
-                parts.push(format!("{}highlightRectX = {},", inner_indent, x));
-            }
-            if let Some(y) = meta.highlight_rect_y {
-                parts.push(format!("{}highlightRectY = {},", inner_indent, y));
-            }
-            if let Some(w) = meta.highlight_rect_w {
-                parts.push(format!("{}highlightRectW = {},", inner_indent, w));
-            }
-            if let Some(h) = meta.highlight_rect_h {
-                parts.push(format!("{}highlightRectH = {},", inner_indent, h));
-            }
-            parts.push(format!("{}}}", indent_str));
-            let result = parts.join("\n");
+            let result = serialize_meta(meta, indent, order, syntax);
             if first_level {
                 format!("{}\n", result)
             } else {
                 result
             }
         }
-        AssetValue::Table(map) => {
+        AssetValue::Surface(surface) => {
             let mut parts = vec!["{".to_string()];
-            let mut keys: Vec<String> = map.keys().cloned().collect();
-            keys.sort();
-
-            for key in keys {
-                let key_str = if is_simple_identifier(&key) {
-                    format!("{}{} = ", inner_indent, key)
-                } else {
-                    format!(
-                        "{}[{}] = ",
-                        inner_indent,
-                        serde_json::to_string(&key).unwrap()
-                    )
-                };
-                let value_str = serialize_luau(&map[&key], indent + 1);
-                parts.push(format!("{}{},", key_str, value_str));
+            for (name, meta) in surface.maps() {
+                parts.push(format!(
+                    "{}{}{},",
+                    inner_indent,
+                    syntax.field(name),
+                    serialize_meta(meta, indent + 1, order, syntax)
+                ));
             }
             parts.push(format!("{}}}", indent_str));
+            parts.join("\n")
+        }
+        AssetValue::Table(map) => {
+            let items = array_items(map);
+            let (open, close) = match items {
+                Some(_) => (syntax.list_open(), syntax.list_close()),
+                None => ("{", "}"),
+            };
+            let mut parts = vec![open.to_string()];
+            match items {
+                Some(items) => {
+                    for item in items {
+                        let value_str = serialize_value(item, indent + 1, order, syntax, None);
+                        parts.push(format!("{}{},", inner_indent, value_str));
+                    }
+                }
+                None => {
+                    for key in order.sorted(map.keys()) {
+                        push_comments(comments, key, &inner_indent, &mut parts);
+                        let key_str = format!("{}{}", inner_indent, syntax.field(key));
+                        let value_str = serialize_value(
+                            &map[key],
+                            indent + 1,
+                            order,
+                            syntax,
+                            entry_comments(comments, key),
+                        );
+                        parts.push(format!("{}{},", key_str, value_str));
+                    }
+                }
+            }
+            parts.push(format!("{}{}", indent_str, close));
             let result = parts.join("\n");
             if first_level {
                 format!("{}\n", result)
@@ -118,7 +952,414 @@ fn serialize_luau(value: &AssetValue, indent: usize) -> String {
     }
 }
 
-fn serialize_dts(value: &AssetValue, indent: usize) -> String {
+fn serialize_meta(meta: &AssetMeta, indent: usize, order: KeyOrder, syntax: Syntax) -> String {
+    let indent_str = "\t".repeat(indent);
+    let inner_indent = format!("{}\t", indent_str);
+
+    let mut parts = vec!["{".to_string()];
+    parts.push(format!(
+        "{}{}{},",
+        inner_indent,
+        syntax.field("id"),
+        syntax.string(&meta.id)
+    ));
+    if let Some(w) = meta.width {
+        parts.push(format!("{}{}{},", inner_indent, syntax.field("width"), w));
+    }
+    if let Some(h) = meta.height {
+        parts.push(format!("{}{}{},", inner_indent, syntax.field("height"), h));
+    }
+    if let Some(x) = meta.rect_x {
+        parts.push(format!("{}{}{},", inner_indent, syntax.field("rectX"), x));
+    }
+    if let Some(y) = meta.rect_y {
+        parts.push(format!("{}{}{},", inner_indent, syntax.field("rectY"), y));
+    }
+    if let Some(w) = meta.rect_w {
+        parts.push(format!("{}{}{},", inner_indent, syntax.field("rectW"), w));
+    }
+    if let Some(h) = meta.rect_h {
+        parts.push(format!("{}{}{},", inner_indent, syntax.field("rectH"), h));
+    }
+    if let Some(rotated) = meta.rotated {
+        parts.push(format!(
+            "{}{}{},",
+            inner_indent,
+            syntax.field("rotated"),
+            rotated
+        ));
+    }
+    if let Some(x) = meta.trim_x {
+        parts.push(format!("{}{}{},", inner_indent, syntax.field("trimX"), x));
+    }
+    if let Some(y) = meta.trim_y {
+        parts.push(format!("{}{}{},", inner_indent, syntax.field("trimY"), y));
+    }
+    if let Some(w) = meta.source_width {
+        parts.push(format!(
+            "{}{}{},",
+            inner_indent,
+            syntax.field("sourceWidth"),
+            w
+        ));
+    }
+    if let Some(h) = meta.source_height {
+        parts.push(format!(
+            "{}{}{},",
+            inner_indent,
+            syntax.field("sourceHeight"),
+            h
+        ));
+    }
+    if let Some(center) = meta.slice_center {
+        parts.push(format!(
+            "{}{}{},",
+            inner_indent,
+            syntax.field("sliceCenter"),
+            syntax.rect(center)
+        ));
+    }
+    if let Some(scale) = meta.slice_scale {
+        parts.push(format!(
+            "{}{}{},",
+            inner_indent,
+            syntax.field("sliceScale"),
+            syntax.number(scale)
+        ));
+    }
+    if let Some(ref h_id) = meta.highlight_id {
+        parts.push(format!(
+            "{}{}{},",
+            inner_indent,
+            syntax.field("highlightId"),
+            syntax.string(h_id)
+        ));
+    }
+    if let Some(x) = meta.highlight_rect_x {
+        parts.push(format!(
+            "{}{}{},",
+            inner_indent,
+            syntax.field("highlightRectX"),
+            x
+        ));
+    }
+    if let Some(y) = meta.highlight_rect_y {
+        parts.push(format!(
+            "{}{}{},",
+            inner_indent,
+            syntax.field("highlightRectY"),
+            y
+        ));
+    }
+    if let Some(w) = meta.highlight_rect_w {
+        parts.push(format!(
+            "{}{}{},",
+            inner_indent,
+            syntax.field("highlightRectW"),
+            w
+        ));
+    }
+    if let Some(h) = meta.highlight_rect_h {
+        parts.push(format!(
+            "{}{}{},",
+            inner_indent,
+            syntax.field("highlightRectH"),
+            h
+        ));
+    }
+    if let Some(rotated) = meta.highlight_rotated {
+        parts.push(format!(
+            "{}{}{},",
+            inner_indent,
+            syntax.field("highlightRotated"),
+            rotated
+        ));
+    }
+    if let Some(x) = meta.highlight_trim_x {
+        parts.push(format!(
+            "{}{}{},",
+            inner_indent,
+            syntax.field("highlightTrimX"),
+            x
+        ));
+    }
+    if let Some(y) = meta.highlight_trim_y {
+        parts.push(format!(
+            "{}{}{},",
+            inner_indent,
+            syntax.field("highlightTrimY"),
+            y
+        ));
+    }
+    if let Some(ref locales) = meta.locales {
+        parts.push(format!(
+            "{}{}{},",
+            inner_indent,
+            syntax.field("locales"),
+            serialize_variants(locales, indent + 1, order, syntax)
+        ));
+    }
+    if let Some(ref variants) = meta.variants {
+        parts.push(format!(
+            "{}{}{},",
+            inner_indent,
+            syntax.field("variants"),
+            serialize_variants(variants, indent + 1, order, syntax)
+        ));
+    }
+    if let Some(scale) = meta.scale {
+        parts.push(format!(
+            "{}{}{},",
+            inner_indent,
+            syntax.field("scale"),
+            scale
+        ));
+    }
+    if let Some(ref scales) = meta.scales {
+        parts.push(format!(
+            "{}{}{},",
+            inner_indent,
+            syntax.field("scales"),
+            serialize_variants(scales, indent + 1, order, syntax)
+        ));
+    }
+    if let Some(ref asset_type) = meta.asset_type {
+        parts.push(format!(
+            "{}{}{},",
+            inner_indent,
+            syntax.field("assetType"),
+            syntax.string(asset_type)
+        ));
+    }
+    if let Some(ref kind) = meta.kind {
+        parts.push(format!(
+            "{}{}{},",
+            inner_indent,
+            syntax.field("kind"),
+            syntax.string(kind)
+        ));
+    }
+    if let Some(duration) = meta.duration {
+        parts.push(format!(
+            "{}{}{},",
+            inner_indent,
+            syntax.field("duration"),
+            syntax.number(duration)
+        ));
+    }
+    if let Some(ref color) = meta.avg_color {
+        parts.push(format!(
+            "{}{}{},",
+            inner_indent,
+            syntax.field("avgColor"),
+            syntax.string(color)
+        ));
+    }
+    if let Some(ref color) = meta.dominant_color {
+        parts.push(format!(
+            "{}{}{},",
+            inner_indent,
+            syntax.field("dominantColor"),
+            syntax.string(color)
+        ));
+    }
+    if let Some(opaque) = meta.opaque {
+        parts.push(format!(
+            "{}{}{},",
+            inner_indent,
+            syntax.field("opaque"),
+            opaque
+        ));
+    }
+    if let Some(bounds) = meta.alpha_bleed {
+        parts.push(format!(
+            "{}{}{},",
+            inner_indent,
+            syntax.field("alphaBleed"),
+            syntax.rect(bounds)
+        ));
+    }
+    for key in order.sorted(meta.extra.keys()) {
+        parts.push(format!(
+            "{}{}{},",
+            inner_indent,
+            syntax.field(key),
+            serialize_value(&meta.extra[key], indent + 1, order, syntax, None)
+        ));
+    }
+    parts.push(format!("{}}}", indent_str));
+    parts.join("\n")
+}
+
+fn serialize_variants(
+    variants: &BTreeMap<String, AssetMeta>,
+    indent: usize,
+    order: KeyOrder,
+    syntax: Syntax,
+) -> String {
+    let indent_str = "\t".repeat(indent);
+    let inner_indent = format!("{}\t", indent_str);
+
+    let mut parts = vec!["{".to_string()];
+    for name in order.sorted(variants.keys()) {
+        parts.push(format!(
+            "{}{}{},",
+            inner_indent,
+            syntax.field(name),
+            serialize_meta(&variants[name], indent + 1, order, syntax)
+        ));
+    }
+    parts.push(format!("{}}}", indent_str));
+    parts.join("\n")
+}
+
+fn serialize_animations(
+    animations: &BTreeMap<String, Animation>,
+    order: KeyOrder,
+    syntax: Syntax,
+) -> String {
+    let mut parts = vec!["{".to_string()];
+    for key in order.sorted(animations.keys()) {
+        let animation = &animations[key];
+        parts.push(format!("\t{}{{", syntax.field(key)));
+        parts.push(format!(
+            "\t\t{}{},",
+            syntax.field("loop"),
+            serde_json::to_string(&animation.loop_mode).unwrap()
+        ));
+        parts.push(format!(
+            "\t\t{}{},",
+            syntax.field("repeatCount"),
+            animation.repeat
+        ));
+        parts.push(format!(
+            "\t\t{}{}",
+            syntax.field("frames"),
+            syntax.list_open()
+        ));
+        for frame in &animation.frames {
+            parts.push("\t\t\t{".to_string());
+            parts.push(format!(
+                "\t\t\t\t{}{},",
+                syntax.field("duration"),
+                syntax.number(f64::from(frame.duration_ms) / 1000.0)
+            ));
+            parts.push(format!(
+                "\t\t\t\t{}{},",
+                syntax.field("asset"),
+                serialize_meta(&frame.asset, 4, order, syntax)
+            ));
+            parts.push("\t\t\t},".to_string());
+        }
+        parts.push(format!("\t\t{},", syntax.list_close()));
+        parts.push("\t},".to_string());
+    }
+    parts.push("}".to_string());
+    parts.join("\n")
+}
+
+/// Literal syntax of a generated module: Luau tables or TypeScript objects.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Syntax {
+    Luau,
+    TypeScript,
+}
+
+impl Syntax {
+    /// `key = ` in Luau, `key: ` in TypeScript.
+    fn field(self, key: &str) -> String {
+        match self {
+            Self::Luau => format!("{} = ", luau_key(key)),
+            Self::TypeScript => format!("{}: ", dts_key(key)),
+        }
+    }
+
+    fn string(self, value: &str) -> String {
+        match self {
+            Self::Luau => format!("\"{}\"", value.replace('"', "\\\"")),
+            Self::TypeScript => serde_json::to_string(value).unwrap(),
+        }
+    }
+
+    /// The canonical spelling of `value`, the same on every platform and
+    /// run: integers without a fraction (`-0` as `0`), the shortest digits
+    /// that read back as `value` otherwise, exponents outside `1e-7..1e21`
+    /// (like JavaScript), and infinities and NaN as expressions.
+    fn number(self, value: f64) -> String {
+        if value.is_nan() {
+            return match self {
+                Self::Luau => "0 / 0",
+                Self::TypeScript => "NaN",
+            }
+            .to_string();
+        }
+        if value.is_infinite() {
+            let infinity = match self {
+                Self::Luau => "math.huge",
+                Self::TypeScript => "Infinity",
+            };
+            return if value < 0.0 {
+                format!("-{}", infinity)
+            } else {
+                infinity.to_string()
+            };
+        }
+        if value == 0.0 {
+            return "0".to_string();
+        }
+        let magnitude = value.abs();
+        if !(1e-7..1e21).contains(&magnitude) {
+            return format!("{:e}", value);
+        }
+        value.to_string()
+    }
+
+    fn rect(self, rect: SliceCenter) -> String {
+        format!(
+            "{{ {}{}, {}{}, {}{}, {}{} }}",
+            self.field("minX"),
+            rect.min_x,
+            self.field("minY"),
+            rect.min_y,
+            self.field("maxX"),
+            rect.max_x,
+            self.field("maxY"),
+            rect.max_y
+        )
+    }
+
+    fn list_open(self) -> &'static str {
+        match self {
+            Self::Luau => "{",
+            Self::TypeScript => "[",
+        }
+    }
+
+    fn list_close(self) -> &'static str {
+        match self {
+            Self::Luau => "}",
+            Self::TypeScript => "]",
+        }
+    }
+}
+
+fn luau_key(key: &str) -> String {
+    if is_simple_identifier(key) {
+        key.to_string()
+    } else {
+        format!("[{}]", serde_json::to_string(key).unwrap())
+    }
+}
+
+fn dts_key(key: &str) -> String {
+    if is_simple_identifier(key) {
+        key.to_string()
+    } else {
+        serde_json::to_string(key).unwrap()
+    }
+}
+
+fn serialize_dts(value: &AssetValue, indent: usize, order: KeyOrder) -> String {
     let indent_str = " ".repeat(indent);
     let inner_indent = format!("{}    ", indent_str);
 
@@ -126,24 +1367,34 @@ fn serialize_dts(value: &AssetValue, indent: usize) -> String {
         AssetValue::String(_) | AssetValue::Number(_) | AssetValue::Object(_) => {
             "AssetMeta;".to_string()
         }
+        AssetValue::Bool(_) => "boolean;".to_string(),
+        AssetValue::Surface(_) => "AssetSurface;".to_string(),
         AssetValue::Table(map) => {
+            if let Some(items) = array_items(map) {
+                let mut parts = vec!["[".to_string()];
+                for item in items {
+                    let value_str = serialize_dts(item, indent + 4, order);
+                    parts.push(format!(
+                        "{}{},",
+                        inner_indent,
+                        value_str.trim_end_matches(';')
+                    ));
+                }
+                parts.push(format!("{}]", indent_str));
+                return parts.join("\n");
+            }
             let mut parts = vec!["{".to_string()];
-            let mut keys: Vec<String> = map.keys().cloned().collect();
-            keys.sort();
-
-            for key in keys {
-                let value = &map[&key];
-                let key_str = if is_simple_identifier(&key) {
-                    format!("{}{}: ", inner_indent, key)
-                } else {
-                    format!("{}{}: ", inner_indent, serde_json::to_string(&key).unwrap())
-                };
+            for key in order.sorted(map.keys()) {
+                let value = &map[key];
+                let key_str = format!("{}{}: ", inner_indent, dts_key(key));
 
                 let value_str = match value {
                     AssetValue::Object(_) | AssetValue::String(_) | AssetValue::Number(_) => {
                         "AssetMeta;".to_string()
                     }
-                    AssetValue::Table(_) => serialize_dts(value, indent + 4),
+                    AssetValue::Bool(_) => "boolean;".to_string(),
+                    AssetValue::Surface(_) => "AssetSurface;".to_string(),
+                    AssetValue::Table(_) => serialize_dts(value, indent + 4, order),
                 };
                 parts.push(format!("{}{}", key_str, value_str));
             }
@@ -165,7 +1416,7 @@ fn is_simple_identifier(key: &str) -> bool {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::assets::model::{AssetMeta, AssetValue};
+    use crate::assets::model::{AssetMeta, AssetValue, SurfaceSet};
     use std::collections::BTreeMap;
 
     fn sample_assets() -> BTreeMap<String, AssetValue> {
@@ -174,15 +1425,7 @@ mod tests {
                 id: id.to_string(),
                 width: Some(1536),
                 height: Some(864),
-                rect_x: None,
-                rect_y: None,
-                rect_w: None,
-                rect_h: None,
-                highlight_id: None,
-                highlight_rect_x: None,
-                highlight_rect_y: None,
-                highlight_rect_w: None,
-                highlight_rect_h: None,
+                ..Default::default()
             })
         }
 
@@ -204,16 +1447,532 @@ mod tests {
 
     #[test]
     fn luau_output_contains_expected_blocks() {
-        let output = render_luau_module(&sample_assets());
+        let output = render_luau_module(
+            &sample_assets(),
+            &BTreeMap::new(),
+            &RenderOptions::default(),
+        );
         assert!(output.contains("local assets = {"));
         assert!(output.contains("return {"));
         assert!(output.contains("rain01.png"));
     }
 
+    #[test]
+    fn split_modules_are_required_by_the_index() {
+        let mut root = sample_assets();
+        root.insert("main-menu".into(), AssetValue::Table(BTreeMap::new()));
+        root.insert("assets".into(), AssetValue::Table(BTreeMap::new()));
+        root.insert(
+            "logo.png".into(),
+            AssetValue::String("rbxassetid://9".into()),
+        );
+
+        let (luau, dts, modules) =
+            render_split_modules(&root, &BTreeMap::new(), &RenderOptions::default(), "assets");
+        let names: Vec<&str> = modules.iter().map(|m| m.name.as_str()).collect();
+        assert_eq!(names, vec!["ambience", "main-menu"]);
+        assert!(luau.contains("\tambience = require(script.Parent.ambience),\n"));
+        assert!(luau.contains("\t[\"main-menu\"] = require(script.Parent[\"main-menu\"]),\n"));
+        assert!(luau.contains("[\"logo.png\"] = "));
+        assert!(!luau.contains("rain01.png"));
+        assert_eq!(split_module_names(&luau), vec!["ambience", "main-menu"]);
+
+        assert!(dts.contains("    ambience: typeof import(\"./ambience\");\n"));
+        assert!(dts.contains("    \"main-menu\": typeof import(\"./main-menu\");\n"));
+        assert!(dts.contains("    assets: {\n"));
+
+        let ambience = &modules[0];
+        assert!(ambience.luau.contains("local assets = {"));
+        assert!(ambience.luau.ends_with("return assets\n"));
+        assert!(ambience.luau.contains("rain01.png"));
+        assert!(ambience
+            .dts
+            .contains("import type { AssetMeta } from \"./assets\";"));
+        assert!(ambience.dts.ends_with("export = assets;\n"));
+    }
+
+    #[test]
+    fn luau_types_mirror_the_declaration() {
+        let mut root = sample_assets();
+        root.insert(
+            "play.png".into(),
+            AssetValue::Object(AssetMeta {
+                id: "rbxassetid://1".into(),
+                kind: Some("audio".into()),
+                extra: BTreeMap::from([("pivot-x".to_string(), AssetValue::Number(0.5))]),
+                ..Default::default()
+            }),
+        );
+        let options = RenderOptions {
+            luau_types: true,
+            ..Default::default()
+        };
+
+        let luau = render_luau_module(&root, &BTreeMap::new(), &options);
+        assert!(luau.contains("export type AssetMeta = {\n\tid: string,\n\twidth: number?,\n"));
+        assert!(luau.contains("\tkind: \"audio\"?,\n"));
+        assert!(luau.contains("\t[\"pivot-x\"]: number?,\n"));
+        assert!(luau.contains("export type Assets = {\n\tambience: {\n\t\train: {\n\t\t\t[\"rain01.png\"]: AssetMeta,"));
+        assert!(luau.contains("\t[\"play.png\"]: AssetMeta,\n}\n\nlocal assets: Assets = {\n"));
+
+        let (index, _, modules) = render_split_modules(&root, &BTreeMap::new(), &options, "assets");
+        assert!(index.contains("\tambience: typeof(require(script.Parent.ambience)),\n"));
+        assert!(modules[0].luau.contains("export type AssetMeta = {"));
+        assert!(modules[0].luau.contains("local assets: {\n\train: {"));
+    }
+
+    #[test]
+    fn natural_key_order_sorts_numbers_by_value() {
+        let mut root = BTreeMap::new();
+        for key in ["frame10.png", "frame2.png", "frame1.png"] {
+            root.insert(key.to_string(), AssetValue::String("rbxassetid://1".into()));
+        }
+        let options = RenderOptions {
+            key_order: KeyOrder::Natural,
+            ..Default::default()
+        };
+
+        let luau = render_luau_module(&root, &BTreeMap::new(), &options);
+        let positions: Vec<usize> = ["frame1.png", "frame2.png", "frame10.png"]
+            .iter()
+            .map(|key| luau.find(key).unwrap())
+            .collect();
+        assert!(positions.windows(2).all(|w| w[0] < w[1]));
+
+        let dts = render_dts_module(&root, &BTreeMap::new(), &options);
+        assert!(dts.find("frame2.png").unwrap() < dts.find("frame10.png").unwrap());
+        let lexical = render_dts_module(&root, &BTreeMap::new(), &RenderOptions::default());
+        assert!(lexical.find("frame10.png").unwrap() < lexical.find("frame2.png").unwrap());
+    }
+
     #[test]
     fn dts_output_contains_expected_tree() {
-        let output = render_dts_module(&sample_assets());
+        let output = render_dts_module(
+            &sample_assets(),
+            &BTreeMap::new(),
+            &RenderOptions::default(),
+        );
         assert!(output.contains("export interface AssetMeta"));
         assert!(output.contains("\"rain02.png\": AssetMeta;"));
     }
+
+    #[test]
+    fn locale_variants_are_rendered_and_typed() {
+        let mut locales = BTreeMap::new();
+        locales.insert(
+            "de".to_string(),
+            AssetMeta {
+                id: "rbxassetid://2".into(),
+                ..Default::default()
+            },
+        );
+        let mut root = sample_assets();
+        root.insert(
+            "play.png".into(),
+            AssetValue::Object(AssetMeta {
+                id: "rbxassetid://1".into(),
+                locales: Some(locales),
+                ..Default::default()
+            }),
+        );
+
+        let luau = render_luau_module(&root, &BTreeMap::new(), &RenderOptions::default());
+        assert!(luau.contains("locales = {"));
+        assert!(luau.contains("de = {"));
+
+        let dts = render_dts_module(&root, &BTreeMap::new(), &RenderOptions::default());
+        assert!(dts.contains("export type AssetLocale = \"de\";"));
+        assert!(dts.contains("locales?: Partial<Record<AssetLocale, AssetMeta>>;"));
+
+        let plain = render_dts_module(
+            &sample_assets(),
+            &BTreeMap::new(),
+            &RenderOptions::default(),
+        );
+        assert!(!plain.contains("AssetLocale"));
+    }
+
+    #[test]
+    fn animations_are_exported_next_to_assets() {
+        use crate::assets::animations::AnimationFrame;
+
+        let frame = |id: &str| AnimationFrame {
+            asset: AssetMeta {
+                id: id.into(),
+                ..Default::default()
+            },
+            duration_ms: 100,
+        };
+        let mut animations = BTreeMap::new();
+        animations.insert(
+            "hero/run".to_string(),
+            Animation {
+                frames: vec![frame("rbxassetid://1"), frame("rbxassetid://2")],
+                loop_mode: "pingpong".into(),
+                repeat: 0,
+            },
+        );
+
+        let luau = render_luau_module(&sample_assets(), &animations, &RenderOptions::default());
+        assert!(luau.contains("local animations = {"));
+        assert!(luau.contains("\t[\"hero/run\"] = {"));
+        assert!(luau.contains("loop = \"pingpong\","));
+        assert!(luau.contains("duration = 0.1,"));
+        assert!(luau.contains("\tanimations = animations\n"));
+
+        let dts = render_dts_module(&sample_assets(), &animations, &RenderOptions::default());
+        assert!(dts.contains("export interface AssetAnimation {"));
+        assert!(dts.contains("\"hero/run\": AssetAnimation;"));
+        assert!(dts.contains("export { assets, animations };"));
+    }
+
+    #[test]
+    fn comments_are_kept_above_entries() {
+        let comment = |line: &str, children: Vec<(&str, EntryComments)>| EntryComments {
+            lines: if line.is_empty() {
+                Vec::new()
+            } else {
+                vec![line.to_string()]
+            },
+            children: children
+                .into_iter()
+                .map(|(key, entry)| (key.to_string(), entry))
+                .collect(),
+        };
+        let options = RenderOptions {
+            comments: comment(
+                "",
+                vec![(
+                    "ambience",
+                    comment(
+                        "-- Looped in the lobby",
+                        vec![(
+                            "rain",
+                            comment("", vec![("rain02.png", comment("-- Louder take", vec![]))]),
+                        )],
+                    ),
+                )],
+            ),
+            ..Default::default()
+        };
+
+        let luau = render_luau_module(&sample_assets(), &BTreeMap::new(), &options);
+        assert!(luau.contains("{\n\t-- Looped in the lobby\n\tambience = {\n"));
+        assert!(luau.contains("\t\t},\n\t\t\t-- Louder take\n\t\t\t[\"rain02.png\"] = {\n"));
+
+        let (index, _, modules) =
+            render_split_modules(&sample_assets(), &BTreeMap::new(), &options, "assets");
+        assert!(index.contains("\t-- Looped in the lobby\n\tambience = require("));
+        assert!(modules[0]
+            .luau
+            .contains("\t\t-- Louder take\n\t\t[\"rain02.png\"] = {\n"));
+
+        let ts = render_ts_module(&sample_assets(), &BTreeMap::new(), &options);
+        assert!(!ts.contains("--"));
+    }
+
+    #[test]
+    fn extra_returns_are_kept() {
+        let options = RenderOptions {
+            returns: vec!["version = 3".into(), "getId = getId".into()],
+            ..Default::default()
+        };
+        let luau = render_luau_module(&sample_assets(), &BTreeMap::new(), &options);
+        assert!(
+            luau.ends_with("return {\n\tassets = assets,\n\tversion = 3,\n\tgetId = getId\n}\n")
+        );
+
+        let options = RenderOptions {
+            returns: vec![
+                "util = require(script.Parent.util)".into(),
+                "version = 3".into(),
+            ],
+            ..Default::default()
+        };
+        let (index, _, _) =
+            render_split_modules(&sample_assets(), &BTreeMap::new(), &options, "assets");
+        assert_eq!(split_module_names(&index), vec!["ambience"]);
+    }
+
+    #[test]
+    fn arrays_are_written_without_keys() {
+        let frame = |id: &str| {
+            AssetValue::Object(AssetMeta {
+                id: id.into(),
+                ..Default::default()
+            })
+        };
+        let root = BTreeMap::from([(
+            "frames".to_string(),
+            AssetValue::Table(BTreeMap::from([
+                ("1".to_string(), frame("rbxassetid://1")),
+                ("2".to_string(), frame("rbxassetid://2")),
+            ])),
+        )]);
+        let options = RenderOptions {
+            luau_types: true,
+            ..Default::default()
+        };
+
+        let luau = render_luau_module(&root, &BTreeMap::new(), &options);
+        assert!(luau.contains("\tframes: { AssetMeta },\n"));
+        assert!(
+            luau.contains("\tframes = {\n\t\t{\n\t\t\tid = \"rbxassetid://1\",\n\t\t},\n\t\t{\n")
+        );
+
+        let dts = render_dts_module(&root, &BTreeMap::new(), &options);
+        assert!(dts.contains("    frames: [\n        AssetMeta,\n        AssetMeta,\n    ]\n"));
+
+        let ts = render_ts_module(&root, &BTreeMap::new(), &options);
+        assert!(ts.contains("\tframes: [\n\t\t{\n\t\t\tid: \"rbxassetid://1\",\n"));
+        assert!(ts.contains("\t\t},\n\t],\n"));
+    }
+
+    #[test]
+    fn numbers_are_canonical() {
+        let luau = Syntax::Luau;
+        assert_eq!(luau.number(123.0), "123");
+        assert_eq!(luau.number(-0.0), "0");
+        assert_eq!(luau.number(0.1 + 0.2), "0.30000000000000004");
+        assert_eq!(luau.number(f64::from(100u32) / 1000.0), "0.1");
+        assert_eq!(luau.number(9_007_199_254_740_992.0), "9007199254740992");
+        assert_eq!(luau.number(1e21), "1e21");
+        assert_eq!(luau.number(-1.5e-8), "-1.5e-8");
+        assert_eq!(luau.number(f64::INFINITY), "math.huge");
+        assert_eq!(luau.number(f64::NEG_INFINITY), "-math.huge");
+        assert_eq!(luau.number(f64::NAN), "0 / 0");
+        assert_eq!(Syntax::TypeScript.number(f64::NEG_INFINITY), "-Infinity");
+        assert_eq!(Syntax::TypeScript.number(f64::NAN), "NaN");
+
+        let meta = AssetValue::Object(AssetMeta {
+            id: "rbxassetid://1".into(),
+            slice_scale: Some(2.0),
+            duration: Some(1.25),
+            extra: BTreeMap::from([("pivot".to_string(), AssetValue::Number(-0.0))]),
+            ..Default::default()
+        });
+        let luau = serialize_luau(&meta, 0, KeyOrder::Lexical, None);
+        assert!(luau.contains("\tsliceScale = 2,\n"));
+        assert!(luau.contains("\tduration = 1.25,\n"));
+        assert!(luau.contains("\tpivot = 0,\n"));
+    }
+
+    #[test]
+    fn templates_replace_the_module_layout() {
+        let options = RenderOptions {
+            luau_template: Some(
+                "{{ header }}\n--!strict\n\nlocal Assets = {{ assets }}\n\nreturn table.freeze(Assets)\n"
+                    .into(),
+            ),
+            dts_template: Some(
+                "{{ header }}\n{{ types }}\n\nexport declare const Assets: {{ assets }};\nexport declare const Animations: {{ animations }};\n"
+                    .into(),
+            ),
+            ..Default::default()
+        };
+
+        let luau = render_luau_module(&sample_assets(), &BTreeMap::new(), &options);
+        assert!(luau.starts_with(LUAU_HEADER));
+        assert!(luau.contains("--!strict\n\nlocal Assets = {\n\tambience = {\n"));
+        assert!(luau.ends_with("\n}\n\nreturn table.freeze(Assets)\n"));
+
+        let dts = render_dts_module(&sample_assets(), &BTreeMap::new(), &options);
+        assert!(dts.starts_with(DTS_HEADER));
+        assert!(dts.contains("export interface AssetMeta {\n"));
+        assert!(dts.contains("}\n\nexport declare const Assets: {\n    ambience: {"));
+        assert!(dts.ends_with("export declare const Animations: {};\n"));
+    }
+
+    #[test]
+    fn ts_module_exports_const_literals() {
+        use crate::assets::animations::AnimationFrame;
+
+        let mut root = sample_assets();
+        root.insert(
+            "button.png".into(),
+            AssetValue::Object(AssetMeta {
+                id: "rbxassetid://4".into(),
+                slice_center: Some(SliceCenter {
+                    min_x: 1,
+                    min_y: 2,
+                    max_x: 3,
+                    max_y: 4,
+                }),
+                extra: BTreeMap::from([("pivot-x".to_string(), AssetValue::Number(0.5))]),
+                ..Default::default()
+            }),
+        );
+        let animations = BTreeMap::from([(
+            "hero/run".to_string(),
+            Animation {
+                frames: vec![AnimationFrame {
+                    asset: AssetMeta {
+                        id: "rbxassetid://1".into(),
+                        ..Default::default()
+                    },
+                    duration_ms: 100,
+                }],
+                loop_mode: "loop".into(),
+                repeat: 0,
+            },
+        )]);
+
+        let ts = render_ts_module(&root, &animations, &RenderOptions::default());
+        assert!(ts.contains("export const assets = {\n\tambience: {\n\t\train: {\n"));
+        assert!(ts.contains(
+            "\t\t\t\"rain01.png\": {\n\t\t\t\tid: \"rbxassetid://1\",\n\t\t\t\twidth: 1536,\n"
+        ));
+        assert!(ts.contains("\t\tsliceCenter: { minX: 1, minY: 2, maxX: 3, maxY: 4 },\n"));
+        assert!(ts.contains("\t\t\"pivot-x\": 0.5,\n"));
+        assert!(ts.contains("\n} as const;\n\nexport const animations = {\n\t\"hero/run\": {\n"));
+        assert!(ts.contains("\t\tframes: [\n\t\t\t{\n\t\t\t\tduration: 0.1,\n"));
+        assert!(ts.ends_with("\t\t],\n\t},\n} as const;\n"));
+        assert!(!ts.contains("id = "));
+    }
+
+    #[test]
+    fn surfaces_are_rendered_and_typed() {
+        let mut root = sample_assets();
+        root.insert(
+            "granite".into(),
+            AssetValue::Surface(SurfaceSet {
+                color_map: Some(AssetMeta {
+                    id: "rbxassetid://1".into(),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            }),
+        );
+
+        let luau = render_luau_module(&root, &BTreeMap::new(), &RenderOptions::default());
+        assert!(luau.contains("granite = {\n\t\tcolorMap = {\n\t\t\tid = \"rbxassetid://1\","));
+        assert!(!luau.contains("normalMap"));
+
+        let dts = render_dts_module(&root, &BTreeMap::new(), &RenderOptions::default());
+        assert!(dts.contains("export interface AssetSurface {"));
+        assert!(dts.contains("granite: AssetSurface;"));
+        assert!(!render_dts_module(
+            &sample_assets(),
+            &BTreeMap::new(),
+            &RenderOptions::default()
+        )
+        .contains("AssetSurface"));
+    }
+
+    #[test]
+    fn asset_types_are_rendered_and_typed() {
+        let mut root = sample_assets();
+        crate::assets::model::set_asset_type(&mut root, Some("Decal"));
+
+        let luau = render_luau_module(&root, &BTreeMap::new(), &RenderOptions::default());
+        assert!(luau.contains("assetType = \"Decal\","));
+        let dts = render_dts_module(&root, &BTreeMap::new(), &RenderOptions::default());
+        assert!(dts.contains("\tassetType?: \"Decal\";"));
+
+        crate::assets::model::set_asset_type(&mut root, None);
+        let dts = render_dts_module(&root, &BTreeMap::new(), &RenderOptions::default());
+        assert!(!dts.contains("assetType"));
+    }
+
+    #[test]
+    fn audio_entries_carry_kind_and_duration() {
+        let mut root = sample_assets();
+        let dts = render_dts_module(&root, &BTreeMap::new(), &RenderOptions::default());
+        assert!(dts.contains("\twidth: number;\n"));
+        assert!(!dts.contains("kind"));
+
+        root.insert(
+            "click.ogg".to_string(),
+            AssetValue::Object(AssetMeta {
+                id: "rbxassetid://9".into(),
+                kind: Some("audio".into()),
+                duration: Some(1.25),
+                ..Default::default()
+            }),
+        );
+        let luau = render_luau_module(&root, &BTreeMap::new(), &RenderOptions::default());
+        assert!(luau.contains("\t\tkind = \"audio\",\n\t\tduration = 1.25,\n"));
+        let dts = render_dts_module(&root, &BTreeMap::new(), &RenderOptions::default());
+        assert!(dts.contains("\twidth?: number;\n\theight?: number;\n"));
+        assert!(dts.contains("\tkind?: \"audio\";\n\tduration?: number;\n"));
+    }
+
+    #[test]
+    fn unknown_fields_round_trip() {
+        let parsed = crate::assets::model::convert_map_to_asset_meta(&BTreeMap::from([
+            (
+                "id".to_string(),
+                AssetValue::String("rbxassetid://3".into()),
+            ),
+            ("width".to_string(), AssetValue::Number(64.0)),
+            ("pivotX".to_string(), AssetValue::Number(0.5)),
+            ("category".to_string(), AssetValue::String("ui".into())),
+            ("hit-box".to_string(), AssetValue::Bool(true)),
+        ]))
+        .unwrap();
+        assert_eq!(parsed.width, Some(64));
+        assert_eq!(parsed.extra.len(), 3);
+
+        let root = BTreeMap::from([("coin.png".to_string(), AssetValue::Object(parsed))]);
+        let luau = render_luau_module(&root, &BTreeMap::new(), &RenderOptions::default());
+        assert!(
+            luau.contains("\t\tcategory = \"ui\",\n\t\t[\"hit-box\"] = true,\n\t\tpivotX = 0.5,\n")
+        );
+        let dts = render_dts_module(&root, &BTreeMap::new(), &RenderOptions::default());
+        assert!(
+            dts.contains("\tcategory?: string;\n\t\"hit-box\"?: boolean;\n\tpivotX?: number;\n")
+        );
+    }
+
+    #[test]
+    fn rotated_sprites_are_flagged() {
+        let mut root = sample_assets();
+        root.insert(
+            "banner.png".into(),
+            AssetValue::Object(AssetMeta {
+                id: "rbxassetid://1".into(),
+                width: Some(64),
+                height: Some(16),
+                rect_w: Some(16),
+                rect_h: Some(64),
+                rotated: Some(true),
+                ..Default::default()
+            }),
+        );
+
+        let luau = render_luau_module(&root, &BTreeMap::new(), &RenderOptions::default());
+        assert!(luau.contains("\t\trectH = 64,\n\t\trotated = true,"));
+        let dts = render_dts_module(&root, &BTreeMap::new(), &RenderOptions::default());
+        assert!(dts.contains("\trotated?: boolean;"));
+        assert!(!render_dts_module(
+            &sample_assets(),
+            &BTreeMap::new(),
+            &RenderOptions::default()
+        )
+        .contains("rotated"));
+    }
+
+    #[test]
+    fn trim_offsets_are_rendered_and_typed() {
+        let mut root = sample_assets();
+        root.insert(
+            "panel.png".into(),
+            AssetValue::Object(AssetMeta {
+                id: "rbxassetid://1".into(),
+                width: Some(64),
+                height: Some(64),
+                rect_w: Some(40),
+                rect_h: Some(20),
+                trim_x: Some(12),
+                trim_y: Some(22),
+                ..Default::default()
+            }),
+        );
+
+        let luau = render_luau_module(&root, &BTreeMap::new(), &RenderOptions::default());
+        assert!(luau.contains("\t\ttrimX = 12,\n\t\ttrimY = 22,"));
+        let dts = render_dts_module(&root, &BTreeMap::new(), &RenderOptions::default());
+        assert!(dts.contains("\ttrimX?: number;"));
+        assert!(!dts.contains("rotated"));
+    }
 }
@@ -0,0 +1,118 @@
+use std::cmp::Ordering;
+
+/// How keys are ordered in generated modules.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum KeyOrder {
+    /// Byte-wise order: `frame10.png` sorts before `frame2.png`.
+    #[default]
+    Lexical,
+    /// Runs of digits compare by value: `frame2.png` sorts before `frame10.png`.
+    Natural,
+}
+
+impl KeyOrder {
+    pub fn compare(self, a: &str, b: &str) -> Ordering {
+        match self {
+            Self::Lexical => a.cmp(b),
+            Self::Natural => natural_cmp(a, b),
+        }
+    }
+
+    /// Returns the keys sorted in this order.
+    pub fn sorted<'a>(self, keys: impl IntoIterator<Item = &'a String>) -> Vec<&'a String> {
+        let mut keys: Vec<&String> = keys.into_iter().collect();
+        keys.sort_by(|a, b| self.compare(a, b));
+        keys
+    }
+}
+
+/// Compares strings with digit runs ordered by value. Equal values with
+/// different zero padding (`01` and `1`) fall back to byte order so the
+/// order stays total.
+pub fn natural_cmp(a: &str, b: &str) -> Ordering {
+    let (mut x, mut y) = (a.as_bytes(), b.as_bytes());
+    while let (Some(&cx), Some(&cy)) = (x.first(), y.first()) {
+        let ordering = if cx.is_ascii_digit() && cy.is_ascii_digit() {
+            let (dx, rest_x) = split_digits(x);
+            let (dy, rest_y) = split_digits(y);
+            x = rest_x;
+            y = rest_y;
+            let (tx, ty) = (trim_zeros(dx), trim_zeros(dy));
+            tx.len().cmp(&ty.len()).then_with(|| tx.cmp(ty))
+        } else {
+            x = &x[1..];
+            y = &y[1..];
+            cx.cmp(&cy)
+        };
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+    x.len().cmp(&y.len()).then_with(|| a.cmp(b))
+}
+
+fn split_digits(s: &[u8]) -> (&[u8], &[u8]) {
+    let end = s
+        .iter()
+        .position(|c| !c.is_ascii_digit())
+        .unwrap_or(s.len());
+    s.split_at(end)
+}
+
+fn trim_zeros(digits: &[u8]) -> &[u8] {
+    let start = digits
+        .iter()
+        .position(|&c| c != b'0')
+        .unwrap_or(digits.len());
+    &digits[start..]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn numbers_sort_by_value() {
+        let keys: Vec<String> = [
+            "frame10.png",
+            "frame2.png",
+            "frame1.png",
+            "frame02.png",
+            "a",
+        ]
+        .into_iter()
+        .map(String::from)
+        .collect();
+        let natural: Vec<&str> = KeyOrder::Natural
+            .sorted(&keys)
+            .into_iter()
+            .map(String::as_str)
+            .collect();
+        assert_eq!(
+            natural,
+            [
+                "a",
+                "frame1.png",
+                "frame02.png",
+                "frame2.png",
+                "frame10.png"
+            ]
+        );
+
+        let lexical: Vec<&str> = KeyOrder::Lexical
+            .sorted(&keys)
+            .into_iter()
+            .map(String::as_str)
+            .collect();
+        assert_eq!(
+            lexical,
+            [
+                "a",
+                "frame02.png",
+                "frame1.png",
+                "frame10.png",
+                "frame2.png"
+            ]
+        );
+    }
+}
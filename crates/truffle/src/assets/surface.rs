@@ -0,0 +1,188 @@
+use super::model::{AssetMeta, AssetValue, SurfaceSet};
+use super::variants::{collect_leaves, insert_value, remove_leaf};
+use std::collections::BTreeMap;
+use std::path::Path;
+use walkdir::WalkDir;
+
+/// File name suffixes of the SurfaceAppearance texture maps.
+const SURFACE_MAPS: [&str; 4] = ["_color", "_normal", "_metalness", "_roughness"];
+
+/// A texture map's path and suffix (`_normal`).
+type SurfaceMap = (Vec<String>, &'static str);
+
+/// Groups PBR texture sets (`rock_color.png`, `rock_normal.png`, …) into a
+/// single surface entry keyed by their shared name (`rock`).
+///
+/// At least two maps must share a name to form a set. Sets whose name is
+/// already taken by another entry are reported and left as separate images.
+pub fn merge_surface_sets(assets: &mut BTreeMap<String, AssetValue>) {
+    let mut leaves = Vec::new();
+    collect_leaves(assets, &mut Vec::new(), &mut leaves);
+
+    let mut groups: BTreeMap<Vec<String>, Vec<SurfaceMap>> = BTreeMap::new();
+    for (path, _) in &leaves {
+        let Some((base, suffix)) = split_surface_map(path) else {
+            continue;
+        };
+        groups.entry(base).or_default().push((path.clone(), suffix));
+    }
+
+    let metas: BTreeMap<Vec<String>, AssetMeta> = leaves.into_iter().collect();
+    for (base, maps) in groups {
+        if maps.len() < 2 {
+            continue;
+        }
+        if contains_path(assets, &base) {
+            println!(
+                "[sync] WARN: Surface set {} conflicts with an existing entry – keeping its maps as separate images.",
+                base.join("/")
+            );
+            continue;
+        }
+
+        let mut surface = SurfaceSet::default();
+        for (path, suffix) in &maps {
+            let slot = match *suffix {
+                "_color" => &mut surface.color_map,
+                "_normal" => &mut surface.normal_map,
+                "_metalness" => &mut surface.metalness_map,
+                _ => &mut surface.roughness_map,
+            };
+            *slot = metas.get(path).cloned();
+            remove_leaf(assets, path);
+        }
+        insert_value(assets, &base, AssetValue::Surface(surface));
+    }
+}
+
+/// Keys (relative to `images_folder`) of every PNG that belongs to a surface
+/// set. Surface textures are uploaded as standalone images, never atlased.
+pub fn surface_texture_keys(images_folder: &Path) -> Vec<String> {
+    let mut groups: BTreeMap<Vec<String>, Vec<String>> = BTreeMap::new();
+    let files = WalkDir::new(images_folder)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file())
+        .filter(|e| e.path().extension().and_then(|s| s.to_str()) == Some("png"));
+    for entry in files {
+        let Ok(rel) = entry.path().strip_prefix(images_folder) else {
+            continue;
+        };
+        let key = rel
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        if let Some((base, _)) = split_surface_map(std::slice::from_ref(&key)) {
+            groups.entry(base).or_default().push(key);
+        }
+    }
+
+    groups
+        .into_values()
+        .filter(|keys| keys.len() >= 2)
+        .flatten()
+        .collect()
+}
+
+/// Splits a texture map's path into its set path and map suffix.
+///
+/// `rocks/granite_normal.png` yields `rocks/granite` and `_normal`.
+fn split_surface_map(path: &[String]) -> Option<(Vec<String>, &'static str)> {
+    let (file, _) = path.split_last()?;
+    let stem = file.strip_suffix(".png").unwrap_or(file);
+    let (name, suffix) = SURFACE_MAPS
+        .iter()
+        .find_map(|suffix| Some((stem.strip_suffix(suffix)?, *suffix)))?;
+    if name.is_empty() || name.ends_with('/') {
+        return None;
+    }
+
+    let mut base = path.to_vec();
+    let last = base.len() - 1;
+    base[last] = name.to_string();
+    Some((base, suffix))
+}
+
+fn contains_path(assets: &BTreeMap<String, AssetValue>, path: &[String]) -> bool {
+    let Some((last, parents)) = path.split_last() else {
+        return false;
+    };
+    let mut map = assets;
+    for segment in parents {
+        match map.get(segment) {
+            Some(AssetValue::Table(child)) => map = child,
+            Some(_) => return true,
+            None => return false,
+        }
+    }
+    map.contains_key(last)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn meta(id: &str) -> AssetValue {
+        AssetValue::Object(AssetMeta {
+            id: id.to_string(),
+            width: Some(512),
+            height: Some(512),
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn texture_maps_merge_into_a_surface() {
+        let mut rocks = BTreeMap::new();
+        rocks.insert("granite_color.png".to_string(), meta("1"));
+        rocks.insert("granite_normal.png".to_string(), meta("2"));
+        rocks.insert("granite_roughness.png".to_string(), meta("3"));
+        rocks.insert("moss_color.png".to_string(), meta("4"));
+        let mut assets = BTreeMap::new();
+        assets.insert("rocks".to_string(), AssetValue::Table(rocks));
+
+        merge_surface_sets(&mut assets);
+
+        let AssetValue::Table(rocks) = &assets["rocks"] else {
+            panic!("rocks should stay a table");
+        };
+        let Some(AssetValue::Surface(granite)) = rocks.get("granite") else {
+            panic!("granite should be a surface");
+        };
+        assert_eq!(granite.color_map.as_ref().unwrap().id, "1");
+        assert_eq!(granite.normal_map.as_ref().unwrap().id, "2");
+        assert!(granite.metalness_map.is_none());
+        assert_eq!(granite.roughness_map.as_ref().unwrap().id, "3");
+        assert!(!rocks.contains_key("granite_color.png"));
+        // A lone map is not a set.
+        assert!(rocks.contains_key("moss_color.png"));
+    }
+
+    #[test]
+    fn flat_keys_are_grouped() {
+        let mut assets = BTreeMap::new();
+        assets.insert("rocks/granite_color.png".to_string(), meta("1"));
+        assets.insert("rocks/granite_normal.png".to_string(), meta("2"));
+
+        merge_surface_sets(&mut assets);
+
+        assert!(matches!(
+            assets.get("rocks/granite"),
+            Some(AssetValue::Surface(_))
+        ));
+        assert_eq!(assets.len(), 1);
+    }
+
+    #[test]
+    fn conflicting_names_are_left_alone() {
+        let mut assets = BTreeMap::new();
+        assets.insert("granite".to_string(), meta("0"));
+        assets.insert("granite_color".to_string(), meta("1"));
+        assets.insert("granite_normal".to_string(), meta("2"));
+
+        merge_surface_sets(&mut assets);
+
+        assert_eq!(assets.len(), 3);
+    }
+}
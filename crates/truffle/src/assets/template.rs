@@ -0,0 +1,82 @@
+use std::ops::Range;
+
+/// Placeholders a codegen template can use, each written as `{{ name }}`.
+pub const PLACEHOLDERS: [&str; 4] = ["header", "types", "assets", "animations"];
+
+/// Checks that every placeholder of `template` is one of [`PLACEHOLDERS`].
+pub fn check_template(template: &str) -> Result<(), String> {
+    for (_, name) in placeholders(template) {
+        if !PLACEHOLDERS.contains(&name) {
+            return Err(format!(
+                "Unknown placeholder {{{{ {} }}}} (expected one of: {})",
+                name,
+                PLACEHOLDERS.join(", ")
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Replaces the placeholders of `template` with their `values`. Values are
+/// inserted without trailing whitespace, so templates control the spacing.
+pub fn fill_template(template: &str, values: &[(&str, &str)]) -> String {
+    let mut output = String::with_capacity(template.len());
+    let mut rest = 0;
+    for (range, name) in placeholders(template) {
+        output.push_str(&template[rest..range.start]);
+        if let Some((_, value)) = values.iter().find(|(key, _)| *key == name) {
+            output.push_str(value.trim_end());
+        }
+        rest = range.end;
+    }
+    output.push_str(&template[rest..]);
+    output
+}
+
+/// The `{{ word }}` placeholders of `template`. Braces around anything but
+/// a single word (`{{ assets = assets }}` in Luau) are left alone.
+fn placeholders(template: &str) -> Vec<(Range<usize>, &str)> {
+    let mut found = Vec::new();
+    let mut at = 0;
+    while let Some(start) = template[at..].find("{{").map(|i| at + i) {
+        let Some(end) = template[start + 2..].find("}}").map(|i| start + 2 + i) else {
+            break;
+        };
+        let name = template[start + 2..end].trim();
+        let is_word =
+            !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+        if is_word {
+            found.push((start..end + 2, name));
+            at = end + 2;
+        } else {
+            at = start + 2;
+        }
+    }
+    found
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fills_known_placeholders() {
+        let template = "{{header}}\nlocal Assets = {{ assets }}\nreturn { Assets = Assets }\n";
+        assert!(check_template(template).is_ok());
+        assert_eq!(
+            fill_template(
+                template,
+                &[("header", "-- generated\n"), ("assets", "{\n\ta = 1,\n}\n")]
+            ),
+            "-- generated\nlocal Assets = {\n\ta = 1,\n}\nreturn { Assets = Assets }\n"
+        );
+
+        let luau_table = "return {{ assets = assets }}";
+        assert!(check_template(luau_table).is_ok());
+        assert_eq!(fill_template(luau_table, &[]), luau_table);
+
+        assert!(check_template("{{ asset }}")
+            .unwrap_err()
+            .contains("{{ asset }}"));
+    }
+}
@@ -0,0 +1,514 @@
+use super::model::{AssetMeta, AssetValue};
+use std::collections::BTreeMap;
+
+/// Locales recognized when merging localized images.
+#[derive(Debug, Clone, Default)]
+pub struct LocaleOptions {
+    pub locales: Vec<String>,
+    pub default_locale: Option<String>,
+}
+
+/// Folds per-locale images (`ui/de/play.png` or `ui/play@de.png`) into the
+/// `locales` map of their base entry (`ui/play.png`).
+///
+/// When no unlocalized base image exists, the default locale (or the first
+/// configured locale that is present) provides the base metadata.
+pub fn merge_locale_variants(assets: &mut BTreeMap<String, AssetValue>, options: &LocaleOptions) {
+    merge_variants(
+        assets,
+        &options.locales,
+        options.default_locale.as_deref(),
+        |meta| &mut meta.locales,
+    );
+}
+
+/// Themes recognized when merging themed images.
+#[derive(Debug, Clone, Default)]
+pub struct ThemeOptions {
+    pub themes: Vec<String>,
+    pub default_theme: Option<String>,
+}
+
+/// Folds per-theme images (`ui/dark/play.png` or `ui/play@dark.png`) into the
+/// `variants` map of their base entry (`ui/play.png`).
+///
+/// Base entries are picked the same way as for [`merge_locale_variants`].
+pub fn merge_theme_variants(assets: &mut BTreeMap<String, AssetValue>, options: &ThemeOptions) {
+    merge_variants(
+        assets,
+        &options.themes,
+        options.default_theme.as_deref(),
+        |meta| &mut meta.variants,
+    );
+}
+
+/// A scaled image's path, suffix name (`2x`) and metadata.
+type ScaledLeaf = (Vec<String>, String, AssetMeta);
+
+/// Folds `@2x`/`@3x` images into the `scales` map of their base entry.
+///
+/// Each scaled entry carries its `scale` factor. Images whose size is not an
+/// exact multiple of the base are reported and left where they are.
+pub fn merge_scale_variants(assets: &mut BTreeMap<String, AssetValue>) {
+    let mut leaves = Vec::new();
+    collect_leaves(assets, &mut Vec::new(), &mut leaves);
+
+    let mut groups: BTreeMap<Vec<String>, Vec<ScaledLeaf>> = BTreeMap::new();
+    for (path, mut meta) in leaves {
+        let Some((base_path, name)) = split_suffix(&path) else {
+            continue;
+        };
+        let Some(scale) = parse_scale(&name) else {
+            continue;
+        };
+        meta.scale = Some(scale);
+        groups
+            .entry(base_path)
+            .or_default()
+            .push((path, name, meta));
+    }
+
+    for (base_path, scaled) in groups {
+        let Some(mut base) = get_leaf(assets, &base_path).cloned() else {
+            continue;
+        };
+
+        let mut scales = base.scales.take().unwrap_or_default();
+        for (path, name, meta) in scaled {
+            let scale = meta.scale.unwrap_or(1);
+            let expected = (
+                base.width.unwrap_or(0) * scale,
+                base.height.unwrap_or(0) * scale,
+            );
+            let actual = (meta.width.unwrap_or(0), meta.height.unwrap_or(0));
+            if actual != expected {
+                println!(
+                    "[sync] WARN: {} is {}x{}, expected {}x{} ({}x of {}) – not merging scale variant.",
+                    path.join("/"),
+                    actual.0,
+                    actual.1,
+                    expected.0,
+                    expected.1,
+                    scale,
+                    base_path.join("/")
+                );
+                continue;
+            }
+
+            remove_leaf(assets, &path);
+            scales.insert(name, meta);
+        }
+
+        if scales.is_empty() {
+            continue;
+        }
+        base.scales = Some(scales);
+        insert_leaf(assets, &base_path, base);
+    }
+}
+
+fn parse_scale(name: &str) -> Option<u32> {
+    let scale: u32 = name.strip_suffix('x')?.parse().ok()?;
+    if scale >= 2 {
+        Some(scale)
+    } else {
+        None
+    }
+}
+
+fn merge_variants(
+    assets: &mut BTreeMap<String, AssetValue>,
+    names: &[String],
+    default_name: Option<&str>,
+    field: fn(&mut AssetMeta) -> &mut Option<BTreeMap<String, AssetMeta>>,
+) {
+    if names.is_empty() {
+        return;
+    }
+
+    let mut leaves = Vec::new();
+    collect_leaves(assets, &mut Vec::new(), &mut leaves);
+
+    let mut groups: BTreeMap<Vec<String>, BTreeMap<String, AssetMeta>> = BTreeMap::new();
+    for (path, meta) in leaves {
+        let Some((base_path, name)) = split_variant(&path, names) else {
+            continue;
+        };
+        remove_leaf(assets, &path);
+        groups.entry(base_path).or_default().insert(name, meta);
+    }
+
+    for (base_path, variants) in groups {
+        let mut base = match get_leaf(assets, &base_path) {
+            Some(meta) => meta.clone(),
+            None => {
+                let fallback = default_name
+                    .filter(|name| variants.contains_key(*name))
+                    .or_else(|| {
+                        names
+                            .iter()
+                            .map(String::as_str)
+                            .find(|name| variants.contains_key(*name))
+                    })
+                    .unwrap_or_else(|| variants.keys().next().unwrap());
+                variants[fallback].clone()
+            }
+        };
+
+        field(&mut base)
+            .get_or_insert_with(BTreeMap::new)
+            .extend(variants);
+        insert_leaf(assets, &base_path, base);
+    }
+}
+
+/// Splits a leaf path into its base path and variant name, if it names one.
+///
+/// Keys may be nested tables or flat `a/b/c.png` keys, so folder names are
+/// matched against every `/`-separated component.
+fn split_variant(path: &[String], names: &[String]) -> Option<(Vec<String>, String)> {
+    let is_name = |part: &str| names.iter().any(|name| name == part);
+    let last = path.len().checked_sub(1)?;
+
+    for (index, segment) in path.iter().enumerate() {
+        let parts: Vec<&str> = segment.split('/').collect();
+        let dir_parts = if index == last {
+            parts.len() - 1
+        } else {
+            parts.len()
+        };
+        let Some(found) = parts[..dir_parts].iter().position(|part| is_name(part)) else {
+            continue;
+        };
+
+        let remaining: Vec<&str> = parts
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| *i != found)
+            .map(|(_, part)| *part)
+            .collect();
+        let mut base = path.to_vec();
+        if remaining.is_empty() {
+            base.remove(index);
+        } else {
+            base[index] = remaining.join("/");
+        }
+        return Some((base, parts[found].to_string()));
+    }
+
+    let (base, name) = split_suffix(path)?;
+    if is_name(&name) {
+        Some((base, name))
+    } else {
+        None
+    }
+}
+
+/// Splits an `@<name>` suffix off the file name at the end of `path`.
+///
+/// `play@de.png` yields `play.png`, and `play@de-highlight.png` yields
+/// `play-highlight.png` so generated highlights follow their source image.
+fn split_suffix(path: &[String]) -> Option<(Vec<String>, String)> {
+    let (file, _) = path.split_last()?;
+    let (dir, file_name) = match file.rfind('/') {
+        Some(index) => file.split_at(index + 1),
+        None => ("", file.as_str()),
+    };
+    let (stem, ext) = match file_name.rfind('.') {
+        Some(index) => file_name.split_at(index),
+        None => (file_name, ""),
+    };
+    let (stem, suffix) = match stem.strip_suffix("-highlight") {
+        Some(stem) => (stem, "-highlight"),
+        None => (stem, ""),
+    };
+    let (base_stem, name) = stem.rsplit_once('@')?;
+    if base_stem.is_empty() || name.is_empty() {
+        return None;
+    }
+
+    let mut base = path.to_vec();
+    let last = base.len() - 1;
+    base[last] = format!("{}{}{}{}", dir, base_stem, suffix, ext);
+    Some((base, name.to_string()))
+}
+
+pub(crate) fn collect_leaves(
+    assets: &BTreeMap<String, AssetValue>,
+    path: &mut Vec<String>,
+    out: &mut Vec<(Vec<String>, AssetMeta)>,
+) {
+    for (key, value) in assets {
+        path.push(key.clone());
+        match value {
+            AssetValue::Object(meta) => out.push((path.clone(), meta.clone())),
+            AssetValue::Table(map) => collect_leaves(map, path, out),
+            _ => {}
+        }
+        path.pop();
+    }
+}
+
+fn get_leaf<'a>(
+    assets: &'a BTreeMap<String, AssetValue>,
+    path: &[String],
+) -> Option<&'a AssetMeta> {
+    let (last, parents) = path.split_last()?;
+    let mut map = assets;
+    for segment in parents {
+        match map.get(segment)? {
+            AssetValue::Table(child) => map = child,
+            _ => return None,
+        }
+    }
+    match map.get(last)? {
+        AssetValue::Object(meta) => Some(meta),
+        _ => None,
+    }
+}
+
+fn insert_leaf(assets: &mut BTreeMap<String, AssetValue>, path: &[String], meta: AssetMeta) {
+    insert_value(assets, path, AssetValue::Object(meta));
+}
+
+/// Inserts `value` at `path`, creating (or replacing) tables along the way.
+pub(crate) fn insert_value(
+    assets: &mut BTreeMap<String, AssetValue>,
+    path: &[String],
+    value: AssetValue,
+) {
+    let Some((head, rest)) = path.split_first() else {
+        return;
+    };
+
+    if rest.is_empty() {
+        assets.insert(head.clone(), value);
+        return;
+    }
+
+    let entry = assets
+        .entry(head.clone())
+        .or_insert_with(|| AssetValue::Table(BTreeMap::new()));
+    if !matches!(entry, AssetValue::Table(_)) {
+        *entry = AssetValue::Table(BTreeMap::new());
+    }
+    if let AssetValue::Table(map) = entry {
+        insert_value(map, rest, value);
+    }
+}
+
+/// Removes the leaf at `path`, pruning tables left empty by the removal.
+pub(crate) fn remove_leaf(assets: &mut BTreeMap<String, AssetValue>, path: &[String]) {
+    let Some((head, rest)) = path.split_first() else {
+        return;
+    };
+
+    if rest.is_empty() {
+        assets.remove(head);
+        return;
+    }
+
+    if let Some(AssetValue::Table(map)) = assets.get_mut(head) {
+        remove_leaf(map, rest);
+        if map.is_empty() {
+            assets.remove(head);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn meta(id: &str) -> AssetValue {
+        AssetValue::Object(AssetMeta {
+            id: id.to_string(),
+            width: Some(64),
+            height: Some(32),
+            ..Default::default()
+        })
+    }
+
+    fn table(entries: &[(&str, AssetValue)]) -> AssetValue {
+        AssetValue::Table(
+            entries
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.clone()))
+                .collect(),
+        )
+    }
+
+    fn options(default_locale: Option<&str>) -> LocaleOptions {
+        LocaleOptions {
+            locales: vec!["en".into(), "de".into()],
+            default_locale: default_locale.map(str::to_string),
+        }
+    }
+
+    fn leaf<'a>(assets: &'a BTreeMap<String, AssetValue>, path: &[&str]) -> &'a AssetMeta {
+        let path: Vec<String> = path.iter().map(|s| s.to_string()).collect();
+        get_leaf(assets, &path).expect("missing leaf")
+    }
+
+    #[test]
+    fn merges_locale_folders_into_base_entry() {
+        let mut assets = BTreeMap::new();
+        assets.insert(
+            "ui".to_string(),
+            table(&[
+                ("en", table(&[("play.png", meta("rbxassetid://1"))])),
+                ("de", table(&[("play.png", meta("rbxassetid://2"))])),
+            ]),
+        );
+
+        merge_locale_variants(&mut assets, &options(Some("de")));
+
+        let play = leaf(&assets, &["ui", "play.png"]);
+        assert_eq!(play.id, "rbxassetid://2");
+        let locales = play.locales.as_ref().unwrap();
+        assert_eq!(locales["en"].id, "rbxassetid://1");
+        assert_eq!(locales["de"].id, "rbxassetid://2");
+
+        let AssetValue::Table(ui) = &assets["ui"] else {
+            panic!("ui should stay a table");
+        };
+        assert!(!ui.contains_key("en"));
+        assert!(!ui.contains_key("de"));
+    }
+
+    #[test]
+    fn merges_locale_suffixes_and_keeps_existing_base() {
+        let mut assets = BTreeMap::new();
+        assets.insert(
+            "ui".to_string(),
+            table(&[
+                ("play.png", meta("rbxassetid://1")),
+                ("play@de.png", meta("rbxassetid://2")),
+                ("play@de-highlight.png", meta("rbxassetid://3")),
+                ("play-highlight.png", meta("rbxassetid://4")),
+            ]),
+        );
+
+        merge_locale_variants(&mut assets, &options(None));
+
+        let play = leaf(&assets, &["ui", "play.png"]);
+        assert_eq!(play.id, "rbxassetid://1");
+        assert_eq!(play.locales.as_ref().unwrap()["de"].id, "rbxassetid://2");
+
+        let highlight = leaf(&assets, &["ui", "play-highlight.png"]);
+        assert_eq!(highlight.id, "rbxassetid://4");
+        assert_eq!(
+            highlight.locales.as_ref().unwrap()["de"].id,
+            "rbxassetid://3"
+        );
+
+        let AssetValue::Table(ui) = &assets["ui"] else {
+            panic!("ui should stay a table");
+        };
+        assert_eq!(ui.len(), 2);
+    }
+
+    #[test]
+    fn merges_flat_keys() {
+        let mut assets = BTreeMap::new();
+        assets.insert("ui/en/play.png".to_string(), meta("rbxassetid://1"));
+        assets.insert("ui/de/play.png".to_string(), meta("rbxassetid://2"));
+        assets.insert("ui/back@de.png".to_string(), meta("rbxassetid://3"));
+
+        merge_locale_variants(&mut assets, &options(None));
+
+        let play = leaf(&assets, &["ui/play.png"]);
+        assert_eq!(play.id, "rbxassetid://1");
+        assert_eq!(play.locales.as_ref().unwrap()["de"].id, "rbxassetid://2");
+        let back = leaf(&assets, &["ui/back.png"]);
+        assert_eq!(back.id, "rbxassetid://3");
+        assert_eq!(assets.len(), 2);
+    }
+
+    #[test]
+    fn merges_themes_of_localized_assets() {
+        let mut assets = BTreeMap::new();
+        assets.insert(
+            "ui".to_string(),
+            table(&[
+                ("light", table(&[("play.png", meta("rbxassetid://1"))])),
+                (
+                    "dark",
+                    table(&[
+                        ("play.png", meta("rbxassetid://2")),
+                        ("play@de.png", meta("rbxassetid://3")),
+                    ]),
+                ),
+            ]),
+        );
+
+        merge_locale_variants(&mut assets, &options(None));
+        merge_theme_variants(
+            &mut assets,
+            &ThemeOptions {
+                themes: vec!["light".into(), "dark".into()],
+                default_theme: Some("light".into()),
+            },
+        );
+
+        let play = leaf(&assets, &["ui", "play.png"]);
+        assert_eq!(play.id, "rbxassetid://1");
+        let dark = &play.variants.as_ref().unwrap()["dark"];
+        assert_eq!(dark.id, "rbxassetid://2");
+        assert_eq!(dark.locales.as_ref().unwrap()["de"].id, "rbxassetid://3");
+    }
+
+    #[test]
+    fn merges_scale_suffixes_with_matching_sizes() {
+        fn sized(id: &str, width: u32, height: u32) -> AssetValue {
+            AssetValue::Object(AssetMeta {
+                id: id.to_string(),
+                width: Some(width),
+                height: Some(height),
+                ..Default::default()
+            })
+        }
+
+        let mut assets = BTreeMap::new();
+        assets.insert(
+            "ui".to_string(),
+            table(&[
+                ("icon.png", sized("rbxassetid://1", 16, 8)),
+                ("icon@3x.png", sized("rbxassetid://3", 48, 24)),
+                ("icon@2x.png", sized("rbxassetid://2", 32, 16)),
+                ("icon@4x.png", sized("rbxassetid://4", 60, 32)),
+            ]),
+        );
+
+        merge_scale_variants(&mut assets);
+
+        let icon = leaf(&assets, &["ui", "icon.png"]);
+        let scales = icon.scales.as_ref().unwrap();
+        assert_eq!(scales.len(), 2);
+        assert_eq!(scales["2x"].id, "rbxassetid://2");
+        assert_eq!(scales["2x"].scale, Some(2));
+        assert_eq!(scales["3x"].scale, Some(3));
+
+        // Mismatched sizes stay as standalone entries.
+        assert_eq!(leaf(&assets, &["ui", "icon@4x.png"]).id, "rbxassetid://4");
+    }
+
+    #[test]
+    fn ignores_unknown_suffixes_and_unconfigured_locales() {
+        let mut assets = BTreeMap::new();
+        assets.insert(
+            "ui".to_string(),
+            table(&[
+                ("icon@2x.png", meta("rbxassetid://1")),
+                ("fr", table(&[("play.png", meta("rbxassetid://2"))])),
+            ]),
+        );
+        let before = assets.clone();
+
+        merge_locale_variants(&mut assets, &options(None));
+        assert_eq!(assets, before);
+
+        merge_locale_variants(&mut assets, &LocaleOptions::default());
+        assert_eq!(assets, before);
+    }
+}
@@ -0,0 +1,204 @@
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+mod remote;
+
+pub use remote::RemoteCache;
+
+/// Bumped whenever the layout of cached entries changes.
+const CACHE_VERSION: &[u8] = b"truffle-cache-v1";
+
+/// Content-addressed store for expensive intermediates (highlights, SVG
+/// rasters, atlas pages, font atlases) and atlas manifests.
+///
+/// Entries are keyed by a hash of everything that affects the output, so a
+/// rerun or a branch switch back to known content reuses earlier work. Each
+/// entry is a directory of named files at `<dir>/<key[..2]>/<key>/`. With a
+/// [`RemoteCache`], local misses are fetched from the shared store and new
+/// entries are published to it.
+#[derive(Debug, Clone)]
+pub struct ContentCache {
+    dir: PathBuf,
+    remote: Option<RemoteCache>,
+}
+
+impl ContentCache {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: dir.into(),
+            remote: None,
+        }
+    }
+
+    pub fn with_remote(mut self, remote: Option<RemoteCache>) -> Self {
+        self.remote = remote;
+        self
+    }
+
+    /// Hashes the inputs of an intermediate into a cache key. `kind` keeps
+    /// different intermediates with equal inputs apart.
+    pub fn key(kind: &str, parts: &[&[u8]]) -> String {
+        let mut hasher = blake3::Hasher::new();
+        hasher.update(CACHE_VERSION);
+        for part in std::iter::once(kind.as_bytes()).chain(parts.iter().copied()) {
+            hasher.update(&(part.len() as u64).to_le_bytes());
+            hasher.update(part);
+        }
+        hasher.finalize().to_hex().to_string()
+    }
+
+    fn object_path(key: &str, name: &str) -> String {
+        format!("{}/{}/{}", &key[..2], key, name)
+    }
+
+    fn local_path(&self, key: &str, name: &str) -> PathBuf {
+        self.dir.join(Self::object_path(key, name))
+    }
+
+    /// Makes sure `name` of `key` is cached locally, downloading it from the
+    /// remote store if needed.
+    fn fetch(&self, key: &str, name: &str) -> bool {
+        let path = self.local_path(key, name);
+        if path.is_file() {
+            return true;
+        }
+        let Some(remote) = &self.remote else {
+            return false;
+        };
+        match remote.get(&Self::object_path(key, name)) {
+            Ok(Some(bytes)) => write_atomic(&path, &bytes).is_ok(),
+            Ok(None) => false,
+            Err(e) => {
+                println!("[cache] WARN: {:#}", e);
+                false
+            }
+        }
+    }
+
+    fn publish(&self, key: &str, name: &str, bytes: Vec<u8>) {
+        if let Some(remote) = self.remote.as_ref().filter(|r| !r.read_only()) {
+            if let Err(e) = remote.put(&Self::object_path(key, name), bytes) {
+                println!("[cache] WARN: {:#}", e);
+            }
+        }
+    }
+
+    /// Copies the cached files of `key` to their destinations. Returns `false`
+    /// without touching anything unless every file is cached.
+    pub fn restore(&self, key: &str, outputs: &[(&str, &Path)]) -> bool {
+        if !outputs.iter().all(|(name, _)| self.fetch(key, name)) {
+            return false;
+        }
+
+        outputs.iter().all(|(name, dest)| {
+            if let Some(parent) = dest.parent() {
+                std::fs::create_dir_all(parent).ok();
+            }
+            std::fs::copy(self.local_path(key, name), dest).is_ok()
+        })
+    }
+
+    /// Stores freshly generated files under `key`.
+    pub fn store(&self, key: &str, outputs: &[(&str, &Path)]) -> Result<()> {
+        for (name, source) in outputs {
+            let bytes = std::fs::read(source)
+                .with_context(|| format!("Failed to cache {}", source.display()))?;
+            self.put(key, name, bytes)?;
+        }
+        Ok(())
+    }
+
+    /// Reads a single cached file.
+    pub fn get(&self, key: &str, name: &str) -> Option<Vec<u8>> {
+        if !self.fetch(key, name) {
+            return None;
+        }
+        std::fs::read(self.local_path(key, name)).ok()
+    }
+
+    /// Caches a single file.
+    pub fn put(&self, key: &str, name: &str, bytes: Vec<u8>) -> Result<()> {
+        let path = self.local_path(key, name);
+        write_atomic(&path, &bytes)
+            .with_context(|| format!("Failed to write cache entry {}", path.display()))?;
+        self.publish(key, name, bytes);
+        Ok(())
+    }
+}
+
+/// Writes to a temporary name first so readers never see partial files. The
+/// name is unique per call, as threads may write the same entry at once.
+fn write_atomic(path: &Path, bytes: &[u8]) -> std::io::Result<()> {
+    static WRITES: AtomicU64 = AtomicU64::new(0);
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(format!(
+        ".tmp-{}-{}",
+        std::process::id(),
+        WRITES.fetch_add(1, Ordering::Relaxed)
+    ));
+    std::fs::write(&tmp, bytes)?;
+    std::fs::rename(&tmp, path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keys_depend_on_kind_and_part_boundaries() {
+        let key = ContentCache::key("highlight", &[b"ab", b"c"]);
+        assert_eq!(key, ContentCache::key("highlight", &[b"ab", b"c"]));
+        assert_ne!(key, ContentCache::key("highlight", &[b"a", b"bc"]));
+        assert_ne!(key, ContentCache::key("svg", &[b"ab", b"c"]));
+    }
+
+    #[test]
+    fn stored_files_are_restored() {
+        let dir = std::env::temp_dir().join(format!("truffle-cache-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let cache = ContentCache::new(dir.join("cache"));
+        let key = ContentCache::key("test", &[b"input"]);
+        let output = dir.join("out.png");
+
+        assert!(!cache.restore(&key, &[("out.png", &output)]));
+        std::fs::write(&output, b"pixels").unwrap();
+        cache.store(&key, &[("out.png", &output)]).unwrap();
+        std::fs::remove_file(&output).unwrap();
+
+        let restored = dir.join("nested/out.png");
+        assert!(cache.restore(&key, &[("out.png", &restored)]));
+        assert_eq!(std::fs::read(&restored).unwrap(), b"pixels");
+        // Partially cached entries are misses.
+        assert!(!cache.restore(&key, &[("out.png", &restored), ("meta.luau", &output)]));
+
+        cache.put(&key, "ids.json", b"{}".to_vec()).unwrap();
+        assert_eq!(cache.get(&key, "ids.json").unwrap(), b"{}");
+        assert!(cache.get(&key, "missing.json").is_none());
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn concurrent_writes_of_one_entry_stay_whole() {
+        let dir = std::env::temp_dir().join(format!("truffle-cache-race-{}", std::process::id()));
+        let path = dir.join("entry.bin");
+        std::thread::scope(|scope| {
+            for byte in 0..8u8 {
+                let path = &path;
+                scope.spawn(move || {
+                    for _ in 0..20 {
+                        write_atomic(path, &vec![byte; 64 * 1024]).unwrap();
+                    }
+                });
+            }
+        });
+        let bytes = std::fs::read(&path).unwrap();
+        assert_eq!(bytes.len(), 64 * 1024);
+        assert!(bytes.iter().all(|&b| b == bytes[0]));
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
@@ -0,0 +1,474 @@
+use anyhow::{Context, Result};
+use hmac::{Hmac, Mac};
+use reqwest::StatusCode;
+use sha2::{Digest, Sha256};
+use std::sync::{mpsc, Arc};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::runtime::Runtime;
+use truffle_config::RemoteCacheOptions;
+
+/// Bearer token sent to plain HTTP cache servers.
+const TOKEN_ENV: &str = "TRUFFLE_CACHE_TOKEN";
+
+/// Shared store that backs the local content-addressed cache.
+#[derive(Debug, Clone)]
+pub struct RemoteCache {
+    backend: Backend,
+    read_only: bool,
+    client: reqwest::Client,
+    runtime: Arc<RequestRuntime>,
+}
+
+/// Runtime the requests of a [`RemoteCache`] and its clones run on.
+#[derive(Debug)]
+struct RequestRuntime(Option<Runtime>);
+
+impl Drop for RequestRuntime {
+    fn drop(&mut self) {
+        // The last clone may be dropped inside another runtime, where a
+        // blocking shutdown would panic.
+        if let Some(runtime) = self.0.take() {
+            runtime.shutdown_background();
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Backend {
+    /// Objects live at `<base>/<path>` and are read with GET, written with PUT.
+    Http { base: String, token: Option<String> },
+    /// Objects live at `<endpoint>/<bucket>/<prefix>/<path>`, signed with SigV4.
+    S3 {
+        endpoint: String,
+        bucket: String,
+        prefix: String,
+        region: String,
+        credentials: Credentials,
+    },
+}
+
+#[derive(Clone)]
+struct Credentials {
+    access_key: String,
+    secret_key: String,
+    session_token: Option<String>,
+}
+
+impl std::fmt::Debug for Credentials {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Credentials")
+            .field("access_key", &self.access_key)
+            .finish_non_exhaustive()
+    }
+}
+
+impl RemoteCache {
+    /// Builds a remote cache from truffle.toml. S3 credentials come from the
+    /// standard `AWS_ACCESS_KEY_ID`/`AWS_SECRET_ACCESS_KEY` variables.
+    pub fn from_options(options: &RemoteCacheOptions) -> Result<Self> {
+        Self::from_options_with(options, |name| std::env::var(name).ok())
+    }
+
+    /// Like [`RemoteCache::from_options`], reading credentials through `var`.
+    fn from_options_with(
+        options: &RemoteCacheOptions,
+        var: impl Fn(&str) -> Option<String>,
+    ) -> Result<Self> {
+        let env = |name: &str| var(name).filter(|v| !v.is_empty());
+        let url = options.url.trim_end_matches('/');
+        let backend = if let Some(location) = url.strip_prefix("s3://") {
+            let (bucket, prefix) = location.split_once('/').unwrap_or((location, ""));
+            if bucket.is_empty() {
+                anyhow::bail!("Remote cache URL is missing a bucket: {}", options.url);
+            }
+            let credentials = Credentials {
+                access_key: env("AWS_ACCESS_KEY_ID")
+                    .context("AWS_ACCESS_KEY_ID must be set for an s3:// remote cache")?,
+                secret_key: env("AWS_SECRET_ACCESS_KEY")
+                    .context("AWS_SECRET_ACCESS_KEY must be set for an s3:// remote cache")?,
+                session_token: env("AWS_SESSION_TOKEN"),
+            };
+            Backend::S3 {
+                endpoint: options
+                    .endpoint
+                    .clone()
+                    .unwrap_or_else(|| format!("https://s3.{}.amazonaws.com", options.region))
+                    .trim_end_matches('/')
+                    .to_string(),
+                bucket: bucket.to_string(),
+                prefix: prefix.to_string(),
+                region: options.region.clone(),
+                credentials,
+            }
+        } else if url.starts_with("http://") || url.starts_with("https://") {
+            Backend::Http {
+                base: url.to_string(),
+                token: env(TOKEN_ENV),
+            }
+        } else {
+            anyhow::bail!(
+                "Unsupported remote cache URL (expected s3:// or http(s)://): {}",
+                options.url
+            );
+        };
+
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(2)
+            .thread_name("truffle-remote-cache")
+            .enable_all()
+            .build()
+            .context("Failed to create tokio runtime")?;
+        Ok(Self {
+            backend,
+            read_only: options.read_only,
+            client: reqwest::Client::new(),
+            runtime: Arc::new(RequestRuntime(Some(runtime))),
+        })
+    }
+
+    pub fn read_only(&self) -> bool {
+        self.read_only
+    }
+
+    /// Downloads an object, returning `None` if the store doesn't have it.
+    pub fn get(&self, path: &str) -> Result<Option<Vec<u8>>> {
+        let request = self.request(reqwest::Method::GET, path, Vec::new());
+        self.block_on(async move {
+            let response = request?.send().await?;
+            if response.status() == StatusCode::NOT_FOUND {
+                return Ok(None);
+            }
+            let response = response.error_for_status()?;
+            Ok(Some(response.bytes().await?.to_vec()))
+        })
+        .with_context(|| format!("Failed to download {} from the remote cache", path))
+    }
+
+    /// Uploads an object. Does nothing for read-only caches.
+    pub fn put(&self, path: &str, bytes: Vec<u8>) -> Result<()> {
+        if self.read_only {
+            return Ok(());
+        }
+        let request = self.request(reqwest::Method::PUT, path, bytes);
+        self.block_on(async move {
+            request?.send().await?.error_for_status()?;
+            Ok(())
+        })
+        .with_context(|| format!("Failed to upload {} to the remote cache", path))
+    }
+
+    fn request(
+        &self,
+        method: reqwest::Method,
+        path: &str,
+        body: Vec<u8>,
+    ) -> Result<reqwest::RequestBuilder> {
+        let client = &self.client;
+        match &self.backend {
+            Backend::Http { base, token } => {
+                let mut request = client
+                    .request(method, format!("{}/{}", base, path))
+                    .body(body);
+                if let Some(token) = token {
+                    request = request.bearer_auth(token);
+                }
+                Ok(request)
+            }
+            Backend::S3 {
+                endpoint,
+                bucket,
+                prefix,
+                region,
+                credentials,
+            } => {
+                let key = if prefix.is_empty() {
+                    path.to_string()
+                } else {
+                    format!("{}/{}", prefix, path)
+                };
+                let uri = uri_encode_path(&format!("/{}/{}", bucket, key));
+                let host = endpoint
+                    .split_once("://")
+                    .map_or(endpoint.as_str(), |(_, host)| host);
+                let headers = sign_s3(
+                    method.as_str(),
+                    host,
+                    &uri,
+                    &body,
+                    region,
+                    credentials,
+                    SystemTime::now(),
+                );
+
+                let mut request = client
+                    .request(method, format!("{}{}", endpoint, uri))
+                    .body(body);
+                for (name, value) in headers {
+                    request = request.header(name, value);
+                }
+                Ok(request)
+            }
+        }
+    }
+}
+
+impl RemoteCache {
+    /// Runs a request on the cache's runtime and waits for it. Cache lookups
+    /// happen in synchronous code that may itself be inside a tokio runtime,
+    /// so the request is spawned rather than run with `Runtime::block_on`.
+    fn block_on<T: Send + 'static>(
+        &self,
+        future: impl std::future::Future<Output = Result<T>> + Send + 'static,
+    ) -> Result<T> {
+        let Some(runtime) = &self.runtime.0 else {
+            anyhow::bail!("Remote cache runtime is shut down");
+        };
+        let (sender, receiver) = mpsc::channel();
+        runtime.spawn(async move {
+            sender.send(future.await).ok();
+        });
+        receiver
+            .recv()
+            .unwrap_or_else(|_| Err(anyhow::anyhow!("Remote cache request panicked")))
+    }
+}
+
+/// Computes AWS Signature Version 4 headers for a path-style S3 request.
+fn sign_s3(
+    method: &str,
+    host: &str,
+    uri: &str,
+    body: &[u8],
+    region: &str,
+    credentials: &Credentials,
+    now: SystemTime,
+) -> Vec<(&'static str, String)> {
+    let (date, amz_date) = amz_dates(now);
+    let payload_hash = hex(&Sha256::digest(body));
+
+    let mut headers = vec![
+        ("host", host.to_string()),
+        ("x-amz-content-sha256", payload_hash.clone()),
+        ("x-amz-date", amz_date.clone()),
+    ];
+    if let Some(token) = &credentials.session_token {
+        headers.push(("x-amz-security-token", token.clone()));
+    }
+
+    let canonical_headers: String = headers
+        .iter()
+        .map(|(name, value)| format!("{}:{}\n", name, value.trim()))
+        .collect();
+    let signed_headers = headers
+        .iter()
+        .map(|(name, _)| *name)
+        .collect::<Vec<_>>()
+        .join(";");
+    let canonical_request = format!(
+        "{}\n{}\n\n{}\n{}\n{}",
+        method, uri, canonical_headers, signed_headers, payload_hash
+    );
+
+    let scope = format!("{}/{}/s3/aws4_request", date, region);
+    let string_to_sign = format!(
+        "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+        amz_date,
+        scope,
+        hex(&Sha256::digest(canonical_request.as_bytes()))
+    );
+    let key = signing_key(&credentials.secret_key, &date, region, "s3");
+    let signature = hex(&hmac(&key, string_to_sign.as_bytes()));
+
+    headers.retain(|(name, _)| *name != "host");
+    headers.push((
+        "authorization",
+        format!(
+            "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+            credentials.access_key, scope, signed_headers, signature
+        ),
+    ));
+    headers
+}
+
+fn signing_key(secret_key: &str, date: &str, region: &str, service: &str) -> Vec<u8> {
+    let key = hmac(format!("AWS4{}", secret_key).as_bytes(), date.as_bytes());
+    let key = hmac(&key, region.as_bytes());
+    let key = hmac(&key, service.as_bytes());
+    hmac(&key, b"aws4_request")
+}
+
+fn hmac(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts any key length");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Percent-encodes everything but unreserved characters and `/`.
+fn uri_encode_path(path: &str) -> String {
+    let mut out = String::with_capacity(path.len());
+    for byte in path.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b'/' => {
+                out.push(byte as char)
+            }
+            _ => out.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    out
+}
+
+/// Formats `now` as SigV4's `YYYYMMDD` and `YYYYMMDDTHHMMSSZ` (UTC).
+fn amz_dates(now: SystemTime) -> (String, String) {
+    let secs = now
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let days = (secs / 86_400) as i64;
+    let time = secs % 86_400;
+
+    // Civil-from-days (Howard Hinnant's algorithm).
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    let date = format!("{:04}{:02}{:02}", year, month, day);
+    let amz_date = format!(
+        "{}T{:02}{:02}{:02}Z",
+        date,
+        time / 3_600,
+        time / 60 % 60,
+        time % 60
+    );
+    (date, amz_date)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn signing_key_matches_aws_example() {
+        // From the AWS SigV4 documentation.
+        let key = signing_key(
+            "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY",
+            "20120215",
+            "us-east-1",
+            "iam",
+        );
+        assert_eq!(
+            hex(&key),
+            "f4780e2d9f65fa895f9c67b32ce1baf0b0d8a43505a000a1a9e090d414db404d"
+        );
+    }
+
+    #[test]
+    fn dates_are_utc() {
+        let now = UNIX_EPOCH + Duration::from_secs(1_361_021_599);
+        assert_eq!(
+            amz_dates(now),
+            ("20130216".to_string(), "20130216T133319Z".to_string())
+        );
+        let leap = UNIX_EPOCH + Duration::from_secs(951_782_400);
+        assert_eq!(amz_dates(leap).0, "20000229");
+    }
+
+    #[test]
+    fn s3_urls_are_parsed() {
+        let options = RemoteCacheOptions {
+            url: "s3://builds/truffle/".into(),
+            endpoint: Some("https://minio.local:9000/".into()),
+            region: "auto".into(),
+            read_only: true,
+        };
+        assert!(RemoteCache::from_options_with(&options, |_| None).is_err());
+        let remote = RemoteCache::from_options_with(&options, |name| match name {
+            "AWS_ACCESS_KEY_ID" => Some("AKID".into()),
+            "AWS_SECRET_ACCESS_KEY" => Some("secret".into()),
+            _ => None,
+        })
+        .unwrap();
+        let Backend::S3 {
+            endpoint,
+            bucket,
+            prefix,
+            credentials,
+            ..
+        } = &remote.backend
+        else {
+            panic!("expected an S3 backend");
+        };
+        assert_eq!(endpoint, "https://minio.local:9000");
+        assert_eq!(bucket, "builds");
+        assert_eq!(prefix, "truffle");
+        assert_eq!(credentials.access_key, "AKID");
+        assert!(remote.read_only());
+
+        assert!(RemoteCache::from_options_with(
+            &RemoteCacheOptions {
+                url: "ftp://example.com".into(),
+                endpoint: None,
+                region: "us-east-1".into(),
+                read_only: false,
+            },
+            |_| None
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn requests_run_from_inside_another_runtime() {
+        use std::io::{BufRead, BufReader, Write};
+
+        let listener = std::net::TcpListener::bind(("127.0.0.1", 0)).unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = std::thread::spawn(move || {
+            for status in ["404 Not Found", "200 OK"] {
+                let (stream, _) = listener.accept().unwrap();
+                let mut reader = BufReader::new(&stream);
+                let mut line = String::new();
+                while reader.read_line(&mut line).unwrap() > 2 {
+                    line.clear();
+                }
+                write!(
+                    &stream,
+                    "HTTP/1.1 {status}\r\nContent-Length: 3\r\nConnection: close\r\n\r\nabc"
+                )
+                .unwrap();
+            }
+        });
+
+        let remote = RemoteCache::from_options_with(
+            &RemoteCacheOptions {
+                url: format!("http://127.0.0.1:{port}"),
+                endpoint: None,
+                region: "us-east-1".into(),
+                read_only: true,
+            },
+            |_| None,
+        )
+        .unwrap();
+        tokio::runtime::Runtime::new()
+            .unwrap()
+            .block_on(async move {
+                assert_eq!(remote.get("ab/missing").unwrap(), None);
+                assert_eq!(remote.clone().get("ab/hit").unwrap(), Some(b"abc".to_vec()));
+            });
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn paths_are_uri_encoded() {
+        assert_eq!(uri_encode_path("/b/ab/a b+c.png"), "/b/ab/a%20b%2Bc.png");
+    }
+}
@@ -0,0 +1,245 @@
+use crate::image::animation::{self, AnimationEntry, ANIMATIONS_FILE};
+use crate::image::aseprite::{self, Sprite};
+use clap::{Parser, ValueEnum};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, ValueEnum)]
+pub enum AsepriteMode {
+    /// Write the first frame with all visible layers as `name.png`
+    Flatten,
+    /// Write each tag as numbered frames under `name/` plus `animations.json`
+    Tags,
+}
+
+#[derive(Parser)]
+#[command(about = "Export Aseprite files to PNG images and animation frames")]
+pub struct AsepriteArgs {
+    /// Input path (file or directory)
+    #[arg(value_name = "INPUT_PATH")]
+    pub input_path: PathBuf,
+
+    /// What to export from each file
+    #[arg(long, value_enum, default_value_t = AsepriteMode::Flatten)]
+    pub mode: AsepriteMode,
+
+    /// Preview what would be generated without creating files
+    #[arg(long)]
+    pub dry_run: bool,
+
+    /// Overwrite exports that are newer than their Aseprite source
+    #[arg(long)]
+    pub force: bool,
+
+    /// Recursively process directories
+    #[arg(short, long)]
+    pub recursive: bool,
+}
+
+fn is_aseprite(path: &Path) -> bool {
+    matches!(
+        path.extension().and_then(|s| s.to_str()),
+        Some("aseprite") | Some("ase")
+    )
+}
+
+fn get_output_path(source: &Path, mode: AsepriteMode) -> PathBuf {
+    let stem = source
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or_default();
+    match mode {
+        AsepriteMode::Flatten => source.with_file_name(format!("{}.png", stem)),
+        AsepriteMode::Tags => source.with_file_name(stem).join(ANIMATIONS_FILE),
+    }
+}
+
+fn is_up_to_date(source: &Path, output: &Path) -> bool {
+    let modified = |path: &Path| path.metadata().and_then(|m| m.modified()).ok();
+    match (modified(source), modified(output)) {
+        (Some(source), Some(output)) => output >= source,
+        _ => false,
+    }
+}
+
+fn export_tags(sprite: &Sprite, output_dir: &Path) -> Result<usize, String> {
+    std::fs::create_dir_all(output_dir)
+        .map_err(|e| format!("Failed to create {}: {}", output_dir.display(), e))?;
+
+    let tags = if sprite.tags.is_empty() {
+        vec![aseprite::Tag {
+            name: "frames".into(),
+            from: 0,
+            to: sprite.frames.len().saturating_sub(1),
+            direction: aseprite::Direction::Forward,
+            repeat: 0,
+        }]
+    } else {
+        sprite.tags.clone()
+    };
+
+    let mut animations = BTreeMap::new();
+    let mut written = 0;
+    for tag in &tags {
+        let frames = sprite
+            .frames
+            .get(tag.from..=tag.to)
+            .ok_or_else(|| format!("Tag {} points past the last frame", tag.name))?;
+        let mut entry = AnimationEntry {
+            frames: Vec::new(),
+            durations: Vec::new(),
+            direction: tag.direction.as_str().to_string(),
+            repeat: tag.repeat.into(),
+        };
+        for (index, frame) in frames.iter().enumerate() {
+            let file_name = animation::frame_file_name(&tag.name, index, frames.len());
+            let path = output_dir.join(&file_name);
+            frame
+                .image
+                .save(&path)
+                .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+            entry.frames.push(file_name);
+            entry.durations.push(frame.duration_ms.into());
+            written += 1;
+        }
+        animations.insert(tag.name.clone(), entry);
+    }
+
+    animation::write_manifest(output_dir, &animations)?;
+    Ok(written)
+}
+
+fn process_file(
+    source: &Path,
+    mode: AsepriteMode,
+    dry_run: bool,
+    force: bool,
+) -> Result<bool, String> {
+    let output = get_output_path(source, mode);
+
+    if !force && is_up_to_date(source, &output) {
+        println!(
+            "[aseprite] SKIP: {} (export is up to date)",
+            source.display()
+        );
+        return Ok(false);
+    }
+
+    if dry_run {
+        println!("[aseprite] DRY-RUN: Would generate {}", output.display());
+        return Ok(true);
+    }
+
+    println!("[aseprite] Processing: {}", source.display());
+    let sprite = aseprite::read_aseprite(source)?;
+
+    match mode {
+        AsepriteMode::Flatten => {
+            let frame = sprite
+                .frames
+                .first()
+                .ok_or_else(|| format!("{} has no frames", source.display()))?;
+            frame
+                .image
+                .save(&output)
+                .map_err(|e| format!("Failed to write {}: {}", output.display(), e))?;
+            println!("[aseprite] ✅ Generated: {}", output.display());
+        }
+        AsepriteMode::Tags => {
+            let output_dir = output.parent().unwrap_or(Path::new("."));
+            let written = export_tags(&sprite, output_dir)?;
+            println!(
+                "[aseprite] ✅ Generated {} frame(s) in {}",
+                written,
+                output_dir.display()
+            );
+        }
+    }
+
+    Ok(true)
+}
+
+fn process_path(
+    path: &Path,
+    mode: AsepriteMode,
+    dry_run: bool,
+    force: bool,
+    recursive: bool,
+) -> Result<(usize, usize, usize), String> {
+    let mut processed = 0;
+    let mut skipped = 0;
+    let mut errors = 0;
+
+    if !path.exists() {
+        return Err(format!("Path does not exist: {}", path.display()));
+    }
+
+    let files: Vec<PathBuf> = if path.is_file() {
+        if !is_aseprite(path) {
+            return Err(format!(
+                "Input must be an .aseprite or .ase file: {}",
+                path.display()
+            ));
+        }
+        vec![path.to_path_buf()]
+    } else {
+        let max_depth = if recursive { usize::MAX } else { 1 };
+        WalkDir::new(path)
+            .max_depth(max_depth)
+            .into_iter()
+            .filter_map(|e| e.ok())
+            .filter(|e| e.file_type().is_file())
+            .map(|e| e.path().to_path_buf())
+            .filter(|p| is_aseprite(p))
+            .collect()
+    };
+
+    if files.is_empty() {
+        println!("[aseprite] No Aseprite files found in: {}", path.display());
+        return Ok((0, 0, 0));
+    }
+
+    println!(
+        "[aseprite] Found {} Aseprite file(s) to process",
+        files.len()
+    );
+
+    for file in &files {
+        match process_file(file, mode, dry_run, force) {
+            Ok(true) => processed += 1,
+            Ok(false) => skipped += 1,
+            Err(e) => {
+                eprintln!("[aseprite] ERROR: {}", e);
+                errors += 1;
+            }
+        }
+    }
+
+    if dry_run {
+        println!("[aseprite] DRY-RUN: Would process {} file(s)", processed);
+    } else {
+        println!(
+            "[aseprite] Done ✅ Processed: {}, Skipped: {}, Errors: {}",
+            processed, skipped, errors
+        );
+    }
+
+    Ok((processed, skipped, errors))
+}
+
+pub fn run(args: AsepriteArgs) -> bool {
+    match process_path(
+        &args.input_path,
+        args.mode,
+        args.dry_run,
+        args.force,
+        args.recursive,
+    ) {
+        Ok((_, _, errors)) => errors == 0,
+        Err(e) => {
+            eprintln!("[aseprite] ERROR: {}", e);
+            false
+        }
+    }
+}
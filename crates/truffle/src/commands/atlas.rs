@@ -0,0 +1,129 @@
+use crate::commands::sync::{pack_project_atlases, SyncArgs};
+use crate::image::atlas_preview::{overlay_svg, render_preview, PreviewSprite};
+use crate::image::svg::svg_options;
+use anyhow::Context;
+use clap::{Parser, Subcommand};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use tokio::runtime::Runtime;
+use truffle_config::TruffleConfig;
+
+#[derive(Subcommand)]
+pub enum AtlasCommands {
+    /// Write atlas pages with sprite outlines and names burned in
+    Preview(AtlasPreviewArgs),
+}
+
+#[derive(Parser)]
+#[command(about = "Write atlas pages with sprite outlines and names burned in")]
+pub struct AtlasPreviewArgs {
+    /// Path to the raw assets images folder
+    #[arg(long, default_value = "assets/images")]
+    pub images_folder: PathBuf,
+
+    /// Atlas texture size (power-of-two square)
+    #[arg(long)]
+    pub atlas_size: Option<u32>,
+
+    /// Padding (in pixels) around each sprite in the atlas
+    #[arg(long)]
+    pub atlas_padding: Option<u32>,
+
+    /// Image keys to exclude from atlas packing (repeatable)
+    #[arg(long)]
+    pub atlas_exclude: Vec<String>,
+
+    /// Folder to write the preview PNGs to (defaults to `<scratch_dir>/atlas-preview`)
+    #[arg(long)]
+    pub out_dir: Option<PathBuf>,
+
+    /// Scratch directory for intermediate/generated files
+    #[arg(long)]
+    pub scratch_dir: Option<PathBuf>,
+
+    /// Directory for the content-addressed cache (defaults to `<scratch_dir>/cache`)
+    #[arg(long)]
+    pub cache_dir: Option<PathBuf>,
+}
+
+impl AtlasPreviewArgs {
+    fn sync_args(&self) -> SyncArgs {
+        let mut args = SyncArgs::parse_from(["sync"]);
+        args.images_folder = self.images_folder.clone();
+        args.atlas = true;
+        args.atlas_size = self.atlas_size;
+        args.atlas_padding = self.atlas_padding;
+        args.atlas_exclude = self.atlas_exclude.clone();
+        args.dry_run = true;
+        args.scratch_dir = self.scratch_dir.clone();
+        args.cache_dir = self.cache_dir.clone();
+        args
+    }
+}
+
+fn preview(args: &AtlasPreviewArgs) -> anyhow::Result<()> {
+    let rt = Runtime::new().expect("Failed to create tokio runtime");
+    let config = rt
+        .block_on(TruffleConfig::read())
+        .context("Failed to read truffle.toml. Make sure it exists in the current directory.")?;
+
+    let (pages_dir, placements) = pack_project_atlases(&args.sync_args(), &config)?;
+    let out_dir = args.out_dir.clone().unwrap_or_else(|| {
+        args.scratch_dir
+            .clone()
+            .unwrap_or_else(|| config.truffle.scratch_dir.clone())
+            .join("atlas-preview")
+    });
+    std::fs::create_dir_all(&out_dir)
+        .with_context(|| format!("Failed to create {}", out_dir.display()))?;
+
+    let mut pages: BTreeMap<&str, Vec<PreviewSprite>> = BTreeMap::new();
+    for (key, placement) in &placements {
+        pages
+            .entry(placement.atlas_file_name.as_str())
+            .or_default()
+            .push(PreviewSprite {
+                key,
+                rect: placement.rect,
+                rotated: placement.rotated,
+            });
+    }
+
+    let options = svg_options();
+    for (page, sprites) in &pages {
+        let page_path = pages_dir.join(page);
+        let (width, height) = image::image_dimensions(&page_path)
+            .with_context(|| format!("Failed to read {}", page_path.display()))?;
+        let output = out_dir.join(page);
+        render_preview(
+            &page_path,
+            &overlay_svg(width, height, sprites),
+            &output,
+            &options,
+        )
+        .map_err(anyhow::Error::msg)?;
+        println!(
+            "[atlas] ✅ Wrote preview: {} ({} sprite(s))",
+            output.display(),
+            sprites.len()
+        );
+    }
+    if pages.is_empty() {
+        println!("[atlas] No sprites to pack");
+    }
+    Ok(())
+}
+
+pub fn run(command: AtlasCommands) -> bool {
+    let result = match command {
+        AtlasCommands::Preview(args) => preview(&args),
+    };
+
+    match result {
+        Ok(()) => true,
+        Err(e) => {
+            eprintln!("[atlas] ERROR: {:#}", e);
+            false
+        }
+    }
+}
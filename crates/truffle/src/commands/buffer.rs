@@ -0,0 +1,219 @@
+use crate::image::buffer;
+use asphalt::glob::Glob;
+use clap::Parser;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+/// Base name of the loader module written into the output folder.
+const LOADER_NAME: &str = "loader";
+
+#[derive(Parser)]
+#[command(about = "Export PNG images as raw RGBA Luau modules for EditableImage")]
+pub struct BufferArgs {
+    /// Input path (file or directory)
+    #[arg(value_name = "INPUT_PATH")]
+    pub input_path: PathBuf,
+
+    /// Folder to write the image modules and `loader.luau` to
+    #[arg(long, default_value = "src/shared/data/editable")]
+    pub output: PathBuf,
+
+    /// Only export images whose path (relative to INPUT_PATH) matches this glob (repeatable)
+    #[arg(long)]
+    pub include: Vec<String>,
+
+    /// Preview what would be generated without creating files
+    #[arg(long)]
+    pub dry_run: bool,
+
+    /// Overwrite modules that are newer than their PNG source
+    #[arg(long)]
+    pub force: bool,
+
+    /// Recursively process directories
+    #[arg(short, long)]
+    pub recursive: bool,
+}
+
+fn is_up_to_date(source: &Path, output: &Path) -> bool {
+    let modified = |path: &Path| path.metadata().and_then(|m| m.modified()).ok();
+    match (modified(source), modified(output)) {
+        (Some(source), Some(output)) => output >= source,
+        _ => false,
+    }
+}
+
+/// Forward-slash path of `file` relative to the input root.
+fn relative_key(root: &Path, file: &Path) -> String {
+    let rel = file.strip_prefix(root).unwrap_or(file);
+    let rel = if rel.as_os_str().is_empty() {
+        Path::new(file.file_name().unwrap_or_default())
+    } else {
+        rel
+    };
+    rel.components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+fn process_image(
+    source: &Path,
+    key: &str,
+    output_dir: &Path,
+    dry_run: bool,
+    force: bool,
+) -> Result<bool, String> {
+    let module_path = output_dir.join(key).with_extension("luau");
+    if module_path == output_dir.join(LOADER_NAME).with_extension("luau") {
+        return Err(format!(
+            "{} would overwrite the loader module; rename the image",
+            source.display()
+        ));
+    }
+
+    if !force && is_up_to_date(source, &module_path) {
+        println!(
+            "[buffer] SKIP: {} (module is up to date)",
+            module_path.display()
+        );
+        return Ok(false);
+    }
+
+    if dry_run {
+        println!("[buffer] DRY-RUN: Would generate {}", module_path.display());
+        return Ok(true);
+    }
+
+    println!("[buffer] Processing: {}", source.display());
+    let image = image::open(source)
+        .map_err(|e| format!("Failed to decode {}: {}", source.display(), e))?
+        .to_rgba8();
+
+    if let Some(parent) = module_path.parent() {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+    }
+    std::fs::write(&module_path, buffer::render_image_module(&image))
+        .map_err(|e| format!("Failed to write {}: {}", module_path.display(), e))?;
+    let dts_path = module_path.with_extension("d.ts");
+    std::fs::write(&dts_path, buffer::IMAGE_DTS)
+        .map_err(|e| format!("Failed to write {}: {}", dts_path.display(), e))?;
+
+    println!("[buffer] ✅ Generated: {}", module_path.display());
+    Ok(true)
+}
+
+fn write_loader(output_dir: &Path) -> Result<(), String> {
+    std::fs::create_dir_all(output_dir)
+        .map_err(|e| format!("Failed to create {}: {}", output_dir.display(), e))?;
+    for (extension, contents) in [("luau", buffer::LOADER_LUAU), ("d.ts", buffer::LOADER_DTS)] {
+        let path = output_dir.join(LOADER_NAME).with_extension(extension);
+        std::fs::write(&path, contents)
+            .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    }
+    Ok(())
+}
+
+fn process_path(args: &BufferArgs, include: &[Glob]) -> Result<(usize, usize, usize), String> {
+    let mut processed = 0;
+    let mut skipped = 0;
+    let mut errors = 0;
+
+    let path = &args.input_path;
+    if !path.exists() {
+        return Err(format!("Path does not exist: {}", path.display()));
+    }
+
+    let is_png = |p: &Path| p.extension().and_then(|s| s.to_str()) == Some("png");
+
+    let files: Vec<PathBuf> = if path.is_file() {
+        if !is_png(path) {
+            return Err(format!("Input must be a PNG file: {}", path.display()));
+        }
+        vec![path.to_path_buf()]
+    } else {
+        let max_depth = if args.recursive { usize::MAX } else { 1 };
+        WalkDir::new(path)
+            .max_depth(max_depth)
+            .into_iter()
+            .filter_map(|e| e.ok())
+            .filter(|e| e.file_type().is_file())
+            .map(|e| e.path().to_path_buf())
+            .filter(|p| is_png(p))
+            .collect()
+    };
+
+    let root = if path.is_file() {
+        path.parent().unwrap_or(Path::new("."))
+    } else {
+        path.as_path()
+    };
+    let files: Vec<(PathBuf, String)> = files
+        .into_iter()
+        .map(|file| {
+            let key = relative_key(root, &file);
+            (file, key)
+        })
+        .filter(|(_, key)| include.is_empty() || include.iter().any(|glob| glob.is_match(key)))
+        .collect();
+
+    if files.is_empty() {
+        println!(
+            "[buffer] No matching PNG files found in: {}",
+            path.display()
+        );
+        return Ok((0, 0, 0));
+    }
+
+    println!("[buffer] Found {} PNG file(s) to process", files.len());
+
+    if !args.dry_run {
+        write_loader(&args.output)?;
+    }
+
+    for (file, key) in &files {
+        match process_image(file, key, &args.output, args.dry_run, args.force) {
+            Ok(true) => processed += 1,
+            Ok(false) => skipped += 1,
+            Err(e) => {
+                eprintln!("[buffer] ERROR: {}", e);
+                errors += 1;
+            }
+        }
+    }
+
+    if args.dry_run {
+        println!("[buffer] DRY-RUN: Would process {} file(s)", processed);
+    } else {
+        println!(
+            "[buffer] Done ✅ Processed: {}, Skipped: {}, Errors: {}",
+            processed, skipped, errors
+        );
+    }
+
+    Ok((processed, skipped, errors))
+}
+
+pub fn run(args: BufferArgs) -> bool {
+    let include = match args
+        .include
+        .iter()
+        .map(|pattern| Glob::new(pattern))
+        .collect::<Result<Vec<_>, _>>()
+    {
+        Ok(include) => include,
+        Err(e) => {
+            eprintln!("[buffer] ERROR: Invalid include glob: {}", e);
+            return false;
+        }
+    };
+
+    match process_path(&args, &include) {
+        Ok((_, _, errors)) => errors == 0,
+        Err(e) => {
+            eprintln!("[buffer] ERROR: {}", e);
+            false
+        }
+    }
+}
@@ -0,0 +1,281 @@
+use crate::assets::load_assets;
+use crate::assets::model::{array_items, AssetMeta, AssetValue};
+use crate::assets::style_keys;
+use crate::assets::variants::insert_value;
+use crate::commands::sync::merge_configured_variants;
+use anyhow::Context;
+use asphalt::config::KeyStyle;
+use clap::Parser;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+use tokio::runtime::Runtime;
+use truffle_config::{TruffleConfig, TruffleOptions};
+use walkdir::WalkDir;
+
+/// Source and metadata files that truffle converts rather than uploads.
+const SOURCE_EXTENSIONS: [&str; 6] = ["aseprite", "ase", "psd", "gif", "svg", "json"];
+
+#[derive(Parser, Debug)]
+#[command(about = "Check that the assets module and the images folder list the same files")]
+pub struct CheckArgs {
+    /// Path to the Luau assets module file
+    #[arg(long, default_value = "src/shared/data/assets/assets.luau")]
+    pub assets_input: PathBuf,
+
+    /// Path to the raw assets images folder
+    #[arg(long, default_value = "assets/images")]
+    pub images_folder: PathBuf,
+
+    /// Fail on files missing from the module too, not only on entries
+    /// missing from the images folder
+    #[arg(long)]
+    pub strict: bool,
+}
+
+/// Entries of an assets module and files of its images folder that don't
+/// match up.
+#[derive(Debug, Default, PartialEq)]
+pub struct References {
+    /// Module keys without a file in the images folder.
+    pub missing: Vec<String>,
+    /// Files in the images folder that no module entry lists.
+    pub unlisted: Vec<String>,
+}
+
+pub fn run(args: CheckArgs) -> bool {
+    match run_impl(&args) {
+        Ok(ok) => ok,
+        Err(e) => {
+            eprintln!("[check] ERROR: {:#}", e);
+            false
+        }
+    }
+}
+
+fn run_impl(args: &CheckArgs) -> anyhow::Result<bool> {
+    let rt = Runtime::new().expect("Failed to create tokio runtime");
+    let config = rt
+        .block_on(TruffleConfig::read())
+        .context("Failed to read truffle.toml. Make sure it exists in the current directory.")?;
+
+    let assets = load_assets(&args.assets_input).map_err(anyhow::Error::msg)?;
+    let references = check_references(
+        &assets,
+        &args.images_folder,
+        &config.truffle,
+        config.asphalt.codegen.key_style,
+    );
+
+    for key in &references.missing {
+        eprintln!(
+            "[check] ERROR: {} is listed in {} but has no file in {}",
+            key,
+            args.assets_input.display(),
+            args.images_folder.display()
+        );
+    }
+    for key in &references.unlisted {
+        let message = format!("{} is not listed in {}", key, args.assets_input.display());
+        if args.strict {
+            eprintln!("[check] ERROR: {}", message);
+        } else {
+            println!("[check] WARN: {}", message);
+        }
+    }
+
+    let ok = references.missing.is_empty() && (!args.strict || references.unlisted.is_empty());
+    if ok {
+        println!("[check] Assets module matches the images folder");
+    }
+    Ok(ok)
+}
+
+/// Compares the entries of `assets` with the files in `images_folder`.
+///
+/// The files are grouped the way a sync groups them (scale, locale and theme
+/// variants, surface sets) and named in `key_style`, so a module written by
+/// `truffle sync` matches as well as the raw module Asphalt writes.
+pub fn check_references(
+    assets: &BTreeMap<String, AssetValue>,
+    images_folder: &Path,
+    options: &TruffleOptions,
+    key_style: KeyStyle,
+) -> References {
+    let files = image_files(images_folder);
+    let mut tree = BTreeMap::new();
+    for file in &files {
+        let path: Vec<String> = file.split('/').map(String::from).collect();
+        let meta = AssetMeta {
+            id: file.clone(),
+            ..Default::default()
+        };
+        insert_value(&mut tree, &path, AssetValue::Object(meta));
+    }
+    merge_configured_variants(&mut tree, options);
+
+    // Generated key → the files it was built from.
+    let mut sources: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for (key, value) in entries(&style_keys(&tree, key_style)) {
+        let mut ids = Vec::new();
+        value_ids(value, &mut ids);
+        sources.insert(key, ids);
+    }
+
+    let listed: BTreeSet<String> = entries(assets).into_iter().map(|(key, _)| key).collect();
+    let mut covered: BTreeSet<&str> = BTreeSet::new();
+    let mut missing = Vec::new();
+    for key in &listed {
+        if files.contains(key) {
+            covered.insert(key);
+        } else if let Some(ids) = sources.get(key) {
+            covered.extend(ids.iter().map(String::as_str));
+        } else {
+            missing.push(key.clone());
+        }
+    }
+    let unlisted = files
+        .iter()
+        .filter(|file| !covered.contains(file.as_str()))
+        .cloned()
+        .collect();
+
+    References { missing, unlisted }
+}
+
+/// Keys of the uploadable files below `images_folder`, sorted.
+fn image_files(images_folder: &Path) -> Vec<String> {
+    let mut files: Vec<String> = WalkDir::new(images_folder)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file())
+        .filter(|e| !e.file_name().to_string_lossy().starts_with('.'))
+        .filter(|e| {
+            let extension = e
+                .path()
+                .extension()
+                .map(|ext| ext.to_string_lossy().to_lowercase())
+                .unwrap_or_default();
+            !SOURCE_EXTENSIONS.contains(&extension.as_str())
+        })
+        .filter_map(|e| {
+            let rel = e.path().strip_prefix(images_folder).ok()?;
+            Some(
+                rel.components()
+                    .map(|c| c.as_os_str().to_string_lossy())
+                    .collect::<Vec<_>>()
+                    .join("/"),
+            )
+        })
+        .collect();
+    files.sort();
+    files
+}
+
+/// The `/`-joined keys of the asset entries in `assets`. Arrays are
+/// hand-written lists rather than files and are skipped.
+fn entries(assets: &BTreeMap<String, AssetValue>) -> Vec<(String, &AssetValue)> {
+    let mut out = Vec::new();
+    for (key, value) in assets {
+        match value {
+            AssetValue::Table(map) if array_items(map).is_some() => {}
+            AssetValue::Table(map) => {
+                for (child, value) in entries(map) {
+                    out.push((format!("{}/{}", key, child), value));
+                }
+            }
+            AssetValue::Object(_) | AssetValue::String(_) | AssetValue::Surface(_) => {
+                out.push((key.clone(), value));
+            }
+            AssetValue::Number(_) | AssetValue::Bool(_) => {}
+        }
+    }
+    out
+}
+
+/// Ids of `value` and of every variant merged into it.
+fn value_ids(value: &AssetValue, out: &mut Vec<String>) {
+    match value {
+        AssetValue::Object(meta) => meta_ids(meta, out),
+        AssetValue::Surface(surface) => {
+            for map in [
+                &surface.color_map,
+                &surface.normal_map,
+                &surface.metalness_map,
+                &surface.roughness_map,
+            ]
+            .into_iter()
+            .flatten()
+            {
+                meta_ids(map, out);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn meta_ids(meta: &AssetMeta, out: &mut Vec<String>) {
+    out.push(meta.id.clone());
+    for variants in [&meta.locales, &meta.variants, &meta.scales]
+        .into_iter()
+        .flatten()
+    {
+        for variant in variants.values() {
+            meta_ids(variant, out);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn asset(id: &str) -> AssetValue {
+        AssetValue::Object(AssetMeta {
+            id: id.into(),
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn reports_missing_and_unlisted_files() {
+        let dir = std::env::temp_dir().join(format!("truffle-check-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("ui")).unwrap();
+        for file in [
+            "ui/play-button.png",
+            "ui/play-button@2x.png",
+            "ui/play-button.9.json",
+            "ui/hero.aseprite",
+            "extra.png",
+        ] {
+            std::fs::write(dir.join(file), [0u8; 4]).unwrap();
+        }
+
+        let raw = BTreeMap::from([
+            (
+                "ui".to_string(),
+                AssetValue::Table(BTreeMap::from([
+                    ("play-button.png".to_string(), asset("rbxassetid://1")),
+                    ("play-button@2x.png".to_string(), asset("rbxassetid://2")),
+                ])),
+            ),
+            ("old.png".to_string(), asset("rbxassetid://3")),
+        ]);
+        let options = TruffleOptions::default();
+        let references = check_references(&raw, &dir, &options, KeyStyle::Keep);
+        assert_eq!(references.missing, vec!["old.png"]);
+        assert_eq!(references.unlisted, vec!["extra.png"]);
+
+        // A synced module merges the @2x variant and renames the keys.
+        let synced = BTreeMap::from([(
+            "ui".to_string(),
+            AssetValue::Table(BTreeMap::from([(
+                "playButton".to_string(),
+                asset("rbxassetid://1"),
+            )])),
+        )]);
+        let references = check_references(&synced, &dir, &options, KeyStyle::CamelCase);
+        std::fs::remove_dir_all(&dir).ok();
+        assert!(references.missing.is_empty());
+        assert_eq!(references.unlisted, vec!["extra.png"]);
+    }
+}
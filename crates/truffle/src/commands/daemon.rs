@@ -0,0 +1,551 @@
+use crate::assets::CachedImageMetadata;
+use crate::commands::sync::{self, SyncArgs};
+use crate::since::ChangeScope;
+use crate::watch::{normalize, FileWatcher};
+use anyhow::Context;
+use clap::{Args, Parser, Subcommand};
+use log::{error, info, Level, LevelFilter};
+use serde::{Deserialize, Serialize};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+use subtle::ConstantTimeEq;
+use tokio::runtime::Runtime;
+use truffle_config::TruffleConfig;
+
+/// File in the scratch directory that tells clients where the daemon listens.
+const DAEMON_FILE: &str = "daemon.json";
+
+/// How long a client may take to send its request before it is dropped.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The longest request or response line read from the socket, so a client
+/// can't make the daemon buffer an unbounded message.
+const MAX_MESSAGE_BYTES: u64 = 1024 * 1024;
+
+#[derive(Subcommand)]
+pub enum DaemonCommands {
+    /// Start the daemon in the foreground
+    Start(DaemonArgs),
+    /// Run a sync in the running daemon (accepts `truffle sync` options)
+    Sync(DaemonSyncArgs),
+    /// Show what the running daemon has cached
+    Status(DaemonArgs),
+    /// Stop the running daemon
+    Stop(DaemonArgs),
+}
+
+#[derive(Args)]
+pub struct DaemonArgs {
+    /// Scratch directory the daemon records its address in
+    #[arg(long, default_value = ".truffle")]
+    pub scratch_dir: PathBuf,
+}
+
+#[derive(Parser)]
+pub struct DaemonSyncArgs {
+    #[command(flatten)]
+    pub daemon: DaemonArgs,
+
+    /// Options passed on to `truffle sync`
+    #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
+    pub sync_args: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct DaemonInfo {
+    port: u16,
+    token: String,
+    pid: u32,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Request {
+    token: String,
+    command: String,
+    #[serde(default)]
+    args: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Response {
+    ok: bool,
+    message: String,
+}
+
+/// Watches what syncs read, so a sync only looks at the files that changed
+/// since the last one instead of hashing every tracked file again.
+struct WarmWatcher {
+    watcher: FileWatcher,
+    /// Folders whose changes scope the next sync.
+    folders: Vec<PathBuf>,
+    /// The sync options the watcher was set up for.
+    options: String,
+    /// Contents of the state files and the assets module after the last
+    /// sync, to tell the sync's own writes from edits.
+    written: Vec<(PathBuf, Option<Vec<u8>>)>,
+}
+
+impl WarmWatcher {
+    fn record_writes(&mut self) {
+        for (path, written) in &mut self.written {
+            *written = std::fs::read(&*path).ok();
+        }
+    }
+}
+
+/// State kept warm between requests. The lockfile isn't: Asphalt reads and
+/// rewrites it during every sync.
+struct DaemonState {
+    runtime: Runtime,
+    config: Option<(Option<SystemTime>, TruffleConfig)>,
+    images: CachedImageMetadata,
+    watcher: Option<WarmWatcher>,
+    started: Instant,
+    syncs: usize,
+    last_sync: Option<String>,
+}
+
+impl DaemonState {
+    /// Returns the cached config, re-reading truffle.toml if it changed.
+    fn config(&mut self) -> anyhow::Result<&TruffleConfig> {
+        let modified = std::fs::metadata(truffle_config::FILE_NAME)
+            .and_then(|m| m.modified())
+            .ok();
+        let stale = !matches!(&self.config, Some((cached, _)) if *cached == modified);
+        if stale {
+            let config = self
+                .runtime
+                .block_on(TruffleConfig::read())
+                .context("Failed to read truffle.toml")?;
+            info!("Loaded {}", truffle_config::FILE_NAME);
+            self.config = Some((modified, config));
+            // What the watcher covers depends on the config.
+            self.watcher = None;
+        }
+        Ok(&self.config.as_ref().unwrap().1)
+    }
+
+    /// Scopes `args` to the files the watcher saw change since the last
+    /// successful sync. The first sync, and the first after truffle.toml or
+    /// the options changed, starts the watcher and runs like `truffle sync`,
+    /// as do syncs that are scoped already and dry runs.
+    fn scope_to_changes(
+        &mut self,
+        args: &mut SyncArgs,
+        config: &TruffleConfig,
+    ) -> anyhow::Result<()> {
+        if args.dry_run || args.full || args.since.is_some() || args.filter.is_some() {
+            return Ok(());
+        }
+        let options = format!(
+            "{:?}",
+            (
+                &args.images_folder,
+                &args.image_roots,
+                &args.assets_input,
+                &args.assets_output,
+                &args.dts_output,
+                &args.scratch_dir
+            )
+        );
+        if let Some(warm) = self.watcher.as_mut().filter(|warm| warm.options == options) {
+            let mut files = warm.watcher.drain();
+            let edited = warm.written.iter().find(|(path, written)| {
+                files.contains(&normalize(path)) && std::fs::read(path).ok() != *written
+            });
+            if let Some((path, _)) = edited {
+                info!(
+                    "{} changed since the last sync; syncing everything",
+                    path.display()
+                );
+            } else {
+                files.retain(|file| {
+                    warm.folders
+                        .iter()
+                        .any(|folder| file.starts_with(normalize(folder)))
+                });
+                info!("{} file(s) changed since the last sync", files.len());
+                args.changes = Some(ChangeScope::from_files(files, &args.images_folder));
+            }
+            return Ok(());
+        }
+
+        // Started before the sync, so edits made while it runs aren't missed.
+        self.watcher = sync::content_watcher(args, config)?.map(|(watcher, folders)| {
+            let mut written: Vec<(PathBuf, Option<Vec<u8>>)> = sync::STATE_FILES
+                .iter()
+                .map(|file| (PathBuf::from(file), None))
+                .collect();
+            written.push((args.assets_input.clone(), None));
+            WarmWatcher {
+                watcher,
+                folders,
+                options,
+                written,
+            }
+        });
+        Ok(())
+    }
+
+    fn sync(&mut self, args: &[String]) -> Response {
+        let mut args = match SyncArgs::try_parse_from(
+            std::iter::once("sync".to_string()).chain(args.iter().cloned()),
+        ) {
+            Ok(args) => args,
+            Err(e) => {
+                return Response {
+                    ok: false,
+                    message: e.to_string(),
+                }
+            }
+        };
+
+        let started = Instant::now();
+        let result = if args.workspace {
+            self.runtime
+                .block_on(crate::workspace::sync_workspace(args, &self.images))
+        } else {
+            let dry_run = args.dry_run;
+            let result = self.config().cloned().and_then(|config| {
+                self.scope_to_changes(&mut args, &config)?;
+                self.runtime
+                    .block_on(sync::run_with_config(args, &config, &self.images))
+            });
+            match (&result, self.watcher.as_mut()) {
+                (Ok(()), Some(warm)) if !dry_run => warm.record_writes(),
+                // Changes the failed sync skipped would be lost otherwise.
+                (Err(_), _) => self.watcher = None,
+                _ => {}
+            }
+            result
+        };
+        self.syncs += 1;
+
+        let (ok, message) = match result {
+            Ok(()) => (
+                true,
+                format!("Sync finished in {:.1}s", started.elapsed().as_secs_f64()),
+            ),
+            Err(e) => (false, format!("Sync failed: {:#}", e)),
+        };
+        info!("{}", message);
+        self.last_sync = Some(message.clone());
+        Response { ok, message }
+    }
+
+    fn status(&self) -> Response {
+        Response {
+            ok: true,
+            message: format!(
+                "Running for {}s, {} sync(s), {} cached image size(s), config {}, {}. Last sync: {}",
+                self.started.elapsed().as_secs(),
+                self.syncs,
+                self.images.cached_images(),
+                if self.config.is_some() {
+                    "loaded"
+                } else {
+                    "not loaded yet"
+                },
+                match &self.watcher {
+                    Some(warm) => format!("watching {} folder(s)", warm.folders.len()),
+                    None => "not watching".to_string(),
+                },
+                self.last_sync.as_deref().unwrap_or("none")
+            ),
+        }
+    }
+}
+
+fn daemon_file(args: &DaemonArgs) -> PathBuf {
+    args.scratch_dir.join(DAEMON_FILE)
+}
+
+fn make_token() -> anyhow::Result<String> {
+    let mut bytes = [0u8; 16];
+    getrandom::fill(&mut bytes)
+        .map_err(|e| anyhow::anyhow!("Failed to generate the daemon token: {}", e))?;
+    Ok(bytes.iter().map(|b| format!("{:02x}", b)).collect())
+}
+
+/// Writes `contents` to `path` readable by the current user only, as it holds
+/// the daemon token.
+fn write_private(path: &Path, contents: &str) -> std::io::Result<()> {
+    // An existing file keeps its permissions when opened, so start fresh.
+    match std::fs::remove_file(path) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e),
+        _ => {}
+    }
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    options.open(path)?.write_all(contents.as_bytes())
+}
+
+fn read_line<T: for<'de> Deserialize<'de>>(stream: &TcpStream) -> anyhow::Result<T> {
+    let mut line = String::new();
+    BufReader::new(stream.take(MAX_MESSAGE_BYTES))
+        .read_line(&mut line)
+        .context("Failed to read from daemon socket")?;
+    if !line.ends_with('\n') {
+        anyhow::bail!(
+            "Daemon message is incomplete or longer than {} bytes",
+            MAX_MESSAGE_BYTES
+        );
+    }
+    serde_json::from_str(&line).context("Invalid daemon message")
+}
+
+fn write_line<T: Serialize>(mut stream: &TcpStream, value: &T) -> anyhow::Result<()> {
+    let mut json = serde_json::to_string(value)?;
+    json.push('\n');
+    stream
+        .write_all(json.as_bytes())
+        .context("Failed to write to daemon socket")
+}
+
+/// Answers one request. Returns `true` when the daemon should stop.
+fn handle(stream: &TcpStream, token: &str, state: &mut DaemonState) -> anyhow::Result<bool> {
+    let request: Request = read_line(stream)?;
+    if !bool::from(request.token.as_bytes().ct_eq(token.as_bytes())) {
+        write_line(
+            stream,
+            &Response {
+                ok: false,
+                message: "Invalid daemon token".into(),
+            },
+        )?;
+        return Ok(false);
+    }
+
+    let (response, stop) = match request.command.as_str() {
+        "sync" => {
+            info!("Sync requested");
+            (state.sync(&request.args), false)
+        }
+        "status" => (state.status(), false),
+        "stop" => (
+            Response {
+                ok: true,
+                message: "Daemon stopped".into(),
+            },
+            true,
+        ),
+        other => (
+            Response {
+                ok: false,
+                message: format!("Unknown daemon command: {}", other),
+            },
+            false,
+        ),
+    };
+    write_line(stream, &response)?;
+    Ok(stop)
+}
+
+fn start(args: &DaemonArgs) -> anyhow::Result<()> {
+    let info_path = daemon_file(args);
+    if send(args, "status", Vec::new()).is_ok() {
+        anyhow::bail!(
+            "A daemon is already running for {}",
+            args.scratch_dir.display()
+        );
+    }
+
+    let listener =
+        TcpListener::bind(("127.0.0.1", 0)).context("Failed to open the daemon socket")?;
+    let info = DaemonInfo {
+        port: listener.local_addr()?.port(),
+        token: make_token()?,
+        pid: std::process::id(),
+    };
+    std::fs::create_dir_all(&args.scratch_dir)
+        .with_context(|| format!("Failed to create {}", args.scratch_dir.display()))?;
+    write_private(&info_path, &serde_json::to_string_pretty(&info)?)
+        .with_context(|| format!("Failed to write {}", info_path.display()))?;
+
+    let mut state = DaemonState {
+        runtime: Runtime::new().context("Failed to create tokio runtime")?,
+        config: None,
+        images: CachedImageMetadata::default(),
+        watcher: None,
+        started: Instant::now(),
+        syncs: 0,
+        last_sync: None,
+    };
+
+    info!("Listening on 127.0.0.1:{} (pid {})", info.port, info.pid);
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(e) => {
+                error!("{}", e);
+                continue;
+            }
+        };
+        // A client that never finishes its request would otherwise block
+        // every later one.
+        if let Err(e) = stream.set_read_timeout(Some(REQUEST_TIMEOUT)) {
+            error!("{}", e);
+            continue;
+        }
+        match handle(&stream, &info.token, &mut state) {
+            Ok(true) => break,
+            Ok(false) => {}
+            Err(e) => error!("{}", e),
+        }
+    }
+
+    std::fs::remove_file(&info_path).ok();
+    info!("Stopped");
+    Ok(())
+}
+
+fn read_info(path: &Path) -> anyhow::Result<DaemonInfo> {
+    let json = std::fs::read_to_string(path).with_context(|| {
+        format!(
+            "No daemon found ({} is missing). Start one with `truffle daemon start`",
+            path.display()
+        )
+    })?;
+    serde_json::from_str(&json).with_context(|| format!("Failed to parse {}", path.display()))
+}
+
+fn send(args: &DaemonArgs, command: &str, command_args: Vec<String>) -> anyhow::Result<Response> {
+    let info = read_info(&daemon_file(args))?;
+    let stream = TcpStream::connect(("127.0.0.1", info.port)).with_context(|| {
+        format!(
+            "Daemon (pid {}) is not responding. Start one with `truffle daemon start`",
+            info.pid
+        )
+    })?;
+    write_line(
+        &stream,
+        &Request {
+            token: info.token,
+            command: command.into(),
+            args: command_args,
+        },
+    )?;
+    read_line(&stream)
+}
+
+fn request(args: &DaemonArgs, command: &str, command_args: Vec<String>) -> anyhow::Result<()> {
+    let response = send(args, command, command_args)?;
+    if !response.ok {
+        anyhow::bail!(response.message);
+    }
+    info!("{}", response.message);
+    Ok(())
+}
+
+/// Sends this module's log records to stderr, tagged like the other commands' output.
+fn init_logger() {
+    env_logger::Builder::new()
+        .filter_module(module_path!(), LevelFilter::Info)
+        .format(|buf, record| match record.level() {
+            Level::Info => writeln!(buf, "[daemon] {}", record.args()),
+            level => writeln!(buf, "[daemon] {}: {}", level, record.args()),
+        })
+        .try_init()
+        .ok();
+}
+
+pub fn run(command: DaemonCommands) -> bool {
+    init_logger();
+    let result = match command {
+        DaemonCommands::Start(args) => start(&args),
+        DaemonCommands::Sync(args) => request(&args.daemon, "sync", args.sync_args),
+        DaemonCommands::Status(args) => request(&args, "status", Vec::new()),
+        DaemonCommands::Stop(args) => request(&args, "stop", Vec::new()),
+    };
+
+    match result {
+        Ok(()) => true,
+        Err(e) => {
+            error!("{}", e);
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tokens_are_unique_hex() {
+        let a = make_token().unwrap();
+        assert_eq!(a.len(), 32);
+        assert!(a.chars().all(|c| c.is_ascii_hexdigit()));
+        assert_ne!(a, make_token().unwrap());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn daemon_file_is_private() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = std::env::temp_dir().join(format!("truffle-daemon-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(DAEMON_FILE);
+        std::fs::write(&path, "stale").unwrap();
+        write_private(&path, "{}").unwrap();
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "{}");
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn requests_with_wrong_token_are_rejected() {
+        let listener = TcpListener::bind(("127.0.0.1", 0)).unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let client = std::thread::spawn(move || {
+            let stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
+            write_line(
+                &stream,
+                &Request {
+                    token: "wrong".into(),
+                    command: "stop".into(),
+                    args: Vec::new(),
+                },
+            )
+            .unwrap();
+            read_line::<Response>(&stream).unwrap()
+        });
+
+        let (stream, _) = listener.accept().unwrap();
+        let mut state = DaemonState {
+            runtime: Runtime::new().unwrap(),
+            config: None,
+            images: CachedImageMetadata::default(),
+            watcher: None,
+            started: Instant::now(),
+            syncs: 0,
+            last_sync: None,
+        };
+        assert!(!handle(&stream, "secret", &mut state).unwrap());
+        let response = client.join().unwrap();
+        assert!(!response.ok);
+    }
+
+    #[test]
+    fn oversized_messages_are_rejected() {
+        let listener = TcpListener::bind(("127.0.0.1", 0)).unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let client = std::thread::spawn(move || {
+            let mut stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
+            let line = vec![b'x'; MAX_MESSAGE_BYTES as usize + 1];
+            // The daemon may hang up before reading everything.
+            let _ = stream.write_all(&line);
+        });
+
+        let (stream, _) = listener.accept().unwrap();
+        let error = read_line::<serde_json::Value>(&stream).unwrap_err();
+        assert!(error.to_string().contains("longer than"));
+        client.join().unwrap();
+    }
+}
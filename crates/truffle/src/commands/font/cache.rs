@@ -0,0 +1,270 @@
+use super::kerning::KerningPair;
+use super::{file_name, Effect, FontArgs, FontFormat, FontPacking, FontPaths, Plan};
+use crate::cache::ContentCache;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+/// A run's atlases and metadata in the content cache of `--cache-dir`.
+pub(crate) struct FontCache {
+    cache: ContentCache,
+    key: String,
+    /// The files written, by the name they're cached under.
+    outputs: Vec<(String, PathBuf)>,
+}
+
+impl FontCache {
+    /// The cache of `args`, or `None` without `--cache-dir`.
+    pub(crate) fn new(
+        args: &FontArgs,
+        plan: &Plan,
+        font_bytes: &[Vec<u8>],
+        paths: &FontPaths,
+    ) -> anyhow::Result<Option<Self>> {
+        let Some(dir) = &args.cache_dir else {
+            return Ok(None);
+        };
+        let outputs = cached_outputs(paths, args.format, &plan.effects);
+        let key = font_cache_key(
+            args,
+            plan.pack,
+            &plan.sizes,
+            font_bytes,
+            &outputs_ref(&outputs),
+        )?;
+        Ok(Some(Self {
+            cache: ContentCache::new(dir),
+            key,
+            outputs,
+        }))
+    }
+
+    /// Copies the cached files into place, if they're all cached.
+    pub(crate) fn restore(&self) -> bool {
+        self.cache.restore(&self.key, &outputs_ref(&self.outputs))
+    }
+
+    pub(crate) fn store(&self) -> anyhow::Result<()> {
+        self.cache.store(&self.key, &outputs_ref(&self.outputs))
+    }
+
+    /// The optical kerning pairs at `px`, reused when the same glyphs were
+    /// measured before and otherwise computed with `compute` and cached.
+    pub(crate) fn optical_kerning(
+        &self,
+        args: &FontArgs,
+        font_bytes: &[Vec<u8>],
+        px: f32,
+        compute: impl FnOnce() -> Vec<KerningPair>,
+    ) -> anyhow::Result<Vec<KerningPair>> {
+        let key = optical_kerning_key(args, font_bytes, px);
+        let cached = self
+            .cache
+            .get(&key, OPTICAL_KERNING_ENTRY)
+            .and_then(|bytes| serde_json::from_slice::<Vec<KerningPair>>(&bytes).ok());
+        if let Some(pairs) = cached {
+            return Ok(pairs);
+        }
+        let pairs = compute();
+        self.cache
+            .put(&key, OPTICAL_KERNING_ENTRY, serde_json::to_vec(&pairs)?)?;
+        Ok(pairs)
+    }
+}
+
+fn outputs_ref(outputs: &[(String, PathBuf)]) -> Vec<(&str, &Path)> {
+    outputs
+        .iter()
+        .map(|(name, path)| (name.as_str(), path.as_path()))
+        .collect()
+}
+
+/// The files written for `format`, by the name they're cached under.
+fn cached_outputs(
+    paths: &FontPaths,
+    format: FontFormat,
+    effects: &[Effect],
+) -> Vec<(String, PathBuf)> {
+    let mut entries: Vec<(String, PathBuf)> = paths
+        .atlases
+        .iter()
+        .enumerate()
+        .map(|(i, path)| (format!("atlas-{i}.png"), path.clone()))
+        .collect();
+    match format {
+        FontFormat::Luau => {
+            entries.push(("module.luau".into(), paths.luau.clone()));
+            entries.push(("module.d.ts".into(), paths.dts.clone()));
+        }
+        FontFormat::Bmfont => {
+            for (i, path) in paths.fnts.iter().enumerate() {
+                entries.push((format!("font-{i}.fnt"), path.clone()));
+            }
+            for (effect, path) in effects.iter().zip(&paths.variant_fnts) {
+                entries.push((format!("{}.fnt", effect.name()), path.clone()));
+            }
+        }
+        FontFormat::Json => entries.push(("font.json".into(), paths.json.clone())),
+    }
+    for (effect, path) in effects.iter().zip(&paths.variant_pngs) {
+        entries.push((format!("{}.png", effect.name()), path.clone()));
+    }
+    entries
+}
+
+/// Cache key of the files generated from `font_bytes`. Directories stay out of
+/// it, but file names are in: the metadata names the fonts, the BMFont face
+/// and pages, and the per-size atlases of `--sizes`.
+fn font_cache_key(
+    args: &FontArgs,
+    pack: FontPacking,
+    sizes: &[u32],
+    font_bytes: &[Vec<u8>],
+    outputs: &[(&str, &Path)],
+) -> anyhow::Result<String> {
+    let names: Vec<String> = outputs.iter().map(|(_, path)| file_name(path)).collect();
+    let font_names: Vec<String> = std::iter::once(&args.input_ttf)
+        .chain(&args.fallback)
+        .map(|path| file_name(path))
+        .collect();
+    let settings = serde_json::to_vec(&FontCacheSettings {
+        args,
+        pack,
+        sizes,
+        outputs: names,
+        fonts: font_names,
+    })?;
+    let mut key_parts: Vec<&[u8]> = font_bytes.iter().map(Vec::as_slice).collect();
+    key_parts.push(&settings);
+    Ok(ContentCache::key("font", &key_parts))
+}
+
+/// Everything besides the font files that the generated atlases depend on.
+#[derive(Serialize)]
+struct FontCacheSettings<'a> {
+    args: &'a FontArgs,
+    /// The packing and sizes `args` resolve to.
+    pack: FontPacking,
+    sizes: &'a [u32],
+    /// File names of the outputs and fonts, which the metadata refers to.
+    outputs: Vec<String>,
+    fonts: Vec<String>,
+}
+
+/// Cache key of the optical kerning pairs at `px`. Only the glyphs and their
+/// shapes affect the pairs, so changing other settings (atlas size, packing,
+/// format) reuses the cached ones.
+fn optical_kerning_key(args: &FontArgs, font_bytes: &[Vec<u8>], px: f32) -> String {
+    // --language swaps in localized glyphs and --ligatures adds glyphs.
+    let settings = format!(
+        "{}|{}|{:?}|{}|{}|{}|{:?}|{}|{}|{:?}|{}",
+        args.charset,
+        px,
+        args.optical_kerning,
+        args.optical_kerning_gap,
+        args.outline,
+        args.no_antialias,
+        args.hinting,
+        args.subpixel,
+        args.pixel_mode,
+        args.language,
+        args.ligatures
+    );
+    let mut parts: Vec<&[u8]> = font_bytes.iter().map(Vec::as_slice).collect();
+    parts.push(settings.as_bytes());
+    ContentCache::key("optical-kerning", &parts)
+}
+
+/// Cache entry holding the optical kerning pairs of one pixel size.
+const OPTICAL_KERNING_ENTRY: &str = "kerning.json";
+
+#[cfg(test)]
+mod tests {
+    use super::super::derive_sibling_path;
+    use super::*;
+    use clap::Parser;
+
+    #[test]
+    fn cache_key_covers_output_file_names() {
+        let args = FontArgs::parse_from(["font", "ui.ttf", "out/ui.png", "--format", "bmfont"]);
+        let fonts = [b"font".to_vec()];
+        let key = |png: &str| {
+            font_cache_key(
+                &args,
+                FontPacking::Grid,
+                &[],
+                &fonts,
+                &[("atlas-0.png", Path::new(png))],
+            )
+            .unwrap()
+        };
+        // The BMFont page names the atlas, but not the directory it is in.
+        assert_eq!(key("out/ui.png"), key("elsewhere/ui.png"));
+        assert_ne!(key("out/ui.png"), key("out/menu.png"));
+
+        // With --split-sizes, each size's module names its own atlas.
+        let args = FontArgs::parse_from([
+            "font",
+            "ui.ttf",
+            "ui.png",
+            "--sizes",
+            "16,24",
+            "--split-sizes",
+        ]);
+        let key = |png: &Path| {
+            let atlases: Vec<PathBuf> = [16, 24]
+                .iter()
+                .map(|px| derive_sibling_path(png, &px.to_string(), "png"))
+                .collect();
+            let outputs = [
+                ("atlas-0.png", atlases[0].as_path()),
+                ("atlas-1.png", atlases[1].as_path()),
+            ];
+            font_cache_key(&args, FontPacking::Tight, &[16, 24], &fonts, &outputs).unwrap()
+        };
+        assert_ne!(key(Path::new("ui.png")), key(Path::new("menu.png")));
+    }
+
+    #[test]
+    fn cache_key_covers_font_file_names() {
+        let fonts = [b"font".to_vec(), b"fallback".to_vec()];
+        let key = |font: &str, fallback: &str| {
+            let args = FontArgs::parse_from(["font", font, "ui.png", "--fallback", fallback]);
+            font_cache_key(&args, FontPacking::Grid, &[], &fonts, &[]).unwrap()
+        };
+        assert_eq!(key("a/ui.ttf", "b/cjk.ttf"), key("c/ui.ttf", "d/cjk.ttf"));
+        assert_ne!(key("ui.ttf", "cjk.ttf"), key("menu.ttf", "cjk.ttf"));
+        assert_ne!(key("ui.ttf", "cjk.ttf"), key("ui.ttf", "emoji.ttf"));
+    }
+
+    #[test]
+    fn cache_key_covers_options_but_not_output_paths() {
+        let fonts = [b"font".to_vec()];
+        let key = |flags: &[&str]| {
+            let args = FontArgs::parse_from(["font", "ui.ttf", "ui.png"].iter().chain(flags));
+            font_cache_key(&args, FontPacking::Grid, &[], &fonts, &[]).unwrap()
+        };
+        assert_eq!(
+            key(&[]),
+            key(&["--cache-dir", "cache", "--luau", "out/ui.luau"])
+        );
+        assert_ne!(key(&[]), key(&["--sdf-range", "8"]));
+        assert_ne!(key(&[]), key(&["--orientation", "vertical"]));
+    }
+
+    #[test]
+    fn optical_kerning_key_covers_glyph_selection() {
+        let fonts = [b"font".to_vec()];
+        let key = |flags: &[&str]| {
+            let args = FontArgs::parse_from(
+                ["font", "ui.ttf", "ui.png", "--optical-kerning", "fill"]
+                    .iter()
+                    .chain(flags),
+            );
+            optical_kerning_key(&args, &fonts, 16.0)
+        };
+        assert_eq!(key(&[]), key(&["--size", "512x512", "--format", "json"]));
+        assert_ne!(key(&[]), key(&["--language", "TRK"]));
+        assert_ne!(key(&["--language", "TRK"]), key(&["--language", "NLD"]));
+        assert_ne!(key(&[]), key(&["--ligatures"]));
+    }
+}
@@ -0,0 +1,253 @@
+use super::{FontArgs, Plan};
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use ttf_parser::gsub::{SingleSubstitution, SubstitutionSubtable};
+use ttf_parser::opentype_layout::Lookup;
+use ttf_parser::{GlyphId, Tag};
+
+/// The input font and its fallbacks, in chain order: parsed by fontdue to
+/// rasterize, and by ttf-parser for the tables fontdue doesn't read.
+pub(crate) struct Fonts<'a> {
+    pub(crate) fonts: Vec<fontdue::Font>,
+    pub(crate) faces: Vec<ttf_parser::Face<'a>>,
+}
+
+impl<'a> Fonts<'a> {
+    pub(crate) fn parse(
+        font_bytes: &'a [Vec<u8>],
+        font_paths: &[&PathBuf],
+    ) -> anyhow::Result<Self> {
+        let fonts = font_bytes
+            .iter()
+            .zip(font_paths)
+            .map(|(bytes, path)| {
+                fontdue::Font::from_bytes(bytes.as_slice(), fontdue::FontSettings::default())
+                    .map_err(|e| anyhow::anyhow!("failed to parse font {}: {e:?}", path.display()))
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        // Color glyphs and distance fields are read from the font tables rather than fontdue.
+        let faces = font_bytes
+            .iter()
+            .map(|bytes| {
+                ttf_parser::Face::parse(bytes, 0)
+                    .map_err(|e| anyhow::anyhow!("failed to parse font outlines: {e}"))
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        Ok(Self { fonts, faces })
+    }
+}
+
+/// The glyph of each atlas entry: the charset's cmap glyphs, localized with
+/// --language, then ligature glyphs under private-use stand-in characters.
+pub(crate) struct GlyphSet {
+    pub(crate) chars: Vec<char>,
+    /// Index into `Fonts` of the font each glyph is rasterized from.
+    pub(crate) sources: Vec<usize>,
+    pub(crate) ids: Vec<u16>,
+    /// The characters each ligature replaces and its stand-in character.
+    pub(crate) ligatures: Vec<(String, char)>,
+}
+
+impl GlyphSet {
+    pub(crate) fn select(args: &FontArgs, plan: &Plan, fonts: &Fonts) -> anyhow::Result<Self> {
+        let mut sources: Vec<usize> = args
+            .charset
+            .chars()
+            .map(|ch| {
+                fonts
+                    .fonts
+                    .iter()
+                    .position(|font| font.lookup_glyph_index(ch) != 0)
+                    .unwrap_or_else(|| {
+                        if fonts.fonts.len() > 1 {
+                            println!(
+                                "[font] WARN: no font in the fallback chain has a glyph for {ch:?}"
+                            );
+                        }
+                        0
+                    })
+            })
+            .collect();
+
+        let mut chars: Vec<char> = args.charset.chars().collect();
+        let localized: Vec<Vec<Lookup>> = fonts
+            .faces
+            .iter()
+            .map(|face| match plan.language {
+                Some(language) => gsub_feature_lookups(face, b"locl", Some(language)),
+                None => Vec::new(),
+            })
+            .collect();
+        let mut ids: Vec<u16> = chars
+            .iter()
+            .zip(&sources)
+            .map(|(ch, source)| {
+                let glyph = GlyphId(fonts.fonts[*source].lookup_glyph_index(*ch));
+                substitute_single(&localized[*source], glyph).0
+            })
+            .collect();
+        let mut ligatures: Vec<(String, char)> = Vec::new();
+        if args.ligatures {
+            ligatures = add_ligatures(
+                &fonts.faces,
+                plan.language,
+                &mut chars,
+                &mut sources,
+                &mut ids,
+            );
+            if plan.grid() && chars.len() > plan.capacity() {
+                anyhow::bail!(
+                    "charset and its {} ligature(s) have {} glyph(s) but atlas capacity is {} cell(s) ({}x{} cells)",
+                    ligatures.len(),
+                    chars.len(),
+                    plan.capacity(),
+                    plan.cols,
+                    plan.rows
+                );
+            }
+        }
+        Ok(Self {
+            chars,
+            sources,
+            ids,
+            ligatures,
+        })
+    }
+}
+
+/// Appends the fonts' standard ligatures made only of atlas glyphs to the
+/// atlas, each under a private-use stand-in character. Returns the characters
+/// each ligature replaces with its stand-in.
+fn add_ligatures(
+    faces: &[ttf_parser::Face],
+    language: Option<Tag>,
+    glyph_chars: &mut Vec<char>,
+    sources: &mut Vec<usize>,
+    glyph_ids: &mut Vec<u16>,
+) -> Vec<(String, char)> {
+    let mut found = Vec::new();
+    for (source, face) in faces.iter().enumerate() {
+        let glyphs: Vec<(char, GlyphId)> = glyph_chars
+            .iter()
+            .zip(sources.iter())
+            .zip(glyph_ids.iter())
+            .filter(|((_, s), _)| **s == source)
+            .map(|((ch, _), glyph)| (*ch, GlyphId(*glyph)))
+            .collect();
+        let lookups = gsub_feature_lookups(face, b"liga", language);
+        for (sequence, glyph) in find_ligatures(&lookups, &glyphs) {
+            found.push((sequence, source, glyph));
+        }
+    }
+    let charset: HashSet<char> = glyph_chars.iter().copied().collect();
+    let mut stand_ins = ('\u{F0000}'..='\u{FFFFD}').filter(|ch| !charset.contains(ch));
+    let mut ligatures = Vec::new();
+    for (sequence, source, glyph) in found {
+        let Some(ch) = stand_ins.next() else {
+            break;
+        };
+        glyph_chars.push(ch);
+        sources.push(source);
+        glyph_ids.push(glyph.0);
+        ligatures.push((sequence, ch));
+    }
+    ligatures
+}
+
+/// The GSUB lookups of `feature` across every script, for the `language`
+/// system where a script has one and its default system otherwise.
+fn gsub_feature_lookups<'a>(
+    face: &ttf_parser::Face<'a>,
+    feature: &[u8; 4],
+    language: Option<Tag>,
+) -> Vec<Lookup<'a>> {
+    let Some(gsub) = face.tables().gsub else {
+        return Vec::new();
+    };
+    let feature = Tag::from_bytes(feature);
+    let mut indices = Vec::new();
+    for script in gsub.scripts {
+        let system = language
+            .and_then(|language| script.languages.find(language))
+            .or(script.default_language);
+        for index in system.into_iter().flat_map(|system| system.feature_indices) {
+            match gsub.features.get(index) {
+                Some(found) if found.tag == feature => indices.extend(found.lookup_indices),
+                _ => {}
+            }
+        }
+    }
+    // Lookups apply in lookup list order, whichever script listed them.
+    indices.sort_unstable();
+    indices.dedup();
+    indices
+        .into_iter()
+        .filter_map(|index| gsub.lookups.get(index))
+        .collect()
+}
+
+/// Applies the single substitutions of `lookups` to `glyph` in turn.
+/// Contextual substitutions need the surrounding text, so they're skipped.
+fn substitute_single(lookups: &[Lookup], glyph: GlyphId) -> GlyphId {
+    lookups.iter().fold(glyph, |glyph, lookup| {
+        lookup
+            .subtables
+            .into_iter::<SubstitutionSubtable>()
+            .find_map(|subtable| {
+                let SubstitutionSubtable::Single(single) = subtable else {
+                    return None;
+                };
+                let index = single.coverage().get(glyph)?;
+                match single {
+                    SingleSubstitution::Format1 { delta, .. } => {
+                        Some(GlyphId(glyph.0.wrapping_add(delta as u16)))
+                    }
+                    SingleSubstitution::Format2 { substitutes, .. } => substitutes.get(index),
+                }
+            })
+            .unwrap_or(glyph)
+    })
+}
+
+/// The ligatures of `lookups` whose components are all among `glyphs`, as
+/// the characters each replaces and its glyph, in charset order.
+fn find_ligatures(lookups: &[Lookup], glyphs: &[(char, GlyphId)]) -> Vec<(String, GlyphId)> {
+    let mut chars = HashMap::new();
+    for (ch, glyph) in glyphs {
+        chars.entry(*glyph).or_insert(*ch);
+    }
+
+    let mut seen = HashSet::new();
+    let mut out = Vec::new();
+    for (first, glyph) in glyphs {
+        for lookup in lookups {
+            for subtable in lookup.subtables.into_iter::<SubstitutionSubtable>() {
+                let SubstitutionSubtable::Ligature(substitution) = subtable else {
+                    continue;
+                };
+                let Some(set) = substitution
+                    .coverage
+                    .get(*glyph)
+                    .and_then(|index| substitution.ligature_sets.get(index))
+                else {
+                    continue;
+                };
+                for ligature in set {
+                    let rest: Option<String> = ligature
+                        .components
+                        .into_iter()
+                        .map(|component| chars.get(&component).copied())
+                        .collect();
+                    let Some(rest) = rest else {
+                        continue;
+                    };
+                    let sequence = format!("{first}{rest}");
+                    if seen.insert(sequence.clone()) {
+                        out.push((sequence, ligature.glyph));
+                    }
+                }
+            }
+        }
+    }
+    out
+}
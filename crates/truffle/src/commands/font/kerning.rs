@@ -0,0 +1,764 @@
+use super::cache::FontCache;
+use super::metadata::GlyphMeta;
+use super::{FontArgs, OpticalKerningMode};
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use ttf_parser::{GlyphId, Tag};
+
+#[derive(Clone, Serialize, Deserialize)]
+pub(crate) struct KerningPair {
+    pub(crate) left: char,
+    pub(crate) right: char,
+    /// Kerning adjustment in pixels (float) at `px` size (add to advance).
+    pub(crate) kern: f32,
+}
+
+/// Kerning pairs at `px` from the fonts' kerning tables, or measured from
+/// `ink_profiles` with `--optical-kerning`. Optical pairs are reused from
+/// `cache` when the same glyphs were measured before.
+pub(crate) fn kerning_pairs(
+    args: &FontArgs,
+    font_bytes: &[Vec<u8>],
+    sources: &[usize],
+    px: f32,
+    glyphs: &[GlyphMeta],
+    ink_profiles: &HashMap<char, InkProfile>,
+    cache: Option<&FontCache>,
+) -> anyhow::Result<Vec<KerningPair>> {
+    // Only glyphs from the same font kern against each other.
+    let mut kerning = Vec::new();
+    for (source, bytes) in font_bytes.iter().enumerate() {
+        let charset: String = args
+            .charset
+            .chars()
+            .zip(sources)
+            .filter(|(_, s)| **s == source)
+            .map(|(ch, _)| ch)
+            .collect();
+        if !charset.is_empty() {
+            kerning.extend(compute_kerning_table(bytes, &charset, px).unwrap_or_default());
+        }
+    }
+    if !matches!(args.optical_kerning, OpticalKerningMode::Off) {
+        // Prefer optical kerning when enabled; it works even when the font has no kerning tables.
+        // If optical yields nothing (e.g. empty masks), keep table kerning as a fallback.
+        let compute =
+            || compute_optical_kerning_pairs(glyphs, ink_profiles, args.optical_kerning_gap);
+        let optical = match cache {
+            Some(cache) => cache.optical_kerning(args, font_bytes, px, compute)?,
+            None => compute(),
+        };
+        if !optical.is_empty() {
+            kerning = optical;
+        }
+    }
+    if args.pixel_mode {
+        for pair in &mut kerning {
+            pair.kern = pair.kern.round();
+        }
+        kerning.retain(|pair| pair.kern != 0.0);
+    }
+    Ok(kerning)
+}
+
+#[derive(Clone)]
+pub(crate) struct InkProfile {
+    // Baseline-relative top y (inclusive) for row 0.
+    ymin: i32,
+    // Left bearing offset: bitmap x=0 corresponds to font x=xmin.
+    xmin: i32,
+    // For each row, left/right extents (inclusive) in glyph-local x coordinates.
+    // None means the row has no ink.
+    rows: Vec<Option<(u32, u32)>>,
+}
+
+pub(crate) fn ink_profile_from_alpha(
+    alpha: &[u8],
+    w: u32,
+    h: u32,
+    ymin: i32,
+    xmin: i32,
+    threshold: u8,
+) -> InkProfile {
+    let mut rows = Vec::with_capacity(h as usize);
+    if w == 0 || h == 0 {
+        return InkProfile { ymin, xmin, rows };
+    }
+    // Iterate from bottom (h-1) to top (0) so that rows[0] corresponds to ymin (bottom).
+    for y in (0..h).rev() {
+        let mut left: Option<u32> = None;
+        let mut right: Option<u32> = None;
+        let row_off = (y * w) as usize;
+        for x in 0..w {
+            let a = alpha[row_off + x as usize];
+            if a > threshold {
+                left = Some(left.map_or(x, |v| v.min(x)));
+                right = Some(right.map_or(x, |v| v.max(x)));
+            }
+        }
+        rows.push(left.zip(right));
+    }
+    InkProfile { ymin, xmin, rows }
+}
+
+/// One side of a glyph's ink: per baseline-relative row, the x of its left
+/// edge or just past its right edge, in font pixels. Rows without ink are
+/// `None`; empty rows at either end are trimmed so equal shapes compare equal.
+#[derive(Clone, PartialEq, Eq, Hash)]
+struct EdgeProfile {
+    ymin: i32,
+    edges: Vec<Option<i32>>,
+}
+
+impl EdgeProfile {
+    fn new(profile: &InkProfile, right: bool) -> Self {
+        let mut edges: Vec<Option<i32>> = profile
+            .rows
+            .iter()
+            .map(|row| {
+                row.map(|(l, r)| {
+                    if right {
+                        profile.xmin + r as i32 + 1
+                    } else {
+                        profile.xmin + l as i32
+                    }
+                })
+            })
+            .collect();
+        let leading = edges.iter().take_while(|e| e.is_none()).count();
+        let trailing = edges[leading..]
+            .iter()
+            .rev()
+            .take_while(|e| e.is_none())
+            .count();
+        edges.truncate(edges.len() - trailing);
+        edges.drain(..leading);
+        Self {
+            ymin: profile.ymin + leading as i32,
+            edges,
+        }
+    }
+}
+
+/// Smallest horizontal distance between `left`'s right edges and `right`'s
+/// left edges over the rows both have ink in, with both glyphs at x = 0.
+fn min_edge_gap(left: &EdgeProfile, right: &EdgeProfile) -> Option<i32> {
+    let y0 = left.ymin.max(right.ymin);
+    let y1 = (left.ymin + left.edges.len() as i32).min(right.ymin + right.edges.len() as i32);
+    (y0..y1)
+        .filter_map(|y| {
+            let l = left.edges[(y - left.ymin) as usize]?;
+            let r = right.edges[(y - right.ymin) as usize]?;
+            Some(r - l)
+        })
+        .min()
+}
+
+/// Interns profiles so glyphs with identical edges share one class.
+fn edge_class(
+    profile: EdgeProfile,
+    classes: &mut Vec<EdgeProfile>,
+    index: &mut HashMap<EdgeProfile, usize>,
+) -> usize {
+    *index.entry(profile).or_insert_with_key(|profile| {
+        classes.push(profile.clone());
+        classes.len() - 1
+    })
+}
+
+fn compute_optical_kerning_pairs(
+    glyph_metas: &[GlyphMeta],
+    profiles: &HashMap<char, InkProfile>,
+    target_gap_px: u32,
+) -> Vec<KerningPair> {
+    let target_gap = target_gap_px as f32;
+
+    // Edge profiles are computed once per glyph and grouped into classes, so
+    // the row scan runs once per distinct (right edge, left edge) shape pair
+    // instead of once per glyph pair.
+    let mut right_edges = Vec::new();
+    let mut right_index = HashMap::new();
+    let mut left_edges = Vec::new();
+    let mut left_index = HashMap::new();
+    let mut seen = std::collections::HashSet::new();
+    let mut glyphs = Vec::with_capacity(glyph_metas.len());
+    for g in glyph_metas {
+        // Avoid kerning around spaces; in most bitmap-font uses, spacing is handled separately.
+        if g.ch == ' ' || !seen.insert(g.ch) {
+            continue;
+        }
+        let Some(profile) = profiles.get(&g.ch) else {
+            continue;
+        };
+        let right = edge_class(
+            EdgeProfile::new(profile, true),
+            &mut right_edges,
+            &mut right_index,
+        );
+        let left = edge_class(
+            EdgeProfile::new(profile, false),
+            &mut left_edges,
+            &mut left_index,
+        );
+        glyphs.push((g.ch, g.advance, right, left));
+    }
+
+    // Both loops are split by left glyph across threads; collecting keeps
+    // the pairs in charset order.
+    let gaps: Vec<Option<i32>> = right_edges
+        .par_iter()
+        .flat_map_iter(|r| left_edges.iter().map(move |l| min_edge_gap(r, l)))
+        .collect();
+
+    glyphs
+        .par_iter()
+        .flat_map_iter(|&(left, advance, right_class, _)| {
+            let gaps = &gaps[right_class * left_edges.len()..][..left_edges.len()];
+            glyphs.iter().filter_map(move |&(right, _, _, left_class)| {
+                // Gap between the ink of both glyphs when right is placed at x = advance(left).
+                let min_gap = advance + gaps[left_class]? as f32;
+
+                // If min_gap is bigger than target, tighten (negative kern).
+                // If min_gap is smaller than target, loosen (positive kern).
+                // Use the delta directly to preserve subpixel precision.
+                let kern_px = target_gap - min_gap;
+
+                // Filter out very small kerning adjustments to avoid noise.
+                (kern_px.abs() >= 0.01).then_some(KerningPair {
+                    left,
+                    right,
+                    kern: kern_px,
+                })
+            })
+        })
+        .collect()
+}
+
+fn compute_kerning_table(
+    font_bytes: &[u8],
+    charset: &str,
+    px: f32,
+) -> anyhow::Result<Vec<KerningPair>> {
+    let face = ttf_parser::Face::parse(font_bytes, 0)
+        .map_err(|_| anyhow::anyhow!("failed to parse font for kerning"))?;
+    let upem = face.units_per_em() as f32;
+    let scale = px / upem;
+    // Filter out near-zero values to avoid noisy kerning entries while preserving subpixel kerning.
+    const KERN_EPS_PX: f32 = 1e-6;
+
+    let chars: Vec<char> = charset.chars().collect();
+    let mut gids = Vec::with_capacity(chars.len());
+    for &ch in &chars {
+        gids.push(face.glyph_index(ch));
+    }
+
+    let mut out = Vec::new();
+    if let Some(gpos) = face.raw_face().table(Tag::from_bytes(b"GPOS")) {
+        if let Ok(gpos_pairs) = compute_gpos_kerning_pairs(gpos, &chars, &gids) {
+            for (left, right, kern_units) in gpos_pairs {
+                let kern_px = kern_units as f32 * scale;
+                if kern_px.abs() >= KERN_EPS_PX {
+                    out.push(KerningPair {
+                        left,
+                        right,
+                        kern: kern_px,
+                    });
+                }
+            }
+            if !out.is_empty() {
+                return Ok(out);
+            }
+        }
+    }
+
+    let kern_table = face.tables().kern;
+    for (i, &left) in chars.iter().enumerate() {
+        let Some(lgid) = gids[i] else { continue };
+        for (j, &right) in chars.iter().enumerate() {
+            let Some(rgid) = gids[j] else { continue };
+            let mut kern_units: i32 = 0;
+            if let Some(kern_table) = kern_table {
+                for sub in kern_table.subtables {
+                    if !sub.horizontal || sub.has_cross_stream {
+                        continue;
+                    }
+                    if let Some(v) = sub.glyphs_kerning(lgid, rgid) {
+                        kern_units += v as i32;
+                    }
+                }
+            }
+
+            if kern_units != 0 {
+                let kern_px = kern_units as f32 * scale;
+                if kern_px.abs() < KERN_EPS_PX {
+                    continue;
+                }
+                out.push(KerningPair {
+                    left,
+                    right,
+                    kern: kern_px,
+                });
+            }
+        }
+    }
+
+    Ok(out)
+}
+
+fn read_u16(data: &[u8], offset: usize) -> Option<u16> {
+    let b0 = *data.get(offset)? as u16;
+    let b1 = *data.get(offset + 1)? as u16;
+    Some((b0 << 8) | b1)
+}
+
+fn read_i16(data: &[u8], offset: usize) -> Option<i16> {
+    Some(read_u16(data, offset)? as i16)
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Option<u32> {
+    let b0 = *data.get(offset)? as u32;
+    let b1 = *data.get(offset + 1)? as u32;
+    let b2 = *data.get(offset + 2)? as u32;
+    let b3 = *data.get(offset + 3)? as u32;
+    Some((b0 << 24) | (b1 << 16) | (b2 << 8) | b3)
+}
+
+fn tag_at(data: &[u8], offset: usize) -> Option<[u8; 4]> {
+    Some([
+        *data.get(offset)?,
+        *data.get(offset + 1)?,
+        *data.get(offset + 2)?,
+        *data.get(offset + 3)?,
+    ])
+}
+
+fn compute_gpos_kerning_pairs(
+    gpos: &[u8],
+    chars: &[char],
+    gids: &[Option<GlyphId>],
+) -> anyhow::Result<Vec<(char, char, i16)>> {
+    let major = read_u16(gpos, 0).unwrap_or(0);
+    let _minor = read_u16(gpos, 2).unwrap_or(0);
+    if major != 1 {
+        return Ok(vec![]);
+    }
+
+    let script_list_offset = read_u16(gpos, 4).unwrap_or(0) as usize;
+    let feature_list_offset = read_u16(gpos, 6).unwrap_or(0) as usize;
+    let lookup_list_offset = read_u16(gpos, 8).unwrap_or(0) as usize;
+
+    let mut lookup_indices =
+        select_kern_feature_lookups(gpos, script_list_offset, feature_list_offset)?;
+    if lookup_indices.is_empty() {
+        // Some fonts store kerning-like PairPos adjustments under other feature tags (e.g. `dist`,
+        // `palt`, etc) or rely on shaping defaults that don't include an explicit `kern` feature.
+        // As a fallback, scan all lookups and let `gpos_pair_adjust_xadvance` ignore non-PairPos.
+        lookup_indices = all_gpos_lookup_indices(gpos, lookup_list_offset);
+        if lookup_indices.is_empty() {
+            return Ok(vec![]);
+        }
+    }
+
+    let mut out = Vec::new();
+
+    for (i, &left) in chars.iter().enumerate() {
+        let Some(lgid) = gids[i] else { continue };
+        for (j, &right) in chars.iter().enumerate() {
+            let Some(rgid) = gids[j] else { continue };
+            if let Some(k) =
+                gpos_pair_adjust_xadvance(gpos, lookup_list_offset, &lookup_indices, lgid, rgid)
+            {
+                if k != 0 {
+                    out.push((left, right, k));
+                }
+            }
+        }
+    }
+
+    Ok(out)
+}
+
+fn all_gpos_lookup_indices(gpos: &[u8], lookup_list_offset: usize) -> Vec<u16> {
+    let lookup_count = read_u16(gpos, lookup_list_offset).unwrap_or(0) as usize;
+    (0..lookup_count).map(|i| i as u16).collect()
+}
+
+fn select_kern_feature_lookups(
+    gpos: &[u8],
+    script_list_offset: usize,
+    feature_list_offset: usize,
+) -> anyhow::Result<Vec<u16>> {
+    let script_count = read_u16(gpos, script_list_offset).unwrap_or(0) as usize;
+    let mut chosen_script_offset: Option<usize> = None;
+    let mut fallback_script_offset: Option<usize> = None;
+    for i in 0..script_count {
+        let rec = script_list_offset + 2 + i * 6;
+        let tag = tag_at(gpos, rec).unwrap_or([0, 0, 0, 0]);
+        let off = read_u16(gpos, rec + 4).unwrap_or(0) as usize;
+        let script_offset = script_list_offset + off;
+        if tag == *b"DFLT" {
+            chosen_script_offset = Some(script_offset);
+            break;
+        }
+        if fallback_script_offset.is_none() && tag == *b"latn" {
+            fallback_script_offset = Some(script_offset);
+        }
+        if fallback_script_offset.is_none() {
+            fallback_script_offset = Some(script_offset);
+        }
+    }
+    let script_offset = chosen_script_offset.or(fallback_script_offset);
+    let Some(script_offset) = script_offset else {
+        return Ok(vec![]);
+    };
+
+    let default_lang_sys_off = read_u16(gpos, script_offset).unwrap_or(0) as usize;
+    let mut lang_sys_offset = if default_lang_sys_off != 0 {
+        Some(script_offset + default_lang_sys_off)
+    } else {
+        None
+    };
+
+    if lang_sys_offset.is_none() {
+        let lang_sys_count = read_u16(gpos, script_offset + 2).unwrap_or(0) as usize;
+        for i in 0..lang_sys_count {
+            let rec = script_offset + 4 + i * 6;
+            let off = read_u16(gpos, rec + 4).unwrap_or(0) as usize;
+            if off != 0 {
+                lang_sys_offset = Some(script_offset + off);
+                break;
+            }
+        }
+    }
+
+    let Some(lang_sys_offset) = lang_sys_offset else {
+        return Ok(vec![]);
+    };
+
+    // LangSys table:
+    // u16 LookupOrder (unused, typically 0)
+    // u16 RequiredFeatureIndex (0xFFFF if none)
+    // u16 FeatureIndexCount
+    // u16 FeatureIndices[FeatureIndexCount]
+    let required_feature_index = read_u16(gpos, lang_sys_offset + 2).unwrap_or(0xFFFF);
+    let feature_count = read_u16(gpos, lang_sys_offset + 4).unwrap_or(0) as usize;
+    let mut feature_indices = Vec::with_capacity(feature_count);
+    for i in 0..feature_count {
+        feature_indices.push(read_u16(gpos, lang_sys_offset + 6 + i * 2).unwrap_or(0));
+    }
+    if required_feature_index != 0xFFFF {
+        feature_indices.push(required_feature_index);
+    }
+
+    let list_feature_count = read_u16(gpos, feature_list_offset).unwrap_or(0) as usize;
+    let mut lookup_indices = Vec::new();
+    for &feat_index in &feature_indices {
+        let idx = feat_index as usize;
+        if idx >= list_feature_count {
+            continue;
+        }
+        let rec = feature_list_offset + 2 + idx * 6;
+        let tag = tag_at(gpos, rec).unwrap_or([0, 0, 0, 0]);
+        if tag != *b"kern" {
+            continue;
+        }
+        let off = read_u16(gpos, rec + 4).unwrap_or(0) as usize;
+        let feature_offset = feature_list_offset + off;
+        let lookup_count = read_u16(gpos, feature_offset + 2).unwrap_or(0) as usize;
+        for i in 0..lookup_count {
+            lookup_indices.push(read_u16(gpos, feature_offset + 4 + i * 2).unwrap_or(0));
+        }
+    }
+
+    Ok(lookup_indices)
+}
+
+fn gpos_pair_adjust_xadvance(
+    gpos: &[u8],
+    lookup_list_offset: usize,
+    lookup_indices: &[u16],
+    left: GlyphId,
+    right: GlyphId,
+) -> Option<i16> {
+    let lookup_count = read_u16(gpos, lookup_list_offset).unwrap_or(0) as usize;
+    for &lookup_index in lookup_indices {
+        let idx = lookup_index as usize;
+        if idx >= lookup_count {
+            continue;
+        }
+        let lookup_offset = read_u16(gpos, lookup_list_offset + 2 + idx * 2).unwrap_or(0) as usize;
+        if lookup_offset == 0 {
+            continue;
+        }
+        let lookup = lookup_list_offset + lookup_offset;
+        let lookup_type = read_u16(gpos, lookup).unwrap_or(0);
+        let sub_count = read_u16(gpos, lookup + 4).unwrap_or(0) as usize;
+        for s in 0..sub_count {
+            let off = read_u16(gpos, lookup + 6 + s * 2).unwrap_or(0) as usize;
+            if off == 0 {
+                continue;
+            }
+            let sub = lookup + off;
+            let (resolved_type, resolved_sub) = if lookup_type == 9 {
+                let ext_format = read_u16(gpos, sub).unwrap_or(0);
+                if ext_format != 1 {
+                    continue;
+                }
+                let ext_type = read_u16(gpos, sub + 2).unwrap_or(0);
+                let ext_off = read_u32(gpos, sub + 4).unwrap_or(0) as usize;
+                if ext_off == 0 {
+                    continue;
+                }
+                (ext_type, sub + ext_off)
+            } else {
+                (lookup_type, sub)
+            };
+
+            if resolved_type != 2 {
+                continue;
+            }
+            if let Some(v) = pairpos_subtable_xadvance(gpos, resolved_sub, left, right) {
+                if v != 0 {
+                    return Some(v);
+                }
+            }
+        }
+    }
+    Some(0)
+}
+
+fn pairpos_subtable_xadvance(
+    gpos: &[u8],
+    sub: usize,
+    left: GlyphId,
+    right: GlyphId,
+) -> Option<i16> {
+    let pos_format = read_u16(gpos, sub).unwrap_or(0);
+    if pos_format == 1 {
+        let coverage_off = read_u16(gpos, sub + 2).unwrap_or(0) as usize;
+        let value_format_1 = read_u16(gpos, sub + 4).unwrap_or(0);
+        let value_format_2 = read_u16(gpos, sub + 6).unwrap_or(0);
+        let pair_set_count = read_u16(gpos, sub + 8).unwrap_or(0) as usize;
+        let coverage = sub + coverage_off;
+        let left_index = coverage_index(gpos, coverage, left.0)?;
+        if left_index >= pair_set_count {
+            return Some(0);
+        }
+        let pair_set_off = read_u16(gpos, sub + 10 + left_index * 2).unwrap_or(0) as usize;
+        if pair_set_off == 0 {
+            return Some(0);
+        }
+        let pair_set = sub + pair_set_off;
+        let pair_value_count = read_u16(gpos, pair_set).unwrap_or(0) as usize;
+        let mut record = pair_set + 2;
+        for _ in 0..pair_value_count {
+            let second = read_u16(gpos, record).unwrap_or(0);
+            record += 2;
+            let (v1, s1) = read_value_record_xadvance_xplace(gpos, record, value_format_1)?;
+            record += s1;
+            let (v2, s2) = read_value_record_xadvance_xplace(gpos, record, value_format_2)?;
+            record += s2;
+            if second == right.0 {
+                return Some(v1 + v2);
+            }
+        }
+        return Some(0);
+    }
+
+    if pos_format == 2 {
+        let coverage_off = read_u16(gpos, sub + 2).unwrap_or(0) as usize;
+        let value_format_1 = read_u16(gpos, sub + 4).unwrap_or(0);
+        let value_format_2 = read_u16(gpos, sub + 6).unwrap_or(0);
+        let class_def_1_off = read_u16(gpos, sub + 8).unwrap_or(0) as usize;
+        let class_def_2_off = read_u16(gpos, sub + 10).unwrap_or(0) as usize;
+        let class_count_1 = read_u16(gpos, sub + 12).unwrap_or(0) as usize;
+        let class_count_2 = read_u16(gpos, sub + 14).unwrap_or(0) as usize;
+        let coverage = sub + coverage_off;
+        if coverage_index(gpos, coverage, left.0).is_none() {
+            return Some(0);
+        }
+        let class_def_1 = sub + class_def_1_off;
+        let class_def_2 = sub + class_def_2_off;
+        let class_1 = class_def_value(gpos, class_def_1, left.0).unwrap_or(0) as usize;
+        let class_2 = class_def_value(gpos, class_def_2, right.0).unwrap_or(0) as usize;
+        if class_1 >= class_count_1 || class_2 >= class_count_2 {
+            return Some(0);
+        }
+        let rec_size_1 = value_record_size(value_format_1);
+        let rec_size_2 = value_record_size(value_format_2);
+        let class2_record_size = rec_size_1 + rec_size_2;
+        let class1_record_size = class2_record_size * class_count_2;
+        let base = sub + 16 + class_1 * class1_record_size + class_2 * class2_record_size;
+        let (v1, _) = read_value_record_xadvance_xplace(gpos, base, value_format_1)?;
+        let (v2, _) = read_value_record_xadvance_xplace(gpos, base + rec_size_1, value_format_2)?;
+        return Some(v1 + v2);
+    }
+
+    Some(0)
+}
+
+fn coverage_index(data: &[u8], coverage: usize, glyph_id: u16) -> Option<usize> {
+    let format = read_u16(data, coverage)? as u16;
+    if format == 1 {
+        let count = read_u16(data, coverage + 2)? as usize;
+        for i in 0..count {
+            let gid = read_u16(data, coverage + 4 + i * 2)?;
+            if gid == glyph_id {
+                return Some(i);
+            }
+        }
+        return None;
+    }
+    if format == 2 {
+        let count = read_u16(data, coverage + 2)? as usize;
+        for i in 0..count {
+            let rec = coverage + 4 + i * 6;
+            let start = read_u16(data, rec)?;
+            let end = read_u16(data, rec + 2)?;
+            let start_index = read_u16(data, rec + 4)? as usize;
+            if glyph_id >= start && glyph_id <= end {
+                return Some(start_index + (glyph_id - start) as usize);
+            }
+        }
+        return None;
+    }
+    None
+}
+
+fn class_def_value(data: &[u8], class_def: usize, glyph_id: u16) -> Option<u16> {
+    let format = read_u16(data, class_def)?;
+    if format == 1 {
+        let start = read_u16(data, class_def + 2)?;
+        let count = read_u16(data, class_def + 4)? as usize;
+        if glyph_id < start {
+            return Some(0);
+        }
+        let idx = (glyph_id - start) as usize;
+        if idx >= count {
+            return Some(0);
+        }
+        return Some(read_u16(data, class_def + 6 + idx * 2)?);
+    }
+    if format == 2 {
+        let count = read_u16(data, class_def + 2)? as usize;
+        for i in 0..count {
+            let rec = class_def + 4 + i * 6;
+            let start = read_u16(data, rec)?;
+            let end = read_u16(data, rec + 2)?;
+            let class = read_u16(data, rec + 4)?;
+            if glyph_id >= start && glyph_id <= end {
+                return Some(class);
+            }
+        }
+        return Some(0);
+    }
+    Some(0)
+}
+
+fn value_record_size(value_format: u16) -> usize {
+    let mut count = 0;
+    for bit in 0..8 {
+        if (value_format & (1 << bit)) != 0 {
+            count += 1;
+        }
+    }
+    count * 2
+}
+
+fn read_value_record_xadvance_xplace(
+    data: &[u8],
+    offset: usize,
+    value_format: u16,
+) -> Option<(i16, usize)> {
+    let mut cursor = offset;
+    let mut x_placement: i16 = 0;
+    let mut x_advance: i16 = 0;
+
+    if (value_format & 0x0001) != 0 {
+        x_placement = read_i16(data, cursor)?;
+        cursor += 2;
+    }
+    if (value_format & 0x0002) != 0 {
+        cursor += 2;
+    }
+    if (value_format & 0x0004) != 0 {
+        x_advance = read_i16(data, cursor)?;
+        cursor += 2;
+    }
+    if (value_format & 0x0008) != 0 {
+        cursor += 2;
+    }
+    if (value_format & 0x0010) != 0 {
+        let dev_off = read_u16(data, cursor)? as usize;
+        if dev_off != 0 {
+            cursor += 2;
+        } else {
+            cursor += 2;
+        }
+    }
+    if (value_format & 0x0020) != 0 {
+        cursor += 2;
+    }
+    if (value_format & 0x0040) != 0 {
+        cursor += 2;
+    }
+    if (value_format & 0x0080) != 0 {
+        cursor += 2;
+    }
+
+    Some((x_advance + x_placement, cursor - offset))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn optical_kerning_uses_edge_gaps() {
+        let glyph = |ch, advance| GlyphMeta {
+            ch,
+            index: 0,
+            font: 0,
+            colored: false,
+            col: 0,
+            row: 0,
+            cell_x: 0,
+            cell_y: 0,
+            cell_w: 0,
+            cell_h: 0,
+            draw_x: 0,
+            draw_y: 0,
+            draw_w: 0,
+            draw_h: 0,
+            offset_x: 0,
+            offset_y: 0,
+            advance,
+            vertical: None,
+        };
+        let block = InkProfile {
+            ymin: 0,
+            xmin: 0,
+            rows: vec![None, Some((0, 3)), Some((0, 3))],
+        };
+        let ell = InkProfile {
+            ymin: 0,
+            xmin: 0,
+            rows: vec![Some((0, 3)), Some((0, 0))],
+        };
+        let profiles = HashMap::from([('A', block.clone()), ('B', block), ('L', ell)]);
+        let metas = [
+            glyph('A', 5.0),
+            glyph('B', 5.0),
+            glyph('L', 7.0),
+            glyph(' ', 3.0),
+        ];
+
+        let pairs = compute_optical_kerning_pairs(&metas, &profiles, 1);
+        let pairs: Vec<_> = pairs.iter().map(|p| (p.left, p.right, p.kern)).collect();
+        assert_eq!(
+            pairs,
+            vec![('L', 'A', -5.0), ('L', 'B', -5.0), ('L', 'L', -2.0)]
+        );
+    }
+}
@@ -0,0 +1,1606 @@
+use super::cache::FontCache;
+use super::glyphs::GlyphSet;
+use super::kerning::{kerning_pairs, KerningPair};
+use super::render::DrawnSize;
+use super::{
+    file_name, FontArgs, FontFormat, FontPacking, FontPaths, KerningFormat, Orientation, Plan,
+    SdfMode,
+};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use ttf_parser::GlyphId;
+
+pub(crate) struct FontAtlasMeta {
+    atlas_w: u32,
+    atlas_h: u32,
+    /// File name of the atlas image holding this size's glyphs; only set
+    /// with `--sizes`.
+    atlas: Option<String>,
+    /// Asset id of the uploaded atlas image, when sync has uploaded it.
+    image: Option<String>,
+    /// Tight atlases describe glyphs by rect and offset; `cell`, `inner` and
+    /// `baseline` only apply to the grid.
+    packing: FontPacking,
+    cell: u32,
+    padding: u32,
+    inner: u32,
+    px: f32,
+    baseline: u32,
+    /// Kind of distance field the atlas stores and its range in pixels.
+    distance_field: Option<(SdfMode, f32)>,
+    charset: String,
+    /// File names of the input font and its fallbacks, in chain order.
+    fonts: Vec<String>,
+    glyphs: Vec<GlyphMeta>,
+    /// Kerning adjustments in pixels (float) for pairs within the charset.
+    kerning: Vec<KerningPair>,
+    kerning_format: KerningFormat,
+    /// Ligatures in the atlas: the characters each replaces and the stand-in
+    /// character its glyph is stored under.
+    ligatures: Vec<(String, char)>,
+    line_metrics: LineMetrics,
+}
+
+impl FontAtlasMeta {
+    /// Metadata of an effect variant drawn from this atlas: `glyphs` in the
+    /// variant's atlas image, uploaded as `image`.
+    fn variant(&self, glyphs: Vec<GlyphMeta>, image: Option<String>) -> FontAtlasMeta {
+        FontAtlasMeta {
+            atlas: None,
+            image,
+            distance_field: None,
+            charset: self.charset.clone(),
+            fonts: self.fonts.clone(),
+            glyphs,
+            kerning: self.kerning.clone(),
+            ligatures: self.ligatures.clone(),
+            ..*self
+        }
+    }
+
+    /// Metadata key of an atlas glyph: its character, or the characters a
+    /// ligature glyph replaces.
+    fn glyph_names(&self) -> impl Fn(char) -> String + '_ {
+        let ligatures: HashMap<char, &str> = self
+            .ligatures
+            .iter()
+            .map(|(sequence, ch)| (*ch, sequence.as_str()))
+            .collect();
+        move |ch| match ligatures.get(&ch) {
+            Some(sequence) => sequence.to_string(),
+            None => ch.to_string(),
+        }
+    }
+
+    /// Ligatures grouped by their first character, longest first, so a
+    /// shaper can take the first one that matches the text.
+    fn ligatures_by_first_char(&self) -> Vec<(char, Vec<&str>)> {
+        let mut groups: Vec<(char, Vec<&str>)> = Vec::new();
+        for (sequence, _) in &self.ligatures {
+            let Some(first) = sequence.chars().next() else {
+                continue;
+            };
+            match groups.iter_mut().find(|(ch, _)| *ch == first) {
+                Some((_, group)) => group.push(sequence),
+                None => groups.push((first, vec![sequence])),
+            }
+        }
+        for (_, group) in &mut groups {
+            group.sort_by_key(|sequence| std::cmp::Reverse(sequence.chars().count()));
+        }
+        groups
+    }
+}
+
+/// Vertical metrics of the input font in pixels at `px` size.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+struct LineMetrics {
+    /// Distance from the baseline up to the top of the tallest glyphs.
+    ascent: f32,
+    /// Distance from the baseline down to the bottom of descenders (positive).
+    descent: f32,
+    line_gap: f32,
+    /// Baseline-to-baseline distance: `ascent + descent + line_gap`.
+    line_height: f32,
+    /// Height of lowercase letters, from OS/2 or else measured from `x`.
+    x_height: Option<f32>,
+    /// Column metrics, with `--orientation vertical`.
+    vertical: Option<ColumnMetrics>,
+}
+
+/// Vertical text metrics of the input font in pixels at `px` size, from vhea,
+/// or else an em-wide column centered on the vertical baseline.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+struct ColumnMetrics {
+    /// Distance from the vertical baseline (the column's center line) to its right edge.
+    ascent: f32,
+    /// Distance from the vertical baseline to the column's left edge (positive).
+    descent: f32,
+    line_gap: f32,
+    /// Baseline-to-baseline distance between columns: `ascent + descent + line_gap`.
+    width: f32,
+}
+
+/// Placement of a glyph in vertical text, in pixels at `px` size.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct VerticalGlyphMetrics {
+    /// Advance height, from vmtx or else the ascender-to-descender height.
+    pub(crate) advance: f32,
+    /// Position of the glyph's top-left corner relative to the pen on the
+    /// vertical baseline (y down).
+    pub(crate) bearing_x: f32,
+    pub(crate) bearing_y: f32,
+}
+
+#[derive(Clone, Copy)]
+pub(crate) struct GlyphMeta {
+    pub(crate) ch: char,
+    pub(crate) index: u32,
+    /// Index into `FontAtlasMeta::fonts` of the font the glyph came from.
+    pub(crate) font: usize,
+    /// Drawn from the font's color data rather than as a white mask.
+    pub(crate) colored: bool,
+    pub(crate) col: u32,
+    pub(crate) row: u32,
+    pub(crate) cell_x: u32,
+    pub(crate) cell_y: u32,
+    pub(crate) cell_w: u32,
+    pub(crate) cell_h: u32,
+    pub(crate) draw_x: u32,
+    pub(crate) draw_y: u32,
+    pub(crate) draw_w: u32,
+    pub(crate) draw_h: u32,
+    /// Position of the glyph's top-left corner relative to the pen on the
+    /// baseline (y down).
+    pub(crate) offset_x: i32,
+    pub(crate) offset_y: i32,
+    /// Advance width in pixels at `px` size.
+    pub(crate) advance: f32,
+    /// With `--orientation vertical`, the glyph's placement in a column.
+    pub(crate) vertical: Option<VerticalGlyphMetrics>,
+}
+
+/// The metadata of a run's atlases.
+pub(crate) struct AtlasMetas {
+    /// One per size drawn.
+    sizes: Vec<FontAtlasMeta>,
+    /// The effect variants of the last size; effects can't be combined with
+    /// `--sizes`.
+    variants: Vec<(&'static str, FontAtlasMeta)>,
+}
+
+pub(crate) fn atlas_metas(
+    args: &FontArgs,
+    plan: &Plan,
+    paths: &FontPaths,
+    font_bytes: &[Vec<u8>],
+    glyphs: &GlyphSet,
+    sizes: Vec<DrawnSize>,
+    cache: Option<&FontCache>,
+) -> anyhow::Result<AtlasMetas> {
+    let font_names: Vec<String> = std::iter::once(&args.input_ttf)
+        .chain(&args.fallback)
+        .map(|path| file_name(path))
+        .collect();
+    let mut metas = Vec::with_capacity(sizes.len());
+    let mut variant_metas = Vec::new();
+    for drawn in sizes {
+        let kerning = kerning_pairs(
+            args,
+            font_bytes,
+            &glyphs.sources,
+            drawn.px,
+            &drawn.glyphs,
+            &drawn.ink_profiles,
+            cache,
+        )?;
+        let line_metrics = compute_line_metrics(
+            &font_bytes[0],
+            drawn.px,
+            args.orientation == Orientation::Vertical,
+        )?;
+        let atlas = &paths.atlases[drawn.atlas];
+        let meta = FontAtlasMeta {
+            atlas_w: plan.atlas_w,
+            atlas_h: plan.atlas_h,
+            atlas: plan.sized().then(|| file_name(atlas)),
+            image: args.image_ids.get(atlas).cloned(),
+            packing: plan.pack,
+            cell: args.cell,
+            padding: args.padding,
+            inner: plan.inner,
+            px: drawn.px,
+            baseline: drawn.baseline,
+            distance_field: args.sdf.map(|mode| (mode, args.sdf_range)),
+            charset: args.charset.clone(),
+            fonts: font_names.clone(),
+            glyphs: drawn.glyphs,
+            kerning,
+            kerning_format: args.kerning_format,
+            ligatures: glyphs.ligatures.clone(),
+            line_metrics,
+        };
+        variant_metas = plan
+            .effects
+            .iter()
+            .zip(drawn.variant_glyphs)
+            .zip(&paths.variant_pngs)
+            .map(|((effect, variant_glyphs), png_path)| {
+                let image = args.image_ids.get(png_path).cloned();
+                (effect.name(), meta.variant(variant_glyphs, image))
+            })
+            .collect();
+        metas.push(meta);
+    }
+    Ok(AtlasMetas {
+        sizes: metas,
+        variants: variant_metas,
+    })
+}
+
+/// Writes `metas` in `--format`: the Luau module and its declarations,
+/// BMFont descriptors or JSON.
+pub(crate) fn write_metadata(
+    args: &FontArgs,
+    paths: &FontPaths,
+    plan: &Plan,
+    metas: &AtlasMetas,
+) -> anyhow::Result<()> {
+    let (sized, variant_metas) = (plan.sized(), &metas.variants[..]);
+    let metas = &metas.sizes[..];
+    match args.format {
+        FontFormat::Luau => {
+            let luau = if sized {
+                render_sized_font_luau_module(metas)
+            } else {
+                render_font_luau_module(&metas[0], variant_metas)
+            };
+            fs::write(&paths.luau, luau).map_err(|e| {
+                anyhow::anyhow!(
+                    "failed to write Luau metadata {}: {e}",
+                    paths.luau.display()
+                )
+            })?;
+            let names = metas[0].glyph_names();
+            let glyph_names: Vec<String> = metas[0]
+                .glyphs
+                .iter()
+                .map(|glyph| names(glyph.ch))
+                .collect();
+            fs::write(
+                &paths.dts,
+                render_font_dts_module(
+                    &glyph_names,
+                    &plan
+                        .effects
+                        .iter()
+                        .map(|effect| effect.name())
+                        .collect::<Vec<_>>(),
+                    metas[0].packing,
+                    args.kerning_format,
+                    sized,
+                ),
+            )
+            .map_err(|e| {
+                anyhow::anyhow!(
+                    "failed to write TypeScript declarations {}: {e}",
+                    paths.dts.display()
+                )
+            })?;
+            println!(
+                "[font] Wrote metadata: {} and {}",
+                paths.luau.display(),
+                paths.dts.display()
+            );
+        }
+        FontFormat::Bmfont => {
+            let face = args
+                .input_ttf
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            let mut descriptors: Vec<(&Path, String)> = metas
+                .iter()
+                .zip(&paths.fnts)
+                .map(|(meta, path)| {
+                    let page = meta
+                        .atlas
+                        .clone()
+                        .unwrap_or_else(|| file_name(&args.output_png));
+                    (path.as_path(), render_bmfont(meta, &face, &page))
+                })
+                .collect();
+            for (((_, variant), png_path), fnt_path) in variant_metas
+                .iter()
+                .zip(&paths.variant_pngs)
+                .zip(&paths.variant_fnts)
+            {
+                descriptors.push((
+                    fnt_path,
+                    render_bmfont(variant, &face, &file_name(png_path)),
+                ));
+            }
+            for (path, fnt) in &descriptors {
+                fs::write(path, fnt).map_err(|e| {
+                    anyhow::anyhow!("failed to write BMFont descriptor {}: {e}", path.display())
+                })?;
+                println!("[font] Wrote metadata: {}", path.display());
+            }
+        }
+        FontFormat::Json => {
+            let json = render_font_json(metas, variant_metas, sized);
+            fs::write(&paths.json, serde_json::to_string_pretty(&json)? + "\n").map_err(|e| {
+                anyhow::anyhow!(
+                    "failed to write JSON metadata {}: {e}",
+                    paths.json.display()
+                )
+            })?;
+            println!("[font] Wrote metadata: {}", paths.json.display());
+        }
+    }
+    Ok(())
+}
+
+fn compute_line_metrics(font_bytes: &[u8], px: f32, vertical: bool) -> anyhow::Result<LineMetrics> {
+    let face = ttf_parser::Face::parse(font_bytes, 0)
+        .map_err(|_| anyhow::anyhow!("failed to parse font for line metrics"))?;
+    let scale = px / face.units_per_em() as f32;
+    // `ascender` and friends pick OS/2 typographic metrics when the font asks
+    // for them and hhea otherwise.
+    let ascent = face.ascender() as f32 * scale;
+    let descent = -(face.descender() as f32) * scale;
+    let line_gap = face.line_gap() as f32 * scale;
+    let x_height = face
+        .x_height()
+        .filter(|h| *h > 0)
+        .or_else(|| {
+            let glyph = face.glyph_index('x')?;
+            Some(face.glyph_bounding_box(glyph)?.y_max)
+        })
+        .map(|h| h as f32 * scale);
+    let vertical = vertical.then(|| {
+        let half_em = face.units_per_em() as f32 / 2.0;
+        let ascent = face.vertical_ascender().map_or(half_em, f32::from) * scale;
+        let descent = -face.vertical_descender().map_or(-half_em, f32::from) * scale;
+        let line_gap = face.vertical_line_gap().map_or(0.0, f32::from) * scale;
+        ColumnMetrics {
+            ascent,
+            descent,
+            line_gap,
+            width: ascent + descent + line_gap,
+        }
+    });
+    Ok(LineMetrics {
+        ascent,
+        descent,
+        line_gap,
+        line_height: ascent + descent + line_gap,
+        x_height,
+        vertical,
+    })
+}
+
+/// Where `glyph` sits in vertical text. The pen rests on the glyph's vertical
+/// origin: the one VORG gives, else the one vmtx implies (the top side bearing
+/// above the outline), else the ascender, centered horizontally on the column.
+pub(crate) fn vertical_glyph_metrics(
+    face: &ttf_parser::Face,
+    glyph: GlyphId,
+    px: f32,
+    metrics: &fontdue::Metrics,
+) -> VerticalGlyphMetrics {
+    let scale = px / face.units_per_em() as f32;
+    let advance = face
+        .glyph_ver_advance(glyph)
+        .map_or(face.ascender() as f32 - face.descender() as f32, f32::from);
+    let origin_y = face
+        .glyph_y_origin(glyph)
+        .map(f32::from)
+        .or_else(|| {
+            let bearing = face.glyph_ver_side_bearing(glyph)?;
+            let bbox = face.glyph_bounding_box(glyph)?;
+            Some(bbox.y_max as f32 + bearing as f32)
+        })
+        .unwrap_or(face.ascender() as f32);
+    VerticalGlyphMetrics {
+        advance: advance * scale,
+        bearing_x: metrics.xmin as f32 - metrics.advance_width / 2.0,
+        bearing_y: origin_y * scale - (metrics.ymin + metrics.height as i32) as f32,
+    }
+}
+
+/// Kerning pairs grouped by left character, both in first-appearance order.
+/// A repeated pair keeps its last amount.
+fn kerning_rows(pairs: &[KerningPair]) -> Vec<(char, Vec<(char, f32)>)> {
+    let mut rows: Vec<(char, Vec<(char, f32)>)> = Vec::new();
+    let mut row_index = HashMap::new();
+    let mut pair_index = HashMap::new();
+    for k in pairs {
+        let row = *row_index.entry(k.left).or_insert_with(|| {
+            rows.push((k.left, Vec::new()));
+            rows.len() - 1
+        });
+        let row = &mut rows[row].1;
+        let entry = *pair_index.entry((k.left, k.right)).or_insert_with(|| {
+            row.push((k.right, 0.0));
+            row.len() - 1
+        });
+        row[entry].1 = k.kern;
+    }
+    rows
+}
+
+/// Kerning with glyphs that kern identically grouped into classes, numbered
+/// from 1 like Luau arrays.
+struct KerningClasses {
+    left: Vec<(char, usize)>,
+    right: Vec<(char, usize)>,
+    /// For each left class, its amounts by right class.
+    pairs: Vec<Vec<(usize, f32)>>,
+}
+
+fn kerning_classes(pairs: &[KerningPair]) -> KerningClasses {
+    // Left glyphs with the same amount against every right glyph share a class.
+    let mut left = Vec::new();
+    let mut left_rows = Vec::new();
+    let mut left_index: HashMap<Vec<(char, u32)>, usize> = HashMap::new();
+    for (ch, row) in kerning_rows(pairs) {
+        let mut shape: Vec<(char, u32)> = row.iter().map(|(r, k)| (*r, k.to_bits())).collect();
+        shape.sort_unstable();
+        let class = *left_index.entry(shape).or_insert_with(|| {
+            left_rows.push(row);
+            left_rows.len()
+        });
+        left.push((ch, class));
+    }
+
+    // Right glyphs with the same amount against every left class share one too.
+    let mut columns: Vec<(char, Vec<(usize, f32)>)> = Vec::new();
+    let mut column_index = HashMap::new();
+    for (i, row) in left_rows.iter().enumerate() {
+        for (ch, kern) in row {
+            let column = *column_index.entry(*ch).or_insert_with(|| {
+                columns.push((*ch, Vec::new()));
+                columns.len() - 1
+            });
+            columns[column].1.push((i + 1, *kern));
+        }
+    }
+    let mut right = Vec::new();
+    let mut pairs = vec![Vec::new(); left_rows.len()];
+    let mut right_index: HashMap<Vec<(usize, u32)>, usize> = HashMap::new();
+    for (ch, column) in columns {
+        let shape: Vec<(usize, u32)> = column.iter().map(|(l, k)| (*l, k.to_bits())).collect();
+        let next = right_index.len() + 1;
+        let class = *right_index.entry(shape).or_insert(next);
+        if class == next {
+            for (left_class, kern) in column {
+                pairs[left_class - 1].push((class, kern));
+            }
+        }
+        right.push((ch, class));
+    }
+
+    KerningClasses { left, right, pairs }
+}
+
+/// The module of a single-size atlas, with one table per effect variant
+/// (`outline`, `shadow`, `gradient`) next to `font`.
+fn render_font_luau_module(meta: &FontAtlasMeta, variants: &[(&str, FontAtlasMeta)]) -> String {
+    let mut s = String::new();
+    s.push_str("-- This file is automatically @generated by truffle.\n");
+    s.push_str("-- DO NOT EDIT MANUALLY.\n\n");
+    s.push_str("local font = ");
+    s.push_str(&serialize_font_luau(meta, 0));
+    s.push_str("\n");
+    for (name, variant) in variants {
+        s.push_str(&format!("local {name} = "));
+        s.push_str(&serialize_font_luau(variant, 0));
+        s.push_str("\n");
+    }
+    s.push_str("return {\n");
+    s.push_str("\tfont = font,\n");
+    for (name, _) in variants {
+        s.push_str(&format!("\t{name} = {name},\n"));
+    }
+    s.push_str("}\n");
+    s
+}
+
+/// The module of a `--sizes` atlas: one font table per pixel size.
+fn render_sized_font_luau_module(metas: &[FontAtlasMeta]) -> String {
+    let mut s = String::new();
+    s.push_str("-- This file is automatically @generated by truffle.\n");
+    s.push_str("-- DO NOT EDIT MANUALLY.\n\n");
+    s.push_str("local sizes = {\n");
+    for meta in metas {
+        s.push_str(&format!(
+            "\t[{}] = {},\n",
+            float_luau(meta.px),
+            serialize_font_luau(meta, 1)
+        ));
+    }
+    s.push_str("}\n");
+    s.push_str("return {\n");
+    s.push_str("\tsizes = sizes,\n");
+    s.push_str("}\n");
+    s
+}
+
+/// The AngelCode BMFont text descriptor of an atlas whose image is `page`.
+///
+/// BMFont measures glyph offsets from the top of the line, which puts the
+/// baseline at `base`, the font's ascent. Distance field glyphs span their
+/// whole cell so the field's falloff is drawn too, and the descriptor gets
+/// the `distanceField` line msdf-atlas-gen writes.
+fn render_bmfont(meta: &FontAtlasMeta, face: &str, page: &str) -> String {
+    let lm = &meta.line_metrics;
+    let base = lm.ascent.round() as i32;
+    let quoted = |s: &str| format!("\"{}\"", s.replace('"', ""));
+    let (alpha, color) = match meta.distance_field {
+        // Every channel of a multi-channel field holds glyph data.
+        Some((SdfMode::Msdf, _)) => (0, 0),
+        // Glyph data in alpha, white in RGB.
+        _ => (0, 4),
+    };
+
+    let mut lines = vec![
+        format!(
+            "info face={} size={} bold=0 italic=0 charset=\"\" unicode=1 stretchH=100 smooth=1 aa=1 padding={p},{p},{p},{p} spacing=0,0",
+            quoted(face),
+            meta.px.round() as i32,
+            p = meta.padding
+        ),
+        format!(
+            "common lineHeight={} base={} scaleW={} scaleH={} pages=1 packed=0 alphaChnl={alpha} redChnl={color} greenChnl={color} blueChnl={color}",
+            lm.line_height.round() as i32,
+            base,
+            meta.atlas_w,
+            meta.atlas_h
+        ),
+    ];
+    if let Some((mode, range)) = meta.distance_field {
+        lines.push(format!(
+            "distanceField fieldType={} distanceRange={}",
+            mode.name(),
+            float_luau(range)
+        ));
+    }
+    lines.push(format!("page id=0 file={}", quoted(page)));
+
+    let mut seen = std::collections::HashSet::new();
+    let glyphs: Vec<&GlyphMeta> = meta.glyphs.iter().filter(|g| seen.insert(g.ch)).collect();
+    lines.push(format!("chars count={}", glyphs.len()));
+    for g in glyphs {
+        let (x, y, w, h) = if meta.distance_field.is_some() {
+            (g.cell_x, g.cell_y, g.cell_w, g.cell_h)
+        } else {
+            (g.draw_x, g.draw_y, g.draw_w, g.draw_h)
+        };
+        let (x, y, w, h, xoffset, yoffset) = if g.draw_w == 0 || g.draw_h == 0 {
+            (0, 0, 0, 0, 0, 0)
+        } else {
+            // Shift the offsets by however far the rect starts before the ink.
+            let xoffset = g.offset_x - (g.draw_x - x) as i32;
+            let yoffset = base + g.offset_y - (g.draw_y - y) as i32;
+            (x, y, w, h, xoffset, yoffset)
+        };
+        lines.push(format!(
+            "char id={} x={x} y={y} width={w} height={h} xoffset={xoffset} yoffset={yoffset} xadvance={} page=0 chnl=15",
+            g.ch as u32,
+            g.advance.round() as i32
+        ));
+    }
+
+    let kernings: Vec<(char, char, i32)> = meta
+        .kerning
+        .iter()
+        .map(|k| (k.left, k.right, k.kern.round() as i32))
+        .filter(|(_, _, amount)| *amount != 0)
+        .collect();
+    lines.push(format!("kernings count={}", kernings.len()));
+    for (first, second, amount) in kernings {
+        lines.push(format!(
+            "kerning first={} second={} amount={amount}",
+            first as u32, second as u32
+        ));
+    }
+
+    lines.join("\n") + "\n"
+}
+
+/// The JSON descriptor: the same shape as the Luau module.
+fn render_font_json(
+    metas: &[FontAtlasMeta],
+    variants: &[(&str, FontAtlasMeta)],
+    sized: bool,
+) -> serde_json::Value {
+    let mut root = serde_json::Map::new();
+    if sized {
+        let sizes = metas
+            .iter()
+            .map(|meta| (float_luau(meta.px), font_meta_json(meta)))
+            .collect();
+        root.insert("sizes".into(), serde_json::Value::Object(sizes));
+    } else {
+        root.insert("font".into(), font_meta_json(&metas[0]));
+        for (name, variant) in variants {
+            root.insert((*name).into(), font_meta_json(variant));
+        }
+    }
+    serde_json::Value::Object(root)
+}
+
+fn font_meta_json(meta: &FontAtlasMeta) -> serde_json::Value {
+    use serde_json::json;
+
+    let grid = meta.packing == FontPacking::Grid;
+    let mut out = serde_json::Map::new();
+    out.insert("atlasW".into(), json!(meta.atlas_w));
+    out.insert("atlasH".into(), json!(meta.atlas_h));
+    if let Some(atlas) = &meta.atlas {
+        out.insert("atlas".into(), json!(atlas));
+    }
+    if let Some(image) = &meta.image {
+        out.insert("image".into(), json!(image));
+    }
+    if grid {
+        out.insert("cell".into(), json!(meta.cell));
+        out.insert("inner".into(), json!(meta.inner));
+        out.insert("baseline".into(), json!(meta.baseline));
+    } else {
+        out.insert("packing".into(), json!("tight"));
+    }
+    out.insert("padding".into(), json!(meta.padding));
+    out.insert("px".into(), json!(meta.px));
+    let lm = &meta.line_metrics;
+    out.insert("ascent".into(), json!(lm.ascent));
+    out.insert("descent".into(), json!(lm.descent));
+    out.insert("lineGap".into(), json!(lm.line_gap));
+    out.insert("lineHeight".into(), json!(lm.line_height));
+    if let Some(x_height) = lm.x_height {
+        out.insert("xHeight".into(), json!(x_height));
+    }
+    if let Some(column) = lm.vertical {
+        out.insert("orientation".into(), json!("vertical"));
+        out.insert("vAscent".into(), json!(column.ascent));
+        out.insert("vDescent".into(), json!(column.descent));
+        out.insert("vLineGap".into(), json!(column.line_gap));
+        out.insert("columnWidth".into(), json!(column.width));
+    }
+    if let Some((mode, range)) = meta.distance_field {
+        out.insert("sdf".into(), json!(mode.name()));
+        out.insert("distanceRange".into(), json!(range));
+    }
+    out.insert("charset".into(), json!(meta.charset));
+
+    let name = meta.glyph_names();
+    let mut glyphs = serde_json::Map::new();
+    for g in &meta.glyphs {
+        let mut glyph = serde_json::Map::new();
+        glyph.insert("ch".into(), json!(name(g.ch)));
+        glyph.insert("index".into(), json!(g.index));
+        if meta.fonts.len() > 1 {
+            glyph.insert("font".into(), json!(meta.fonts[g.font]));
+        }
+        if grid {
+            glyph.insert("col".into(), json!(g.col));
+            glyph.insert("row".into(), json!(g.row));
+            glyph.insert("cellX".into(), json!(g.cell_x));
+            glyph.insert("cellY".into(), json!(g.cell_y));
+            glyph.insert("cellW".into(), json!(g.cell_w));
+            glyph.insert("cellH".into(), json!(g.cell_h));
+            glyph.insert("drawX".into(), json!(g.draw_x));
+            glyph.insert("drawY".into(), json!(g.draw_y));
+            glyph.insert("drawW".into(), json!(g.draw_w));
+            glyph.insert("drawH".into(), json!(g.draw_h));
+        } else {
+            glyph.insert("x".into(), json!(g.draw_x));
+            glyph.insert("y".into(), json!(g.draw_y));
+            glyph.insert("w".into(), json!(g.draw_w));
+            glyph.insert("h".into(), json!(g.draw_h));
+            glyph.insert("offsetX".into(), json!(g.offset_x));
+            glyph.insert("offsetY".into(), json!(g.offset_y));
+        }
+        glyph.insert("advance".into(), json!(g.advance));
+        if let Some(v) = g.vertical {
+            glyph.insert("vAdvance".into(), json!(v.advance));
+            glyph.insert("vBearingX".into(), json!(v.bearing_x));
+            glyph.insert("vBearingY".into(), json!(v.bearing_y));
+        }
+        glyph.insert("colored".into(), json!(g.colored));
+        glyphs.insert(name(g.ch), serde_json::Value::Object(glyph));
+    }
+    out.insert("glyphs".into(), serde_json::Value::Object(glyphs));
+    out.insert(
+        "kerning".into(),
+        match meta.kerning_format {
+            KerningFormat::Nested => kerning_rows(&meta.kerning)
+                .into_iter()
+                .map(|(left, row)| {
+                    let row = row
+                        .into_iter()
+                        .map(|(right, kern)| (name(right), json!(kern)))
+                        .collect();
+                    (name(left), serde_json::Value::Object(row))
+                })
+                .collect(),
+            KerningFormat::List => meta
+                .kerning
+                .iter()
+                .map(|k| json!({ "left": name(k.left), "right": name(k.right), "kern": k.kern }))
+                .collect(),
+            KerningFormat::Classes => {
+                let classes = kerning_classes(&meta.kerning);
+                let members =
+                    |members: &[(char, usize)]| -> serde_json::Map<String, serde_json::Value> {
+                        members
+                            .iter()
+                            .map(|(ch, class)| (name(*ch), json!(class)))
+                            .collect()
+                    };
+                // Keyed by class number like the Luau arrays, which count from 1.
+                let pairs: serde_json::Map<String, serde_json::Value> = classes
+                    .pairs
+                    .iter()
+                    .enumerate()
+                    .map(|(i, row)| {
+                        let row = row
+                            .iter()
+                            .map(|(class, kern)| (class.to_string(), json!(kern)))
+                            .collect();
+                        ((i + 1).to_string(), serde_json::Value::Object(row))
+                    })
+                    .collect();
+                json!({
+                    "left": members(&classes.left),
+                    "right": members(&classes.right),
+                    "pairs": pairs,
+                })
+            }
+        },
+    );
+    if !meta.ligatures.is_empty() {
+        out.insert(
+            "ligatures".into(),
+            meta.ligatures_by_first_char()
+                .into_iter()
+                .map(|(first, group)| (first.to_string(), json!(group)))
+                .collect(),
+        );
+    }
+    serde_json::Value::Object(out)
+}
+
+/// `glyph_names` are the keys of `glyphs`, typed as a `FontGlyphName` union so
+/// TypeScript catches lookups of glyphs that aren't in the atlas.
+fn render_font_dts_module(
+    glyph_names: &[String],
+    variants: &[&str],
+    packing: FontPacking,
+    kerning_format: KerningFormat,
+    sized: bool,
+) -> String {
+    // This is intentionally simple: the Luau module returns `{ font = ... }`.
+    // TS consumers can use the declared shape to read widths/kerning later.
+    let (glyph_fields, layout_fields) = match packing {
+        FontPacking::Grid => (
+            "\tcol: number;\n\
+             \trow: number;\n\
+             \tcellX: number;\n\
+             \tcellY: number;\n\
+             \tcellW: number;\n\
+             \tcellH: number;\n\
+             \tdrawX: number;\n\
+             \tdrawY: number;\n\
+             \tdrawW: number;\n\
+             \tdrawH: number;\n",
+            "\tcell: number;\n\
+             \tpadding: number;\n\
+             \tinner: number;\n\
+             \tpx: number;\n\
+             \tbaseline: number;\n",
+        ),
+        FontPacking::Tight => (
+            "\tx: number;\n\
+             \ty: number;\n\
+             \tw: number;\n\
+             \th: number;\n\
+             \toffsetX: number;\n\
+             \toffsetY: number;\n",
+            "\tpacking: \"tight\";\n\
+             \tpadding: number;\n\
+             \tpx: number;\n",
+        ),
+    };
+    let (kerning_types, kerning_field) = match kerning_format {
+        KerningFormat::Nested => (
+            "",
+            "Partial<Record<FontGlyphName, Partial<Record<FontGlyphName, number>>>>",
+        ),
+        KerningFormat::List => (
+            "export interface FontKerningPair {\n\
+             \tleft: FontGlyphName;\n\
+             \tright: FontGlyphName;\n\
+             \tkern: number;\n\
+             }\n\n",
+            "FontKerningPair[]",
+        ),
+        KerningFormat::Classes => (
+            "export interface FontKerningClasses {\n\
+             \tleft: Partial<Record<FontGlyphName, number>>;\n\
+             \tright: Partial<Record<FontGlyphName, number>>;\n\
+             \tpairs: Record<number, Record<number, number>>;\n\
+             }\n\n",
+            "FontKerningClasses",
+        ),
+    };
+    let atlas_field = if sized { "\tatlas: string;\n" } else { "" };
+    let exports = if sized {
+        "declare const sizes: Record<number, FontAtlasMeta>;\n\
+         export { sizes };\n"
+    } else {
+        "declare const font: FontAtlasMeta;\n\
+         export { font };\n"
+    };
+    let glyph_name_type = if glyph_names.is_empty() {
+        "string".to_string()
+    } else {
+        glyph_names
+            .iter()
+            .map(|name| serde_json::to_string(name).unwrap())
+            .collect::<Vec<_>>()
+            .join(" | ")
+    };
+    let mut out = format!(
+        "// This file is automatically @generated by truffle.\n\
+         // DO NOT EDIT MANUALLY.\n\n\
+         export type FontGlyphName = {glyph_name_type};\n\n\
+         export interface FontGlyph {{\n\
+         \tch: FontGlyphName;\n\
+         \tindex: number;\n\
+         \tfont?: string;\n\
+         {glyph_fields}\
+         \tadvance: number;\n\
+         \tvAdvance?: number;\n\
+         \tvBearingX?: number;\n\
+         \tvBearingY?: number;\n\
+         \tcolored: boolean;\n\
+         }}\n\n\
+         {kerning_types}\
+         export interface FontAtlasMeta {{\n\
+         \tatlasW: number;\n\
+         \tatlasH: number;\n\
+         {atlas_field}\
+         \timage?: string;\n\
+         {layout_fields}\
+         \tascent: number;\n\
+         \tdescent: number;\n\
+         \tlineGap: number;\n\
+         \tlineHeight: number;\n\
+         \txHeight?: number;\n\
+         \torientation?: \"vertical\";\n\
+         \tvAscent?: number;\n\
+         \tvDescent?: number;\n\
+         \tvLineGap?: number;\n\
+         \tcolumnWidth?: number;\n\
+         \tsdf?: \"sdf\" | \"msdf\";\n\
+         \tdistanceRange?: number;\n\
+         \tcharset: string;\n\
+         \tglyphs: Record<FontGlyphName, FontGlyph>;\n\
+         \tkerning: {kerning_field};\n\
+         \tligatures?: Partial<Record<FontGlyphName, FontGlyphName[]>>;\n\
+         }}\n\n\
+         {exports}"
+    );
+    for name in variants {
+        out.push_str("\n");
+        out.push_str(&format!("declare const {name}: FontAtlasMeta;\n"));
+        out.push_str(&format!("export {{ {name} }};\n"));
+    }
+    out
+}
+
+fn serialize_font_luau(meta: &FontAtlasMeta, indent: usize) -> String {
+    let indent_str = "\t".repeat(indent);
+    let inner_indent = format!("{}\t", indent_str);
+    let first_level = indent == 0;
+
+    let mut parts = vec!["{".to_string()];
+    parts.push(format!("{}atlasW = {},", inner_indent, meta.atlas_w));
+    parts.push(format!("{}atlasH = {},", inner_indent, meta.atlas_h));
+    if let Some(atlas) = &meta.atlas {
+        parts.push(format!(
+            "{}atlas = {},",
+            inner_indent,
+            serde_json::to_string(atlas).unwrap()
+        ));
+    }
+    if let Some(image) = &meta.image {
+        parts.push(format!(
+            "{}image = {},",
+            inner_indent,
+            serde_json::to_string(image).unwrap()
+        ));
+    }
+    let grid = meta.packing == FontPacking::Grid;
+    if grid {
+        parts.push(format!("{}cell = {},", inner_indent, meta.cell));
+        parts.push(format!("{}padding = {},", inner_indent, meta.padding));
+        parts.push(format!("{}inner = {},", inner_indent, meta.inner));
+        parts.push(format!("{}px = {},", inner_indent, float_luau(meta.px)));
+        parts.push(format!("{}baseline = {},", inner_indent, meta.baseline));
+    } else {
+        parts.push(format!("{}packing = \"tight\",", inner_indent));
+        parts.push(format!("{}padding = {},", inner_indent, meta.padding));
+        parts.push(format!("{}px = {},", inner_indent, float_luau(meta.px)));
+    }
+    let lm = &meta.line_metrics;
+    parts.push(format!(
+        "{}ascent = {},",
+        inner_indent,
+        float_luau(lm.ascent)
+    ));
+    parts.push(format!(
+        "{}descent = {},",
+        inner_indent,
+        float_luau(lm.descent)
+    ));
+    parts.push(format!(
+        "{}lineGap = {},",
+        inner_indent,
+        float_luau(lm.line_gap)
+    ));
+    parts.push(format!(
+        "{}lineHeight = {},",
+        inner_indent,
+        float_luau(lm.line_height)
+    ));
+    if let Some(x_height) = lm.x_height {
+        parts.push(format!(
+            "{}xHeight = {},",
+            inner_indent,
+            float_luau(x_height)
+        ));
+    }
+    if let Some(column) = lm.vertical {
+        parts.push(format!("{}orientation = \"vertical\",", inner_indent));
+        parts.push(format!(
+            "{}vAscent = {},",
+            inner_indent,
+            float_luau(column.ascent)
+        ));
+        parts.push(format!(
+            "{}vDescent = {},",
+            inner_indent,
+            float_luau(column.descent)
+        ));
+        parts.push(format!(
+            "{}vLineGap = {},",
+            inner_indent,
+            float_luau(column.line_gap)
+        ));
+        parts.push(format!(
+            "{}columnWidth = {},",
+            inner_indent,
+            float_luau(column.width)
+        ));
+    }
+    if let Some((mode, range)) = meta.distance_field {
+        parts.push(format!("{}sdf = \"{}\",", inner_indent, mode.name()));
+        parts.push(format!(
+            "{}distanceRange = {},",
+            inner_indent,
+            float_luau(range)
+        ));
+    }
+    parts.push(format!(
+        "{}charset = {},",
+        inner_indent,
+        serde_json::to_string(&meta.charset).unwrap()
+    ));
+
+    // Glyphs as a dictionary keyed by character for easy lookup later.
+    let name = meta.glyph_names();
+    let key = |ch: char| serde_json::to_string(&name(ch)).unwrap();
+    parts.push(format!("{}glyphs = {{", inner_indent));
+    for g in &meta.glyphs {
+        let key = key(g.ch);
+        parts.push(format!("{}\t[{}] = {{", inner_indent, key));
+        parts.push(format!("{}\t\tch = {},", inner_indent, key));
+        parts.push(format!("{}\t\tindex = {},", inner_indent, g.index));
+        // The source font only matters once there's a fallback to tell apart.
+        if meta.fonts.len() > 1 {
+            parts.push(format!(
+                "{}\t\tfont = {},",
+                inner_indent,
+                serde_json::to_string(&meta.fonts[g.font]).unwrap()
+            ));
+        }
+        if grid {
+            parts.push(format!("{}\t\tcol = {},", inner_indent, g.col));
+            parts.push(format!("{}\t\trow = {},", inner_indent, g.row));
+            parts.push(format!("{}\t\tcellX = {},", inner_indent, g.cell_x));
+            parts.push(format!("{}\t\tcellY = {},", inner_indent, g.cell_y));
+            parts.push(format!("{}\t\tcellW = {},", inner_indent, g.cell_w));
+            parts.push(format!("{}\t\tcellH = {},", inner_indent, g.cell_h));
+            parts.push(format!("{}\t\tdrawX = {},", inner_indent, g.draw_x));
+            parts.push(format!("{}\t\tdrawY = {},", inner_indent, g.draw_y));
+            parts.push(format!("{}\t\tdrawW = {},", inner_indent, g.draw_w));
+            parts.push(format!("{}\t\tdrawH = {},", inner_indent, g.draw_h));
+        } else {
+            parts.push(format!("{}\t\tx = {},", inner_indent, g.draw_x));
+            parts.push(format!("{}\t\ty = {},", inner_indent, g.draw_y));
+            parts.push(format!("{}\t\tw = {},", inner_indent, g.draw_w));
+            parts.push(format!("{}\t\th = {},", inner_indent, g.draw_h));
+            parts.push(format!("{}\t\toffsetX = {},", inner_indent, g.offset_x));
+            parts.push(format!("{}\t\toffsetY = {},", inner_indent, g.offset_y));
+        }
+        parts.push(format!(
+            "{}\t\tadvance = {},",
+            inner_indent,
+            float_luau(g.advance)
+        ));
+        if let Some(v) = g.vertical {
+            parts.push(format!(
+                "{}\t\tvAdvance = {},",
+                inner_indent,
+                float_luau(v.advance)
+            ));
+            parts.push(format!(
+                "{}\t\tvBearingX = {},",
+                inner_indent,
+                float_luau(v.bearing_x)
+            ));
+            parts.push(format!(
+                "{}\t\tvBearingY = {},",
+                inner_indent,
+                float_luau(v.bearing_y)
+            ));
+        }
+        parts.push(format!("{}\t\tcolored = {},", inner_indent, g.colored));
+        parts.push(format!("{}\t}},", inner_indent));
+    }
+    parts.push(format!("{}}},", inner_indent));
+
+    parts.push(format!("{}kerning = {{", inner_indent));
+    match meta.kerning_format {
+        KerningFormat::Nested => {
+            for (left, row) in kerning_rows(&meta.kerning) {
+                let amounts: Vec<String> = row
+                    .iter()
+                    .map(|(right, kern)| format!("[{}] = {}", key(*right), float_luau(*kern)))
+                    .collect();
+                parts.push(format!(
+                    "{}\t[{}] = {{ {} }},",
+                    inner_indent,
+                    key(left),
+                    amounts.join(", ")
+                ));
+            }
+        }
+        KerningFormat::List => {
+            for k in &meta.kerning {
+                parts.push(format!(
+                    "{}\t{{ left = {}, right = {}, kern = {} }},",
+                    inner_indent,
+                    key(k.left),
+                    key(k.right),
+                    float_luau(k.kern)
+                ));
+            }
+        }
+        KerningFormat::Classes => {
+            let classes = kerning_classes(&meta.kerning);
+            for (name, members) in [("left", &classes.left), ("right", &classes.right)] {
+                parts.push(format!("{}\t{} = {{", inner_indent, name));
+                for (ch, class) in members {
+                    parts.push(format!("{}\t\t[{}] = {},", inner_indent, key(*ch), class));
+                }
+                parts.push(format!("{}\t}},", inner_indent));
+            }
+            parts.push(format!("{}\tpairs = {{", inner_indent));
+            for row in &classes.pairs {
+                let amounts: Vec<String> = row
+                    .iter()
+                    .map(|(class, kern)| format!("[{}] = {}", class, float_luau(*kern)))
+                    .collect();
+                parts.push(format!("{}\t\t{{ {} }},", inner_indent, amounts.join(", ")));
+            }
+            parts.push(format!("{}\t}},", inner_indent));
+        }
+    }
+    parts.push(format!("{}}},", inner_indent));
+
+    // Ligature glyphs are keyed by the characters they replace, grouped here
+    // by first character for the shaper.
+    if !meta.ligatures.is_empty() {
+        parts.push(format!("{}ligatures = {{", inner_indent));
+        for (first, group) in meta.ligatures_by_first_char() {
+            let sequences: Vec<String> = group
+                .iter()
+                .map(|sequence| serde_json::to_string(sequence).unwrap())
+                .collect();
+            parts.push(format!(
+                "{}\t[{}] = {{ {} }},",
+                inner_indent,
+                key(first),
+                sequences.join(", ")
+            ));
+        }
+        parts.push(format!("{}}},", inner_indent));
+    }
+
+    parts.push(format!("{}}}", indent_str));
+    let result = parts.join("\n");
+    if first_level {
+        format!("{}\n", result)
+    } else {
+        result
+    }
+}
+
+fn float_luau(v: f32) -> String {
+    if v.is_finite() {
+        // Keep it reasonably compact but stable-ish.
+        let s = format!("{v:.6}");
+        s.trim_end_matches('0').trim_end_matches('.').to_string()
+    } else {
+        "0".to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dts_contains_expected_exports() {
+        let dts = render_font_dts_module(&[], &[], FontPacking::Grid, KerningFormat::Nested, false);
+        assert!(dts.contains("export interface FontAtlasMeta"));
+        assert!(dts.contains("declare const font: FontAtlasMeta;"));
+        assert!(dts.contains("export { font };"));
+        assert!(dts.contains("export type FontGlyphName = string;"));
+
+        let names = ["A".to_string(), "\"".to_string(), "fi".to_string()];
+        let dts =
+            render_font_dts_module(&names, &[], FontPacking::Grid, KerningFormat::List, false);
+        assert!(dts.contains("export type FontGlyphName = \"A\" | \"\\\"\" | \"fi\";\n"));
+        assert!(dts.contains("\tglyphs: Record<FontGlyphName, FontGlyph>;\n"));
+        assert!(dts.contains("\tleft: FontGlyphName;\n"));
+
+        let dts =
+            render_font_dts_module(&[], &[], FontPacking::Tight, KerningFormat::Nested, false);
+        assert!(dts.contains("\toffsetX: number;"));
+        assert!(!dts.contains("cellX"));
+    }
+
+    #[test]
+    fn dts_includes_outline_when_enabled() {
+        let dts = render_font_dts_module(
+            &[],
+            &["outline"],
+            FontPacking::Grid,
+            KerningFormat::Nested,
+            false,
+        );
+        assert!(dts.contains("declare const outline: FontAtlasMeta;"));
+        assert!(dts.contains("export { outline };"));
+    }
+
+    #[test]
+    fn luau_records_the_distance_field() {
+        let mut meta = FontAtlasMeta {
+            atlas_w: 64,
+            atlas_h: 64,
+            atlas: None,
+            image: None,
+            cell: 32,
+            padding: 4,
+            packing: FontPacking::Grid,
+            inner: 24,
+            px: 24.0,
+            baseline: 20,
+            distance_field: None,
+            charset: "A".into(),
+            fonts: vec!["font.ttf".into()],
+            glyphs: Vec::new(),
+            kerning: Vec::new(),
+            kerning_format: KerningFormat::Nested,
+            ligatures: Vec::new(),
+            line_metrics: LineMetrics::default(),
+        };
+        assert!(!serialize_font_luau(&meta, 0).contains("sdf"));
+
+        meta.distance_field = Some((SdfMode::Msdf, 4.0));
+        let luau = serialize_font_luau(&meta, 0);
+        assert!(luau.contains("\tsdf = \"msdf\",\n\tdistanceRange = 4,"));
+        assert!(
+            render_font_dts_module(&[], &[], FontPacking::Grid, KerningFormat::Nested, false)
+                .contains("sdf?: \"sdf\" | \"msdf\";")
+        );
+    }
+
+    #[test]
+    fn luau_records_line_metrics() {
+        let mut meta = FontAtlasMeta {
+            atlas_w: 64,
+            atlas_h: 64,
+            atlas: None,
+            image: None,
+            cell: 16,
+            padding: 1,
+            packing: FontPacking::Tight,
+            inner: 14,
+            px: 16.0,
+            baseline: 0,
+            distance_field: None,
+            charset: "x".into(),
+            fonts: vec!["font.ttf".into()],
+            glyphs: Vec::new(),
+            kerning: Vec::new(),
+            kerning_format: KerningFormat::Nested,
+            ligatures: Vec::new(),
+            line_metrics: LineMetrics {
+                ascent: 14.5,
+                descent: 3.5,
+                line_gap: 1.0,
+                line_height: 19.0,
+                x_height: Some(8.25),
+                vertical: None,
+            },
+        };
+        let luau = serialize_font_luau(&meta, 0);
+        assert!(luau.contains(
+            "\tpx = 16,\n\tascent = 14.5,\n\tdescent = 3.5,\n\tlineGap = 1,\n\tlineHeight = 19,\n\txHeight = 8.25,\n"
+        ));
+
+        meta.line_metrics.x_height = None;
+        assert!(!serialize_font_luau(&meta, 0).contains("xHeight"));
+        let dts =
+            render_font_dts_module(&[], &[], FontPacking::Tight, KerningFormat::Nested, false);
+        assert!(dts.contains("\tlineHeight: number;\n\txHeight?: number;\n"));
+    }
+
+    #[test]
+    fn vertical_orientation_adds_column_and_glyph_metrics() {
+        let meta = FontAtlasMeta {
+            atlas_w: 32,
+            atlas_h: 32,
+            atlas: None,
+            image: None,
+            cell: 16,
+            padding: 1,
+            packing: FontPacking::Tight,
+            inner: 14,
+            px: 16.0,
+            baseline: 0,
+            distance_field: None,
+            charset: "永".into(),
+            fonts: vec!["font.ttf".into()],
+            glyphs: vec![GlyphMeta {
+                ch: '永',
+                index: 0,
+                font: 0,
+                colored: false,
+                col: 0,
+                row: 0,
+                cell_x: 0,
+                cell_y: 0,
+                cell_w: 16,
+                cell_h: 16,
+                draw_x: 1,
+                draw_y: 1,
+                draw_w: 14,
+                draw_h: 15,
+                offset_x: 1,
+                offset_y: -13,
+                advance: 16.0,
+                vertical: Some(VerticalGlyphMetrics {
+                    advance: 16.0,
+                    bearing_x: -7.0,
+                    bearing_y: 0.5,
+                }),
+            }],
+            kerning: Vec::new(),
+            kerning_format: KerningFormat::Nested,
+            ligatures: Vec::new(),
+            line_metrics: LineMetrics {
+                ascent: 14.0,
+                descent: 2.0,
+                line_gap: 0.0,
+                line_height: 16.0,
+                x_height: None,
+                vertical: Some(ColumnMetrics {
+                    ascent: 8.0,
+                    descent: 8.0,
+                    line_gap: 2.0,
+                    width: 18.0,
+                }),
+            },
+        };
+        let luau = serialize_font_luau(&meta, 0);
+        assert!(luau.contains(
+            "\tlineHeight = 16,\n\torientation = \"vertical\",\n\tvAscent = 8,\n\tvDescent = 8,\n\tvLineGap = 2,\n\tcolumnWidth = 18,\n"
+        ));
+        assert!(luau.contains(
+            "\t\t\tadvance = 16,\n\t\t\tvAdvance = 16,\n\t\t\tvBearingX = -7,\n\t\t\tvBearingY = 0.5,\n"
+        ));
+
+        let json = font_meta_json(&meta);
+        assert_eq!(json["orientation"], "vertical");
+        assert_eq!(json["columnWidth"], 18.0);
+        assert_eq!(json["glyphs"]["永"]["vBearingX"], -7.0);
+        assert_eq!(json["glyphs"]["永"]["vAdvance"], 16.0);
+    }
+
+    #[test]
+    fn bmfont_and_json_describe_the_atlas() {
+        let glyph = |ch: char, draw_x, draw_w| GlyphMeta {
+            ch,
+            index: 0,
+            font: 0,
+            colored: false,
+            col: 0,
+            row: 0,
+            cell_x: draw_x - 2,
+            cell_y: 0,
+            cell_w: 16,
+            cell_h: 16,
+            draw_x,
+            draw_y: 3,
+            draw_w,
+            draw_h: if draw_w > 0 { 10 } else { 0 },
+            offset_x: 1,
+            offset_y: -10,
+            advance: 9.4,
+            vertical: None,
+        };
+        let mut meta = FontAtlasMeta {
+            atlas_w: 64,
+            atlas_h: 16,
+            atlas: None,
+            image: None,
+            cell: 16,
+            padding: 2,
+            packing: FontPacking::Grid,
+            inner: 12,
+            px: 12.0,
+            baseline: 4,
+            distance_field: None,
+            charset: "A V".into(),
+            fonts: vec!["font.ttf".into()],
+            glyphs: vec![glyph('A', 4, 8), glyph(' ', 18, 0), glyph('V', 36, 8)],
+            kerning: vec![
+                KerningPair {
+                    left: 'A',
+                    right: 'V',
+                    kern: -1.2,
+                },
+                KerningPair {
+                    left: 'V',
+                    right: ' ',
+                    kern: 0.3,
+                },
+            ],
+            kerning_format: KerningFormat::List,
+            ligatures: Vec::new(),
+            line_metrics: LineMetrics {
+                ascent: 11.2,
+                descent: 2.6,
+                line_gap: 0.0,
+                line_height: 13.8,
+                vertical: None,
+                x_height: None,
+            },
+        };
+        let fnt = render_bmfont(&meta, "Inter", "inter.png");
+        assert!(fnt.starts_with("info face=\"Inter\" size=12 "));
+        assert!(fnt.contains("\ncommon lineHeight=14 base=11 scaleW=64 scaleH=16 pages=1 "));
+        assert!(fnt.contains("\npage id=0 file=\"inter.png\"\nchars count=3\n"));
+        assert!(fnt.contains(
+            "\nchar id=65 x=4 y=3 width=8 height=10 xoffset=1 yoffset=1 xadvance=9 page=0 chnl=15\n"
+        ));
+        assert!(fnt.contains(
+            "\nchar id=32 x=0 y=0 width=0 height=0 xoffset=0 yoffset=0 xadvance=9 page=0 chnl=15\n"
+        ));
+        assert!(fnt.ends_with("kernings count=1\nkerning first=65 second=86 amount=-1\n"));
+
+        // Distance fields span the whole cell.
+        meta.distance_field = Some((SdfMode::Sdf, 4.0));
+        let fnt = render_bmfont(&meta, "Inter", "inter.png");
+        assert!(fnt.contains("\ndistanceField fieldType=sdf distanceRange=4\n"));
+        assert!(fnt.contains("\nchar id=65 x=2 y=0 width=16 height=16 xoffset=-1 yoffset=-2 "));
+
+        let json = render_font_json(&[meta], &[], false);
+        assert_eq!(json["font"]["glyphs"]["V"]["drawX"], 36);
+        assert_eq!(json["font"]["sdf"], "sdf");
+        assert_eq!(json["font"]["kerning"][0]["left"], "A");
+        assert!(json.get("outline").is_none());
+    }
+
+    #[test]
+    fn luau_records_glyph_fonts_with_fallbacks() {
+        let glyph = |ch, font| GlyphMeta {
+            ch,
+            index: 0,
+            font,
+            colored: false,
+            col: 0,
+            row: 0,
+            cell_x: 0,
+            cell_y: 0,
+            cell_w: 16,
+            cell_h: 16,
+            draw_x: 1,
+            draw_y: 1,
+            draw_w: 8,
+            draw_h: 10,
+            offset_x: 0,
+            offset_y: -10,
+            advance: 9.0,
+            vertical: None,
+        };
+        let mut meta = FontAtlasMeta {
+            atlas_w: 64,
+            atlas_h: 64,
+            atlas: None,
+            image: None,
+            cell: 16,
+            padding: 1,
+            packing: FontPacking::Grid,
+            inner: 14,
+            px: 14.0,
+            baseline: 12,
+            distance_field: None,
+            charset: "Aあ".into(),
+            fonts: vec!["Inter.ttf".into()],
+            glyphs: vec![glyph('A', 0)],
+            kerning: Vec::new(),
+            kerning_format: KerningFormat::Nested,
+            ligatures: Vec::new(),
+            line_metrics: LineMetrics::default(),
+        };
+        assert!(!serialize_font_luau(&meta, 0).contains("font ="));
+
+        meta.fonts.push("NotoSansJP.ttf".into());
+        meta.glyphs.push(glyph('あ', 1));
+        let luau = serialize_font_luau(&meta, 0);
+        assert!(luau.contains("\t\t\tch = \"A\",\n\t\t\tindex = 0,\n\t\t\tfont = \"Inter.ttf\","));
+        assert!(luau.contains("\t\t\tfont = \"NotoSansJP.ttf\","));
+    }
+
+    #[test]
+    fn ligature_glyphs_are_keyed_by_their_characters() {
+        let glyph = |ch| GlyphMeta {
+            ch,
+            index: 0,
+            font: 0,
+            colored: false,
+            col: 0,
+            row: 0,
+            cell_x: 0,
+            cell_y: 0,
+            cell_w: 16,
+            cell_h: 16,
+            draw_x: 1,
+            draw_y: 1,
+            draw_w: 8,
+            draw_h: 10,
+            offset_x: 0,
+            offset_y: -10,
+            advance: 9.0,
+            vertical: None,
+        };
+        let (fi, ffi) = ('\u{F0000}', '\u{F0001}');
+        let meta = FontAtlasMeta {
+            atlas_w: 64,
+            atlas_h: 64,
+            atlas: None,
+            image: None,
+            cell: 16,
+            padding: 1,
+            packing: FontPacking::Grid,
+            inner: 14,
+            px: 14.0,
+            baseline: 12,
+            distance_field: None,
+            charset: "fi".into(),
+            fonts: vec!["Inter.ttf".into()],
+            glyphs: vec![glyph('f'), glyph('i'), glyph(fi), glyph(ffi)],
+            kerning: vec![KerningPair {
+                left: fi,
+                right: 'f',
+                kern: -1.0,
+            }],
+            kerning_format: KerningFormat::Nested,
+            ligatures: vec![("fi".into(), fi), ("ffi".into(), ffi)],
+            line_metrics: LineMetrics::default(),
+        };
+        let luau = serialize_font_luau(&meta, 0);
+        assert!(luau.contains("\t\t[\"ffi\"] = {\n\t\t\tch = \"ffi\",\n"));
+        assert!(luau.contains("\t\t[\"fi\"] = { [\"f\"] = -1 },\n"));
+        // Longest first, so a shaper can take the first match.
+        assert!(luau.ends_with("\tligatures = {\n\t\t[\"f\"] = { \"ffi\", \"fi\" },\n\t},\n}\n"));
+
+        let json = font_meta_json(&meta);
+        assert_eq!(json["glyphs"]["fi"]["ch"], "fi");
+        assert_eq!(json["ligatures"]["f"][0], "ffi");
+    }
+
+    #[test]
+    fn sized_modules_key_each_size() {
+        let meta = |px: f32, atlas: &str| FontAtlasMeta {
+            atlas_w: 128,
+            atlas_h: 128,
+            atlas: Some(atlas.into()),
+            image: None,
+            cell: 16,
+            padding: 1,
+            packing: FontPacking::Tight,
+            inner: 14,
+            px,
+            baseline: 0,
+            distance_field: None,
+            charset: "A".into(),
+            fonts: vec!["font.ttf".into()],
+            glyphs: Vec::new(),
+            kerning: Vec::new(),
+            kerning_format: KerningFormat::Nested,
+            ligatures: Vec::new(),
+            line_metrics: LineMetrics::default(),
+        };
+        let luau =
+            render_sized_font_luau_module(&[meta(16.0, "font_16.png"), meta(24.0, "font_24.png")]);
+        assert!(luau.contains("local sizes = {\n\t[16] = {\n\t\tatlasW = 128,"));
+        assert!(luau.contains("\t[24] = {"));
+        assert!(luau.contains("\t\tatlas = \"font_24.png\",\n\t\tpacking = \"tight\","));
+        assert!(luau.ends_with("\t},\n}\nreturn {\n\tsizes = sizes,\n}\n"));
+
+        let dts = render_font_dts_module(&[], &[], FontPacking::Tight, KerningFormat::Nested, true);
+        assert!(dts.contains("\tatlas: string;"));
+        assert!(dts.contains("declare const sizes: Record<number, FontAtlasMeta>;"));
+        assert!(!dts.contains("const font"));
+    }
+
+    #[test]
+    fn kerning_groups_by_left_glyph_and_class() {
+        let pair = |left, right, kern| KerningPair { left, right, kern };
+        let pairs = [
+            pair('A', 'V', -1.0),
+            pair('L', 'V', -2.0),
+            pair('A', 'W', -1.0),
+            pair('Á', 'W', -1.0),
+            pair('Á', 'V', -1.0),
+            pair('L', 'T', -2.0),
+            pair('L', 'T', -2.5),
+        ];
+        assert_eq!(
+            kerning_rows(&pairs),
+            vec![
+                ('A', vec![('V', -1.0), ('W', -1.0)]),
+                ('L', vec![('V', -2.0), ('T', -2.5)]),
+                ('Á', vec![('W', -1.0), ('V', -1.0)]),
+            ]
+        );
+
+        // A and Á kern the same against every glyph; no two right glyphs do.
+        let classes = kerning_classes(&pairs);
+        assert_eq!(classes.left, vec![('A', 1), ('L', 2), ('Á', 1)]);
+        assert_eq!(classes.right, vec![('V', 1), ('W', 2), ('T', 3)]);
+        assert_eq!(
+            classes.pairs,
+            vec![vec![(1, -1.0), (2, -1.0)], vec![(1, -2.0), (3, -2.5)]]
+        );
+
+        let dts =
+            render_font_dts_module(&[], &[], FontPacking::Grid, KerningFormat::Classes, false);
+        assert!(dts.contains("\tkerning: FontKerningClasses;\n"));
+        assert!(!dts.contains("FontKerningPair"));
+        let dts = render_font_dts_module(&[], &[], FontPacking::Grid, KerningFormat::Nested, false);
+        assert!(dts.contains(
+            "\tkerning: Partial<Record<FontGlyphName, Partial<Record<FontGlyphName, number>>>>;\n"
+        ));
+    }
+}
@@ -0,0 +1,866 @@
+mod cache;
+mod glyphs;
+mod kerning;
+mod metadata;
+mod render;
+
+use cache::FontCache;
+use clap::Parser;
+use clap::ValueEnum;
+use glyphs::{Fonts, GlyphSet};
+use metadata::{GlyphMeta, VerticalGlyphMetrics};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use ttf_parser::Tag;
+
+#[derive(Copy, Clone, Debug, ValueEnum, Serialize)]
+pub enum OpticalKerningMode {
+    /// Disable optical kerning.
+    Off,
+    /// Compute optical kerning from filled glyph masks (font rasterization).
+    Fill,
+    /// Compute optical kerning from outline masks (dilated alpha). Falls back to Fill if outline is disabled.
+    Outline,
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, ValueEnum, Serialize)]
+pub enum Hinting {
+    /// Rasterize outlines as designed.
+    #[default]
+    None,
+    /// Snap the baseline, x-height, cap height, ascender and descender to whole pixels.
+    Slight,
+    /// Also snap vertical stems to whole pixels, and round advances.
+    Full,
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, ValueEnum, Serialize)]
+pub enum Orientation {
+    /// Glyphs are set in rows along a horizontal baseline.
+    #[default]
+    Horizontal,
+    /// Also emit vertical metrics so glyphs can be set in columns, as in CJK text.
+    Vertical,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, ValueEnum, Serialize)]
+pub enum SdfMode {
+    /// Single-channel signed distance field, stored in alpha.
+    Sdf,
+    /// Multi-channel signed distance field in RGB, with the single-channel field in alpha.
+    Msdf,
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, ValueEnum, Serialize)]
+pub enum FontPacking {
+    /// One `--cell` sized cell per glyph, in charset order.
+    #[default]
+    Grid,
+    /// Each glyph's own rect, packed with the atlas bin packer.
+    Tight,
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, ValueEnum, Serialize)]
+pub enum FontFormat {
+    /// Luau module with a TypeScript declaration file.
+    #[default]
+    Luau,
+    /// AngelCode BMFont text descriptor (.fnt).
+    Bmfont,
+    /// Plain JSON descriptor with the same shape as the Luau module.
+    Json,
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, ValueEnum, Serialize)]
+pub enum KerningFormat {
+    /// `kerning[left][right] = px`, for constant-time lookup.
+    #[default]
+    Nested,
+    /// The legacy flat list of `{ left, right, kern }` pairs.
+    List,
+    /// Glyphs that kern identically share a class: `pairs[left[l]][right[r]] = px`.
+    Classes,
+}
+
+/// A styled variant of the atlas, drawn into its own image next to the plain one.
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum Effect {
+    /// Black stroke of this many pixels around a white fill.
+    Outline(u32),
+    /// Blurred black copy offset by `dx`,`dy` behind a white fill.
+    Shadow { dx: i32, dy: i32, blur: u32 },
+    /// Fill blended from `top` to `bottom` over the charset's full height.
+    Gradient { top: [u8; 3], bottom: [u8; 3] },
+}
+
+impl Effect {
+    fn name(self) -> &'static str {
+        match self {
+            Effect::Outline(_) => "outline",
+            Effect::Shadow { .. } => "shadow",
+            Effect::Gradient { .. } => "gradient",
+        }
+    }
+
+    /// Pixels the effect draws beyond the glyph bitmap: left, top, right, bottom.
+    fn margins(self) -> (u32, u32, u32, u32) {
+        match self {
+            Effect::Outline(r) => (r, r, r, r),
+            Effect::Shadow { dx, dy, blur } => {
+                let blur = blur as i32;
+                (
+                    (blur - dx).max(0) as u32,
+                    (blur - dy).max(0) as u32,
+                    (blur + dx).max(0) as u32,
+                    (blur + dy).max(0) as u32,
+                )
+            }
+            Effect::Gradient { .. } => (0, 0, 0, 0),
+        }
+    }
+
+    /// `glyph` as drawn in the variant: the plain rect grown by the margins.
+    fn glyph_meta(self, glyph: &GlyphMeta) -> GlyphMeta {
+        let (left, top, right, bottom) = self.margins();
+        let (draw_w, draw_h) = if glyph.draw_w > 0 && glyph.draw_h > 0 {
+            (glyph.draw_w + left + right, glyph.draw_h + top + bottom)
+        } else {
+            (0, 0)
+        };
+        GlyphMeta {
+            draw_x: glyph.draw_x.saturating_sub(left),
+            draw_y: glyph.draw_y.saturating_sub(top),
+            draw_w,
+            draw_h,
+            offset_x: glyph.offset_x - left as i32,
+            offset_y: glyph.offset_y - top as i32,
+            vertical: glyph.vertical.map(|v| VerticalGlyphMetrics {
+                bearing_x: v.bearing_x - left as f32,
+                bearing_y: v.bearing_y - top as f32,
+                ..v
+            }),
+            ..*glyph
+        }
+    }
+
+    fn describe(self) -> String {
+        match self {
+            Effect::Outline(r) => format!("dilate {r}px"),
+            Effect::Shadow { dx, dy, blur } => format!("offset {dx},{dy}, blur {blur}px"),
+            Effect::Gradient { top, bottom } => {
+                format!("{} to {}", hex_color(top), hex_color(bottom))
+            }
+        }
+    }
+}
+
+impl SdfMode {
+    fn name(self) -> &'static str {
+        match self {
+            SdfMode::Sdf => "sdf",
+            SdfMode::Msdf => "msdf",
+        }
+    }
+}
+
+// Serialized into the atlas cache key, so every option invalidates cached
+// atlases unless it is skipped here.
+#[derive(Parser, Debug, Serialize)]
+#[command(about = "Generate an image atlas from a .ttf font")]
+pub struct FontArgs {
+    /// Input .ttf font file
+    #[arg(value_name = "INPUT_TTF")]
+    #[serde(skip)]
+    pub input_ttf: PathBuf,
+
+    /// Output PNG atlas path
+    #[arg(value_name = "OUTPUT_PNG")]
+    #[serde(skip)]
+    pub output_png: PathBuf,
+
+    /// Fallback font for charset characters INPUT_TTF has no glyph for. Repeat to build a
+    /// chain; each character comes from the first font that contains it.
+    #[arg(long, value_name = "FONT")]
+    #[serde(skip)]
+    pub fallback: Vec<PathBuf>,
+
+    /// Cell size in pixels (cell x cell)
+    #[arg(long, default_value = "16")]
+    pub cell: u32,
+
+    /// Padding in pixels inside each cell (applied on all sides)
+    #[arg(long, default_value = "1")]
+    pub padding: u32,
+
+    /// How glyphs are laid out in the atlas. `tight` packs each glyph's own rect and
+    /// emits the rects instead of cell coordinates; `--cell` still sets the glyph size.
+    #[arg(long, default_value = "grid", value_enum)]
+    pub pack: FontPacking,
+
+    /// Rasterize the charset at each of these pixel sizes (e.g. 16,24,32) instead of
+    /// fitting one size to `--cell`. Implies `--pack tight`; metadata is keyed by size.
+    #[arg(long, value_delimiter = ',', value_name = "PX")]
+    pub sizes: Vec<u32>,
+
+    /// With `--sizes`, write one atlas per size to `<OUTPUT_PNG stem>_<px>.png` instead
+    /// of packing every size into OUTPUT_PNG.
+    #[arg(long, default_value_t = false)]
+    pub split_sizes: bool,
+
+    /// Rasterize at exactly this pixel size instead of fitting the size to `--cell`.
+    /// Glyphs that don't fit their cell are reported as an error.
+    #[arg(long, value_name = "PX")]
+    pub px: Option<f32>,
+
+    /// Charset string; glyphs are packed in this order (left-to-right, top-to-bottom)
+    #[arg(
+        long,
+        default_value = " !\"#$%&'()*+,-./0123456789:;<=>?@ABCDEFGHIJKLMNOPQRSTUVWXYZ[\\]^_`abcdefghijklmnopqrstuvwxyz{|}~"
+    )]
+    pub charset: String,
+
+    /// Atlas size in pixels as WxH (e.g. 1024x1024)
+    #[arg(long, default_value = "1024x1024", value_name = "WxH")]
+    pub size: String,
+
+    /// Metadata format. `bmfont` and `json` write OUTPUT_PNG with a .fnt or .json extension
+    /// instead of the Luau module and declaration file.
+    #[arg(long, default_value = "luau", value_enum)]
+    pub format: FontFormat,
+
+    /// Also atlas the font's standard ligatures (`liga`, e.g. fi and fl) made only of charset
+    /// characters, and list them in a `ligatures` table for text shaping.
+    #[arg(long)]
+    pub ligatures: bool,
+
+    /// OpenType language system tag (e.g. TRK, ROM, NLD) whose `locl` substitutions replace
+    /// charset glyphs with their localized forms. Also selects the language's ligatures.
+    #[arg(long, value_name = "TAG")]
+    pub language: Option<String>,
+
+    /// Shape of the kerning table in the Luau and JSON metadata. `classes` groups glyphs
+    /// with identical kerning to shrink the module for large charsets.
+    #[arg(long, default_value = "nested", value_enum)]
+    pub kerning_format: KerningFormat,
+
+    /// Output Luau metadata module path. Defaults to OUTPUT_PNG with .luau extension.
+    #[arg(long, value_name = "OUTPUT_LUAU")]
+    #[serde(skip)]
+    pub luau: Option<PathBuf>,
+
+    /// Output TypeScript declaration file for the Luau module. Defaults to OUTPUT_PNG with .d.ts extension.
+    #[arg(long, value_name = "OUTPUT_D_TS")]
+    #[serde(skip)]
+    pub dts: Option<PathBuf>,
+
+    /// Generate an outline (thicker fill) variant by dilating glyph alpha by this many pixels.
+    /// 0 disables outline generation.
+    #[arg(long, default_value = "0", value_name = "PX")]
+    pub outline: u32,
+
+    /// Output PNG atlas path for the outline variant. Defaults to OUTPUT_PNG with `_outline.png` suffix.
+    #[arg(long, value_name = "OUTPUT_OUTLINE_PNG")]
+    #[serde(skip)]
+    pub outline_png: Option<PathBuf>,
+
+    /// Generate a drop shadow variant: a black copy of each glyph offset by DX,DY pixels
+    /// and blurred over BLUR pixels, behind the fill. Written to OUTPUT_PNG with `_shadow.png` suffix.
+    #[arg(long, value_name = "DX,DY,BLUR", allow_hyphen_values = true)]
+    pub shadow: Option<String>,
+
+    /// Generate a gradient fill variant, blended from the TOP hex color at the top of the
+    /// tallest glyph to BOTTOM at the lowest descender. Written to OUTPUT_PNG with `_gradient.png` suffix.
+    #[arg(long, value_name = "TOP,BOTTOM")]
+    pub gradient: Option<String>,
+
+    /// Compute kerning optically from the glyph bitmap masks and emit it in metadata.
+    ///
+    /// Useful for fonts that do not contain OpenType kerning tables (e.g. missing GPOS/kern).
+    #[arg(long, default_value = "off", value_enum)]
+    pub optical_kerning: OpticalKerningMode,
+
+    /// Target minimum pixel gap between adjacent glyph ink when computing optical kerning.
+    #[arg(long, default_value = "1", value_name = "PX")]
+    pub optical_kerning_gap: u32,
+
+    /// Disable anti-aliasing by converting rasterized glyph alpha to hard 0/255.
+    #[arg(long, default_value_t = false)]
+    pub no_antialias: bool,
+
+    /// Fit glyph outlines to the pixel grid before rasterizing, so small sizes come out crisp.
+    #[arg(long, default_value = "none", value_enum)]
+    pub hinting: Hinting,
+
+    /// Antialias each pixel's red, green and blue stripes separately (RGB LCD rendering),
+    /// storing per-channel coverage in the glyph's color.
+    #[arg(long, default_value_t = false)]
+    pub subpixel: bool,
+
+    /// Treat the font as a pixel font: rasterize at its native em size (one design
+    /// grid unit per pixel, unless --px is given), threshold alpha to hard edges,
+    /// and snap advances and kerning to whole pixels.
+    #[arg(long, default_value_t = false)]
+    pub pixel_mode: bool,
+
+    /// With `vertical`, also emit each glyph's vertical advance and bearings (from
+    /// the font's vmtx and VORG tables) and the column metrics from vhea.
+    #[arg(long, default_value = "horizontal", value_enum)]
+    pub orientation: Orientation,
+
+    /// Render signed distance fields from the glyph outlines instead of plain alpha,
+    /// so glyphs stay crisp at any scale in shader-based UI.
+    #[arg(long, value_enum)]
+    pub sdf: Option<SdfMode>,
+
+    /// Distance in pixels the field spans, from fully outside to fully inside a glyph.
+    #[arg(long, default_value = "4", value_name = "PX")]
+    pub sdf_range: f32,
+
+    /// Reuse generated atlases from this content-addressed cache directory
+    #[arg(long, value_name = "DIR")]
+    #[serde(skip)]
+    pub cache_dir: Option<PathBuf>,
+
+    /// Asset ids of already uploaded atlas images by path (set by sync), recorded
+    /// in the metadata as `image`
+    #[arg(skip)]
+    pub image_ids: BTreeMap<PathBuf, String>,
+}
+
+pub fn run(args: FontArgs) -> bool {
+    match run_impl(args) {
+        Ok(()) => true,
+        Err(e) => {
+            eprintln!("[font] ERROR: {e}");
+            false
+        }
+    }
+}
+
+fn run_impl(args: FontArgs) -> anyhow::Result<()> {
+    let plan = Plan::new(&args)?;
+    let font_paths: Vec<&PathBuf> = std::iter::once(&args.input_ttf)
+        .chain(&args.fallback)
+        .collect();
+    let font_bytes = read_fonts(&font_paths)?;
+
+    let paths = FontPaths::new(&args, &plan.sizes, &plan.effects);
+    let cache = FontCache::new(&args, &plan, &font_bytes, &paths)?;
+    if let Some(cache) = &cache {
+        if cache.restore() {
+            println!(
+                "[font] ✅ Restored {} from cache",
+                args.output_png.display()
+            );
+            return Ok(());
+        }
+    }
+
+    // fontdue and the kerning table reader share the buffers read above.
+    let fonts = Fonts::parse(&font_bytes, &font_paths)?;
+    let glyphs = GlyphSet::select(&args, &plan, &fonts)?;
+    let (atlases, sizes) = render::draw_atlases(&args, &plan, &fonts, &glyphs, &paths)?;
+    let metas = metadata::atlas_metas(
+        &args,
+        &plan,
+        &paths,
+        &font_bytes,
+        &glyphs,
+        sizes,
+        cache.as_ref(),
+    )?;
+
+    atlases.save(&paths, &plan.effects)?;
+    metadata::write_metadata(&args, &paths, &plan, &metas)?;
+    print_atlases(&args, &paths, &plan, glyphs.chars.len());
+    if let Some(cache) = &cache {
+        cache.store()?;
+    }
+    Ok(())
+}
+
+fn read_fonts(font_paths: &[&PathBuf]) -> anyhow::Result<Vec<Vec<u8>>> {
+    font_paths
+        .iter()
+        .map(|path| {
+            fs::read(path)
+                .map_err(|e| anyhow::anyhow!("failed to read input font {}: {e}", path.display()))
+        })
+        .collect()
+}
+
+/// The atlas layout and effects a run's options resolve to.
+struct Plan {
+    atlas_w: u32,
+    atlas_h: u32,
+    /// `--sizes`, sorted and without repeats.
+    sizes: Vec<u32>,
+    language: Option<Tag>,
+    effects: Vec<Effect>,
+    pack: FontPacking,
+    /// Cells per row and per column of a grid atlas.
+    cols: u32,
+    rows: u32,
+    /// The part of a cell inside its padding.
+    inner: u32,
+}
+
+impl Plan {
+    fn new(args: &FontArgs) -> anyhow::Result<Self> {
+        let (atlas_w, atlas_h) = parse_size(&args.size)?;
+
+        let mut sizes = args.sizes.clone();
+        sizes.sort_unstable();
+        sizes.dedup();
+        if sizes.contains(&0) {
+            anyhow::bail!("--sizes must be > 0");
+        }
+        let sized = !sizes.is_empty();
+        let language = args
+            .language
+            .as_deref()
+            .map(parse_language_tag)
+            .transpose()?;
+        let effects = parse_effects(args)?;
+        check_options(args, sized, &effects)?;
+
+        if atlas_w == 0 || atlas_h == 0 {
+            anyhow::bail!("--size must be > 0x0");
+        }
+        let plan = Self {
+            atlas_w,
+            atlas_h,
+            sizes,
+            language,
+            effects,
+            // Glyphs of different sizes don't share a cell grid.
+            pack: if sized { FontPacking::Tight } else { args.pack },
+            cols: atlas_w / args.cell,
+            rows: atlas_h / args.cell,
+            inner: args
+                .cell
+                .checked_sub(args.padding.saturating_mul(2))
+                .ok_or_else(|| anyhow::anyhow!("--cell must be > 2*--padding"))?,
+        };
+        plan.check_capacity(args)?;
+        Ok(plan)
+    }
+
+    fn sized(&self) -> bool {
+        !self.sizes.is_empty()
+    }
+
+    fn grid(&self) -> bool {
+        self.pack == FontPacking::Grid
+    }
+
+    fn capacity(&self) -> usize {
+        (self.cols as usize) * (self.rows as usize)
+    }
+
+    /// Fails when the grid doesn't divide the atlas or the charset doesn't
+    /// fit in its cells.
+    fn check_capacity(&self, args: &FontArgs) -> anyhow::Result<()> {
+        if self.grid()
+            && (!self.atlas_w.is_multiple_of(args.cell) || !self.atlas_h.is_multiple_of(args.cell))
+        {
+            anyhow::bail!(
+                "--size must be divisible by --cell (got size {}x{}, cell {})",
+                self.atlas_w,
+                self.atlas_h,
+                args.cell
+            );
+        }
+        let charset_len = args.charset.chars().count();
+        if charset_len == 0 {
+            anyhow::bail!("--charset must not be empty");
+        }
+        if self.grid() && charset_len > self.capacity() {
+            anyhow::bail!(
+                "charset has {charset_len} glyph(s) but atlas capacity is {} cell(s) ({}x{} cells)",
+                self.capacity(),
+                self.cols,
+                self.rows
+            );
+        }
+        Ok(())
+    }
+}
+
+/// The effect variants `--outline`, `--shadow` and `--gradient` ask for.
+fn parse_effects(args: &FontArgs) -> anyhow::Result<Vec<Effect>> {
+    let mut effects = Vec::new();
+    if args.outline > 0 {
+        effects.push(Effect::Outline(args.outline));
+    }
+    if let Some(shadow) = &args.shadow {
+        effects.push(parse_shadow(shadow)?);
+    }
+    if let Some(gradient) = &args.gradient {
+        effects.push(parse_gradient(gradient)?);
+    }
+    Ok(effects)
+}
+
+/// Fails on options that can't be combined or are out of range.
+fn check_options(args: &FontArgs, sized: bool, effects: &[Effect]) -> anyhow::Result<()> {
+    check_combinations(args, sized, effects)?;
+    check_padding(args, effects)?;
+    check_distance_field(args, effects)
+}
+
+/// Fails on options that can't be combined with each other.
+fn check_combinations(args: &FontArgs, sized: bool, effects: &[Effect]) -> anyhow::Result<()> {
+    if let (true, Some(effect)) = (sized, effects.first()) {
+        anyhow::bail!("--{} cannot be combined with --sizes", effect.name());
+    }
+    if args.split_sizes && !sized {
+        anyhow::bail!("--split-sizes needs --sizes");
+    }
+    if args.pixel_mode {
+        if sized {
+            anyhow::bail!("--pixel-mode cannot be combined with --sizes");
+        }
+        if args.sdf.is_some() {
+            anyhow::bail!("--pixel-mode cannot be combined with --sdf");
+        }
+        if args.hinting != Hinting::None || args.subpixel {
+            anyhow::bail!("--pixel-mode cannot be combined with --hinting or --subpixel");
+        }
+    }
+    if args.subpixel {
+        if args.no_antialias {
+            anyhow::bail!("--subpixel cannot be combined with --no-antialias");
+        }
+        if args.gradient.is_some() {
+            anyhow::bail!("--subpixel cannot be combined with --gradient");
+        }
+    }
+    if args.orientation == Orientation::Vertical && args.format == FontFormat::Bmfont {
+        anyhow::bail!("--orientation vertical cannot be combined with --format bmfont");
+    }
+    if let Some(px) = args.px {
+        if sized {
+            anyhow::bail!("--px cannot be combined with --sizes");
+        }
+        if !px.is_finite() || px <= 0.0 {
+            anyhow::bail!("--px must be > 0");
+        }
+    }
+    Ok(())
+}
+
+/// Fails when a cell has no room inside its padding, or the padding has no
+/// room for what the effects draw around each glyph.
+fn check_padding(args: &FontArgs, effects: &[Effect]) -> anyhow::Result<()> {
+    if args.cell == 0 {
+        anyhow::bail!("--cell must be > 0");
+    }
+    if args.cell <= args.padding.saturating_mul(2) {
+        anyhow::bail!("--cell must be > 2*--padding");
+    }
+    if args.outline > 0 && args.padding < args.outline {
+        anyhow::bail!(
+            "--padding must be >= --outline when outline is enabled (got padding {}, outline {})",
+            args.padding,
+            args.outline
+        );
+    }
+    for effect in effects {
+        let (left, top, right, bottom) = effect.margins();
+        let margin = left.max(top).max(right).max(bottom);
+        if matches!(effect, Effect::Shadow { .. }) && args.padding < margin {
+            anyhow::bail!(
+                "--padding must be >= the shadow's offset plus blur (got padding {}, needs {margin})",
+                args.padding
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Fails on options that don't apply to distance fields with `--sdf`.
+fn check_distance_field(args: &FontArgs, effects: &[Effect]) -> anyhow::Result<()> {
+    if args.sdf.is_none() {
+        return Ok(());
+    }
+    if let Some(effect) = effects.first() {
+        anyhow::bail!("--{} cannot be combined with --sdf", effect.name());
+    }
+    if args.no_antialias {
+        anyhow::bail!("--no-antialias cannot be combined with --sdf");
+    }
+    if args.hinting != Hinting::None {
+        anyhow::bail!("--hinting cannot be combined with --sdf");
+    }
+    if args.subpixel {
+        anyhow::bail!("--subpixel cannot be combined with --sdf");
+    }
+    if !args.sdf_range.is_finite() || args.sdf_range <= 0.0 {
+        anyhow::bail!("--sdf-range must be > 0");
+    }
+    Ok(())
+}
+
+/// Where a font atlas and its metadata are written.
+struct FontPaths {
+    /// OUTPUT_PNG, or one atlas per size with `--split-sizes`.
+    atlases: Vec<PathBuf>,
+    /// The atlas of each effect variant.
+    variant_pngs: Vec<PathBuf>,
+    luau: PathBuf,
+    dts: PathBuf,
+    /// BMFont descriptors hold one size each, so `--sizes` writes one per size.
+    fnts: Vec<PathBuf>,
+    variant_fnts: Vec<PathBuf>,
+    json: PathBuf,
+}
+
+impl FontPaths {
+    fn new(args: &FontArgs, sizes: &[u32], effects: &[Effect]) -> Self {
+        let per_size = |extension: &str| -> Vec<PathBuf> {
+            sizes
+                .iter()
+                .map(|px| derive_sibling_path(&args.output_png, &px.to_string(), extension))
+                .collect()
+        };
+        Self {
+            atlases: if args.split_sizes {
+                per_size("png")
+            } else {
+                vec![args.output_png.clone()]
+            },
+            variant_pngs: effects
+                .iter()
+                .map(|effect| match (effect, &args.outline_png) {
+                    (Effect::Outline(_), Some(path)) => path.clone(),
+                    _ => derive_sibling_path(&args.output_png, effect.name(), "png"),
+                })
+                .collect(),
+            luau: args
+                .luau
+                .clone()
+                .unwrap_or_else(|| args.output_png.with_extension("luau")),
+            dts: args
+                .dts
+                .clone()
+                .unwrap_or_else(|| args.output_png.with_extension("d.ts")),
+            fnts: if sizes.is_empty() {
+                vec![args.output_png.with_extension("fnt")]
+            } else {
+                per_size("fnt")
+            },
+            variant_fnts: effects
+                .iter()
+                .map(|effect| derive_sibling_path(&args.output_png, effect.name(), "fnt"))
+                .collect(),
+            json: args.output_png.with_extension("json"),
+        }
+    }
+}
+
+/// Reports the atlases written for `args`, with `glyph_count` glyphs per size.
+fn print_atlases(args: &FontArgs, paths: &FontPaths, plan: &Plan, glyph_count: usize) {
+    let (atlas_w, atlas_h, sizes) = (plan.atlas_w, plan.atlas_h, &plan.sizes[..]);
+    for (i, path) in paths.atlases.iter().enumerate() {
+        if plan.sized() {
+            let atlas_sizes = if args.split_sizes {
+                &sizes[i..=i]
+            } else {
+                sizes
+            };
+            println!(
+                "[font] ✅ Wrote {} ({}x{}, sizes {:?}, padding {}, glyphs {})",
+                path.display(),
+                atlas_w,
+                atlas_h,
+                atlas_sizes,
+                args.padding,
+                glyph_count
+            );
+        } else {
+            println!(
+                "[font] ✅ Wrote {} ({}x{}, cell {}, padding {}, glyphs {})",
+                path.display(),
+                atlas_w,
+                atlas_h,
+                args.cell,
+                args.padding,
+                glyph_count
+            );
+        }
+    }
+    for (effect, path) in plan.effects.iter().zip(&paths.variant_pngs) {
+        println!(
+            "[font] ✅ Wrote {} {} ({})",
+            effect.name(),
+            path.display(),
+            effect.describe()
+        );
+    }
+}
+
+/// `<stem of base>_<suffix>.<extension>`, next to `base`.
+fn derive_sibling_path(base: &Path, suffix: &str, extension: &str) -> PathBuf {
+    let stem = base
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("font_atlas");
+    base.with_file_name(format!("{stem}_{suffix}.{extension}"))
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+/// Blends `a` into `b`; `t` is clamped to 0..=1.
+fn lerp_rgb(a: [u8; 3], b: [u8; 3], t: f32) -> [u8; 3] {
+    let t = t.clamp(0.0, 1.0);
+    [0, 1, 2].map(|i| (a[i] as f32 + (b[i] as f32 - a[i] as f32) * t).round() as u8)
+}
+
+fn hex_color([r, g, b]: [u8; 3]) -> String {
+    format!("#{r:02x}{g:02x}{b:02x}")
+}
+
+fn parse_hex_color(s: &str) -> Option<[u8; 3]> {
+    let hex = s.strip_prefix('#').unwrap_or(s);
+    if hex.len() != 6 || !hex.is_ascii() {
+        return None;
+    }
+    let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+    Some([channel(0)?, channel(2)?, channel(4)?])
+}
+
+fn parse_shadow(s: &str) -> anyhow::Result<Effect> {
+    let parts: Vec<&str> = s.split(',').map(str::trim).collect();
+    let [dx_s, dy_s, blur_s] = parts[..] else {
+        anyhow::bail!("invalid --shadow (expected DX,DY,BLUR): {s}");
+    };
+    let dx: i32 = dx_s
+        .parse()
+        .map_err(|_| anyhow::anyhow!("invalid --shadow x offset: {dx_s}"))?;
+    let dy: i32 = dy_s
+        .parse()
+        .map_err(|_| anyhow::anyhow!("invalid --shadow y offset: {dy_s}"))?;
+    let blur: u32 = blur_s
+        .parse()
+        .map_err(|_| anyhow::anyhow!("invalid --shadow blur: {blur_s}"))?;
+    Ok(Effect::Shadow { dx, dy, blur })
+}
+
+fn parse_gradient(s: &str) -> anyhow::Result<Effect> {
+    let (top_s, bottom_s) = s
+        .split_once(',')
+        .ok_or_else(|| anyhow::anyhow!("invalid --gradient (expected TOP,BOTTOM): {s}"))?;
+    let color = |c: &str| {
+        parse_hex_color(c.trim())
+            .ok_or_else(|| anyhow::anyhow!("invalid --gradient color (expected #rrggbb): {c}"))
+    };
+    Ok(Effect::Gradient {
+        top: color(top_s)?,
+        bottom: color(bottom_s)?,
+    })
+}
+
+/// An OpenType language system tag, padded with spaces to four characters
+/// (`TRK` becomes `TRK `).
+fn parse_language_tag(s: &str) -> anyhow::Result<Tag> {
+    if s.is_empty() || s.len() > 4 || !s.bytes().all(|b| b.is_ascii_alphanumeric()) {
+        anyhow::bail!("invalid --language (expected an OpenType language tag like TRK): {s}");
+    }
+    let mut tag = *b"    ";
+    tag[..s.len()].copy_from_slice(s.as_bytes());
+    Ok(Tag::from_bytes(&tag))
+}
+
+fn parse_size(s: &str) -> anyhow::Result<(u32, u32)> {
+    let (w_s, h_s) = s
+        .split_once('x')
+        .ok_or_else(|| anyhow::anyhow!("invalid --size (expected WxH): {s}"))?;
+    let w: u32 = w_s
+        .parse()
+        .map_err(|_| anyhow::anyhow!("invalid --size width: {w_s}"))?;
+    let h: u32 = h_s
+        .parse()
+        .map_err(|_| anyhow::anyhow!("invalid --size height: {h_s}"))?;
+    Ok((w, h))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_size_ok() {
+        assert_eq!(parse_size("1024x1024").unwrap(), (1024, 1024));
+        assert_eq!(parse_size("1x2").unwrap(), (1, 2));
+    }
+
+    #[test]
+    fn parse_size_err() {
+        assert!(parse_size("1024").is_err());
+        assert!(parse_size("axb").is_err());
+        assert!(parse_size("10x").is_err());
+    }
+
+    #[test]
+    fn capacity_math() {
+        let atlas_w = 64u32;
+        let atlas_h = 32u32;
+        let cell = 16u32;
+        let cols = atlas_w / cell;
+        let rows = atlas_h / cell;
+        let capacity = (cols as usize) * (rows as usize);
+        assert_eq!(cols, 4);
+        assert_eq!(rows, 2);
+        assert_eq!(capacity, 8);
+    }
+
+    #[test]
+    fn parses_shadow_and_gradient_effects() {
+        let shadow = parse_shadow("-2, 3,4").unwrap();
+        assert_eq!(
+            shadow,
+            Effect::Shadow {
+                dx: -2,
+                dy: 3,
+                blur: 4
+            }
+        );
+        // The blur spreads to every side; the offset moves it right and down.
+        assert_eq!(shadow.margins(), (6, 1, 2, 7));
+        assert!(parse_shadow("2,2").is_err());
+
+        let gradient = parse_gradient("#ffe066,FF8C00").unwrap();
+        assert_eq!(
+            gradient,
+            Effect::Gradient {
+                top: [0xff, 0xe0, 0x66],
+                bottom: [0xff, 0x8c, 0x00]
+            }
+        );
+        assert_eq!(gradient.describe(), "#ffe066 to #ff8c00");
+        assert!(parse_gradient("#ffe066").is_err());
+        assert!(parse_gradient("#ffe066,#ff8c0").is_err());
+        assert_eq!(lerp_rgb([0, 100, 200], [200, 100, 0], 0.5), [100, 100, 100]);
+    }
+
+    #[test]
+    fn derive_sibling_path_adds_a_suffix() {
+        assert_eq!(
+            derive_sibling_path(Path::new("ui/font.png"), "24", "png"),
+            PathBuf::from("ui/font_24.png")
+        );
+    }
+
+    #[test]
+    fn parse_language_tag_pads_with_spaces() {
+        assert_eq!(parse_language_tag("TRK").unwrap(), Tag::from_bytes(b"TRK "));
+        assert!(parse_language_tag("TOOLONG").is_err());
+    }
+}
@@ -0,0 +1,1152 @@
+use super::glyphs::{Fonts, GlyphSet};
+use super::kerning::{ink_profile_from_alpha, InkProfile};
+use super::metadata::{vertical_glyph_metrics, GlyphMeta};
+use super::{
+    lerp_rgb, Effect, FontArgs, FontPaths, Hinting, OpticalKerningMode, Orientation, Plan, SdfMode,
+};
+use crate::assets::atlas::AtlasRect;
+use crate::assets::{pack_rects, PackingAlgorithm};
+use crate::image::color_glyph;
+use crate::image::composite::blend_over;
+use crate::image::hinting;
+use crate::image::sdf::Shape;
+use std::collections::HashMap;
+use ttf_parser::{GlyphId, OutlineBuilder};
+
+/// The atlas images of a run.
+pub(crate) struct Atlases {
+    /// OUTPUT_PNG, or one image per size with `--split-sizes`.
+    images: Vec<image::RgbaImage>,
+    /// The image of each effect variant.
+    variants: Vec<image::RgbaImage>,
+}
+
+impl Atlases {
+    pub(crate) fn save(&self, paths: &FontPaths, effects: &[Effect]) -> anyhow::Result<()> {
+        for (atlas, path) in self.images.iter().zip(&paths.atlases) {
+            atlas
+                .save(path)
+                .map_err(|e| anyhow::anyhow!("failed to write {}: {e}", path.display()))?;
+        }
+
+        for ((effect, atlas), path) in effects.iter().zip(&self.variants).zip(&paths.variant_pngs) {
+            atlas.save(path).map_err(|e| {
+                anyhow::anyhow!(
+                    "failed to write {} atlas {}: {e}",
+                    effect.name(),
+                    path.display()
+                )
+            })?;
+        }
+        Ok(())
+    }
+}
+
+/// The glyphs drawn at one pixel size.
+pub(crate) struct DrawnSize {
+    pub(crate) px: f32,
+    /// Index into `FontPaths::atlases` of the atlas they're drawn in.
+    pub(crate) atlas: usize,
+    pub(crate) baseline: u32,
+    pub(crate) glyphs: Vec<GlyphMeta>,
+    /// The glyphs as drawn in each effect variant.
+    pub(crate) variant_glyphs: Vec<Vec<GlyphMeta>>,
+    /// Per-glyph ink profiles used for optical kerning computation.
+    pub(crate) ink_profiles: HashMap<char, InkProfile>,
+}
+
+/// Rasterizes `glyphs` at each pixel size and draws them into the atlases
+/// and their effect variants.
+pub(crate) fn draw_atlases(
+    args: &FontArgs,
+    plan: &Plan,
+    fonts: &Fonts,
+    glyphs: &GlyphSet,
+    paths: &FontPaths,
+) -> anyhow::Result<(Atlases, Vec<DrawnSize>)> {
+    let blank =
+        || image::RgbaImage::from_pixel(plan.atlas_w, plan.atlas_h, image::Rgba([0, 0, 0, 0]));
+    let mut atlases = Atlases {
+        images: paths.atlases.iter().map(|_| blank()).collect(),
+        variants: plan.effects.iter().map(|_| blank()).collect(),
+    };
+
+    let rasterized = rasterize_sizes(args, fonts, glyphs, pixel_sizes(args, plan, fonts, glyphs)?);
+    if (args.px.is_some() || args.pixel_mode) && plan.grid() {
+        check_glyphs_fit(&rasterized[0].glyphs, plan.inner, args.padding)?;
+    }
+    let packed = pack_sizes(args, plan, &rasterized)?;
+
+    let drawer = Drawer {
+        args,
+        plan,
+        faces: &fonts.faces,
+        glyphs,
+        packed: packed.as_ref(),
+    };
+    let sizes = rasterized
+        .into_iter()
+        .enumerate()
+        .map(|(size, rasterized)| drawer.draw_size(size, rasterized, &mut atlases))
+        .collect();
+    Ok((atlases, sizes))
+}
+
+/// The pixel sizes to rasterize at: `--sizes`, `--px`, the font's native size
+/// with `--pixel-mode`, or else the single size that makes all glyph bitmaps
+/// fit within the inner box.
+fn pixel_sizes(
+    args: &FontArgs,
+    plan: &Plan,
+    fonts: &Fonts,
+    glyphs: &GlyphSet,
+) -> anyhow::Result<Vec<f32>> {
+    if plan.sized() {
+        return Ok(plan.sizes.iter().map(|px| *px as f32).collect());
+    }
+    if let Some(px) = args.px {
+        return Ok(vec![px]);
+    }
+    if args.pixel_mode {
+        let primary: Vec<u16> = glyphs
+            .ids
+            .iter()
+            .zip(&glyphs.sources)
+            .filter(|(glyph, source)| **glyph != 0 && **source == 0)
+            .map(|(glyph, _)| *glyph)
+            .collect();
+        let Some(px) = native_pixel_size(&fonts.faces[0], &primary) else {
+            anyhow::bail!(
+                "--pixel-mode found no outlines to measure the font's pixel grid from; pass --px"
+            );
+        };
+        println!("[font] Pixel mode: native size {px}px");
+        return Ok(vec![px]);
+    }
+    let fitted: Vec<(u16, &fontdue::Font)> = glyphs
+        .ids
+        .iter()
+        .zip(&glyphs.sources)
+        .map(|(glyph, source)| (*glyph, &fonts.fonts[*source]))
+        .collect();
+    Ok(vec![fit_pixel_size(
+        &fitted,
+        plan.inner.max(1) as f32,
+        plan.inner,
+    )?])
+}
+
+/// The glyphs of the atlas rasterized at one pixel size, in atlas order.
+struct RasterizedSize {
+    px: f32,
+    glyphs: Vec<(char, fontdue::Metrics, Vec<u8>)>,
+    /// Color and subpixel glyphs keep their RGBA bitmap next to the alpha the
+    /// other effects use, flagged whether it came from the font's color tables.
+    colors: HashMap<char, (Vec<u8>, bool)>,
+}
+
+fn rasterize_sizes(
+    args: &FontArgs,
+    fonts: &Fonts,
+    glyphs: &GlyphSet,
+    pixel_sizes: Vec<f32>,
+) -> Vec<RasterizedSize> {
+    let rendering = hinting::Rendering {
+        snap_heights: args.hinting != Hinting::None,
+        snap_stems: args.hinting == Hinting::Full,
+        subpixel: args.subpixel,
+    };
+    let hinted = (args.hinting != Hinting::None || args.subpixel).then_some(rendering);
+    pixel_sizes
+        .into_iter()
+        .map(|px| {
+            let mut colors = HashMap::new();
+            let rasterized = glyphs
+                .chars
+                .iter()
+                .zip(&glyphs.sources)
+                .zip(&glyphs.ids)
+                .map(|((&ch, source), &glyph)| {
+                    let glyph = rasterize_glyph(
+                        &fonts.fonts[*source],
+                        &fonts.faces[*source],
+                        glyph,
+                        px,
+                        hinted,
+                        args.pixel_mode,
+                    );
+                    if let Some(color) = glyph.color {
+                        colors.insert(ch, color);
+                    }
+                    (ch, glyph.metrics, glyph.alpha)
+                })
+                .collect();
+            RasterizedSize {
+                px,
+                glyphs: rasterized,
+                colors,
+            }
+        })
+        .collect()
+}
+
+fn fit_pixel_size(
+    glyphs: &[(u16, &fontdue::Font)],
+    initial_px: f32,
+    inner: u32,
+) -> anyhow::Result<f32> {
+    let mut px = initial_px.max(1.0);
+
+    // Iterate a few times to converge if needed. Only metrics are needed here,
+    // so glyphs are rasterized once, after the size is chosen.
+    for _ in 0..10 {
+        let mut max_w = 0u32;
+        let mut max_h = 0u32;
+        let mut min_ymin = i32::MAX;
+        let mut max_ymax = i32::MIN;
+
+        for (glyph, font) in glyphs {
+            let m = font.metrics_indexed(*glyph, px);
+            max_w = max_w.max(m.width as u32);
+            max_h = max_h.max(m.height as u32);
+
+            if m.width > 0 && m.height > 0 {
+                min_ymin = min_ymin.min(m.ymin);
+                max_ymax = max_ymax.max(m.ymin + m.height as i32);
+            }
+        }
+
+        let height_span = if min_ymin == i32::MAX || max_ymax == i32::MIN {
+            0
+        } else {
+            (max_ymax - min_ymin) as u32
+        };
+        let max_dim = max_w.max(max_h).max(height_span);
+        if max_dim == 0 {
+            // Entire charset rasterizes to nothing; keep something valid.
+            return Ok(px.max(1.0));
+        }
+
+        let scale = (inner as f32) / (max_dim as f32);
+        if (scale - 1.0).abs() < 0.001 {
+            return Ok(px.max(1.0));
+        }
+        let next_px = if scale > 1.0 {
+            (px * scale).ceil()
+        } else {
+            (px * scale).floor()
+        }
+        .max(1.0);
+        if (next_px - px).abs() < f32::EPSILON {
+            return Ok(px.max(1.0));
+        }
+        px = next_px;
+    }
+
+    Ok(px.max(1.0))
+}
+
+/// Outline point coordinates in font units.
+#[derive(Default)]
+struct Coordinates(Vec<f32>);
+
+impl OutlineBuilder for Coordinates {
+    fn move_to(&mut self, x: f32, y: f32) {
+        self.0.extend([x, y]);
+    }
+
+    fn line_to(&mut self, x: f32, y: f32) {
+        self.0.extend([x, y]);
+    }
+
+    fn quad_to(&mut self, x1: f32, y1: f32, x: f32, y: f32) {
+        self.0.extend([x1, y1, x, y]);
+    }
+
+    fn curve_to(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, x: f32, y: f32) {
+        self.0.extend([x1, y1, x2, y2, x, y]);
+    }
+
+    fn close(&mut self) {}
+}
+
+/// The pixel size at which one unit of a pixel font's design grid is one pixel:
+/// units per em over the largest grid every outline point and advance of
+/// `glyphs` sits on. `None` if they have no outlines.
+fn native_pixel_size(face: &ttf_parser::Face, glyphs: &[u16]) -> Option<f32> {
+    let mut coordinates = Coordinates::default();
+    for &glyph in glyphs {
+        face.outline_glyph(GlyphId(glyph), &mut coordinates);
+    }
+    if coordinates.0.is_empty() {
+        return None;
+    }
+    let advances = glyphs
+        .iter()
+        .filter_map(|glyph| face.glyph_hor_advance(GlyphId(*glyph)));
+    let grid = coordinates
+        .0
+        .iter()
+        .map(|v| v.round().abs() as u32)
+        .chain(advances.map(u32::from))
+        .fold(0, gcd);
+    Some(face.units_per_em() as f32 / grid.max(1) as f32)
+}
+
+fn gcd(a: u32, b: u32) -> u32 {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+/// A glyph rasterized at one pixel size.
+struct RasterizedGlyph {
+    metrics: fontdue::Metrics,
+    alpha: Vec<u8>,
+    /// The RGBA bitmap of color and subpixel glyphs, flagged whether it came
+    /// from the font's color tables.
+    color: Option<(Vec<u8>, bool)>,
+}
+
+/// Rasterizes `glyph` at `px` from the font's color tables when it has them,
+/// else through the hinter with `hinted` rendering, else with fontdue.
+fn rasterize_glyph(
+    font: &fontdue::Font,
+    face: &ttf_parser::Face,
+    glyph: u16,
+    px: f32,
+    hinted: Option<hinting::Rendering>,
+    pixel_mode: bool,
+) -> RasterizedGlyph {
+    let color = (glyph != 0)
+        .then_some(GlyphId(glyph))
+        .and_then(|glyph| color_glyph::rasterize(face, font, glyph, px));
+    if let Some(color) = color {
+        return RasterizedGlyph {
+            metrics: fontdue::Metrics {
+                xmin: color.xmin,
+                ymin: color.ymin,
+                width: color.width as usize,
+                height: color.height as usize,
+                ..font.metrics_indexed(glyph, px)
+            },
+            alpha: color.alpha(),
+            color: Some((color.rgba, true)),
+        };
+    }
+    if let (Some(rendering), true) = (hinted, glyph != 0) {
+        if let Some(hinted) = hinting::rasterize(face, GlyphId(glyph), px, rendering) {
+            let mut metrics = font.metrics_indexed(glyph, px);
+            if rendering.snap_stems {
+                metrics.advance_width = metrics.advance_width.round();
+            }
+            return RasterizedGlyph {
+                metrics: fontdue::Metrics {
+                    xmin: hinted.xmin,
+                    ymin: hinted.ymin,
+                    width: hinted.width as usize,
+                    height: hinted.height as usize,
+                    ..metrics
+                },
+                alpha: hinted.alpha,
+                color: hinted.rgba.map(|rgba| (rgba, false)),
+            };
+        }
+    }
+    let (mut metrics, alpha) = font.rasterize_indexed(glyph, px);
+    if pixel_mode {
+        metrics.advance_width = metrics.advance_width.round();
+    }
+    RasterizedGlyph {
+        metrics,
+        alpha,
+        color: None,
+    }
+}
+
+/// Overflowing glyphs named in a `--px` error before the rest are counted.
+const MAX_LISTED_GLYPHS: usize = 10;
+
+/// Fails with the glyphs that overflow their cell's `inner` box when placed
+/// on a shared baseline, and the `--cell` that would fit them all.
+fn check_glyphs_fit(
+    rasterized: &[(char, fontdue::Metrics, Vec<u8>)],
+    inner: u32,
+    padding: u32,
+) -> anyhow::Result<()> {
+    let inked = || {
+        rasterized
+            .iter()
+            .filter(|(_, metrics, _)| metrics.width > 0 && metrics.height > 0)
+    };
+    let Some(min_ymin) = inked().map(|(_, metrics, _)| metrics.ymin).min() else {
+        return Ok(());
+    };
+
+    let mut needed = 0;
+    let mut overflowing = Vec::new();
+    for (ch, metrics, _) in inked() {
+        // Height from the lowest descender in the charset to the top of this glyph.
+        let top = (metrics.ymin + metrics.height as i32 - min_ymin) as u32;
+        let width = metrics.width as u32;
+        needed = needed.max(width).max(top);
+        if width > inner || top > inner {
+            overflowing.push(format!("{ch:?} ({width}x{top})"));
+        }
+    }
+    if !overflowing.is_empty() {
+        let count = overflowing.len();
+        if count > MAX_LISTED_GLYPHS {
+            overflowing.truncate(MAX_LISTED_GLYPHS);
+            overflowing.push(format!("and {} more", count - MAX_LISTED_GLYPHS));
+        }
+        anyhow::bail!(
+            "{count} glyph(s) overflow the {inner}x{inner} cell interior: {}; use --cell {} or a smaller --px",
+            overflowing.join(", "),
+            needed + 2 * padding
+        );
+    }
+    Ok(())
+}
+
+/// The rects of a tight atlas's glyphs, keyed like `pack_glyphs`; `None` for
+/// the grid.
+fn pack_sizes(
+    args: &FontArgs,
+    plan: &Plan,
+    sizes: &[RasterizedSize],
+) -> anyhow::Result<Option<HashMap<(usize, char), AtlasRect>>> {
+    if plan.grid() {
+        return Ok(None);
+    }
+    let glyph_sizes = |size: usize| {
+        sizes[size]
+            .glyphs
+            .iter()
+            .map(move |(ch, metrics, _)| (size, *ch, metrics.width as u32, metrics.height as u32))
+    };
+    let page = (plan.atlas_w, plan.atlas_h);
+    let mut packed = HashMap::new();
+    if args.split_sizes {
+        for size in 0..sizes.len() {
+            let glyphs: Vec<_> = glyph_sizes(size).collect();
+            packed.extend(pack_glyphs(&glyphs, args.padding, page)?);
+        }
+    } else {
+        let glyphs: Vec<_> = (0..sizes.len()).flat_map(glyph_sizes).collect();
+        packed = pack_glyphs(&glyphs, args.padding, page)?;
+    }
+    Ok(Some(packed))
+}
+
+/// Packs the inked glyphs of a tight atlas onto a single `page`. Glyphs are
+/// keyed by their index in `--sizes` and character; repeated characters
+/// share one rect.
+fn pack_glyphs(
+    glyphs: &[(usize, char, u32, u32)],
+    padding: u32,
+    page: (u32, u32),
+) -> anyhow::Result<HashMap<(usize, char), AtlasRect>> {
+    let mut seen = std::collections::HashSet::new();
+    let glyphs: Vec<&(usize, char, u32, u32)> = glyphs
+        .iter()
+        .filter(|(size, ch, w, h)| *w > 0 && *h > 0 && seen.insert((*size, *ch)))
+        .collect();
+    let sizes: Vec<(String, u32, u32)> = glyphs
+        .iter()
+        .map(|(_, ch, w, h)| (format!("glyph {ch:?}"), *w, *h))
+        .collect();
+    let rects = pack_rects(&sizes, padding, page, PackingAlgorithm::MaxRects)?;
+    if rects.iter().any(|(page, _)| *page > 0) {
+        anyhow::bail!(
+            "glyphs don't fit in a {}x{} atlas; raise --size",
+            page.0,
+            page.1
+        );
+    }
+    Ok(glyphs
+        .iter()
+        .zip(rects)
+        .map(|((size, ch, _, _), (_, rect))| ((*size, *ch), rect))
+        .collect())
+}
+
+/// What drawing a glyph needs besides the glyph itself.
+struct Drawer<'a> {
+    args: &'a FontArgs,
+    plan: &'a Plan,
+    faces: &'a [ttf_parser::Face<'a>],
+    glyphs: &'a GlyphSet,
+    /// The rects of a tight atlas.
+    packed: Option<&'a HashMap<(usize, char), AtlasRect>>,
+}
+
+/// Where a glyph goes in its atlas: its cell, and the top-left corner of its
+/// bitmap. Unplaced glyphs (empty, or overflowing their cell) aren't drawn.
+struct Placement {
+    col: u32,
+    row: u32,
+    cell_x: u32,
+    cell_y: u32,
+    cell_w: u32,
+    cell_h: u32,
+    draw_x: u32,
+    draw_y: u32,
+    placed: bool,
+}
+
+impl Drawer<'_> {
+    /// Draws the glyphs of `size` into its atlas and the effect variants.
+    fn draw_size(
+        &self,
+        size: usize,
+        rasterized: RasterizedSize,
+        atlases: &mut Atlases,
+    ) -> DrawnSize {
+        let args = self.args;
+        let px = rasterized.px;
+        let atlas_index = if args.split_sizes { size } else { 0 };
+
+        let min_ymin = rasterized
+            .glyphs
+            .iter()
+            .filter(|(_, metrics, _)| metrics.width > 0 && metrics.height > 0)
+            .map(|(_, metrics, _)| metrics.ymin)
+            .min();
+        let baseline_in_inner = min_ymin.map_or(0, |ymin| -ymin);
+        // Gradients span from the top of the tallest glyph to the lowest descender.
+        let ink_top = rasterized
+            .glyphs
+            .iter()
+            .filter(|(_, metrics, _)| metrics.width > 0 && metrics.height > 0)
+            .map(|(_, metrics, _)| metrics.ymin + metrics.height as i32)
+            .max();
+        let ink_span = ink_top.zip(min_ymin);
+
+        let glyph_count = rasterized.glyphs.len();
+        let mut drawn = DrawnSize {
+            px,
+            atlas: atlas_index,
+            baseline: args.padding + baseline_in_inner.max(0) as u32,
+            glyphs: Vec::with_capacity(glyph_count),
+            variant_glyphs: self
+                .plan
+                .effects
+                .iter()
+                .map(|_| Vec::with_capacity(glyph_count))
+                .collect(),
+            ink_profiles: HashMap::new(),
+        };
+
+        for (i, (ch, metrics, mut bitmap)) in rasterized.glyphs.into_iter().enumerate() {
+            let color = rasterized.colors.get(&ch).map(|(rgba, _)| rgba);
+            let colored = rasterized
+                .colors
+                .get(&ch)
+                .is_some_and(|(_, colored)| *colored);
+            if args.no_antialias && color.is_none() {
+                binarize_alpha(&mut bitmap);
+            } else if args.pixel_mode && color.is_none() {
+                threshold_alpha(&mut bitmap);
+            }
+            let (gw, gh) = (metrics.width as u32, metrics.height as u32);
+            let place = self.place(size, i, ch, &metrics, baseline_in_inner);
+
+            if place.placed {
+                let glyph = DrawnGlyph {
+                    x: place.draw_x,
+                    y: place.draw_y,
+                    width: gw,
+                    height: gh,
+                    alpha: &bitmap,
+                    color: color.map(Vec::as_slice),
+                    xmin: metrics.xmin,
+                    ymin: metrics.ymin,
+                };
+                self.draw_glyph(&mut atlases.images[atlas_index], &place, i, px, &glyph);
+                for (effect, variant_atlas) in self.plan.effects.iter().zip(&mut atlases.variants) {
+                    let outline = draw_effect(effect, variant_atlas, &glyph, ink_span);
+                    if let (Some(profile), OpticalKerningMode::Outline) =
+                        (outline, args.optical_kerning)
+                    {
+                        drawn.ink_profiles.insert(ch, profile);
+                    }
+                }
+            }
+
+            if matches!(args.optical_kerning, OpticalKerningMode::Fill)
+                || (matches!(args.optical_kerning, OpticalKerningMode::Outline)
+                    && args.outline == 0)
+            {
+                drawn.ink_profiles.insert(
+                    ch,
+                    ink_profile_from_alpha(&bitmap, gw, gh, metrics.ymin, metrics.xmin, 0),
+                );
+            }
+
+            let glyph = self.glyph_meta(i, colored, &place, &metrics, px);
+            for (effect, variant_glyphs) in self.plan.effects.iter().zip(&mut drawn.variant_glyphs)
+            {
+                variant_glyphs.push(effect.glyph_meta(&glyph));
+            }
+            drawn.glyphs.push(glyph);
+        }
+        drawn
+    }
+
+    /// Places the `index`th glyph of `size`: in its packed rect, or centered
+    /// horizontally on the shared baseline of its grid cell.
+    fn place(
+        &self,
+        size: usize,
+        index: usize,
+        ch: char,
+        metrics: &fontdue::Metrics,
+        baseline_in_inner: i32,
+    ) -> Placement {
+        let (padding, inner) = (self.args.padding, self.plan.inner);
+        let (gw, gh) = (metrics.width as u32, metrics.height as u32);
+        match self.packed {
+            Some(rects) => {
+                // A packed glyph's cell is its rect plus the padding around it.
+                let rect = rects.get(&(size, ch));
+                let (draw_x, draw_y) = rect.map_or((0, 0), |rect| (rect.x, rect.y));
+                let (cell_w, cell_h) = match rect {
+                    Some(_) => (gw + 2 * padding, gh + 2 * padding),
+                    None => (0, 0),
+                };
+                Placement {
+                    col: 0,
+                    row: 0,
+                    cell_x: draw_x.saturating_sub(padding),
+                    cell_y: draw_y.saturating_sub(padding),
+                    cell_w,
+                    cell_h,
+                    draw_x,
+                    draw_y,
+                    placed: rect.is_some(),
+                }
+            }
+            None => {
+                let cell = self.args.cell;
+                let (col, row) = (
+                    (index as u32) % self.plan.cols,
+                    (index as u32) / self.plan.cols,
+                );
+                let (cell_x, cell_y) = (col * cell, row * cell);
+                // Some glyphs may rasterize to empty; keep cell empty.
+                let placed = gw > 0 && gh > 0 && gw <= inner && gh <= inner;
+                let (draw_x, draw_y) = if placed {
+                    (
+                        cell_x + padding + (inner - gw) / 2,
+                        (cell_y as i32 + padding as i32 + baseline_in_inner + metrics.ymin).max(0)
+                            as u32,
+                    )
+                } else {
+                    (cell_x + padding, cell_y + padding)
+                };
+                Placement {
+                    col,
+                    row,
+                    cell_x,
+                    cell_y,
+                    cell_w: cell,
+                    cell_h: cell,
+                    draw_x,
+                    draw_y,
+                    placed,
+                }
+            }
+        }
+    }
+
+    /// Draws the `index`th glyph into the main atlas: color glyphs as they
+    /// are, others as a distance field with `--sdf` or else a white mask.
+    fn draw_glyph(
+        &self,
+        atlas: &mut image::RgbaImage,
+        place: &Placement,
+        index: usize,
+        px: f32,
+        glyph: &DrawnGlyph,
+    ) {
+        let face = self.faces.get(self.glyphs.sources[index]);
+        match (glyph.color, self.args.sdf, face) {
+            // Color glyphs are drawn as they are, even in distance field atlases.
+            (Some(rgba), _, _) => {
+                blit_rgba(atlas, glyph.x, glyph.y, glyph.width, glyph.height, rgba)
+            }
+            (None, Some(mode), Some(face)) => {
+                let id = self.glyphs.ids[index];
+                let shape = (id != 0)
+                    .then_some(GlyphId(id))
+                    .and_then(|id| Shape::from_glyph(face, id, px / face.units_per_em() as f32));
+                if let Some(shape) = shape {
+                    // The field fills the whole cell, so the padding holds its falloff.
+                    let origin = (
+                        (glyph.xmin - (glyph.x - place.cell_x) as i32) as f32,
+                        (glyph.ymin + glyph.height as i32 + (glyph.y - place.cell_y) as i32) as f32,
+                    );
+                    shape.render(
+                        atlas,
+                        place.cell_x,
+                        place.cell_y,
+                        place.cell_w,
+                        place.cell_h,
+                        origin,
+                        self.args.sdf_range,
+                        mode == SdfMode::Msdf,
+                    );
+                }
+            }
+            _ => blit_alpha_white(
+                atlas,
+                glyph.x,
+                glyph.y,
+                glyph.width,
+                glyph.height,
+                glyph.alpha,
+            ),
+        }
+    }
+
+    /// The metadata of the `index`th glyph, drawn at `place`.
+    fn glyph_meta(
+        &self,
+        index: usize,
+        colored: bool,
+        place: &Placement,
+        metrics: &fontdue::Metrics,
+        px: f32,
+    ) -> GlyphMeta {
+        let args = self.args;
+        let source = self.glyphs.sources[index];
+        let vertical = (args.orientation == Orientation::Vertical).then(|| {
+            let glyph = GlyphId(self.glyphs.ids[index]);
+            let mut vertical = vertical_glyph_metrics(&self.faces[source], glyph, px, metrics);
+            if args.pixel_mode || args.hinting == Hinting::Full {
+                vertical.advance = vertical.advance.round();
+            }
+            vertical
+        });
+        let (gw, gh) = (metrics.width as u32, metrics.height as u32);
+        GlyphMeta {
+            ch: self.glyphs.chars[index],
+            index: index as u32,
+            font: source,
+            colored,
+            col: place.col,
+            row: place.row,
+            cell_x: place.cell_x,
+            cell_y: place.cell_y,
+            cell_w: place.cell_w,
+            cell_h: place.cell_h,
+            draw_x: place.draw_x,
+            draw_y: place.draw_y,
+            draw_w: gw,
+            draw_h: gh,
+            offset_x: metrics.xmin,
+            offset_y: -(metrics.ymin + gh as i32),
+            // fontdue provides an advance width in px
+            advance: metrics.advance_width,
+            vertical,
+        }
+    }
+}
+
+/// A glyph bitmap as drawn into the main atlas.
+struct DrawnGlyph<'a> {
+    x: u32,
+    y: u32,
+    width: u32,
+    height: u32,
+    alpha: &'a [u8],
+    /// The RGBA bitmap of color and subpixel glyphs, drawn in place of `alpha`.
+    color: Option<&'a [u8]>,
+    /// Baseline-relative left and bottom edges of the bitmap, as in fontdue's metrics.
+    xmin: i32,
+    ymin: i32,
+}
+
+/// Draws `glyph` into the atlas of `effect`'s variant at the same position.
+/// Gradients span `ink_span`, the baseline-relative top and bottom of the
+/// charset's ink. Returns the ink profile of an outline, which
+/// `--optical-kerning outline` measures instead of the fill.
+fn draw_effect(
+    effect: &Effect,
+    atlas: &mut image::RgbaImage,
+    glyph: &DrawnGlyph,
+    ink_span: Option<(i32, i32)>,
+) -> Option<InkProfile> {
+    let DrawnGlyph {
+        x,
+        y,
+        width,
+        height,
+        alpha,
+        color,
+        xmin,
+        ymin,
+    } = *glyph;
+    match *effect {
+        Effect::Outline(r) => {
+            let (dw, dh, dilated) = dilate_alpha_with_border(alpha, width, height, r);
+            // Outline variant: black stroke (dilated alpha), white fill (original alpha).
+            blit_alpha_color(
+                atlas,
+                x.saturating_sub(r),
+                y.saturating_sub(r),
+                dw,
+                dh,
+                &dilated,
+                [0, 0, 0],
+            );
+            match color {
+                Some(rgba) => blit_rgba(atlas, x, y, width, height, rgba),
+                None => blit_alpha_white(atlas, x, y, width, height, alpha),
+            }
+            // The dilated bitmap has a border of `r` pixels around the original glyph,
+            // so its baseline-relative top is shifted by -r and xmin is shifted by -r.
+            return Some(ink_profile_from_alpha(
+                &dilated,
+                dw,
+                dh,
+                ymin - r as i32,
+                xmin - r as i32,
+                0,
+            ));
+        }
+        Effect::Shadow { dx, dy, blur } => {
+            let (bw, bh, blurred) = blur_alpha_with_border(alpha, width, height, blur);
+            blit_alpha_color(
+                atlas,
+                (x as i32 + dx - blur as i32).max(0) as u32,
+                (y as i32 + dy - blur as i32).max(0) as u32,
+                bw,
+                bh,
+                &blurred,
+                [0, 0, 0],
+            );
+            // The fill blends over the shadow so its antialiased edge stays soft.
+            let white: Vec<u8>;
+            let fill = match color {
+                Some(rgba) => rgba,
+                None => {
+                    white = alpha.iter().flat_map(|a| [255, 255, 255, *a]).collect();
+                    &white
+                }
+            };
+            blit_rgba(atlas, x, y, width, height, fill);
+        }
+        Effect::Gradient { top, bottom } => match (color, ink_span) {
+            (Some(rgba), _) => blit_rgba(atlas, x, y, width, height, rgba),
+            (None, Some((ink_top, ink_bottom))) => {
+                for row_y in 0..height {
+                    // Baseline-relative height of the middle of this row.
+                    let row_height = (ymin + (height - row_y) as i32) as f32 - 0.5;
+                    let t = (ink_top as f32 - row_height) / (ink_top - ink_bottom).max(1) as f32;
+                    let row = (row_y * width) as usize..((row_y + 1) * width) as usize;
+                    blit_alpha_color(
+                        atlas,
+                        x,
+                        y + row_y,
+                        width,
+                        1,
+                        &alpha[row],
+                        lerp_rgb(top, bottom, t),
+                    );
+                }
+            }
+            (None, None) => {}
+        },
+    }
+    None
+}
+
+fn blit_alpha_white(dst: &mut image::RgbaImage, x0: u32, y0: u32, w: u32, h: u32, alpha: &[u8]) {
+    blit_alpha_color(dst, x0, y0, w, h, alpha, [255, 255, 255]);
+}
+
+fn blit_alpha_color(
+    dst: &mut image::RgbaImage,
+    x0: u32,
+    y0: u32,
+    w: u32,
+    h: u32,
+    alpha: &[u8],
+    rgb: [u8; 3],
+) {
+    let dst_w = dst.width();
+    let dst_h = dst.height();
+
+    for y in 0..h {
+        for x in 0..w {
+            let a = alpha[(y * w + x) as usize];
+            if a == 0 {
+                continue;
+            }
+            let dx = x0 + x;
+            let dy = y0 + y;
+            if dx >= dst_w || dy >= dst_h {
+                continue;
+            }
+            let existing = dst.get_pixel(dx, dy).0;
+            let out_a = existing[3].max(a);
+            dst.put_pixel(dx, dy, image::Rgba([rgb[0], rgb[1], rgb[2], out_a]));
+        }
+    }
+}
+
+/// Draws a straight-alpha RGBA bitmap over `dst` with source-over blending.
+fn blit_rgba(dst: &mut image::RgbaImage, x0: u32, y0: u32, w: u32, h: u32, rgba: &[u8]) {
+    let dst_w = dst.width();
+    let dst_h = dst.height();
+
+    for y in 0..h {
+        for x in 0..w {
+            let i = ((y * w + x) * 4) as usize;
+            let dx = x0 + x;
+            let dy = y0 + y;
+            if rgba[i + 3] == 0 || dx >= dst_w || dy >= dst_h {
+                continue;
+            }
+            let src = [rgba[i], rgba[i + 1], rgba[i + 2], rgba[i + 3]];
+            blend_over(dst.get_pixel_mut(dx, dy), src, 255);
+        }
+    }
+}
+
+fn dilate_alpha_with_border(alpha: &[u8], w: u32, h: u32, r: u32) -> (u32, u32, Vec<u8>) {
+    if r == 0 || w == 0 || h == 0 {
+        return (w, h, alpha.to_vec());
+    }
+
+    let (out_w, out_h, expanded) = expand_alpha_with_border(alpha, w, h, r);
+    // A square max filter is separable: filter rows, then columns. Each pass
+    // is linear in the image size regardless of the radius.
+    let r = r as usize;
+    let dilated = filter_separable(&expanded, out_w as usize, |src, out| {
+        sliding_max(src, r, out)
+    });
+    (out_w, out_h, dilated)
+}
+
+/// Blurs glyph alpha into a bitmap with a border of `r` pixels, which the
+/// blur spreads into. Two box passes of about `r / 2` each make a tent
+/// filter, soft enough for drop shadows.
+fn blur_alpha_with_border(alpha: &[u8], w: u32, h: u32, r: u32) -> (u32, u32, Vec<u8>) {
+    if r == 0 || w == 0 || h == 0 {
+        return (w, h, alpha.to_vec());
+    }
+
+    let (out_w, out_h, mut blurred) = expand_alpha_with_border(alpha, w, h, r);
+    for radius in [r.div_ceil(2), r / 2] {
+        if radius > 0 {
+            blurred = filter_separable(&blurred, out_w as usize, |src, out| {
+                sliding_mean(src, radius as usize, out)
+            });
+        }
+    }
+    (out_w, out_h, blurred)
+}
+
+/// Copies `alpha` into the center of a buffer `r` pixels larger on every side.
+fn expand_alpha_with_border(alpha: &[u8], w: u32, h: u32, r: u32) -> (u32, u32, Vec<u8>) {
+    let out_w = w + 2 * r;
+    let out_h = h + 2 * r;
+    let mut expanded = vec![0u8; (out_w * out_h) as usize];
+    for y in 0..h {
+        let src_row = (y * w) as usize;
+        let dst_row = ((y + r) * out_w + r) as usize;
+        expanded[dst_row..dst_row + (w as usize)]
+            .copy_from_slice(&alpha[src_row..src_row + (w as usize)]);
+    }
+    (out_w, out_h, expanded)
+}
+
+/// Runs a 1D `filter` over every row of a `width` wide bitmap, then over
+/// every column of the result.
+fn filter_separable(src: &[u8], width: usize, filter: impl Fn(&[u8], &mut [u8])) -> Vec<u8> {
+    let height = src.len() / width;
+    let mut rows = vec![0u8; src.len()];
+    for (src, dst) in src.chunks_exact(width).zip(rows.chunks_exact_mut(width)) {
+        filter(src, dst);
+    }
+
+    let mut out = vec![0u8; src.len()];
+    let mut column = vec![0u8; height];
+    let mut filtered = vec![0u8; height];
+    for x in 0..width {
+        for (y, value) in column.iter_mut().enumerate() {
+            *value = rows[y * width + x];
+        }
+        filter(&column, &mut filtered);
+        for (y, value) in filtered.iter().enumerate() {
+            out[y * width + x] = *value;
+        }
+    }
+    out
+}
+
+/// Writes the mean of `src[i - r..=i + r]` to `out[i]`, counting samples
+/// past either end of the slice as 0.
+fn sliding_mean(src: &[u8], r: usize, out: &mut [u8]) {
+    let window = (2 * r + 1) as u32;
+    let mut sum: u32 = src.iter().take(r).map(|&a| a as u32).sum();
+    for (i, value) in out.iter_mut().enumerate() {
+        if let Some(&entering) = src.get(i + r) {
+            sum += entering as u32;
+        }
+        if i > r {
+            sum -= src[i - r - 1] as u32;
+        }
+        *value = ((sum + window / 2) / window) as u8;
+    }
+}
+
+/// Writes the maximum of `src[i - r..=i + r]` (clamped to the slice) to
+/// `out[i]`, keeping a monotonic queue of candidate indices.
+fn sliding_max(src: &[u8], r: usize, out: &mut [u8]) {
+    let mut window = std::collections::VecDeque::with_capacity(2 * r + 1);
+    let mut next = 0;
+    for (i, value) in out.iter_mut().enumerate() {
+        let end = (i + r).min(src.len() - 1);
+        while next <= end {
+            while window.back().is_some_and(|&j| src[j] <= src[next]) {
+                window.pop_back();
+            }
+            window.push_back(next);
+            next += 1;
+        }
+        while window.front().is_some_and(|&j| j + r < i) {
+            window.pop_front();
+        }
+        *value = src[window[0]];
+    }
+}
+
+fn binarize_alpha(alpha: &mut [u8]) {
+    for a in alpha.iter_mut() {
+        *a = if *a == 0 { 0 } else { 255 };
+    }
+}
+
+/// Keeps pixels at least half covered and clears the rest, so pixel fonts
+/// rasterized on their grid lose the faint coverage rounding leaves at edges.
+fn threshold_alpha(alpha: &mut [u8]) {
+    for a in alpha.iter_mut() {
+        *a = if *a >= 128 { 255 } else { 0 };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shadow_blur_spreads_into_the_border() {
+        let (w, h, blurred) = blur_alpha_with_border(&[255], 1, 1, 2);
+        assert_eq!((w, h), (5, 5));
+        let at = |x: usize, y: usize| blurred[y * 5 + x];
+        // A tent filter: brightest in the middle, fading out to the border.
+        assert!(at(2, 2) > at(1, 2) && at(1, 2) > at(0, 2));
+        assert!(at(0, 2) > 0);
+        assert_eq!(at(1, 2), at(3, 2));
+        assert_eq!(at(2, 1), at(1, 2));
+        // Without blur the bitmap is left alone.
+        assert_eq!(blur_alpha_with_border(&[7, 9], 2, 1, 0), (2, 1, vec![7, 9]));
+    }
+
+    #[test]
+    fn explicit_px_reports_overflowing_glyphs() {
+        let glyph = |ch, width, height, ymin| {
+            let metrics = fontdue::Metrics {
+                width,
+                height,
+                ymin,
+                ..Default::default()
+            };
+            (ch, metrics, Vec::new())
+        };
+        let rasterized = [
+            glyph('a', 8, 8, 0),
+            glyph('g', 8, 11, -3),
+            glyph(' ', 0, 0, 0),
+            glyph('W', 15, 12, 0),
+        ];
+        assert!(check_glyphs_fit(&rasterized, 15, 1).is_ok());
+
+        let err = check_glyphs_fit(&rasterized, 14, 1)
+            .unwrap_err()
+            .to_string();
+        assert_eq!(
+            err,
+            "1 glyph(s) overflow the 14x14 cell interior: 'W' (15x15); use --cell 17 or a smaller --px"
+        );
+    }
+
+    #[test]
+    fn tight_packing_gives_each_glyph_its_rect() {
+        let glyphs = [
+            (0, 'i', 3, 12),
+            (0, 'W', 14, 12),
+            (0, ' ', 0, 0),
+            (0, 'i', 3, 12),
+            (0, '.', 3, 3),
+        ];
+        let rects = pack_glyphs(&glyphs, 1, (32, 32)).unwrap();
+        assert_eq!(rects.len(), 3);
+        assert_eq!((rects[&(0, 'W')].w, rects[&(0, 'W')].h), (14, 12));
+        let all: Vec<&AtlasRect> = rects.values().collect();
+        for (i, a) in all.iter().enumerate() {
+            assert!(a.x >= 1 && a.y >= 1 && a.x + a.w < 32 && a.y + a.h < 32);
+            for b in &all[i + 1..] {
+                let apart =
+                    a.x + a.w < b.x || b.x + b.w < a.x || a.y + a.h < b.y || b.y + b.h < a.y;
+                assert!(apart, "{a:?} overlaps {b:?}");
+            }
+        }
+
+        assert!(pack_glyphs(&glyphs, 1, (16, 16)).is_err());
+    }
+
+    #[test]
+    fn dilation_matches_square_max_filter() {
+        let (w, h, r) = (7u32, 5u32, 2u32);
+        let alpha: Vec<u8> = (0..w * h).map(|i| ((i * 37) % 11 * 23) as u8).collect();
+        let (out_w, out_h, dilated) = dilate_alpha_with_border(&alpha, w, h, r);
+        assert_eq!((out_w, out_h), (w + 2 * r, h + 2 * r));
+
+        for y in 0..out_h as i32 {
+            for x in 0..out_w as i32 {
+                let mut expected = 0;
+                for yy in (y - r as i32)..=(y + r as i32) {
+                    for xx in (x - r as i32)..=(x + r as i32) {
+                        let (sx, sy) = (xx - r as i32, yy - r as i32);
+                        if sx >= 0 && sy >= 0 && sx < w as i32 && sy < h as i32 {
+                            expected = expected.max(alpha[(sy as u32 * w + sx as u32) as usize]);
+                        }
+                    }
+                }
+                assert_eq!(dilated[(y as u32 * out_w + x as u32) as usize], expected);
+            }
+        }
+    }
+
+    #[test]
+    fn binarize_alpha_makes_hard_edges() {
+        let mut alpha = vec![0, 1, 127, 128, 254, 255];
+        binarize_alpha(&mut alpha);
+        assert_eq!(alpha, vec![0, 255, 255, 255, 255, 255]);
+    }
+
+    #[test]
+    fn threshold_alpha_keeps_half_covered_pixels() {
+        let mut alpha = vec![0, 1, 127, 128, 254, 255];
+        threshold_alpha(&mut alpha);
+        assert_eq!(alpha, vec![0, 0, 0, 255, 255, 255]);
+        assert_eq!([640, 128, 0, 384].into_iter().fold(0, gcd), 128);
+    }
+}
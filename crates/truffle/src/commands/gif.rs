@@ -0,0 +1,168 @@
+use crate::image::animation::{self, AnimationEntry, ANIMATIONS_FILE};
+use crate::image::gif;
+use clap::Parser;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+/// Animation name used for GIF frames in `animations.json`.
+const GIF_ANIMATION: &str = "frames";
+
+#[derive(Parser)]
+#[command(about = "Export animated GIFs to numbered PNG frames with a timeline")]
+pub struct GifArgs {
+    /// Input path (file or directory)
+    #[arg(value_name = "INPUT_PATH")]
+    pub input_path: PathBuf,
+
+    /// Preview what would be generated without creating files
+    #[arg(long)]
+    pub dry_run: bool,
+
+    /// Overwrite exports that are newer than their GIF source
+    #[arg(long)]
+    pub force: bool,
+
+    /// Recursively process directories
+    #[arg(short, long)]
+    pub recursive: bool,
+}
+
+fn is_gif(path: &Path) -> bool {
+    path.extension().and_then(|s| s.to_str()) == Some("gif")
+}
+
+fn is_up_to_date(source: &Path, output: &Path) -> bool {
+    let modified = |path: &Path| path.metadata().and_then(|m| m.modified()).ok();
+    match (modified(source), modified(output)) {
+        (Some(source), Some(output)) => output >= source,
+        _ => false,
+    }
+}
+
+fn process_file(source: &Path, dry_run: bool, force: bool) -> Result<bool, String> {
+    let stem = source
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or_default();
+    let output_dir = source.with_file_name(stem);
+    let manifest = output_dir.join(ANIMATIONS_FILE);
+
+    if !force && is_up_to_date(source, &manifest) {
+        println!("[gif] SKIP: {} (export is up to date)", source.display());
+        return Ok(false);
+    }
+
+    if dry_run {
+        println!("[gif] DRY-RUN: Would generate {}", manifest.display());
+        return Ok(true);
+    }
+
+    println!("[gif] Processing: {}", source.display());
+    let frames = gif::read_gif(source)?;
+    if frames.is_empty() {
+        return Err(format!("{} has no frames", source.display()));
+    }
+
+    std::fs::create_dir_all(&output_dir)
+        .map_err(|e| format!("Failed to create {}: {}", output_dir.display(), e))?;
+
+    let mut entry = AnimationEntry {
+        frames: Vec::new(),
+        durations: Vec::new(),
+        direction: "forward".into(),
+        repeat: 0,
+    };
+    for (index, frame) in frames.iter().enumerate() {
+        let file_name = animation::frame_file_name(GIF_ANIMATION, index, frames.len());
+        let path = output_dir.join(&file_name);
+        frame
+            .image
+            .save(&path)
+            .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+        entry.frames.push(file_name);
+        entry.durations.push(frame.duration_ms);
+    }
+
+    let mut animations = BTreeMap::new();
+    animations.insert(GIF_ANIMATION.to_string(), entry);
+    animation::write_manifest(&output_dir, &animations)?;
+
+    println!(
+        "[gif] ✅ Generated {} frame(s) in {}",
+        frames.len(),
+        output_dir.display()
+    );
+    Ok(true)
+}
+
+fn process_path(
+    path: &Path,
+    dry_run: bool,
+    force: bool,
+    recursive: bool,
+) -> Result<(usize, usize, usize), String> {
+    let mut processed = 0;
+    let mut skipped = 0;
+    let mut errors = 0;
+
+    if !path.exists() {
+        return Err(format!("Path does not exist: {}", path.display()));
+    }
+
+    let files: Vec<PathBuf> = if path.is_file() {
+        if !is_gif(path) {
+            return Err(format!("Input must be a GIF file: {}", path.display()));
+        }
+        vec![path.to_path_buf()]
+    } else {
+        let max_depth = if recursive { usize::MAX } else { 1 };
+        WalkDir::new(path)
+            .max_depth(max_depth)
+            .into_iter()
+            .filter_map(|e| e.ok())
+            .filter(|e| e.file_type().is_file())
+            .map(|e| e.path().to_path_buf())
+            .filter(|p| is_gif(p))
+            .collect()
+    };
+
+    if files.is_empty() {
+        println!("[gif] No GIF files found in: {}", path.display());
+        return Ok((0, 0, 0));
+    }
+
+    println!("[gif] Found {} GIF file(s) to process", files.len());
+
+    for file in &files {
+        match process_file(file, dry_run, force) {
+            Ok(true) => processed += 1,
+            Ok(false) => skipped += 1,
+            Err(e) => {
+                eprintln!("[gif] ERROR: {}", e);
+                errors += 1;
+            }
+        }
+    }
+
+    if dry_run {
+        println!("[gif] DRY-RUN: Would process {} file(s)", processed);
+    } else {
+        println!(
+            "[gif] Done ✅ Processed: {}, Skipped: {}, Errors: {}",
+            processed, skipped, errors
+        );
+    }
+
+    Ok((processed, skipped, errors))
+}
+
+pub fn run(args: GifArgs) -> bool {
+    match process_path(&args.input_path, args.dry_run, args.force, args.recursive) {
+        Ok((_, _, errors)) => errors == 0,
+        Err(e) => {
+            eprintln!("[gif] ERROR: {}", e);
+            false
+        }
+    }
+}
@@ -1,61 +1,136 @@
-use crate::image::highlight;
+use crate::cache::{ContentCache, RemoteCache};
+use crate::commands::output::OutputOptions;
+use crate::image::{highlight, RASTER_EXTENSIONS};
 use clap::Parser;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::path::{Path, PathBuf};
-use walkdir::WalkDir;
 
 #[derive(Parser)]
-#[command(about = "Generate highlight variants of PNG images with white outlines")]
+#[command(about = "Generate highlight variants of images with white outlines")]
 pub struct HighlightArgs {
     /// Input path (file or directory)
     #[arg(value_name = "INPUT_PATH")]
     pub input_path: PathBuf,
 
-    /// Preview what would be generated without creating files
-    #[arg(long)]
-    pub dry_run: bool,
-
-    /// Overwrite existing highlight variants
-    #[arg(long)]
-    pub force: bool,
-
     /// Outline thickness in pixels
     #[arg(long, default_value = "1")]
     pub thickness: u32,
 
-    /// Recursively process directories
-    #[arg(short, long)]
-    pub recursive: bool,
+    #[command(flatten)]
+    pub output: OutputOptions,
+
+    /// Reuse highlights from this content-addressed cache directory
+    #[arg(long)]
+    pub cache_dir: Option<PathBuf>,
+
+    /// Shared store backing `cache_dir` (set by sync from truffle.toml)
+    #[arg(skip)]
+    pub remote_cache: Option<RemoteCache>,
 }
 
-fn get_highlight_path(image_path: &Path) -> PathBuf {
-    if let Some(stem) = image_path.file_stem().and_then(|s| s.to_str()) {
-        let mut path = image_path.to_path_buf();
-        path.set_file_name(format!("{}-highlight.png", stem));
-        path
-    } else {
-        let mut path = image_path.to_path_buf();
-        path.set_file_name(format!("{}-highlight.png", image_path.display()));
-        path
+/// Sidecar next to generated highlights recording the hash of the source
+/// and settings each one was generated from.
+const HASHES_FILE: &str = ".highlights.json";
+
+/// The source hashes of the highlights in each directory touched by a run,
+/// loaded on first use and written back by [`SourceHashes::save`].
+#[derive(Default)]
+struct SourceHashes {
+    dirs: HashMap<PathBuf, BTreeMap<String, String>>,
+    dirty: HashSet<PathBuf>,
+}
+
+impl SourceHashes {
+    fn split(highlight_path: &Path) -> (PathBuf, String) {
+        let dir = highlight_path
+            .parent()
+            .unwrap_or(Path::new(""))
+            .to_path_buf();
+        let name = highlight_path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        (dir, name)
+    }
+
+    fn dir(&mut self, dir: PathBuf) -> &mut BTreeMap<String, String> {
+        self.dirs.entry(dir).or_insert_with_key(|dir| {
+            std::fs::read_to_string(dir.join(HASHES_FILE))
+                .ok()
+                .and_then(|content| serde_json::from_str(&content).ok())
+                .unwrap_or_default()
+        })
+    }
+
+    fn get(&mut self, highlight_path: &Path) -> Option<&String> {
+        let (dir, name) = Self::split(highlight_path);
+        self.dir(dir).get(&name)
+    }
+
+    fn set(&mut self, highlight_path: &Path, hash: String) {
+        let (dir, name) = Self::split(highlight_path);
+        self.dir(dir.clone()).insert(name, hash);
+        self.dirty.insert(dir);
+    }
+
+    fn save(&self) -> Result<(), String> {
+        for dir in &self.dirty {
+            let path = dir.join(HASHES_FILE);
+            let json = serde_json::to_string_pretty(&self.dirs[dir])
+                .map_err(|e| format!("Failed to serialize {}: {}", path.display(), e))?;
+            std::fs::write(&path, json + "\n")
+                .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+        }
+        Ok(())
     }
 }
 
+/// Default suffix of highlight variants.
+const SUFFIX: &str = "-highlight";
+
+/// Hash of everything a highlight depends on. Doubles as its cache key.
+fn source_hash(source: &[u8], thickness: u32) -> String {
+    ContentCache::key("highlight", &[source, &thickness.to_le_bytes()])
+}
+
 fn process_image(
     image_path: &Path,
-    dry_run: bool,
-    force: bool,
+    highlight_path: &Path,
+    options: &OutputOptions,
     thickness: u32,
+    cache: Option<&ContentCache>,
+    hashes: &mut SourceHashes,
 ) -> Result<bool, String> {
-    let highlight_path = get_highlight_path(image_path);
+    let source = std::fs::read(image_path)
+        .map_err(|e| format!("Failed to read {}: {}", image_path.display(), e))?;
+    let hash = source_hash(&source, thickness);
 
-    if highlight_path.exists() && !force {
-        println!(
-            "[highlight] SKIP: {} (highlight already exists)",
-            image_path.display()
-        );
-        return Ok(false);
+    // Highlights without a recorded hash predate tracking or were made by
+    // hand, so only --force replaces them.
+    if !options.should_write(image_path, highlight_path) {
+        match hashes.get(highlight_path) {
+            Some(recorded) if *recorded == hash => {
+                println!(
+                    "[highlight] SKIP: {} (highlight is up to date)",
+                    image_path.display()
+                );
+                return Ok(false);
+            }
+            None => {
+                println!(
+                    "[highlight] SKIP: {} (highlight already exists)",
+                    image_path.display()
+                );
+                return Ok(false);
+            }
+            Some(_) => println!(
+                "[highlight] Source or settings changed: {}",
+                image_path.display()
+            ),
+        }
     }
 
-    if dry_run {
+    if options.dry_run {
         println!(
             "[highlight] DRY-RUN: Would generate {}",
             highlight_path.display()
@@ -63,14 +138,36 @@ fn process_image(
         return Ok(true);
     }
 
+    let outputs = [("highlight.png", highlight_path)];
+    if let Some(cache) = cache {
+        if cache.restore(&hash, &outputs) {
+            println!(
+                "[highlight] ✅ Restored from cache: {}",
+                highlight_path.display()
+            );
+            hashes.set(highlight_path, hash);
+            return Ok(true);
+        }
+    }
+
     println!("[highlight] Processing: {}", image_path.display());
-    highlight::generate_highlight(image_path, &highlight_path, thickness).map_err(|e| {
+    if let Some(parent) = highlight_path.parent() {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+    }
+    highlight::generate_highlight(image_path, highlight_path, thickness).map_err(|e| {
         format!(
             "Failed to generate highlight for {}: {}",
             image_path.display(),
             e
         )
     })?;
+    if let Some(cache) = cache {
+        if let Err(e) = cache.store(&hash, &outputs) {
+            println!("[highlight] WARN: {:#}", e);
+        }
+    }
+    hashes.set(highlight_path, hash);
 
     println!("[highlight] ✅ Generated: {}", highlight_path.display());
     Ok(true)
@@ -78,90 +175,57 @@ fn process_image(
 
 fn process_path(
     path: &Path,
-    dry_run: bool,
-    force: bool,
+    options: &OutputOptions,
     thickness: u32,
-    recursive: bool,
+    cache: Option<&ContentCache>,
 ) -> Result<(usize, usize, usize), String> {
     let mut processed = 0;
     let mut skipped = 0;
     let mut errors = 0;
+    let mut hashes = SourceHashes::default();
 
-    if !path.exists() {
-        return Err(format!("Path does not exist: {}", path.display()));
-    }
-
-    if path.is_file() {
-        if path.extension().and_then(|s| s.to_str()) != Some("png") {
-            return Err(format!("Input must be a PNG file: {}", path.display()));
-        }
-
-        match process_image(path, dry_run, force, thickness) {
-            Ok(true) => processed += 1,
-            Ok(false) => skipped += 1,
-            Err(_) => errors += 1,
-        }
+    let suffix = options.suffix(SUFFIX);
+    let image_files = options.collect_inputs(path, &RASTER_EXTENSIONS, suffix)?;
+    let input_root = if path.is_file() {
+        path.parent().unwrap_or(Path::new(""))
     } else {
-        let png_files: Vec<PathBuf> = if recursive {
-            WalkDir::new(path)
-                .into_iter()
-                .filter_map(|e| e.ok())
-                .filter(|e| e.file_type().is_file())
-                .map(|e| e.path().to_path_buf())
-                .filter(|p| {
-                    p.extension().and_then(|s| s.to_str()) == Some("png")
-                        && !p
-                            .file_name()
-                            .and_then(|n| n.to_str())
-                            .map(|n| n.contains("-highlight.png"))
-                            .unwrap_or(false)
-                })
-                .collect()
-        } else {
-            // Non-recursive: only process files directly in the directory
-            std::fs::read_dir(path)
-                .map_err(|e| format!("Failed to read directory: {}", e))?
-                .filter_map(|e| e.ok())
-                .filter(|e| e.file_type().map(|ft| ft.is_file()).unwrap_or(false))
-                .map(|e| e.path())
-                .filter(|p| {
-                    p.extension().and_then(|s| s.to_str()) == Some("png")
-                        && !p
-                            .file_name()
-                            .and_then(|n| n.to_str())
-                            .map(|n| n.contains("-highlight.png"))
-                            .unwrap_or(false)
-                })
-                .collect()
-        };
-
-        if png_files.is_empty() {
-            println!("[highlight] No PNG files found in: {}", path.display());
-            return Ok((0, 0, 0));
-        }
+        path
+    };
+
+    if image_files.is_empty() {
+        println!("[highlight] No images found in: {}", path.display());
+        return Ok((0, 0, 0));
+    }
 
+    if path.is_dir() {
         println!(
-            "[highlight] Found {} PNG file(s) to process",
-            png_files.len()
+            "[highlight] Found {} image(s) to process",
+            image_files.len()
         );
+    }
 
-        for file in png_files {
-            match process_image(&file, dry_run, force, thickness) {
-                Ok(true) => processed += 1,
-                Ok(false) => {
-                    let highlight_path = get_highlight_path(&file);
-                    if highlight_path.exists() {
-                        skipped += 1;
-                    } else {
-                        errors += 1;
-                    }
-                }
-                Err(_) => errors += 1,
+    for file in image_files {
+        let highlight_path = options.output_path(input_root, &file, SUFFIX, "png");
+        match process_image(
+            &file,
+            &highlight_path,
+            options,
+            thickness,
+            cache,
+            &mut hashes,
+        ) {
+            Ok(true) => processed += 1,
+            Ok(false) => skipped += 1,
+            Err(e) => {
+                eprintln!("[highlight] ERROR: {}", e);
+                errors += 1;
             }
         }
     }
 
-    if dry_run {
+    hashes.save()?;
+
+    if options.dry_run {
         println!("[highlight] DRY-RUN: Would process {} file(s)", processed);
     } else {
         println!(
@@ -181,15 +245,40 @@ pub fn run(args: HighlightArgs) -> bool {
 
     match process_path(
         &args.input_path,
-        args.dry_run,
-        args.force,
+        &args.output,
         args.thickness,
-        args.recursive,
+        args.cache_dir
+            .map(|dir| ContentCache::new(dir).with_remote(args.remote_cache))
+            .as_ref(),
     ) {
-        Ok((processed, _, _)) => processed > 0 || args.dry_run,
+        Ok((processed, _, _)) => processed > 0 || args.output.dry_run,
         Err(e) => {
             eprintln!("[highlight] ERROR: {}", e);
             false
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hashes_round_trip_per_directory() {
+        let dir = std::env::temp_dir().join(format!("truffle-highlight-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("ui")).unwrap();
+        let play = dir.join("ui/play-highlight.png");
+
+        assert_ne!(source_hash(b"png", 1), source_hash(b"png", 2));
+        let mut hashes = SourceHashes::default();
+        assert_eq!(hashes.get(&play), None);
+        hashes.set(&play, source_hash(b"png", 1));
+        hashes.save().unwrap();
+
+        let mut hashes = SourceHashes::default();
+        assert_eq!(hashes.get(&play), Some(&source_hash(b"png", 1)));
+        assert!(dir.join("ui").join(HASHES_FILE).is_file());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
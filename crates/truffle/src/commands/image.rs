@@ -1,23 +1,47 @@
+pub use crate::commands::aseprite::{run as aseprite_run, AsepriteArgs, AsepriteMode};
+pub use crate::commands::buffer::{run as buffer_run, BufferArgs};
+pub use crate::commands::gif::{run as gif_run, GifArgs};
 pub use crate::commands::highlight::{run as highlight_run, HighlightArgs};
 pub use crate::commands::palette::{run as palette_run, PaletteArgs};
+pub use crate::commands::palette_extract::{run as palette_extract_run, PaletteExtractArgs};
+pub use crate::commands::psd::{run as psd_run, PsdArgs};
+pub use crate::commands::svg::{run as svg_run, SvgArgs};
 pub use crate::commands::terrain::{run as terrain_run, TerrainArgs};
 
 use clap::Subcommand;
 
 #[derive(Subcommand)]
 pub enum ImageCommands {
+    /// Export Aseprite files to PNG images and animation frames
+    Aseprite(AsepriteArgs),
+    /// Export PNG images as raw RGBA Luau modules for EditableImage
+    Buffer(BufferArgs),
+    /// Export animated GIFs to numbered PNG frames with a timeline
+    Gif(GifArgs),
     /// Generate highlight variants of PNG images with white outlines
     Highlight(HighlightArgs),
     /// Apply a color palette to PNG images
     Palette(PaletteArgs),
+    /// Extract the dominant colors of an image into a palette PNG
+    PaletteExtract(PaletteExtractArgs),
+    /// Flatten PSD files or export their layer groups to PNG
+    Psd(PsdArgs),
+    /// Rasterize SVG images to PNG at one or more scales
+    Svg(SvgArgs),
     /// Generate grass integration PNG overlays
     Terrain(TerrainArgs),
 }
 
 pub fn run(command: ImageCommands) -> bool {
     match command {
+        ImageCommands::Aseprite(args) => aseprite_run(args),
+        ImageCommands::Buffer(args) => buffer_run(args),
+        ImageCommands::Gif(args) => gif_run(args),
         ImageCommands::Highlight(args) => highlight_run(args),
         ImageCommands::Palette(args) => palette_run(args),
+        ImageCommands::PaletteExtract(args) => palette_extract_run(args),
+        ImageCommands::Psd(args) => psd_run(args),
+        ImageCommands::Svg(args) => svg_run(args),
         ImageCommands::Terrain(args) => terrain_run(args),
     }
 }
@@ -1,6 +1,19 @@
+pub mod aseprite;
+pub mod atlas;
+pub mod buffer;
+pub mod check;
+pub mod daemon;
 pub mod font;
+pub mod gif;
 pub mod highlight;
 pub mod image;
+pub mod output;
 pub mod palette;
+pub mod palette_extract;
+pub mod psd;
+pub mod svg;
 pub mod sync;
 pub mod terrain;
+pub mod tilemap;
+pub mod upload;
+pub mod verify;
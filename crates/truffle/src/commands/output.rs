@@ -0,0 +1,159 @@
+use clap::Args;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+/// Output options shared by the image subcommands that write one image per
+/// input image, so batch workflows behave the same whichever command runs.
+///
+/// Outputs are named `<stem><suffix>.<ext>` and written next to their source,
+/// or below `--output-dir` mirroring the layout of the input path. Existing
+/// outputs are only replaced with `--force`; commands that edit images in
+/// place (no suffix and no output dir) always write.
+#[derive(Args, Debug, Clone, Default)]
+pub struct OutputOptions {
+    /// Write outputs below this directory, mirroring the layout of
+    /// INPUT_PATH, instead of next to each source image
+    #[arg(long)]
+    pub output_dir: Option<PathBuf>,
+
+    /// Suffix appended to output file names (defaults per command)
+    #[arg(long)]
+    pub suffix: Option<String>,
+
+    /// Recursively process directories
+    #[arg(short, long)]
+    pub recursive: bool,
+
+    /// Preview what would be written without creating files
+    #[arg(long)]
+    pub dry_run: bool,
+
+    /// Overwrite existing outputs
+    #[arg(long)]
+    pub force: bool,
+}
+
+impl OutputOptions {
+    /// The suffix in effect, `default` unless `--suffix` was given.
+    pub fn suffix<'a>(&'a self, default: &'a str) -> &'a str {
+        self.suffix.as_deref().unwrap_or(default)
+    }
+
+    /// Where the output for `source` goes. `input_root` is the directory the
+    /// run started from; `extension` replaces the source's extension.
+    pub fn output_path(
+        &self,
+        input_root: &Path,
+        source: &Path,
+        default_suffix: &str,
+        extension: &str,
+    ) -> PathBuf {
+        let mut path = match &self.output_dir {
+            Some(dir) => dir.join(source.strip_prefix(input_root).unwrap_or(source)),
+            None => source.to_path_buf(),
+        };
+        let stem = source
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_else(|| source.display().to_string());
+        path.set_file_name(format!(
+            "{}{}.{}",
+            stem,
+            self.suffix(default_suffix),
+            extension
+        ));
+        path
+    }
+
+    /// Whether `output` may be written: it is new, the run is forced, or the
+    /// command edits `source` in place.
+    pub fn should_write(&self, source: &Path, output: &Path) -> bool {
+        self.force || !output.exists() || output == source
+    }
+
+    /// The files with one of `extensions` in `input`: `input` itself if it is
+    /// a file, otherwise the directory's files, recursing with `--recursive`.
+    /// Outputs of an earlier run (stems ending in `own_suffix`) are left out.
+    pub fn collect_inputs(
+        &self,
+        input: &Path,
+        extensions: &[&str],
+        own_suffix: &str,
+    ) -> Result<Vec<PathBuf>, String> {
+        if !input.exists() {
+            return Err(format!("Input path does not exist: {}", input.display()));
+        }
+        if input.is_file() {
+            if !has_extension(input, extensions) {
+                return Err(format!(
+                    "Input must be a .{} file: {}",
+                    extensions.join("/."),
+                    input.display()
+                ));
+            }
+            return Ok(vec![input.to_path_buf()]);
+        }
+
+        let max_depth = if self.recursive { usize::MAX } else { 1 };
+        let mut files: Vec<PathBuf> = WalkDir::new(input)
+            .max_depth(max_depth)
+            .into_iter()
+            .filter_map(|e| e.ok())
+            .filter(|e| e.file_type().is_file())
+            .map(|e| e.into_path())
+            .filter(|path| has_extension(path, extensions))
+            .filter(|path| {
+                own_suffix.is_empty()
+                    || !path
+                        .file_stem()
+                        .and_then(|stem| stem.to_str())
+                        .is_some_and(|stem| stem.ends_with(own_suffix))
+            })
+            .collect();
+        files.sort();
+        Ok(files)
+    }
+}
+
+fn has_extension(path: &Path, extensions: &[&str]) -> bool {
+    path.extension()
+        .and_then(|s| s.to_str())
+        .is_some_and(|ext| extensions.iter().any(|e| ext.eq_ignore_ascii_case(e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolves_output_paths() {
+        let root = Path::new("assets/images");
+        let source = Path::new("assets/images/ui/play.png");
+
+        let options = OutputOptions::default();
+        assert_eq!(
+            options.output_path(root, source, "-highlight", "png"),
+            Path::new("assets/images/ui/play-highlight.png")
+        );
+        assert_eq!(options.output_path(root, source, "", "png"), source);
+
+        let options = OutputOptions {
+            output_dir: Some(PathBuf::from("out")),
+            suffix: Some("@outlined".into()),
+            ..Default::default()
+        };
+        assert_eq!(
+            options.output_path(root, source, "-highlight", "png"),
+            Path::new("out/ui/play@outlined.png")
+        );
+    }
+
+    #[test]
+    fn in_place_edits_always_write() {
+        let options = OutputOptions::default();
+        let existing = Path::new("Cargo.toml");
+        assert!(options.should_write(existing, existing));
+        assert!(!options.should_write(Path::new("a.png"), existing));
+        assert!(options.should_write(Path::new("a.png"), Path::new("missing.png")));
+    }
+}
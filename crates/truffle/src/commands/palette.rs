@@ -1,7 +1,7 @@
+use crate::commands::output::OutputOptions;
 use crate::image::palette;
 use clap::Parser;
 use std::path::{Path, PathBuf};
-use walkdir::WalkDir;
 
 #[derive(Parser)]
 #[command(about = "Apply a palette PNG to one image or all images in a directory")]
@@ -14,13 +14,9 @@ pub struct PaletteArgs {
     #[arg(value_name = "PALETTE_PATH")]
     pub palette_path: PathBuf,
 
-    /// Preview what would be changed without writing files
-    #[arg(long)]
-    pub dry_run: bool,
-
-    /// Recursively process directories
-    #[arg(short, long)]
-    pub recursive: bool,
+    // Images are edited in place unless `--output-dir` or `--suffix` is given
+    #[command(flatten)]
+    pub output: OutputOptions,
 }
 
 fn is_png(path: &Path) -> bool {
@@ -38,59 +34,45 @@ fn same_file(path: &Path, other: &Path) -> bool {
     }
 }
 
+/// Returns false when the output already exists and was left alone.
 fn process_image(
     image_path: &Path,
+    output_path: &Path,
     palette_colors: &[[u8; 3]],
-    dry_run: bool,
-) -> Result<(), String> {
-    if dry_run {
-        println!("[palette] DRY-RUN: Would process {}", image_path.display());
-        return Ok(());
+    options: &OutputOptions,
+) -> Result<bool, String> {
+    if !options.should_write(image_path, output_path) {
+        println!(
+            "[palette] SKIP: {} (output already exists)",
+            output_path.display()
+        );
+        return Ok(false);
     }
 
-    println!("[palette] Processing: {}", image_path.display());
-    palette::apply_palette_to_path(image_path, palette_colors)?;
-    println!("[palette] ✅ Updated: {}", image_path.display());
-    Ok(())
-}
+    if options.dry_run {
+        println!("[palette] DRY-RUN: Would write {}", output_path.display());
+        return Ok(true);
+    }
 
-fn collect_png_files(path: &Path, recursive: bool) -> Result<Vec<PathBuf>, String> {
-    if recursive {
-        Ok(WalkDir::new(path)
-            .into_iter()
-            .filter_map(|e| e.ok())
-            .filter(|e| e.file_type().is_file())
-            .map(|e| e.path().to_path_buf())
-            .filter(|p| is_png(p))
-            .collect())
-    } else {
-        Ok(std::fs::read_dir(path)
-            .map_err(|e| format!("Failed to read directory {}: {}", path.display(), e))?
-            .filter_map(|e| e.ok())
-            .filter(|e| e.file_type().map(|ft| ft.is_file()).unwrap_or(false))
-            .map(|e| e.path())
-            .filter(|p| is_png(p))
-            .collect())
+    println!("[palette] Processing: {}", image_path.display());
+    if let Some(parent) = output_path.parent() {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
     }
+    palette::apply_palette_to_path(image_path, output_path, palette_colors)?;
+    println!("[palette] ✅ Updated: {}", output_path.display());
+    Ok(true)
 }
 
 fn process_path(
     input_path: &Path,
     palette_path: &Path,
-    dry_run: bool,
-    recursive: bool,
+    options: &OutputOptions,
 ) -> Result<(usize, usize, usize), String> {
     let mut processed = 0usize;
     let mut skipped = 0usize;
     let mut errors = 0usize;
 
-    if !input_path.exists() {
-        return Err(format!(
-            "Input path does not exist: {}",
-            input_path.display()
-        ));
-    }
-
     if !palette_path.exists() {
         return Err(format!(
             "Palette path does not exist: {}",
@@ -114,60 +96,44 @@ fn process_path(
 
     let palette_colors = palette::load_palette_colors(palette_path)?;
 
-    if input_path.is_file() {
-        if !is_png(input_path) {
-            return Err(format!(
-                "Input must be a PNG file: {}",
-                input_path.display()
-            ));
-        }
+    let png_files = options.collect_inputs(input_path, &["png"], options.suffix(""))?;
+    let input_root = if input_path.is_file() {
+        input_path.parent().unwrap_or(Path::new(""))
+    } else {
+        input_path
+    };
 
-        if same_file(input_path, palette_path) {
+    if png_files.is_empty() {
+        println!("[palette] No PNG files found in: {}", input_path.display());
+        return Ok((0, 0, 0));
+    }
+
+    if input_path.is_dir() {
+        println!("[palette] Found {} PNG file(s) to process", png_files.len());
+    }
+
+    for file in png_files {
+        if same_file(&file, palette_path) {
             println!(
                 "[palette] SKIP: {} (palette image is excluded from processing)",
-                input_path.display()
+                file.display()
             );
             skipped += 1;
-        } else {
-            match process_image(input_path, &palette_colors, dry_run) {
-                Ok(()) => processed += 1,
-                Err(err) => {
-                    eprintln!("[palette] ERROR: {}", err);
-                    errors += 1;
-                }
-            }
+            continue;
         }
-    } else {
-        let png_files = collect_png_files(input_path, recursive)?;
-
-        if png_files.is_empty() {
-            println!("[palette] No PNG files found in: {}", input_path.display());
-            return Ok((0, 0, 0));
-        }
-
-        println!("[palette] Found {} PNG file(s) to process", png_files.len());
-
-        for file in png_files {
-            if same_file(&file, palette_path) {
-                println!(
-                    "[palette] SKIP: {} (palette image is excluded from processing)",
-                    file.display()
-                );
-                skipped += 1;
-                continue;
-            }
 
-            match process_image(&file, &palette_colors, dry_run) {
-                Ok(()) => processed += 1,
-                Err(err) => {
-                    eprintln!("[palette] ERROR: {}", err);
-                    errors += 1;
-                }
+        let output_path = options.output_path(input_root, &file, "", "png");
+        match process_image(&file, &output_path, &palette_colors, options) {
+            Ok(true) => processed += 1,
+            Ok(false) => skipped += 1,
+            Err(err) => {
+                eprintln!("[palette] ERROR: {}", err);
+                errors += 1;
             }
         }
     }
 
-    if dry_run {
+    if options.dry_run {
         println!(
             "[palette] DRY-RUN: Would process {} file(s), Skipped: {}",
             processed, skipped
@@ -183,13 +149,8 @@ fn process_path(
 }
 
 pub fn run(args: PaletteArgs) -> bool {
-    match process_path(
-        &args.input_path,
-        &args.palette_path,
-        args.dry_run,
-        args.recursive,
-    ) {
-        Ok((processed, _, _)) => processed > 0 || args.dry_run,
+    match process_path(&args.input_path, &args.palette_path, &args.output) {
+        Ok((processed, _, _)) => processed > 0 || args.output.dry_run,
         Err(err) => {
             eprintln!("[palette] ERROR: {}", err);
             false
@@ -0,0 +1,88 @@
+use crate::image::palette;
+use clap::Parser;
+use std::path::{Path, PathBuf};
+
+#[derive(Parser)]
+#[command(about = "Extract the dominant colors of an image into a palette PNG")]
+pub struct PaletteExtractArgs {
+    /// Image to take the colors from
+    #[arg(value_name = "IMAGE")]
+    pub image_path: PathBuf,
+
+    /// Number of colors to extract
+    #[arg(long, default_value = "16", value_parser = clap::value_parser!(u32).range(1..=256))]
+    pub colors: u32,
+
+    /// Palette PNG to write, usable with `truffle image palette`
+    #[arg(long, default_value = "palette.png")]
+    pub out: PathBuf,
+
+    /// Preview the palette without writing it
+    #[arg(long)]
+    pub dry_run: bool,
+
+    /// Overwrite an existing palette
+    #[arg(long)]
+    pub force: bool,
+}
+
+fn hex(color: [u8; 3]) -> String {
+    format!("#{:02x}{:02x}{:02x}", color[0], color[1], color[2])
+}
+
+fn extract(args: &PaletteExtractArgs) -> Result<(), String> {
+    if !args.image_path.is_file() {
+        return Err(format!(
+            "Image does not exist: {}",
+            args.image_path.display()
+        ));
+    }
+    if args.out.exists() && !args.force && !args.dry_run {
+        return Err(format!(
+            "{} already exists (use --force to overwrite)",
+            args.out.display()
+        ));
+    }
+
+    let image = image::open(&args.image_path)
+        .map_err(|e| format!("Failed to read image {}: {}", args.image_path.display(), e))?
+        .to_rgba8();
+    let colors = palette::extract_palette(&image, args.colors as usize);
+    if colors.is_empty() {
+        return Err(format!(
+            "Image has no visible pixels: {}",
+            args.image_path.display()
+        ));
+    }
+
+    println!(
+        "[palette-extract] {} color(s): {}",
+        colors.len(),
+        colors.iter().map(|c| hex(*c)).collect::<Vec<_>>().join(" ")
+    );
+    if args.dry_run {
+        println!(
+            "[palette-extract] DRY-RUN: Would write {}",
+            args.out.display()
+        );
+        return Ok(());
+    }
+
+    if let Some(parent) = args.out.parent().filter(|p| *p != Path::new("")) {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+    }
+    palette::write_palette(&colors, &args.out)?;
+    println!("[palette-extract] ✅ Wrote: {}", args.out.display());
+    Ok(())
+}
+
+pub fn run(args: PaletteExtractArgs) -> bool {
+    match extract(&args) {
+        Ok(()) => true,
+        Err(e) => {
+            eprintln!("[palette-extract] ERROR: {}", e);
+            false
+        }
+    }
+}
@@ -0,0 +1,215 @@
+use crate::image::psd;
+use clap::Parser;
+use regex::Regex;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+#[derive(Parser)]
+#[command(about = "Flatten PSD files or export their layer groups to PNG")]
+pub struct PsdArgs {
+    /// Input path (file or directory)
+    #[arg(value_name = "INPUT_PATH")]
+    pub input_path: PathBuf,
+
+    /// Export layer groups whose name matches this regex as `name/<group>.png` instead of flattening
+    #[arg(long, value_name = "PATTERN")]
+    pub groups: Option<String>,
+
+    /// Preview what would be generated without creating files
+    #[arg(long)]
+    pub dry_run: bool,
+
+    /// Overwrite PNGs that are newer than their PSD source
+    #[arg(long)]
+    pub force: bool,
+
+    /// Recursively process directories
+    #[arg(short, long)]
+    pub recursive: bool,
+}
+
+fn is_psd(path: &Path) -> bool {
+    path.extension().and_then(|s| s.to_str()) == Some("psd")
+}
+
+fn is_up_to_date(source: &Path, output: &Path) -> bool {
+    let modified = |path: &Path| path.metadata().and_then(|m| m.modified()).ok();
+    match (modified(source), modified(output)) {
+        (Some(source), Some(output)) => output >= source,
+        _ => false,
+    }
+}
+
+/// Turn a layer group name into a file name, replacing path separators and
+/// characters that are invalid on common filesystems.
+fn group_file_name(name: &str) -> String {
+    let cleaned: String = name
+        .trim()
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            c => c,
+        })
+        .collect();
+    format!("{}.png", cleaned)
+}
+
+fn write_png(image: &image::RgbaImage, path: &Path) -> Result<(), String> {
+    image
+        .save(path)
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+fn process_file(
+    source: &Path,
+    groups: Option<&Regex>,
+    dry_run: bool,
+    force: bool,
+) -> Result<bool, String> {
+    let stem = source
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or_default();
+
+    let Some(pattern) = groups else {
+        let output = source.with_file_name(format!("{}.png", stem));
+        if !force && is_up_to_date(source, &output) {
+            println!("[psd] SKIP: {} (PNG is up to date)", output.display());
+            return Ok(false);
+        }
+        if dry_run {
+            println!("[psd] DRY-RUN: Would generate {}", output.display());
+            return Ok(true);
+        }
+
+        println!("[psd] Processing: {}", source.display());
+        let document = psd::read_psd(source)?;
+        write_png(&document.composite, &output)?;
+        println!("[psd] ✅ Generated: {}", output.display());
+        return Ok(true);
+    };
+
+    let output_dir = source.with_file_name(stem);
+    let document = psd::read_psd(source)?;
+    let matching: Vec<_> = document
+        .groups
+        .iter()
+        .filter(|group| pattern.is_match(&group.name))
+        .collect();
+    if matching.is_empty() {
+        println!(
+            "[psd] SKIP: {} (no layer groups match {})",
+            source.display(),
+            pattern
+        );
+        return Ok(false);
+    }
+
+    let mut generated = false;
+    for group in matching {
+        let output = output_dir.join(group_file_name(&group.name));
+        if !force && is_up_to_date(source, &output) {
+            println!("[psd] SKIP: {} (PNG is up to date)", output.display());
+            continue;
+        }
+        if dry_run {
+            println!("[psd] DRY-RUN: Would generate {}", output.display());
+            generated = true;
+            continue;
+        }
+
+        std::fs::create_dir_all(&output_dir)
+            .map_err(|e| format!("Failed to create {}: {}", output_dir.display(), e))?;
+        write_png(&document.render_group(group), &output)?;
+        println!("[psd] ✅ Generated: {}", output.display());
+        generated = true;
+    }
+
+    Ok(generated)
+}
+
+fn process_path(
+    path: &Path,
+    groups: Option<&Regex>,
+    dry_run: bool,
+    force: bool,
+    recursive: bool,
+) -> Result<(usize, usize, usize), String> {
+    let mut processed = 0;
+    let mut skipped = 0;
+    let mut errors = 0;
+
+    if !path.exists() {
+        return Err(format!("Path does not exist: {}", path.display()));
+    }
+
+    let files: Vec<PathBuf> = if path.is_file() {
+        if !is_psd(path) {
+            return Err(format!("Input must be a PSD file: {}", path.display()));
+        }
+        vec![path.to_path_buf()]
+    } else {
+        let max_depth = if recursive { usize::MAX } else { 1 };
+        WalkDir::new(path)
+            .max_depth(max_depth)
+            .into_iter()
+            .filter_map(|e| e.ok())
+            .filter(|e| e.file_type().is_file())
+            .map(|e| e.path().to_path_buf())
+            .filter(|p| is_psd(p))
+            .collect()
+    };
+
+    if files.is_empty() {
+        println!("[psd] No PSD files found in: {}", path.display());
+        return Ok((0, 0, 0));
+    }
+
+    println!("[psd] Found {} PSD file(s) to process", files.len());
+
+    for file in &files {
+        match process_file(file, groups, dry_run, force) {
+            Ok(true) => processed += 1,
+            Ok(false) => skipped += 1,
+            Err(e) => {
+                eprintln!("[psd] ERROR: {}", e);
+                errors += 1;
+            }
+        }
+    }
+
+    if dry_run {
+        println!("[psd] DRY-RUN: Would process {} file(s)", processed);
+    } else {
+        println!(
+            "[psd] Done ✅ Processed: {}, Skipped: {}, Errors: {}",
+            processed, skipped, errors
+        );
+    }
+
+    Ok((processed, skipped, errors))
+}
+
+pub fn run(args: PsdArgs) -> bool {
+    let groups = match args.groups.as_deref().map(Regex::new).transpose() {
+        Ok(groups) => groups,
+        Err(e) => {
+            eprintln!("[psd] ERROR: Invalid group pattern: {}", e);
+            return false;
+        }
+    };
+
+    match process_path(
+        &args.input_path,
+        groups.as_ref(),
+        args.dry_run,
+        args.force,
+        args.recursive,
+    ) {
+        Ok((_, _, errors)) => errors == 0,
+        Err(e) => {
+            eprintln!("[psd] ERROR: {}", e);
+            false
+        }
+    }
+}
@@ -0,0 +1,265 @@
+use crate::cache::{ContentCache, RemoteCache};
+use crate::image::svg;
+use clap::Parser;
+use resvg::usvg::Options;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+#[derive(Parser)]
+#[command(about = "Rasterize SVG images to PNG at one or more scales")]
+pub struct SvgArgs {
+    /// Input path (file or directory)
+    #[arg(value_name = "INPUT_PATH")]
+    pub input_path: PathBuf,
+
+    /// Preview what would be generated without creating files
+    #[arg(long)]
+    pub dry_run: bool,
+
+    /// Overwrite PNGs that are newer than their SVG source
+    #[arg(long)]
+    pub force: bool,
+
+    /// Scale factor to rasterize at (repeatable; 1 writes `name.png`, N writes `name@Nx.png`)
+    #[arg(long = "scale", default_values_t = vec![1])]
+    pub scales: Vec<u32>,
+
+    /// Recursively process directories
+    #[arg(short, long)]
+    pub recursive: bool,
+
+    /// Write PNGs under this directory, mirroring the input layout, instead of next to their sources
+    #[arg(long)]
+    pub output_dir: Option<PathBuf>,
+
+    /// Reuse rasterized PNGs from this content-addressed cache directory
+    #[arg(long)]
+    pub cache_dir: Option<PathBuf>,
+
+    /// Shared store backing `cache_dir` (set by sync from truffle.toml)
+    #[arg(skip)]
+    pub remote_cache: Option<RemoteCache>,
+}
+
+/// Where rasterized PNGs go and how they're named.
+struct RasterNaming<'a> {
+    input_root: &'a Path,
+    output_dir: Option<&'a Path>,
+}
+
+impl RasterNaming<'_> {
+    fn raster_path(&self, svg_path: &Path, scale: u32) -> PathBuf {
+        let stem = svg_path
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .unwrap_or_default();
+        let file_name = if scale == 1 {
+            format!("{}.png", stem)
+        } else {
+            format!("{}@{}x.png", stem, scale)
+        };
+
+        let mut path = match self.output_dir {
+            Some(dir) => dir.join(
+                svg_path
+                    .strip_prefix(self.input_root)
+                    .ok()
+                    .filter(|relative| !relative.as_os_str().is_empty())
+                    .unwrap_or(svg_path.file_name().unwrap_or_default().as_ref()),
+            ),
+            None => svg_path.to_path_buf(),
+        };
+        path.set_file_name(file_name);
+        path
+    }
+}
+
+fn is_up_to_date(svg_path: &Path, raster_path: &Path) -> bool {
+    let modified = |path: &Path| path.metadata().and_then(|m| m.modified()).ok();
+    match (modified(svg_path), modified(raster_path)) {
+        (Some(source), Some(raster)) => raster >= source,
+        _ => false,
+    }
+}
+
+fn process_image(
+    svg_path: &Path,
+    raster_path: &Path,
+    scale: u32,
+    dry_run: bool,
+    force: bool,
+    options: &Options,
+    cache: Option<&ContentCache>,
+) -> Result<bool, String> {
+    if !force && is_up_to_date(svg_path, raster_path) {
+        println!("[svg] SKIP: {} (PNG is up to date)", raster_path.display());
+        return Ok(false);
+    }
+
+    if dry_run {
+        println!("[svg] DRY-RUN: Would generate {}", raster_path.display());
+        return Ok(true);
+    }
+
+    let cache_key = cache.and_then(|_| {
+        let source = std::fs::read(svg_path).ok()?;
+        Some(ContentCache::key("svg", &[&source, &scale.to_le_bytes()]))
+    });
+    let outputs = [("raster.png", raster_path)];
+    if let (Some(cache), Some(key)) = (cache, &cache_key) {
+        if cache.restore(key, &outputs) {
+            println!("[svg] ✅ Restored from cache: {}", raster_path.display());
+            return Ok(true);
+        }
+    }
+
+    println!("[svg] Processing: {} ({}x)", svg_path.display(), scale);
+    if let Some(parent) = raster_path.parent() {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+    }
+    svg::rasterize_svg(svg_path, raster_path, scale, options).map_err(|e| {
+        eprintln!("[svg] ERROR: {}", e);
+        e
+    })?;
+    if let (Some(cache), Some(key)) = (cache, &cache_key) {
+        if let Err(e) = cache.store(key, &outputs) {
+            println!("[svg] WARN: {:#}", e);
+        }
+    }
+
+    println!("[svg] ✅ Generated: {}", raster_path.display());
+    Ok(true)
+}
+
+fn process_path(
+    path: &Path,
+    naming: &RasterNaming,
+    scales: &[u32],
+    dry_run: bool,
+    force: bool,
+    recursive: bool,
+    cache: Option<&ContentCache>,
+) -> Result<(usize, usize, usize), String> {
+    let mut processed = 0;
+    let mut skipped = 0;
+    let mut errors = 0;
+
+    if !path.exists() {
+        return Err(format!("Path does not exist: {}", path.display()));
+    }
+
+    let is_svg = |p: &Path| p.extension().and_then(|s| s.to_str()) == Some("svg");
+
+    let svg_files: Vec<PathBuf> = if path.is_file() {
+        if !is_svg(path) {
+            return Err(format!("Input must be an SVG file: {}", path.display()));
+        }
+        vec![path.to_path_buf()]
+    } else {
+        let max_depth = if recursive { usize::MAX } else { 1 };
+        WalkDir::new(path)
+            .max_depth(max_depth)
+            .into_iter()
+            .filter_map(|e| e.ok())
+            .filter(|e| e.file_type().is_file())
+            .map(|e| e.path().to_path_buf())
+            .filter(|p| is_svg(p))
+            .collect()
+    };
+
+    if svg_files.is_empty() {
+        println!("[svg] No SVG files found in: {}", path.display());
+        return Ok((0, 0, 0));
+    }
+
+    println!("[svg] Found {} SVG file(s) to process", svg_files.len());
+
+    let options = svg::svg_options();
+    for file in &svg_files {
+        for &scale in scales {
+            let raster_path = naming.raster_path(file, scale);
+            match process_image(file, &raster_path, scale, dry_run, force, &options, cache) {
+                Ok(true) => processed += 1,
+                Ok(false) => skipped += 1,
+                Err(_) => errors += 1,
+            }
+        }
+    }
+
+    if dry_run {
+        println!("[svg] DRY-RUN: Would process {} file(s)", processed);
+    } else {
+        println!(
+            "[svg] Done ✅ Processed: {}, Skipped: {}, Errors: {}",
+            processed, skipped, errors
+        );
+    }
+
+    Ok((processed, skipped, errors))
+}
+
+pub fn run(args: SvgArgs) -> bool {
+    if args.scales.iter().any(|&scale| scale < 1) {
+        eprintln!("[svg] ERROR: Scale must be >= 1");
+        return false;
+    }
+
+    let mut scales = args.scales.clone();
+    scales.sort_unstable();
+    scales.dedup();
+
+    let naming = RasterNaming {
+        input_root: &args.input_path,
+        output_dir: args.output_dir.as_deref(),
+    };
+    match process_path(
+        &args.input_path,
+        &naming,
+        &scales,
+        args.dry_run,
+        args.force,
+        args.recursive,
+        args.cache_dir
+            .map(|dir| ContentCache::new(dir).with_remote(args.remote_cache))
+            .as_ref(),
+    ) {
+        Ok((_, _, errors)) => errors == 0,
+        Err(e) => {
+            eprintln!("[svg] ERROR: {}", e);
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn raster_paths_mirror_the_input_under_output_dir() {
+        let root = Path::new("assets/images");
+        let svg = root.join("ui/icon.svg");
+        let siblings = RasterNaming {
+            input_root: root,
+            output_dir: None,
+        };
+        assert_eq!(siblings.raster_path(&svg, 1), root.join("ui/icon.png"));
+        assert_eq!(siblings.raster_path(&svg, 2), root.join("ui/icon@2x.png"));
+
+        let scratch = Path::new(".truffle/generated");
+        let generated = RasterNaming {
+            input_root: root,
+            output_dir: Some(scratch),
+        };
+        assert_eq!(
+            generated.raster_path(&svg, 2),
+            scratch.join("ui/icon@2x.png")
+        );
+        let single = RasterNaming {
+            input_root: &svg,
+            ..generated
+        };
+        assert_eq!(single.raster_path(&svg, 1), scratch.join("icon.png"));
+    }
+}
@@ -1,308 +1,2279 @@
+use crate::assets::augment::ImageMetadataReader;
+use crate::assets::model::{set_asset_type, AssetValue};
 use crate::assets::{
-    augment_assets, build_atlased_assets, build_atlases, load_assets, render_dts_module,
-    render_luau_module, AtlasExclude, AtlasOptions, FsImageMetadata,
+    atlas_report, augment_assets, build_animations, build_atlased_assets, build_atlases,
+    check_template, enabled_augmenters, load_assets, merge_locale_variants, merge_scale_variants,
+    merge_surface_sets, merge_theme_variants, module_comments, non_image_keys, render_dts_module,
+    render_luau_module, render_split_modules, render_ts_module, returned_fields, run_augmenters,
+    split_module_names, sprite_keys_outside_size, style_keys, surface_texture_keys, AlphaCoverage,
+    AnimationOptions, AtlasExclude, AtlasOptions, Augmenter, CachedImageMetadata, DownscaleLimits,
+    EntryComments, FsImageMetadata, GeneratedImageMetadata, ImageColors, KeyOrder, LocaleOptions,
+    NineSlices, PackingAlgorithm, PrepackedAtlas, RenderOptions, ThemeOptions, FLIPBOOK_DTS,
+    FLIPBOOK_LUAU,
 };
-use crate::commands::image::HighlightArgs;
+use crate::cache::{ContentCache, RemoteCache};
+use crate::commands::font::FontArgs;
+use crate::commands::image::{
+    AsepriteArgs, AsepriteMode, BufferArgs, GifArgs, HighlightArgs, PsdArgs, SvgArgs,
+};
+use crate::commands::output::OutputOptions;
+use crate::report::{asset_ids, SyncReport};
+use crate::since::{ChangeScope, ContentHashes};
+use crate::watch::FileWatcher;
 use anyhow::Context;
 use asphalt::{
     cli::{SyncArgs as AsphaltSyncArgs, SyncTarget},
-    config::{Config as AsphaltConfig, Input as AsphaltInput},
+    config::{Config as AsphaltConfig, Input as AsphaltInput, InputAssetType},
     glob::Glob,
-    sync, sync_with_config,
+    hash::Hash,
+    lockfile::{self, Lockfile, RawLockfile},
+    sync, sync_with_config, DownscaleRule, ProgressCallback, ProgressEvent,
 };
 use clap::Parser;
-use indicatif::MultiProgress;
+use image::codecs::png::CompressionType;
+use indicatif::{HumanBytes, MultiProgress};
 use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fs;
 use std::path::PathBuf;
+use std::sync::Arc;
 use tokio::runtime::Runtime;
-use truffle_config::TruffleConfig;
+use truffle_config::{
+    AsepriteExport, AtlasCompression, AtlasPacker, FontOptions, ImageRoot, TruffleConfig,
+    TruffleOptions,
+};
+
+/// Cache entry holding an atlas file name → asset id map.
+const ATLAS_MANIFEST: &str = "atlas-ids.json";
+
+/// Content hashes of the synced files as of the last successful sync.
+const CONTENT_HASHES: &str = "content-hashes.json";
+
+/// Files whose edits make the next sync cover everything.
+pub(crate) const STATE_FILES: [&str; 2] = [truffle_config::FILE_NAME, lockfile::FILE_NAME];
+
+/// Per-page packing statistics, written when `atlas_report` is set.
+const ATLAS_REPORT: &str = "atlas-report.json";
+
+/// Asphalt input the atlas pages are uploaded through.
+const ATLAS_INPUT: &str = "atlases";
 
-#[derive(Parser)]
+/// Upload journal inside the scratch dir.
+const JOURNAL_FILE: &str = "sync-journal.jsonl";
+
+/// Asphalt input (and scratch subdirectory) for generated variants kept out
+/// of the images folder.
+const GENERATED_INPUT: &str = "generated";
+
+/// Asphalt input (and scratch subdirectory) for the font atlases configured
+/// under `[fonts]`.
+const FONT_INPUT: &str = "fonts";
+
+#[derive(Parser, Clone)]
 #[command(about = "Sync assets and augment metadata with image dimensions")]
 pub struct SyncArgs {
     /// Path to the Luau assets module file
     #[arg(long, default_value = "src/shared/data/assets/assets.luau")]
     pub assets_input: PathBuf,
 
-    /// Path to write the augmented Luau assets module
-    #[arg(long, default_value = "src/shared/data/assets/assets.luau")]
-    pub assets_output: PathBuf,
+    /// Path to write the augmented Luau assets module
+    #[arg(long, default_value = "src/shared/data/assets/assets.luau")]
+    pub assets_output: PathBuf,
+
+    /// Path to write the TypeScript declaration file
+    #[arg(long, default_value = "src/shared/data/assets/assets.d.ts")]
+    pub dts_output: PathBuf,
+
+    /// Path to the raw assets images folder
+    #[arg(long, default_value = "assets/images")]
+    pub images_folder: PathBuf,
+
+    /// Images folder whose keys go under PREFIX, as `PREFIX=PATH` (repeatable;
+    /// replaces --images-folder and `image_roots` in truffle.toml)
+    #[arg(long = "image-root", value_name = "PREFIX=PATH", value_parser = parse_image_root)]
+    pub image_roots: Vec<ImageRoot>,
+
+    /// Pack images into atlas textures before syncing
+    #[arg(long)]
+    pub atlas: bool,
+
+    /// Atlas texture size (power-of-two square)
+    #[arg(long)]
+    pub atlas_size: Option<u32>,
+
+    /// Padding (in pixels) around each sprite in the atlas, filled with its
+    /// extruded edge pixels
+    #[arg(long)]
+    pub atlas_padding: Option<u32>,
+
+    /// Image keys to exclude from atlas packing (repeatable)
+    #[arg(long)]
+    pub atlas_exclude: Vec<String>,
+
+    /// Write outputs without syncing to Roblox
+    #[arg(long)]
+    pub dry_run: bool,
+
+    /// Regenerate the modules in memory and fail with a diff if the files on
+    /// disk are out of date, like `truffle verify`
+    #[arg(long, conflicts_with_all = ["watch", "workspace"])]
+    pub check: bool,
+
+    /// Scratch directory for intermediate/generated files
+    #[arg(long)]
+    pub scratch_dir: Option<PathBuf>,
+
+    /// Only process and upload assets changed since this git ref (plus their
+    /// highlight variants and the atlases they land in)
+    #[arg(long, value_name = "REF")]
+    pub since: Option<String>,
+
+    /// Only process and upload images whose key (relative to the images
+    /// folder) matches this glob, keeping the rest of the module as it is
+    #[arg(long, value_name = "GLOB")]
+    pub filter: Option<String>,
+
+    /// Keep running and re-sync the images that change
+    #[arg(long, conflicts_with = "workspace")]
+    pub watch: bool,
+
+    /// Process every image instead of only those whose content changed since
+    /// the last sync
+    #[arg(long)]
+    pub full: bool,
+
+    /// Directory for the content-addressed cache (defaults to `<scratch_dir>/cache`)
+    #[arg(long)]
+    pub cache_dir: Option<PathBuf>,
+
+    /// Sync every member project listed in truffle-workspace.toml
+    #[arg(long)]
+    pub workspace: bool,
+
+    /// Write a combined JSON asset report for all workspace members
+    #[arg(long, value_name = "PATH", requires = "workspace")]
+    pub workspace_report: Option<PathBuf>,
+
+    /// Write a JSON summary of added, changed and removed assets
+    #[arg(long, value_name = "PATH")]
+    pub report: Option<PathBuf>,
+
+    /// Write the same summary as Markdown, e.g. to post as a PR comment
+    #[arg(long, value_name = "PATH")]
+    pub report_md: Option<PathBuf>,
+
+    /// Maximum number of uploads in flight at once
+    #[arg(long)]
+    pub upload_concurrency: Option<usize>,
+
+    /// Maximum number of Open Cloud requests per minute
+    #[arg(long, value_name = "REQUESTS_PER_MINUTE")]
+    pub rate_limit: Option<u32>,
+
+    /// Robux you are willing to spend per upload if uploads are not free;
+    /// dry runs estimate the total from it
+    #[arg(long, value_name = "ROBUX")]
+    pub expected_price: Option<u32>,
+
+    /// TRUFFLE_API_KEY environment variable (or read from .env file)
+    #[arg(long)]
+    pub api_key: Option<String>,
+
+    /// Asset ids uploaded by other workspace members, reused for identical files
+    #[arg(skip)]
+    pub shared_lockfile: Option<Lockfile>,
+
+    /// Files changed since the last sync in watch mode; scopes the sync like `--since`
+    #[arg(skip)]
+    pub changes: Option<ChangeScope>,
+}
+
+pub fn run(args: SyncArgs) -> bool {
+    if args.check {
+        return crate::commands::verify::check(args);
+    }
+
+    let rt = Runtime::new().expect("Failed to create tokio runtime");
+
+    rt.block_on(async {
+        let result = if args.workspace {
+            crate::workspace::sync_workspace(args, &FsImageMetadata).await
+        } else if args.watch {
+            watch(args).await
+        } else {
+            run_async(args).await
+        };
+        match result {
+            Ok(()) => true,
+            Err(e) => {
+                eprintln!("[sync] ERROR: {}", e);
+                false
+            }
+        }
+    })
+}
+
+async fn run_async(args: SyncArgs) -> anyhow::Result<()> {
+    // Load truffle.toml config
+    let config = TruffleConfig::read()
+        .await
+        .context("Failed to read truffle.toml. Make sure it exists in the current directory.")?;
+
+    let sizes = image_sizes_path(&args, &config);
+    let images = CachedImageMetadata::load(&sizes);
+    let result = run_with_config(args, &config, &images).await;
+    save_image_sizes(&images, &sizes);
+    result
+}
+
+/// Where image sizes are kept between syncs, so unchanged images aren't
+/// decoded again.
+fn image_sizes_path(args: &SyncArgs, config: &TruffleConfig) -> PathBuf {
+    args.scratch_dir
+        .clone()
+        .unwrap_or_else(|| config.truffle.scratch_dir.clone())
+        .join("image-sizes.json")
+}
+
+fn save_image_sizes(images: &CachedImageMetadata, path: &std::path::Path) {
+    if let Err(e) = images.save(path) {
+        eprintln!("[sync] WARN: Failed to save image sizes: {:#}", e);
+    }
+}
+
+/// Runs a full sync, then re-syncs each debounced batch of changed files
+/// until interrupted. Image sizes stay cached between runs.
+///
+/// Changed images are synced incrementally. Edits to the assets module (other
+/// than the sync's own writes) and to truffle.toml trigger a full sync; the
+/// config is reloaded first.
+async fn watch(args: SyncArgs) -> anyhow::Result<()> {
+    let mut config = TruffleConfig::read()
+        .await
+        .context("Failed to read truffle.toml. Make sure it exists in the current directory.")?;
+    let sizes = image_sizes_path(&args, &config);
+    let images = CachedImageMetadata::load(&sizes);
+
+    if let Err(e) = run_with_config(args.clone(), &config, &images).await {
+        eprintln!("[sync] ERROR: {}", e);
+    }
+    save_image_sizes(&images, &sizes);
+    let mut written = fs::read(&args.assets_output).ok();
+
+    let config_path = PathBuf::from(truffle_config::FILE_NAME);
+    let module = crate::watch::normalize(&args.assets_input);
+    let (mut watcher, mut folders) = sync_watcher(&args, &config)?;
+
+    loop {
+        let mut files = tokio::task::block_in_place(|| watcher.next_batch())?;
+        let config_changed = files.remove(&config_path);
+        let module_changed = files.remove(&module) && fs::read(&args.assets_input).ok() != written;
+        files.retain(|file| {
+            folders
+                .iter()
+                .any(|folder| file.starts_with(crate::watch::normalize(folder)))
+        });
+        if !config_changed && !module_changed && files.is_empty() {
+            continue;
+        }
+
+        if config_changed {
+            println!("[sync] {} changed, reloading …", truffle_config::FILE_NAME);
+            match TruffleConfig::read().await {
+                Ok(reloaded) => {
+                    config = reloaded;
+                    (watcher, folders) = sync_watcher(&args, &config)?;
+                }
+                Err(e) => eprintln!(
+                    "[sync] ERROR: Failed to reload {}: {:#}",
+                    truffle_config::FILE_NAME,
+                    e
+                ),
+            }
+        }
+
+        let mut batch_args = args.clone();
+        if config_changed || module_changed {
+            println!("[sync] Running a full sync …");
+        } else {
+            println!("[sync] {} file(s) changed", files.len());
+            batch_args.changes = Some(ChangeScope::from_files(files, &args.images_folder));
+        }
+        if let Err(e) = run_with_config(batch_args, &config, &images).await {
+            eprintln!("[sync] ERROR: {}", e);
+        }
+        save_image_sizes(&images, &sizes);
+        written = fs::read(&args.assets_output).ok();
+    }
+}
+
+/// Watches the images folders of `config`, the assets module and
+/// truffle.toml. Returns the watcher and the images folders.
+fn sync_watcher(
+    args: &SyncArgs,
+    config: &TruffleConfig,
+) -> anyhow::Result<(FileWatcher, Vec<PathBuf>)> {
+    let scratch_dir = args
+        .scratch_dir
+        .clone()
+        .unwrap_or_else(|| config.truffle.scratch_dir.clone());
+    let roots = image_roots(args, config);
+    let folders: Vec<PathBuf> = if roots.is_empty() {
+        vec![args.images_folder.clone()]
+    } else {
+        roots.iter().map(|root| root.path.clone()).collect()
+    };
+
+    let mut watcher = FileWatcher::new(&folders, &watch_ignores(args, scratch_dir))?;
+    watcher.watch_files(&[
+        args.assets_input.clone(),
+        PathBuf::from(truffle_config::FILE_NAME),
+    ])?;
+    println!(
+        "[sync] Watching {}, {} and {} for changes …",
+        folders
+            .iter()
+            .map(|folder| folder.display().to_string())
+            .collect::<Vec<_>>()
+            .join(", "),
+        args.assets_input.display(),
+        truffle_config::FILE_NAME
+    );
+    Ok((watcher, folders))
+}
+
+/// Paths a watcher for `args` skips: the scratch dir and the generated
+/// modules. The module is rewritten by every sync; when it is also the input,
+/// its events are kept and compared with what the sync wrote instead.
+fn watch_ignores(args: &SyncArgs, scratch_dir: PathBuf) -> Vec<PathBuf> {
+    let mut ignore = vec![scratch_dir, args.dts_output.clone()];
+    if args.assets_output != args.assets_input {
+        ignore.push(args.assets_output.clone());
+    }
+    ignore
+}
+
+/// Watches everything [`track_content`] would hash, for the daemon to scope
+/// syncs by instead. Returns the watcher and the folders whose changes scope
+/// a sync, or `None` when the content can't be tracked.
+pub(crate) fn content_watcher(
+    args: &SyncArgs,
+    config: &TruffleConfig,
+) -> anyhow::Result<Option<(FileWatcher, Vec<PathBuf>)>> {
+    let Some(folders) = tracked_folders(args, config) else {
+        return Ok(None);
+    };
+    let scratch_dir = args
+        .scratch_dir
+        .clone()
+        .unwrap_or_else(|| config.truffle.scratch_dir.clone());
+    let mut watcher = FileWatcher::new(&folders, &watch_ignores(args, scratch_dir))?;
+    let mut files: Vec<PathBuf> = STATE_FILES.iter().map(PathBuf::from).collect();
+    files.push(args.assets_input.clone());
+    watcher.watch_files(&files)?;
+    Ok(Some((watcher, folders)))
+}
+
+/// Runs a sync with an already loaded config, reading image sizes through
+/// `reader` (the daemon passes a cache that outlives a single sync).
+pub async fn run_with_config(
+    mut args: SyncArgs,
+    config: &TruffleConfig,
+    reader: &dyn ImageMetadataReader,
+) -> anyhow::Result<()> {
+    let dry_run = args.dry_run;
+    let tracked = track_content(&mut args, config)?;
+    let result = sync_project(args, config, reader).await;
+    if let (Some(tracked), Ok(_)) = (&tracked, &result) {
+        if let Err(e) = tracked.record() {
+            eprintln!("[sync] WARN: Failed to record content hashes: {:#}", e);
+        }
+    }
+    if let Some(notify) = &config.truffle.notify {
+        crate::notify::send(notify, dry_run, &result).await;
+    }
+    result.map(|_| ())
+}
+
+/// Content hashes taken before a sync, recorded once it succeeds.
+struct TrackedContent {
+    path: PathBuf,
+    hashes: ContentHashes,
+}
+
+impl TrackedContent {
+    fn record(&self) -> anyhow::Result<()> {
+        self.hashes.save(&self.path)
+    }
+}
+
+/// Scopes a sync that isn't scoped already to the files whose bytes changed
+/// since the last successful sync, so unchanged images are neither processed
+/// nor uploaded. The images folders and the folders of the Asphalt inputs are
+/// tracked. Edits to truffle.toml or the lockfile, `--full` and a
+/// missing record sync everything. Returns the hashes read here, to record
+/// after the sync, so edits made while it runs show up in the next one;
+/// scoped and dry-run syncs record nothing, as they may skip changes.
+fn track_content(
+    args: &mut SyncArgs,
+    config: &TruffleConfig,
+) -> anyhow::Result<Option<TrackedContent>> {
+    if args.dry_run || args.since.is_some() || args.filter.is_some() || args.changes.is_some() {
+        return Ok(None);
+    }
+
+    let scratch_dir = args
+        .scratch_dir
+        .clone()
+        .unwrap_or_else(|| config.truffle.scratch_dir.clone());
+    let Some(mut paths) = tracked_folders(args, config) else {
+        return Ok(None);
+    };
+    paths.extend(STATE_FILES.iter().map(PathBuf::from));
+    let tracked = TrackedContent {
+        path: scratch_dir.join(CONTENT_HASHES),
+        hashes: ContentHashes::scan(&paths)?,
+    };
+
+    if args.full {
+        return Ok(Some(tracked));
+    }
+    let Some(previous) = ContentHashes::load(&tracked.path) else {
+        return Ok(Some(tracked));
+    };
+    let current = &tracked.hashes;
+    if let Some(file) = STATE_FILES
+        .iter()
+        .find(|file| !current.unchanged(&previous, std::path::Path::new(file)))
+    {
+        println!(
+            "[sync] {} changed since the last sync; syncing everything",
+            file
+        );
+        return Ok(Some(tracked));
+    }
+
+    let files = current.changed_since(&previous);
+    println!("[sync] {} file(s) changed since the last sync", files.len());
+    args.changes = Some(ChangeScope::from_files(files, &args.images_folder));
+    Ok(Some(tracked))
+}
+
+/// The images folders and the folders of the Asphalt inputs, whose changes
+/// scope a sync. `None` when an input is rooted at the project directory, as
+/// tracking it would mean hashing the whole project.
+fn tracked_folders(args: &SyncArgs, config: &TruffleConfig) -> Option<Vec<PathBuf>> {
+    let roots = image_roots(args, config);
+    let mut paths: Vec<PathBuf> = if roots.is_empty() {
+        vec![args.images_folder.clone()]
+    } else {
+        roots.iter().map(|root| root.path.clone()).collect()
+    };
+    // Other inputs (audio, models, …) are scoped too, so they are tracked as
+    // well.
+    for input in config.asphalt.inputs.values() {
+        let prefix = input.include.get_prefix();
+        if prefix.as_os_str().is_empty() || prefix == std::path::Path::new(".") {
+            return None;
+        }
+        paths.push(prefix);
+    }
+    paths.sort();
+    paths.dedup_by(|path, parent| path.starts_with(parent));
+    Some(paths)
+}
+
+async fn sync_project(
+    args: SyncArgs,
+    config: &TruffleConfig,
+    reader: &dyn ImageMetadataReader,
+) -> anyhow::Result<Option<SyncReport>> {
+    let settings = SyncSettings::resolve(&args, config)?;
+    let roots = image_roots(&args, config);
+    if !roots.is_empty() {
+        return sync_roots(args, config, &settings, &roots, reader).await;
+    }
+    let SyncSettings {
+        scratch_dir,
+        cache_dir,
+        remote_cache,
+    } = &settings;
+    let cache = settings.cache();
+
+    let generated_dir = generated_dir(&config.truffle, scratch_dir);
+    let reader = GeneratedImageMetadata {
+        inner: reader,
+        images_folder: &args.images_folder,
+        generated_dir: generated_dir.as_deref(),
+    };
+    let reader: &dyn ImageMetadataReader = &reader;
+
+    let scope = change_scope(&args)?;
+
+    // Remember the current ids so the report can tell what this sync changed
+    let previous_ids = if wants_report(&args, config) {
+        load_assets(&args.assets_output)
+            .map(|assets| asset_ids(&assets))
+            .unwrap_or_default()
+    } else {
+        BTreeMap::new()
+    };
+
+    prepare_images(
+        &args,
+        config,
+        scope.as_ref(),
+        generated_dir.as_deref(),
+        cache_dir,
+        remote_cache,
+        reader,
+    )?;
+    generate_fonts(config, scratch_dir, cache_dir)?;
+
+    if let Some(player) = &config.truffle.animation_player {
+        println!("[sync] Writing flipbook player …");
+        write_animation_player(player)?;
+    }
+
+    let atlas_enabled = args.atlas || config.truffle.atlas;
+    if atlas_enabled {
+        println!("[sync] Building image atlases …");
+        let atlas = build_project_atlases(&args, config, scratch_dir, &cache)?;
+
+        let pages: HashSet<&String> = atlas
+            .placements
+            .values()
+            .map(|p| &p.atlas_file_name)
+            .collect();
+        crate::policy::report(&crate::policy::check_atlas_pages(
+            pages.len(),
+            &config.truffle.policy,
+        ))?;
+
+        std::fs::create_dir_all(&atlas.codegen_dir).ok();
+        let previous_atlas_ids = load_assets(&atlas.codegen_output())
+            .map(|assets| atlas_file_ids_from_assets(&assets))
+            .unwrap_or_default();
+
+        // Repack pages that hold a changed sprite; the rest keep their ids.
+        let only = scope.as_ref().map(|scope| {
+            let pages: HashSet<&String> = atlas
+                .placements
+                .iter()
+                .filter(|(key, _)| scope.contains_image(key))
+                .map(|(_, placement)| &placement.atlas_file_name)
+                .collect();
+            scope.upload_files(
+                &args.images_folder,
+                pages
+                    .into_iter()
+                    .map(|page| atlas.dir.join(page))
+                    .chain(generated_files(scope, generated_dir.as_deref()))
+                    .chain(font_files(scratch_dir)),
+            )
+        });
+
+        let mut estimated_cost = None;
+        if args.dry_run {
+            let mut inputs = atlas_inputs(
+                &args,
+                config,
+                &atlas,
+                generated_dir.as_ref(),
+                &config.asphalt.inputs,
+            )?;
+            insert_font_input(config, scratch_dir, &mut inputs)?;
+            estimated_cost = summarize_dry_run(&args, &inputs, only.as_ref(), Some(&atlas.dir))?;
+        } else {
+            let multi_progress = MultiProgress::new();
+            let sync_args = settings.upload_args(&args, config, only, &multi_progress)?;
+
+            let mut asphalt_config = AsphaltConfig::read_from(PathBuf::from("."))
+                .await
+                .context("Failed to read Asphalt config from truffle.toml")?;
+
+            // Ensure atlas file names are preserved as keys.
+            asphalt_config.codegen.strip_extensions = false;
+            asphalt_config.inputs = atlas_inputs(
+                &args,
+                config,
+                &atlas,
+                generated_dir.as_ref(),
+                &asphalt_config.inputs,
+            )?;
+            insert_font_input(config, scratch_dir, &mut asphalt_config.inputs)?;
+
+            // Run Asphalt sync on the generated atlas PNGs
+            println!("[sync] Running backend sync …");
+            sync_with_config(asphalt_config, sync_args, multi_progress)
+                .await
+                .context("Failed to sync atlases with Asphalt")?;
+        }
+
+        let lockfile = if args.dry_run {
+            Some(read_lockfile()?)
+        } else {
+            None
+        };
+        let atlas_ids = resolve_atlas_ids(&atlas, &cache, lockfile.as_ref(), !args.dry_run)?;
+        write_font_modules(config, scratch_dir, cache_dir)?;
+        let final_assets = atlased_assets(&args, config, scratch_dir, &atlas, &atlas_ids, reader)?;
+        let modules = render_modules(final_assets, &args.images_folder, config)?;
+        write_modules(&args, &modules)?;
+
+        let mut report = sync_report(&args, config, &previous_ids, &modules.assets);
+        if let Some(report) = &mut report {
+            report.diff_atlases(&previous_atlas_ids, &atlas_ids);
+            report.estimated_cost = estimated_cost;
+            report.write(args.report.as_deref(), args.report_md.as_deref())?;
+        }
+
+        println!("[sync] Done");
+        return Ok(report);
+    }
+
+    let only = scope.as_ref().map(|scope| {
+        scope.upload_files(
+            &args.images_folder,
+            generated_files(scope, generated_dir.as_deref()).chain(font_files(scratch_dir)),
+        )
+    });
+
+    if args.dry_run {
+        println!("[sync] Dry-run: skipping backend sync …");
+        let mut inputs = config.asphalt.inputs.clone();
+        insert_scratch_inputs(&args, config, scratch_dir, &[], &mut inputs)?;
+        let estimated_cost = summarize_dry_run(&args, &inputs, only.as_ref(), None)?;
+        write_font_modules(config, scratch_dir, cache_dir)?;
+
+        println!("[sync] Augmenting with image dimensions …");
+        let assets = load_source_assets(&args, config, scratch_dir)?;
+        let mut augmented_assets =
+            augment_assets(&assets, &args.images_folder, reader, &config.truffle);
+        set_asset_type(
+            &mut augmented_assets,
+            input_asset_type(config, &args.images_folder).map(|ty| ty.name()),
+        );
+        let modules = render_modules(augmented_assets, &args.images_folder, config)?;
+        write_modules(&args, &modules)?;
+
+        let mut report = sync_report(&args, config, &previous_ids, &modules.assets);
+        if let Some(report) = &mut report {
+            report.estimated_cost = estimated_cost;
+            report.write(args.report.as_deref(), args.report_md.as_deref())?;
+        }
+
+        println!("[sync] Done");
+        return Ok(report);
+    }
+
+    upload_inputs(&args, config, &settings, &[], only).await?;
+    write_font_modules(config, scratch_dir, cache_dir)?;
+
+    // Augment with image dimensions
+    println!("[sync] Augmenting with image dimensions …");
+    let assets = load_source_assets(&args, config, scratch_dir)?;
+
+    let mut augmented_assets =
+        augment_assets(&assets, &args.images_folder, reader, &config.truffle);
+    set_asset_type(
+        &mut augmented_assets,
+        input_asset_type(config, &args.images_folder).map(|ty| ty.name()),
+    );
+    let modules = render_modules(augmented_assets, &args.images_folder, config)?;
+    write_modules(&args, &modules)?;
+
+    let report = sync_report(&args, config, &previous_ids, &modules.assets);
+    if let Some(report) = &report {
+        report.write(args.report.as_deref(), args.report_md.as_deref())?;
+    }
+
+    println!("[sync] Done");
+    Ok(report)
+}
+
+/// Syncs several images folders in one run. Each root is prepared on its own
+/// and uploaded through its Asphalt input; its keys are nested under its
+/// prefix in the combined modules.
+async fn sync_roots(
+    args: SyncArgs,
+    config: &TruffleConfig,
+    settings: &SyncSettings,
+    roots: &[ImageRoot],
+    reader: &dyn ImageMetadataReader,
+) -> anyhow::Result<Option<SyncReport>> {
+    if args.atlas || config.truffle.atlas {
+        anyhow::bail!("Atlas packing does not support image_roots yet");
+    }
+    let SyncSettings {
+        scratch_dir,
+        cache_dir,
+        remote_cache,
+    } = settings;
+
+    let previous_ids = if wants_report(&args, config) {
+        load_assets(&args.assets_output)
+            .map(|assets| asset_ids(&assets))
+            .unwrap_or_default()
+    } else {
+        BTreeMap::new()
+    };
+
+    generate_fonts(config, scratch_dir, cache_dir)?;
+    let mut only: Option<HashSet<PathBuf>> = None;
+    for root in roots {
+        println!(
+            "[sync] Preparing {} as {} …",
+            root.path.display(),
+            root.prefix
+        );
+        root_module(config, root)?;
+        let mut root_args = args.clone();
+        root_args.images_folder = root.path.clone();
+        let scope = change_scope(&root_args)?;
+        let generated_dir = root_generated_dir(config, scratch_dir, root);
+        let reader = GeneratedImageMetadata {
+            inner: reader,
+            images_folder: &root.path,
+            generated_dir: generated_dir.as_deref(),
+        };
+
+        prepare_images(
+            &root_args,
+            config,
+            scope.as_ref(),
+            generated_dir.as_deref(),
+            cache_dir,
+            remote_cache,
+            &reader,
+        )?;
+        if let Some(scope) = &scope {
+            only.get_or_insert_with(HashSet::new)
+                .extend(scope.upload_files(
+                    &root.path,
+                    generated_files(scope, generated_dir.as_deref()).chain(font_files(scratch_dir)),
+                ));
+        }
+    }
+
+    if let Some(player) = &config.truffle.animation_player {
+        println!("[sync] Writing flipbook player …");
+        write_animation_player(player)?;
+    }
+
+    let mut estimated_cost = None;
+    if args.dry_run {
+        println!("[sync] Dry-run: skipping backend sync …");
+        let mut inputs = config.asphalt.inputs.clone();
+        insert_scratch_inputs(&args, config, scratch_dir, roots, &mut inputs)?;
+        estimated_cost = summarize_dry_run(&args, &inputs, only.as_ref(), None)?;
+    } else {
+        upload_inputs(&args, config, settings, roots, only).await?;
+    }
+    write_font_modules(config, scratch_dir, cache_dir)?;
+
+    println!("[sync] Augmenting with image dimensions …");
+    let modules = render_roots(roots, config, scratch_dir, reader)?;
+    write_modules(&args, &modules)?;
+
+    let mut report = sync_report(&args, config, &previous_ids, &modules.assets);
+    if let Some(report) = &mut report {
+        report.estimated_cost = estimated_cost;
+        report.write(args.report.as_deref(), args.report_md.as_deref())?;
+    }
+
+    println!("[sync] Done");
+    Ok(report)
+}
+
+/// Runs the exporters, highlight generation and buffer export over the images
+/// folder of `args`, then checks the asset policy, before anything is
+/// uploaded.
+fn prepare_images(
+    args: &SyncArgs,
+    config: &TruffleConfig,
+    scope: Option<&ChangeScope>,
+    generated_dir: Option<&std::path::Path>,
+    cache_dir: &std::path::Path,
+    remote_cache: &Option<RemoteCache>,
+    reader: &dyn ImageMetadataReader,
+) -> anyhow::Result<()> {
+    // Export Aseprite sources first so highlights and atlases pick up the PNGs
+    if let Some(export) = config.truffle.aseprite_export {
+        println!("[sync] Exporting Aseprite files …");
+        let aseprite_args = AsepriteArgs {
+            input_path: args.images_folder.clone(),
+            mode: match export {
+                AsepriteExport::Flatten => AsepriteMode::Flatten,
+                AsepriteExport::Tags => AsepriteMode::Tags,
+            },
+            dry_run: false,
+            force: false,
+            recursive: true,
+        };
+        if !crate::commands::image::run(crate::commands::image::ImageCommands::Aseprite(
+            aseprite_args,
+        )) {
+            anyhow::bail!("Failed to export Aseprite files");
+        }
+    }
+
+    // Split animated GIFs into frames with a timeline
+    if config.truffle.gif_export {
+        println!("[sync] Exporting GIF files …");
+        let gif_args = GifArgs {
+            input_path: args.images_folder.clone(),
+            dry_run: false,
+            force: false,
+            recursive: true,
+        };
+        if !crate::commands::image::run(crate::commands::image::ImageCommands::Gif(gif_args)) {
+            anyhow::bail!("Failed to export GIF files");
+        }
+    }
+
+    // Flatten PSD sources (or export matching layer groups)
+    if config.truffle.psd_export {
+        println!("[sync] Exporting PSD files …");
+        let psd_args = PsdArgs {
+            input_path: args.images_folder.clone(),
+            groups: config.truffle.psd_groups.clone(),
+            dry_run: false,
+            force: false,
+            recursive: true,
+        };
+        if !crate::commands::image::run(crate::commands::image::ImageCommands::Psd(psd_args)) {
+            anyhow::bail!("Failed to export PSD files");
+        }
+    }
+
+    // Rasterize SVG sources so highlights and atlases pick up the PNGs
+    if !config.truffle.svg_scales.is_empty() {
+        println!("[sync] Rasterizing SVG images …");
+        let svg_args = SvgArgs {
+            input_path: args.images_folder.clone(),
+            dry_run: false,
+            force: false,
+            scales: config.truffle.svg_scales.clone(),
+            recursive: true,
+            output_dir: generated_dir
+                .filter(|_| config.truffle.svg_in_scratch)
+                .map(PathBuf::from),
+            cache_dir: Some(cache_dir.to_path_buf()),
+            remote_cache: remote_cache.clone(),
+        };
+        if !crate::commands::image::run(crate::commands::image::ImageCommands::Svg(svg_args)) {
+            anyhow::bail!("Failed to rasterize SVG images");
+        }
+    }
+
+    // Auto-generate highlights if configured (before sync so they get synced too)
+    if config.truffle.auto_highlight {
+        println!("[sync] Generating highlight variants …");
+        // With --since, only changed images get their highlights regenerated.
+        let inputs = match scope {
+            Some(scope) => scope
+                .image_keys
+                .iter()
+                .filter(|key| crate::image::highlight::highlight_key(key).is_some())
+                .map(|key| {
+                    let output_dir = generated_dir.map(|dir| {
+                        dir.join(std::path::Path::new(key).parent().unwrap_or("".as_ref()))
+                    });
+                    (args.images_folder.join(key), output_dir)
+                })
+                .filter(|(path, _)| path.is_file())
+                .collect(),
+            None => vec![(args.images_folder.clone(), generated_dir.map(PathBuf::from))],
+        };
+        for (input_path, output_dir) in inputs {
+            let highlight_args = HighlightArgs {
+                input_path,
+                thickness: config.truffle.highlight_thickness,
+                output: OutputOptions {
+                    output_dir,
+                    recursive: true,
+                    force: config.truffle.highlight_force,
+                    ..Default::default()
+                },
+                cache_dir: Some(cache_dir.to_path_buf()),
+                remote_cache: remote_cache.clone(),
+            };
+            crate::commands::image::run(crate::commands::image::ImageCommands::Highlight(
+                highlight_args,
+            ));
+        }
+    }
+
+    // Export raw pixel modules for images used with EditableImage
+    if !config.truffle.editable_images.is_empty() {
+        println!("[sync] Exporting EditableImage buffers …");
+        let buffer_args = BufferArgs {
+            input_path: args.images_folder.clone(),
+            output: config.truffle.editable_images_output.clone(),
+            include: config.truffle.editable_images.clone(),
+            dry_run: false,
+            force: false,
+            recursive: true,
+        };
+        if !crate::commands::image::run(crate::commands::image::ImageCommands::Buffer(buffer_args))
+        {
+            anyhow::bail!("Failed to export EditableImage buffers");
+        }
+    }
+
+    // Enforce asset policy before anything is uploaded
+    let violations =
+        crate::policy::check_images(&args.images_folder, &config.truffle.policy, reader)?;
+    crate::policy::report(&violations)?;
+    Ok(())
+}
+
+/// Renders the generated modules the way a sync would write them, without
+/// uploading anything. Atlas pages are still packed into the scratch dir, but
+/// their ids come from the last sync.
+pub fn generate_modules(
+    args: &SyncArgs,
+    config: &TruffleConfig,
+    reader: &dyn ImageMetadataReader,
+) -> anyhow::Result<GeneratedModules> {
+    let settings = SyncSettings::resolve(args, config)?;
+    let (scratch_dir, cache) = (&settings.scratch_dir, settings.cache());
+
+    let roots = image_roots(args, config);
+    if !roots.is_empty() {
+        if args.atlas || config.truffle.atlas {
+            anyhow::bail!("Atlas packing does not support image_roots yet");
+        }
+        return render_roots(&roots, config, scratch_dir, reader);
+    }
+
+    let generated_dir = generated_dir(&config.truffle, scratch_dir);
+    let reader = GeneratedImageMetadata {
+        inner: reader,
+        images_folder: &args.images_folder,
+        generated_dir: generated_dir.as_deref(),
+    };
+    let reader: &dyn ImageMetadataReader = &reader;
+
+    let assets = if args.atlas || config.truffle.atlas {
+        let atlas = build_project_atlases(args, config, scratch_dir, &cache)?;
+        let lockfile = read_lockfile()?;
+        let atlas_ids = resolve_atlas_ids(&atlas, &cache, Some(&lockfile), false)?;
+        atlased_assets(args, config, scratch_dir, &atlas, &atlas_ids, reader)?
+    } else {
+        let assets = load_source_assets(args, config, scratch_dir)?;
+        let mut assets = augment_assets(&assets, &args.images_folder, reader, &config.truffle);
+        set_asset_type(
+            &mut assets,
+            input_asset_type(config, &args.images_folder).map(|ty| ty.name()),
+        );
+        assets
+    };
+    render_modules(assets, &args.images_folder, config)
+}
+
+/// The augmented assets tree and the modules rendered from it.
+pub struct GeneratedModules {
+    pub assets: BTreeMap<String, AssetValue>,
+    animations: BTreeMap<String, crate::assets::animations::Animation>,
+    render: RenderOptions,
+    split: bool,
+    ts_module: Option<PathBuf>,
+}
+
+impl GeneratedModules {
+    /// The files to write for the Luau module at `luau_path` and its
+    /// declaration at `dts_path`: both of them, the module of each top-level
+    /// folder next to them when `codegen.split` is set, and the
+    /// `codegen.typescript_module` if configured.
+    pub fn files(
+        &self,
+        luau_path: &std::path::Path,
+        dts_path: &std::path::Path,
+    ) -> Vec<(PathBuf, String)> {
+        let mut files = self.module_files(luau_path, dts_path);
+        if let Some(path) = &self.ts_module {
+            files.push((
+                path.clone(),
+                render_ts_module(&self.assets, &self.animations, &self.render),
+            ));
+        }
+        files
+    }
+
+    fn module_files(
+        &self,
+        luau_path: &std::path::Path,
+        dts_path: &std::path::Path,
+    ) -> Vec<(PathBuf, String)> {
+        // Hand-written fields next to `assets` in the returned table and
+        // comments above entries survive the rewrite.
+        let previous = fs::read_to_string(luau_path).unwrap_or_default();
+        let render = RenderOptions {
+            returns: returned_fields(&previous),
+            comments: previous_comments(luau_path, &previous),
+            ..self.render.clone()
+        };
+        if !self.split {
+            return vec![
+                (
+                    luau_path.to_path_buf(),
+                    render_luau_module(&self.assets, &self.animations, &render),
+                ),
+                (
+                    dts_path.to_path_buf(),
+                    render_dts_module(&self.assets, &self.animations, &render),
+                ),
+            ];
+        }
+
+        let index_name = luau_path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let (luau, dts, modules) =
+            render_split_modules(&self.assets, &self.animations, &render, &index_name);
+        let mut files = vec![
+            (luau_path.to_path_buf(), luau),
+            (dts_path.to_path_buf(), dts),
+        ];
+        for module in modules {
+            files.push((split_module_path(luau_path, &module.name), module.luau));
+            files.push((
+                dts_path.with_file_name(format!("{}.d.ts", module.name)),
+                module.dts,
+            ));
+        }
+        files
+    }
+
+    /// Writes the modules, removing the split modules of folders that are
+    /// gone since the index was last written.
+    pub fn write(
+        &self,
+        luau_path: &std::path::Path,
+        dts_path: &std::path::Path,
+    ) -> anyhow::Result<()> {
+        let previous = fs::read_to_string(luau_path)
+            .map(|index| split_module_names(&index))
+            .unwrap_or_default();
+        let files = self.files(luau_path, dts_path);
+        for name in previous {
+            let luau = split_module_path(luau_path, &name);
+            if files.iter().all(|(path, _)| *path != luau) {
+                fs::remove_file(&luau).ok();
+                fs::remove_file(dts_path.with_file_name(format!("{}.d.ts", name))).ok();
+            }
+        }
+
+        for (path, contents) in &files {
+            fs::write(path, contents)
+                .with_context(|| format!("Failed to write {}", path.display()))?;
+        }
+        Ok(())
+    }
+}
+
+/// The comments of the module at `luau_path`, whose contents are `index`,
+/// including those of its split modules.
+fn previous_comments(luau_path: &std::path::Path, index: &str) -> EntryComments {
+    let mut comments = module_comments(index);
+    for name in split_module_names(index) {
+        let Ok(module) = fs::read_to_string(split_module_path(luau_path, &name)) else {
+            continue;
+        };
+        let children = module_comments(&module).children;
+        if !children.is_empty() {
+            comments.children.entry(name).or_default().children = children;
+        }
+    }
+    comments
+}
+
+/// `<name>.luau` next to the index at `luau_path`.
+fn split_module_path(luau_path: &std::path::Path, name: &str) -> PathBuf {
+    luau_path.with_file_name(match luau_path.extension() {
+        Some(extension) => format!("{}.{}", name, extension.to_string_lossy()),
+        None => name.to_string(),
+    })
+}
+
+/// Applies nine-slice sidecars, merges configured variants, builds animations
+/// and renders both modules.
+pub(crate) fn render_modules(
+    mut assets: BTreeMap<String, AssetValue>,
+    images_folder: &std::path::Path,
+    config: &TruffleConfig,
+) -> anyhow::Result<GeneratedModules> {
+    let options = &config.truffle;
+    run_layout_augmenters(&mut assets, images_folder, "", options);
+    merge_configured_variants(&mut assets, options);
+    let animations = configured_animations(&assets, images_folder, options);
+    finish_modules(assets, animations, config)
+}
+
+/// Runs the enabled stages that need the final sizes, after atlas rects are
+/// known: nine-slice sidecars, image colors, alpha coverage and downscaled
+/// sizes.
+fn run_layout_augmenters(
+    assets: &mut BTreeMap<String, AssetValue>,
+    images_folder: &std::path::Path,
+    prefix: &str,
+    options: &TruffleOptions,
+) {
+    let limits = DownscaleLimits::new(options);
+    let mut stages: Vec<&dyn Augmenter> = vec![&NineSlices, &ImageColors, &AlphaCoverage];
+    if let Some(limits) = &limits {
+        stages.push(limits);
+    }
+    run_augmenters(
+        assets,
+        images_folder,
+        prefix,
+        &enabled_augmenters(&stages, options),
+    );
+}
+
+/// Augments each root's module and nests it under the root's prefix, then
+/// renders the combined modules. Variants and animations are resolved per
+/// root, so highlights and sequences pair up within their own folder.
+fn render_roots(
+    roots: &[ImageRoot],
+    config: &TruffleConfig,
+    scratch_dir: &std::path::Path,
+    reader: &dyn ImageMetadataReader,
+) -> anyhow::Result<GeneratedModules> {
+    let mut assets = BTreeMap::new();
+    let mut animations = BTreeMap::new();
+    for root in roots {
+        let generated_dir = root_generated_dir(config, scratch_dir, root);
+        let reader = GeneratedImageMetadata {
+            inner: reader,
+            images_folder: &root.path,
+            generated_dir: generated_dir.as_deref(),
+        };
+        let reader: &dyn ImageMetadataReader = &reader;
+
+        let generated_module = generated_dir
+            .is_some()
+            .then(|| generated_module(scratch_dir, &root_generated_input(root)));
+        let root_assets = load_module(&root_module(config, root)?, generated_module.as_deref())?;
+        let mut root_assets = augment_assets(&root_assets, &root.path, reader, &config.truffle);
+        set_asset_type(
+            &mut root_assets,
+            input_asset_type(config, &root.path).map(|ty| ty.name()),
+        );
+        run_layout_augmenters(&mut root_assets, &root.path, &root.prefix, &config.truffle);
+        merge_configured_variants(&mut root_assets, &config.truffle);
+        for (name, animation) in configured_animations(&root_assets, &root.path, &config.truffle) {
+            animations.insert(prefixed_key(&root.prefix, &name), animation);
+        }
+        merge_asset_values(&mut assets, &nest_under_prefix(root_assets, &root.prefix));
+    }
+    finish_modules(assets, animations, config)
+}
+
+/// Prepares the modules of the final assets tree, with its keys named in
+/// the configured `codegen.key_style`.
+fn finish_modules(
+    assets: BTreeMap<String, AssetValue>,
+    animations: BTreeMap<String, crate::assets::animations::Animation>,
+    config: &TruffleConfig,
+) -> anyhow::Result<GeneratedModules> {
+    Ok(GeneratedModules {
+        assets: style_keys(&assets, config.asphalt.codegen.key_style),
+        animations,
+        render: render_options(config)?,
+        split: config.asphalt.codegen.split,
+        ts_module: config.asphalt.codegen.typescript_module.clone(),
+    })
+}
+
+fn write_modules(args: &SyncArgs, modules: &GeneratedModules) -> anyhow::Result<()> {
+    println!("[sync] Writing augmented Luau module and TypeScript declaration …");
+    modules.write(&args.assets_output, &args.dts_output)
+}
+
+/// The scratch dir and content cache for runs that upload nothing.
+/// Where a sync keeps its intermediates, resolved from the options and
+/// truffle.toml the same way by every sync path.
+struct SyncSettings {
+    scratch_dir: PathBuf,
+    cache_dir: PathBuf,
+    remote_cache: Option<RemoteCache>,
+}
+
+impl SyncSettings {
+    fn resolve(args: &SyncArgs, config: &TruffleConfig) -> anyhow::Result<Self> {
+        let scratch_dir = args
+            .scratch_dir
+            .clone()
+            .unwrap_or_else(|| config.truffle.scratch_dir.clone());
+        let remote_cache = config
+            .truffle
+            .remote_cache
+            .as_ref()
+            .map(RemoteCache::from_options)
+            .transpose()
+            .context("Invalid remote cache configuration")?;
+        let cache_dir = args
+            .cache_dir
+            .clone()
+            .unwrap_or_else(|| scratch_dir.join("cache"));
+        Ok(Self {
+            scratch_dir,
+            cache_dir,
+            remote_cache,
+        })
+    }
+
+    /// The content cache, backed by the remote cache when one is configured.
+    fn cache(&self) -> ContentCache {
+        ContentCache::new(self.cache_dir.clone()).with_remote(self.remote_cache.clone())
+    }
+
+    /// Options for uploading through Asphalt, limited to `only` when set.
+    fn upload_args(
+        &self,
+        args: &SyncArgs,
+        config: &TruffleConfig,
+        only: Option<HashSet<PathBuf>>,
+        multi_progress: &MultiProgress,
+    ) -> anyhow::Result<AsphaltSyncArgs> {
+        // Resolve API key (TRUFFLE_API_KEY instead of ASPHALT_API_KEY)
+        let api_key = resolve_api_key(args.api_key.clone())?;
+        Ok(AsphaltSyncArgs {
+            api_key: Some(api_key),
+            target: Some(SyncTarget::Cloud { dry_run: false }),
+            expected_price: args.expected_price,
+            upload_concurrency: args.upload_concurrency,
+            rate_limit: args.rate_limit,
+            project: PathBuf::from("."),
+            only,
+            shared_lockfile: args.shared_lockfile.clone(),
+            progress: Some(upload_progress(multi_progress)),
+            downscale: downscale_rule(args, config, &self.scratch_dir),
+            journal: Some(sync_journal(&self.scratch_dir)),
+        })
+    }
+}
+
+/// Uploads the project's inputs, plus what the sync keeps in the scratch
+/// dir (see [`insert_scratch_inputs`]), limited to `only` when set.
+async fn upload_inputs(
+    args: &SyncArgs,
+    config: &TruffleConfig,
+    settings: &SyncSettings,
+    roots: &[ImageRoot],
+    only: Option<HashSet<PathBuf>>,
+) -> anyhow::Result<()> {
+    let multi_progress = MultiProgress::new();
+    let sync_args = settings.upload_args(args, config, only, &multi_progress)?;
+    println!("[sync] Running backend sync …");
+    let scratch_dir = &settings.scratch_dir;
+    let synced =
+        if generated_dir(&config.truffle, scratch_dir).is_some() || !config.fonts.is_empty() {
+            let mut asphalt_config = AsphaltConfig::read_from(PathBuf::from("."))
+                .await
+                .context("Failed to read Asphalt config from truffle.toml")?;
+            insert_scratch_inputs(args, config, scratch_dir, roots, &mut asphalt_config.inputs)?;
+            sync_with_config(asphalt_config, sync_args, multi_progress).await
+        } else {
+            sync(sync_args, multi_progress).await
+        };
+    synced.context("Failed to sync assets with Asphalt")
+}
+
+/// Adds the Asphalt inputs for files a sync writes to the scratch dir: the
+/// generated variants of the images folder (or of each of `roots`) and the
+/// font atlases.
+fn insert_scratch_inputs(
+    args: &SyncArgs,
+    config: &TruffleConfig,
+    scratch_dir: &std::path::Path,
+    roots: &[ImageRoot],
+    inputs: &mut HashMap<String, AsphaltInput>,
+) -> anyhow::Result<()> {
+    let codegen_dir = scratch_dir.join("asphalt");
+    if roots.is_empty() {
+        if let Some(dir) = generated_dir(&config.truffle, scratch_dir) {
+            inputs.insert(
+                GENERATED_INPUT.to_string(),
+                generated_input(
+                    &format!("{}/**/*.png", dir.display()),
+                    &codegen_dir,
+                    input_asset_type(config, &args.images_folder),
+                )?,
+            );
+        }
+    }
+    for root in roots {
+        if let Some(dir) = root_generated_dir(config, scratch_dir, root) {
+            inputs.insert(
+                root_generated_input(root),
+                generated_input(
+                    &format!("{}/**/*.png", dir.display()),
+                    &codegen_dir,
+                    input_asset_type(config, &root.path),
+                )?,
+            );
+        }
+    }
+    insert_font_input(config, scratch_dir, inputs)
+}
+
+/// Packs the project's atlases into the scratch dir exactly as an atlas sync
+/// would, without uploading. Returns the page folder and the placements.
+pub(crate) fn pack_project_atlases(
+    args: &SyncArgs,
+    config: &TruffleConfig,
+) -> anyhow::Result<(PathBuf, BTreeMap<String, crate::assets::SpritePlacement>)> {
+    let settings = SyncSettings::resolve(args, config)?;
+    if !image_roots(args, config).is_empty() {
+        anyhow::bail!("Atlas packing does not support image_roots yet");
+    }
+    let atlas = build_project_atlases(args, config, &settings.scratch_dir, &settings.cache())?;
+    Ok((atlas.dir, atlas.placements))
+}
+
+/// Atlas pages packed into the scratch dir for one sync.
+struct ProjectAtlases {
+    dir: PathBuf,
+    codegen_dir: PathBuf,
+    placements: BTreeMap<String, crate::assets::SpritePlacement>,
+    exclude: Vec<String>,
+    exclude_matcher: AtlasExclude,
+}
+
+impl ProjectAtlases {
+    /// Asphalt codegen writes `{input_name}.luau` for the atlas input.
+    fn codegen_output(&self) -> PathBuf {
+        self.codegen_dir.join(format!("{}.luau", ATLAS_INPUT))
+    }
+}
+
+fn build_project_atlases(
+    args: &SyncArgs,
+    config: &TruffleConfig,
+    scratch_dir: &std::path::Path,
+    cache: &ContentCache,
+) -> anyhow::Result<ProjectAtlases> {
+    let dir = scratch_dir.join("atlases");
+    let atlas_padding = args.atlas_padding.unwrap_or(config.truffle.atlas_padding);
+    let atlas_size = args.atlas_size.unwrap_or(config.truffle.atlas_size);
+    let mut exclude = resolve_atlas_exclude(
+        &args.atlas_exclude,
+        &config.truffle.atlas_exclude,
+        &args.images_folder,
+    );
+    // SurfaceAppearance maps need whole textures, so keep them out of atlases.
+    exclude.extend(surface_texture_keys(&args.images_folder));
+    // Audio and models next to the images are uploaded as they are.
+    exclude.extend(non_image_keys(&args.images_folder));
+    let generated = generated_dir(&config.truffle, scratch_dir);
+    let mut roots = vec![args.images_folder.as_path()];
+    roots.extend(generated.as_deref().filter(|dir| dir.is_dir()));
+    exclude.extend(
+        sprite_keys_outside_size(
+            &roots,
+            config.truffle.atlas_min_sprite_size,
+            config.truffle.atlas_max_sprite_size,
+        )
+        .context("Failed to read sprite sizes")?,
+    );
+    exclude.sort();
+    exclude.dedup();
+    let exclude_matcher = build_atlas_exclude(&exclude)?;
+
+    // Pre-packed sheets become pages of their own: they are neither repacked
+    // nor uploaded as standalone images.
+    let prepacked = config
+        .truffle
+        .atlas_prepacked
+        .iter()
+        .map(|key| PrepackedAtlas::read(&args.images_folder, key))
+        .collect::<anyhow::Result<Vec<_>>>()
+        .context("Failed to read pre-packed atlases")?;
+    let mut pack_exclude = exclude.clone();
+    pack_exclude.extend(prepacked.iter().map(|sheet| sheet.sheet_key.clone()));
+
+    let mut placements = build_atlases(
+        &args.images_folder,
+        &dir,
+        AtlasOptions {
+            padding: atlas_padding,
+            size: atlas_size,
+            height: config.truffle.atlas_height,
+            shrink: config.truffle.atlas_shrink,
+            exclude: build_atlas_exclude(&pack_exclude)?,
+            cache: Some(cache.clone()),
+            generated_dir: generated,
+            algorithm: match config.truffle.atlas_packer {
+                AtlasPacker::Shelf => PackingAlgorithm::Shelf,
+                AtlasPacker::MaxRects => PackingAlgorithm::MaxRects,
+            },
+            allow_rotation: config.truffle.atlas_rotation,
+            trim: config.truffle.atlas_trim,
+            compression: match config.truffle.atlas_compression {
+                AtlasCompression::Fast => CompressionType::Fast,
+                AtlasCompression::Default => CompressionType::Default,
+                AtlasCompression::Best => CompressionType::Best,
+            },
+            low_memory: config.truffle.atlas_low_memory,
+        },
+    )
+    .context("Failed to build atlases")?;
+    for sheet in prepacked {
+        sheet
+            .write_page(&dir)
+            .context("Failed to add pre-packed atlas")?;
+        for (key, placement) in sheet.placements {
+            if placements.insert(key.clone(), placement).is_some() {
+                println!(
+                    "[sync] WARN: {} from {} replaces the packed image with the same key.",
+                    key, sheet.sheet_key
+                );
+            }
+        }
+    }
+    report_atlas_pages(&dir, &placements, atlas_padding, config, scratch_dir)?;
+
+    Ok(ProjectAtlases {
+        dir,
+        codegen_dir: scratch_dir.join("asphalt"),
+        placements,
+        exclude,
+        exclude_matcher,
+    })
+}
+
+/// Prints fill and free space per atlas page, and writes them as JSON to the
+/// scratch dir when `atlas_report` is set.
+fn report_atlas_pages(
+    dir: &std::path::Path,
+    placements: &BTreeMap<String, crate::assets::SpritePlacement>,
+    padding: u32,
+    config: &TruffleConfig,
+    scratch_dir: &std::path::Path,
+) -> anyhow::Result<()> {
+    let pages = atlas_report(dir, placements, padding).context("Failed to read atlas pages")?;
+    for page in &pages {
+        println!(
+            "[sync] {} ({}x{}): {} sprite(s), {:.1}% filled, largest free area {}x{}",
+            page.file_name,
+            page.width,
+            page.height,
+            page.sprites,
+            page.fill_percent,
+            page.largest_free.w,
+            page.largest_free.h
+        );
+    }
+
+    if config.truffle.atlas_report {
+        let path = scratch_dir.join(ATLAS_REPORT);
+        let json = serde_json::to_string_pretty(&pages)?;
+        fs::write(&path, json).with_context(|| format!("Failed to write {}", path.display()))?;
+        println!("[sync] Wrote atlas report to {}", path.display());
+    }
+    Ok(())
+}
 
-    /// Path to write the TypeScript declaration file
-    #[arg(long, default_value = "src/shared/data/assets/assets.d.ts")]
-    pub dts_output: PathBuf,
+/// The Asphalt inputs of an atlas sync: the generated pages, the configured
+/// inputs with the images input narrowed to the excluded images, and the
+/// generated variants of those.
+fn atlas_inputs(
+    args: &SyncArgs,
+    config: &TruffleConfig,
+    atlas: &ProjectAtlases,
+    generated_dir: Option<&PathBuf>,
+    base: &HashMap<String, AsphaltInput>,
+) -> anyhow::Result<HashMap<String, AsphaltInput>> {
+    let mut inputs = HashMap::new();
+
+    let atlas_glob = format!("{}/**/*.png", atlas.dir.display());
+    inputs.insert(
+        ATLAS_INPUT.to_string(),
+        AsphaltInput {
+            include: Glob::new(atlas_glob.as_str()).context("Invalid atlas include glob")?,
+            output_path: atlas.codegen_dir.clone(),
+            bleed: false,
+            asset_type: None,
+            web: HashMap::new(),
+        },
+    );
+
+    let exclude_glob = if atlas.exclude.is_empty() {
+        None
+    } else {
+        Some(
+            build_exclude_glob(&args.images_folder, &atlas.exclude)
+                .context("Atlas exclude list was empty after normalization")?,
+        )
+    };
 
-    /// Path to the raw assets images folder
-    #[arg(long, default_value = "assets/images")]
-    pub images_folder: PathBuf,
+    let mut found_images_input = false;
+    for (name, input) in base.iter() {
+        if is_images_input(&args.images_folder, &input.include.get_prefix()) {
+            found_images_input = true;
+            if let Some(exclude_glob) = &exclude_glob {
+                let mut updated = input.clone();
+                updated.include =
+                    Glob::new(exclude_glob.as_str()).context("Invalid atlas exclude glob")?;
+                inputs.insert(name.clone(), updated);
+            }
+            continue;
+        }
 
-    /// Pack images into atlas textures before syncing
-    #[arg(long)]
-    pub atlas: bool,
+        inputs.insert(name.clone(), input.clone());
+    }
 
-    /// Atlas texture size (power-of-two square)
-    #[arg(long)]
-    pub atlas_size: Option<u32>,
+    if !atlas.exclude.is_empty() && !found_images_input {
+        anyhow::bail!("Failed to find images input matching images_folder");
+    }
 
-    /// Padding (in pixels) around each sprite in the atlas
-    #[arg(long)]
-    pub atlas_padding: Option<u32>,
+    // Variants of excluded images are uploaded whole as well
+    if let Some(glob) = generated_dir.and_then(|dir| build_exclude_glob(dir, &atlas.exclude)) {
+        inputs.insert(
+            GENERATED_INPUT.to_string(),
+            generated_input(
+                &glob,
+                &atlas.codegen_dir,
+                input_asset_type(config, &args.images_folder),
+            )?,
+        );
+    }
 
-    /// Image keys to exclude from atlas packing (repeatable)
-    #[arg(long)]
-    pub atlas_exclude: Vec<String>,
+    Ok(inputs)
+}
 
-    /// Write outputs without syncing to Roblox
-    #[arg(long)]
-    pub dry_run: bool,
+/// Loads the atlas asset ids produced by Asphalt. When there are none, falls
+/// back to the cached atlas manifest; otherwise the ids are stored in the
+/// manifest if `store` is set.
+///
+/// Runs that upload nothing (dry runs, verify) pass the `lockfile`: pages
+/// whose exact content was uploaded before take their id from it, so stale
+/// or missing codegen never replaces a real id. Pages left without an id get
+/// a placeholder.
+fn resolve_atlas_ids(
+    atlas: &ProjectAtlases,
+    cache: &ContentCache,
+    lockfile: Option<&Lockfile>,
+    store: bool,
+) -> anyhow::Result<HashMap<String, String>> {
+    let codegen_output = atlas.codegen_output();
+    let mut atlas_ids = if codegen_output.exists() {
+        let atlas_assets = load_assets(&codegen_output)
+            .map_err(|e| anyhow::anyhow!("Failed to load atlas assets: {}", e))?;
+        atlas_file_ids_from_assets(&atlas_assets)
+    } else {
+        HashMap::new()
+    };
 
-    /// Scratch directory for intermediate/generated files
-    #[arg(long)]
-    pub scratch_dir: Option<PathBuf>,
+    // The manifest maps this exact set of pages to their uploaded ids, so
+    // machines that never uploaded them can still resolve real ids.
+    let manifest_key = atlas_manifest_key(&atlas.dir, &atlas.placements)?;
+    if atlas_ids.is_empty() {
+        if let Some(ids) = cache
+            .get(&manifest_key, ATLAS_MANIFEST)
+            .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+        {
+            println!("[sync] Using atlas ids from the cached atlas manifest");
+            atlas_ids = ids;
+        }
+    } else if store {
+        cache.put(
+            &manifest_key,
+            ATLAS_MANIFEST,
+            serde_json::to_vec_pretty(&atlas_ids)?,
+        )?;
+    }
+    if let Some(lockfile) = lockfile {
+        let pages: HashSet<&String> = atlas
+            .placements
+            .values()
+            .map(|placement| &placement.atlas_file_name)
+            .collect();
+        for page in pages {
+            let path = atlas.dir.join(page);
+            let bytes = fs::read(&path)
+                .with_context(|| format!("Failed to read atlas page {}", path.display()))?;
+            if let Some(entry) = lockfile.get(ATLAS_INPUT, &Hash::new_from_bytes(&bytes)) {
+                atlas_ids.insert(page.clone(), format!("rbxassetid://{}", entry.asset_id));
+            }
+        }
+    }
+    // Pages that were never uploaded still need an id to write the modules.
+    for placement in atlas.placements.values() {
+        atlas_ids
+            .entry(placement.atlas_file_name.clone())
+            .or_insert_with(|| "rbxassetid://0".into());
+    }
+    Ok(atlas_ids)
+}
 
-    /// TRUFFLE_API_KEY environment variable (or read from .env file)
-    #[arg(long)]
-    pub api_key: Option<String>,
+/// Reads the project's lockfile, empty if there is none yet.
+fn read_lockfile() -> anyhow::Result<Lockfile> {
+    let content = match fs::read_to_string(lockfile::FILE_NAME) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Lockfile::default()),
+        Err(e) => return Err(e).context("Failed to read the lockfile"),
+    };
+    RawLockfile::parse(&content)?.into_lockfile()
 }
 
-pub fn run(args: SyncArgs) -> bool {
-    let rt = Runtime::new().expect("Failed to create tokio runtime");
+/// What a dry run would send to Roblox: the files Asphalt would upload and
+/// how many keep the id they already have.
+#[derive(Debug, Default, PartialEq)]
+struct DryRunSummary {
+    /// Project-relative files without a lockfile entry, sorted.
+    uploads: Vec<PathBuf>,
+    unchanged: usize,
+}
 
-    rt.block_on(async {
-        match run_async(args).await {
-            Ok(()) => true,
-            Err(e) => {
-                eprintln!("[sync] ERROR: {}", e);
-                false
+impl DryRunSummary {
+    /// Walks `inputs` like Asphalt does: a file is uploaded when it is in
+    /// scope (`only`) and its content is in neither lockfile. Files whose
+    /// content repeats within an input are uploaded once.
+    fn collect(
+        project_dir: &std::path::Path,
+        inputs: &HashMap<String, AsphaltInput>,
+        lockfile: &Lockfile,
+        shared_lockfile: Option<&Lockfile>,
+        only: Option<&HashSet<PathBuf>>,
+    ) -> anyhow::Result<Self> {
+        let mut summary = Self::default();
+        for (name, input) in inputs {
+            let mut seen = HashSet::new();
+            let prefix = project_dir.join(input.include.get_prefix());
+            for entry in walkdir::WalkDir::new(&prefix).into_iter().flatten() {
+                let path = entry.path();
+                let Ok(rel_path) = path.strip_prefix(project_dir) else {
+                    continue;
+                };
+                let supported = path
+                    .extension()
+                    .is_some_and(asphalt::asset::is_supported_extension);
+                if !entry.file_type().is_file() || !supported || !input.include.is_match(rel_path) {
+                    continue;
+                }
+
+                let bytes =
+                    fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+                let hash = Hash::new_from_bytes(&bytes);
+                let rel_path = crate::watch::normalize(rel_path);
+                let uploaded = lockfile.get(name, &hash).is_some()
+                    || shared_lockfile.is_some_and(|shared| shared.find(&hash).is_some());
+                let in_scope = only.is_none_or(|only| only.contains(&rel_path));
+                if uploaded || !in_scope || !seen.insert(hash) {
+                    summary.unchanged += 1;
+                } else {
+                    summary.uploads.push(rel_path);
+                }
             }
         }
-    })
+        summary.uploads.sort();
+        Ok(summary)
+    }
 }
 
-async fn run_async(args: SyncArgs) -> anyhow::Result<()> {
-    // Load truffle.toml config
-    let config = TruffleConfig::read()
-        .await
-        .context("Failed to read truffle.toml. Make sure it exists in the current directory.")?;
-
-    let scratch_dir = args
-        .scratch_dir
-        .clone()
-        .unwrap_or_else(|| config.truffle.scratch_dir.clone());
+/// Prints what a dry run would upload, listing the atlas pages under
+/// `atlas_dir` that would be rebuilt. Returns the estimated cost in Robux
+/// when `--expected-price` is set.
+fn summarize_dry_run(
+    args: &SyncArgs,
+    inputs: &HashMap<String, AsphaltInput>,
+    only: Option<&HashSet<PathBuf>>,
+    atlas_dir: Option<&PathBuf>,
+) -> anyhow::Result<Option<u64>> {
+    let lockfile = read_lockfile()?;
+    let summary = DryRunSummary::collect(
+        std::path::Path::new("."),
+        inputs,
+        &lockfile,
+        args.shared_lockfile.as_ref(),
+        only,
+    )?;
+
+    println!(
+        "[sync] Dry-run: {} asset(s) would be uploaded, {} unchanged",
+        summary.uploads.len(),
+        summary.unchanged
+    );
+    let atlas_dir = atlas_dir.map(|dir| crate::watch::normalize(dir));
+    let (pages, files): (Vec<&PathBuf>, Vec<&PathBuf>) = summary
+        .uploads
+        .iter()
+        .partition(|path| atlas_dir.as_ref().is_some_and(|dir| path.starts_with(dir)));
+    for file in files {
+        println!("[sync]   would upload {}", file.display());
+    }
+    if !pages.is_empty() {
+        println!(
+            "[sync] Dry-run: {} atlas page(s) would be rebuilt:",
+            pages.len()
+        );
+        for page in pages {
+            println!("[sync]   would upload {}", page.display());
+        }
+    }
 
-    // Auto-generate highlights if configured (before sync so they get synced too)
-    if config.truffle.auto_highlight {
-        println!("[sync] Generating highlight variants …");
-        let highlight_args = HighlightArgs {
-            input_path: args.images_folder.clone(),
-            dry_run: false,
-            force: config.truffle.highlight_force,
-            thickness: config.truffle.highlight_thickness,
-            recursive: true,
-        };
-        crate::commands::image::run(crate::commands::image::ImageCommands::Highlight(
-            highlight_args,
-        ));
+    let cost = args
+        .expected_price
+        .map(|price| u64::from(price) * summary.uploads.len() as u64);
+    if let (Some(cost), Some(price)) = (cost, args.expected_price) {
+        println!(
+            "[sync] Dry-run: estimated cost {} Robux ({} per upload)",
+            cost, price
+        );
     }
+    Ok(cost)
+}
 
-    let atlas_enabled = args.atlas || config.truffle.atlas;
-    if atlas_enabled {
-        println!("[sync] Building image atlases …");
-        let atlas_dir = scratch_dir.join("atlases");
-        let atlas_codegen_dir = scratch_dir.join("asphalt");
-        // Asphalt codegen writes `{input_name}.luau`. Our atlas input is named `atlases`.
-        let atlas_assets_output = atlas_codegen_dir.join("atlases.luau");
-        let atlas_padding = args.atlas_padding.unwrap_or(config.truffle.atlas_padding);
-        let atlas_size = args.atlas_size.unwrap_or(config.truffle.atlas_size);
-        let atlas_exclude = resolve_atlas_exclude(
-            &args.atlas_exclude,
-            &config.truffle.atlas_exclude,
+/// Builds the final assets tree keyed by original image paths, with the
+/// images kept out of atlases augmented as usual.
+fn atlased_assets(
+    args: &SyncArgs,
+    config: &TruffleConfig,
+    scratch_dir: &std::path::Path,
+    atlas: &ProjectAtlases,
+    atlas_ids: &HashMap<String, String>,
+    reader: &dyn ImageMetadataReader,
+) -> anyhow::Result<BTreeMap<String, AssetValue>> {
+    let mut final_assets = build_atlased_assets(&atlas.placements, atlas_ids)
+        .context("Failed to build atlased asset metadata")?;
+
+    if !atlas.exclude.is_empty() {
+        let excluded_assets = load_source_assets(args, config, scratch_dir)?;
+        let filtered_excluded = filter_assets_by_exclude(&excluded_assets, &atlas.exclude_matcher);
+        let mut augmented_excluded = augment_assets(
+            &filtered_excluded,
             &args.images_folder,
+            reader,
+            &config.truffle,
         );
-        let atlas_exclude_matcher = build_atlas_exclude(&atlas_exclude)?;
+        set_asset_type(
+            &mut augmented_excluded,
+            input_asset_type(config, &args.images_folder).map(|ty| ty.name()),
+        );
+        merge_asset_values(&mut final_assets, &augmented_excluded);
+    }
+    Ok(final_assets)
+}
 
-        let placements = build_atlases(
-            &args.images_folder,
-            &atlas_dir,
-            AtlasOptions {
-                padding: atlas_padding,
-                size: atlas_size,
-                exclude: atlas_exclude_matcher.clone(),
-            },
-        )
-        .context("Failed to build atlases")?;
+/// Caps oversized images in the images folder (or each root) and their
+/// generated variants before they are uploaded.
+fn downscale_rule(
+    args: &SyncArgs,
+    config: &TruffleConfig,
+    scratch_dir: &std::path::Path,
+) -> Option<DownscaleRule> {
+    let limits = DownscaleLimits::new(&config.truffle)?;
+    let roots = image_roots(args, config);
+    let mut folders = Vec::new();
+    if roots.is_empty() {
+        folders.push((args.images_folder.clone(), String::new()));
+        if let Some(dir) = generated_dir(&config.truffle, scratch_dir) {
+            folders.push((dir, String::new()));
+        }
+    }
+    for root in &roots {
+        folders.push((root.path.clone(), root.prefix.clone()));
+        if let Some(dir) = root_generated_dir(config, scratch_dir, root) {
+            folders.push((dir, root.prefix.clone()));
+        }
+    }
+    Some(limits.rule(folders))
+}
 
-        std::fs::create_dir_all(&atlas_codegen_dir).ok();
+/// Generates the atlases of the fonts configured under `[fonts]` into
+/// `<scratch_dir>/fonts`, so they are uploaded with the other assets. Their
+/// modules are written by [`write_font_modules`] once the ids are known.
+fn generate_fonts(
+    config: &TruffleConfig,
+    scratch_dir: &std::path::Path,
+    cache_dir: &std::path::Path,
+) -> anyhow::Result<()> {
+    if config.fonts.is_empty() {
+        return Ok(());
+    }
+    println!("[sync] Generating font atlases …");
+    let dir = scratch_dir.join(FONT_INPUT);
+    fs::create_dir_all(&dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+    for (name, font) in &config.fonts {
+        if !crate::commands::font::run(font_args(name, font, scratch_dir, cache_dir)) {
+            anyhow::bail!("Failed to generate font atlas {}", name);
+        }
+    }
+    Ok(())
+}
 
-        if !args.dry_run {
-            // Resolve API key (TRUFFLE_API_KEY instead of ASPHALT_API_KEY)
-            let api_key = resolve_api_key(args.api_key.clone())?;
+/// Writes the module of each font configured under `[fonts]` to its
+/// `output`, with the asset ids of its atlas images.
+fn write_font_modules(
+    config: &TruffleConfig,
+    scratch_dir: &std::path::Path,
+    cache_dir: &std::path::Path,
+) -> anyhow::Result<()> {
+    if config.fonts.is_empty() {
+        return Ok(());
+    }
+    println!("[sync] Writing font modules …");
+    let image_ids = font_image_ids(scratch_dir, &read_lockfile()?)?;
+    for (name, font) in &config.fonts {
+        let mut args = font_args(name, font, scratch_dir, cache_dir);
+        args.luau = Some(font.output.clone());
+        args.dts = Some(font.output.with_extension("d.ts"));
+        args.image_ids = image_ids.clone();
+        if let Some(parent) = font.output.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create {}", parent.display()))?;
+        }
+        if !crate::commands::font::run(args) {
+            anyhow::bail!("Failed to write font module {}", font.output.display());
+        }
+    }
+    Ok(())
+}
 
-            let mut asphalt_config = AsphaltConfig::read_from(PathBuf::from("."))
-                .await
-                .context("Failed to read Asphalt config from truffle.toml")?;
+/// The asset ids `lockfile` has for the font atlas images by path. Images
+/// that were never uploaded get a placeholder id.
+fn font_image_ids(
+    scratch_dir: &std::path::Path,
+    lockfile: &Lockfile,
+) -> anyhow::Result<BTreeMap<PathBuf, String>> {
+    let mut ids = BTreeMap::new();
+    for path in font_files(scratch_dir) {
+        let bytes = fs::read(&path)
+            .with_context(|| format!("Failed to read font atlas {}", path.display()))?;
+        let id = lockfile
+            .get(FONT_INPUT, &Hash::new_from_bytes(&bytes))
+            .map_or(0, |entry| entry.asset_id);
+        ids.insert(path, format!("rbxassetid://{}", id));
+    }
+    Ok(ids)
+}
 
-            // Ensure atlas file names are preserved as keys.
-            asphalt_config.codegen.strip_extensions = false;
-            asphalt_config.inputs = {
-                let mut inputs = HashMap::new();
-
-                let atlas_glob = format!("{}/**/*.png", atlas_dir.display());
-                inputs.insert(
-                    "atlases".to_string(),
-                    AsphaltInput {
-                        include: Glob::new(atlas_glob.as_str())
-                            .context("Invalid atlas include glob")?,
-                        output_path: atlas_codegen_dir.clone(),
-                        bleed: false,
-                        web: HashMap::new(),
-                    },
-                );
+/// `truffle font` arguments generating the atlas of the font configured as
+/// `name` into `<scratch_dir>/fonts/<name>.png`.
+fn font_args(
+    name: &str,
+    font: &FontOptions,
+    scratch_dir: &std::path::Path,
+    cache_dir: &std::path::Path,
+) -> FontArgs {
+    let png = scratch_dir.join(FONT_INPUT).join(format!("{}.png", name));
+    let mut args = FontArgs::parse_from(["font".as_ref(), font.input.as_os_str(), png.as_os_str()]);
+    if let Some(charset) = &font.charset {
+        args.charset = charset.clone();
+    }
+    if let Some(cell) = font.cell {
+        args.cell = cell;
+    }
+    args.px = font.size;
+    args.outline = font.outline;
+    // The outline is drawn into the cell's padding.
+    args.padding = args.padding.max(font.outline);
+    args.cache_dir = Some(cache_dir.to_path_buf());
+    args
+}
 
-                let exclude_glob = if atlas_exclude.is_empty() {
-                    None
-                } else {
-                    Some(
-                        build_exclude_glob(&args.images_folder, &atlas_exclude)
-                            .context("Atlas exclude list was empty after normalization")?,
-                    )
-                };
+/// The font atlas images in `<scratch_dir>/fonts`.
+fn font_files(scratch_dir: &std::path::Path) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(scratch_dir.join(FONT_INPUT)) else {
+        return Vec::new();
+    };
+    let mut files: Vec<PathBuf> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "png"))
+        .collect();
+    files.sort();
+    files
+}
 
-                let mut found_images_input = false;
-                for (name, input) in asphalt_config.inputs.iter() {
-                    if is_images_input(&args.images_folder, &input.include.get_prefix()) {
-                        found_images_input = true;
-                        if let Some(exclude_glob) = &exclude_glob {
-                            let mut updated = input.clone();
-                            updated.include = Glob::new(exclude_glob.as_str())
-                                .context("Invalid atlas exclude glob")?;
-                            inputs.insert(name.clone(), updated);
-                        }
-                        continue;
-                    }
+/// Adds the Asphalt input uploading the `[fonts]` atlases to `inputs`.
+fn insert_font_input(
+    config: &TruffleConfig,
+    scratch_dir: &std::path::Path,
+    inputs: &mut HashMap<String, AsphaltInput>,
+) -> anyhow::Result<()> {
+    if config.fonts.is_empty() {
+        return Ok(());
+    }
+    let glob = format!("{}/**/*.png", scratch_dir.join(FONT_INPUT).display());
+    inputs.insert(
+        FONT_INPUT.to_string(),
+        AsphaltInput {
+            include: Glob::new(glob.as_str()).context("Invalid font atlas glob")?,
+            output_path: scratch_dir.join("asphalt"),
+            bleed: false,
+            asset_type: None,
+            web: HashMap::new(),
+        },
+    );
+    Ok(())
+}
 
-                    inputs.insert(name.clone(), input.clone());
-                }
+/// Where generated variants go when `generated_in_scratch` (or `svg_in_scratch`)
+/// is set.
+fn generated_dir(options: &TruffleOptions, scratch_dir: &std::path::Path) -> Option<PathBuf> {
+    (options.generated_in_scratch || options.svg_in_scratch)
+        .then(|| scratch_dir.join(GENERATED_INPUT))
+}
 
-                if !atlas_exclude.is_empty() && !found_images_input {
-                    anyhow::bail!("Failed to find images input matching images_folder");
-                }
+/// Asphalt input uploading the generated variants matched by `glob` as the
+/// same asset type as their sources. Its codegen mirrors the images input's
+/// keys.
+fn generated_input(
+    glob: &str,
+    codegen_dir: &std::path::Path,
+    asset_type: Option<InputAssetType>,
+) -> anyhow::Result<AsphaltInput> {
+    Ok(AsphaltInput {
+        include: Glob::new(glob).context("Invalid generated variants glob")?,
+        output_path: codegen_dir.to_path_buf(),
+        bleed: true,
+        asset_type,
+        web: HashMap::new(),
+    })
+}
 
-                inputs
-            };
+/// Asset type set on the Asphalt input that uploads `images_folder`.
+fn input_asset_type(
+    config: &TruffleConfig,
+    images_folder: &std::path::Path,
+) -> Option<InputAssetType> {
+    let images_folder = images_folder.to_path_buf();
+    config
+        .asphalt
+        .inputs
+        .values()
+        .find(|input| is_images_input(&images_folder, &input.include.get_prefix()))
+        .and_then(|input| input.asset_type)
+}
 
-            // Run Asphalt sync on the generated atlas PNGs
-            println!("[sync] Running backend sync …");
-            let multi_progress = MultiProgress::new();
-            let sync_args = AsphaltSyncArgs {
-                api_key: Some(api_key),
-                target: Some(SyncTarget::Cloud { dry_run: false }),
-                expected_price: None,
-                project: PathBuf::from("."),
-            };
+/// Generated variants of the images in `scope`.
+fn generated_files<'a>(
+    scope: &'a ChangeScope,
+    generated_dir: Option<&'a std::path::Path>,
+) -> impl Iterator<Item = PathBuf> + 'a {
+    generated_dir
+        .into_iter()
+        .flat_map(|dir| scope.image_keys.iter().map(move |key| dir.join(key)))
+}
 
-            sync_with_config(asphalt_config, sync_args, multi_progress)
-                .await
-                .context("Failed to sync atlases with Asphalt")?;
-        }
+/// Loads the assets module. With a generated dir, the ids Asphalt
+/// generated for the variants in the scratch dir are merged in as if they
+/// had been synced from the images folder.
+fn load_source_assets(
+    args: &SyncArgs,
+    config: &TruffleConfig,
+    scratch_dir: &std::path::Path,
+) -> anyhow::Result<BTreeMap<String, AssetValue>> {
+    let generated = generated_dir(&config.truffle, scratch_dir)
+        .map(|_| generated_module(scratch_dir, GENERATED_INPUT));
+    load_module(&args.assets_input, generated.as_deref())
+}
 
-        // Load atlas asset ids produced by Asphalt
-        let atlas_ids = if atlas_assets_output.exists() {
-            let atlas_assets = load_assets(&atlas_assets_output)
-                .map_err(|e| anyhow::anyhow!("Failed to load atlas assets: {}", e))?;
-            atlas_file_ids_from_assets(&atlas_assets)
-        } else {
-            HashMap::new()
-        };
+/// Loads an assets module plus, if it exists, the module Asphalt generated
+/// for the variants in the scratch dir.
+fn load_module(
+    path: &std::path::Path,
+    generated: Option<&std::path::Path>,
+) -> anyhow::Result<BTreeMap<String, AssetValue>> {
+    let mut assets =
+        load_assets(path).map_err(|e| anyhow::anyhow!("Failed to load assets: {}", e))?;
+    if let Some(generated) = generated.filter(|path| path.exists()) {
+        let generated = load_assets(generated)
+            .map_err(|e| anyhow::anyhow!("Failed to load generated assets: {}", e))?;
+        merge_asset_values(&mut assets, &generated);
+    }
+    Ok(assets)
+}
 
-        let mut atlas_ids = atlas_ids;
-        if atlas_ids.is_empty() {
-            // In dry-run or missing output, fill placeholder ids so we can still write modules.
-            for placement in placements.values() {
-                atlas_ids
-                    .entry(placement.atlas_file_name.clone())
-                    .or_insert_with(|| "rbxassetid://0".into());
-            }
-        }
+/// Module Asphalt writes for a generated variants input.
+fn generated_module(scratch_dir: &std::path::Path, input: &str) -> PathBuf {
+    scratch_dir.join("asphalt").join(format!("{}.luau", input))
+}
 
-        // Build the final assets tree keyed by original image paths
-        let mut final_assets = build_atlased_assets(&placements, &atlas_ids)
-            .context("Failed to build atlased asset metadata")?;
+/// The image roots of this sync, from the command line or truffle.toml.
+/// Empty when the project syncs a single images folder.
+fn image_roots(args: &SyncArgs, config: &TruffleConfig) -> Vec<ImageRoot> {
+    if args.image_roots.is_empty() {
+        config.truffle.image_roots.clone()
+    } else {
+        args.image_roots.clone()
+    }
+}
 
-        if !atlas_exclude.is_empty() {
-            let excluded_assets = load_assets(&args.assets_input)
-                .map_err(|e| anyhow::anyhow!("Failed to load assets: {}", e))?;
-            let filtered_excluded =
-                filter_assets_by_exclude(&excluded_assets, &atlas_exclude_matcher);
-            let augmented_excluded =
-                augment_assets(&filtered_excluded, &args.images_folder, &FsImageMetadata);
-            merge_asset_values(&mut final_assets, &augmented_excluded);
-        }
+fn parse_image_root(value: &str) -> Result<ImageRoot, String> {
+    let (prefix, path) = value
+        .split_once('=')
+        .ok_or_else(|| format!("expected PREFIX=PATH, got `{}`", value))?;
+    Ok(ImageRoot {
+        path: PathBuf::from(path),
+        prefix: prefix.to_string(),
+    })
+}
 
-        println!("[sync] Writing augmented Luau module …");
-        fs::write(&args.assets_output, render_luau_module(&final_assets))
-            .context("Failed to write Luau file")?;
+/// The assets module Asphalt generates for the input that uploads `root`.
+fn root_module(config: &TruffleConfig, root: &ImageRoot) -> anyhow::Result<PathBuf> {
+    config
+        .asphalt
+        .inputs
+        .iter()
+        .find(|(_, input)| is_images_input(&root.path, &input.include.get_prefix()))
+        .map(|(name, input)| input.output_path.join(format!("{}.luau", name)))
+        .with_context(|| {
+            format!(
+                "Failed to find an Asphalt input for image root {}",
+                root.path.display()
+            )
+        })
+}
 
-        println!("[sync] Writing TypeScript declaration …");
-        fs::write(&args.dts_output, render_dts_module(&final_assets))
-            .context("Failed to write TypeScript file")?;
+/// Where `root`'s generated variants go when `generated_in_scratch` is set.
+fn root_generated_dir(
+    config: &TruffleConfig,
+    scratch_dir: &std::path::Path,
+    root: &ImageRoot,
+) -> Option<PathBuf> {
+    generated_dir(&config.truffle, scratch_dir).map(|dir| dir.join(root.prefix.trim_matches('/')))
+}
 
-        println!("[sync] Done");
-        return Ok(());
+/// Asphalt input uploading `root`'s generated variants.
+fn root_generated_input(root: &ImageRoot) -> String {
+    format!(
+        "{}-{}",
+        GENERATED_INPUT,
+        root.prefix.trim_matches('/').replace('/', "-")
+    )
+}
+
+fn prefixed_key(prefix: &str, key: &str) -> String {
+    match prefix.trim_matches('/') {
+        "" => key.to_string(),
+        prefix => format!("{}/{}", prefix, key),
     }
+}
 
-    if args.dry_run {
-        println!("[sync] Dry-run: skipping backend sync …");
+/// Wraps `assets` in one table per segment of `prefix` (`ui/hud` nests two
+/// levels deep).
+fn nest_under_prefix(
+    assets: BTreeMap<String, AssetValue>,
+    prefix: &str,
+) -> BTreeMap<String, AssetValue> {
+    prefix
+        .split('/')
+        .filter(|segment| !segment.is_empty())
+        .rev()
+        .fold(assets, |assets, segment| {
+            BTreeMap::from([(segment.to_string(), AssetValue::Table(assets))])
+        })
+}
 
-        println!("[sync] Augmenting with image dimensions …");
-        let assets = load_assets(&args.assets_input)
-            .map_err(|e| anyhow::anyhow!("Failed to load assets: {}", e))?;
-        let augmented_assets = augment_assets(&assets, &args.images_folder, &FsImageMetadata);
+/// What `--since` or a watch batch limits this sync to, narrowed by
+/// `--filter`, relative to the images folder of `args`.
+fn change_scope(args: &SyncArgs) -> anyhow::Result<Option<ChangeScope>> {
+    let scope = changed_scope(args)?;
+    let Some(filter) = &args.filter else {
+        return Ok(scope);
+    };
+    let glob = Glob::new(filter).with_context(|| format!("Invalid --filter glob: {}", filter))?;
+    let scope = match scope {
+        Some(mut scope) => {
+            scope.retain_matching(&glob, &args.images_folder);
+            scope
+        }
+        None => ChangeScope::from_filter(&glob, &args.images_folder),
+    };
+    println!(
+        "[sync] {} image(s) match --filter {}",
+        scope.image_keys.len(),
+        filter
+    );
+    Ok(Some(scope))
+}
+
+/// What `--since` or a watch batch limits this sync to.
+fn changed_scope(args: &SyncArgs) -> anyhow::Result<Option<ChangeScope>> {
+    Ok(match (&args.changes, &args.since) {
+        (Some(changes), _) => Some(ChangeScope::from_files(
+            changes.files.clone(),
+            &args.images_folder,
+        )),
+        (None, Some(since)) => {
+            let scope = ChangeScope::from_git(since, &args.images_folder)?;
+            println!(
+                "[sync] {} image(s) in scope since {}",
+                scope.image_keys.len(),
+                since
+            );
+            Some(scope)
+        }
+        (None, None) => None,
+    })
+}
 
-        println!("[sync] Writing augmented Luau module …");
-        fs::write(&args.assets_output, render_luau_module(&augmented_assets))
-            .context("Failed to write Luau file")?;
+/// Journal of in-flight uploads. A journal left behind means the last sync
+/// was interrupted; Asphalt reuses the uploads it records instead of
+/// uploading those files again.
+fn sync_journal(scratch_dir: &std::path::Path) -> PathBuf {
+    let path = scratch_dir.join(JOURNAL_FILE);
+    if path.exists() {
+        println!("[sync] Resuming interrupted sync from {}", path.display());
+    }
+    path
+}
 
-        println!("[sync] Writing TypeScript declaration …");
-        fs::write(&args.dts_output, render_dts_module(&augmented_assets))
-            .context("Failed to write TypeScript file")?;
+/// Prints each upload and failure above Asphalt's progress bar.
+fn upload_progress(multi_progress: &MultiProgress) -> ProgressCallback {
+    let multi_progress = multi_progress.clone();
+    Arc::new(move |event| {
+        let line = match event {
+            ProgressEvent::Completed {
+                path,
+                bytes,
+                new: true,
+                asset_id,
+                ..
+            } => format!(
+                "[sync] Uploaded {} ({}) → {}",
+                path.display(),
+                HumanBytes(bytes),
+                asset_id.as_deref().unwrap_or("no id")
+            ),
+            ProgressEvent::Failed { path, error, .. } => {
+                format!("[sync] FAILED {}: {}", path.display(), error)
+            }
+            _ => return,
+        };
+        multi_progress.println(line).ok();
+    })
+}
 
-        println!("[sync] Done");
-        return Ok(());
+/// Reports are built for `--report`/`--report-md` and webhook notifications.
+fn wants_report(args: &SyncArgs, config: &TruffleConfig) -> bool {
+    args.report.is_some() || args.report_md.is_some() || config.truffle.notify.is_some()
+}
+
+/// Compares the written assets to `previous`, if anything needs a report.
+fn sync_report(
+    args: &SyncArgs,
+    config: &TruffleConfig,
+    previous: &BTreeMap<String, String>,
+    assets: &BTreeMap<String, AssetValue>,
+) -> Option<SyncReport> {
+    wants_report(args, config).then(|| SyncReport::new(args.dry_run, previous, &asset_ids(assets)))
+}
+
+/// Hashes every atlas page (name and pixels) into the atlas manifest key.
+fn atlas_manifest_key(
+    atlas_dir: &std::path::Path,
+    placements: &BTreeMap<String, crate::assets::SpritePlacement>,
+) -> anyhow::Result<String> {
+    let pages: std::collections::BTreeSet<&String> =
+        placements.values().map(|p| &p.atlas_file_name).collect();
+    let mut parts = Vec::new();
+    for page in pages {
+        let path = atlas_dir.join(page);
+        parts.push(page.as_bytes().to_vec());
+        parts.push(fs::read(&path).with_context(|| format!("Failed to read {}", path.display()))?);
     }
+    let parts: Vec<&[u8]> = parts.iter().map(Vec::as_slice).collect();
+    Ok(ContentCache::key("atlas-manifest", &parts))
+}
 
-    // Run Asphalt sync
-    // Resolve API key (TRUFFLE_API_KEY instead of ASPHALT_API_KEY)
-    let api_key = resolve_api_key(args.api_key)?;
-    println!("[sync] Running backend sync …");
-    let multi_progress = MultiProgress::new();
-    let sync_args = AsphaltSyncArgs {
-        api_key: Some(api_key),
-        target: Some(SyncTarget::Cloud { dry_run: false }),
-        expected_price: None,
-        project: PathBuf::from("."),
-    };
-    sync(sync_args, multi_progress)
-        .await
-        .context("Failed to sync assets with Asphalt")?;
+/// Folds scale, locale and theme variant images into their base entries, then
+/// groups PBR texture maps into surface sets.
+pub(crate) fn merge_configured_variants(
+    assets: &mut BTreeMap<String, crate::assets::model::AssetValue>,
+    options: &TruffleOptions,
+) {
+    merge_scale_variants(assets);
+    merge_locale_variants(
+        assets,
+        &LocaleOptions {
+            locales: options.locales.clone(),
+            default_locale: options.default_locale.clone(),
+        },
+    );
+    merge_theme_variants(
+        assets,
+        &ThemeOptions {
+            themes: options.themes.clone(),
+            default_theme: options.default_theme.clone(),
+        },
+    );
+    merge_surface_sets(assets);
+}
 
-    // Augment with image dimensions
-    println!("[sync] Augmenting with image dimensions …");
-    let assets = load_assets(&args.assets_input)
-        .map_err(|e| anyhow::anyhow!("Failed to load assets: {}", e))?;
+/// Builds animation timelines when `animations` is enabled in truffle.toml.
+fn configured_animations(
+    assets: &BTreeMap<String, crate::assets::model::AssetValue>,
+    images_folder: &std::path::Path,
+    options: &TruffleOptions,
+) -> BTreeMap<String, crate::assets::animations::Animation> {
+    if !options.animations {
+        return BTreeMap::new();
+    }
 
-    let augmented_assets = augment_assets(&assets, &args.images_folder, &FsImageMetadata);
+    println!("[sync] Building animation timelines …");
+    let animations = build_animations(
+        assets,
+        images_folder,
+        &AnimationOptions {
+            frame_ms: options.animation_frame_ms,
+            key_order: key_order(options),
+        },
+    );
+    println!("[sync] Found {} animation(s)", animations.len());
+    animations
+}
 
-    println!("[sync] Writing augmented Luau module …");
-    fs::write(&args.assets_output, render_luau_module(&augmented_assets))
-        .context("Failed to write Luau file")?;
+fn key_order(options: &TruffleOptions) -> KeyOrder {
+    if options.natural_sort {
+        KeyOrder::Natural
+    } else {
+        KeyOrder::Lexical
+    }
+}
 
-    println!("[sync] Writing TypeScript declaration …");
-    fs::write(&args.dts_output, render_dts_module(&augmented_assets))
-        .context("Failed to write TypeScript file")?;
+fn render_options(config: &TruffleConfig) -> anyhow::Result<RenderOptions> {
+    let codegen = &config.asphalt.codegen;
+    Ok(RenderOptions {
+        key_order: key_order(&config.truffle),
+        luau_types: codegen.luau_types,
+        luau_template: read_template(codegen.luau_template.as_deref())?,
+        dts_template: read_template(codegen.dts_template.as_deref())?,
+        ..Default::default()
+    })
+}
 
-    println!("[sync] Done");
+/// Reads a `codegen` template, rejecting placeholders it can't fill.
+fn read_template(path: Option<&std::path::Path>) -> anyhow::Result<Option<String>> {
+    let Some(path) = path else {
+        return Ok(None);
+    };
+    let template = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read codegen template {}", path.display()))?;
+    check_template(&template)
+        .map_err(|e| anyhow::anyhow!("Invalid codegen template {}: {}", path.display(), e))?;
+    Ok(Some(template))
+}
+
+/// Writes the flipbook player module and its TypeScript declaration.
+fn write_animation_player(path: &std::path::Path) -> anyhow::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create {}", parent.display()))?;
+    }
+    fs::write(path, FLIPBOOK_LUAU)
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+
+    let dts = path.with_extension("d.ts");
+    fs::write(&dts, FLIPBOOK_DTS).with_context(|| format!("Failed to write {}", dts.display()))?;
     Ok(())
 }
 
@@ -352,7 +2323,7 @@ fn atlas_file_ids_from_assets(
     out
 }
 
-fn resolve_api_key(provided: Option<String>) -> anyhow::Result<String> {
+pub(crate) fn resolve_api_key(provided: Option<String>) -> anyhow::Result<String> {
     if let Some(key) = provided {
         return Ok(key);
     }
@@ -633,7 +2604,7 @@ fn walk_asset_values(
     }
 }
 
-fn insert_asset_value(
+pub(crate) fn insert_asset_value(
     root: &mut BTreeMap<String, crate::assets::model::AssetValue>,
     path: &[String],
     value: crate::assets::model::AssetValue,
@@ -664,3 +2635,150 @@ fn insert_asset_value(
 
     insert_asset_value(map, &path[1..], value);
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn image_roots_nest_under_their_prefix() {
+        let root = parse_image_root("ui/hud=assets/hud").unwrap();
+        assert_eq!(root.path, PathBuf::from("assets/hud"));
+        assert!(parse_image_root("assets/hud").is_err());
+
+        let assets = BTreeMap::from([("play.png".to_string(), AssetValue::Number(1.0))]);
+        let nested = nest_under_prefix(assets.clone(), &root.prefix);
+        let AssetValue::Table(ui) = &nested["ui"] else {
+            panic!("expected a table");
+        };
+        assert_eq!(ui["hud"], AssetValue::Table(assets.clone()));
+        assert_eq!(nest_under_prefix(assets.clone(), ""), assets);
+        assert_eq!(prefixed_key("ui/hud", "fx/walk"), "ui/hud/fx/walk");
+    }
+
+    #[test]
+    fn dry_runs_reuse_uploaded_atlas_ids() {
+        let dir = std::env::temp_dir().join(format!("truffle-atlas-ids-{}", std::process::id()));
+        let pages = dir.join("atlases");
+        fs::create_dir_all(&pages).unwrap();
+        fs::write(pages.join("atlas_0.png"), b"uploaded").unwrap();
+        fs::write(pages.join("atlas_1.png"), b"changed").unwrap();
+
+        let placement = |page: &str| crate::assets::SpritePlacement {
+            atlas_file_name: page.to_string(),
+            rect: crate::assets::atlas::AtlasRect {
+                x: 0,
+                y: 0,
+                w: 1,
+                h: 1,
+            },
+            rotated: false,
+            trim: None,
+        };
+        let atlas = ProjectAtlases {
+            dir: pages,
+            codegen_dir: dir.join("asphalt"),
+            placements: BTreeMap::from([
+                ("a.png".to_string(), placement("atlas_0.png")),
+                ("b.png".to_string(), placement("atlas_1.png")),
+            ]),
+            exclude: Vec::new(),
+            exclude_matcher: build_atlas_exclude(&[]).unwrap(),
+        };
+        let mut lockfile = Lockfile::default();
+        lockfile.insert(
+            ATLAS_INPUT,
+            &Hash::new_from_bytes(b"uploaded"),
+            asphalt::lockfile::LockfileEntry { asset_id: 42 },
+        );
+
+        let cache = ContentCache::new(dir.join("cache"));
+        let ids = resolve_atlas_ids(&atlas, &cache, Some(&lockfile), false).unwrap();
+        assert_eq!(ids["atlas_0.png"], "rbxassetid://42");
+        assert_eq!(ids["atlas_1.png"], "rbxassetid://0");
+
+        fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn font_atlases_take_their_ids_from_the_lockfile() {
+        let dir = std::env::temp_dir().join(format!("truffle-font-ids-{}", std::process::id()));
+        let fonts = dir.join(FONT_INPUT);
+        fs::create_dir_all(&fonts).unwrap();
+        fs::write(fonts.join("title.png"), b"uploaded").unwrap();
+        fs::write(fonts.join("title_outline.png"), b"new").unwrap();
+        fs::write(fonts.join("title.luau"), b"return {}").unwrap();
+
+        let font = FontOptions {
+            input: PathBuf::from("fonts/Title.ttf"),
+            output: PathBuf::from("src/fonts/title.luau"),
+            charset: Some("AB".into()),
+            size: Some(24.0),
+            cell: None,
+            outline: 2,
+        };
+        let args = font_args("title", &font, &dir, &dir.join("cache"));
+        assert_eq!(args.output_png, fonts.join("title.png"));
+        assert_eq!(
+            (args.charset.as_str(), args.px, args.outline),
+            ("AB", Some(24.0), 2)
+        );
+        assert_eq!(args.cell, 16);
+
+        let mut lockfile = Lockfile::default();
+        lockfile.insert(
+            FONT_INPUT,
+            &Hash::new_from_bytes(b"uploaded"),
+            asphalt::lockfile::LockfileEntry { asset_id: 7 },
+        );
+        let ids = font_image_ids(&dir, &lockfile).unwrap();
+        assert_eq!(ids.len(), 2);
+        assert_eq!(ids[&fonts.join("title.png")], "rbxassetid://7");
+        assert_eq!(ids[&fonts.join("title_outline.png")], "rbxassetid://0");
+
+        fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn dry_run_summary_counts_new_uploads() {
+        let dir = std::env::temp_dir().join(format!("truffle-dry-run-{}", std::process::id()));
+        let images = dir.join("assets/images");
+        fs::create_dir_all(&images).unwrap();
+        fs::write(images.join("a.png"), b"uploaded").unwrap();
+        fs::write(images.join("b.png"), b"new").unwrap();
+        fs::write(images.join("c.png"), b"new").unwrap();
+        fs::write(images.join("d.png"), b"other").unwrap();
+        fs::write(images.join("notes.txt"), b"ignored").unwrap();
+
+        let inputs = HashMap::from([(
+            "images".to_string(),
+            AsphaltInput {
+                include: Glob::new("assets/images/**/*").unwrap(),
+                output_path: PathBuf::from("out"),
+                bleed: false,
+                asset_type: None,
+                web: HashMap::new(),
+            },
+        )]);
+        let mut lockfile = Lockfile::default();
+        lockfile.insert(
+            "images",
+            &Hash::new_from_bytes(b"uploaded"),
+            asphalt::lockfile::LockfileEntry { asset_id: 1 },
+        );
+
+        let summary = DryRunSummary::collect(&dir, &inputs, &lockfile, None, None).unwrap();
+        assert_eq!(summary.unchanged, 2);
+        assert_eq!(summary.uploads.len(), 2);
+        assert!(summary
+            .uploads
+            .contains(&PathBuf::from("assets/images/d.png")));
+
+        let only = HashSet::from([PathBuf::from("assets/images/d.png")]);
+        let scoped = DryRunSummary::collect(&dir, &inputs, &lockfile, None, Some(&only)).unwrap();
+        assert_eq!(scoped.uploads, vec![PathBuf::from("assets/images/d.png")]);
+        assert_eq!(scoped.unchanged, 3);
+
+        fs::remove_dir_all(&dir).ok();
+    }
+}
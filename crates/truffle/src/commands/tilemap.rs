@@ -0,0 +1,151 @@
+use crate::assets::{build_atlases, stale_atlas_pages, AtlasOptions};
+use crate::cache::ContentCache;
+use crate::tilemap::{load_tilesets, render_tilemap_luau_module, slice_tiles};
+use anyhow::Context;
+use clap::{Parser, Subcommand};
+use std::path::{Path, PathBuf};
+
+#[derive(Subcommand)]
+pub enum TilemapCommands {
+    /// Pack Tiled or LDtk tilesets into atlases and emit Luau tile metadata
+    Import(TilemapImportArgs),
+}
+
+#[derive(Parser)]
+#[command(about = "Pack Tiled or LDtk tilesets into atlases and emit Luau tile metadata")]
+pub struct TilemapImportArgs {
+    /// Tiled tileset (.tsx), Tiled map (.tmx) or LDtk project (.ldtk)
+    #[arg(value_name = "INPUT")]
+    pub input: PathBuf,
+
+    /// Folder to write atlas PNGs to (cleaned on every run; defaults to `assets/images/tilemaps/<name>`)
+    #[arg(long)]
+    pub atlas_dir: Option<PathBuf>,
+
+    /// Path to write the Luau tile lookup module (defaults to `src/shared/data/tilemaps/<name>.luau`)
+    #[arg(long)]
+    pub luau_output: Option<PathBuf>,
+
+    /// Atlas texture size (power-of-two square)
+    #[arg(long, default_value_t = 1024)]
+    pub atlas_size: u32,
+
+    /// Padding (in pixels) around each tile in the atlas
+    #[arg(long, default_value_t = 4)]
+    pub atlas_padding: u32,
+
+    /// Scratch directory for intermediate/generated files
+    #[arg(long, default_value = ".truffle")]
+    pub scratch_dir: PathBuf,
+
+    /// Rebuild in the scratch directory and fail if the atlases or module on
+    /// disk differ, without writing them
+    #[arg(long)]
+    pub check: bool,
+}
+
+fn import(args: &TilemapImportArgs) -> anyhow::Result<()> {
+    let name = args
+        .input
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .context("Input path has no file name")?;
+    let atlas_dir = args
+        .atlas_dir
+        .clone()
+        .unwrap_or_else(|| Path::new("assets/images/tilemaps").join(name));
+    let luau_output = args
+        .luau_output
+        .clone()
+        .unwrap_or_else(|| Path::new("src/shared/data/tilemaps").join(format!("{}.luau", name)));
+    let tiles_dir = args.scratch_dir.join("tilemaps").join(name);
+
+    println!("[tilemap] Reading {}", args.input.display());
+    let tilesets = load_tilesets(&args.input)?;
+    if tilesets.is_empty() {
+        anyhow::bail!("No tilesets found in {}", args.input.display());
+    }
+
+    let written = slice_tiles(&tilesets, &tiles_dir)?;
+    println!(
+        "[tilemap] Sliced {} tile(s) from {} tileset(s)",
+        written,
+        tilesets.len()
+    );
+
+    // Checks build next to the sliced tiles and compare against `atlas_dir`.
+    let out_dir = if args.check {
+        args.scratch_dir.join("tilemap-check").join(name)
+    } else {
+        atlas_dir.clone()
+    };
+    let placements = build_atlases(
+        &tiles_dir,
+        &out_dir,
+        AtlasOptions {
+            padding: args.atlas_padding,
+            size: args.atlas_size,
+            cache: Some(ContentCache::new(args.scratch_dir.join("cache"))),
+            ..Default::default()
+        },
+    )
+    .context("Failed to build tile atlases")?;
+    let module = render_tilemap_luau_module(&tilesets, &placements);
+
+    if args.check {
+        return check(&out_dir, &atlas_dir, &luau_output, &module);
+    }
+    println!("[tilemap] ✅ Wrote atlases to {}", atlas_dir.display());
+
+    if let Some(parent) = luau_output.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create {}", parent.display()))?;
+    }
+    std::fs::write(&luau_output, module)
+        .with_context(|| format!("Failed to write {}", luau_output.display()))?;
+    println!("[tilemap] ✅ Generated: {}", luau_output.display());
+
+    Ok(())
+}
+
+/// Compares a fresh build with the committed atlases and module.
+fn check(
+    fresh_dir: &Path,
+    atlas_dir: &Path,
+    luau_output: &Path,
+    module: &str,
+) -> anyhow::Result<()> {
+    let mut stale: Vec<String> = stale_atlas_pages(fresh_dir, atlas_dir)?
+        .into_iter()
+        .map(|page| atlas_dir.join(page).display().to_string())
+        .collect();
+    if std::fs::read_to_string(luau_output).ok().as_deref() != Some(module) {
+        stale.push(luau_output.display().to_string());
+    }
+
+    if stale.is_empty() {
+        println!("[tilemap] ✅ Atlases and module are up to date");
+        return Ok(());
+    }
+    for path in &stale {
+        println!("[tilemap] Out of date: {}", path);
+    }
+    anyhow::bail!(
+        "{} file(s) differ from a fresh import; run `truffle tilemap import`",
+        stale.len()
+    )
+}
+
+pub fn run(command: TilemapCommands) -> bool {
+    let result = match command {
+        TilemapCommands::Import(args) => import(&args),
+    };
+
+    match result {
+        Ok(()) => true,
+        Err(e) => {
+            eprintln!("[tilemap] ERROR: {}", e);
+            false
+        }
+    }
+}
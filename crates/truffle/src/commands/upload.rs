@@ -0,0 +1,276 @@
+use crate::assets::load_assets;
+use crate::assets::model::{AssetMeta, AssetValue};
+use crate::commands::sync::{insert_asset_value, render_modules, resolve_api_key};
+use anyhow::Context;
+use asphalt::{config::InputAssetType, upload::prepare, web_api::WebApiClient};
+use clap::{Parser, ValueEnum};
+use image::codecs::png::{CompressionType, FilterType, PngEncoder};
+use image::imageops::FilterType as ResizeFilter;
+use image::{DynamicImage, GenericImageView, ImageEncoder};
+use std::fs;
+use std::path::PathBuf;
+use tokio::runtime::Runtime;
+use truffle_config::TruffleConfig;
+
+#[derive(Parser)]
+#[command(about = "Upload a single file and print its asset id")]
+pub struct UploadArgs {
+    /// File to upload
+    #[arg(value_name = "FILE")]
+    pub path: PathBuf,
+
+    /// Downscale PNGs so neither side exceeds this many pixels
+    #[arg(long, value_name = "PX")]
+    pub max_size: Option<u32>,
+
+    /// Re-encode PNGs with maximum compression before uploading
+    #[arg(long)]
+    pub optimize: bool,
+
+    /// Roblox asset type to upload as (defaults to the file's type)
+    #[arg(long, value_enum)]
+    pub asset_type: Option<InputAssetType>,
+
+    /// How to print the result
+    #[arg(long, value_enum, default_value_t = UploadOutput::Text)]
+    pub output: UploadOutput,
+
+    /// Insert the asset into the assets module under this key (e.g. `ui/play.png`)
+    #[arg(long)]
+    pub key: Option<String>,
+
+    /// Path of the augmented Luau assets module to insert into
+    #[arg(long, default_value = "src/shared/data/assets/assets.luau")]
+    pub assets_output: PathBuf,
+
+    /// Path of the TypeScript declaration file to rewrite
+    #[arg(long, default_value = "src/shared/data/assets/assets.d.ts")]
+    pub dts_output: PathBuf,
+
+    /// Images folder the module was synced from (used to rebuild animations)
+    #[arg(long, default_value = "assets/images")]
+    pub images_folder: PathBuf,
+
+    /// Process the file and print what would be uploaded without uploading
+    #[arg(long)]
+    pub dry_run: bool,
+
+    /// Robux you are willing to spend if the upload is not free
+    #[arg(long)]
+    pub expected_price: Option<u32>,
+
+    /// TRUFFLE_API_KEY environment variable (or read from .env file)
+    #[arg(long)]
+    pub api_key: Option<String>,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum UploadOutput {
+    /// The `rbxassetid://` id
+    Text,
+    /// A JSON object with the id, size and key
+    Json,
+}
+
+pub fn run(args: UploadArgs) -> bool {
+    let rt = Runtime::new().expect("Failed to create tokio runtime");
+
+    match rt.block_on(upload(args)) {
+        Ok(()) => true,
+        Err(e) => {
+            eprintln!("[upload] ERROR: {}", e);
+            false
+        }
+    }
+}
+
+async fn upload(args: UploadArgs) -> anyhow::Result<()> {
+    let config = TruffleConfig::read()
+        .await
+        .context("Failed to read truffle.toml. Make sure it exists in the current directory.")?;
+
+    let data =
+        fs::read(&args.path).with_context(|| format!("Failed to read {}", args.path.display()))?;
+    let Preprocessed { data, size } = preprocess(&args, data)?;
+    let asset = prepare(&args.path, data, true, args.asset_type)?;
+
+    if args.dry_run {
+        println!(
+            "[upload] DRY-RUN: Would upload {} as {} ({} bytes)",
+            args.path.display(),
+            asset.ty.asset_type(),
+            asset.data.len()
+        );
+        return Ok(());
+    }
+
+    let client = WebApiClient::new(
+        resolve_api_key(args.api_key.clone())?,
+        config.asphalt.creator.clone(),
+        args.expected_price,
+    );
+    let asset_id = client
+        .upload(&asset)
+        .await
+        .with_context(|| format!("Failed to upload {}", args.path.display()))?;
+    let id = format!("rbxassetid://{}", asset_id);
+
+    if let Some(key) = &args.key {
+        let meta = AssetMeta {
+            id: id.clone(),
+            width: size.map(|(width, _)| width),
+            height: size.map(|(_, height)| height),
+            asset_type: args.asset_type.map(|ty| ty.name().to_string()),
+            ..Default::default()
+        };
+        insert_into_module(&args, &config, key, meta)?;
+    }
+
+    match args.output {
+        UploadOutput::Text => println!("{}", id),
+        UploadOutput::Json => {
+            let json = serde_json::json!({
+                "path": args.path,
+                "assetId": asset_id,
+                "id": id,
+                "width": size.map(|(width, _)| width),
+                "height": size.map(|(_, height)| height),
+                "key": args.key,
+            });
+            println!("{}", serde_json::to_string_pretty(&json)?);
+        }
+    }
+    Ok(())
+}
+
+/// The bytes to upload and, for PNGs, their final size.
+struct Preprocessed {
+    data: Vec<u8>,
+    size: Option<(u32, u32)>,
+}
+
+/// Applies `--max-size` and `--optimize` to PNGs.
+fn preprocess(args: &UploadArgs, data: Vec<u8>) -> anyhow::Result<Preprocessed> {
+    let is_png = args
+        .path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("png"));
+    if !is_png {
+        if args.max_size.is_some() || args.optimize {
+            anyhow::bail!("--max-size and --optimize only apply to PNG files");
+        }
+        return Ok(Preprocessed { data, size: None });
+    }
+
+    let mut image = image::load_from_memory(&data)
+        .with_context(|| format!("Failed to decode {}", args.path.display()))?;
+    let (width, height) = image.dimensions();
+    let resized = args
+        .max_size
+        .map(|max| fit_within(width, height, max))
+        .filter(|&size| size != (width, height));
+    if let Some((new_width, new_height)) = resized {
+        println!(
+            "[upload] Resizing {}x{} → {}x{}",
+            width, height, new_width, new_height
+        );
+        image = image.resize_exact(new_width, new_height, ResizeFilter::Lanczos3);
+    }
+    let size = image.dimensions();
+
+    if resized.is_none() && !args.optimize {
+        return Ok(Preprocessed {
+            data,
+            size: Some(size),
+        });
+    }
+    let encoded = encode_png(&image, args.optimize)?;
+    if args.optimize {
+        println!(
+            "[upload] Optimized {} → {} bytes",
+            data.len(),
+            encoded.len()
+        );
+    }
+    Ok(Preprocessed {
+        data: encoded,
+        size: Some(size),
+    })
+}
+
+/// Scales `width`x`height` down, keeping the aspect ratio, so neither side
+/// exceeds `max`. Images that already fit are left alone.
+fn fit_within(width: u32, height: u32, max: u32) -> (u32, u32) {
+    let max = max.max(1);
+    if width <= max && height <= max {
+        return (width, height);
+    }
+    let scale = max as f64 / width.max(height) as f64;
+    let scaled = |side: u32| ((side as f64 * scale).round() as u32).clamp(1, max);
+    (scaled(width), scaled(height))
+}
+
+fn encode_png(image: &DynamicImage, best: bool) -> anyhow::Result<Vec<u8>> {
+    let rgba = image.to_rgba8();
+    let mut out = Vec::new();
+    let encoder = if best {
+        PngEncoder::new_with_quality(&mut out, CompressionType::Best, FilterType::Adaptive)
+    } else {
+        PngEncoder::new(&mut out)
+    };
+    encoder
+        .write_image(
+            rgba.as_raw(),
+            rgba.width(),
+            rgba.height(),
+            image::ExtendedColorType::Rgba8,
+        )
+        .context("Failed to encode PNG")?;
+    Ok(out)
+}
+
+/// Adds the uploaded asset to the generated modules under `key`. A later
+/// sync regenerates the modules from Asphalt's codegen, dropping the entry
+/// unless the file is moved into the images folder.
+fn insert_into_module(
+    args: &UploadArgs,
+    config: &TruffleConfig,
+    key: &str,
+    meta: AssetMeta,
+) -> anyhow::Result<()> {
+    let path: Vec<String> = key
+        .split('/')
+        .filter(|segment| !segment.is_empty())
+        .map(str::to_string)
+        .collect();
+    if path.is_empty() {
+        anyhow::bail!("--key must not be empty");
+    }
+
+    let mut assets = load_assets(&args.assets_output)
+        .map_err(|e| anyhow::anyhow!("Failed to load assets: {}", e))?;
+    insert_asset_value(&mut assets, &path, AssetValue::Object(meta));
+    let modules = render_modules(assets, &args.images_folder, config)?;
+
+    modules.write(&args.assets_output, &args.dts_output)?;
+    println!(
+        "[upload] Added {} to {}",
+        path.join("/"),
+        args.assets_output.display()
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fits_images_within_max_size() {
+        assert_eq!(fit_within(64, 32, 128), (64, 32));
+        assert_eq!(fit_within(2048, 1024, 512), (512, 256));
+        assert_eq!(fit_within(300, 1200, 100), (25, 100));
+        assert_eq!(fit_within(4000, 1, 100), (100, 1));
+    }
+}
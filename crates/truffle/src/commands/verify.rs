@@ -0,0 +1,164 @@
+use crate::assets::FsImageMetadata;
+use crate::commands::sync::{generate_modules, SyncArgs};
+use anyhow::Context;
+use clap::Parser;
+use similar::TextDiff;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tokio::runtime::Runtime;
+use truffle_config::TruffleConfig;
+
+#[derive(Parser, Debug)]
+#[command(about = "Check that the generated Luau and TypeScript modules are up to date")]
+pub struct VerifyArgs {
+    /// Path to the Luau assets module file
+    #[arg(long, default_value = "src/shared/data/assets/assets.luau")]
+    pub assets_input: PathBuf,
+
+    /// Path of the augmented Luau assets module to check
+    #[arg(long, default_value = "src/shared/data/assets/assets.luau")]
+    pub assets_output: PathBuf,
+
+    /// Path of the TypeScript declaration file to check
+    #[arg(long, default_value = "src/shared/data/assets/assets.d.ts")]
+    pub dts_output: PathBuf,
+
+    /// Path to the raw assets images folder
+    #[arg(long, default_value = "assets/images")]
+    pub images_folder: PathBuf,
+
+    /// Images folder whose keys go under PREFIX, as `PREFIX=PATH` (repeatable)
+    #[arg(long = "image-root", value_name = "PREFIX=PATH")]
+    pub image_roots: Vec<String>,
+
+    /// Check the modules of an atlas sync
+    #[arg(long)]
+    pub atlas: bool,
+
+    /// Atlas texture size (power-of-two square)
+    #[arg(long)]
+    pub atlas_size: Option<u32>,
+
+    /// Padding (in pixels) around each sprite in the atlas
+    #[arg(long)]
+    pub atlas_padding: Option<u32>,
+
+    /// Image keys to exclude from atlas packing (repeatable)
+    #[arg(long)]
+    pub atlas_exclude: Vec<String>,
+
+    /// Scratch directory for intermediate/generated files
+    #[arg(long)]
+    pub scratch_dir: Option<PathBuf>,
+
+    /// Directory for the content-addressed cache (defaults to `<scratch_dir>/cache`)
+    #[arg(long)]
+    pub cache_dir: Option<PathBuf>,
+}
+
+impl VerifyArgs {
+    fn sync_args(&self) -> SyncArgs {
+        let roots = self
+            .image_roots
+            .iter()
+            .flat_map(|root| ["--image-root", root.as_str()]);
+        let mut args = SyncArgs::parse_from(["sync"].into_iter().chain(roots));
+        args.assets_input = self.assets_input.clone();
+        args.assets_output = self.assets_output.clone();
+        args.dts_output = self.dts_output.clone();
+        args.images_folder = self.images_folder.clone();
+        args.atlas = self.atlas;
+        args.atlas_size = self.atlas_size;
+        args.atlas_padding = self.atlas_padding;
+        args.atlas_exclude = self.atlas_exclude.clone();
+        args.dry_run = true;
+        args.scratch_dir = self.scratch_dir.clone();
+        args.cache_dir = self.cache_dir.clone();
+        args
+    }
+}
+
+pub fn run(args: VerifyArgs) -> bool {
+    check(args.sync_args())
+}
+
+/// Regenerates the modules of a sync with `args` in memory and compares them
+/// to the files on disk. `truffle sync --check` runs this as well.
+pub fn check(mut args: SyncArgs) -> bool {
+    args.dry_run = true;
+    match run_impl(&args) {
+        Ok(true) => {
+            println!("[verify] Generated files are up to date");
+            true
+        }
+        Ok(false) => {
+            eprintln!("[verify] ERROR: Generated files are out of date; run `truffle sync`");
+            false
+        }
+        Err(e) => {
+            eprintln!("[verify] ERROR: {:#}", e);
+            false
+        }
+    }
+}
+
+fn run_impl(args: &SyncArgs) -> anyhow::Result<bool> {
+    let rt = Runtime::new().expect("Failed to create tokio runtime");
+    let config = rt
+        .block_on(TruffleConfig::read())
+        .context("Failed to read truffle.toml. Make sure it exists in the current directory.")?;
+
+    let modules = generate_modules(args, &config, &FsImageMetadata)?;
+    let mut fresh = true;
+    for (path, contents) in modules.files(&args.assets_output, &args.dts_output) {
+        fresh &= check_file(&path, &contents)?;
+    }
+    Ok(fresh)
+}
+
+/// Prints a unified diff and returns false when `path` does not hold `expected`.
+fn check_file(path: &Path, expected: &str) -> anyhow::Result<bool> {
+    let actual = if path.exists() {
+        fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?
+    } else {
+        String::new()
+    };
+    match unified_diff(path, &actual, expected) {
+        Some(diff) => {
+            println!("[verify] {} is out of date:", path.display());
+            print!("{}", diff);
+            Ok(false)
+        }
+        None => Ok(true),
+    }
+}
+
+/// Diff from the file on disk to the freshly generated contents.
+fn unified_diff(path: &Path, actual: &str, expected: &str) -> Option<String> {
+    if actual == expected {
+        return None;
+    }
+    let name = path.display().to_string();
+    Some(
+        TextDiff::from_lines(actual, expected)
+            .unified_diff()
+            .context_radius(3)
+            .header(&format!("a/{}", name), &format!("b/{}", name))
+            .to_string(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn diff_shows_stale_lines() {
+        let path = Path::new("assets.luau");
+        assert_eq!(unified_diff(path, "a\nb\n", "a\nb\n"), None);
+        assert_eq!(
+            unified_diff(path, "a\nb\nc\n", "a\nB\nc\n").unwrap(),
+            "--- a/assets.luau\n+++ b/assets.luau\n@@ -1,3 +1,3 @@\n a\n-b\n+B\n c\n"
+        );
+    }
+}
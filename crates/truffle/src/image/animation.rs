@@ -0,0 +1,53 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// Name of the timeline manifest written next to exported frames.
+pub const ANIMATIONS_FILE: &str = "animations.json";
+
+/// Timeline of one exported animation, as stored in `animations.json`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AnimationEntry {
+    /// Frame file names, relative to the manifest's folder.
+    pub frames: Vec<String>,
+    /// Per-frame durations in milliseconds.
+    pub durations: Vec<u32>,
+    /// `forward`, `reverse`, `pingpong` or `pingpong_reverse`.
+    pub direction: String,
+    /// Number of times to play the animation (0 = loop forever).
+    pub repeat: u32,
+}
+
+/// File name of frame `index` (0-based) out of `count`, e.g. `run01.png`.
+pub fn frame_file_name(name: &str, index: usize, count: usize) -> String {
+    let digits = count.to_string().len().max(2);
+    format!("{}{:0width$}.png", name, index + 1, width = digits)
+}
+
+pub fn write_manifest(
+    output_dir: &Path,
+    animations: &BTreeMap<String, AnimationEntry>,
+) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(animations)
+        .map_err(|e| format!("Failed to encode animations: {}", e))?;
+    let manifest = output_dir.join(ANIMATIONS_FILE);
+    std::fs::write(&manifest, json)
+        .map_err(|e| format!("Failed to write {}: {}", manifest.display(), e))
+}
+
+pub fn read_manifest(path: &Path) -> Result<BTreeMap<String, AnimationEntry>, String> {
+    let json = std::fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    serde_json::from_str(&json).map_err(|e| format!("Failed to parse {}: {}", path.display(), e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frame_names_are_zero_padded() {
+        assert_eq!(frame_file_name("run", 0, 4), "run01.png");
+        assert_eq!(frame_file_name("run", 99, 120), "run100.png");
+    }
+}
@@ -0,0 +1,614 @@
+use super::composite::blend_over;
+use flate2::read::ZlibDecoder;
+use image::{ImageBuffer, Rgba, RgbaImage};
+use std::io::Read;
+use std::path::Path;
+
+const HEADER_MAGIC: u16 = 0xA5E0;
+const FRAME_MAGIC: u16 = 0xF1FA;
+const HEADER_SIZE: usize = 128;
+const FRAME_HEADER_SIZE: usize = 16;
+
+const CHUNK_OLD_PALETTE: u16 = 0x0004;
+const CHUNK_LAYER: u16 = 0x2004;
+const CHUNK_CEL: u16 = 0x2005;
+const CHUNK_TAGS: u16 = 0x2018;
+const CHUNK_PALETTE: u16 = 0x2019;
+
+const LAYER_FLAG_VISIBLE: u16 = 1;
+const LAYER_TYPE_GROUP: u16 = 1;
+const LAYER_TYPE_TILEMAP: u16 = 2;
+const HEADER_FLAG_LAYER_OPACITY: u32 = 1;
+
+/// Playback direction of an Aseprite tag.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Forward,
+    Reverse,
+    PingPong,
+    PingPongReverse,
+}
+
+impl Direction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Direction::Forward => "forward",
+            Direction::Reverse => "reverse",
+            Direction::PingPong => "pingpong",
+            Direction::PingPongReverse => "pingpong_reverse",
+        }
+    }
+}
+
+/// A named frame range (inclusive) from the Aseprite timeline.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Tag {
+    pub name: String,
+    pub from: usize,
+    pub to: usize,
+    pub direction: Direction,
+    /// Times to play the range; 0 means loop forever.
+    pub repeat: u16,
+}
+
+/// A frame with all visible layers composited into one image.
+pub struct Frame {
+    pub duration_ms: u16,
+    pub image: RgbaImage,
+}
+
+pub struct Sprite {
+    pub frames: Vec<Frame>,
+    pub tags: Vec<Tag>,
+}
+
+pub fn read_aseprite(path: &Path) -> Result<Sprite, String> {
+    let data =
+        std::fs::read(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    parse_aseprite(&data).map_err(|e| format!("Failed to parse {}: {}", path.display(), e))
+}
+
+#[derive(Clone, Copy)]
+enum ColorDepth {
+    Rgba,
+    Grayscale,
+    Indexed,
+}
+
+impl ColorDepth {
+    fn bytes_per_pixel(self) -> usize {
+        match self {
+            ColorDepth::Rgba => 4,
+            ColorDepth::Grayscale => 2,
+            ColorDepth::Indexed => 1,
+        }
+    }
+}
+
+struct Layer {
+    visible: bool,
+    opacity: u8,
+}
+
+#[derive(Clone)]
+struct Cel {
+    layer: usize,
+    x: i32,
+    y: i32,
+    opacity: u8,
+    z_index: i16,
+    width: u32,
+    height: u32,
+    pixels: Vec<u8>,
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data, pos: 0 }
+    }
+
+    fn bytes(&mut self, len: usize) -> Result<&'a [u8], String> {
+        let end = self
+            .pos
+            .checked_add(len)
+            .filter(|end| *end <= self.data.len())
+            .ok_or("unexpected end of file")?;
+        let slice = &self.data[self.pos..end];
+        self.pos = end;
+        Ok(slice)
+    }
+
+    fn skip(&mut self, len: usize) -> Result<(), String> {
+        self.bytes(len).map(|_| ())
+    }
+
+    fn u8(&mut self) -> Result<u8, String> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, String> {
+        let b = self.bytes(2)?;
+        Ok(u16::from_le_bytes([b[0], b[1]]))
+    }
+
+    fn i16(&mut self) -> Result<i16, String> {
+        Ok(self.u16()? as i16)
+    }
+
+    fn u32(&mut self) -> Result<u32, String> {
+        let b = self.bytes(4)?;
+        Ok(u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        let len = self.u16()? as usize;
+        Ok(String::from_utf8_lossy(self.bytes(len)?).into_owned())
+    }
+}
+
+/// Parse an `.aseprite`/`.ase` file and composite every frame.
+///
+/// Only the normal blend mode is supported; layers using other blend modes
+/// are composited as if they were normal. Tilemap layers are skipped.
+pub fn parse_aseprite(data: &[u8]) -> Result<Sprite, String> {
+    let mut header = Reader::new(data);
+    header.u32()?;
+    if header.u16()? != HEADER_MAGIC {
+        return Err("not an Aseprite file".into());
+    }
+    let frame_count = header.u16()? as usize;
+    let width = header.u16()? as u32;
+    let height = header.u16()? as u32;
+    let depth = match header.u16()? {
+        32 => ColorDepth::Rgba,
+        16 => ColorDepth::Grayscale,
+        8 => ColorDepth::Indexed,
+        other => return Err(format!("unsupported color depth {}", other)),
+    };
+    let flags = header.u32()?;
+    header.skip(2 + 4 + 4)?;
+    let transparent_index = header.u8()?;
+
+    let mut reader = Reader::new(data);
+    reader.skip(HEADER_SIZE)?;
+
+    let mut layers: Vec<Layer> = Vec::new();
+    // Visibility of each open group, indexed by child level.
+    let mut group_visibility: Vec<bool> = Vec::new();
+    let mut palette: Vec<[u8; 4]> = Vec::new();
+    let mut tags = Vec::new();
+    let mut frame_cels: Vec<Vec<Cel>> = Vec::with_capacity(frame_count);
+    let mut durations = Vec::with_capacity(frame_count);
+
+    for _ in 0..frame_count {
+        let frame_start = reader.pos;
+        let frame_size = reader.u32()? as usize;
+        if frame_size < FRAME_HEADER_SIZE {
+            return Err("invalid frame size".into());
+        }
+        if reader.u16()? != FRAME_MAGIC {
+            return Err("invalid frame header".into());
+        }
+        let old_chunks = reader.u16()? as usize;
+        durations.push(reader.u16()?);
+        reader.skip(2)?;
+        let new_chunks = reader.u32()? as usize;
+        let chunk_count = if new_chunks == 0 {
+            old_chunks
+        } else {
+            new_chunks
+        };
+
+        let mut cels = Vec::new();
+        for _ in 0..chunk_count {
+            let chunk_start = reader.pos;
+            let chunk_size = reader.u32()? as usize;
+            let chunk_type = reader.u16()?;
+            if chunk_size < 6 {
+                return Err("invalid chunk size".into());
+            }
+            let mut chunk = Reader::new(reader.bytes(chunk_size - 6)?);
+
+            match chunk_type {
+                CHUNK_LAYER => {
+                    let layer_flags = chunk.u16()?;
+                    let layer_type = chunk.u16()?;
+                    let level = chunk.u16()? as usize;
+                    chunk.skip(2 + 2 + 2)?;
+                    let opacity = chunk.u8()?;
+
+                    group_visibility.truncate(level);
+                    let parent_visible = group_visibility.iter().all(|v| *v);
+                    let visible = parent_visible && layer_flags & LAYER_FLAG_VISIBLE != 0;
+                    if layer_type == LAYER_TYPE_GROUP {
+                        group_visibility.push(visible);
+                    }
+
+                    layers.push(Layer {
+                        visible: visible
+                            && layer_type != LAYER_TYPE_GROUP
+                            && layer_type != LAYER_TYPE_TILEMAP,
+                        opacity: if flags & HEADER_FLAG_LAYER_OPACITY != 0 {
+                            opacity
+                        } else {
+                            255
+                        },
+                    });
+                }
+                CHUNK_CEL => {
+                    let layer = chunk.u16()? as usize;
+                    let x = chunk.i16()? as i32;
+                    let y = chunk.i16()? as i32;
+                    let opacity = chunk.u8()?;
+                    let cel_type = chunk.u16()?;
+                    let z_index = chunk.i16()?;
+                    chunk.skip(5)?;
+
+                    let cel = match cel_type {
+                        0 | 2 => {
+                            let cel_width = chunk.u16()? as u32;
+                            let cel_height = chunk.u16()? as u32;
+                            let len =
+                                cel_width as usize * cel_height as usize * depth.bytes_per_pixel();
+                            let pixels = if cel_type == 0 {
+                                chunk.bytes(len)?.to_vec()
+                            } else {
+                                let mut out = Vec::with_capacity(len);
+                                ZlibDecoder::new(&chunk.data[chunk.pos..])
+                                    .read_to_end(&mut out)
+                                    .map_err(|e| format!("corrupt cel data: {}", e))?;
+                                out
+                            };
+                            if pixels.len() < len {
+                                return Err("cel data is truncated".into());
+                            }
+                            Some(Cel {
+                                layer,
+                                x,
+                                y,
+                                opacity,
+                                z_index,
+                                width: cel_width,
+                                height: cel_height,
+                                pixels,
+                            })
+                        }
+                        1 => {
+                            let linked = chunk.u16()? as usize;
+                            frame_cels
+                                .get(linked)
+                                .and_then(|cels: &Vec<Cel>| cels.iter().find(|c| c.layer == layer))
+                                .map(|cel| Cel {
+                                    x,
+                                    y,
+                                    opacity,
+                                    z_index,
+                                    ..cel.clone()
+                                })
+                        }
+                        // Compressed tilemaps are not supported.
+                        _ => None,
+                    };
+                    cels.extend(cel);
+                }
+                CHUNK_TAGS => {
+                    let count = chunk.u16()? as usize;
+                    chunk.skip(8)?;
+                    for _ in 0..count {
+                        let from = chunk.u16()? as usize;
+                        let to = chunk.u16()? as usize;
+                        let direction = match chunk.u8()? {
+                            1 => Direction::Reverse,
+                            2 => Direction::PingPong,
+                            3 => Direction::PingPongReverse,
+                            _ => Direction::Forward,
+                        };
+                        let repeat = chunk.u16()?;
+                        chunk.skip(6 + 3 + 1)?;
+                        let name = chunk.string()?;
+                        tags.push(Tag {
+                            name,
+                            from,
+                            to,
+                            direction,
+                            repeat,
+                        });
+                    }
+                }
+                CHUNK_PALETTE => {
+                    let size = chunk.u32()? as usize;
+                    let first = chunk.u32()? as usize;
+                    let last = chunk.u32()? as usize;
+                    chunk.skip(8)?;
+                    if palette.len() < size {
+                        palette.resize(size, [0, 0, 0, 0]);
+                    }
+                    for index in first..=last {
+                        let entry_flags = chunk.u16()?;
+                        let rgba = chunk.bytes(4)?;
+                        if let Some(slot) = palette.get_mut(index) {
+                            *slot = [rgba[0], rgba[1], rgba[2], rgba[3]];
+                        }
+                        if entry_flags & 1 != 0 {
+                            chunk.string()?;
+                        }
+                    }
+                }
+                CHUNK_OLD_PALETTE if palette.is_empty() => {
+                    let packets = chunk.u16()?;
+                    let mut index = 0usize;
+                    for _ in 0..packets {
+                        index += chunk.u8()? as usize;
+                        let count = match chunk.u8()? {
+                            0 => 256,
+                            n => n as usize,
+                        };
+                        for _ in 0..count {
+                            let rgb = chunk.bytes(3)?;
+                            if palette.len() <= index {
+                                palette.resize(index + 1, [0, 0, 0, 0]);
+                            }
+                            palette[index] = [rgb[0], rgb[1], rgb[2], 255];
+                            index += 1;
+                        }
+                    }
+                }
+                _ => {}
+            }
+
+            reader.pos = chunk_start + chunk_size;
+        }
+
+        frame_cels.push(cels);
+        reader.pos = frame_start + frame_size;
+    }
+
+    let to_rgba = |pixel: &[u8]| -> [u8; 4] {
+        match depth {
+            ColorDepth::Rgba => [pixel[0], pixel[1], pixel[2], pixel[3]],
+            ColorDepth::Grayscale => [pixel[0], pixel[0], pixel[0], pixel[1]],
+            ColorDepth::Indexed => {
+                if pixel[0] == transparent_index {
+                    [0, 0, 0, 0]
+                } else {
+                    palette
+                        .get(pixel[0] as usize)
+                        .copied()
+                        .unwrap_or([0, 0, 0, 0])
+                }
+            }
+        }
+    };
+
+    let frames = frame_cels
+        .into_iter()
+        .zip(durations)
+        .map(|(mut cels, duration_ms)| {
+            // Aseprite orders cels by layer index shifted by their z-index.
+            cels.sort_by_key(|cel| (cel.layer as i64 + cel.z_index as i64, cel.z_index));
+
+            let mut image = ImageBuffer::from_pixel(width, height, Rgba([0, 0, 0, 0]));
+            for cel in &cels {
+                let Some(layer) = layers.get(cel.layer) else {
+                    continue;
+                };
+                if !layer.visible {
+                    continue;
+                }
+                let opacity = cel.opacity as u32 * layer.opacity as u32 / 255;
+                composite_cel(&mut image, cel, opacity, depth, &to_rgba);
+            }
+
+            Frame { duration_ms, image }
+        })
+        .collect();
+
+    Ok(Sprite { frames, tags })
+}
+
+fn composite_cel(
+    image: &mut RgbaImage,
+    cel: &Cel,
+    opacity: u32,
+    depth: ColorDepth,
+    to_rgba: &dyn Fn(&[u8]) -> [u8; 4],
+) {
+    let bpp = depth.bytes_per_pixel();
+    for cy in 0..cel.height {
+        let y = cel.y + cy as i32;
+        if y < 0 || y >= image.height() as i32 {
+            continue;
+        }
+        for cx in 0..cel.width {
+            let x = cel.x + cx as i32;
+            if x < 0 || x >= image.width() as i32 {
+                continue;
+            }
+
+            let offset = (cy as usize * cel.width as usize + cx as usize) * bpp;
+            let src = to_rgba(&cel.pixels[offset..offset + bpp]);
+            blend_over(image.get_pixel_mut(x as u32, y as u32), src, opacity);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::write::ZlibEncoder;
+    use flate2::Compression;
+    use std::io::Write;
+
+    fn chunk(kind: u16, body: &[u8]) -> Vec<u8> {
+        let mut out = ((body.len() + 6) as u32).to_le_bytes().to_vec();
+        out.extend(kind.to_le_bytes());
+        out.extend(body);
+        out
+    }
+
+    fn layer(name: &str, flags: u16, layer_type: u16, level: u16) -> Vec<u8> {
+        let mut body = Vec::new();
+        for value in [flags, layer_type, level, 0, 0, 0] {
+            body.extend(value.to_le_bytes());
+        }
+        body.extend([255, 0, 0, 0]);
+        body.extend((name.len() as u16).to_le_bytes());
+        body.extend(name.as_bytes());
+        chunk(CHUNK_LAYER, &body)
+    }
+
+    fn cel(layer: u16, x: i16, kind: u16, payload: &[u8]) -> Vec<u8> {
+        let mut body = Vec::new();
+        body.extend(layer.to_le_bytes());
+        body.extend(x.to_le_bytes());
+        body.extend(0i16.to_le_bytes());
+        body.push(255);
+        body.extend(kind.to_le_bytes());
+        body.extend([0; 7]);
+        body.extend(payload);
+        chunk(CHUNK_CEL, &body)
+    }
+
+    fn frame(duration: u16, chunks: &[Vec<u8>]) -> Vec<u8> {
+        let body: Vec<u8> = chunks.concat();
+        let mut out = ((body.len() + FRAME_HEADER_SIZE) as u32)
+            .to_le_bytes()
+            .to_vec();
+        out.extend(FRAME_MAGIC.to_le_bytes());
+        out.extend((chunks.len() as u16).to_le_bytes());
+        out.extend(duration.to_le_bytes());
+        out.extend([0, 0]);
+        out.extend((chunks.len() as u32).to_le_bytes());
+        out.extend(body);
+        out
+    }
+
+    fn file(frames: &[Vec<u8>]) -> Vec<u8> {
+        let mut header = vec![0u8; HEADER_SIZE];
+        header[4..6].copy_from_slice(&HEADER_MAGIC.to_le_bytes());
+        header[6..8].copy_from_slice(&(frames.len() as u16).to_le_bytes());
+        header[8..10].copy_from_slice(&2u16.to_le_bytes());
+        header[10..12].copy_from_slice(&1u16.to_le_bytes());
+        header[12..14].copy_from_slice(&32u16.to_le_bytes());
+        header[14..18].copy_from_slice(&HEADER_FLAG_LAYER_OPACITY.to_le_bytes());
+        let mut out = header;
+        for frame in frames {
+            out.extend(frame);
+        }
+        let len = out.len() as u32;
+        out[0..4].copy_from_slice(&len.to_le_bytes());
+        out
+    }
+
+    fn raw_pixels(width: u16, height: u16, rgba: [u8; 4]) -> Vec<u8> {
+        let mut out = Vec::new();
+        out.extend(width.to_le_bytes());
+        out.extend(height.to_le_bytes());
+        for _ in 0..width * height {
+            out.extend(rgba);
+        }
+        out
+    }
+
+    fn compressed_pixels(width: u16, height: u16, rgba: [u8; 4]) -> Vec<u8> {
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        for _ in 0..width * height {
+            encoder.write_all(&rgba).unwrap();
+        }
+        let mut out = Vec::new();
+        out.extend(width.to_le_bytes());
+        out.extend(height.to_le_bytes());
+        out.extend(encoder.finish().unwrap());
+        out
+    }
+
+    fn tags(name: &str, from: u16, to: u16, direction: u8) -> Vec<u8> {
+        let mut body = Vec::new();
+        body.extend(1u16.to_le_bytes());
+        body.extend([0; 8]);
+        body.extend(from.to_le_bytes());
+        body.extend(to.to_le_bytes());
+        body.push(direction);
+        body.extend(0u16.to_le_bytes());
+        body.extend([0; 10]);
+        body.extend((name.len() as u16).to_le_bytes());
+        body.extend(name.as_bytes());
+        chunk(CHUNK_TAGS, &body)
+    }
+
+    #[test]
+    fn composites_visible_layers_and_linked_cels() {
+        let red = [255, 0, 0, 255];
+        let blue = [0, 0, 255, 255];
+        let data = file(&[
+            frame(
+                100,
+                &[
+                    layer("base", LAYER_FLAG_VISIBLE, 0, 0),
+                    layer("hidden", 0, 0, 0),
+                    tags("idle", 0, 1, 2),
+                    cel(0, 0, 0, &raw_pixels(1, 1, red)),
+                    cel(1, 1, 0, &raw_pixels(1, 1, blue)),
+                ],
+            ),
+            frame(
+                50,
+                &[
+                    cel(0, 1, 1, &0u16.to_le_bytes()),
+                    cel(1, 0, 2, &compressed_pixels(1, 1, blue)),
+                ],
+            ),
+        ]);
+
+        let sprite = parse_aseprite(&data).unwrap();
+        assert_eq!(sprite.frames[0].image.dimensions(), (2, 1));
+        assert_eq!(sprite.frames.len(), 2);
+        assert_eq!(sprite.frames[0].duration_ms, 100);
+        assert_eq!(sprite.frames[1].duration_ms, 50);
+
+        let first = &sprite.frames[0].image;
+        assert_eq!(first.get_pixel(0, 0).0, red);
+        assert_eq!(first.get_pixel(1, 0).0, [0, 0, 0, 0]);
+
+        // The linked cel reuses frame 0's pixels at its own position.
+        let second = &sprite.frames[1].image;
+        assert_eq!(second.get_pixel(0, 0).0, [0, 0, 0, 0]);
+        assert_eq!(second.get_pixel(1, 0).0, red);
+
+        assert_eq!(
+            sprite.tags,
+            vec![Tag {
+                name: "idle".into(),
+                from: 0,
+                to: 1,
+                direction: Direction::PingPong,
+                repeat: 0,
+            }]
+        );
+    }
+
+    #[test]
+    fn hidden_groups_hide_their_children() {
+        let data = file(&[frame(
+            100,
+            &[
+                layer("group", 0, LAYER_TYPE_GROUP, 0),
+                layer("child", LAYER_FLAG_VISIBLE, 0, 1),
+                cel(1, 0, 0, &raw_pixels(2, 1, [9, 9, 9, 255])),
+            ],
+        )]);
+
+        let sprite = parse_aseprite(&data).unwrap();
+        assert_eq!(sprite.frames[0].image.get_pixel(0, 0).0, [0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn rejects_non_aseprite_data() {
+        assert!(parse_aseprite(&[0u8; HEADER_SIZE]).is_err());
+    }
+}
@@ -0,0 +1,152 @@
+use crate::assets::atlas::AtlasRect;
+use image::{ImageBuffer, Rgba};
+use resvg::tiny_skia::{Pixmap, Transform};
+use resvg::usvg::{Options, Tree};
+use std::path::Path;
+
+/// Outline colors, cycled per sprite so neighbours are easy to tell apart.
+const COLORS: [&str; 6] = [
+    "#ff3b30", "#34c759", "#0a84ff", "#ff9f0a", "#bf5af2", "#64d2ff",
+];
+
+/// Drawn behind the page so transparent sprites stay visible.
+const BACKGROUND: Rgba<u8> = Rgba([32, 32, 32, 255]);
+
+/// One sprite on a preview page.
+pub struct PreviewSprite<'a> {
+    pub key: &'a str,
+    pub rect: AtlasRect,
+    pub rotated: bool,
+}
+
+/// Builds an SVG overlay for a `width`x`height` page: an outline around each
+/// sprite's rect (dashed when rotated) and its file name, clipped to the rect.
+pub fn overlay_svg(width: u32, height: u32, sprites: &[PreviewSprite]) -> String {
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\">\n",
+        width, height
+    );
+    for (i, sprite) in sprites.iter().enumerate() {
+        let color = COLORS[i % COLORS.len()];
+        let AtlasRect { x, y, w, h } = sprite.rect;
+        let dash = if sprite.rotated {
+            " stroke-dasharray=\"3 2\""
+        } else {
+            ""
+        };
+        svg.push_str(&format!(
+            "<rect x=\"{}.5\" y=\"{}.5\" width=\"{}\" height=\"{}\" fill=\"none\" stroke=\"{}\"{}/>\n",
+            x,
+            y,
+            w.saturating_sub(1),
+            h.saturating_sub(1),
+            color,
+            dash
+        ));
+
+        let name = sprite.key.rsplit('/').next().unwrap_or(sprite.key);
+        let font_size = (h / 4).clamp(6, 12);
+        svg.push_str(&format!(
+            "<svg x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\"><text x=\"2\" y=\"{}\" font-family=\"sans-serif\" font-size=\"{}\" fill=\"{}\" stroke=\"#000000\" stroke-width=\"0.5\">{}</text></svg>\n",
+            x,
+            y,
+            w,
+            h,
+            font_size + 1,
+            font_size,
+            color,
+            escape_xml(name)
+        ));
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
+
+/// Draws `overlay` over the atlas page at `page_path` on a dark background
+/// and writes the result to `output_path`.
+pub fn render_preview(
+    page_path: &Path,
+    overlay: &str,
+    output_path: &Path,
+    options: &Options,
+) -> Result<(), String> {
+    let page = image::open(page_path)
+        .map_err(|e| format!("Failed to read {}: {}", page_path.display(), e))?
+        .to_rgba8();
+    let tree = Tree::from_data(overlay.as_bytes(), options).map_err(|e| e.to_string())?;
+    let mut pixmap = Pixmap::new(page.width(), page.height())
+        .ok_or_else(|| format!("Invalid page size {}x{}", page.width(), page.height()))?;
+    resvg::render(&tree, Transform::identity(), &mut pixmap.as_mut());
+
+    let mut preview = ImageBuffer::from_pixel(page.width(), page.height(), BACKGROUND);
+    for (x, y, pixel) in preview.enumerate_pixels_mut() {
+        blend(pixel, *page.get_pixel(x, y));
+        if let Some(overlay) = pixmap.pixel(x, y) {
+            let color = overlay.demultiply();
+            blend(
+                pixel,
+                Rgba([color.red(), color.green(), color.blue(), color.alpha()]),
+            );
+        }
+    }
+    preview
+        .save(output_path)
+        .map_err(|e| format!("Failed to write {}: {}", output_path.display(), e))
+}
+
+/// Source-over blend of `src` onto an opaque `dst`.
+fn blend(dst: &mut Rgba<u8>, src: Rgba<u8>) {
+    let alpha = src[3] as u32;
+    for c in 0..3 {
+        dst[c] = ((src[c] as u32 * alpha + dst[c] as u32 * (255 - alpha) + 127) / 255) as u8;
+    }
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn outlines_each_sprite() {
+        let sprites = [
+            PreviewSprite {
+                key: "ui/a&b.png",
+                rect: AtlasRect {
+                    x: 2,
+                    y: 2,
+                    w: 8,
+                    h: 8,
+                },
+                rotated: false,
+            },
+            PreviewSprite {
+                key: "ui/tall.png",
+                rect: AtlasRect {
+                    x: 12,
+                    y: 2,
+                    w: 8,
+                    h: 4,
+                },
+                rotated: true,
+            },
+        ];
+        let svg = overlay_svg(32, 32, &sprites);
+        assert!(svg.contains("<rect x=\"2.5\" y=\"2.5\" width=\"7\" height=\"7\""));
+        assert!(svg.contains(">a&amp;b.png</text>"));
+        assert_eq!(svg.matches("stroke-dasharray").count(), 1);
+
+        let tree = Tree::from_data(svg.as_bytes(), &Options::default()).unwrap();
+        let mut pixmap = Pixmap::new(32, 32).unwrap();
+        resvg::render(&tree, Transform::identity(), &mut pixmap.as_mut());
+        let edge = pixmap.pixel(2, 5).unwrap();
+        assert!(edge.alpha() > 0 && edge.red() > edge.blue());
+        assert_eq!(pixmap.pixel(30, 30).unwrap().alpha(), 0);
+    }
+}
@@ -0,0 +1,75 @@
+use image::RgbaImage;
+
+/// Luau loader that decodes raw image modules into EditableImages.
+pub const LOADER_LUAU: &str = include_str!("buffer_loader.luau");
+
+/// TypeScript declarations for [`LOADER_LUAU`].
+pub const LOADER_DTS: &str = include_str!("buffer_loader.d.ts");
+
+/// TypeScript declaration shared by every raw image module.
+pub const IMAGE_DTS: &str = "// This file is automatically @generated by truffle.\n\
+                             // DO NOT EDIT MANUALLY.\n\n\
+                             declare const image: { width: number; height: number; data: string };\n\
+                             export = image;\n";
+
+const BASE64_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+fn base64_encode(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let n = (u32::from(chunk[0]) << 16)
+            | (u32::from(*chunk.get(1).unwrap_or(&0)) << 8)
+            | u32::from(*chunk.get(2).unwrap_or(&0));
+        for (i, shift) in [18, 12, 6, 0].into_iter().enumerate() {
+            if i <= chunk.len() {
+                out.push(BASE64_ALPHABET[(n >> shift) as usize & 63] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+/// Render an image as a Luau module holding its RGBA8 pixels.
+///
+/// Pixels are row-major from the top-left, which is the layout
+/// `EditableImage:WritePixelsBuffer` expects.
+pub fn render_image_module(image: &RgbaImage) -> String {
+    format!(
+        "-- This file is automatically @generated by truffle.\n\
+         -- DO NOT EDIT MANUALLY.\n\n\
+         return {{\n\
+         \twidth = {},\n\
+         \theight = {},\n\
+         \t-- Base64-encoded RGBA8 pixels\n\
+         \tdata = \"{}\",\n\
+         }}\n",
+        image.width(),
+        image.height(),
+        base64_encode(image.as_raw())
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgba;
+
+    #[test]
+    fn base64_pads_partial_chunks() {
+        assert_eq!(base64_encode(b"Man"), "TWFu");
+        assert_eq!(base64_encode(b"Ma"), "TWE=");
+        assert_eq!(base64_encode(b"M"), "TQ==");
+        assert_eq!(base64_encode(b""), "");
+    }
+
+    #[test]
+    fn module_contains_size_and_pixels() {
+        let image = RgbaImage::from_pixel(1, 1, Rgba([255, 0, 0, 255]));
+        let module = render_image_module(&image);
+        assert!(module.contains("width = 1,"));
+        assert!(module.contains("data = \"/wAA/w==\","));
+    }
+}
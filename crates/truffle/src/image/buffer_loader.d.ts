@@ -0,0 +1,11 @@
+// This file is automatically @generated by truffle.
+// DO NOT EDIT MANUALLY.
+
+export interface RawImage {
+	width: number;
+	height: number;
+	data: string;
+}
+
+export declare function toBuffer(image: RawImage): buffer;
+export declare function create(image: RawImage): EditableImage;
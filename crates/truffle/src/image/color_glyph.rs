@@ -0,0 +1,236 @@
+use image::imageops::FilterType;
+use ttf_parser::colr::{ClipBox, CompositeMode, Paint, Painter};
+use ttf_parser::{Face, GlyphId, RasterImageFormat, RgbaColor, Transform};
+
+/// The color version of a glyph, placed like fontdue's coverage bitmaps:
+/// `xmin` and `ymin` are its bottom-left corner relative to the pen on the
+/// baseline (y up).
+pub struct ColorGlyph {
+    pub xmin: i32,
+    pub ymin: i32,
+    pub width: u32,
+    pub height: u32,
+    /// Straight (not premultiplied) RGBA rows, top to bottom.
+    pub rgba: Vec<u8>,
+}
+
+impl ColorGlyph {
+    /// The alpha channel, for the effects that work on coverage.
+    pub fn alpha(&self) -> Vec<u8> {
+        self.rgba.chunks_exact(4).map(|p| p[3]).collect()
+    }
+}
+
+/// Renders `glyph` in color at `px`: its COLR layers painted with `font`,
+/// or its sbix/CBDT bitmap scaled to size. `None` for glyphs without color
+/// data.
+pub fn rasterize(face: &Face, font: &fontdue::Font, glyph: GlyphId, px: f32) -> Option<ColorGlyph> {
+    if face.is_color_glyph(glyph) {
+        return paint_layers(face, font, glyph, px);
+    }
+    embedded_bitmap(face, glyph, px)
+}
+
+fn embedded_bitmap(face: &Face, glyph: GlyphId, px: f32) -> Option<ColorGlyph> {
+    let ppem = px.round().clamp(1.0, u16::MAX as f32) as u16;
+    let raster = face.glyph_raster_image(glyph, ppem)?;
+    let decoded = match raster.format {
+        RasterImageFormat::PNG => {
+            image::load_from_memory_with_format(raster.data, image::ImageFormat::Png)
+                .ok()?
+                .to_rgba8()
+        }
+        RasterImageFormat::BitmapPremulBgra32 => {
+            let rgba = raster
+                .data
+                .chunks_exact(4)
+                .flat_map(|p| {
+                    let [b, g, r, a] = [p[0], p[1], p[2], p[3]];
+                    let unpremultiply = |c: u8| match a {
+                        0 => 0,
+                        _ => (c as u32 * 255 / a as u32).min(255) as u8,
+                    };
+                    [unpremultiply(r), unpremultiply(g), unpremultiply(b), a]
+                })
+                .collect();
+            image::RgbaImage::from_raw(raster.width as u32, raster.height as u32, rgba)?
+        }
+        // Monochrome and grayscale strikes have no color to keep.
+        _ => return None,
+    };
+
+    // Strikes come in a few fixed sizes; scale the nearest one to `px`.
+    let scale = px / raster.pixels_per_em.max(1) as f32;
+    let width = ((decoded.width() as f32 * scale).round() as u32).max(1);
+    let height = ((decoded.height() as f32 * scale).round() as u32).max(1);
+    let resized = if (width, height) == decoded.dimensions() {
+        decoded
+    } else {
+        image::imageops::resize(&decoded, width, height, FilterType::Triangle)
+    };
+    Some(ColorGlyph {
+        xmin: (raster.x as f32 * scale).round() as i32,
+        ymin: (raster.y as f32 * scale).round() as i32,
+        width,
+        height,
+        rgba: resized.into_raw(),
+    })
+}
+
+/// Collects the solid-filled layers of a COLR glyph, rasterized with fontdue.
+struct LayerPainter<'f> {
+    font: &'f fontdue::Font,
+    px: f32,
+    outline: Option<GlyphId>,
+    layers: Vec<(fontdue::Metrics, Vec<u8>, RgbaColor)>,
+}
+
+impl<'a> Painter<'a> for LayerPainter<'_> {
+    fn outline_glyph(&mut self, glyph_id: GlyphId) {
+        self.outline = Some(glyph_id);
+    }
+
+    fn paint(&mut self, paint: Paint<'a>) {
+        // COLRv0 layers are all solid; gradients, clips and transforms of
+        // COLRv1 glyphs aren't supported.
+        let (Some(outline), Paint::Solid(color)) = (self.outline, paint) else {
+            return;
+        };
+        let (metrics, coverage) = self.font.rasterize_indexed(outline.0, self.px);
+        if metrics.width > 0 && metrics.height > 0 {
+            self.layers.push((metrics, coverage, color));
+        }
+    }
+
+    fn push_clip(&mut self) {}
+    fn push_clip_box(&mut self, _: ClipBox) {}
+    fn pop_clip(&mut self) {}
+    fn push_layer(&mut self, _: CompositeMode) {}
+    fn pop_layer(&mut self) {}
+    fn push_translate(&mut self, _: f32, _: f32) {}
+    fn push_scale(&mut self, _: f32, _: f32) {}
+    fn push_rotate(&mut self, _: f32) {}
+    fn push_skew(&mut self, _: f32, _: f32) {}
+    fn push_transform(&mut self, _: Transform) {}
+    fn pop_transform(&mut self) {}
+}
+
+fn paint_layers(face: &Face, font: &fontdue::Font, glyph: GlyphId, px: f32) -> Option<ColorGlyph> {
+    let mut painter = LayerPainter {
+        font,
+        px,
+        outline: None,
+        layers: Vec::new(),
+    };
+    // Layers in the text color are painted white, so they can still be tinted.
+    let foreground = RgbaColor::new(255, 255, 255, 255);
+    face.paint_color_glyph(glyph, 0, foreground, &mut painter)?;
+    composite_layers(&painter.layers)
+}
+
+/// Draws `layers` over each other, bottom layer first, into one bitmap
+/// covering all of them.
+fn composite_layers(layers: &[(fontdue::Metrics, Vec<u8>, RgbaColor)]) -> Option<ColorGlyph> {
+    let xmin = layers.iter().map(|(m, _, _)| m.xmin).min()?;
+    let ymin = layers.iter().map(|(m, _, _)| m.ymin).min()?;
+    let xmax = layers
+        .iter()
+        .map(|(m, _, _)| m.xmin + m.width as i32)
+        .max()?;
+    let ymax = layers
+        .iter()
+        .map(|(m, _, _)| m.ymin + m.height as i32)
+        .max()?;
+    let (width, height) = ((xmax - xmin) as u32, (ymax - ymin) as u32);
+
+    // Premultiplied, so layers blend with plain source-over.
+    let mut canvas = vec![[0f32; 4]; (width * height) as usize];
+    for (metrics, coverage, color) in layers {
+        let left = (metrics.xmin - xmin) as u32;
+        let top = (ymax - metrics.ymin - metrics.height as i32) as u32;
+        for y in 0..metrics.height as u32 {
+            for x in 0..metrics.width as u32 {
+                let a = coverage[(y * metrics.width as u32 + x) as usize] as f32 / 255.0
+                    * color.alpha as f32
+                    / 255.0;
+                if a == 0.0 {
+                    continue;
+                }
+                let dst = &mut canvas[((top + y) * width + left + x) as usize];
+                let src = [color.red, color.green, color.blue].map(|c| c as f32 / 255.0 * a);
+                for c in 0..3 {
+                    dst[c] = src[c] + dst[c] * (1.0 - a);
+                }
+                dst[3] = a + dst[3] * (1.0 - a);
+            }
+        }
+    }
+
+    let rgba = canvas
+        .iter()
+        .flat_map(|[r, g, b, a]| {
+            let straight = |c: f32| match *a {
+                0.0 => 0,
+                _ => ((c / a).clamp(0.0, 1.0) * 255.0).round() as u8,
+            };
+            [
+                straight(*r),
+                straight(*g),
+                straight(*b),
+                (a * 255.0).round() as u8,
+            ]
+        })
+        .collect();
+    Some(ColorGlyph {
+        xmin,
+        ymin,
+        width,
+        height,
+        rgba,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn layers_composite_bottom_first() {
+        let layer = |xmin, ymin, color| {
+            let metrics = fontdue::Metrics {
+                xmin,
+                ymin,
+                width: 2,
+                height: 2,
+                ..Default::default()
+            };
+            (metrics, vec![255; 4], color)
+        };
+        let glyph = composite_layers(&[
+            layer(0, 0, RgbaColor::new(255, 0, 0, 255)),
+            layer(1, 1, RgbaColor::new(0, 0, 255, 128)),
+        ])
+        .unwrap();
+        assert_eq!(
+            (glyph.xmin, glyph.ymin, glyph.width, glyph.height),
+            (0, 0, 3, 3)
+        );
+
+        let pixel = |x: u32, y: u32| {
+            let i = ((y * 3 + x) * 4) as usize;
+            [
+                glyph.rgba[i],
+                glyph.rgba[i + 1],
+                glyph.rgba[i + 2],
+                glyph.rgba[i + 3],
+            ]
+        };
+        // The bottom layer sits in the lower left, y down in the bitmap.
+        assert_eq!(pixel(0, 2), [255, 0, 0, 255]);
+        assert_eq!(pixel(2, 0), [0, 0, 255, 128]);
+        assert_eq!(pixel(0, 0), [0, 0, 0, 0]);
+        // Half-transparent blue over red.
+        assert_eq!(pixel(1, 1), [127, 0, 128, 255]);
+        assert_eq!(glyph.alpha()[4], 255);
+    }
+}
@@ -0,0 +1,47 @@
+use image::Rgba;
+
+/// Composite `src` over `dst` using normal (source-over) blending, with the
+/// source alpha scaled by `opacity` (0–255).
+pub fn blend_over(dst: &mut Rgba<u8>, src: [u8; 4], opacity: u32) {
+    let src_alpha = src[3] as u32 * opacity / 255;
+    if src_alpha == 0 {
+        return;
+    }
+
+    let dst_alpha = dst.0[3] as u32;
+    let out_alpha = src_alpha + dst_alpha * (255 - src_alpha) / 255;
+    for (dst_channel, src_channel) in dst.0.iter_mut().zip(src).take(3) {
+        let blended = (src_channel as u32 * src_alpha
+            + *dst_channel as u32 * dst_alpha * (255 - src_alpha) / 255)
+            / out_alpha;
+        *dst_channel = blended as u8;
+    }
+    dst.0[3] = out_alpha as u8;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn opaque_source_replaces_destination() {
+        let mut dst = Rgba([10, 20, 30, 255]);
+        blend_over(&mut dst, [200, 100, 50, 255], 255);
+        assert_eq!(dst.0, [200, 100, 50, 255]);
+    }
+
+    #[test]
+    fn half_opacity_mixes_with_destination() {
+        let mut dst = Rgba([0, 0, 0, 255]);
+        blend_over(&mut dst, [255, 255, 255, 255], 128);
+        assert_eq!(dst.0[3], 255);
+        assert!((127..=129).contains(&dst.0[0]));
+    }
+
+    #[test]
+    fn transparent_destination_takes_source_color() {
+        let mut dst = Rgba([0, 0, 0, 0]);
+        blend_over(&mut dst, [40, 80, 120, 100], 255);
+        assert_eq!(dst.0, [40, 80, 120, 100]);
+    }
+}
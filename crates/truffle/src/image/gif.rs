@@ -0,0 +1,64 @@
+use image::codecs::gif::GifDecoder;
+use image::{AnimationDecoder, RgbaImage};
+use std::io::BufReader;
+use std::path::Path;
+
+/// A fully composited GIF frame and how long it stays on screen.
+pub struct GifFrame {
+    pub image: RgbaImage,
+    pub duration_ms: u32,
+}
+
+/// Decode every frame of an animated GIF at canvas size.
+pub fn read_gif(path: &Path) -> Result<Vec<GifFrame>, String> {
+    let file = std::fs::File::open(path)
+        .map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    let decoder = GifDecoder::new(BufReader::new(file))
+        .map_err(|e| format!("Failed to decode {}: {}", path.display(), e))?;
+
+    decoder
+        .into_frames()
+        .map(|frame| {
+            let frame = frame.map_err(|e| format!("Failed to decode {}: {}", path.display(), e))?;
+            let (numer, denom) = frame.delay().numer_denom_ms();
+            Ok(GifFrame {
+                duration_ms: numer / denom.max(1),
+                image: frame.into_buffer(),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::codecs::gif::GifEncoder;
+    use image::{Delay, Frame, Rgba};
+
+    #[test]
+    fn reads_frames_and_delays() {
+        let dir = std::env::temp_dir().join(format!("truffle-gif-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("blink.gif");
+
+        let frames = [Rgba([255, 0, 0, 255]), Rgba([0, 0, 255, 255])]
+            .into_iter()
+            .map(|color| {
+                Frame::from_parts(
+                    RgbaImage::from_pixel(4, 4, color),
+                    0,
+                    0,
+                    Delay::from_numer_denom_ms(120, 1),
+                )
+            });
+        let file = std::fs::File::create(&path).unwrap();
+        GifEncoder::new(file).encode_frames(frames).unwrap();
+
+        let frames = read_gif(&path).unwrap();
+        assert_eq!(frames.len(), 2);
+        assert_eq!(frames[0].duration_ms, 120);
+        assert_eq!(frames[1].image.get_pixel(0, 0).0[2], 255);
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
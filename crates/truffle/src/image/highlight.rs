@@ -1,7 +1,18 @@
 use image::{ImageBuffer, Rgba, RgbaImage};
 use std::path::Path;
 
-/// Generate a highlight variant for the provided PNG image.
+/// The key of the highlight generated for the image `key`
+/// (`ui/play.jpg` → `ui/play-highlight.png`), or `None` when `key` is a
+/// highlight itself or not a raster image.
+pub fn highlight_key(key: &str) -> Option<String> {
+    let (stem, _) = key.rsplit_once('.')?;
+    if stem.ends_with("-highlight") || !super::is_raster_image(Path::new(key)) {
+        return None;
+    }
+    Some(format!("{}-highlight.png", stem))
+}
+
+/// Generate a highlight variant for the provided image.
 /// The algorithm mirrors the previous ImageMagick pipeline:
 /// 1. Extract the alpha mask.
 /// 2. Apply a diamond-shaped erosion to shrink the mask inward.
@@ -41,45 +52,75 @@ fn extract_alpha(image: &RgbaImage) -> Vec<u8> {
     image.pixels().map(|p| p[3]).collect()
 }
 
+/// Grayscale erosion with a diamond (Manhattan radius) structuring element;
+/// pixels outside the image count as transparent.
+///
+/// Rotating by 45° (`u = x + y`, `v = x - y`) turns the diamond into a square,
+/// which is separable into two sliding-window minimums. Runtime depends only
+/// on the image size, not on the radius.
 fn erode_diamond(mask: &[u8], width: usize, height: usize, radius: usize) -> Vec<u8> {
     if radius == 0 {
         return mask.to_vec();
     }
+    if width == 0 || height == 0 {
+        return Vec::new();
+    }
 
-    let mut eroded = vec![0u8; mask.len()];
-    let radius_i = radius as isize;
-    let width_i = width as isize;
-    let height_i = height as isize;
-
-    for y in 0..height_i {
-        for x in 0..width_i {
-            let mut min_val = u8::MAX;
-            'outer: for dy in -radius_i..=radius_i {
-                let ny = y + dy;
-                let dx_limit = radius_i - dy.abs();
-                for dx in -dx_limit..=dx_limit {
-                    let nx = x + dx;
-                    if ny < 0 || ny >= height_i || nx < 0 || nx >= width_i {
-                        min_val = 0;
-                        break 'outer;
-                    }
-                    let idx = (ny as usize) * width + (nx as usize);
-                    let val = mask[idx];
-                    if val < min_val {
-                        min_val = val;
-                        if min_val == 0 {
-                            break 'outer;
-                        }
-                    }
-                }
-            }
-            eroded[(y as usize) * width + (x as usize)] = min_val;
+    // Cells between lattice points stay at 255 so they never lower a minimum.
+    let n = width + height - 1;
+    let mut rotated = vec![u8::MAX; n * n];
+    for y in 0..height {
+        for x in 0..width {
+            rotated[(x + y) * n + (x + height - 1 - y)] = mask[y * width + x];
         }
     }
 
+    let mut rows = vec![0u8; n * n];
+    for (src, dst) in rotated.chunks_exact(n).zip(rows.chunks_exact_mut(n)) {
+        sliding_min(src, radius, dst);
+    }
+    let mut column = vec![0u8; n];
+    let mut filtered = vec![0u8; n];
+    for v in 0..n {
+        for (u, value) in column.iter_mut().enumerate() {
+            *value = rows[u * n + v];
+        }
+        sliding_min(&column, radius, &mut filtered);
+        for (u, value) in filtered.iter().enumerate() {
+            rotated[u * n + v] = *value;
+        }
+    }
+
+    let mut eroded = vec![0u8; mask.len()];
+    for y in radius..height.saturating_sub(radius) {
+        for x in radius..width.saturating_sub(radius) {
+            eroded[y * width + x] = rotated[(x + y) * n + (x + height - 1 - y)];
+        }
+    }
     eroded
 }
 
+/// Writes the minimum of `src[i - r..=i + r]` (clamped to the slice) to
+/// `out[i]`, keeping a monotonic queue of candidate indices.
+fn sliding_min(src: &[u8], r: usize, out: &mut [u8]) {
+    let mut window = std::collections::VecDeque::with_capacity(2 * r + 1);
+    let mut next = 0;
+    for (i, value) in out.iter_mut().enumerate() {
+        let end = (i + r).min(src.len() - 1);
+        while next <= end {
+            while window.back().is_some_and(|&j| src[j] >= src[next]) {
+                window.pop_back();
+            }
+            window.push_back(next);
+            next += 1;
+        }
+        while window.front().is_some_and(|&j| j + r < i) {
+            window.pop_front();
+        }
+        *value = src[window[0]];
+    }
+}
+
 fn subtract_mask(a: &[u8], b: &[u8]) -> Vec<u8> {
     a.iter()
         .zip(b.iter())
@@ -200,6 +241,56 @@ mod tests {
         }
     }
 
+    #[test]
+    fn erosion_matches_brute_force_diamond() {
+        let (width, height) = (9usize, 6usize);
+        let mask: Vec<u8> = (0..width * height)
+            .map(|i| if i % 7 == 0 { 0 } else { (i * 53 % 256) as u8 })
+            .collect();
+
+        for radius in 0..4 {
+            let eroded = erode_diamond(&mask, width, height, radius);
+            for y in 0..height as isize {
+                for x in 0..width as isize {
+                    let r = radius as isize;
+                    let mut expected = u8::MAX;
+                    for dy in -r..=r {
+                        for dx in (dy.abs() - r)..=(r - dy.abs()) {
+                            let (nx, ny) = (x + dx, y + dy);
+                            let inside = (0..width as isize).contains(&nx)
+                                && (0..height as isize).contains(&ny);
+                            let value = if inside {
+                                mask[ny as usize * width + nx as usize]
+                            } else {
+                                0
+                            };
+                            expected = expected.min(value);
+                        }
+                    }
+                    assert_eq!(
+                        eroded[y as usize * width + x as usize],
+                        expected,
+                        "({x},{y}) r={radius}"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn highlight_keys_follow_any_raster_source() {
+        assert_eq!(
+            highlight_key("ui/play.jpg").as_deref(),
+            Some("ui/play-highlight.png")
+        );
+        assert_eq!(
+            highlight_key("play.png").as_deref(),
+            Some("play-highlight.png")
+        );
+        assert_eq!(highlight_key("play-highlight.png"), None);
+        assert_eq!(highlight_key("click.ogg"), None);
+    }
+
     #[test]
     fn thicker_outline_can_consume_entire_shape() {
         let base = sample_image();
@@ -0,0 +1,314 @@
+use resvg::tiny_skia::{FillRule, Paint, PathBuilder, Pixmap, Transform};
+use ttf_parser::{Face, GlyphId, OutlineBuilder};
+
+type Point = (f32, f32);
+
+/// Vertical outline lines shorter than this many pixels aren't treated as stems.
+const MIN_STEM_LENGTH: f32 = 1.0;
+
+/// Edges closer than this many pixels may snap to the same pixel boundary;
+/// further apart, they stay at least a pixel apart so thin stems don't vanish.
+const MIN_EDGE_GAP: f32 = 0.5;
+
+/// How a glyph outline is fitted to the pixel grid and rasterized.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Rendering {
+    /// Snap the baseline, x-height, cap height, ascender and descender to whole pixels.
+    pub snap_heights: bool,
+    /// Snap the edges of vertical stems to whole pixels.
+    pub snap_stems: bool,
+    /// Render each pixel's red, green and blue stripes separately.
+    pub subpixel: bool,
+}
+
+/// A rasterized glyph, placed like fontdue's coverage bitmaps: `xmin` and
+/// `ymin` are its bottom-left corner relative to the pen on the baseline
+/// (y up).
+pub struct HintedGlyph {
+    pub xmin: i32,
+    pub ymin: i32,
+    pub width: u32,
+    pub height: u32,
+    /// Coverage rows, top to bottom.
+    pub alpha: Vec<u8>,
+    /// With subpixel rendering, straight RGBA rows holding each stripe's
+    /// coverage in its channel. Alpha is the strongest stripe, so white text
+    /// blended over a dark background lights each stripe by its coverage.
+    pub rgba: Option<Vec<u8>>,
+}
+
+/// Rasterizes the outline of `glyph` at `px`, fitted to the pixel grid as
+/// `rendering` asks. `None` for glyphs without an outline.
+pub fn rasterize(
+    face: &Face,
+    glyph: GlyphId,
+    px: f32,
+    rendering: Rendering,
+) -> Option<HintedGlyph> {
+    let mut outline = Outline::default();
+    face.outline_glyph(glyph, &mut outline)?;
+    let scale = px / face.units_per_em() as f32;
+
+    let heights = if rendering.snap_heights {
+        // Older OS/2 tables leave out the x-height and cap height, so measure them.
+        let zones = [
+            Some(0),
+            face.x_height().or_else(|| glyph_top(face, 'x')),
+            face.capital_height().or_else(|| glyph_top(face, 'H')),
+            Some(face.ascender()),
+            Some(face.descender()),
+        ];
+        GridMap::new(zones.into_iter().flatten().map(|h| h as f32 * scale))
+    } else {
+        GridMap::default()
+    };
+    let stems = if rendering.snap_stems {
+        GridMap::new(std::iter::once(0.0).chain(outline.stem_edges(scale)))
+    } else {
+        GridMap::default()
+    };
+    let fit = |(x, y): Point| (stems.apply(x * scale), heights.apply(y * scale));
+
+    let points: Vec<Point> = outline.points().map(fit).collect();
+    if points.is_empty() {
+        return None;
+    }
+    let (mut x0, mut y0, mut x1, mut y1) = (f32::MAX, f32::MAX, f32::MIN, f32::MIN);
+    for (x, y) in &points {
+        (x0, y0, x1, y1) = (x0.min(*x), y0.min(*y), x1.max(*x), y1.max(*y));
+    }
+    let (xmin, ymin) = (x0.floor() as i32, y0.floor() as i32);
+    let (xmax, ymax) = (x1.ceil() as i32, y1.ceil() as i32);
+    let (width, height) = ((xmax - xmin) as u32, (ymax - ymin) as u32);
+    if width == 0 || height == 0 {
+        return None;
+    }
+
+    // Subpixel rendering draws three samples per pixel plus one on either
+    // side for the filter below.
+    let (stripes, pad) = if rendering.subpixel { (3, 1) } else { (1, 0) };
+    let to_pixmap = |p: Point| {
+        let (x, y) = fit(p);
+        (
+            (x - xmin as f32) * stripes as f32 + pad as f32,
+            ymax as f32 - y,
+        )
+    };
+    let mut path = PathBuilder::new();
+    for segment in &outline.segments {
+        match *segment {
+            Segment::Move(p) => {
+                let (x, y) = to_pixmap(p);
+                path.move_to(x, y);
+            }
+            Segment::Line(p) => {
+                let (x, y) = to_pixmap(p);
+                path.line_to(x, y);
+            }
+            Segment::Quad(p1, p) => {
+                let ((x1, y1), (x, y)) = (to_pixmap(p1), to_pixmap(p));
+                path.quad_to(x1, y1, x, y);
+            }
+            Segment::Cubic(p1, p2, p) => {
+                let ((x1, y1), (x2, y2), (x, y)) = (to_pixmap(p1), to_pixmap(p2), to_pixmap(p));
+                path.cubic_to(x1, y1, x2, y2, x, y);
+            }
+            Segment::Close => path.close(),
+        }
+    }
+    let path = path.finish()?;
+    let samples_w = width * stripes + 2 * pad;
+    let mut pixmap = Pixmap::new(samples_w, height)?;
+    let mut paint = Paint::default();
+    paint.set_color_rgba8(255, 255, 255, 255);
+    paint.anti_alias = true;
+    pixmap.fill_path(
+        &path,
+        &paint,
+        FillRule::Winding,
+        Transform::identity(),
+        None,
+    );
+    let samples: Vec<u8> = pixmap.pixels().iter().map(|p| p.alpha()).collect();
+
+    if !rendering.subpixel {
+        return Some(HintedGlyph {
+            xmin,
+            ymin,
+            width,
+            height,
+            alpha: samples,
+            rgba: None,
+        });
+    }
+
+    // Each stripe averages its own sample with its neighbours, which keeps
+    // color fringes down.
+    let mut alpha = Vec::with_capacity((width * height) as usize);
+    let mut rgba = Vec::with_capacity((width * height * 4) as usize);
+    for row in samples.chunks_exact(samples_w as usize) {
+        for x in 0..width as usize {
+            let stripe = |c: usize| {
+                let window = &row[3 * x + c..3 * x + c + 3];
+                ((window.iter().map(|&s| s as u32).sum::<u32>() + 1) / 3) as u8
+            };
+            let channels = [stripe(0), stripe(1), stripe(2)];
+            let a = channels.into_iter().max().unwrap_or(0);
+            alpha.push(a);
+            rgba.extend(channels.map(|c| match a {
+                0 => 0,
+                _ => (c as u32 * 255 / a as u32) as u8,
+            }));
+            rgba.push(a);
+        }
+    }
+    Some(HintedGlyph {
+        xmin,
+        ymin,
+        width,
+        height,
+        alpha,
+        rgba: Some(rgba),
+    })
+}
+
+/// The top of the outline of `ch`, in font units.
+fn glyph_top(face: &Face, ch: char) -> Option<i16> {
+    face.glyph_bounding_box(face.glyph_index(ch)?)
+        .map(|bbox| bbox.y_max)
+}
+
+/// A piecewise linear remapping of one axis that moves each snapped edge
+/// onto a pixel boundary and stretches the outline between them.
+#[derive(Default)]
+struct GridMap {
+    /// Edge positions and where they snap to, sorted.
+    edges: Vec<(f32, f32)>,
+}
+
+impl GridMap {
+    fn new(edges: impl IntoIterator<Item = f32>) -> Self {
+        let mut sorted: Vec<f32> = edges.into_iter().collect();
+        sorted.sort_by(f32::total_cmp);
+        sorted.dedup_by(|a, b| (*a - *b).abs() < 0.01);
+
+        let mut snapped: Vec<(f32, f32)> = Vec::with_capacity(sorted.len());
+        for edge in sorted {
+            let mut target = edge.round();
+            if let Some(&(previous, previous_target)) = snapped.last() {
+                if edge - previous >= MIN_EDGE_GAP {
+                    target = target.max(previous_target + 1.0);
+                }
+                target = target.max(previous_target);
+            }
+            snapped.push((edge, target));
+        }
+        Self { edges: snapped }
+    }
+
+    fn apply(&self, v: f32) -> f32 {
+        let (Some(first), Some(last)) = (self.edges.first(), self.edges.last()) else {
+            return v;
+        };
+        if v <= first.0 {
+            return v - first.0 + first.1;
+        }
+        if v >= last.0 {
+            return v - last.0 + last.1;
+        }
+        let i = self.edges.partition_point(|(edge, _)| *edge <= v);
+        let ((a, a_to), (b, b_to)) = (self.edges[i - 1], self.edges[i]);
+        a_to + (v - a) / (b - a) * (b_to - a_to)
+    }
+}
+
+enum Segment {
+    Move(Point),
+    Line(Point),
+    Quad(Point, Point),
+    Cubic(Point, Point, Point),
+    Close,
+}
+
+/// A glyph outline in font units.
+#[derive(Default)]
+struct Outline {
+    segments: Vec<Segment>,
+}
+
+impl Outline {
+    fn points(&self) -> impl Iterator<Item = Point> + '_ {
+        self.segments.iter().flat_map(|segment| match *segment {
+            Segment::Move(p) | Segment::Line(p) => vec![p],
+            Segment::Quad(p1, p) => vec![p1, p],
+            Segment::Cubic(p1, p2, p) => vec![p1, p2, p],
+            Segment::Close => Vec::new(),
+        })
+    }
+
+    /// The x of every vertical line at least [`MIN_STEM_LENGTH`] pixels long,
+    /// in pixels.
+    fn stem_edges(&self, scale: f32) -> Vec<f32> {
+        let mut edges = Vec::new();
+        let mut last = (0.0, 0.0);
+        for segment in &self.segments {
+            match *segment {
+                Segment::Move(p) => last = p,
+                Segment::Line(p) => {
+                    let (dx, dy) = (p.0 - last.0, p.1 - last.1);
+                    if dx.abs() <= dy.abs() * 0.05 && dy.abs() * scale >= MIN_STEM_LENGTH {
+                        edges.push((p.0 + last.0) / 2.0 * scale);
+                    }
+                    last = p;
+                }
+                Segment::Quad(_, p) | Segment::Cubic(_, _, p) => last = p,
+                Segment::Close => {}
+            }
+        }
+        edges
+    }
+}
+
+impl OutlineBuilder for Outline {
+    fn move_to(&mut self, x: f32, y: f32) {
+        self.segments.push(Segment::Move((x, y)));
+    }
+
+    fn line_to(&mut self, x: f32, y: f32) {
+        self.segments.push(Segment::Line((x, y)));
+    }
+
+    fn quad_to(&mut self, x1: f32, y1: f32, x: f32, y: f32) {
+        self.segments.push(Segment::Quad((x1, y1), (x, y)));
+    }
+
+    fn curve_to(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, x: f32, y: f32) {
+        self.segments
+            .push(Segment::Cubic((x1, y1), (x2, y2), (x, y)));
+    }
+
+    fn close(&mut self) {
+        self.segments.push(Segment::Close);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn grid_map_snaps_edges_and_keeps_stems() {
+        // A 0.8px stem from 2.6 to 3.4 would round to nothing.
+        let map = GridMap::new([0.0, 3.4, 2.6]);
+        assert_eq!(map.edges, vec![(0.0, 0.0), (2.6, 3.0), (3.4, 4.0)]);
+        assert_eq!(map.apply(2.6), 3.0);
+        assert!((map.apply(3.0) - 3.5).abs() < 1e-5);
+        assert!((map.apply(5.4) - 6.0).abs() < 1e-5);
+        assert_eq!(map.apply(-1.0), -1.0);
+
+        // Edges under half a pixel apart may share a pixel boundary.
+        let map = GridMap::new([1.1, 1.3]);
+        assert_eq!(map.edges, vec![(1.1, 1.0), (1.3, 1.0)]);
+        assert_eq!(GridMap::default().apply(1.7), 1.7);
+    }
+}
@@ -1,3 +1,31 @@
+pub mod animation;
+pub mod aseprite;
+pub mod atlas_preview;
+pub mod buffer;
+pub mod color_glyph;
+pub mod composite;
+pub mod gif;
 pub mod highlight;
+pub mod hinting;
 pub mod palette;
+pub mod psd;
+pub mod sdf;
+pub mod svg;
 pub mod terrain;
+
+use std::path::Path;
+
+/// Raster formats accepted as image sources. Roblox takes PNG, JPEG and BMP
+/// as they are; WebP is converted to PNG when it is uploaded.
+pub const RASTER_EXTENSIONS: [&str; 5] = ["png", "jpg", "jpeg", "webp", "bmp"];
+
+/// Whether `path` has one of the [`RASTER_EXTENSIONS`], in any case.
+pub fn is_raster_image(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| {
+            RASTER_EXTENSIONS
+                .iter()
+                .any(|raster| ext.eq_ignore_ascii_case(raster))
+        })
+}
@@ -18,7 +18,11 @@ pub fn load_palette_colors(palette_path: &Path) -> Result<Vec<[u8; 3]>, String>
     Ok(colors)
 }
 
-pub fn apply_palette_to_path(image_path: &Path, palette_colors: &[[u8; 3]]) -> Result<(), String> {
+pub fn apply_palette_to_path(
+    image_path: &Path,
+    output_path: &Path,
+    palette_colors: &[[u8; 3]],
+) -> Result<(), String> {
     if palette_colors.is_empty() {
         return Err("Palette contains no colors".into());
     }
@@ -28,8 +32,97 @@ pub fn apply_palette_to_path(image_path: &Path, palette_colors: &[[u8; 3]]) -> R
         .to_rgba8();
     let output = apply_palette(&source, palette_colors);
     output
-        .save(image_path)
-        .map_err(|e| format!("Failed to write image {}: {}", image_path.display(), e))
+        .save(output_path)
+        .map_err(|e| format!("Failed to write image {}: {}", output_path.display(), e))
+}
+
+/// Picks up to `count` representative colors of the visible pixels by median
+/// cut: the pixels are repeatedly split at the median of the box with the
+/// widest channel range, and each box contributes its average color. Colors
+/// are ordered by how many pixels they stand for, most common first.
+pub fn extract_palette(image: &RgbaImage, count: usize) -> Vec<[u8; 3]> {
+    let pixels: Vec<[u8; 3]> = image
+        .pixels()
+        .filter(|pixel| pixel[3] != 0)
+        .map(|pixel| [pixel[0], pixel[1], pixel[2]])
+        .collect();
+    if pixels.is_empty() || count == 0 {
+        return Vec::new();
+    }
+
+    let mut boxes = vec![pixels];
+    while boxes.len() < count {
+        let Some((index, channel)) = boxes
+            .iter()
+            .enumerate()
+            .map(|(index, pixels)| {
+                let (channel, range) = widest_channel(pixels);
+                (index, channel, range)
+            })
+            .filter(|&(_, _, range)| range > 0)
+            .max_by_key(|&(_, _, range)| range)
+            .map(|(index, channel, _)| (index, channel))
+        else {
+            break;
+        };
+
+        let mut pixels = boxes.swap_remove(index);
+        pixels.sort_unstable_by_key(|color| color[channel]);
+        // Split at the median, moved off a run of equal values so both
+        // halves stay non-empty and distinct
+        let mut median = pixels.len() / 2;
+        let value = pixels[median][channel];
+        if pixels[0][channel] == value {
+            median = pixels.partition_point(|color| color[channel] <= value);
+        } else {
+            median = pixels.partition_point(|color| color[channel] < value);
+        }
+        let upper = pixels.split_off(median);
+        boxes.push(pixels);
+        boxes.push(upper);
+    }
+
+    boxes.sort_by_key(|pixels| std::cmp::Reverse(pixels.len()));
+    let mut seen = HashSet::new();
+    boxes
+        .iter()
+        .map(|pixels| average_color(pixels))
+        .filter(|color| seen.insert(*color))
+        .collect()
+}
+
+/// Writes `colors` as a one-row palette PNG, one opaque pixel per color.
+pub fn write_palette(colors: &[[u8; 3]], path: &Path) -> Result<(), String> {
+    let image = RgbaImage::from_fn(colors.len() as u32, 1, |x, _| {
+        let [r, g, b] = colors[x as usize];
+        Rgba([r, g, b, 255])
+    });
+    image
+        .save(path)
+        .map_err(|e| format!("Failed to write palette {}: {}", path.display(), e))
+}
+
+fn widest_channel(pixels: &[[u8; 3]]) -> (usize, u8) {
+    (0..3)
+        .map(|channel| {
+            let (min, max) = pixels.iter().fold((u8::MAX, u8::MIN), |(min, max), color| {
+                (min.min(color[channel]), max.max(color[channel]))
+            });
+            (channel, max - min)
+        })
+        .max_by_key(|&(_, range)| range)
+        .unwrap_or((0, 0))
+}
+
+fn average_color(pixels: &[[u8; 3]]) -> [u8; 3] {
+    let len = pixels.len() as u64;
+    let mut sum = [0u64; 3];
+    for color in pixels {
+        for channel in 0..3 {
+            sum[channel] += color[channel] as u64;
+        }
+    }
+    sum.map(|total| ((total + len / 2) / len) as u8)
 }
 
 fn collect_palette_colors(palette_image: &RgbaImage) -> Vec<[u8; 3]> {
@@ -130,9 +223,37 @@ mod tests {
         assert!(colors.is_empty());
     }
 
+    #[test]
+    fn extracts_dominant_colors() {
+        let source = ImageBuffer::from_fn(10, 1, |x, _| match x {
+            0..=5 => Rgba([200, 10, 10, 255]),
+            6..=8 => Rgba([10, 10, 200, 255]),
+            _ => Rgba([10, 200, 10, 0]),
+        });
+
+        assert_eq!(
+            extract_palette(&source, 16),
+            vec![[200, 10, 10], [10, 10, 200]]
+        );
+        assert_eq!(extract_palette(&source, 1), vec![[137, 10, 73]]);
+    }
+
+    #[test]
+    fn extracted_palette_round_trips_through_a_palette_png() {
+        let source = ImageBuffer::from_fn(4, 4, |x, y| Rgba([x as u8 * 60, y as u8 * 60, 90, 255]));
+        let colors = extract_palette(&source, 4);
+        assert_eq!(colors.len(), 4);
+
+        let path = std::env::temp_dir().join(format!("truffle-palette-{}.png", std::process::id()));
+        write_palette(&colors, &path).unwrap();
+        assert_eq!(load_palette_colors(&path).unwrap(), colors);
+        std::fs::remove_file(&path).unwrap();
+    }
+
     #[test]
     fn empty_palette_validation_errors() {
-        let err = apply_palette_to_path(Path::new("ignored.png"), &[]).unwrap_err();
+        let err = apply_palette_to_path(Path::new("ignored.png"), Path::new("ignored.png"), &[])
+            .unwrap_err();
         assert!(err.contains("Palette contains no colors"));
     }
 }
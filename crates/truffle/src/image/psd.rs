@@ -0,0 +1,558 @@
+use super::composite::blend_over;
+use image::{ImageBuffer, Rgba, RgbaImage};
+use std::path::Path;
+
+const SIGNATURE: &[u8] = b"8BPS";
+const COLOR_MODE_GRAYSCALE: u16 = 1;
+const COLOR_MODE_RGB: u16 = 3;
+
+const CHANNEL_ALPHA: i16 = -1;
+const LAYER_FLAG_HIDDEN: u8 = 0x02;
+
+const SECTION_OPEN_FOLDER: u32 = 1;
+const SECTION_CLOSED_FOLDER: u32 = 2;
+const SECTION_DIVIDER: u32 = 3;
+
+/// A parsed Photoshop document: the merged composite plus its layer tree.
+pub struct Document {
+    pub width: u32,
+    pub height: u32,
+    /// The composite Photoshop saves alongside the layers ("maximize compatibility").
+    pub composite: RgbaImage,
+    pub groups: Vec<Group>,
+    layers: Vec<Layer>,
+}
+
+/// A layer group (folder) and the pixel layers it contains.
+pub struct Group {
+    pub name: String,
+    /// Layer indices paired with whether they are visible inside this group.
+    layers: Vec<(usize, bool)>,
+}
+
+struct Layer {
+    opacity: u8,
+    left: i32,
+    top: i32,
+    image: RgbaImage,
+}
+
+impl Document {
+    /// Composite the visible layers of `group` at document size.
+    ///
+    /// The group's own visibility is ignored, so hidden export groups still render.
+    pub fn render_group(&self, group: &Group) -> RgbaImage {
+        let mut out = ImageBuffer::from_pixel(self.width, self.height, Rgba([0, 0, 0, 0]));
+        for &(index, visible) in &group.layers {
+            if !visible {
+                continue;
+            }
+            let layer = &self.layers[index];
+            for (lx, ly, pixel) in layer.image.enumerate_pixels() {
+                let x = layer.left + lx as i32;
+                let y = layer.top + ly as i32;
+                if x < 0 || y < 0 || x >= self.width as i32 || y >= self.height as i32 {
+                    continue;
+                }
+                blend_over(
+                    out.get_pixel_mut(x as u32, y as u32),
+                    pixel.0,
+                    layer.opacity as u32,
+                );
+            }
+        }
+        out
+    }
+}
+
+pub fn read_psd(path: &Path) -> Result<Document, String> {
+    let data =
+        std::fs::read(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    parse_psd(&data).map_err(|e| format!("Failed to parse {}: {}", path.display(), e))
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data, pos: 0 }
+    }
+
+    fn bytes(&mut self, len: usize) -> Result<&'a [u8], String> {
+        let end = self
+            .pos
+            .checked_add(len)
+            .filter(|end| *end <= self.data.len())
+            .ok_or("unexpected end of file")?;
+        let slice = &self.data[self.pos..end];
+        self.pos = end;
+        Ok(slice)
+    }
+
+    fn skip(&mut self, len: usize) -> Result<(), String> {
+        self.bytes(len).map(|_| ())
+    }
+
+    fn u8(&mut self) -> Result<u8, String> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, String> {
+        let b = self.bytes(2)?;
+        Ok(u16::from_be_bytes([b[0], b[1]]))
+    }
+
+    fn i16(&mut self) -> Result<i16, String> {
+        Ok(self.u16()? as i16)
+    }
+
+    fn u32(&mut self) -> Result<u32, String> {
+        let b = self.bytes(4)?;
+        Ok(u32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+    }
+
+    fn i32(&mut self) -> Result<i32, String> {
+        Ok(self.u32()? as i32)
+    }
+
+    /// Read a `u32` length-prefixed section and return a reader over it.
+    fn section(&mut self) -> Result<Reader<'a>, String> {
+        let len = self.u32()? as usize;
+        Ok(Reader::new(self.bytes(len)?))
+    }
+}
+
+/// Parse an 8-bit RGB or grayscale `.psd` file.
+///
+/// Layers are composited with normal blending only; blend modes, masks,
+/// adjustment layers and layer effects are ignored.
+pub fn parse_psd(data: &[u8]) -> Result<Document, String> {
+    let mut reader = Reader::new(data);
+    if reader.bytes(4)? != SIGNATURE {
+        return Err("not a Photoshop file".into());
+    }
+    if reader.u16()? != 1 {
+        return Err("only PSD version 1 is supported (not PSB)".into());
+    }
+    reader.skip(6)?;
+    let channels = reader.u16()? as usize;
+    let height = reader.u32()?;
+    let width = reader.u32()?;
+    let depth = reader.u16()?;
+    let color_mode = reader.u16()?;
+    if depth != 8 {
+        return Err(format!("unsupported bit depth {}", depth));
+    }
+    if color_mode != COLOR_MODE_RGB && color_mode != COLOR_MODE_GRAYSCALE {
+        return Err(format!("unsupported color mode {}", color_mode));
+    }
+    let color_channels = if color_mode == COLOR_MODE_RGB { 3 } else { 1 };
+
+    reader.section()?; // color mode data
+    reader.section()?; // image resources
+
+    let mut layer_and_mask = reader.section()?;
+    let (layers, groups) = if layer_and_mask.data.is_empty() {
+        (Vec::new(), Vec::new())
+    } else {
+        let mut layer_info = layer_and_mask.section()?;
+        if layer_info.data.is_empty() {
+            (Vec::new(), Vec::new())
+        } else {
+            parse_layers(&mut layer_info, color_channels)?
+        }
+    };
+
+    let composite = parse_composite(&mut reader, width, height, channels, color_channels)?;
+
+    Ok(Document {
+        width,
+        height,
+        composite,
+        groups,
+        layers,
+    })
+}
+
+struct LayerRecord {
+    top: i32,
+    left: i32,
+    bottom: i32,
+    right: i32,
+    channels: Vec<(i16, usize)>,
+    opacity: u8,
+    hidden: bool,
+    name: String,
+    section: Option<u32>,
+}
+
+fn parse_layers(
+    reader: &mut Reader,
+    color_channels: usize,
+) -> Result<(Vec<Layer>, Vec<Group>), String> {
+    let count = reader.i16()?.unsigned_abs() as usize;
+
+    let mut records = Vec::with_capacity(count);
+    for _ in 0..count {
+        let top = reader.i32()?;
+        let left = reader.i32()?;
+        let bottom = reader.i32()?;
+        let right = reader.i32()?;
+        let channel_count = reader.u16()? as usize;
+        let mut channels = Vec::with_capacity(channel_count);
+        for _ in 0..channel_count {
+            let id = reader.i16()?;
+            let len = reader.u32()? as usize;
+            channels.push((id, len));
+        }
+        reader.skip(4 + 4)?; // blend signature and key
+        let opacity = reader.u8()?;
+        reader.skip(1)?; // clipping
+        let flags = reader.u8()?;
+        reader.skip(1)?; // filler
+
+        let mut extra = reader.section()?;
+        extra.section()?; // layer mask data
+        extra.section()?; // blending ranges
+        let name_len = extra.u8()? as usize;
+        let mut name = String::from_utf8_lossy(extra.bytes(name_len)?).into_owned();
+        // Pascal string padded to a multiple of four bytes, including the length byte.
+        let padded = (name_len + 1).div_ceil(4) * 4;
+        extra.skip(padded - name_len - 1)?;
+
+        let mut section = None;
+        while extra.data.len() - extra.pos >= 12 {
+            let signature = extra.bytes(4)?;
+            if signature != b"8BIM" && signature != b"8B64" {
+                break;
+            }
+            let key = extra.bytes(4)?;
+            let mut block = extra.section()?;
+            match key {
+                b"lsct" | b"lsdk" => section = Some(block.u32()?),
+                b"luni" => {
+                    let chars = block.u32()? as usize;
+                    let units: Vec<u16> = block
+                        .bytes(chars * 2)?
+                        .chunks_exact(2)
+                        .map(|c| u16::from_be_bytes([c[0], c[1]]))
+                        .collect();
+                    name = String::from_utf16_lossy(&units)
+                        .trim_end_matches('\0')
+                        .to_string();
+                }
+                _ => {}
+            }
+        }
+
+        records.push(LayerRecord {
+            top,
+            left,
+            bottom,
+            right,
+            channels,
+            opacity,
+            hidden: flags & LAYER_FLAG_HIDDEN != 0,
+            name,
+            section,
+        });
+    }
+
+    // Channel image data follows all records, in the same order.
+    let mut layers = Vec::new();
+    let mut layer_index = Vec::with_capacity(records.len());
+    for record in &records {
+        let width = (record.right - record.left).max(0) as u32;
+        let height = (record.bottom - record.top).max(0) as u32;
+        let mut image = ImageBuffer::from_pixel(width, height, Rgba([0, 0, 0, 255]));
+
+        for &(id, len) in &record.channels {
+            let mut channel = Reader::new(reader.bytes(len)?);
+            if len < 2 || !(CHANNEL_ALPHA..color_channels as i16).contains(&id) {
+                continue;
+            }
+            let compression = channel.u16()?;
+            let plane = decode_plane(&mut channel, compression, width, height)?;
+            let target = if id == CHANNEL_ALPHA { 3 } else { id as usize };
+            for (pixel, value) in image.pixels_mut().zip(plane) {
+                if color_channels == 1 && target == 0 {
+                    pixel.0[0] = value;
+                    pixel.0[1] = value;
+                    pixel.0[2] = value;
+                } else {
+                    pixel.0[target] = value;
+                }
+            }
+        }
+
+        if record.section.is_some() || width == 0 || height == 0 {
+            layer_index.push(None);
+            continue;
+        }
+        layer_index.push(Some(layers.len()));
+        layers.push(Layer {
+            opacity: record.opacity,
+            left: record.left,
+            top: record.top,
+            image,
+        });
+    }
+
+    // Records run bottom to top: a divider opens a group, its folder record closes it.
+    let mut groups = Vec::new();
+    let mut open: Vec<Vec<(usize, bool)>> = Vec::new();
+    for (record, index) in records.iter().zip(&layer_index) {
+        match record.section {
+            Some(SECTION_DIVIDER) => open.push(Vec::new()),
+            Some(SECTION_OPEN_FOLDER) | Some(SECTION_CLOSED_FOLDER) => {
+                let members = open.pop().unwrap_or_default();
+                if let Some(parent) = open.last_mut() {
+                    parent.extend(
+                        members
+                            .iter()
+                            .map(|&(member, visible)| (member, visible && !record.hidden)),
+                    );
+                }
+                groups.push(Group {
+                    name: record.name.clone(),
+                    layers: members,
+                });
+            }
+            _ => {
+                if let (Some(index), Some(parent)) = (index, open.last_mut()) {
+                    parent.push((*index, !record.hidden));
+                }
+            }
+        }
+    }
+
+    Ok((layers, groups))
+}
+
+fn parse_composite(
+    reader: &mut Reader,
+    width: u32,
+    height: u32,
+    channels: usize,
+    color_channels: usize,
+) -> Result<RgbaImage, String> {
+    let compression = reader.u16()?;
+    let used = channels.min(color_channels + 1);
+    let pixel_count = width as usize * height as usize;
+
+    let planes: Vec<Vec<u8>> = match compression {
+        0 => (0..used)
+            .map(|_| reader.bytes(pixel_count).map(<[u8]>::to_vec))
+            .collect::<Result<_, _>>()?,
+        1 => {
+            // Row byte counts for every channel come before any pixel data.
+            let mut counts = Vec::with_capacity(channels * height as usize);
+            for _ in 0..channels * height as usize {
+                counts.push(reader.u16()? as usize);
+            }
+            let mut planes = Vec::with_capacity(used);
+            for channel in 0..used {
+                let rows = &counts[channel * height as usize..(channel + 1) * height as usize];
+                let mut plane = Vec::with_capacity(pixel_count);
+                for &count in rows {
+                    plane.extend(unpack_bits(reader.bytes(count)?, width as usize)?);
+                }
+                planes.push(plane);
+            }
+            planes
+        }
+        other => return Err(format!("unsupported compression {}", other)),
+    };
+
+    let mut image = ImageBuffer::from_pixel(width, height, Rgba([0, 0, 0, 255]));
+    for (i, pixel) in image.pixels_mut().enumerate() {
+        for (channel, plane) in planes.iter().enumerate() {
+            let value = plane[i];
+            if channel < color_channels {
+                if color_channels == 1 {
+                    pixel.0[..3].fill(value);
+                } else {
+                    pixel.0[channel] = value;
+                }
+            } else {
+                pixel.0[3] = value;
+            }
+        }
+    }
+    Ok(image)
+}
+
+fn decode_plane(
+    reader: &mut Reader,
+    compression: u16,
+    width: u32,
+    height: u32,
+) -> Result<Vec<u8>, String> {
+    let pixel_count = width as usize * height as usize;
+    match compression {
+        0 => Ok(reader.bytes(pixel_count)?.to_vec()),
+        1 => {
+            let mut counts = Vec::with_capacity(height as usize);
+            for _ in 0..height {
+                counts.push(reader.u16()? as usize);
+            }
+            let mut plane = Vec::with_capacity(pixel_count);
+            for count in counts {
+                plane.extend(unpack_bits(reader.bytes(count)?, width as usize)?);
+            }
+            Ok(plane)
+        }
+        other => Err(format!("unsupported layer compression {}", other)),
+    }
+}
+
+/// Decode one PackBits-compressed row.
+fn unpack_bits(data: &[u8], row_len: usize) -> Result<Vec<u8>, String> {
+    let mut out = Vec::with_capacity(row_len);
+    let mut i = 0;
+    while i < data.len() && out.len() < row_len {
+        let header = data[i] as i8;
+        i += 1;
+        if header >= 0 {
+            let len = header as usize + 1;
+            let literal = data.get(i..i + len).ok_or("truncated PackBits run")?;
+            out.extend_from_slice(literal);
+            i += len;
+        } else if header != -128 {
+            let len = (1 - header as isize) as usize;
+            let value = *data.get(i).ok_or("truncated PackBits run")?;
+            out.extend(std::iter::repeat_n(value, len));
+            i += 1;
+        }
+    }
+    if out.len() < row_len {
+        return Err("PackBits row is too short".into());
+    }
+    out.truncate(row_len);
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn section(body: &[u8]) -> Vec<u8> {
+        let mut out = (body.len() as u32).to_be_bytes().to_vec();
+        out.extend(body);
+        out
+    }
+
+    fn header(width: u32, height: u32, channels: u16) -> Vec<u8> {
+        let mut out = SIGNATURE.to_vec();
+        out.extend(1u16.to_be_bytes());
+        out.extend([0; 6]);
+        out.extend(channels.to_be_bytes());
+        out.extend(height.to_be_bytes());
+        out.extend(width.to_be_bytes());
+        out.extend(8u16.to_be_bytes());
+        out.extend(COLOR_MODE_RGB.to_be_bytes());
+        out.extend(section(&[]));
+        out.extend(section(&[]));
+        out
+    }
+
+    /// A 1x1 layer at (`left`, 0) with RGBA channels, optionally a section divider.
+    fn layer_record(name: &str, left: i32, hidden: bool, section_type: Option<u32>) -> Vec<u8> {
+        let mut out = Vec::new();
+        for value in [0, left, 1, left + 1] {
+            out.extend(value.to_be_bytes());
+        }
+        out.extend(4u16.to_be_bytes());
+        for id in [-1i16, 0, 1, 2] {
+            out.extend(id.to_be_bytes());
+            out.extend(3u32.to_be_bytes());
+        }
+        out.extend(b"8BIMnorm");
+        out.extend([255, 0, if hidden { LAYER_FLAG_HIDDEN } else { 0 }, 0]);
+
+        let mut extra = section(&[]);
+        extra.extend(section(&[]));
+        let mut pascal = vec![name.len() as u8];
+        pascal.extend(name.as_bytes());
+        while pascal.len() % 4 != 0 {
+            pascal.push(0);
+        }
+        extra.extend(pascal);
+        if let Some(kind) = section_type {
+            extra.extend(b"8BIMlsct");
+            extra.extend(section(&kind.to_be_bytes()));
+        }
+        out.extend(section(&extra));
+        out
+    }
+
+    fn channel_data(rgba: [u8; 4]) -> Vec<u8> {
+        let mut out = Vec::new();
+        for value in [rgba[3], rgba[0], rgba[1], rgba[2]] {
+            out.extend(0u16.to_be_bytes());
+            out.push(value);
+        }
+        out
+    }
+
+    fn sample_psd() -> Vec<u8> {
+        let red = [255, 0, 0, 255];
+        let green = [0, 255, 0, 255];
+
+        // Bottom to top: divider, "icon" layer, "hidden" layer, "Icons" folder, loose layer.
+        let mut layer_info = (-5i16).to_be_bytes().to_vec();
+        layer_info.extend(layer_record(
+            "</Layer group>",
+            0,
+            false,
+            Some(SECTION_DIVIDER),
+        ));
+        layer_info.extend(layer_record("icon", 0, false, None));
+        layer_info.extend(layer_record("hidden", 1, true, None));
+        layer_info.extend(layer_record("Icons", 0, false, Some(SECTION_OPEN_FOLDER)));
+        layer_info.extend(layer_record("loose", 1, false, None));
+        for rgba in [[0; 4], red, green, [0; 4], green] {
+            layer_info.extend(channel_data(rgba));
+        }
+
+        let mut out = header(2, 1, 4);
+        out.extend(section(&section(&layer_info)));
+
+        // Raw composite: planar R, G, B, A.
+        out.extend(0u16.to_be_bytes());
+        out.extend([255, 0, 0, 255, 0, 0, 255, 255]);
+        out
+    }
+
+    #[test]
+    fn reads_merged_composite() {
+        let document = parse_psd(&sample_psd()).unwrap();
+        assert_eq!((document.width, document.height), (2, 1));
+        assert_eq!(document.composite.get_pixel(0, 0).0, [255, 0, 0, 255]);
+        assert_eq!(document.composite.get_pixel(1, 0).0, [0, 255, 0, 255]);
+    }
+
+    #[test]
+    fn renders_visible_group_members() {
+        let document = parse_psd(&sample_psd()).unwrap();
+        assert_eq!(document.groups.len(), 1);
+
+        let group = &document.groups[0];
+        assert_eq!(group.name, "Icons");
+        let image = document.render_group(group);
+        assert_eq!(image.get_pixel(0, 0).0, [255, 0, 0, 255]);
+        // The hidden layer and the layer outside the group are not drawn.
+        assert_eq!(image.get_pixel(1, 0).0, [0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn unpacks_packbits_rows() {
+        // Literal run of 2, then 3 repeats of 9.
+        let row = unpack_bits(&[1, 4, 5, 0xFE, 9], 5).unwrap();
+        assert_eq!(row, vec![4, 5, 9, 9, 9]);
+        assert!(unpack_bits(&[0xFE, 9], 5).is_err());
+    }
+}
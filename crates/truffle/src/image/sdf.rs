@@ -0,0 +1,427 @@
+use ttf_parser::{Face, GlyphId, OutlineBuilder};
+
+type Point = (f32, f32);
+
+/// Line pieces each quadratic and cubic segment is flattened into.
+const CURVE_PIECES: usize = 8;
+
+/// Corners sharper than this (sine of the angle between the edges) split
+/// the edge colors of a multi-channel field.
+const CORNER_THRESHOLD: f32 = 0.141;
+
+const RED: u8 = 1;
+const GREEN: u8 = 2;
+const BLUE: u8 = 4;
+const WHITE: u8 = RED | GREEN | BLUE;
+const EDGE_COLORS: [u8; 3] = [GREEN | BLUE, RED | BLUE, RED | GREEN];
+
+/// One segment of a glyph outline, flattened into a polyline, and the
+/// channels of a multi-channel field it contributes to.
+struct Edge {
+    points: Vec<Point>,
+    color: u8,
+}
+
+/// Distance from a point to an edge.
+#[derive(Clone, Copy)]
+struct EdgeDistance {
+    distance: f32,
+    /// How far the approach is from orthogonal; breaks ties at corners.
+    obliqueness: f32,
+    /// Signed distance to the edge, measured to its extended line past
+    /// either end. Positive on the left of the edge.
+    pseudo: f32,
+}
+
+/// A glyph outline in pixels (y up), ready to render distance fields from.
+#[derive(Default)]
+pub struct Shape {
+    contours: Vec<Vec<Edge>>,
+    current: Vec<Edge>,
+    start: Point,
+    last: Point,
+    scale: f32,
+    /// 1 when filled areas lie left of their contours, -1 otherwise.
+    orientation: f32,
+}
+
+impl Shape {
+    /// The outline of `glyph` scaled by `scale` pixels per font unit, or
+    /// `None` for glyphs without one (spaces).
+    pub fn from_glyph(face: &Face, glyph: GlyphId, scale: f32) -> Option<Self> {
+        let mut shape = Shape {
+            scale,
+            ..Default::default()
+        };
+        face.outline_glyph(glyph, &mut shape)?;
+        shape.finish_contour();
+        if shape.contours.is_empty() {
+            return None;
+        }
+        shape.orientation = if shape.area() < 0.0 { -1.0 } else { 1.0 };
+        for contour in &mut shape.contours {
+            color_edges(contour);
+        }
+        Some(shape)
+    }
+
+    /// Signed distance from `p` to the outline, positive inside.
+    pub fn signed_distance(&self, p: Point) -> f32 {
+        let distance = self
+            .edges()
+            .map(|edge| edge_distance(&edge.points, p).distance)
+            .fold(f32::MAX, f32::min);
+        if self.winding(p) != 0 {
+            distance
+        } else {
+            -distance
+        }
+    }
+
+    /// Per-channel signed pseudo-distances from `p`, positive inside. The
+    /// median of the three keeps corners sharp when the field is scaled up.
+    pub fn multi_distance(&self, p: Point) -> [f32; 3] {
+        let mut best: [Option<EdgeDistance>; 3] = [None; 3];
+        for edge in self.edges() {
+            let distance = edge_distance(&edge.points, p);
+            for (channel, best) in best.iter_mut().enumerate() {
+                if edge.color & (1 << channel) == 0 {
+                    continue;
+                }
+                let closer = best.is_none_or(|b| {
+                    distance.distance < b.distance
+                        || (distance.distance == b.distance && distance.obliqueness < b.obliqueness)
+                });
+                if closer {
+                    *best = Some(distance);
+                }
+            }
+        }
+
+        let sdf = self.signed_distance(p);
+        let channels = best.map(|b| b.map_or(sdf, |b| b.pseudo * self.orientation));
+        // Pixels whose median lands on the wrong side of the outline would
+        // show up as specks, so they fall back to the true distance.
+        if (median(channels) > 0.0) != (sdf > 0.0) {
+            [sdf; 3]
+        } else {
+            channels
+        }
+    }
+
+    /// Renders the field into the `width`x`height` box of `atlas` at
+    /// `(x0, y0)`. `origin` is the outline position of the box's top-left
+    /// corner, and `range` the distance in pixels from fully outside to
+    /// fully inside.
+    ///
+    /// Single-channel fields are stored as white with the distance in
+    /// alpha. Multi-channel fields store their channels in RGB and the
+    /// true distance in alpha.
+    #[allow(clippy::too_many_arguments)]
+    pub fn render(
+        &self,
+        atlas: &mut image::RgbaImage,
+        x0: u32,
+        y0: u32,
+        width: u32,
+        height: u32,
+        origin: Point,
+        range: f32,
+        multi: bool,
+    ) {
+        let encode = |d: f32| ((0.5 + d / range).clamp(0.0, 1.0) * 255.0).round() as u8;
+        for y in 0..height {
+            for x in 0..width {
+                let (ax, ay) = (x0 + x, y0 + y);
+                if ax >= atlas.width() || ay >= atlas.height() {
+                    continue;
+                }
+                let p = (origin.0 + x as f32 + 0.5, origin.1 - y as f32 - 0.5);
+                let sdf = encode(self.signed_distance(p));
+                let pixel = if multi {
+                    let [r, g, b] = self.multi_distance(p).map(encode);
+                    [r, g, b, sdf]
+                } else {
+                    [255, 255, 255, sdf]
+                };
+                atlas.put_pixel(ax, ay, image::Rgba(pixel));
+            }
+        }
+    }
+
+    fn edges(&self) -> impl Iterator<Item = &Edge> {
+        self.contours.iter().flatten()
+    }
+
+    fn area(&self) -> f32 {
+        self.edges()
+            .flat_map(|edge| edge.points.windows(2))
+            .map(|w| w[0].0 * w[1].1 - w[1].0 * w[0].1)
+            .sum::<f32>()
+            / 2.0
+    }
+
+    /// Nonzero winding number of the outline around `p`.
+    fn winding(&self, p: Point) -> i32 {
+        let mut winding = 0;
+        for w in self.edges().flat_map(|edge| edge.points.windows(2)) {
+            let (a, b) = (w[0], w[1]);
+            let side = cross(sub(b, a), sub(p, a));
+            if a.1 <= p.1 {
+                if b.1 > p.1 && side > 0.0 {
+                    winding += 1;
+                }
+            } else if b.1 <= p.1 && side < 0.0 {
+                winding -= 1;
+            }
+        }
+        winding
+    }
+
+    fn push_edge(&mut self, points: Vec<Point>) {
+        self.last = *points.last().unwrap();
+        self.current.push(Edge {
+            points,
+            color: WHITE,
+        });
+    }
+
+    fn finish_contour(&mut self) {
+        if self.last != self.start && !self.current.is_empty() {
+            self.push_edge(vec![self.last, self.start]);
+        }
+        if !self.current.is_empty() {
+            self.contours.push(std::mem::take(&mut self.current));
+        }
+    }
+}
+
+impl OutlineBuilder for Shape {
+    fn move_to(&mut self, x: f32, y: f32) {
+        self.finish_contour();
+        self.start = (x * self.scale, y * self.scale);
+        self.last = self.start;
+    }
+
+    fn line_to(&mut self, x: f32, y: f32) {
+        let p = (x * self.scale, y * self.scale);
+        if p != self.last {
+            self.push_edge(vec![self.last, p]);
+        }
+    }
+
+    fn quad_to(&mut self, x1: f32, y1: f32, x: f32, y: f32) {
+        let (p0, s) = (self.last, self.scale);
+        let (p1, p2) = ((x1 * s, y1 * s), (x * s, y * s));
+        let points = (0..=CURVE_PIECES)
+            .map(|i| {
+                let t = i as f32 / CURVE_PIECES as f32;
+                let u = 1.0 - t;
+                (
+                    u * u * p0.0 + 2.0 * u * t * p1.0 + t * t * p2.0,
+                    u * u * p0.1 + 2.0 * u * t * p1.1 + t * t * p2.1,
+                )
+            })
+            .collect();
+        self.push_edge(points);
+    }
+
+    fn curve_to(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, x: f32, y: f32) {
+        let (p0, s) = (self.last, self.scale);
+        let (p1, p2, p3) = ((x1 * s, y1 * s), (x2 * s, y2 * s), (x * s, y * s));
+        let points = (0..=CURVE_PIECES)
+            .map(|i| {
+                let t = i as f32 / CURVE_PIECES as f32;
+                let u = 1.0 - t;
+                let (a, b, c, d) = (u * u * u, 3.0 * u * u * t, 3.0 * u * t * t, t * t * t);
+                (
+                    a * p0.0 + b * p1.0 + c * p2.0 + d * p3.0,
+                    a * p0.1 + b * p1.1 + c * p2.1 + d * p3.1,
+                )
+            })
+            .collect();
+        self.push_edge(points);
+    }
+
+    fn close(&mut self) {
+        self.finish_contour();
+    }
+}
+
+/// Colors the edges of a closed contour so that the two edges meeting at
+/// each sharp corner share exactly one channel. Smooth contours stay white.
+fn color_edges(contour: &mut [Edge]) {
+    let n = contour.len();
+    let corners: Vec<usize> = (0..n)
+        .filter(|&i| {
+            let before = end_direction(&contour[(i + n - 1) % n]);
+            let after = start_direction(&contour[i]);
+            dot(before, after) <= 0.0 || cross(before, after).abs() > CORNER_THRESHOLD
+        })
+        .collect();
+
+    match corners.len() {
+        0 => {}
+        1 => {
+            // A teardrop: split the contour in three around its one corner.
+            if n >= 3 {
+                let colors = [RED | BLUE, WHITE, RED | GREEN];
+                for i in 0..n {
+                    contour[(corners[0] + i) % n].color = colors[i * 3 / n];
+                }
+            }
+        }
+        spans => {
+            for (span, &corner) in corners.iter().enumerate() {
+                let mut color = EDGE_COLORS[span % 3];
+                // The last span also meets the first one.
+                if span == spans - 1 && color == EDGE_COLORS[0] {
+                    color = EDGE_COLORS[1];
+                }
+                let end = corners[(span + 1) % spans];
+                let mut i = corner;
+                loop {
+                    contour[i].color = color;
+                    i = (i + 1) % n;
+                    if i == end {
+                        break;
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn edge_distance(points: &[Point], p: Point) -> EdgeDistance {
+    let last = points.len() - 2;
+    let mut best = EdgeDistance {
+        distance: f32::MAX,
+        obliqueness: f32::MAX,
+        pseudo: 0.0,
+    };
+    for (i, w) in points.windows(2).enumerate() {
+        let (a, b) = (w[0], w[1]);
+        let direction = sub(b, a);
+        let length = dot(direction, direction).sqrt();
+        if length == 0.0 {
+            continue;
+        }
+        let ap = sub(p, a);
+        let t = dot(ap, direction) / (length * length);
+        let nearest = (
+            a.0 + direction.0 * t.clamp(0.0, 1.0),
+            a.1 + direction.1 * t.clamp(0.0, 1.0),
+        );
+        let to_p = sub(p, nearest);
+        let distance = dot(to_p, to_p).sqrt();
+        let obliqueness = if distance > 0.0 {
+            (dot(direction, to_p) / (length * distance)).abs()
+        } else {
+            0.0
+        };
+        if distance > best.distance
+            || (distance == best.distance && obliqueness >= best.obliqueness)
+        {
+            continue;
+        }
+
+        let perpendicular = cross(direction, ap) / length;
+        let beyond_end = (i == 0 && t < 0.0) || (i == last && t > 1.0);
+        best = EdgeDistance {
+            distance,
+            obliqueness,
+            pseudo: if beyond_end {
+                perpendicular
+            } else {
+                distance.copysign(perpendicular)
+            },
+        };
+    }
+    best
+}
+
+fn start_direction(edge: &Edge) -> Point {
+    normalize(sub(edge.points[1], edge.points[0]))
+}
+
+fn end_direction(edge: &Edge) -> Point {
+    let n = edge.points.len();
+    normalize(sub(edge.points[n - 1], edge.points[n - 2]))
+}
+
+fn median([a, b, c]: [f32; 3]) -> f32 {
+    a.min(b).max(a.max(b).min(c))
+}
+
+fn sub(a: Point, b: Point) -> Point {
+    (a.0 - b.0, a.1 - b.1)
+}
+
+fn dot(a: Point, b: Point) -> f32 {
+    a.0 * b.0 + a.1 * b.1
+}
+
+fn cross(a: Point, b: Point) -> f32 {
+    a.0 * b.1 - a.1 * b.0
+}
+
+fn normalize(a: Point) -> Point {
+    let length = dot(a, a).sqrt();
+    if length == 0.0 {
+        a
+    } else {
+        (a.0 / length, a.1 / length)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn square(clockwise: bool) -> Shape {
+        let mut shape = Shape {
+            scale: 1.0,
+            ..Default::default()
+        };
+        shape.move_to(0.0, 0.0);
+        if clockwise {
+            shape.line_to(0.0, 10.0);
+            shape.line_to(10.0, 10.0);
+            shape.line_to(10.0, 0.0);
+        } else {
+            shape.line_to(10.0, 0.0);
+            shape.line_to(10.0, 10.0);
+            shape.line_to(0.0, 10.0);
+        }
+        shape.close();
+        shape.orientation = if shape.area() < 0.0 { -1.0 } else { 1.0 };
+        for contour in &mut shape.contours {
+            color_edges(contour);
+        }
+        shape
+    }
+
+    #[test]
+    fn distances_are_positive_inside() {
+        for clockwise in [false, true] {
+            let shape = square(clockwise);
+            assert_eq!(shape.signed_distance((5.0, 5.0)), 5.0);
+            assert_eq!(shape.signed_distance((15.0, 5.0)), -5.0);
+            assert!((shape.signed_distance((12.0, 12.0)) + 8f32.sqrt()).abs() < 1e-5);
+
+            // The median of the channels keeps the corner square.
+            assert_eq!(median(shape.multi_distance((5.0, 7.0))), 3.0);
+            assert_eq!(median(shape.multi_distance((12.0, 12.0))), -2.0);
+        }
+    }
+
+    #[test]
+    fn corners_split_edge_colors() {
+        let shape = square(false);
+        let colors: Vec<u8> = shape.contours[0].iter().map(|e| e.color).collect();
+        for i in 0..colors.len() {
+            let shared = colors[i] & colors[(i + 1) % colors.len()];
+            assert_eq!(shared.count_ones(), 1, "{:?}", colors);
+        }
+    }
+}
@@ -0,0 +1,80 @@
+use resvg::tiny_skia::{Pixmap, Transform};
+use resvg::usvg::{Options, Tree};
+use std::path::Path;
+
+/// Build usvg options with system fonts loaded so `<text>` elements render.
+/// Loading fonts is slow, so callers should build this once per batch.
+pub fn svg_options() -> Options<'static> {
+    let mut options = Options::default();
+    options.fontdb_mut().load_system_fonts();
+    options
+}
+
+/// Read the intrinsic size of an SVG document, rounded up to whole pixels.
+pub fn svg_size(path: &Path) -> Option<(u32, u32)> {
+    let data = std::fs::read(path).ok()?;
+    let tree = Tree::from_data(&data, &Options::default()).ok()?;
+    let size = tree.size();
+    Some((size.width().ceil() as u32, size.height().ceil() as u32))
+}
+
+/// Rasterize an SVG document to a PNG at `scale` times its intrinsic size.
+pub fn rasterize_svg(
+    input_path: &Path,
+    output_path: &Path,
+    scale: u32,
+    options: &Options,
+) -> Result<(), String> {
+    let data = std::fs::read(input_path)
+        .map_err(|e| format!("Failed to read {}: {}", input_path.display(), e))?;
+    let pixmap = render_svg(&data, scale, options)
+        .map_err(|e| format!("Failed to rasterize {}: {}", input_path.display(), e))?;
+    pixmap
+        .save_png(output_path)
+        .map_err(|e| format!("Failed to write {}: {}", output_path.display(), e))
+}
+
+fn render_svg(data: &[u8], scale: u32, options: &Options) -> Result<Pixmap, String> {
+    if scale == 0 {
+        return Err("Scale must be >= 1".into());
+    }
+
+    let tree = Tree::from_data(data, options).map_err(|e| e.to_string())?;
+    let size = tree.size();
+    let width = (size.width() * scale as f32).ceil() as u32;
+    let height = (size.height() * scale as f32).ceil() as u32;
+
+    let mut pixmap = Pixmap::new(width, height)
+        .ok_or_else(|| format!("Invalid raster size {}x{}", width, height))?;
+    resvg::render(
+        &tree,
+        Transform::from_scale(scale as f32, scale as f32),
+        &mut pixmap.as_mut(),
+    );
+    Ok(pixmap)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SQUARE: &[u8] = br##"<svg xmlns="http://www.w3.org/2000/svg" width="4" height="2">
+        <rect x="0" y="0" width="2" height="2" fill="#ff0000"/>
+    </svg>"##;
+
+    #[test]
+    fn renders_at_requested_scale() {
+        let pixmap = render_svg(SQUARE, 3, &Options::default()).unwrap();
+        assert_eq!((pixmap.width(), pixmap.height()), (12, 6));
+
+        let inside = pixmap.pixel(1, 1).unwrap();
+        assert_eq!((inside.red(), inside.alpha()), (255, 255));
+        let outside = pixmap.pixel(10, 1).unwrap();
+        assert_eq!(outside.alpha(), 0);
+    }
+
+    #[test]
+    fn rejects_zero_scale() {
+        assert!(render_svg(SQUARE, 0, &Options::default()).is_err());
+    }
+}
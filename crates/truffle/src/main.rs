@@ -1,6 +1,14 @@
 mod assets;
+mod cache;
 mod commands;
 mod image;
+mod notify;
+mod policy;
+mod report;
+mod since;
+mod tilemap;
+mod watch;
+mod workspace;
 
 use clap::{builder::styling, Parser, Subcommand};
 
@@ -22,9 +30,16 @@ struct Cli {
 }
 
 #[derive(Subcommand)]
+#[allow(clippy::large_enum_variant)]
 enum Commands {
     /// Sync assets and augment metadata with image dimensions
     Sync(commands::sync::SyncArgs),
+    /// Check that the generated Luau and TypeScript modules are up to date
+    Verify(commands::verify::VerifyArgs),
+    /// Check that the assets module and the images folder list the same files
+    Check(commands::check::CheckArgs),
+    /// Upload a single file and print its asset id
+    Upload(commands::upload::UploadArgs),
     /// Generate a bitmap atlas from a .ttf font
     Font(commands::font::FontArgs),
     /// Image manipulation commands
@@ -32,6 +47,21 @@ enum Commands {
         #[command(subcommand)]
         command: commands::image::ImageCommands,
     },
+    /// Atlas inspection commands
+    Atlas {
+        #[command(subcommand)]
+        command: commands::atlas::AtlasCommands,
+    },
+    /// Tilemap import commands
+    Tilemap {
+        #[command(subcommand)]
+        command: commands::tilemap::TilemapCommands,
+    },
+    /// Long-running daemon that keeps sync state warm
+    Daemon {
+        #[command(subcommand)]
+        command: commands::daemon::DaemonCommands,
+    },
 }
 
 fn main() {
@@ -39,8 +69,14 @@ fn main() {
 
     let result = match cli.command {
         Commands::Sync(args) => commands::sync::run(args),
+        Commands::Verify(args) => commands::verify::run(args),
+        Commands::Check(args) => commands::check::run(args),
+        Commands::Upload(args) => commands::upload::run(args),
         Commands::Font(args) => commands::font::run(args),
         Commands::Image { command } => commands::image::run(command),
+        Commands::Atlas { command } => commands::atlas::run(command),
+        Commands::Tilemap { command } => commands::tilemap::run(command),
+        Commands::Daemon { command } => commands::daemon::run(command),
     };
 
     std::process::exit(if result { 0 } else { 1 });
@@ -0,0 +1,198 @@
+use crate::report::SyncReport;
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::time::Duration;
+use truffle_config::NotifyOptions;
+
+/// JSON body posted to the webhook. `text` is what Slack shows and `content`
+/// what Discord shows; the other fields are for custom endpoints.
+#[derive(Debug, Serialize)]
+struct Payload {
+    text: String,
+    content: String,
+    status: &'static str,
+    project: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    environment: Option<String>,
+    dry_run: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    counts: Option<Counts>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    report_url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct Counts {
+    added: usize,
+    changed: usize,
+    removed: usize,
+    unchanged: usize,
+    atlas_pages: usize,
+}
+
+/// Posts the sync outcome to the configured webhook. Delivery problems are
+/// only warned about; they never fail the sync.
+pub async fn send(options: &NotifyOptions, dry_run: bool, result: &Result<Option<SyncReport>>) {
+    let notify = match result {
+        Ok(_) => options.on_success,
+        Err(_) => options.on_failure,
+    };
+    if !notify {
+        return;
+    }
+
+    let project = std::env::current_dir()
+        .ok()
+        .and_then(|dir| {
+            dir.file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+        })
+        .unwrap_or_default();
+    let report_url = std::env::var("TRUFFLE_REPORT_URL")
+        .ok()
+        .filter(|url| !url.is_empty())
+        .or_else(|| options.report_url.clone());
+    let payload = payload(
+        project,
+        options.environment.clone(),
+        report_url,
+        dry_run,
+        result,
+    );
+
+    if let Err(e) = post(&options.url, &payload).await {
+        eprintln!("[notify] WARN: {:#}", e);
+    }
+}
+
+fn payload(
+    project: String,
+    environment: Option<String>,
+    report_url: Option<String>,
+    dry_run: bool,
+    result: &Result<Option<SyncReport>>,
+) -> Payload {
+    let counts = match result {
+        Ok(Some(report)) => Some(Counts {
+            added: report.added.len(),
+            changed: report.changed.len(),
+            removed: report.removed.len(),
+            unchanged: report.unchanged,
+            atlas_pages: report.atlases.len(),
+        }),
+        _ => None,
+    };
+    let error = result.as_ref().err().map(|e| format!("{:#}", e));
+
+    let (icon, outcome, status) = match error {
+        Some(_) => ("❌", "failed", "failure"),
+        None => ("✅", "finished", "success"),
+    };
+    let name = if project.is_empty() {
+        "project"
+    } else {
+        &project
+    };
+
+    let mut text = format!("{} truffle sync {} for {}", icon, outcome, name);
+    if let Some(environment) = &environment {
+        text.push_str(&format!(" ({})", environment));
+    }
+    if dry_run {
+        text.push_str(" [dry run]");
+    }
+    if let Some(counts) = &counts {
+        text.push_str(&format!(
+            ": {} added, {} changed, {} removed",
+            counts.added, counts.changed, counts.removed
+        ));
+        if counts.atlas_pages > 0 {
+            text.push_str(&format!(", {} atlas page(s) changed", counts.atlas_pages));
+        }
+    }
+    if let Some(error) = &error {
+        text.push_str(&format!(": {}", error));
+    }
+    if let Some(url) = &report_url {
+        text.push_str(&format!("\nReport: {}", url));
+    }
+
+    Payload {
+        content: text.clone(),
+        text,
+        status,
+        project,
+        environment,
+        dry_run,
+        counts,
+        report_url,
+        error,
+    }
+}
+
+async fn post(url: &str, payload: &Payload) -> Result<()> {
+    let response = reqwest::Client::new()
+        .post(url)
+        .header("Content-Type", "application/json")
+        .timeout(Duration::from_secs(10))
+        .body(serde_json::to_vec(payload)?)
+        .send()
+        .await
+        .context("Failed to send webhook notification")?;
+    if !response.status().is_success() {
+        anyhow::bail!("Webhook responded with {}", response.status());
+    }
+    println!("[notify] Sent sync notification");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn payload_summarizes_the_report() {
+        let report = SyncReport {
+            added: vec![crate::report::AssetChange {
+                key: "ui/play.png".into(),
+                id: Some("rbxassetid://1".into()),
+                previous_id: None,
+            }],
+            unchanged: 4,
+            ..Default::default()
+        };
+        let payload = payload(
+            "lobby".into(),
+            Some("production".into()),
+            Some("https://ci.example/1".into()),
+            false,
+            &Ok(Some(report)),
+        );
+        assert_eq!(payload.status, "success");
+        assert_eq!(payload.counts.as_ref().unwrap().unchanged, 4);
+        assert_eq!(
+            payload.text,
+            "✅ truffle sync finished for lobby (production): 1 added, 0 changed, 0 removed\nReport: https://ci.example/1"
+        );
+        assert_eq!(payload.content, payload.text);
+    }
+
+    #[test]
+    fn failures_include_the_error() {
+        let payload = payload(
+            "lobby".into(),
+            None,
+            None,
+            false,
+            &Err(anyhow::anyhow!("Upload rejected")),
+        );
+        assert_eq!(payload.status, "failure");
+        assert!(payload.counts.is_none());
+        assert_eq!(
+            payload.text,
+            "❌ truffle sync failed for lobby: Upload rejected"
+        );
+    }
+}
@@ -0,0 +1,260 @@
+use crate::assets::augment::ImageMetadataReader;
+use anyhow::{Context, Result};
+use asphalt::glob::Glob;
+use regex::Regex;
+use std::path::Path;
+use truffle_config::{PolicyLevel, PolicyOptions};
+use walkdir::WalkDir;
+
+/// Source and metadata files that truffle converts rather than uploads.
+const SOURCE_EXTENSIONS: [&str; 4] = ["aseprite", "ase", "psd", "json"];
+
+/// A single broken policy rule.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Violation {
+    pub rule: &'static str,
+    pub level: PolicyLevel,
+    pub message: String,
+}
+
+/// Checks every file in the images folder against the per-file rules.
+pub fn check_images(
+    images_folder: &Path,
+    policy: &PolicyOptions,
+    reader: &dyn ImageMetadataReader,
+) -> Result<Vec<Violation>> {
+    let naming = policy
+        .naming
+        .as_ref()
+        .map(|rule| {
+            Regex::new(&rule.value)
+                .with_context(|| format!("Invalid policy naming regex: {}", rule.value))
+        })
+        .transpose()?;
+    let require_highlight = policy
+        .require_highlight
+        .as_ref()
+        .map(|rule| {
+            rule.value
+                .iter()
+                .map(|glob| {
+                    Glob::new(glob)
+                        .with_context(|| format!("Invalid policy require_highlight glob: {}", glob))
+                })
+                .collect::<Result<Vec<_>>>()
+        })
+        .transpose()?;
+
+    let mut violations = Vec::new();
+    let mut violate = |rule: &'static str, level: PolicyLevel, message: String| {
+        violations.push(Violation {
+            rule,
+            level,
+            message,
+        });
+    };
+
+    let mut files: Vec<_> = WalkDir::new(images_folder)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file())
+        .map(|e| e.into_path())
+        .collect();
+    files.sort();
+
+    for path in files {
+        let extension = path
+            .extension()
+            .and_then(|s| s.to_str())
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+        if SOURCE_EXTENSIONS.contains(&extension.as_str()) {
+            continue;
+        }
+        let key = path
+            .strip_prefix(images_folder)
+            .unwrap_or(&path)
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+
+        if let Some(rule) = &policy.allowed_formats {
+            if !rule.value.iter().any(|format| {
+                format
+                    .trim_start_matches('.')
+                    .eq_ignore_ascii_case(&extension)
+            }) {
+                violate(
+                    "allowed_formats",
+                    rule.level,
+                    format!("{} has a disallowed format (.{})", key, extension),
+                );
+            }
+        }
+
+        if let Some(rule) = &policy.max_file_size {
+            let size = path.metadata().map(|m| m.len()).unwrap_or(0);
+            if size > rule.value {
+                violate(
+                    "max_file_size",
+                    rule.level,
+                    format!("{} is {} bytes (limit {})", key, size, rule.value),
+                );
+            }
+        }
+
+        if let (Some(rule), Some(naming)) = (&policy.naming, &naming) {
+            if !naming.is_match(&key) {
+                violate(
+                    "naming",
+                    rule.level,
+                    format!("{} does not match {}", key, rule.value),
+                );
+            }
+        }
+
+        if let Some(rule) = &policy.max_dimensions {
+            let [max_width, max_height] = rule.value;
+            if let Some((width, height)) = reader.dimensions(&path) {
+                if width > max_width || height > max_height {
+                    violate(
+                        "max_dimensions",
+                        rule.level,
+                        format!(
+                            "{} is {}x{} (limit {}x{})",
+                            key, width, height, max_width, max_height
+                        ),
+                    );
+                }
+            }
+        }
+
+        if let (Some(rule), Some(globs)) = (&policy.require_highlight, &require_highlight) {
+            let is_highlight = key.ends_with("-highlight.png");
+            if extension == "png" && !is_highlight && globs.iter().any(|g| g.is_match(&key)) {
+                let highlight = path.with_file_name(format!(
+                    "{}-highlight.png",
+                    path.file_stem()
+                        .and_then(|s| s.to_str())
+                        .unwrap_or_default()
+                ));
+                if !highlight.exists() {
+                    violate(
+                        "require_highlight",
+                        rule.level,
+                        format!("{} has no -highlight.png pair", key),
+                    );
+                }
+            }
+        }
+    }
+
+    Ok(violations)
+}
+
+/// Checks the number of atlas pages produced by the packer.
+pub fn check_atlas_pages(pages: usize, policy: &PolicyOptions) -> Vec<Violation> {
+    match &policy.max_atlas_pages {
+        Some(rule) if pages > rule.value => vec![Violation {
+            rule: "max_atlas_pages",
+            level: rule.level,
+            message: format!("Atlases use {} pages (limit {})", pages, rule.value),
+        }],
+        _ => Vec::new(),
+    }
+}
+
+/// Prints violations and fails if any of them is an error.
+pub fn report(violations: &[Violation]) -> Result<()> {
+    let mut errors = 0;
+    for violation in violations {
+        match violation.level {
+            PolicyLevel::Warn => {
+                println!("[policy] WARN: {} ({})", violation.message, violation.rule)
+            }
+            PolicyLevel::Error => {
+                eprintln!("[policy] ERROR: {} ({})", violation.message, violation.rule);
+                errors += 1;
+            }
+        }
+    }
+
+    if errors > 0 {
+        anyhow::bail!("{} asset policy violation(s)", errors);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use truffle_config::PolicyRule;
+
+    struct FixedSize;
+
+    impl ImageMetadataReader for FixedSize {
+        fn dimensions(&self, _path: &Path) -> Option<(u32, u32)> {
+            Some((2048, 512))
+        }
+    }
+
+    fn rule<T>(value: T, level: PolicyLevel) -> Option<PolicyRule<T>> {
+        Some(PolicyRule { value, level })
+    }
+
+    #[test]
+    fn file_rules_report_violations() {
+        let dir = std::env::temp_dir().join(format!("truffle-policy-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("ui")).unwrap();
+        std::fs::write(dir.join("ui/Play Button.png"), [0u8; 16]).unwrap();
+        std::fs::write(dir.join("ui/close.png"), [0u8; 4]).unwrap();
+        std::fs::write(dir.join("ui/close-highlight.png"), [0u8; 4]).unwrap();
+        std::fs::write(dir.join("ui/hero.aseprite"), [0u8; 64]).unwrap();
+        std::fs::write(dir.join("notes.txt"), [0u8; 4]).unwrap();
+
+        let policy = PolicyOptions {
+            max_file_size: rule(8, PolicyLevel::Warn),
+            max_dimensions: rule([1024, 1024], PolicyLevel::Warn),
+            allowed_formats: rule(vec!["png".into()], PolicyLevel::Error),
+            naming: rule(r"^[a-z0-9_/.-]+$".into(), PolicyLevel::Error),
+            require_highlight: rule(vec!["ui/*".into()], PolicyLevel::Warn),
+            max_atlas_pages: None,
+        };
+        let violations = check_images(&dir, &policy, &FixedSize).unwrap();
+        std::fs::remove_dir_all(&dir).ok();
+
+        let found: Vec<_> = violations
+            .iter()
+            .map(|v| (v.rule, v.message.split(' ').next().unwrap().to_string()))
+            .collect();
+        assert!(found.contains(&("allowed_formats", "notes.txt".into())));
+        assert!(found.contains(&("max_file_size", "ui/Play".into())));
+        assert!(found.contains(&("naming", "ui/Play".into())));
+        assert!(found.contains(&("require_highlight", "ui/Play".into())));
+        assert!(!found.iter().any(|(_, key)| key.ends_with(".aseprite")));
+        assert!(!found
+            .iter()
+            .any(|(rule, key)| *rule == "require_highlight" && key == "ui/close.png"));
+        assert_eq!(
+            violations
+                .iter()
+                .filter(|v| v.rule == "max_dimensions")
+                .count(),
+            4
+        );
+        assert!(report(&violations).is_err());
+    }
+
+    #[test]
+    fn atlas_page_limit() {
+        let policy = PolicyOptions {
+            max_atlas_pages: rule(2, PolicyLevel::Warn),
+            ..Default::default()
+        };
+        assert!(check_atlas_pages(2, &policy).is_empty());
+        let violations = check_atlas_pages(3, &policy);
+        assert_eq!(violations.len(), 1);
+        assert!(report(&violations).is_ok());
+    }
+}
@@ -0,0 +1,286 @@
+use crate::assets::model::AssetValue;
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+/// What a sync changed, written as JSON (`--report`) or Markdown (`--report-md`).
+#[derive(Debug, Default, Serialize)]
+pub struct SyncReport {
+    pub dry_run: bool,
+    pub added: Vec<AssetChange>,
+    pub changed: Vec<AssetChange>,
+    pub removed: Vec<AssetChange>,
+    pub unchanged: usize,
+    pub atlases: Vec<AtlasChange>,
+    /// Estimated upload cost in Robux, when the backend reports one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub estimated_cost: Option<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AssetChange {
+    pub key: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub previous_id: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AtlasChange {
+    pub file: String,
+    /// `added`, `changed` or `removed`.
+    pub status: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub previous_id: Option<String>,
+}
+
+impl SyncReport {
+    /// Compares asset ids (see [`asset_ids`]) before and after a sync.
+    pub fn new(
+        dry_run: bool,
+        previous: &BTreeMap<String, String>,
+        current: &BTreeMap<String, String>,
+    ) -> Self {
+        let mut report = Self {
+            dry_run,
+            ..Default::default()
+        };
+        for (key, id) in current {
+            match previous.get(key) {
+                None => report.added.push(AssetChange {
+                    key: key.clone(),
+                    id: Some(id.clone()),
+                    previous_id: None,
+                }),
+                Some(previous_id) if previous_id != id => report.changed.push(AssetChange {
+                    key: key.clone(),
+                    id: Some(id.clone()),
+                    previous_id: Some(previous_id.clone()),
+                }),
+                Some(_) => report.unchanged += 1,
+            }
+        }
+        for (key, id) in previous {
+            if !current.contains_key(key) {
+                report.removed.push(AssetChange {
+                    key: key.clone(),
+                    id: None,
+                    previous_id: Some(id.clone()),
+                });
+            }
+        }
+        report
+    }
+
+    /// Records atlas pages whose uploaded id differs from the last sync.
+    pub fn diff_atlases(
+        &mut self,
+        previous: &HashMap<String, String>,
+        current: &HashMap<String, String>,
+    ) {
+        let mut files: Vec<&String> = previous.keys().chain(current.keys()).collect();
+        files.sort();
+        files.dedup();
+        for file in files {
+            let status = match (previous.get(file), current.get(file)) {
+                (None, Some(_)) => "added",
+                (Some(_), None) => "removed",
+                (Some(before), Some(after)) if before != after => "changed",
+                _ => continue,
+            };
+            self.atlases.push(AtlasChange {
+                file: file.clone(),
+                status,
+                id: current.get(file).cloned(),
+                previous_id: previous.get(file).cloned(),
+            });
+        }
+    }
+
+    pub fn render_markdown(&self) -> String {
+        let mut out = String::from("## Truffle sync report\n\n");
+        if self.dry_run {
+            out.push_str("_Dry run: nothing was uploaded._\n\n");
+        }
+
+        out.push_str("| | Assets |\n| --- | --- |\n");
+        for (label, count) in [
+            ("Added", self.added.len()),
+            ("Changed", self.changed.len()),
+            ("Removed", self.removed.len()),
+            ("Unchanged", self.unchanged),
+            ("Atlas pages changed", self.atlases.len()),
+        ] {
+            out.push_str(&format!("| {} | {} |\n", label, count));
+        }
+        if let Some(cost) = self.estimated_cost {
+            out.push_str(&format!("| Estimated cost | {} Robux |\n", cost));
+        }
+
+        let id = |id: &Option<String>| {
+            id.as_deref()
+                .map_or("–".to_string(), |id| format!("`{}`", id))
+        };
+        for (title, changes) in [
+            ("Added", &self.added),
+            ("Changed", &self.changed),
+            ("Removed", &self.removed),
+        ] {
+            if changes.is_empty() {
+                continue;
+            }
+            out.push_str(&format!(
+                "\n<details><summary>{} ({})</summary>\n\n| Key | Id | Previous id |\n| --- | --- | --- |\n",
+                title,
+                changes.len()
+            ));
+            for change in changes {
+                out.push_str(&format!(
+                    "| `{}` | {} | {} |\n",
+                    change.key,
+                    id(&change.id),
+                    id(&change.previous_id)
+                ));
+            }
+            out.push_str("\n</details>\n");
+        }
+
+        if !self.atlases.is_empty() {
+            out.push_str("\n### Atlases\n\n| Page | Status | Id | Previous id |\n| --- | --- | --- | --- |\n");
+            for atlas in &self.atlases {
+                out.push_str(&format!(
+                    "| `{}` | {} | {} | {} |\n",
+                    atlas.file,
+                    atlas.status,
+                    id(&atlas.id),
+                    id(&atlas.previous_id)
+                ));
+            }
+        }
+        out
+    }
+
+    /// Writes the JSON and/or Markdown report.
+    pub fn write(&self, json: Option<&Path>, markdown: Option<&Path>) -> Result<()> {
+        if let Some(path) = json {
+            let data = serde_json::to_string_pretty(self)?;
+            std::fs::write(path, data)
+                .with_context(|| format!("Failed to write report {}", path.display()))?;
+            println!("[sync] Wrote report: {}", path.display());
+        }
+        if let Some(path) = markdown {
+            std::fs::write(path, self.render_markdown())
+                .with_context(|| format!("Failed to write report {}", path.display()))?;
+            println!("[sync] Wrote report: {}", path.display());
+        }
+        Ok(())
+    }
+}
+
+/// Flattens an assets tree into `path/to/key` → asset id. Surface maps are
+/// listed as `key.colorMap`, … and highlights as `key.highlight`.
+pub fn asset_ids(assets: &BTreeMap<String, AssetValue>) -> BTreeMap<String, String> {
+    fn walk(map: &BTreeMap<String, AssetValue>, prefix: &str, out: &mut BTreeMap<String, String>) {
+        for (key, value) in map {
+            let key = if prefix.is_empty() {
+                key.clone()
+            } else {
+                format!("{}/{}", prefix, key)
+            };
+            match value {
+                AssetValue::String(id) => {
+                    out.insert(key, id.clone());
+                }
+                AssetValue::Object(meta) => {
+                    if let Some(highlight) = &meta.highlight_id {
+                        out.insert(format!("{}.highlight", key), highlight.clone());
+                    }
+                    out.insert(key, meta.id.clone());
+                }
+                AssetValue::Surface(surface) => {
+                    for (name, meta) in surface.maps() {
+                        out.insert(format!("{}.{}", key, name), meta.id.clone());
+                    }
+                }
+                AssetValue::Table(children) => walk(children, &key, out),
+                AssetValue::Number(_) | AssetValue::Bool(_) => {}
+            }
+        }
+    }
+
+    let mut out = BTreeMap::new();
+    walk(assets, "", &mut out);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assets::model::AssetMeta;
+
+    fn ids(pairs: &[(&str, &str)]) -> BTreeMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn assets_are_diffed_by_id() {
+        let previous = ids(&[("ui/a.png", "1"), ("ui/b.png", "2"), ("ui/c.png", "3")]);
+        let current = ids(&[("ui/a.png", "1"), ("ui/b.png", "20"), ("ui/d.png", "4")]);
+        let report = SyncReport::new(false, &previous, &current);
+
+        assert_eq!(report.unchanged, 1);
+        assert_eq!(report.added[0].key, "ui/d.png");
+        assert_eq!(report.changed[0].previous_id.as_deref(), Some("2"));
+        assert_eq!(report.removed[0].key, "ui/c.png");
+
+        let markdown = report.render_markdown();
+        assert!(markdown.contains("| Changed | 1 |"));
+        assert!(markdown.contains("| `ui/b.png` | `20` | `2` |"));
+    }
+
+    #[test]
+    fn atlas_pages_are_diffed() {
+        let previous: HashMap<_, _> = [("atlas_000.png", "1"), ("atlas_001.png", "2")]
+            .into_iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        let current: HashMap<_, _> = [("atlas_000.png", "1"), ("atlas_001.png", "5")]
+            .into_iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        let mut report = SyncReport::default();
+        report.diff_atlases(&previous, &current);
+        assert_eq!(report.atlases.len(), 1);
+        assert_eq!(report.atlases[0].status, "changed");
+    }
+
+    #[test]
+    fn nested_assets_are_flattened() {
+        let mut ui = BTreeMap::new();
+        ui.insert(
+            "play.png".to_string(),
+            AssetValue::Object(AssetMeta {
+                id: "rbxassetid://1".into(),
+                highlight_id: Some("rbxassetid://2".into()),
+                ..Default::default()
+            }),
+        );
+        let mut assets = BTreeMap::new();
+        assets.insert("ui".to_string(), AssetValue::Table(ui));
+
+        assert_eq!(
+            asset_ids(&assets),
+            ids(&[
+                ("ui/play.png", "rbxassetid://1"),
+                ("ui/play.png.highlight", "rbxassetid://2")
+            ])
+        );
+    }
+}
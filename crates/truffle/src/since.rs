@@ -0,0 +1,341 @@
+use anyhow::{Context, Result};
+use asphalt::glob::Glob;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::path::{Component, Path, PathBuf};
+use std::process::Command;
+use walkdir::WalkDir;
+
+/// Source files that truffle converts into PNGs next to them.
+const SOURCE_EXTENSIONS: [&str; 5] = ["aseprite", "ase", "psd", "gif", "svg"];
+
+/// Files that changed since a git ref.
+#[derive(Debug, Clone, Default)]
+pub struct ChangeScope {
+    /// Changed files relative to the project directory.
+    pub files: HashSet<PathBuf>,
+    /// Keys (relative to the images folder) of images to process and upload:
+    /// changed images, their highlight variants and PNGs exported from
+    /// changed sources.
+    pub image_keys: BTreeSet<String>,
+}
+
+impl ChangeScope {
+    /// Asks git for everything that differs from `since` in the working tree,
+    /// including untracked files.
+    pub fn from_git(since: &str, images_folder: &Path) -> Result<Self> {
+        let mut files = git_paths(&["diff", "--name-only", "--relative", since, "--"])
+            .with_context(|| format!("Failed to list files changed since {}", since))?;
+        files.extend(git_paths(&["ls-files", "--others", "--exclude-standard"])?);
+        Ok(Self::from_files(files, images_folder))
+    }
+
+    pub fn from_files(files: HashSet<PathBuf>, images_folder: &Path) -> Self {
+        let images_folder = normalize(images_folder);
+        let changed: Vec<String> = files
+            .iter()
+            .filter_map(|file| file.strip_prefix(&images_folder).ok())
+            .map(path_key)
+            .collect();
+
+        let mut image_keys = BTreeSet::new();
+        let mut sources = Vec::new();
+        for key in changed {
+            let (stem, extension) = key.rsplit_once('.').unwrap_or((key.as_str(), ""));
+            let extension = extension.to_ascii_lowercase();
+            if SOURCE_EXTENSIONS.contains(&extension.as_str()) {
+                sources.push(stem.to_string());
+            } else if let Some(highlight) = crate::image::highlight::highlight_key(&key) {
+                image_keys.insert(highlight);
+            }
+            image_keys.insert(key);
+        }
+
+        // Exported PNGs share their source's name: `hero.png`, `hero@2x.png`,
+        // `hero/run01.png`, …
+        if !sources.is_empty() {
+            let existing = WalkDir::new(&images_folder)
+                .into_iter()
+                .filter_map(|e| e.ok())
+                .filter(|e| e.file_type().is_file())
+                .filter_map(|e| e.path().strip_prefix(&images_folder).ok().map(path_key));
+            for key in existing {
+                let derived = sources.iter().any(|stem| {
+                    key.strip_prefix(stem.as_str())
+                        .and_then(|rest| rest.chars().next())
+                        .is_some_and(|c| !c.is_alphanumeric())
+                });
+                if derived {
+                    image_keys.insert(key);
+                }
+            }
+        }
+
+        let files = files.iter().map(|file| normalize(file)).collect();
+        Self { files, image_keys }
+    }
+
+    /// Scopes to every file below `images_folder` whose key matches
+    /// `filter`, as if they had all changed.
+    pub fn from_filter(filter: &Glob, images_folder: &Path) -> Self {
+        let folder = normalize(images_folder);
+        let files = WalkDir::new(&folder)
+            .into_iter()
+            .filter_map(|e| e.ok())
+            .filter(|e| e.file_type().is_file())
+            .filter(|e| {
+                e.path()
+                    .strip_prefix(&folder)
+                    .is_ok_and(|rel| filter.is_match(path_key(rel)))
+            })
+            .map(|e| normalize(e.path()))
+            .collect();
+        Self::from_files(files, images_folder)
+    }
+
+    /// Narrows the scope to the images whose keys match `filter`, dropping
+    /// every other file.
+    pub fn retain_matching(&mut self, filter: &Glob, images_folder: &Path) {
+        let folder = normalize(images_folder);
+        self.files.retain(|file| {
+            file.strip_prefix(&folder)
+                .is_ok_and(|rel| filter.is_match(path_key(rel)))
+        });
+        self.image_keys.retain(|key| filter.is_match(key));
+    }
+
+    pub fn contains_image(&self, key: &str) -> bool {
+        self.image_keys.contains(key)
+    }
+
+    /// Project-relative files Asphalt may process and upload: the changed
+    /// files, the scoped images and `extra` generated files.
+    pub fn upload_files(
+        &self,
+        images_folder: &Path,
+        extra: impl IntoIterator<Item = PathBuf>,
+    ) -> HashSet<PathBuf> {
+        let mut files = self.files.clone();
+        files.extend(
+            self.image_keys
+                .iter()
+                .map(|key| normalize(&images_folder.join(key))),
+        );
+        files.extend(extra.into_iter().map(|file| normalize(&file)));
+        files
+    }
+}
+
+/// Content hashes of the files a sync depends on, recorded after each
+/// successful sync. Diffing against them scopes the next sync to the files
+/// whose bytes changed, like `--since` but without git.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ContentHashes {
+    /// Project-relative path → blake3 hash of the file's bytes.
+    files: BTreeMap<String, String>,
+}
+
+impl ContentHashes {
+    /// Hashes every file under `paths` (directories are walked recursively).
+    /// Missing paths are skipped.
+    pub fn scan(paths: &[PathBuf]) -> Result<Self> {
+        let mut files = BTreeMap::new();
+        for path in paths {
+            for entry in WalkDir::new(path)
+                .into_iter()
+                .filter_map(|e| e.ok())
+                .filter(|e| e.file_type().is_file())
+            {
+                let bytes = std::fs::read(entry.path())
+                    .with_context(|| format!("Failed to read {}", entry.path().display()))?;
+                files.insert(
+                    path_key(&normalize(entry.path())),
+                    blake3::hash(&bytes).to_hex().to_string(),
+                );
+            }
+        }
+        Ok(Self { files })
+    }
+
+    /// The hashes stored at `path`, if there are any.
+    pub fn load(path: &Path) -> Option<Self> {
+        let content = std::fs::read_to_string(path).ok()?;
+        serde_json::from_str(&content).ok()
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create {}", parent.display()))?;
+        }
+        let json = serde_json::to_string_pretty(self)?;
+        std::fs::write(path, json + "\n")
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+
+    /// Files that were added, removed or whose bytes differ from `previous`.
+    pub fn changed_since(&self, previous: &Self) -> HashSet<PathBuf> {
+        let added_or_changed = self
+            .files
+            .iter()
+            .filter(|(file, hash)| previous.files.get(*file) != Some(*hash))
+            .map(|(file, _)| file);
+        let removed = previous
+            .files
+            .keys()
+            .filter(|file| !self.files.contains_key(*file));
+        added_or_changed.chain(removed).map(PathBuf::from).collect()
+    }
+
+    /// Whether `file` has the same hash in both.
+    pub fn unchanged(&self, previous: &Self, file: &Path) -> bool {
+        let key = path_key(&normalize(file));
+        self.files.get(&key) == previous.files.get(&key)
+    }
+}
+
+fn git_paths(args: &[&str]) -> Result<HashSet<PathBuf>> {
+    let output = Command::new("git")
+        .args(args)
+        .output()
+        .context("Failed to run git")?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "git {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter(|line| !line.is_empty())
+        .map(PathBuf::from)
+        .collect())
+}
+
+/// Drops `.` components so `./assets/a.png` compares equal to `assets/a.png`.
+fn normalize(path: &Path) -> PathBuf {
+    path.components()
+        .filter(|c| !matches!(c, Component::CurDir))
+        .collect()
+}
+
+fn path_key(path: &Path) -> String {
+    path.components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn changed_images_pull_in_dependents() {
+        let root = std::env::temp_dir().join(format!("truffle-since-{}", std::process::id()));
+        let images = root.join("images");
+        std::fs::create_dir_all(images.join("hero")).unwrap();
+        for file in ["hero.png", "hero/run01.png", "heroic.png", "icon.png"] {
+            std::fs::write(images.join(file), [0u8]).unwrap();
+        }
+
+        let files = [
+            images.join("hero.aseprite"),
+            images.join("ui/close.png"),
+            root.join("sounds/click.ogg"),
+        ]
+        .into_iter()
+        .collect();
+        let scope = ChangeScope::from_files(files, &images);
+        std::fs::remove_dir_all(&root).ok();
+
+        let keys: Vec<_> = scope.image_keys.iter().map(String::as_str).collect();
+        assert_eq!(
+            keys,
+            vec![
+                "hero.aseprite",
+                "hero.png",
+                "hero/run01.png",
+                "ui/close-highlight.png",
+                "ui/close.png"
+            ]
+        );
+
+        let upload = scope.upload_files(&images, [root.join("atlases/atlas_0.png")]);
+        assert!(upload.contains(&images.join("ui/close-highlight.png")));
+        assert!(upload.contains(&root.join("sounds/click.ogg")));
+        assert!(upload.contains(&root.join("atlases/atlas_0.png")));
+        assert!(!upload.contains(&images.join("icon.png")));
+    }
+
+    #[test]
+    fn content_hashes_find_changed_files() {
+        let root = std::env::temp_dir().join(format!("truffle-hashes-{}", std::process::id()));
+        let images = root.join("images");
+        std::fs::create_dir_all(&images).unwrap();
+        for file in ["a.png", "b.png", "c.png"] {
+            std::fs::write(images.join(file), file).unwrap();
+        }
+        let previous = ContentHashes::scan(&[images.clone(), root.join("missing")]).unwrap();
+        let path = root.join("scratch/content-hashes.json");
+        previous.save(&path).unwrap();
+        assert_eq!(ContentHashes::load(&path), Some(previous.clone()));
+
+        std::fs::write(images.join("a.png"), "edited").unwrap();
+        std::fs::remove_file(images.join("b.png")).unwrap();
+        std::fs::write(images.join("d.png"), "new").unwrap();
+        let current = ContentHashes::scan(std::slice::from_ref(&images)).unwrap();
+        std::fs::remove_dir_all(&root).ok();
+
+        let changed = current.changed_since(&previous);
+        assert_eq!(changed.len(), 3);
+        assert!(changed.contains(&images.join("a.png")));
+        assert!(changed.contains(&images.join("b.png")));
+        assert!(changed.contains(&images.join("d.png")));
+        assert!(current.unchanged(&previous, &images.join("c.png")));
+        assert!(!current.unchanged(&previous, &images.join("b.png")));
+    }
+
+    #[test]
+    fn filters_scope_images_by_key() {
+        let root = std::env::temp_dir().join(format!("truffle-filter-{}", std::process::id()));
+        let images = root.join("images");
+        std::fs::create_dir_all(images.join("ui/icons")).unwrap();
+        for file in ["ui/icons/play.png", "ui/icons/stop.png", "ui/panel.png"] {
+            std::fs::write(images.join(file), [0u8]).unwrap();
+        }
+        let filter = Glob::new("ui/icons/**").unwrap();
+
+        let scope = ChangeScope::from_filter(&filter, &images);
+        let mut changed = ChangeScope::from_files(
+            [
+                images.join("ui/icons/play.png"),
+                images.join("ui/panel.png"),
+                root.join("a.ogg"),
+            ]
+            .into_iter()
+            .collect(),
+            &images,
+        );
+        changed.retain_matching(&filter, &images);
+        std::fs::remove_dir_all(&root).ok();
+
+        let keys: Vec<_> = scope.image_keys.iter().map(String::as_str).collect();
+        assert_eq!(
+            keys,
+            vec![
+                "ui/icons/play-highlight.png",
+                "ui/icons/play.png",
+                "ui/icons/stop-highlight.png",
+                "ui/icons/stop.png"
+            ]
+        );
+        let keys: Vec<_> = changed.image_keys.iter().map(String::as_str).collect();
+        assert_eq!(
+            keys,
+            vec!["ui/icons/play-highlight.png", "ui/icons/play.png"]
+        );
+        assert_eq!(changed.files.len(), 1);
+    }
+}
@@ -0,0 +1,103 @@
+use super::{grid_tiles, Tileset};
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::path::Path;
+
+#[derive(Deserialize)]
+struct Project {
+    defs: Definitions,
+}
+
+#[derive(Deserialize)]
+struct Definitions {
+    tilesets: Vec<TilesetDefinition>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TilesetDefinition {
+    identifier: String,
+    /// Missing for embedded atlases (e.g. LDtk's internal icons).
+    rel_path: Option<String>,
+    px_wid: u32,
+    px_hei: u32,
+    tile_grid_size: u32,
+    #[serde(default)]
+    spacing: u32,
+    #[serde(default)]
+    padding: u32,
+    #[serde(rename = "__cWid")]
+    c_wid: Option<u32>,
+}
+
+/// Parse the tileset definitions of an LDtk project (`.ldtk`).
+///
+/// Tile ids follow LDtk's numbering: `y * columns + x` on the grid.
+pub fn parse_ldtk(contents: &str, base_dir: &Path) -> Result<Vec<Tileset>> {
+    let project: Project =
+        serde_json::from_str(contents).context("Failed to parse LDtk project JSON")?;
+
+    Ok(project
+        .defs
+        .tilesets
+        .into_iter()
+        .filter_map(|def| {
+            let rel_path = def.rel_path?;
+            Some(Tileset {
+                name: def.identifier,
+                first_gid: None,
+                tile_width: def.tile_grid_size,
+                tile_height: def.tile_grid_size,
+                tiles: grid_tiles(
+                    &base_dir.join(rel_path),
+                    def.px_wid,
+                    def.px_hei,
+                    def.tile_grid_size,
+                    def.tile_grid_size,
+                    def.padding,
+                    def.spacing,
+                    def.c_wid,
+                ),
+            })
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_tileset_definitions() {
+        let json = r#"{
+            "defs": {
+                "tilesets": [
+                    {
+                        "identifier": "Cavernas",
+                        "relPath": "atlas/cavernas.png",
+                        "pxWid": 24,
+                        "pxHei": 16,
+                        "tileGridSize": 8,
+                        "spacing": 0,
+                        "padding": 0,
+                        "__cWid": 3
+                    },
+                    {
+                        "identifier": "Internal_Icons",
+                        "relPath": null,
+                        "pxWid": 16,
+                        "pxHei": 16,
+                        "tileGridSize": 16
+                    }
+                ]
+            }
+        }"#;
+
+        let tilesets = parse_ldtk(json, Path::new("levels")).unwrap();
+        assert_eq!(tilesets.len(), 1);
+        assert_eq!(tilesets[0].name, "Cavernas");
+        assert_eq!(tilesets[0].tiles.len(), 6);
+        let tile = &tilesets[0].tiles[4];
+        assert_eq!((tile.id, tile.x, tile.y), (4, 8, 8));
+    }
+}
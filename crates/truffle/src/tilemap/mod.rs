@@ -0,0 +1,272 @@
+pub mod ldtk;
+pub mod tiled;
+
+use crate::assets::atlas::SpritePlacement;
+use anyhow::{Context, Result};
+use image::GenericImageView;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// A tileset gathered from a Tiled or LDtk project.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Tileset {
+    pub name: String,
+    /// First global tile id in a Tiled map; `None` for standalone tilesets and LDtk.
+    pub first_gid: Option<u32>,
+    pub tile_width: u32,
+    pub tile_height: u32,
+    pub tiles: Vec<Tile>,
+}
+
+/// Where a single tile's pixels live.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Tile {
+    pub id: u32,
+    pub image: PathBuf,
+    pub x: u32,
+    pub y: u32,
+    pub w: u32,
+    pub h: u32,
+}
+
+/// Enumerate the tiles of a regular grid sheet, row by row.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn grid_tiles(
+    image: &Path,
+    image_width: u32,
+    image_height: u32,
+    tile_width: u32,
+    tile_height: u32,
+    margin: u32,
+    spacing: u32,
+    columns: Option<u32>,
+) -> Vec<Tile> {
+    if tile_width == 0 || tile_height == 0 {
+        return Vec::new();
+    }
+
+    let fit = |size: u32, tile: u32| {
+        if size < margin + tile {
+            0
+        } else {
+            (size - margin - tile) / (tile + spacing) + 1
+        }
+    };
+    let columns = columns.unwrap_or_else(|| fit(image_width, tile_width));
+    let rows = fit(image_height, tile_height);
+
+    let mut tiles = Vec::new();
+    for row in 0..rows {
+        for column in 0..columns {
+            tiles.push(Tile {
+                id: row * columns + column,
+                image: image.to_path_buf(),
+                x: margin + column * (tile_width + spacing),
+                y: margin + row * (tile_height + spacing),
+                w: tile_width,
+                h: tile_height,
+            });
+        }
+    }
+    tiles
+}
+
+/// Load tilesets from a `.tsx`, `.tmx` or `.ldtk` file.
+pub fn load_tilesets(path: &Path) -> Result<Vec<Tileset>> {
+    let contents = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    let base_dir = path.parent().unwrap_or(Path::new("."));
+
+    let mut tilesets = match path.extension().and_then(|s| s.to_str()) {
+        Some("tsx") => vec![tiled::parse_tsx(&contents, base_dir, None)?],
+        Some("tmx") => tiled::parse_tmx(&contents, base_dir)?,
+        Some("ldtk") => ldtk::parse_ldtk(&contents, base_dir)?,
+        _ => anyhow::bail!(
+            "Unsupported tilemap file {} (expected .tsx, .tmx or .ldtk)",
+            path.display()
+        ),
+    };
+    dedupe_names(&mut tilesets);
+    Ok(tilesets)
+}
+
+/// Crop every non-empty tile into `<output_dir>/<tileset>/<id>.png` so the
+/// regular atlas packer can pick them up. Returns the number of tiles written.
+pub fn slice_tiles(tilesets: &[Tileset], output_dir: &Path) -> Result<usize> {
+    if output_dir.exists() {
+        std::fs::remove_dir_all(output_dir)
+            .with_context(|| format!("Failed to clean {}", output_dir.display()))?;
+    }
+
+    let mut written = 0;
+    let mut sources: BTreeMap<PathBuf, image::DynamicImage> = BTreeMap::new();
+    for tileset in tilesets {
+        let dir = output_dir.join(&tileset.name);
+        std::fs::create_dir_all(&dir)
+            .with_context(|| format!("Failed to create {}", dir.display()))?;
+
+        for tile in &tileset.tiles {
+            if !sources.contains_key(&tile.image) {
+                let image = image::open(&tile.image)
+                    .with_context(|| format!("Failed to decode {}", tile.image.display()))?;
+                sources.insert(tile.image.clone(), image);
+            }
+            let source = &sources[&tile.image];
+            if tile.x + tile.w > source.width() || tile.y + tile.h > source.height() {
+                anyhow::bail!(
+                    "Tile {} of {} lies outside {}",
+                    tile.id,
+                    tileset.name,
+                    tile.image.display()
+                );
+            }
+
+            let cropped = source.view(tile.x, tile.y, tile.w, tile.h).to_image();
+            if cropped.pixels().all(|p| p.0[3] == 0) {
+                continue;
+            }
+
+            let path = dir.join(format!("{}.png", tile.id));
+            cropped
+                .save(&path)
+                .with_context(|| format!("Failed to write {}", path.display()))?;
+            written += 1;
+        }
+    }
+
+    Ok(written)
+}
+
+/// Render the tile id → atlas rect lookup as a Luau module.
+pub fn render_tilemap_luau_module(
+    tilesets: &[Tileset],
+    placements: &BTreeMap<String, SpritePlacement>,
+) -> String {
+    let mut out = String::new();
+    out.push_str("-- This file is automatically @generated by truffle.\n");
+    out.push_str("-- DO NOT EDIT MANUALLY.\n\n");
+    out.push_str("return {\n\ttilesets = {\n");
+
+    for tileset in tilesets {
+        out.push_str(&format!(
+            "\t\t[{}] = {{\n",
+            serde_json::to_string(&tileset.name).unwrap()
+        ));
+        if let Some(first_gid) = tileset.first_gid {
+            out.push_str(&format!("\t\t\tfirstGid = {},\n", first_gid));
+        }
+        out.push_str(&format!("\t\t\ttileWidth = {},\n", tileset.tile_width));
+        out.push_str(&format!("\t\t\ttileHeight = {},\n", tileset.tile_height));
+        out.push_str("\t\t\ttiles = {\n");
+
+        for tile in &tileset.tiles {
+            let key = format!("{}/{}.png", tileset.name, tile.id);
+            let Some(placement) = placements.get(&key) else {
+                continue;
+            };
+            out.push_str(&format!(
+                "\t\t\t\t[{}] = {{ atlas = \"{}\", rectX = {}, rectY = {}, rectW = {}, rectH = {} }},\n",
+                tile.id,
+                placement.atlas_file_name,
+                placement.rect.x,
+                placement.rect.y,
+                placement.rect.w,
+                placement.rect.h
+            ));
+        }
+
+        out.push_str("\t\t\t},\n\t\t},\n");
+    }
+
+    out.push_str("\t},\n}\n");
+    out
+}
+
+/// Make tileset names unique and safe to use as directory names.
+fn dedupe_names(tilesets: &mut [Tileset]) {
+    let mut seen: BTreeMap<String, usize> = BTreeMap::new();
+    for tileset in tilesets.iter_mut() {
+        let mut name: String = tileset
+            .name
+            .chars()
+            .map(|c| match c {
+                '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+                c => c,
+            })
+            .collect();
+        if name.is_empty() {
+            name = "tileset".into();
+        }
+
+        let count = seen.entry(name.clone()).or_insert(0);
+        *count += 1;
+        if *count > 1 {
+            name = format!("{}_{}", name, count);
+        }
+        tileset.name = name;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assets::atlas::AtlasRect;
+
+    #[test]
+    fn grid_respects_margin_and_spacing() {
+        let tiles = grid_tiles(Path::new("sheet.png"), 37, 20, 16, 16, 1, 2, None);
+        assert_eq!(tiles.len(), 2);
+        assert_eq!((tiles[0].x, tiles[0].y), (1, 1));
+        assert_eq!((tiles[1].id, tiles[1].x), (1, 19));
+    }
+
+    #[test]
+    fn luau_module_maps_ids_to_rects() {
+        let tileset = Tileset {
+            name: "terrain".into(),
+            first_gid: Some(1),
+            tile_width: 16,
+            tile_height: 16,
+            tiles: grid_tiles(Path::new("sheet.png"), 32, 16, 16, 16, 0, 0, None),
+        };
+        let mut placements = BTreeMap::new();
+        placements.insert(
+            "terrain/1.png".to_string(),
+            SpritePlacement {
+                atlas_file_name: "atlas_000.png".into(),
+                rect: AtlasRect {
+                    x: 4,
+                    y: 4,
+                    w: 16,
+                    h: 16,
+                },
+                rotated: false,
+                trim: None,
+            },
+        );
+
+        let output = render_tilemap_luau_module(&[tileset], &placements);
+        assert!(output.contains("[\"terrain\"] = {"));
+        assert!(output.contains("firstGid = 1,"));
+        assert!(output.contains(
+            "[1] = { atlas = \"atlas_000.png\", rectX = 4, rectY = 4, rectW = 16, rectH = 16 },"
+        ));
+        // Tile 0 was empty and never packed.
+        assert!(!output.contains("[0] = {"));
+    }
+
+    #[test]
+    fn duplicate_names_get_suffixes() {
+        let tileset = |name: &str| Tileset {
+            name: name.into(),
+            first_gid: None,
+            tile_width: 8,
+            tile_height: 8,
+            tiles: Vec::new(),
+        };
+        let mut tilesets = vec![tileset("a/b"), tileset("a/b"), tileset("")];
+        dedupe_names(&mut tilesets);
+        let names: Vec<_> = tilesets.iter().map(|t| t.name.as_str()).collect();
+        assert_eq!(names, vec!["a_b", "a_b_2", "tileset"]);
+    }
+}
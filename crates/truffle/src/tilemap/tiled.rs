@@ -0,0 +1,149 @@
+use super::{grid_tiles, Tile, Tileset};
+use anyhow::{Context, Result};
+use roxmltree::{Document, Node};
+use std::path::Path;
+
+fn attr<T: std::str::FromStr>(node: Node, name: &str) -> Option<T> {
+    node.attribute(name).and_then(|value| value.parse().ok())
+}
+
+fn required<T: std::str::FromStr>(node: Node, name: &str) -> Result<T> {
+    attr(node, name).with_context(|| {
+        format!(
+            "<{}> is missing a valid `{}` attribute",
+            node.tag_name().name(),
+            name
+        )
+    })
+}
+
+/// Parse a Tiled tileset (`.tsx`, or a `<tileset>` embedded in a map).
+pub fn parse_tsx(contents: &str, base_dir: &Path, first_gid: Option<u32>) -> Result<Tileset> {
+    let document = Document::parse(contents).context("Failed to parse tileset XML")?;
+    let root = document.root_element();
+    if root.tag_name().name() != "tileset" {
+        anyhow::bail!("Expected a <tileset> root element");
+    }
+    parse_tileset_node(root, base_dir, first_gid)
+}
+
+fn parse_tileset_node(node: Node, base_dir: &Path, first_gid: Option<u32>) -> Result<Tileset> {
+    let name = node.attribute("name").unwrap_or("tileset").to_string();
+    let tile_width: u32 = required(node, "tilewidth")?;
+    let tile_height: u32 = required(node, "tileheight")?;
+
+    let sheet = node.children().find(|n| n.has_tag_name("image"));
+    let tiles = match sheet {
+        Some(image) => {
+            let source: String = required(image, "source")?;
+            grid_tiles(
+                &base_dir.join(source),
+                required(image, "width")?,
+                required(image, "height")?,
+                tile_width,
+                tile_height,
+                attr(node, "margin").unwrap_or(0),
+                attr(node, "spacing").unwrap_or(0),
+                attr(node, "columns"),
+            )
+        }
+        // Image collection tilesets give each tile its own image.
+        None => {
+            let mut tiles = Vec::new();
+            for tile in node.children().filter(|n| n.has_tag_name("tile")) {
+                let Some(image) = tile.children().find(|n| n.has_tag_name("image")) else {
+                    continue;
+                };
+                let source: String = required(image, "source")?;
+                tiles.push(Tile {
+                    id: required(tile, "id")?,
+                    image: base_dir.join(source),
+                    x: attr(tile, "x").unwrap_or(0),
+                    y: attr(tile, "y").unwrap_or(0),
+                    w: attr(tile, "width").or(attr(image, "width")).unwrap_or(0),
+                    h: attr(tile, "height").or(attr(image, "height")).unwrap_or(0),
+                });
+            }
+            tiles
+        }
+    };
+
+    Ok(Tileset {
+        name,
+        first_gid,
+        tile_width,
+        tile_height,
+        tiles,
+    })
+}
+
+/// Parse a Tiled map (`.tmx`), resolving external `.tsx` tilesets.
+pub fn parse_tmx(contents: &str, base_dir: &Path) -> Result<Vec<Tileset>> {
+    let document = Document::parse(contents).context("Failed to parse map XML")?;
+    let root = document.root_element();
+    if root.tag_name().name() != "map" {
+        anyhow::bail!("Expected a <map> root element");
+    }
+
+    let mut tilesets = Vec::new();
+    for node in root.children().filter(|n| n.has_tag_name("tileset")) {
+        let first_gid: u32 = required(node, "firstgid")?;
+        let tileset = match node.attribute("source") {
+            Some(source) => {
+                let path = base_dir.join(source);
+                let external = std::fs::read_to_string(&path)
+                    .with_context(|| format!("Failed to read {}", path.display()))?;
+                parse_tsx(
+                    &external,
+                    path.parent().unwrap_or(base_dir),
+                    Some(first_gid),
+                )
+                .with_context(|| format!("Failed to parse {}", path.display()))?
+            }
+            None => parse_tileset_node(node, base_dir, Some(first_gid))?,
+        };
+        tilesets.push(tileset);
+    }
+
+    Ok(tilesets)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_grid_tileset() {
+        let tsx = r#"<?xml version="1.0" encoding="UTF-8"?>
+<tileset name="terrain" tilewidth="16" tileheight="16" tilecount="4" columns="2" spacing="1" margin="2">
+  <image source="terrain.png" width="35" height="35"/>
+</tileset>"#;
+
+        let tileset = parse_tsx(tsx, Path::new("maps"), None).unwrap();
+        assert_eq!(tileset.name, "terrain");
+        assert_eq!(tileset.tiles.len(), 4);
+        let last = &tileset.tiles[3];
+        assert_eq!((last.id, last.x, last.y), (3, 19, 19));
+        assert_eq!(last.image, Path::new("maps").join("terrain.png"));
+    }
+
+    #[test]
+    fn parses_embedded_map_tilesets_and_collections() {
+        let tmx = r#"<map version="1.10" orientation="orthogonal">
+  <tileset firstgid="1" name="props" tilewidth="32" tileheight="32">
+    <tile id="5"><image source="props/barrel.png" width="32" height="48"/></tile>
+  </tileset>
+</map>"#;
+
+        let tilesets = parse_tmx(tmx, Path::new("maps")).unwrap();
+        assert_eq!(tilesets.len(), 1);
+        assert_eq!(tilesets[0].first_gid, Some(1));
+        let tile = &tilesets[0].tiles[0];
+        assert_eq!((tile.id, tile.w, tile.h), (5, 32, 48));
+    }
+
+    #[test]
+    fn rejects_non_tileset_documents() {
+        assert!(parse_tsx("<map/>", Path::new("."), None).is_err());
+    }
+}
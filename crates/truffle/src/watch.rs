@@ -0,0 +1,226 @@
+use anyhow::{Context, Result};
+use notify::{EventKind, RecursiveMode, Watcher};
+use std::collections::{BTreeSet, HashSet};
+use std::path::{Component, Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::time::{Duration, Instant};
+
+/// How long the tree has to stay quiet before a batch is handed out.
+const QUIET: Duration = Duration::from_millis(300);
+/// Upper bound on how long a constant stream of events can delay a batch.
+const MAX_WAIT: Duration = Duration::from_secs(5);
+
+/// Coalesces bursts of file events (an artist exporting 50 files at once)
+/// into a single batch of dirty paths.
+#[derive(Debug)]
+pub struct Debouncer {
+    quiet: Duration,
+    max_wait: Duration,
+    first: Option<Instant>,
+    last: Option<Instant>,
+    dirty: BTreeSet<PathBuf>,
+}
+
+impl Debouncer {
+    pub fn new(quiet: Duration, max_wait: Duration) -> Self {
+        Self {
+            quiet,
+            max_wait,
+            first: None,
+            last: None,
+            dirty: BTreeSet::new(),
+        }
+    }
+
+    pub fn push(&mut self, path: PathBuf, now: Instant) {
+        self.first.get_or_insert(now);
+        self.last = Some(now);
+        self.dirty.insert(path);
+    }
+
+    /// When the pending batch is due, if anything is pending.
+    pub fn deadline(&self) -> Option<Instant> {
+        let (first, last) = (self.first?, self.last?);
+        Some((last + self.quiet).min(first + self.max_wait))
+    }
+
+    /// Hands out the dirty paths once the batch is due.
+    pub fn take(&mut self, now: Instant) -> Option<BTreeSet<PathBuf>> {
+        if self.deadline()? > now {
+            return None;
+        }
+        self.first = None;
+        self.last = None;
+        Some(std::mem::take(&mut self.dirty))
+    }
+
+    /// Hands out every dirty path, due or not.
+    pub fn take_all(&mut self) -> BTreeSet<PathBuf> {
+        self.first = None;
+        self.last = None;
+        std::mem::take(&mut self.dirty)
+    }
+}
+
+/// Watches files and directories recursively and yields debounced batches of
+/// changed paths, relative to the current directory. Shared by every
+/// long-running mode that re-runs part of the pipeline on changes.
+pub struct FileWatcher {
+    watcher: notify::RecommendedWatcher,
+    events: Receiver<notify::Result<notify::Event>>,
+    root: PathBuf,
+    ignore: Vec<PathBuf>,
+    debouncer: Debouncer,
+}
+
+impl FileWatcher {
+    /// Watches `paths`. Changes under `ignore` (e.g. the scratch dir and the
+    /// generated modules) never show up in a batch.
+    pub fn new(paths: &[PathBuf], ignore: &[PathBuf]) -> Result<Self> {
+        let (tx, events) = mpsc::channel();
+        let mut watcher =
+            notify::recommended_watcher(tx).context("Failed to start file watcher")?;
+        for path in paths {
+            watcher
+                .watch(path, RecursiveMode::Recursive)
+                .with_context(|| format!("Failed to watch {}", path.display()))?;
+        }
+
+        let root = std::env::current_dir().context("Failed to read current directory")?;
+        let root = root.canonicalize().unwrap_or(root);
+        Ok(Self {
+            watcher,
+            events,
+            root,
+            ignore: ignore.iter().map(|path| normalize(path)).collect(),
+            debouncer: Debouncer::new(QUIET, MAX_WAIT),
+        })
+    }
+
+    /// Also watches single files through their parent directories, without
+    /// recursing, so editors that save by replacing a file are still seen.
+    /// Other files in those directories show up in batches too.
+    pub fn watch_files(&mut self, files: &[PathBuf]) -> Result<()> {
+        for file in files {
+            let dir = match file.parent() {
+                Some(parent) if parent != Path::new("") => parent,
+                _ => Path::new("."),
+            };
+            self.watcher
+                .watch(dir, RecursiveMode::NonRecursive)
+                .with_context(|| format!("Failed to watch {}", file.display()))?;
+        }
+        Ok(())
+    }
+
+    /// Blocks until a batch of changes is due.
+    pub fn next_batch(&mut self) -> Result<HashSet<PathBuf>> {
+        loop {
+            let event = match self.debouncer.deadline() {
+                Some(deadline) => {
+                    let timeout = deadline.saturating_duration_since(Instant::now());
+                    match self.events.recv_timeout(timeout) {
+                        Ok(event) => Some(event),
+                        Err(RecvTimeoutError::Timeout) => None,
+                        Err(RecvTimeoutError::Disconnected) => {
+                            anyhow::bail!("File watcher stopped")
+                        }
+                    }
+                }
+                None => Some(self.events.recv().context("File watcher stopped")?),
+            };
+
+            match event {
+                Some(Ok(event)) => self.record(event),
+                Some(Err(e)) => eprintln!("[watch] WARN: {}", e),
+                None => {}
+            }
+            if let Some(batch) = self.debouncer.take(Instant::now()) {
+                return Ok(batch.into_iter().collect());
+            }
+        }
+    }
+
+    /// Everything that changed since the last call, without waiting for the
+    /// tree to settle.
+    pub fn drain(&mut self) -> HashSet<PathBuf> {
+        while let Ok(event) = self.events.try_recv() {
+            match event {
+                Ok(event) => self.record(event),
+                Err(e) => eprintln!("[watch] WARN: {}", e),
+            }
+        }
+        self.debouncer.take_all().into_iter().collect()
+    }
+
+    fn record(&mut self, event: notify::Event) {
+        if matches!(event.kind, EventKind::Access(_)) {
+            return;
+        }
+        let now = Instant::now();
+        for path in event.paths {
+            let path = normalize(path.strip_prefix(&self.root).unwrap_or(&path));
+            if self.ignore.iter().any(|ignored| path.starts_with(ignored)) {
+                continue;
+            }
+            self.debouncer.push(path, now);
+        }
+    }
+}
+
+/// Drops `.` components so `./assets/a.png` compares equal to `assets/a.png`.
+pub fn normalize(path: &Path) -> PathBuf {
+    path.components()
+        .filter(|c| !matches!(c, Component::CurDir))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bursts_become_one_batch() {
+        let start = Instant::now();
+        let ms = Duration::from_millis;
+        let mut debouncer = Debouncer::new(ms(300), ms(5000));
+        assert_eq!(debouncer.deadline(), None);
+
+        for i in 0..50u64 {
+            debouncer.push(
+                PathBuf::from(format!("icons/{}.png", i % 10)),
+                start + ms(i * 10),
+            );
+        }
+        assert_eq!(debouncer.deadline(), Some(start + ms(790)));
+        assert!(debouncer.take(start + ms(700)).is_none());
+
+        let batch = debouncer.take(start + ms(790)).unwrap();
+        assert_eq!(batch.len(), 10);
+        assert_eq!(debouncer.deadline(), None);
+    }
+
+    #[test]
+    fn constant_events_still_flush() {
+        let start = Instant::now();
+        let ms = Duration::from_millis;
+        let mut debouncer = Debouncer::new(ms(300), ms(1000));
+        for i in 0..20u64 {
+            debouncer.push(PathBuf::from("a.png"), start + ms(i * 100));
+        }
+        assert_eq!(debouncer.deadline(), Some(start + ms(1000)));
+        assert!(debouncer.take(start + ms(1000)).is_some());
+    }
+
+    #[test]
+    fn take_all_hands_out_pending_paths_early() {
+        let start = Instant::now();
+        let mut debouncer = Debouncer::new(Duration::from_millis(300), Duration::from_secs(5));
+        debouncer.push(PathBuf::from("a.png"), start);
+        debouncer.push(PathBuf::from("b.png"), start);
+        assert!(debouncer.take(start).is_none());
+        assert_eq!(debouncer.take_all().len(), 2);
+        assert_eq!(debouncer.deadline(), None);
+        assert!(debouncer.take_all().is_empty());
+    }
+}
@@ -0,0 +1,236 @@
+use crate::assets::augment::ImageMetadataReader;
+use crate::assets::load_assets;
+use crate::assets::model::AssetValue;
+use crate::commands::sync::{run_with_config, SyncArgs};
+use anyhow::{Context, Result};
+use asphalt::lockfile::{Lockfile, RawLockfile};
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+use truffle_config::{TruffleConfig, WorkspaceConfig};
+use walkdir::WalkDir;
+
+/// Combined asset report for every workspace member.
+#[derive(Debug, Default, Serialize)]
+struct WorkspaceReport {
+    members: Vec<MemberReport>,
+    /// Images whose content appears in more than one member.
+    shared_images: Vec<SharedImage>,
+}
+
+#[derive(Debug, Serialize)]
+struct MemberReport {
+    path: String,
+    ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+    images: usize,
+    image_bytes: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    assets: Option<usize>,
+}
+
+#[derive(Debug, Serialize)]
+struct SharedImage {
+    hash: String,
+    paths: Vec<String>,
+}
+
+/// Syncs every member listed in truffle-workspace.toml.
+///
+/// Members share one content-addressed cache, and assets uploaded by one
+/// member are reused by later members with the same creator instead of
+/// uploading identical files again. A failing member doesn't stop the others.
+pub async fn sync_workspace(args: SyncArgs, reader: &dyn ImageMetadataReader) -> Result<()> {
+    let root = std::env::current_dir().context("Failed to get the current directory")?;
+    let workspace = WorkspaceConfig::read().await?;
+    if workspace.members.is_empty() {
+        anyhow::bail!("truffle-workspace.toml lists no members");
+    }
+
+    let cache_dir = args
+        .cache_dir
+        .clone()
+        .unwrap_or_else(|| workspace.cache_dir.clone());
+    let cache_dir = root.join(cache_dir);
+
+    // Uploaded assets per creator, so ids never cross account boundaries.
+    let mut shared: HashMap<String, Lockfile> = HashMap::new();
+    let mut report = WorkspaceReport::default();
+    let mut hashes: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
+
+    for member in &workspace.members {
+        let name = member
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        println!("[workspace] Syncing {} …", name);
+
+        let dir = root.join(member);
+        std::env::set_current_dir(&dir)
+            .with_context(|| format!("Failed to enter workspace member {}", dir.display()))?;
+        let result = sync_member(&args, &cache_dir, &mut shared, reader).await;
+        let (images, image_bytes) = scan_images(&args.images_folder, &name, &mut hashes);
+        let assets = load_assets(&args.assets_output)
+            .ok()
+            .map(|assets| count_assets(&assets));
+        std::env::set_current_dir(&root).context("Failed to return to the workspace root")?;
+
+        if let Err(e) = &result {
+            eprintln!("[workspace] ERROR: {}: {:#}", name, e);
+        }
+        report.members.push(MemberReport {
+            path: name,
+            ok: result.is_ok(),
+            error: result.err().map(|e| format!("{:#}", e)),
+            images,
+            image_bytes,
+            assets,
+        });
+    }
+
+    report.shared_images = hashes
+        .into_iter()
+        .filter(|(_, paths)| {
+            let members: BTreeSet<_> = paths
+                .iter()
+                .filter_map(|p| Some(p.split_once(':')?.0))
+                .collect();
+            members.len() > 1
+        })
+        .map(|(hash, paths)| SharedImage {
+            hash,
+            paths: paths.into_iter().map(|p| p.replacen(':', "/", 1)).collect(),
+        })
+        .collect();
+
+    if let Some(path) = &args.workspace_report {
+        let json = serde_json::to_string_pretty(&report)?;
+        std::fs::write(path, json)
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+        println!("[workspace] Wrote report: {}", path.display());
+    }
+
+    let failed = report.members.iter().filter(|m| !m.ok).count();
+    println!(
+        "[workspace] Done ✅ Synced: {}, Failed: {}, Shared images: {}",
+        report.members.len() - failed,
+        failed,
+        report.shared_images.len()
+    );
+    if failed > 0 {
+        anyhow::bail!("{} workspace member(s) failed to sync", failed);
+    }
+    Ok(())
+}
+
+/// Syncs the member in the current directory and records its uploads.
+async fn sync_member(
+    args: &SyncArgs,
+    cache_dir: &Path,
+    shared: &mut HashMap<String, Lockfile>,
+    reader: &dyn ImageMetadataReader,
+) -> Result<()> {
+    let config = TruffleConfig::read().await?;
+    let creator = format!(
+        "{:?}:{}",
+        config.asphalt.creator.ty, config.asphalt.creator.id
+    );
+
+    let mut member_args = args.clone();
+    member_args.cache_dir = Some(cache_dir.to_path_buf());
+    member_args.shared_lockfile = shared.get(&creator).cloned();
+    run_with_config(member_args, &config, reader).await?;
+
+    let lockfile = RawLockfile::read_from(Path::new("."))
+        .await?
+        .into_lockfile()?;
+    shared.entry(creator).or_default().merge(&lockfile);
+    Ok(())
+}
+
+/// Counts and hashes a member's images. Hashes map to `member:path` entries.
+fn scan_images(
+    images_folder: &Path,
+    member: &str,
+    hashes: &mut BTreeMap<String, BTreeSet<String>>,
+) -> (usize, u64) {
+    let mut count = 0;
+    let mut bytes = 0;
+    let files = WalkDir::new(images_folder)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file())
+        .filter(|e| e.path().extension().and_then(|s| s.to_str()) == Some("png"));
+    for entry in files {
+        let Ok(data) = std::fs::read(entry.path()) else {
+            continue;
+        };
+        count += 1;
+        bytes += data.len() as u64;
+        let path: PathBuf = entry.path().components().collect();
+        hashes
+            .entry(blake3::hash(&data).to_hex().to_string())
+            .or_default()
+            .insert(format!(
+                "{}:{}",
+                member,
+                path.to_string_lossy().replace('\\', "/")
+            ));
+    }
+    (count, bytes)
+}
+
+fn count_assets(assets: &BTreeMap<String, AssetValue>) -> usize {
+    assets
+        .values()
+        .map(|value| match value {
+            AssetValue::Table(children) => count_assets(children),
+            _ => 1,
+        })
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn images_are_hashed_per_member() {
+        let root = std::env::temp_dir().join(format!("truffle-workspace-{}", std::process::id()));
+        std::fs::create_dir_all(root.join("ui")).unwrap();
+        std::fs::write(root.join("ui/a.png"), [1u8, 2, 3]).unwrap();
+        std::fs::write(root.join("ui/b.png"), [1u8, 2, 3]).unwrap();
+        std::fs::write(root.join("notes.txt"), [0u8]).unwrap();
+
+        let mut hashes = BTreeMap::new();
+        let (count, bytes) = scan_images(&root, "games/lobby", &mut hashes);
+        std::fs::remove_dir_all(&root).ok();
+
+        assert_eq!((count, bytes), (2, 6));
+        assert_eq!(hashes.len(), 1);
+        let paths = hashes.values().next().unwrap();
+        assert!(paths.iter().all(|p| p.starts_with("games/lobby:")));
+    }
+
+    #[test]
+    fn nested_assets_are_counted() {
+        let mut ui = BTreeMap::new();
+        ui.insert(
+            "a.png".to_string(),
+            AssetValue::String("rbxassetid://1".into()),
+        );
+        ui.insert(
+            "b.png".to_string(),
+            AssetValue::String("rbxassetid://2".into()),
+        );
+        let mut assets = BTreeMap::new();
+        assets.insert("ui".to_string(), AssetValue::Table(ui));
+        assets.insert(
+            "logo.png".to_string(),
+            AssetValue::String("rbxassetid://3".into()),
+        );
+        assert_eq!(count_assets(&assets), 3);
+    }
+}